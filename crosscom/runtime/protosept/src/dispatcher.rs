@@ -36,6 +36,7 @@ use std::ffi::{CString, c_char, c_float, c_long, c_void};
 use std::os::raw::c_int;
 use std::rc::Rc;
 
+use crate::profile::{CallKind, call_span};
 use crate::proto_ccw::wrap_proto_unknown;
 use crate::{ComObjectTable, with_services};
 use libffi::middle::{Arg, Cif, CodePtr, Type, arg};
@@ -84,7 +85,7 @@ pub fn install_com_dispatcher(ctx: &mut Context) {
 /// ```
 fn com_invoke(ctx: &mut Context) -> Result<(), RuntimeError> {
     let type_tag = pop_string(ctx, "com.invoke: type_tag")?;
-    let _method_name = pop_string(ctx, "com.invoke: method_name")?;
+    let method_name = pop_string(ctx, "com.invoke: method_name")?;
     let vtable_slot = pop_int(ctx, "com.invoke: vtable_slot")? as usize;
     let return_ty = pop_return_ty(ctx)?;
 
@@ -143,6 +144,7 @@ fn com_invoke(ctx: &mut Context) -> Result<(), RuntimeError> {
     };
 
     // Marshal the call via the closed signature catalog.
+    let span = call_span(CallKind::Foreign, &type_tag, &method_name);
     let raw = unsafe {
         invoke_via_catalog(fn_ptr, this_ptr, &popped_args, &return_ty).map_err(|e| {
             RuntimeError::Other(format!(
@@ -151,6 +153,7 @@ fn com_invoke(ctx: &mut Context) -> Result<(), RuntimeError> {
             ))
         })?
     };
+    drop(span);
 
     // Drop the QI'd strong ref (the COM table still holds the original).
     unsafe {
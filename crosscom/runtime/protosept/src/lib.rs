@@ -34,10 +34,12 @@ use crosscom::{ComInterface, ComRc, IUnknown};
 
 pub mod adapter;
 pub mod dispatcher;
+pub mod profile;
 pub mod proto_ccw;
 
 pub use adapter::{MinimalServices, P7HostContext};
 pub use dispatcher::install_com_dispatcher;
+pub use profile::{CallKind, CallObserver, call_span, set_call_observer};
 pub use proto_ccw::{
     ArgKind, MethodSpec, ProtoSpec, RetKind, is_proto_registered, register_crosscom_iaction,
     register_proto_ccw, wrap_proto, wrap_proto_unknown,
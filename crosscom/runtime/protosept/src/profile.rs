@@ -0,0 +1,113 @@
+//! Call-boundary instrumentation for profiling script execution.
+//!
+//! The interpreter itself has no notion of wall time, so the cheapest
+//! place to attribute cost is the script/host seam this crate already
+//! owns:
+//!
+//! - [`CallKind::Foreign`] spans wrap every `com.invoke` dispatch
+//!   (script → Rust COM method), labelled `<type_tag>` / `<method>`.
+//! - [`CallKind::Script`] spans wrap every reverse-wrapped CCW method
+//!   (Rust → script proto method), labelled `<iface type_tag>` /
+//!   `<method>`. Embedders add their own `Script` spans around direct
+//!   `push_module_function` entry points.
+//!
+//! Spans nest naturally on the single interpreter thread, so an
+//! observer can derive self time by subtracting child spans. With no
+//! observer installed [`call_span`] costs one thread-local read.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CallKind {
+    /// Time spent executing script code entered from the host.
+    Script,
+    /// Time spent in a Rust COM method called from script.
+    Foreign,
+}
+
+/// Receives span boundaries from [`call_span`]. `enter` / `exit` calls
+/// are strictly nested. Implementations must not call back into the
+/// interpreter.
+pub trait CallObserver {
+    fn enter(&self, kind: CallKind, scope: &str, name: &str);
+    fn exit(&self);
+}
+
+thread_local! {
+    static OBSERVER: RefCell<Option<Rc<dyn CallObserver>>> = const { RefCell::new(None) };
+}
+
+/// Install (or with `None`, remove) the observer for this thread.
+/// Returns the previously installed observer.
+pub fn set_call_observer(observer: Option<Rc<dyn CallObserver>>) -> Option<Rc<dyn CallObserver>> {
+    OBSERVER.with(|o| std::mem::replace(&mut *o.borrow_mut(), observer))
+}
+
+/// Open a span that closes when the returned guard drops.
+pub fn call_span(kind: CallKind, scope: &str, name: &str) -> CallSpan {
+    let observer = OBSERVER.with(|o| o.borrow().clone());
+    if let Some(observer) = &observer {
+        observer.enter(kind, scope, name);
+    }
+    CallSpan { observer }
+}
+
+/// Guard returned by [`call_span`]. Holds its own strong ref so the
+/// matching `exit` reaches the same observer even if it is swapped out
+/// mid-span.
+pub struct CallSpan {
+    observer: Option<Rc<dyn CallObserver>>,
+}
+
+impl Drop for CallSpan {
+    fn drop(&mut self) {
+        if let Some(observer) = &self.observer {
+            observer.exit();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: RefCell<Vec<String>>,
+    }
+
+    impl CallObserver for Recorder {
+        fn enter(&self, kind: CallKind, scope: &str, name: &str) {
+            self.events
+                .borrow_mut()
+                .push(format!("enter {:?} {}.{}", kind, scope, name));
+        }
+
+        fn exit(&self) {
+            self.events.borrow_mut().push("exit".to_string());
+        }
+    }
+
+    #[test]
+    fn spans_nest_and_reach_the_installed_observer() {
+        let recorder = Rc::new(Recorder::default());
+        set_call_observer(Some(recorder.clone()));
+        {
+            let _outer = call_span(CallKind::Script, "$root", "update");
+            let _inner = call_span(CallKind::Foreign, "radiance.comdef.IUiHost", "button");
+        }
+        set_call_observer(None);
+        let _ignored = call_span(CallKind::Script, "$root", "render");
+
+        assert_eq!(
+            *recorder.events.borrow(),
+            vec![
+                "enter Script $root.update",
+                "enter Foreign radiance.comdef.IUiHost.button",
+                "exit",
+                "exit",
+            ]
+        );
+    }
+}
@@ -51,6 +51,7 @@ use crosscom::{ComInterface, ComRc, IUnknown, ResultCode};
 use libffi::middle::{Cif, Closure, Type};
 use p7::interpreter::context::{Context, Data};
 
+use crate::profile::{CallKind, call_span};
 use crate::{HostError, RuntimeHandle, with_services};
 
 // ---------------------------------------------------------------------------
@@ -943,6 +944,7 @@ unsafe fn dispatch_method(
                         Err(err) => return DispatchOutcome::Error(err),
                     }
                 }
+                let _span = call_span(CallKind::Script, &userdata.iface_type_tag, &method_name);
                 invoke_script_method(ctx, root_idx, &method_name, marshalled, &ret_kind)
             })
             .unwrap_or(DispatchOutcome::Error(HostError::message(
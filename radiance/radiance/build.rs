@@ -22,6 +22,8 @@ fn main() {
             build_vulkan_shader("terrain_splat.frag");
             build_vulkan_shader("grass.vert");
             build_vulkan_shader("grass.frag");
+            build_vulkan_shader("water.vert");
+            build_vulkan_shader("water.frag");
             build_vulkan_shader("shadow_depth.vert");
             build_vulkan_shader("shadow_depth_cutout.vert");
             build_vulkan_shader("shadow_depth_cutout.frag");
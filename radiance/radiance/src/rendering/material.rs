@@ -623,6 +623,139 @@ impl GrassMaterialDef {
     }
 }
 
+/// Tunables of a [`WaterMaterialDef`] surface. Distances are world units.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WaterParams {
+    /// Colour the body tends to as the optical path through the water grows.
+    pub deep_color: [f32; 3],
+    /// Opacity when looking straight down; grazing views approach 1 through
+    /// the Fresnel term.
+    pub opacity: f32,
+    /// World units per repeat of the wave normal map.
+    pub tile: f32,
+    /// Normal-map scroll rate, in tiles per second.
+    pub scroll_speed: f32,
+    /// Ripple offset applied to the reflection lookup, in screen UV units.
+    pub distortion: f32,
+    /// Fresnel reflectance at normal incidence (Schlick `F0`).
+    pub fresnel_f0: f32,
+    /// Overall reflection / specular strength (`0` = no reflection).
+    pub reflectivity: f32,
+    /// How quickly the body colour tends to `deep_color` (`0` = clear).
+    pub turbidity: f32,
+}
+
+impl Default for WaterParams {
+    fn default() -> Self {
+        Self {
+            deep_color: [0.05, 0.18, 0.22],
+            opacity: 0.6,
+            tile: 400.0,
+            scroll_speed: 0.03,
+            distortion: 0.02,
+            fresnel_f0: 0.02,
+            reflectivity: 1.0,
+            turbidity: 0.4,
+        }
+    }
+}
+
+/// Builds a water-surface material ([`ShaderProgram::Water`]): the base
+/// colour texture plus a procedural wave normal map, alpha-blended and
+/// double-sided. [`WaterParams`] reach the shader through field reuse:
+/// `tint = [deep_color, opacity]`, `misc.y` = reflectivity (`intensity`),
+/// `misc.z` = turbidity (`ambient_floor`) and
+/// `uv_xform = [tile, scroll_speed, distortion, fresnel_f0]`.
+///
+/// `with_normals` selects [`ShaderProgram::WaterNormals`] for meshes whose
+/// vertex buffer carries normals, so water can share a model with lit
+/// materials.
+pub struct WaterMaterialDef;
+impl WaterMaterialDef {
+    const NORMAL_MAP_NAME: &'static str = "__water_normal";
+    const NORMAL_MAP_SIZE: u32 = 128;
+
+    /// Water material from raw `.dds`/`.tga` base-colour bytes, decoded
+    /// opaque (the body alpha comes from `params.opacity`).
+    pub fn create_with_data(
+        texture_name: &str,
+        data: Option<Vec<u8>>,
+        params: WaterParams,
+        with_normals: bool,
+    ) -> MaterialDef {
+        let base = TextureStore::get_or_update_opaque(texture_name, || decode_texture_data(data));
+        Self::create(texture_name, base, params, with_normals)
+    }
+
+    /// Water material over an already-loaded base-colour texture (e.g. the
+    /// one a model loader resolved for the surface's original material).
+    pub fn create(
+        debug_name: &str,
+        base: Arc<TextureDef>,
+        params: WaterParams,
+        with_normals: bool,
+    ) -> MaterialDef {
+        let normal = TextureStore::get_or_update_raw(Self::NORMAL_MAP_NAME, || {
+            Some(Self::procedural_normal_map(Self::NORMAL_MAP_SIZE))
+        });
+
+        let mut material_params = MaterialParams::default();
+        material_params.tint = [
+            params.deep_color[0],
+            params.deep_color[1],
+            params.deep_color[2],
+            params.opacity,
+        ];
+        material_params.intensity = params.reflectivity;
+        material_params.ambient_floor = params.turbidity;
+        material_params.uv_scale = [params.tile, params.scroll_speed];
+        material_params.uv_offset = [params.distortion, params.fresnel_f0];
+
+        let program = if with_normals {
+            ShaderProgram::WaterNormals
+        } else {
+            ShaderProgram::Water
+        };
+        MaterialDef::builder(program)
+            .debug_name(debug_name)
+            .textures_with_samplers(vec![base, normal], vec![SamplerDef::default(); 2])
+            // `blend()` resets alpha_ref/depth to the mode default, so it MUST
+            // precede `params()`.
+            .blend(BlendMode::AlphaBlend)
+            .cull(CullMode::None)
+            .params(material_params)
+            .build()
+    }
+
+    /// Tileable tangent-space wave normal map (RGB = XYZ, Z up), the
+    /// analytic normal of a sum of sines with integer periods over the tile
+    /// so it wraps seamlessly under REPEAT addressing.
+    fn procedural_normal_map(size: u32) -> image::RgbaImage {
+        // (frequency x, frequency y, amplitude) — integer frequencies keep
+        // the height field periodic over one tile.
+        const WAVES: [(f32, f32, f32); 4] = [
+            (1.0, 2.0, 0.30),
+            (3.0, -1.0, 0.20),
+            (-2.0, 5.0, 0.12),
+            (7.0, 4.0, 0.06),
+        ];
+        let tau = std::f32::consts::TAU;
+        image::RgbaImage::from_fn(size, size, |x, y| {
+            let u = x as f32 / size as f32;
+            let v = y as f32 / size as f32;
+            let (mut dx, mut dy) = (0.0, 0.0);
+            for (fx, fy, amp) in WAVES {
+                let c = (tau * (fx * u + fy * v)).cos() * amp;
+                dx += c * fx;
+                dy += c * fy;
+            }
+            let len = (dx * dx + dy * dy + 1.0).sqrt();
+            let encode = |n: f32| ((n / len * 0.5 + 0.5) * 255.0).round() as u8;
+            image::Rgba([encode(-dx), encode(-dy), encode(1.0), 255])
+        })
+    }
+}
+
 /// `name` plus its raw `.dds`/`.tga` bytes (decoded opaque — terrain
 /// texture alpha is non-coverage detail data).
 pub struct TerrainLayer {
@@ -24,7 +24,7 @@ pub use material::{
     BlendMode, CullMode, DepthMode, GradientYMaterialDef, GrassMaterialDef, LightMapMaterialDef,
     LitMaterialDef, MaterialDef, MaterialDefBuilder, MaterialKey, MaterialParams,
     Pal3ActorMaterialDef, Pal3GeomMaterialDef, Pal3PropMaterialDef, SimpleMaterialDef,
-    TerrainLayer, TerrainSplatMaterialDef,
+    TerrainLayer, TerrainSplatMaterialDef, WaterMaterialDef, WaterParams,
 };
 pub use platform::Window;
 pub use render_object::{RenderObject, RenderObjectHandle};
@@ -60,6 +60,26 @@ pub enum ShaderProgram {
     /// `MaterialParams.uv_xform.xy`. The grass billboard texture (real
     /// `cao###` color masked by a blade alpha) is baked CPU-side.
     GrassWind,
+
+    /// Alpha-blended water surface (PAL5 rivers and lakes). Two scrolling
+    /// normal-map samples perturb a Fresnel blend between a depth-tinted body
+    /// colour and the planar reflection the Vulkan engine renders into
+    /// per-frame binding 2 (see `WaterMaterialDef`). Textures are
+    /// `[base colour, normal map]`; tunables ride `MaterialParams` (`tint`,
+    /// `misc.yz`, `uv_xform`). Requires `POSITION | TEXCOORD`.
+    Water,
+
+    /// [`ShaderProgram::Water`] over a mesh whose vertex buffer also carries
+    /// normals (same shaders; declares NORMAL so the buffer stride matches).
+    /// Requires `POSITION | NORMAL | TEXCOORD`.
+    WaterNormals,
+}
+
+impl ShaderProgram {
+    /// Whether this program draws a reflective water surface.
+    pub fn is_water(self) -> bool {
+        matches!(self, ShaderProgram::Water | ShaderProgram::WaterNormals)
+    }
 }
 
 pub(crate) struct ShaderProgramData {
@@ -158,6 +158,19 @@ fn get_shader_proram_data(shader: ShaderProgram) -> ShaderProgramData {
             include_bytes!("shaders/simple_triangle.frag"),
             VertexComponents::POSITION | VertexComponents::TEXCOORD | VertexComponents::TEXCOORD2,
         ),
+        // PSVita has no reflection pass; water draws as its plain base texture.
+        ShaderProgram::Water => ShaderProgramData::new(
+            "Water",
+            include_bytes!("shaders/simple_triangle.vert"),
+            include_bytes!("shaders/simple_triangle.frag"),
+            VertexComponents::POSITION | VertexComponents::TEXCOORD,
+        ),
+        ShaderProgram::WaterNormals => ShaderProgramData::new(
+            "WaterNormals",
+            include_bytes!("shaders/simple_triangle.vert"),
+            include_bytes!("shaders/simple_triangle.frag"),
+            VertexComponents::POSITION | VertexComponents::NORMAL | VertexComponents::TEXCOORD,
+        ),
         // PSVita has no per-pixel lit PAL3 actor shader; fall back to plain
         // textured (declares NORMAL so the actor buffer stride matches).
         ShaderProgram::Pal3Actor => ShaderProgramData::new(
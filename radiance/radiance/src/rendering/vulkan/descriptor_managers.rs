@@ -41,6 +41,14 @@ pub struct DescriptorManager {
     _dummy_shadow_image: Image,
     _dummy_shadow_view: ImageView,
     dummy_shadow_sampler: vk::Sampler,
+
+    /// Placeholder 1×1 color image written into per-frame **binding 2**
+    /// (the planar water reflection) for every set by default. The engine
+    /// points the swapchain's sets at its reflection target once one exists;
+    /// until then `reflection_params.x = 0` keeps the water shader from
+    /// relying on its contents.
+    _dummy_reflection_image: Image,
+    dummy_reflection_view: ImageView,
 }
 
 impl DescriptorManager {
@@ -68,6 +76,8 @@ impl DescriptorManager {
         //     (lighting + shadow sampling), so visible to both stages.
         //   binding 1 — directional shadow map (COMBINED_IMAGE_SAMPLER),
         //     sampled only by the lit FRAGMENT shaders.
+        //   binding 2 — planar water reflection (COMBINED_IMAGE_SAMPLER),
+        //     sampled only by the water FRAGMENT shader.
         let per_frame_layout = Self::create_per_frame_descriptor_set_layout(&device)?;
         let per_material_params_layout = Self::create_descriptor_set_layout(
             &device,
@@ -108,6 +118,23 @@ impl DescriptorManager {
         .expect("failed to create dummy shadow image view");
         let dummy_shadow_sampler = create_shadow_sampler(&device)?;
 
+        let mut dummy_reflection_image = Image::new_color_image(allocator, 1, 1, 1)
+            .expect("failed to allocate dummy reflection image");
+        dummy_reflection_image
+            .transit_layout(
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                command_runner,
+            )
+            .expect("failed to transition dummy reflection image");
+        let dummy_reflection_view = ImageView::new_color_image_view(
+            device.clone(),
+            dummy_reflection_image.vk_image(),
+            dummy_reflection_image.vk_format(),
+            1,
+        )
+        .expect("failed to create dummy reflection image view");
+
         Ok(Self {
             device,
             texture_pool,
@@ -123,6 +150,8 @@ impl DescriptorManager {
             _dummy_shadow_image: dummy_shadow_image,
             _dummy_shadow_view: dummy_shadow_view,
             dummy_shadow_sampler,
+            _dummy_reflection_image: dummy_reflection_image,
+            dummy_reflection_view,
         })
     }
 
@@ -286,6 +315,12 @@ impl DescriptorManager {
                 .sampler(self.dummy_shadow_sampler)
                 .image_view(self._dummy_shadow_view.vk_image_view())
                 .image_layout(vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL)];
+            // binding 2 (water reflection) likewise defaults to the dummy
+            // color image; see `write_reflection_binding`.
+            let reflection_image_info = [vk::DescriptorImageInfo::default()
+                .sampler(self.sampler_cache.ui_sampler().vk_sampler())
+                .image_view(self.dummy_reflection_view.vk_image_view())
+                .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)];
             let write_descriptor_sets = [
                 vk::WriteDescriptorSet::default()
                     .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
@@ -299,6 +334,12 @@ impl DescriptorManager {
                     .dst_binding(1)
                     .dst_array_element(0)
                     .image_info(&shadow_image_info),
+                vk::WriteDescriptorSet::default()
+                    .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                    .dst_set(descriptor_sets[i])
+                    .dst_binding(2)
+                    .dst_array_element(0)
+                    .image_info(&reflection_image_info),
             ];
             self.device
                 .update_descriptor_sets(&write_descriptor_sets, &[])
@@ -332,6 +373,31 @@ impl DescriptorManager {
         }
     }
 
+    /// Overwrite **binding 2** (the planar water reflection) of the supplied
+    /// per-frame sets with `image_view` (a color image in
+    /// `SHADER_READ_ONLY_OPTIMAL`), sampled clamp-to-edge. The caller must
+    /// ensure none of the sets is in use by a pending submit.
+    pub fn write_reflection_binding(
+        &self,
+        descriptor_sets: &[vk::DescriptorSet],
+        image_view: vk::ImageView,
+    ) {
+        let sampler = self.sampler_cache.ui_sampler();
+        for set in descriptor_sets {
+            let image_info = [vk::DescriptorImageInfo::default()
+                .sampler(sampler.vk_sampler())
+                .image_view(image_view)
+                .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)];
+            let writes = [vk::WriteDescriptorSet::default()
+                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .dst_set(*set)
+                .dst_binding(2)
+                .dst_array_element(0)
+                .image_info(&image_info)];
+            self.device.update_descriptor_sets(&writes, &[]);
+        }
+    }
+
     pub fn free_per_frame_descriptor_sets(&self, descriptor_sets: &[vk::DescriptorSet]) {
         self.device
             .free_descriptor_sets(self.per_frame_pool, descriptor_sets);
@@ -403,8 +469,9 @@ impl DescriptorManager {
     }
 
     fn create_per_frame_descriptor_pool(device: &Device) -> VkResult<vk::DescriptorPool> {
-        // Each per-frame set holds one UBO (binding 0) + one combined image
-        // sampler (binding 1, the shadow map). Size both descriptor classes
+        // Each per-frame set holds one UBO (binding 0) + two combined image
+        // samplers (binding 1, the shadow map; binding 2, the water
+        // reflection). Size both descriptor classes
         // generously — render targets also draw from this pool.
         let uniform_pool_size = vk::DescriptorPoolSize::default()
             .descriptor_count(MAX_DESCRIPTOR_COUNT)
@@ -468,10 +535,11 @@ impl DescriptorManager {
         device.create_descriptor_set_layout(&create_info)
     }
 
-    /// Two-binding set-0 layout: the per-frame UBO (binding 0, VERTEX +
-    /// FRAGMENT) and the directional shadow map sampler (binding 1, FRAGMENT).
-    /// Binding 0 must reach FRAGMENT so the lit shaders can read the lighting
-    /// + shadow matrix; binding 1 is sampled only by FRAGMENT.
+    /// Three-binding set-0 layout: the per-frame UBO (binding 0, VERTEX +
+    /// FRAGMENT), the directional shadow map sampler (binding 1, FRAGMENT) and
+    /// the planar water reflection sampler (binding 2, FRAGMENT). Binding 0
+    /// must reach FRAGMENT so the lit shaders can read the lighting + shadow
+    /// matrix; bindings 1 and 2 are sampled only by FRAGMENT.
     fn create_per_frame_descriptor_set_layout(
         device: &Device,
     ) -> VkResult<vk::DescriptorSetLayout> {
@@ -486,6 +554,11 @@ impl DescriptorManager {
                 .descriptor_count(1)
                 .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
                 .stage_flags(vk::ShaderStageFlags::FRAGMENT),
+            vk::DescriptorSetLayoutBinding::default()
+                .binding(2)
+                .descriptor_count(1)
+                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .stage_flags(vk::ShaderStageFlags::FRAGMENT),
         ];
         let create_info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
         device.create_descriptor_set_layout(&create_info)
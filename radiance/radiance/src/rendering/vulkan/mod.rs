@@ -16,6 +16,7 @@ mod material;
 mod pipeline;
 mod pipeline_layout;
 mod pipeline_manager;
+mod reflection;
 mod render_object;
 mod render_pass;
 mod render_target;
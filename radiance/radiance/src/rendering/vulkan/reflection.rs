@@ -0,0 +1,195 @@
+//! Planar water reflection.
+//!
+//! When a visible material uses [`ShaderProgram::Water`] (or its
+//! `WaterNormals` layout variant), the engine renders the scene a second
+//! time — into a reduced-resolution offscreen target — from the camera
+//! mirrored about the water plane `y = height`, and binds the result to
+//! per-frame **binding 2** so `water.frag` can sample it in screen space.
+//! Only the plane of the visible water surface with the largest horizontal
+//! footprint is reflected each frame; other water surfaces at a different
+//! height reuse it.
+//!
+//! The mirrored view additionally flips view-space X. A reflection alone
+//! reverses triangle winding, which would make every back-face-culled
+//! pipeline cull the wrong side; the extra flip restores the winding so the
+//! reflection pass can reuse the main pass's pipelines unchanged. The water
+//! shader undoes the flip by sampling at `u' = 1 - u`.
+//!
+//! Geometry below the water plane is removed with an oblique near plane
+//! (Lengyel, "Oblique View Frustum Depth Projection and Clipping") instead of
+//! a per-shader clip distance, again so no existing shader needs to change.
+//!
+//! [`ShaderProgram::Water`]: crate::rendering::ShaderProgram::Water
+
+use crate::math::Mat44;
+use crate::scene::Frustum;
+
+/// The reflection target is rendered at `1 / REFLECTION_DOWNSCALE` of the
+/// scene extent on each axis. The water shader distorts and blurs the
+/// reflection anyway, so full resolution is wasted fill rate.
+pub const REFLECTION_DOWNSCALE: u32 = 2;
+
+/// The oblique clip plane sits this far (world units) above the water plane,
+/// so geometry that pierces the surface (banks, piers) does not leave a thin
+/// unclipped sliver right at the waterline.
+const CLIP_BIAS: f32 = 1.0;
+
+/// Mirror a world-space point about the plane `y = height`.
+pub fn mirrored_point(p: [f32; 3], height: f32) -> [f32; 3] {
+    [p[0], 2.0 * height - p[1], p[2]]
+}
+
+/// View matrix of the camera mirrored about `y = height`, with view-space X
+/// flipped to preserve triangle winding (`F · view · R`).
+pub fn mirrored_view(view: &Mat44, height: f32) -> Mat44 {
+    let mut reflect = Mat44::new_identity();
+    reflect[1][1] = -1.0;
+    reflect[1][3] = 2.0 * height;
+
+    let mut flip_x = Mat44::new_identity();
+    flip_x[0][0] = -1.0;
+
+    Mat44::multiplied(&Mat44::multiplied(&flip_x, view), &reflect)
+}
+
+/// Replace the near plane of `proj` with the water plane as seen from
+/// `mirrored_view`, so everything below the water is clipped by the regular
+/// depth range. Returns `None` when the real camera is not above the plane
+/// (the mirrored eye is then on the kept side and the reflection would be
+/// meaningless).
+pub fn oblique_projection(proj: &Mat44, mirrored_view: &Mat44, height: f32) -> Option<Mat44> {
+    let v = mirrored_view.floats();
+    let clip_height = height + CLIP_BIAS;
+
+    // Plane `y = clip_height` (normal +Y, keeping the side above the water)
+    // transformed into mirrored view space. The view is orthonormal, so the
+    // normal maps through the linear part and the offset follows from one
+    // transformed point on the plane.
+    let normal = [v[0][1], v[1][1], v[2][1]];
+    let point = [
+        v[0][1] * clip_height + v[0][3],
+        v[1][1] * clip_height + v[1][3],
+        v[2][1] * clip_height + v[2][3],
+    ];
+    let d = -(normal[0] * point[0] + normal[1] * point[1] + normal[2] * point[2]);
+    let plane = [normal[0], normal[1], normal[2], d];
+
+    // The eye (view-space origin) must be strictly on the clipped side.
+    if plane[3] >= 0.0 {
+        return None;
+    }
+
+    let p = proj.floats();
+    let q = [
+        (plane[0].signum() + p[0][2]) / p[0][0],
+        (plane[1].signum() + p[1][2]) / p[1][1],
+        -1.0,
+        (1.0 + p[2][2]) / p[2][3],
+    ];
+    let dot = plane[0] * q[0] + plane[1] * q[1] + plane[2] * q[2] + plane[3] * q[3];
+    if dot.abs() <= f32::EPSILON {
+        return None;
+    }
+    let scale = 2.0 / dot;
+
+    let mut out = *proj;
+    out[2][0] = plane[0] * scale;
+    out[2][1] = plane[1] * scale;
+    out[2][2] = plane[2] * scale + 1.0;
+    out[2][3] = plane[3] * scale;
+    Some(out)
+}
+
+/// Frustum of the reflection pass, derived from the main camera's frustum:
+/// a point is visible in the reflection exactly when its mirror image is
+/// visible to the main camera, so each plane is mirrored about `y = height`.
+pub fn mirrored_frustum(frustum: &Frustum, height: f32) -> Frustum {
+    let mut planes = frustum.planes;
+    for plane in planes.iter_mut() {
+        plane[3] += 2.0 * height * plane[1];
+        plane[1] = -plane[1];
+    }
+    Frustum { planes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Vec3, aabb_visible};
+    use crate::scene::Camera;
+
+    fn transform(m: &Mat44, p: [f32; 4]) -> [f32; 4] {
+        let f = m.floats();
+        let mut out = [0.0; 4];
+        for (i, row) in f.iter().enumerate() {
+            out[i] = row[0] * p[0] + row[1] * p[1] + row[2] * p[2] + row[3] * p[3];
+        }
+        out
+    }
+
+    fn camera_above_water() -> Camera {
+        let mut camera = Camera::new();
+        camera
+            .transform_mut()
+            .set_position(&Vec3::new(0.0, 500.0, 1000.0))
+            .look_at(&Vec3::new(0.0, 0.0, 0.0));
+        camera
+    }
+
+    #[test]
+    fn mirrored_view_maps_surface_points_to_flipped_x() {
+        let camera = camera_above_water();
+        let view = Mat44::inversed(camera.transform().matrix());
+        let mirrored = mirrored_view(&view, 0.0);
+
+        // A point on the water plane sees the same view-space Y/Z in both
+        // passes, with X negated — hence `u' = 1 - u` in the water shader.
+        let p = [120.0, 0.0, -40.0, 1.0];
+        let a = transform(&view, p);
+        let b = transform(&mirrored, p);
+        assert!((a[0] + b[0]).abs() < 1e-2);
+        assert!((a[1] - b[1]).abs() < 1e-2);
+        assert!((a[2] - b[2]).abs() < 1e-2);
+    }
+
+    #[test]
+    fn oblique_projection_clips_below_water() {
+        let camera = camera_above_water();
+        let view = Mat44::inversed(camera.transform().matrix());
+        let mirrored = mirrored_view(&view, 0.0);
+        let proj = oblique_projection(camera.projection_matrix(), &mirrored, 0.0).unwrap();
+
+        let ndc_z = |p: [f32; 4]| {
+            let c = transform(&proj, transform(&mirrored, p));
+            c[2] / c[3]
+        };
+        // Above the plane, in front of the mirrored eye: inside the depth range.
+        let above = ndc_z([0.0, 100.0, 0.0, 1.0]);
+        assert!((-1.0..=1.0).contains(&above), "above = {above}");
+        // Below the plane: in front of the oblique near plane, so clipped.
+        let below = ndc_z([0.0, -100.0, 0.0, 1.0]);
+        assert!(below < -1.0, "below = {below}");
+    }
+
+    #[test]
+    fn oblique_projection_rejects_camera_below_water() {
+        let camera = camera_above_water();
+        let view = Mat44::inversed(camera.transform().matrix());
+        let mirrored = mirrored_view(&view, 800.0);
+        assert!(oblique_projection(camera.projection_matrix(), &mirrored, 800.0).is_none());
+    }
+
+    #[test]
+    fn mirrored_frustum_sees_mirror_images() {
+        let camera = camera_above_water();
+        let frustum = camera.frustum();
+        let mirrored = mirrored_frustum(&frustum, 0.0);
+
+        // The origin is at the centre of the main view; its mirror is itself.
+        assert!(aabb_visible([-1.0; 3], [1.0; 3], &mirrored));
+        // Visible exactly when the mirror image is visible to the main camera.
+        let p = [0.0, 300.0, 0.0];
+        let m = mirrored_point(p, 0.0);
+        assert_eq!(aabb_visible(p, p, &mirrored), aabb_visible(m, m, &frustum));
+    }
+}
@@ -47,7 +47,7 @@ const COLOR_FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
 /// it through the texture cache after every resize.
 struct Attachments {
     _color_image: Image,
-    _color_view: ImageView,
+    color_view: ImageView,
     _depth_image: Image,
     _depth_view: ImageView,
     framebuffer: vk::Framebuffer,
@@ -203,6 +203,16 @@ impl VulkanRenderTarget {
         &mut self.uniform_buffer
     }
 
+    /// Color attachment view (in `SHADER_READ_ONLY_OPTIMAL` after each
+    /// pass). Used by the engine to sample the planar water reflection
+    /// target from the main pass. Changes on `resize`.
+    pub fn vk_color_image_view(&self) -> vk::ImageView {
+        self.attachments
+            .as_ref()
+            .map(|a| a.color_view.vk_image_view())
+            .unwrap_or_else(vk::ImageView::null)
+    }
+
     pub fn vk_extent(&self) -> vk::Extent2D {
         vk::Extent2D {
             width: self.width,
@@ -349,7 +359,7 @@ fn build_attachments(
     Ok((
         Attachments {
             _color_image: color_image,
-            _color_view: color_view,
+            color_view,
             _depth_image: depth_image,
             _depth_view: depth_view,
             framebuffer,
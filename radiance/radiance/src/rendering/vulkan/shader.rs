@@ -170,6 +170,8 @@ static TERRAIN_SPLAT_FRAG: &'static [u8] =
     include_bytes!(concat!(env!("OUT_DIR"), "/terrain_splat.frag.spv"));
 static GRASS_VERT: &'static [u8] = include_bytes!(concat!(env!("OUT_DIR"), "/grass.vert.spv"));
 static GRASS_FRAG: &'static [u8] = include_bytes!(concat!(env!("OUT_DIR"), "/grass.frag.spv"));
+static WATER_VERT: &'static [u8] = include_bytes!(concat!(env!("OUT_DIR"), "/water.vert.spv"));
+static WATER_FRAG: &'static [u8] = include_bytes!(concat!(env!("OUT_DIR"), "/water.frag.spv"));
 static PAL3_ACTOR_VERT: &'static [u8] =
     include_bytes!(concat!(env!("OUT_DIR"), "/pal3_actor.vert.spv"));
 static PAL3_ACTOR_FRAG: &'static [u8] =
@@ -224,6 +226,18 @@ fn get_shader_proram_data(shader: ShaderProgram) -> ShaderProgramData {
             GRASS_FRAG,
             VertexComponents::POSITION | VertexComponents::TEXCOORD | VertexComponents::TEXCOORD2,
         ),
+        ShaderProgram::Water => ShaderProgramData::new(
+            "water",
+            WATER_VERT,
+            WATER_FRAG,
+            VertexComponents::POSITION | VertexComponents::TEXCOORD,
+        ),
+        ShaderProgram::WaterNormals => ShaderProgramData::new(
+            "water_normals",
+            WATER_VERT,
+            WATER_FRAG,
+            VertexComponents::POSITION | VertexComponents::NORMAL | VertexComponents::TEXCOORD,
+        ),
         ShaderProgram::Pal3Actor => ShaderProgramData::new(
             "pal3_actor",
             PAL3_ACTOR_VERT,
@@ -0,0 +1,115 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+// Water surface — fragment stage.
+//
+// Two copies of a tileable normal map, scrolled in different directions over
+// world XZ, are summed into a wave normal. The normal drives:
+//   * a Schlick Fresnel term blending the body colour toward the reflection;
+//   * a small offset of the planar-reflection lookup (ripple distortion);
+//   * a sun specular highlight.
+//
+// The body colour approximates depth-based tint without a scene-depth read:
+// the optical path through a layer of turbidity `misc.z` grows as the view
+// grazes the surface (`1 / N·V`), so shallow-looking head-on views keep the
+// base texture while distant water tends to the deep colour `tint.rgb`.
+//
+// The reflection is the mirrored scene the engine renders into per-frame
+// binding 2 (see `rendering/vulkan/reflection.rs`). That pass flips view-space
+// X to preserve winding, so it is sampled at `1 - u`. When no reflection was
+// rendered this frame (`reflectionParams.x == 0`: camera below the surface,
+// offscreen editor preview) the fog colour — or a sky estimate from ambient +
+// sun — stands in.
+//
+// Material lanes (`WaterMaterialDef`):
+//   tint      = rgb deep colour, a = head-on opacity
+//   misc.y    = reflectivity (scales Fresnel), misc.z = turbidity, w = fog_exempt
+//   uv_xform  = x normal-map tile (world units), y scroll speed (tiles/sec),
+//               z reflection distortion (UV units), w Fresnel F0
+layout(set = 0, binding = 0) uniform PerFrameUbo {
+    mat4 view;
+    mat4 proj;
+    vec4 ambient;
+    vec4 lightPos[16];
+    vec4 lightColor[16];
+    vec4 sunDir;             // xyz = direction toward the sun, w = enabled
+    vec4 sunColor;
+    mat4 lightViewProj[3];
+    vec4 cascadeSplits;
+    vec4 shadowParams;
+    vec4 fogColor;           // rgb = linear fog color
+    vec4 fogParams;          // x = enabled, y = start depth, z = end depth
+    vec4 timeParams;         // x = elapsed seconds
+    vec4 cameraPos;          // xyz = world-space eye
+    vec4 reflectionParams;   // x = reflection valid
+} perFrameUbo;
+
+layout(set = 0, binding = 2) uniform sampler2D reflectionMap;
+layout(set = 2, binding = 0) uniform sampler2D texSampler[2];   // [base colour, normal map]
+layout(set = 3, binding = 0) uniform MaterialParams {
+    vec4 tint;
+    vec4 misc;
+    vec4 uv_xform;
+} mat;
+
+layout(location = 0) in vec2 fragTexCoord;
+layout(location = 1) in vec3 fragWorldPos;
+layout(location = 2) in vec4 fragClipPos;
+layout(location = 0) out vec4 outColor;
+
+vec3 sampleNormal(vec2 uv) {
+    // Tangent-space normal map (RGB = XYZ, Z up) mapped onto the XZ plane.
+    vec3 n = texture(texSampler[1], uv).rgb * 2.0 - 1.0;
+    return vec3(n.x, n.z, n.y);
+}
+
+void main() {
+    float tile = max(mat.uv_xform.x, 1e-3);
+    float scroll = mat.uv_xform.y * perFrameUbo.timeParams.x;
+    vec2 baseUV = fragWorldPos.xz / tile;
+    vec3 n = normalize(sampleNormal(baseUV + vec2(scroll, scroll * 0.6))
+                       + sampleNormal(baseUV * 1.7 + vec2(-scroll * 0.8, scroll * 0.3)));
+
+    vec3 toEye = perFrameUbo.cameraPos.xyz - fragWorldPos;
+    vec3 v = normalize(toEye);
+    float ndotv = max(dot(n, v), 0.0);
+
+    float f0 = mat.uv_xform.w;
+    float fresnel = clamp((f0 + (1.0 - f0) * pow(1.0 - ndotv, 5.0)) * mat.misc.y, 0.0, 1.0);
+
+    vec3 reflection;
+    if (perFrameUbo.reflectionParams.x > 0.5) {
+        vec2 ndc = fragClipPos.xy / fragClipPos.w;
+        vec2 uv = vec2(1.0 - (ndc.x * 0.5 + 0.5), ndc.y * 0.5 + 0.5);
+        uv = clamp(uv + n.xz * mat.uv_xform.z, vec2(0.001), vec2(0.999));
+        reflection = texture(reflectionMap, uv).rgb;
+    } else if (perFrameUbo.fogParams.x > 0.5) {
+        reflection = perFrameUbo.fogColor.rgb;
+    } else {
+        reflection = perFrameUbo.ambient.rgb + 0.5 * perFrameUbo.sunColor.rgb;
+    }
+
+    vec3 base = texture(texSampler[0], fragTexCoord).rgb;
+    float absorb = 1.0 - exp(-mat.misc.z / max(ndotv, 0.05));
+    vec3 body = mix(base, mat.tint.rgb, absorb);
+    body *= perFrameUbo.ambient.rgb + perFrameUbo.sunColor.rgb * max(perFrameUbo.sunDir.y, 0.0);
+
+    vec3 color = mix(body, reflection, fresnel);
+    if (perFrameUbo.sunDir.w > 0.5) {
+        vec3 h = normalize(normalize(perFrameUbo.sunDir.xyz) + v);
+        color += perFrameUbo.sunColor.rgb * pow(max(dot(n, h), 0.0), 128.0) * mat.misc.y;
+    }
+
+    // Head-on the surface shows `tint.a` of the body; at grazing angles the
+    // reflection makes it opaque. Premultiplied, like every blended material.
+    float alpha = mix(mat.tint.a, 1.0, fresnel);
+    outColor = vec4(color * alpha, alpha);
+
+    if (perFrameUbo.fogParams.x > 0.5 && mat.misc.w < 0.5) {
+        float d = -(vec4(fragWorldPos, 1.0) * perFrameUbo.view).z;
+        float fStart = perFrameUbo.fogParams.y;
+        float fEnd = perFrameUbo.fogParams.z;
+        float vis = clamp((fEnd - d) / max(fEnd - fStart, 1e-4), 0.0, 1.0);
+        outColor.rgb = mix(perFrameUbo.fogColor.rgb * outColor.a, outColor.rgb, vis);
+    }
+}
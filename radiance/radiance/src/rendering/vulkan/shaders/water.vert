@@ -0,0 +1,37 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+// Water surface — vertex stage.
+//
+// Plain transform; all the wave work happens per-pixel in `water.frag`. The
+// world position is forwarded for the scrolling normal-map UVs, the view
+// vector and fog, and the clip position for the screen-space reflection
+// lookup.
+layout(set = 0, binding = 0) uniform PerFrameUbo {
+    mat4 view;
+    mat4 proj;
+} perFrameUbo;
+
+layout(set = 1, binding = 0) uniform PerInstanceUbo {
+    mat4 model;
+} perInstanceUbo;
+
+layout(location = 0) in vec3 position;
+layout(location = 2) in vec2 inTexCoord;
+
+layout(location = 0) out vec2 fragTexCoord;
+layout(location = 1) out vec3 fragWorldPos;
+layout(location = 2) out vec4 fragClipPos;
+
+mat4 clip = mat4(vec4(1.0, 0.0, 0.0, 0.0),
+                 vec4(0.0, -1.0, 0.0, 0.0),
+                 vec4(0.0, 0.0, 0.5, 0.5),
+                 vec4(0.0, 0.0, 0, 1.0));
+
+void main() {
+    vec4 world = vec4(position, 1.0) * perInstanceUbo.model;
+    gl_Position = world * perFrameUbo.view * perFrameUbo.proj * clip;
+    fragTexCoord = inTexCoord;
+    fragWorldPos = world.xyz;
+    fragClipPos = gl_Position;
+}
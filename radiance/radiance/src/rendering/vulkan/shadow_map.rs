@@ -441,6 +441,10 @@ fn program_components(program: ShaderProgram) -> VertexComponents {
         ShaderProgram::GrassWind => {
             VertexComponents::POSITION | VertexComponents::TEXCOORD | VertexComponents::TEXCOORD2
         }
+        ShaderProgram::Water => VertexComponents::POSITION | VertexComponents::TEXCOORD,
+        ShaderProgram::WaterNormals => {
+            VertexComponents::POSITION | VertexComponents::NORMAL | VertexComponents::TEXCOORD
+        }
         ShaderProgram::Pal3Actor => {
             VertexComponents::POSITION | VertexComponents::NORMAL | VertexComponents::TEXCOORD
         }
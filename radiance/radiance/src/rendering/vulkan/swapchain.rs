@@ -434,9 +434,8 @@ impl SwapChain {
     }
 
     /// Logical scene extent in pixels when `SceneScaleMode::Logical` is
-    /// active; otherwise the swapchain `current_extent`. Exposed for
-    /// diagnostics / future callers.
-    #[allow(dead_code)]
+    /// active; otherwise the swapchain `current_extent`. Sizes the planar
+    /// water reflection target.
     pub fn scene_extent(&self) -> vk::Extent2D {
         match &self.logical {
             Some(l) => l.logical_extent,
@@ -449,6 +448,14 @@ impl SwapChain {
         self.logical.is_some()
     }
 
+    /// Point per-frame binding 2 of every swapchain image's set at the
+    /// engine's planar water reflection target. The caller must have waited
+    /// for the device to go idle (the sets may otherwise still be in use).
+    pub fn write_reflection_binding(&self, image_view: vk::ImageView) {
+        self.descriptor_manager
+            .write_reflection_binding(&self.per_frame_descriptor_sets, image_view);
+    }
+
     pub fn set_imgui(&mut self, imgui: Rc<RefCell<ImguiRenderer>>) {
         self.imgui = Some(imgui);
     }
@@ -175,6 +175,14 @@ pub struct PerFrameUniformBuffer {
     /// pre-existing field — which the lit/terrain/simple shaders declare up
     /// to `fogParams` — are unchanged.
     time_params: [f32; 4],
+    /// World-space eye position (`xyz`; `w` reserved). Consumed by the water
+    /// shader's Fresnel and specular terms. Appended after `time_params` for
+    /// the same std140-prefix reason.
+    camera_pos: [f32; 4],
+    /// Planar water reflection: `x` = valid flag (`1.0` when the reflection
+    /// pass rendered into per-frame binding 2 this frame, else `0.0`),
+    /// `yzw` reserved.
+    reflection_params: [f32; 4],
 }
 
 /// Maximum number of scene point lights uploaded per frame. PAL3 scenes ship
@@ -198,6 +206,8 @@ impl PerFrameUniformBuffer {
             fog_color: [0.0; 4],
             fog_params: [0.0; 4],
             time_params: [0.0; 4],
+            camera_pos: [0.0; 4],
+            reflection_params: [0.0; 4],
         }
     }
 
@@ -283,6 +293,18 @@ impl PerFrameUniformBuffer {
     pub fn set_time(&mut self, seconds: f32) {
         self.time_params = [seconds, 0.0, 0.0, 0.0];
     }
+
+    /// Stamp the world-space eye position into `camera_pos.xyz`.
+    pub fn set_camera_position(&mut self, position: [f32; 3]) {
+        self.camera_pos = [position[0], position[1], position[2], 1.0];
+    }
+
+    /// Flag whether per-frame binding 2 holds this frame's planar water
+    /// reflection (`reflection_params.x`). When unset the water shader falls
+    /// back to the fog / ambient colour for its reflection term.
+    pub fn set_reflection(&mut self, valid: bool) {
+        self.reflection_params = [if valid { 1.0 } else { 0.0 }, 0.0, 0.0, 0.0];
+    }
 }
 
 /// GPU-side layout of [`crate::rendering::MaterialParams`] used by the
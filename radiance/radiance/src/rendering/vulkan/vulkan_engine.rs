@@ -1,7 +1,9 @@
 use super::descriptor_managers::DescriptorManager;
 use super::helpers;
 use super::imgui::ImguiRenderer;
+use super::reflection;
 use super::render_object::VulkanRenderObject;
+use super::render_target::VulkanRenderTarget;
 use super::shadow_map::ShadowMap;
 use super::swapchain::SwapChain;
 use super::{adhoc_command_runner::AdhocCommandRunner, device::Device};
@@ -13,7 +15,7 @@ use super::{
 use crate::comdef::{IEntity, IEntityExt, IScene, ISceneExt};
 use crate::math::{Mat44, Vec3};
 use crate::rendering::{BlendMode, RenderObject, RenderingComponent};
-use crate::scene::{Camera, Viewport};
+use crate::scene::{Camera, Frustum, Viewport};
 use crate::{
     imgui::{ImguiContext, ImguiFrame},
     rendering::{ComponentFactory, RenderingEngine, Window},
@@ -50,6 +52,12 @@ fn engine_time_seconds() -> f32 {
     (start.elapsed().as_secs_f64() % 3600.0) as f32
 }
 
+/// World-space eye position of `camera`.
+fn camera_position(camera: &Camera) -> [f32; 3] {
+    let m = camera.transform().matrix();
+    [m[0][3], m[1][3], m[2][3]]
+}
+
 /// Snapshot of a scene's lighting environment in the shape the per-frame UBO
 /// consumes: flat ambient color, point lights as
 /// `(position, color, [inner, outer])`, an optional directional sun as
@@ -86,6 +94,12 @@ pub struct VulkanRenderingEngine {
     /// Resolution-independent, so it survives swapchain recreation.
     shadow_map: Rc<ShadowMap>,
 
+    /// Planar water reflection target (see `reflection`). Created on the
+    /// first frame that shows a water surface and dropped with the swapchain,
+    /// since it is sized from the scene extent and bound into the swapchain's
+    /// per-frame sets.
+    reflection_target: Option<VulkanRenderTarget>,
+
     surface_entry: ash::khr::surface::Instance,
     debug_entry: ash::ext::debug_utils::Instance,
 
@@ -155,6 +169,16 @@ pub struct VulkanRenderingEngine {
     // last `bucketize_visible`, used to fit the CSM cascade splits to actual
     // scene geometry. `None` when nothing is visible.
     scratch_caster_band: Option<(f32, f32)>,
+    // Largest visible water surface from the last `bucketize_visible`, as
+    // `(world-space XZ footprint, world-space height)`. Drives the planar
+    // reflection pass; `None` skips it.
+    scratch_water_plane: Option<(f32, f32)>,
+    // Draw lists of the reflection pass, bucketed from `scratch_components`
+    // against the mirrored frustum (water surfaces excluded).
+    scratch_reflect_opaque: Vec<Rc<VulkanRenderObject>>,
+    scratch_reflect_cutout: Vec<Rc<VulkanRenderObject>>,
+    scratch_reflect_transparent: Vec<(f32, Rc<VulkanRenderObject>)>,
+    scratch_reflect_transparent_ordered: Vec<Rc<VulkanRenderObject>>,
 }
 
 impl RenderingEngine for VulkanRenderingEngine {
@@ -350,10 +374,12 @@ impl RenderingEngine for VulkanRenderingEngine {
             )
         };
 
-        // Editor render-target preview doesn't run a shadow pass.
+        // Editor render-target preview doesn't run a shadow or reflection
+        // pass.
         let mut caster_band = None;
         let mut caster_candidates_opaque = Vec::new();
         let mut caster_candidates_cutout = Vec::new();
+        let mut water_plane = None;
         Self::bucketize_visible(
             &entities,
             &scene.camera(),
@@ -365,6 +391,7 @@ impl RenderingEngine for VulkanRenderingEngine {
             &mut caster_candidates_opaque,
             &mut caster_candidates_cutout,
             &mut caster_band,
+            &mut water_plane,
         );
 
         // Update target's per-frame UBO with the scene's camera + lights.
@@ -374,65 +401,23 @@ impl RenderingEngine for VulkanRenderingEngine {
         ubo.set_sun(sun);
         ubo.set_fog(fog);
         ubo.set_time(engine_time_seconds());
+        ubo.set_camera_position(camera_position(&scene.camera()));
         target.uniform_buffer_mut().copy_memory_from(&[ubo]);
 
         // Record + submit the offscreen pass.
-        let target_cmd = target.vk_command_buffer();
-        let target_rp = target.vk_render_pass();
-        let target_fb = target.vk_framebuffer();
-        let target_extent = target.vk_extent();
-        let target_descriptor = target.per_frame_descriptor_set();
-        let target_semaphore = target.vk_render_finished_semaphore();
-        let target_fence = target.vk_in_flight_fence();
-
-        // Block until the previous offscreen submit using this target's
-        // single command buffer has completed before resetting/re-recording
-        // it. Without this, a heavy scene (e.g. a large BSP) lets the GPU
-        // fall behind and the host overwrites an in-flight command buffer,
-        // which loses the device (ERROR_DEVICE_LOST).
-        if let Err(e) = self.device.wait_for_fences(&[target_fence], true, u64::MAX) {
-            log::warn!("offscreen fence wait failed: {:?}", e);
-            return;
-        }
-
         let swapchain = self.swapchain.as_mut().unwrap();
-        if let Err(e) = swapchain.record_to_external_framebuffer(
-            target_cmd,
-            target_rp,
-            target_fb,
-            target_extent,
-            target_descriptor,
+        if let Some(semaphore) = Self::submit_offscreen_pass(
+            &self.device,
+            self.queue,
+            swapchain,
+            &dub_manager,
+            target,
             &self.scratch_opaque,
             &self.scratch_cutout,
             &self.scratch_transparent_ordered,
-            &dub_manager,
         ) {
-            // Fence is still signaled (we never reset it), so the next
-            // frame's `wait_for_fences` won't deadlock.
-            log::warn!("offscreen record failed: {:?}", e);
-            return;
+            self.pending_offscreen_waits.push(semaphore);
         }
-
-        let signal = [target_semaphore];
-        let commands = [target_cmd];
-        let submit = vk::SubmitInfo::default()
-            .command_buffers(&commands)
-            .signal_semaphores(&signal);
-        // Reset the fence only now that we're certain to submit, so every
-        // early-return path above leaves it signaled.
-        if let Err(e) = self.device.reset_fences(&[target_fence]) {
-            log::warn!("offscreen fence reset failed: {:?}", e);
-            return;
-        }
-        if let Err(e) = self
-            .device
-            .queue_submit(self.queue, &[submit], target_fence)
-        {
-            log::warn!("offscreen submit failed: {:?}", e);
-            return;
-        }
-
-        self.pending_offscreen_waits.push(target_semaphore);
     }
 
     fn update_imgui_font_atlas(&mut self, context: &crate::imgui::ImguiContext) {
@@ -818,6 +803,7 @@ impl VulkanRenderingEngine {
             dub_manager: Some(dub_manager),
             adhoc_command_runner,
             shadow_map,
+            reflection_target: None,
             component_factory,
             surface_entry,
             debug_entry,
@@ -840,6 +826,11 @@ impl VulkanRenderingEngine {
                 .map(|_| Vec::new())
                 .collect(),
             scratch_caster_band: None,
+            scratch_water_plane: None,
+            scratch_reflect_opaque: Vec::new(),
+            scratch_reflect_cutout: Vec::new(),
+            scratch_reflect_transparent: Vec::new(),
+            scratch_reflect_transparent_ordered: Vec::new(),
             logical_extent,
         };
 
@@ -902,6 +893,7 @@ impl VulkanRenderingEngine {
     fn recreate_swapchain(&mut self) -> Result<(), Box<dyn Error>> {
         self.device.wait_idle();
 
+        self.reflection_target = None;
         self.swapchain = None;
         let capabilities = self.get_capabilities()?;
         let mut swapchain = SwapChain::new(
@@ -1028,6 +1020,7 @@ impl VulkanRenderingEngine {
                 &mut self.scratch_caster_candidates_opaque,
                 &mut self.scratch_caster_candidates_cutout,
                 &mut self.scratch_caster_band,
+                &mut self.scratch_water_plane,
             );
         } else {
             self.scratch_components.clear();
@@ -1038,8 +1031,18 @@ impl VulkanRenderingEngine {
             self.scratch_caster_candidates_opaque.clear();
             self.scratch_caster_candidates_cutout.clear();
             self.scratch_caster_band = None;
+            self.scratch_water_plane = None;
         }
 
+        // Planar water reflection: render the mirrored scene into the
+        // reflection target (its own submit, waited on by the main submit
+        // below) so the water shader can sample it through per-frame
+        // binding 2.
+        let reflection_valid = match (camera, self.scratch_water_plane) {
+            (Some(cam), Some((_, height))) => self.render_reflection(cam, height, &lighting),
+            _ => false,
+        };
+
         // Shadows run only when the scene has a directional sun (and a camera
         // to derive the light frustum focus from).
         let shadows_enabled = camera.is_some() && lighting.2.is_some();
@@ -1099,6 +1102,8 @@ impl VulkanRenderingEngine {
                     ubo.set_sun(lighting.2);
                     ubo.set_fog(lighting.3);
                     ubo.set_time(engine_time_seconds());
+                    ubo.set_camera_position(camera_position(cam));
+                    ubo.set_reflection(reflection_valid);
 
                     // Directional CSM: upload the per-cascade matrices + split
                     // depths computed (and used to cull casters) above.
@@ -1190,6 +1195,7 @@ impl VulkanRenderingEngine {
 
     fn drop_swapchain(&mut self) {
         self.device.wait_idle();
+        self.reflection_target = None;
         self.swapchain = None;
         // Indices into the old swapchain's image vec are meaningless
         // for the next one; clear so capture_last_frame doesn't try to
@@ -1324,7 +1330,10 @@ impl VulkanRenderingEngine {
 
     /// Every output Vec is `clear()`ed (capacity retained) before
     /// population, so callers should pass the engine's persistent
-    /// scratch fields to avoid per-frame allocations.
+    /// scratch fields to avoid per-frame allocations. `water_plane_out`
+    /// receives the visible water object with the largest
+    /// world-space XZ footprint as `(footprint area, centroid height)`, so a
+    /// small stray water surface can't move the plane of the main body.
     fn bucketize_visible(
         entities: &[ComRc<IEntity>],
         camera: &Camera,
@@ -1342,6 +1351,7 @@ impl VulkanRenderingEngine {
             Option<([f32; 3], [f32; 3])>,
         )>,
         caster_band_out: &mut Option<(f32, f32)>,
+        water_plane_out: &mut Option<(f32, f32)>,
     ) {
         components_out.clear();
         opaque_out.clear();
@@ -1351,6 +1361,7 @@ impl VulkanRenderingEngine {
         caster_candidates_opaque_out.clear();
         caster_candidates_cutout_out.clear();
         *caster_band_out = None;
+        *water_plane_out = None;
 
         for entity in entities {
             if let Some(rc) = entity.get_rendering_component() {
@@ -1454,12 +1465,20 @@ impl VulkanRenderingEngine {
                         let dx = wx - camera_world[0];
                         let dy = wy - camera_world[1];
                         let dz = wz - camera_world[2];
-                        transparent_out.push((
-                            dx * dx + dy * dy + dz * dz,
-                            entity_idx,
-                            ro_idx,
-                            vro.clone(),
-                        ));
+                        let dist = dx * dx + dy * dy + dz * dz;
+                        if vro.material().key().program.is_water() {
+                            let area = world_aabb.map_or(0.0, |(wmin, wmax)| {
+                                (wmax[0] - wmin[0]) * (wmax[2] - wmin[2])
+                            });
+                            let larger = match *water_plane_out {
+                                Some((a, _)) => area > a,
+                                None => true,
+                            };
+                            if larger {
+                                *water_plane_out = Some((area, wy));
+                            }
+                        }
+                        transparent_out.push((dist, entity_idx, ro_idx, vro.clone()));
                     }
                 }
             }
@@ -1497,6 +1516,218 @@ impl VulkanRenderingEngine {
         }
     }
 
+    /// Bucket the render objects gathered by the last `bucketize_visible`
+    /// for the planar reflection pass: culled against the mirrored frustum,
+    /// water surfaces skipped (a surface can't reflect itself), transparent
+    /// objects sorted back-to-front from the mirrored eye. No shadow-caster
+    /// bookkeeping: the reflection UBO leaves shadows disabled.
+    fn bucketize_reflection(
+        components: &[(Rc<RenderingComponent>, Mat44)],
+        frustum: &Frustum,
+        eye: [f32; 3],
+        opaque_out: &mut Vec<Rc<VulkanRenderObject>>,
+        cutout_out: &mut Vec<Rc<VulkanRenderObject>>,
+        transparent_out: &mut Vec<(f32, Rc<VulkanRenderObject>)>,
+        transparent_ordered_out: &mut Vec<Rc<VulkanRenderObject>>,
+    ) {
+        opaque_out.clear();
+        cutout_out.clear();
+        transparent_out.clear();
+        transparent_ordered_out.clear();
+
+        for (rendering, world) in components {
+            let m = world.floats();
+            for vro in rendering.vulkan_render_objects().iter() {
+                let key = vro.material().key();
+                if key.program.is_water() {
+                    continue;
+                }
+                let visible = match vro.local_aabb() {
+                    Some((lmin, lmax)) => {
+                        let (wmin, wmax) = crate::math::transform_aabb(lmin, lmax, world);
+                        crate::math::aabb_visible(wmin, wmax, frustum)
+                    }
+                    None => true,
+                };
+                if !visible {
+                    continue;
+                }
+                match key.blend {
+                    BlendMode::Opaque => opaque_out.push(vro.clone()),
+                    BlendMode::AlphaTest => cutout_out.push(vro.clone()),
+                    BlendMode::AlphaBlend | BlendMode::Additive | BlendMode::Multiply => {
+                        let c = vro.local_centroid();
+                        let dx =
+                            m[0][0] * c[0] + m[0][1] * c[1] + m[0][2] * c[2] + m[0][3] - eye[0];
+                        let dy =
+                            m[1][0] * c[0] + m[1][1] * c[1] + m[1][2] * c[2] + m[1][3] - eye[1];
+                        let dz =
+                            m[2][0] * c[0] + m[2][1] * c[1] + m[2][2] * c[2] + m[2][3] - eye[2];
+                        transparent_out.push((dx * dx + dy * dy + dz * dz, vro.clone()));
+                    }
+                }
+            }
+        }
+
+        // Stable sort: equal-distance siblings keep loader order, as in the
+        // main pass.
+        transparent_out.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        transparent_ordered_out.extend(transparent_out.iter().map(|(_, ro)| ro.clone()));
+    }
+
+    /// Render the scene mirrored about the water plane `y = height` into the
+    /// reflection target and queue its submit ahead of the main pass (see
+    /// `reflection`). Returns whether per-frame binding 2 holds a valid
+    /// reflection for this frame; when it doesn't, the water shader falls
+    /// back to a sky / fog colour.
+    fn render_reflection(
+        &mut self,
+        camera: &Camera,
+        height: f32,
+        lighting: &SceneLightsSnapshot,
+    ) -> bool {
+        let view = Mat44::inversed(camera.transform().matrix());
+        let mirrored_view = reflection::mirrored_view(&view, height);
+        let Some(proj) =
+            reflection::oblique_projection(camera.projection_matrix(), &mirrored_view, height)
+        else {
+            return false;
+        };
+
+        if self.reflection_target.is_none() {
+            let extent = self.swapchain.as_ref().unwrap().scene_extent();
+            let target = match VulkanRenderTarget::new(
+                (extent.width / reflection::REFLECTION_DOWNSCALE).max(1),
+                (extent.height / reflection::REFLECTION_DOWNSCALE).max(1),
+                &self.instance,
+                self.physical_device,
+                self.device.clone(),
+                self.allocator().clone(),
+                self.descriptor_manager().clone(),
+                self.dub_manager().clone(),
+                self.adhoc_command_runner.clone(),
+                self.imgui.clone(),
+            ) {
+                Ok(target) => target,
+                Err(e) => {
+                    log::warn!("reflection target creation failed: {}", e);
+                    return false;
+                }
+            };
+            // Earlier frames still in flight reference the per-frame sets
+            // being rewritten; this happens once per swapchain.
+            self.device.wait_idle();
+            self.swapchain
+                .as_ref()
+                .unwrap()
+                .write_reflection_binding(target.vk_color_image_view());
+            self.reflection_target = Some(target);
+        }
+
+        let eye = reflection::mirrored_point(camera_position(camera), height);
+        let frustum = reflection::mirrored_frustum(&camera.frustum(), height);
+        Self::bucketize_reflection(
+            &self.scratch_components,
+            &frustum,
+            eye,
+            &mut self.scratch_reflect_opaque,
+            &mut self.scratch_reflect_cutout,
+            &mut self.scratch_reflect_transparent,
+            &mut self.scratch_reflect_transparent_ordered,
+        );
+
+        let mut ubo = PerFrameUniformBuffer::new(&mirrored_view, &proj);
+        ubo.set_lighting(lighting.0, &lighting.1);
+        ubo.set_sun(lighting.2);
+        ubo.set_fog(lighting.3);
+        ubo.set_time(engine_time_seconds());
+        ubo.set_camera_position(eye);
+
+        let dub_manager = self.dub_manager().clone();
+        let target = self.reflection_target.as_mut().unwrap();
+        target.uniform_buffer_mut().copy_memory_from(&[ubo]);
+        let swapchain = self.swapchain.as_mut().unwrap();
+        match Self::submit_offscreen_pass(
+            &self.device,
+            self.queue,
+            swapchain,
+            &dub_manager,
+            target,
+            &self.scratch_reflect_opaque,
+            &self.scratch_reflect_cutout,
+            &self.scratch_reflect_transparent_ordered,
+        ) {
+            Some(semaphore) => {
+                self.pending_offscreen_waits.push(semaphore);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record `opaque` / `cutout` / `transparent` into `target`'s command
+    /// buffer and submit it, returning the semaphore the next main submit
+    /// must wait on. Failures are logged and leave the target's fence
+    /// signaled, so the next call can't deadlock.
+    #[allow(clippy::too_many_arguments)]
+    fn submit_offscreen_pass(
+        device: &Device,
+        queue: vk::Queue,
+        swapchain: &mut SwapChain,
+        dub_manager: &DynamicUniformBufferManager,
+        target: &VulkanRenderTarget,
+        opaque: &[Rc<VulkanRenderObject>],
+        cutout: &[Rc<VulkanRenderObject>],
+        transparent: &[Rc<VulkanRenderObject>],
+    ) -> Option<vk::Semaphore> {
+        let target_cmd = target.vk_command_buffer();
+        let target_semaphore = target.vk_render_finished_semaphore();
+        let target_fence = target.vk_in_flight_fence();
+
+        // Block until the previous offscreen submit using this target's
+        // single command buffer has completed before resetting/re-recording
+        // it. Without this, a heavy scene (e.g. a large BSP) lets the GPU
+        // fall behind and the host overwrites an in-flight command buffer,
+        // which loses the device (ERROR_DEVICE_LOST).
+        if let Err(e) = device.wait_for_fences(&[target_fence], true, u64::MAX) {
+            log::warn!("offscreen fence wait failed: {:?}", e);
+            return None;
+        }
+
+        if let Err(e) = swapchain.record_to_external_framebuffer(
+            target_cmd,
+            target.vk_render_pass(),
+            target.vk_framebuffer(),
+            target.vk_extent(),
+            target.per_frame_descriptor_set(),
+            opaque,
+            cutout,
+            transparent,
+            dub_manager,
+        ) {
+            log::warn!("offscreen record failed: {:?}", e);
+            return None;
+        }
+
+        let signal = [target_semaphore];
+        let commands = [target_cmd];
+        let submit = vk::SubmitInfo::default()
+            .command_buffers(&commands)
+            .signal_semaphores(&signal);
+        // Reset the fence only now that we're certain to submit, so every
+        // early-return path above leaves it signaled.
+        if let Err(e) = device.reset_fences(&[target_fence]) {
+            log::warn!("offscreen fence reset failed: {:?}", e);
+            return None;
+        }
+        if let Err(e) = device.queue_submit(queue, &[submit], target_fence) {
+            log::warn!("offscreen submit failed: {:?}", e);
+            return None;
+        }
+
+        Some(target_semaphore)
+    }
+
     /// Build the per-cascade shadow-caster draw lists by culling the candidate
     /// casters (collected without camera-frustum rejection) against each
     /// cascade's light-space volume. Opaque candidates precede cutout ones so
@@ -1575,6 +1806,7 @@ impl VulkanRenderingEngine {
 impl Drop for VulkanRenderingEngine {
     fn drop(&mut self) {
         self.device.wait_idle();
+        self.reflection_target = None;
         self.swapchain = None;
         self.descriptor_manager = None;
         self.dub_manager = None;
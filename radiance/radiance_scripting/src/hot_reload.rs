@@ -0,0 +1,408 @@
+//! Dev-mode hot reload of protosept UI and director scripts.
+//!
+//! In a normal build every crate's `scripts/` directory is baked into an
+//! in-binary `.ypk` (`mount_scripts`), so editing `main_editor.p7` means
+//! a full rebuild. Setting `YAOBOW_SCRIPT_DEV=1` switches the launchers
+//! to dev mode:
+//!
+//! 1. [`mount_dev_scripts`] mounts each crate's on-disk `scripts/`
+//!    directory over its ypk prefix. The ypk stays mounted underneath,
+//!    so codegen-only modules (`yaobow_editor_services.p7`, …) still
+//!    resolve through it.
+//! 2. [`ScriptReloadDirector`] wraps a script-built director. It polls
+//!    the same directories through a [`ScriptWatcher`]; when a module
+//!    changes it recompiles the package with [`ScriptHost::recompile`]
+//!    and calls the director factory again, deactivating the old
+//!    director and activating the new one in place.
+//!
+//! The interpreter, its `ComObjectTable` and every interned host object
+//! survive a reload; only the wrapped director (and the UI layer it
+//! backs) is rebuilt. A compile or factory error keeps the previous
+//! director running (or, if the very first build failed, runs nothing)
+//! and is drawn as an overlay until the next reload succeeds.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crosscom::ComRc;
+use crosscom_protosept::HostError;
+use radiance::asset::AssetManager;
+use radiance::comdef::{IDirector, IDirectorImpl, IUiHost, IUiLayer, IUiLayerImpl};
+use radiance::radiance::UiManager;
+
+use crate::runtime::ScriptHost;
+
+const ENV_FLAG: &str = "YAOBOW_SCRIPT_DEV";
+
+/// Seconds between two scans of the watched directories.
+const POLL_INTERVAL: f32 = 0.5;
+
+/// Whether `YAOBOW_SCRIPT_DEV` asks for on-disk scripts and hot reload.
+pub fn dev_mode_enabled() -> bool {
+    std::env::var(ENV_FLAG)
+        .map(|v| {
+            matches!(
+                v.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// An on-disk script directory and the VFS prefix its ypk is mounted at.
+#[derive(Clone, Debug)]
+pub struct ScriptSourceDir {
+    pub vfs_prefix: String,
+    pub dir: PathBuf,
+}
+
+impl ScriptSourceDir {
+    pub fn new(vfs_prefix: impl Into<String>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            vfs_prefix: vfs_prefix.into(),
+            dir: dir.into(),
+        }
+    }
+}
+
+/// This crate's `scripts/` directory, mounted at `/radiance_scripting`.
+pub fn source_dir() -> ScriptSourceDir {
+    ScriptSourceDir::new(
+        "/radiance_scripting",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/scripts"),
+    )
+}
+
+/// Mount every existing directory in `dirs` over its ypk prefix. Call
+/// after the `mount_scripts` functions so the on-disk files win.
+/// Missing directories (e.g. a shipped binary run with the flag set)
+/// are skipped with a warning.
+pub fn mount_dev_scripts(assets: &AssetManager, dirs: &[ScriptSourceDir]) {
+    for dir in dirs {
+        if !dir.dir.is_dir() {
+            log::warn!(
+                "script dev mode: {} does not exist, keeping the bundled {}",
+                dir.dir.display(),
+                dir.vfs_prefix
+            );
+            continue;
+        }
+        log::info!(
+            "script dev mode: mounting {} at {}",
+            dir.dir.display(),
+            dir.vfs_prefix
+        );
+        assets.mount_local(dir.vfs_prefix.as_str(), &dir.dir);
+    }
+}
+
+/// Polls a set of script directories for added, removed or modified
+/// `.p7` files. Modification times are compared rather than contents,
+/// so a save that doesn't change the file still counts.
+pub struct ScriptWatcher {
+    dirs: Vec<ScriptSourceDir>,
+    stamps: HashMap<PathBuf, SystemTime>,
+    since_scan: f32,
+}
+
+impl ScriptWatcher {
+    pub fn new(dirs: Vec<ScriptSourceDir>) -> Self {
+        let mut watcher = Self {
+            dirs,
+            stamps: HashMap::new(),
+            since_scan: 0.0,
+        };
+        watcher.stamps = watcher.scan();
+        watcher
+    }
+
+    /// Advance the poll timer by `delta_sec` and, once per
+    /// [`POLL_INTERVAL`], rescan. Returns the dotted module paths
+    /// (`yaobow_editor.main_editor`) that changed since the last scan.
+    pub fn poll(&mut self, delta_sec: f32) -> Vec<String> {
+        self.since_scan += delta_sec;
+        if self.since_scan < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.since_scan = 0.0;
+        self.rescan()
+    }
+
+    /// Rescan now, regardless of the poll timer.
+    pub fn rescan(&mut self) -> Vec<String> {
+        let stamps = self.scan();
+        let mut changed: Vec<String> = stamps
+            .iter()
+            .filter(|(path, stamp)| self.stamps.get(*path) != Some(*stamp))
+            .map(|(path, _)| path)
+            .chain(
+                self.stamps
+                    .keys()
+                    .filter(|path| !stamps.contains_key(*path)),
+            )
+            .filter_map(|path| self.module_path(path))
+            .collect();
+        changed.sort();
+        changed.dedup();
+        self.stamps = stamps;
+        changed
+    }
+
+    fn scan(&self) -> HashMap<PathBuf, SystemTime> {
+        let mut stamps = HashMap::new();
+        for dir in &self.dirs {
+            collect_scripts(&dir.dir, &mut stamps);
+        }
+        stamps
+    }
+
+    /// `<dir>/a/b.p7` under prefix `/crate` -> `crate.a.b`.
+    fn module_path(&self, path: &Path) -> Option<String> {
+        let dir = self.dirs.iter().find(|d| path.starts_with(&d.dir))?;
+        let relative = path.strip_prefix(&dir.dir).ok()?.with_extension("");
+        let segments = dir
+            .vfs_prefix
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .chain(
+                relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+            );
+        Some(segments.collect::<Vec<_>>().join("."))
+    }
+}
+
+fn collect_scripts(dir: &Path, out: &mut HashMap<PathBuf, SystemTime>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            collect_scripts(&path, out);
+        } else if path.extension().is_some_and(|ext| ext == "p7") {
+            if let Ok(modified) = metadata.modified() {
+                out.insert(path, modified);
+            }
+        }
+    }
+}
+
+/// Builds the wrapped director: typically boxes the (already interned)
+/// host context, calls the script's factory function and
+/// `wrap_director`s the result. Called once up front and again after
+/// every successful recompile.
+pub type DirectorFactory = Box<dyn Fn() -> Result<ComRc<IDirector>, HostError>>;
+
+/// Director that forwards to a script-built director and rebuilds it
+/// when the watched scripts change. Transitions requested by the inner
+/// director pass straight through; wrap the next director separately if
+/// it should reload too.
+pub struct ScriptReloadDirector {
+    host: Rc<ScriptHost>,
+    ui: Rc<UiManager>,
+    factory: DirectorFactory,
+    watcher: RefCell<ScriptWatcher>,
+    /// `None` until a build has succeeded.
+    current: RefCell<Option<ComRc<IDirector>>>,
+    active: Cell<bool>,
+    error: RefCell<Option<String>>,
+}
+
+ComObject_ScriptReloadDirector!(super::ScriptReloadDirector);
+
+impl ScriptReloadDirector {
+    /// Build the first director through `factory` and wrap it. If that
+    /// first build fails the error is shown in place of a director and
+    /// the scripts keep being watched, so fixing them recovers without
+    /// a restart.
+    pub fn create(
+        host: Rc<ScriptHost>,
+        ui: Rc<UiManager>,
+        dirs: Vec<ScriptSourceDir>,
+        factory: DirectorFactory,
+    ) -> ComRc<IDirector> {
+        let (current, error) = match factory() {
+            Ok(current) => (Some(current), None),
+            Err(err) => {
+                log::error!("script dev mode: initial build failed: {}", err);
+                (None, Some(err.to_string()))
+            }
+        };
+        ComRc::from_object(Self {
+            host,
+            ui,
+            factory,
+            watcher: RefCell::new(ScriptWatcher::new(dirs)),
+            current: RefCell::new(current),
+            active: Cell::new(false),
+            error: RefCell::new(error),
+        })
+    }
+
+    fn reload(&self, modules: &[String]) {
+        log::info!("script dev mode: reloading after changes to {:?}", modules);
+        let next = self.host.recompile().and_then(|()| (self.factory)());
+        match next {
+            Ok(next) => {
+                let previous = self.current.replace(Some(next.clone()));
+                if self.active.get() {
+                    if let Some(previous) = previous {
+                        previous.deactivate();
+                    }
+                    next.activate();
+                }
+                *self.error.borrow_mut() = None;
+            }
+            Err(err) => {
+                log::error!("script dev mode: reload failed: {}", err);
+                *self.error.borrow_mut() = Some(err.to_string());
+            }
+        }
+    }
+
+    fn render_error(&self, message: &str) {
+        let hint = if self.current.borrow().is_some() {
+            "Still running the previous scripts. Fix the error and save to retry."
+        } else {
+            "The scripts failed to build. Fix the error and save to retry."
+        };
+        let ui = self.ui.ui();
+        ui.window("Script reload failed")
+            .position([16.0, 16.0], imgui::Condition::FirstUseEver)
+            .size([720.0, 320.0], imgui::Condition::FirstUseEver)
+            .flags(imgui::WindowFlags::NO_SAVED_SETTINGS)
+            .build(|| {
+                ui.text_colored([1.0, 0.45, 0.4, 1.0], hint);
+                ui.separator();
+                ui.text_wrapped(message);
+            });
+    }
+}
+
+impl IDirectorImpl for ScriptReloadDirector {
+    fn activate(&self) {
+        self.active.set(true);
+        let current = self.current.borrow().clone();
+        if let Some(current) = current {
+            current.activate();
+        }
+    }
+
+    fn update(&self, delta_sec: f32) -> Option<ComRc<IDirector>> {
+        let changed = self.watcher.borrow_mut().poll(delta_sec);
+        if !changed.is_empty() {
+            self.reload(&changed);
+        }
+
+        // Clone out so a re-entrant reload from inside the script can't
+        // hit an outstanding borrow.
+        let current = self.current.borrow().clone();
+        current.and_then(|current| current.update(delta_sec))
+    }
+
+    fn deactivate(&self) {
+        self.active.set(false);
+        let current = self.current.borrow().clone();
+        if let Some(current) = current {
+            current.deactivate();
+        }
+    }
+}
+
+impl IUiLayerImpl for ScriptReloadDirector {
+    // Forward to the wrapped director's `IUiLayer` slot (re-queried
+    // every frame, so a swapped director is picked up immediately),
+    // then draw the error overlay on top with raw imgui.
+    fn render(&self, ui_host: ComRc<IUiHost>, dt: f32) {
+        let layer = self
+            .current
+            .borrow()
+            .as_ref()
+            .and_then(|current| current.query_interface::<IUiLayer>());
+        if let Some(layer) = layer {
+            layer.render(ui_host, dt);
+        }
+
+        if let Some(message) = self.error.borrow().as_deref() {
+            self.render_error(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    fn unique_tmp(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("script_watcher_{}_{}", std::process::id(), name))
+    }
+
+    /// Bump `path`'s mtime explicitly; coarse file-system timestamps
+    /// would otherwise make back-to-back writes look unchanged.
+    fn touch_later(path: &Path) {
+        let file = fs::File::options().write(true).open(path).unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        file.set_modified(later).unwrap();
+    }
+
+    #[test]
+    fn reports_changed_added_and_removed_modules() {
+        let root = unique_tmp("changes");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("panels")).unwrap();
+        fs::write(root.join("main.p7"), "// main").unwrap();
+        fs::write(root.join("panels/tree.p7"), "// tree").unwrap();
+        fs::write(root.join("notes.txt"), "not a script").unwrap();
+
+        let mut watcher = ScriptWatcher::new(vec![ScriptSourceDir::new("/yaobow_editor", &root)]);
+        assert!(watcher.rescan().is_empty());
+
+        touch_later(&root.join("panels/tree.p7"));
+        assert_eq!(watcher.rescan(), vec!["yaobow_editor.panels.tree"]);
+
+        fs::write(root.join("extra.p7"), "// extra").unwrap();
+        fs::remove_file(root.join("main.p7")).unwrap();
+        assert_eq!(
+            watcher.rescan(),
+            vec!["yaobow_editor.extra", "yaobow_editor.main"]
+        );
+
+        touch_later(&root.join("notes.txt"));
+        assert!(watcher.rescan().is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn poll_waits_for_the_interval() {
+        let root = unique_tmp("interval");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.p7"), "// a").unwrap();
+
+        let mut watcher = ScriptWatcher::new(vec![ScriptSourceDir::new("/", &root)]);
+        touch_later(&root.join("a.p7"));
+        assert!(watcher.poll(POLL_INTERVAL / 2.0).is_empty());
+        assert_eq!(watcher.poll(POLL_INTERVAL), vec!["a"]);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn missing_directories_are_ignored() {
+        let mut watcher =
+            ScriptWatcher::new(vec![ScriptSourceDir::new("/x", unique_tmp("missing"))]);
+        assert!(watcher.rescan().is_empty());
+    }
+}
@@ -20,11 +20,15 @@ pub mod script_bridges {
     }
 }
 
+pub mod hot_reload;
+pub mod profiler;
 pub mod proxies;
 pub mod runtime;
 pub mod script_vfs;
 pub mod services;
 
+pub use hot_reload::{ScriptReloadDirector, ScriptSourceDir};
+pub use profiler::ScriptProfiler;
 pub use proxies::{
     ImguiUiFrameRenderer, install_imgui_ui_renderer, install_imgui_ui_renderer_with_cache,
 };
@@ -0,0 +1,484 @@
+//! Env-gated profiler for p7 script execution.
+//!
+//! Set `YAOBOW_SCRIPT_PROFILE=1` to install a [`ScriptProfiler`] as the
+//! thread's [`CallObserver`]. It then sees every span that
+//! `crosscom_protosept` opens at the script/host seam (host → script
+//! function calls, reverse-wrapped director / UI-layer methods, and
+//! every `@foreign` COM call a script makes) and attributes wall time
+//! to each one:
+//!
+//! - *total* time is the span's inclusive duration;
+//! - *self* time subtracts nested spans, so a script panel that spends
+//!   most of its frame in `IUiHost` calls shows up as cheap script and
+//!   expensive foreign dispatch.
+//!
+//! Per-frame figures are rolled over by [`ScriptProfiler::end_frame`],
+//! which the imgui frame renderer calls once per presented frame; the
+//! perf overlay (`YAOBOW_EDITOR_PERF_OVERLAY=1`) lists the most
+//! expensive functions of the last frame. The most recent spans are also
+//! kept in a bounded ring buffer and can be exported as Chrome-trace
+//! JSON (`chrome://tracing`, Perfetto) via
+//! [`ScriptProfiler::export_chrome_trace`], to `YAOBOW_SCRIPT_TRACE` or
+//! `p7_trace.json` by default.
+//!
+//! Script spans exist only where the host enters p7. A script function
+//! called from another script function has no span of its own, because
+//! the interpreter has no call hook, so its time counts as self time of
+//! the entry point that reached it.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Instant;
+
+use crosscom_protosept::{CallKind, CallObserver, set_call_observer};
+
+const ENV_FLAG: &str = "YAOBOW_SCRIPT_PROFILE";
+const TRACE_PATH_ENV: &str = "YAOBOW_SCRIPT_TRACE";
+const DEFAULT_TRACE_PATH: &str = "p7_trace.json";
+
+/// `OpenSpan::function` of a span that isn't recorded.
+const UNRECORDED: usize = usize::MAX;
+
+/// Spans retained for the Chrome-trace export. At 32 bytes each this
+/// caps the ring buffer at 32 MiB.
+const MAX_TRACE_EVENTS: usize = 1 << 20;
+
+thread_local! {
+    static PROFILER: RefCell<Option<Rc<ScriptProfiler>>> = const { RefCell::new(None) };
+}
+
+pub fn enabled() -> bool {
+    std::env::var(ENV_FLAG)
+        .map(|v| {
+            matches!(
+                v.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// Install the thread's profiler when `YAOBOW_SCRIPT_PROFILE` is set.
+/// Idempotent; called from [`ScriptHost`](crate::ScriptHost)
+/// construction.
+pub fn install_if_enabled() {
+    if enabled() && current().is_none() {
+        install();
+    }
+}
+
+/// Install a fresh profiler on this thread regardless of the env flag,
+/// replacing any previous one.
+pub fn install() -> Rc<ScriptProfiler> {
+    let profiler = Rc::new(ScriptProfiler::new());
+    set_call_observer(Some(profiler.clone()));
+    PROFILER.with(|p| *p.borrow_mut() = Some(profiler.clone()));
+    profiler
+}
+
+/// The profiler installed on this thread, if any.
+pub fn current() -> Option<Rc<ScriptProfiler>> {
+    PROFILER.with(|p| p.borrow().clone())
+}
+
+/// Aggregated figures for one script function or foreign method.
+#[derive(Debug, Clone)]
+pub struct FunctionStats {
+    pub kind: CallKind,
+    /// `module.function` for script entry points, `<type_tag>.<method>`
+    /// for interface methods.
+    pub label: String,
+    pub calls: u64,
+    pub total_ns: u64,
+    pub self_ns: u64,
+    pub max_ns: u64,
+    /// Figures for the last completed frame (see
+    /// [`ScriptProfiler::end_frame`]).
+    pub frame_calls: u64,
+    pub frame_self_ns: u64,
+    pub frame_total_ns: u64,
+    pending_calls: u64,
+    pending_self_ns: u64,
+    pending_total_ns: u64,
+}
+
+impl FunctionStats {
+    fn new(kind: CallKind, label: String) -> Self {
+        Self {
+            kind,
+            label,
+            calls: 0,
+            total_ns: 0,
+            self_ns: 0,
+            max_ns: 0,
+            frame_calls: 0,
+            frame_self_ns: 0,
+            frame_total_ns: 0,
+            pending_calls: 0,
+            pending_self_ns: 0,
+            pending_total_ns: 0,
+        }
+    }
+}
+
+struct OpenSpan {
+    function: usize,
+    start: Instant,
+    child_ns: u64,
+}
+
+struct TraceEvent {
+    function: usize,
+    depth: u32,
+    start_ns: u64,
+    dur_ns: u64,
+}
+
+#[derive(Default)]
+struct State {
+    /// `[kind][scope][name]` → index into `functions`. Two levels so a
+    /// lookup by `&str` pair never allocates.
+    index: [HashMap<String, HashMap<String, usize>>; 2],
+    functions: Vec<FunctionStats>,
+    trace: VecDeque<TraceEvent>,
+    frames: u64,
+}
+
+impl State {
+    fn function_index(&mut self, kind: CallKind, scope: &str, name: &str) -> usize {
+        let by_scope = &mut self.index[kind_slot(kind)];
+        if let Some(&index) = by_scope.get(scope).and_then(|names| names.get(name)) {
+            return index;
+        }
+        let index = self.functions.len();
+        self.functions
+            .push(FunctionStats::new(kind, function_label(scope, name)));
+        by_scope
+            .entry(scope.to_string())
+            .or_default()
+            .insert(name.to_string(), index);
+        index
+    }
+}
+
+pub struct ScriptProfiler {
+    origin: Instant,
+    state: RefCell<State>,
+    /// Open spans. Kept apart from `state` and only borrowed inside
+    /// `enter` / `exit`, so every `enter` pushes and every `exit` pops
+    /// even while a reader holds `state`.
+    stack: RefCell<Vec<OpenSpan>>,
+}
+
+impl Default for ScriptProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScriptProfiler {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            state: RefCell::new(State::default()),
+            stack: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Roll the figures accumulated since the previous call into each
+    /// function's `frame_*` fields.
+    pub fn end_frame(&self) {
+        let mut state = self.state.borrow_mut();
+        state.frames += 1;
+        for f in &mut state.functions {
+            f.frame_calls = std::mem::take(&mut f.pending_calls);
+            f.frame_self_ns = std::mem::take(&mut f.pending_self_ns);
+            f.frame_total_ns = std::mem::take(&mut f.pending_total_ns);
+        }
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.state.borrow().frames
+    }
+
+    /// Functions that ran during the last frame, most expensive self
+    /// time first.
+    pub fn top_last_frame(&self, limit: usize) -> Vec<FunctionStats> {
+        let state = self.state.borrow();
+        let mut out: Vec<FunctionStats> = state
+            .functions
+            .iter()
+            .filter(|f| f.frame_calls > 0)
+            .cloned()
+            .collect();
+        out.sort_by_key(|f| std::cmp::Reverse(f.frame_self_ns));
+        out.truncate(limit);
+        out
+    }
+
+    /// Lifetime figures for every function seen so far, most expensive
+    /// self time first.
+    pub fn snapshot(&self) -> Vec<FunctionStats> {
+        let mut out = self.state.borrow().functions.clone();
+        out.sort_by_key(|f| std::cmp::Reverse(f.self_ns));
+        out
+    }
+
+    /// Drop every aggregate and the trace buffer.
+    pub fn reset(&self) {
+        *self.state.borrow_mut() = State::default();
+        // Spans still open stay on the stack so their `exit` calls stay
+        // balanced, but they no longer map to a function and are not
+        // recorded.
+        for span in self.stack.borrow_mut().iter_mut() {
+            span.function = UNRECORDED;
+        }
+    }
+
+    /// Write the retained spans as a Chrome-trace JSON document
+    /// (`{"traceEvents": [...]}` of complete `"X"` events, microsecond
+    /// timestamps).
+    pub fn write_chrome_trace(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let state = self.state.borrow();
+        let events: Vec<serde_json::Value> = state
+            .trace
+            .iter()
+            .filter_map(|e| {
+                let function = state.functions.get(e.function)?;
+                Some(serde_json::json!({
+                    "name": function.label,
+                    "cat": kind_name(function.kind),
+                    "ph": "X",
+                    "ts": e.start_ns as f64 / 1000.0,
+                    "dur": e.dur_ns as f64 / 1000.0,
+                    "pid": 1,
+                    "tid": 1,
+                    "args": { "depth": e.depth },
+                }))
+            })
+            .collect();
+        let doc = serde_json::json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ms",
+        });
+        serde_json::to_writer(&mut *out, &doc)?;
+        out.flush()
+    }
+
+    /// [`Self::write_chrome_trace`] to `path`, or to
+    /// `YAOBOW_SCRIPT_TRACE` / `p7_trace.json` when `None`. Returns the
+    /// path written.
+    pub fn export_chrome_trace(&self, path: Option<&Path>) -> std::io::Result<PathBuf> {
+        let path = path
+            .map(Path::to_path_buf)
+            .unwrap_or_else(default_trace_path);
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        self.write_chrome_trace(&mut file)?;
+        Ok(path)
+    }
+}
+
+impl CallObserver for ScriptProfiler {
+    fn enter(&self, kind: CallKind, scope: &str, name: &str) {
+        // A span entered while `state` is borrowed is still pushed, just
+        // not attributed to a function.
+        let function = self
+            .state
+            .try_borrow_mut()
+            .map(|mut state| state.function_index(kind, scope, name))
+            .unwrap_or(UNRECORDED);
+        self.stack.borrow_mut().push(OpenSpan {
+            function,
+            start: Instant::now(),
+            child_ns: 0,
+        });
+        radiance::perf::count(
+            match kind {
+                CallKind::Script => "script.calls",
+                CallKind::Foreign => "script.foreign_calls",
+            },
+            1,
+        );
+    }
+
+    fn exit(&self) {
+        let now = Instant::now();
+        let (span, elapsed, depth) = {
+            let mut stack = self.stack.borrow_mut();
+            let Some(span) = stack.pop() else {
+                return;
+            };
+            let elapsed = now.saturating_duration_since(span.start).as_nanos() as u64;
+            if let Some(parent) = stack.last_mut() {
+                parent.child_ns = parent.child_ns.saturating_add(elapsed);
+            }
+            (span, elapsed, stack.len() as u32)
+        };
+        let self_ns = elapsed.saturating_sub(span.child_ns);
+        let Ok(mut state) = self.state.try_borrow_mut() else {
+            return;
+        };
+        let Some(f) = state.functions.get_mut(span.function) else {
+            return;
+        };
+        f.calls += 1;
+        f.total_ns = f.total_ns.saturating_add(elapsed);
+        f.self_ns = f.self_ns.saturating_add(self_ns);
+        f.max_ns = f.max_ns.max(elapsed);
+        f.pending_calls += 1;
+        f.pending_self_ns = f.pending_self_ns.saturating_add(self_ns);
+        f.pending_total_ns = f.pending_total_ns.saturating_add(elapsed);
+
+        if state.trace.len() == MAX_TRACE_EVENTS {
+            state.trace.pop_front();
+        }
+        let start_ns = span.start.saturating_duration_since(self.origin).as_nanos() as u64;
+        state.trace.push_back(TraceEvent {
+            function: span.function,
+            depth,
+            start_ns,
+            dur_ns: elapsed,
+        });
+    }
+}
+
+fn default_trace_path() -> PathBuf {
+    std::env::var_os(TRACE_PATH_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_TRACE_PATH))
+}
+
+fn kind_slot(kind: CallKind) -> usize {
+    match kind {
+        CallKind::Script => 0,
+        CallKind::Foreign => 1,
+    }
+}
+
+pub fn kind_name(kind: CallKind) -> &'static str {
+    match kind {
+        CallKind::Script => "script",
+        CallKind::Foreign => "foreign",
+    }
+}
+
+/// `$root` / `$method` are synthetic scopes for root-module functions
+/// and untyped proto methods; drop them from the label.
+fn function_label(scope: &str, name: &str) -> String {
+    if scope.starts_with('$') {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(
+        profiler: &ScriptProfiler,
+        kind: CallKind,
+        scope: &str,
+        name: &str,
+        body: impl FnOnce(),
+    ) {
+        profiler.enter(kind, scope, name);
+        body();
+        profiler.exit();
+    }
+
+    #[test]
+    fn nested_spans_split_self_and_total_time() {
+        let profiler = ScriptProfiler::new();
+        run(&profiler, CallKind::Script, "$root", "render", || {
+            for _ in 0..3 {
+                run(
+                    &profiler,
+                    CallKind::Foreign,
+                    "radiance.comdef.IUiHost",
+                    "button",
+                    || {
+                        std::thread::sleep(std::time::Duration::from_millis(1));
+                    },
+                );
+            }
+        });
+        profiler.end_frame();
+
+        let top = profiler.top_last_frame(10);
+        assert_eq!(top.len(), 2);
+        let button = top
+            .iter()
+            .find(|f| f.label == "radiance.comdef.IUiHost.button")
+            .unwrap();
+        let render = top.iter().find(|f| f.label == "render").unwrap();
+        assert_eq!(button.kind, CallKind::Foreign);
+        assert_eq!(button.frame_calls, 3);
+        assert_eq!(render.frame_calls, 1);
+        assert!(render.total_ns >= button.total_ns);
+        assert_eq!(render.self_ns, render.total_ns - button.total_ns);
+        // The sleeping child dominates, so it sorts first.
+        assert_eq!(top[0].label, button.label);
+    }
+
+    #[test]
+    fn end_frame_rolls_over_per_frame_figures() {
+        let profiler = ScriptProfiler::new();
+        run(&profiler, CallKind::Script, "editor.panels", "draw", || {});
+        profiler.end_frame();
+        assert_eq!(profiler.top_last_frame(10)[0].label, "editor.panels.draw");
+
+        profiler.end_frame();
+        assert!(profiler.top_last_frame(10).is_empty());
+        assert_eq!(profiler.snapshot()[0].calls, 1);
+        assert_eq!(profiler.frames(), 2);
+    }
+
+    #[test]
+    fn spans_entered_while_state_is_borrowed_stay_balanced() {
+        let profiler = ScriptProfiler::new();
+        profiler.enter(CallKind::Script, "$root", "update");
+        {
+            let _reader = profiler.state.borrow();
+            profiler.enter(CallKind::Foreign, "radiance.comdef.IUiHost", "button");
+            profiler.exit();
+        }
+        profiler.exit();
+        profiler.end_frame();
+
+        let top = profiler.top_last_frame(10);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].label, "update");
+        assert_eq!(top[0].frame_calls, 1);
+        assert!(profiler.stack.borrow().is_empty());
+    }
+
+    #[test]
+    fn chrome_trace_lists_complete_events() {
+        let profiler = ScriptProfiler::new();
+        run(&profiler, CallKind::Script, "$root", "update", || {
+            run(
+                &profiler,
+                CallKind::Foreign,
+                "radiance.comdef.IScene",
+                "camera",
+                || {},
+            );
+        });
+
+        let mut out = Vec::new();
+        profiler.write_chrome_trace(&mut out).unwrap();
+        let doc: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let events = doc["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["name"], "radiance.comdef.IScene.camera");
+        assert_eq!(events[0]["cat"], "foreign");
+        assert_eq!(events[0]["args"]["depth"], 1);
+        assert_eq!(events[1]["name"], "update");
+        assert_eq!(events[1]["ph"], "X");
+    }
+}
@@ -12,6 +12,7 @@
 //! 3. Invoke `render(ui_host, dt)` on each layer in `layers` (already in
 //!    render/z-order), then submit the perf overlay last so it draws on
 //!    top.
+//! 4. Roll the script profiler (if installed) over to the next frame.
 //!
 //! [`CoreRadianceEngine::update`]: radiance::radiance::CoreRadianceEngine::update
 
@@ -84,6 +85,11 @@ impl UiFrameRenderer for ImguiUiFrameRenderer {
             // layer. No-op when the env flag isn't set.
             self.perf_overlay.render(ui);
         });
+        // Close the script profiler's frame after the overlay has read
+        // it, so the overlay always shows the last complete frame.
+        if let Some(profiler) = crate::profiler::current() {
+            profiler.end_frame();
+        }
         radiance::perf::count(
             "ui.render_frame_total_ns",
             frame_start.elapsed().as_nanos() as u64,
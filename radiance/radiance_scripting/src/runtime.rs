@@ -30,8 +30,8 @@ use std::rc::Rc;
 
 use crosscom::{ComInterface, ComRc};
 use crosscom_protosept::{
-    ComObjectTable, HostError, HostServices, P7HostContext, RuntimeAccess, RuntimeHandle,
-    install_com_dispatcher, scope, scope_context, with_context,
+    CallKind, ComObjectTable, HostError, HostServices, P7HostContext, RuntimeAccess, RuntimeHandle,
+    call_span, install_com_dispatcher, scope, scope_context, with_context,
 };
 use p7::interpreter::context::{Context, Data};
 use p7::{InMemoryModuleProvider, ModuleProvider};
@@ -75,16 +75,21 @@ struct Inner {
     /// [`ModuleProvider`]. Set by `set_script_assets`; survives
     /// `reload`.
     script_assets: Option<Rc<radiance::asset::AssetManager>>,
+    /// VFS path of the root source last loaded through
+    /// `load_source_from_path`, recompiled by `recompile`. Survives
+    /// `reload`.
+    root_path: Option<String>,
 }
 
 impl Inner {
     fn fresh() -> Self {
-        Self::with_state(Vec::new(), None)
+        Self::with_state(Vec::new(), None, None)
     }
 
     fn with_state(
         extra_bindings: Vec<(String, String)>,
         script_assets: Option<Rc<radiance::asset::AssetManager>>,
+        root_path: Option<String>,
     ) -> Self {
         let mut host = P7HostContext::new(RuntimeServices::default());
         install_com_dispatcher(&mut host.ctx);
@@ -93,6 +98,7 @@ impl Inner {
             epoch: 0,
             extra_bindings,
             script_assets,
+            root_path,
         }
     }
 }
@@ -156,6 +162,7 @@ impl ScriptHost {
         });
         Self::install_runtime_handle(&host);
         Self::register_built_in_protos();
+        crate::profiler::install_if_enabled();
         host
     }
 
@@ -169,6 +176,7 @@ impl ScriptHost {
         });
         Self::install_runtime_handle(&host);
         Self::register_built_in_protos();
+        crate::profiler::install_if_enabled();
         host
     }
 
@@ -260,7 +268,28 @@ impl ScriptHost {
             .map_err(|err| HostError::message(format!("read {path}: {err}")))?;
         let source = String::from_utf8(bytes)
             .map_err(|err| HostError::message(format!("invalid utf-8 in {path}: {err}")))?;
-        self.load_source(&source)
+        self.load_source(&source)?;
+        self.with_inner(|inner| inner.root_path = Some(path.to_string()));
+        Ok(())
+    }
+
+    /// Re-read and recompile the root source last loaded through
+    /// [`load_source_from_path`], loading the new package over the
+    /// running interpreter. Unlike [`reload`](Self::reload) nothing is
+    /// discarded: the `ComObjectTable`, rooted handles and live CCWs
+    /// stay valid, and objects built before the call keep running the
+    /// code they were built with. Callers rebuild whatever they want
+    /// to pick up the new code (see [`crate::hot_reload`]).
+    ///
+    /// The package is compiled before anything is loaded, so a compile
+    /// error leaves the previously loaded code in place.
+    pub fn recompile(&self) -> Result<(), HostError> {
+        let path = self
+            .with_inner(|inner| inner.root_path.clone())
+            .ok_or_else(|| {
+                HostError::message("ScriptHost::recompile requires load_source_from_path first")
+            })?;
+        self.load_source_from_path(&path)
     }
 
     pub fn load_source(&self, source: &str) -> Result<(), HostError> {
@@ -289,7 +318,8 @@ impl ScriptHost {
         self.with_inner(|inner| {
             let extra = std::mem::take(&mut inner.extra_bindings);
             let assets = inner.script_assets.clone();
-            *inner = Inner::with_state(extra, assets);
+            let root_path = inner.root_path.take();
+            *inner = Inner::with_state(extra, assets, root_path);
             inner.epoch = new_epoch;
             // The fresh `Inner` carries a dangling runtime_handle in
             // its services bundle. Re-stamp the original Weak so
@@ -519,6 +549,7 @@ impl ScriptHost {
         args: Vec<Data>,
     ) -> Result<(), HostError> {
         let host = &mut inner.host;
+        let _span = call_span(CallKind::Script, module_path, name);
         std::panic::catch_unwind(AssertUnwindSafe(|| {
             let P7HostContext { ctx, services } = host;
             // `scope_context` parks the active interpreter pointer in a
@@ -563,6 +594,7 @@ impl ScriptHost {
         args: Vec<Data>,
     ) -> Result<(), HostError> {
         let host = &mut inner.host;
+        let _span = call_span(CallKind::Script, "$method", method_name);
         std::panic::catch_unwind(AssertUnwindSafe(|| {
             let P7HostContext { ctx, services } = host;
             scope(services, || {
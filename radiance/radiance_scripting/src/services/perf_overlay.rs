@@ -16,6 +16,11 @@
 //! The overlay also displays its own per-frame frame-time
 //! (`editor.frame_us`) so callers can correlate tree cost with whole-
 //! frame cost.
+//!
+//! When the script profiler is installed (`YAOBOW_SCRIPT_PROFILE=1`,
+//! see [`crate::profiler`]) the overlay appends the last frame's most
+//! expensive script functions and foreign calls by self time, and
+//! offers a button that exports the profiler's Chrome trace.
 
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -23,13 +28,19 @@ use std::time::Instant;
 
 use radiance::perf::{self, MetricSnapshot};
 
+use crate::profiler::{self, kind_name};
+
 const ENV_FLAG: &str = "YAOBOW_EDITOR_PERF_OVERLAY";
 
+/// Script functions listed per frame when the profiler is installed.
+const SCRIPT_TOP_N: usize = 12;
+
 pub struct PerfOverlay {
     enabled: bool,
     prev_counter_totals: RefCell<HashMap<&'static str, u64>>,
     last_frame_start: RefCell<Option<Instant>>,
     last_copy: RefCell<Option<Instant>>,
+    last_trace_export: RefCell<Option<String>>,
 }
 
 impl Default for PerfOverlay {
@@ -53,6 +64,7 @@ impl PerfOverlay {
             prev_counter_totals: RefCell::new(HashMap::new()),
             last_frame_start: RefCell::new(None),
             last_copy: RefCell::new(None),
+            last_trace_export: RefCell::new(None),
         }
     }
 
@@ -130,6 +142,21 @@ impl PerfOverlay {
             }
         }
 
+        let script_profiler = profiler::current();
+        if let Some(script_profiler) = &script_profiler {
+            buf.push_str("script (last frame, by self time):\n");
+            for f in script_profiler.top_last_frame(SCRIPT_TOP_N) {
+                buf.push_str(&format!(
+                    "  [{}] {}: calls={} self={} total={}\n",
+                    kind_name(f.kind),
+                    f.label,
+                    f.frame_calls,
+                    format_ns(f.frame_self_ns),
+                    format_ns(f.frame_total_ns)
+                ));
+            }
+        }
+
         // Render the overlay in a fixed-position transparent window
         // anchored to the top-right corner so it never overlaps the
         // resource tree pane.
@@ -137,6 +164,7 @@ impl PerfOverlay {
         let win_w = 560.0_f32;
         let pos = [(display_size[0] - win_w - 8.0).max(8.0), 8.0];
         let last_copy = &self.last_copy;
+        let last_trace_export = &self.last_trace_export;
         ui.window("##perf_overlay")
             .position(pos, imgui::Condition::Always)
             .size([win_w, 0.0], imgui::Condition::Always)
@@ -164,6 +192,20 @@ impl PerfOverlay {
                 } else {
                     ui.text_disabled("(copies all lines below)");
                 }
+                if let Some(script_profiler) = &script_profiler {
+                    if ui.button("Save script trace") {
+                        let status = match script_profiler.export_chrome_trace(None) {
+                            Ok(path) => format!("wrote {}", path.display()),
+                            Err(e) => format!("trace export failed: {}", e),
+                        };
+                        log::info!("{}", status);
+                        *last_trace_export.borrow_mut() = Some(status);
+                    }
+                    if let Some(status) = last_trace_export.borrow().as_deref() {
+                        ui.same_line();
+                        ui.text_disabled(status);
+                    }
+                }
                 ui.separator();
                 // Stream the prebuilt buffer line-by-line so what's
                 // on screen matches what got copied byte-for-byte.
@@ -0,0 +1,229 @@
+//! Assembler / disassembler for PAL4 AngelScript `.csb` modules.
+//!
+//! Works on loose `.csb` files (extract them from the cpk first, e.g.
+//! with `repacker`). Typical modding loop for a single function:
+//!
+//! ```text
+//! csb_asm extract M01.csb M01_Talk -o talk.asm
+//! $EDITOR talk.asm
+//! csb_asm replace M01.csb talk.asm -o M01.csb
+//! ```
+//!
+//! `disasm` / `asm` convert whole modules; `verify` checks that a
+//! module survives both the binary and the listing round trip byte
+//! for byte. `decompile` prints AngelScript-like pseudocode for
+//! reading, with PAL4's sysfn names filled in.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use shared::openpal4::scripting::sysfn_names;
+use shared::scripting::angelscript::{
+    DecompileOptions, ScriptModule, assemble_function, assemble_module, decompile_function,
+    decompile_module, function_listing, module_listing,
+};
+
+#[derive(Parser)]
+#[command(about = "Assemble and disassemble PAL4 .csb script modules")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write the full listing of a module.
+    Disasm {
+        input: PathBuf,
+        /// Output listing; stdout when omitted.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Assemble a full listing back into a `.csb`.
+    Asm {
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Write the listing of one function.
+    Extract {
+        input: PathBuf,
+        function: String,
+        /// Output listing; stdout when omitted.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Replace one function (matched by name) with an edited
+    /// `.function` block. Function indices are preserved, so `call`
+    /// sites elsewhere in the module stay valid.
+    Replace {
+        input: PathBuf,
+        listing: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Check that every given module round-trips byte for byte.
+    Verify { inputs: Vec<PathBuf> },
+    /// Write pseudocode for a module, or for one function of it.
+    Decompile {
+        input: PathBuf,
+        function: Option<String>,
+        /// Output file; stdout when omitted.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Disasm { input, output } => {
+            let module = read_module(&input)?;
+            write_text(output.as_deref(), &module_listing(&module))
+        }
+        Command::Asm { input, output } => {
+            let text = std::fs::read_to_string(&input)
+                .with_context(|| format!("read {}", input.display()))?;
+            let module =
+                assemble_module(&text).with_context(|| format!("assemble {}", input.display()))?;
+            write_module(&output, &module)
+        }
+        Command::Extract {
+            input,
+            function,
+            output,
+        } => {
+            let module = read_module(&input)?;
+            let Some(target) = module.functions.iter().find(|f| f.name == function) else {
+                anyhow::bail!(
+                    "{}: no function {:?}; available: {}",
+                    input.display(),
+                    function,
+                    function_names(&module)
+                );
+            };
+            write_text(output.as_deref(), &function_listing(&module, target))
+        }
+        Command::Replace {
+            input,
+            listing,
+            output,
+        } => {
+            let mut module = read_module(&input)?;
+            let text = std::fs::read_to_string(&listing)
+                .with_context(|| format!("read {}", listing.display()))?;
+            let function = assemble_function(&text)
+                .with_context(|| format!("assemble {}", listing.display()))?;
+            let Some(index) = module
+                .functions
+                .iter()
+                .position(|f| f.name == function.name)
+            else {
+                anyhow::bail!(
+                    "{}: no function {:?} to replace; available: {}",
+                    input.display(),
+                    function.name,
+                    function_names(&module)
+                );
+            };
+            eprintln!(
+                "replacing [{}] {} ({} -> {} bytes of code)",
+                index,
+                function.name,
+                module.functions[index].inst.len(),
+                function.inst.len()
+            );
+            module.functions[index] = Arc::new(function);
+            write_module(&output, &module)
+        }
+        Command::Verify { inputs } => {
+            let mut failures = 0;
+            for input in &inputs {
+                match verify(input) {
+                    Ok(()) => eprintln!("OK   {}", input.display()),
+                    Err(e) => {
+                        failures += 1;
+                        eprintln!("FAIL {}: {:#}", input.display(), e);
+                    }
+                }
+            }
+            eprintln!("--- {}/{} modules failed ---", failures, inputs.len());
+            if failures > 0 {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Decompile {
+            input,
+            function,
+            output,
+        } => {
+            let module = read_module(&input)?;
+            let options = DecompileOptions {
+                sysfn_names: sysfn_names(),
+            };
+            let text = match function {
+                None => decompile_module(&module, &options),
+                Some(function) => {
+                    let Some(target) = module.functions.iter().find(|f| f.name == function) else {
+                        anyhow::bail!(
+                            "{}: no function {:?}; available: {}",
+                            input.display(),
+                            function,
+                            function_names(&module)
+                        );
+                    };
+                    decompile_function(&module, target, &options)
+                }
+            };
+            write_text(output.as_deref(), &text)
+        }
+    }
+}
+
+fn verify(input: &Path) -> Result<()> {
+    let bytes = std::fs::read(input)?;
+    let module = ScriptModule::read_from_buffer(&bytes)?;
+    if module.write_to_vec()? != bytes {
+        anyhow::bail!("binary rewrite differs");
+    }
+    let assembled = assemble_module(&module_listing(&module))?;
+    if assembled.write_to_vec()? != bytes {
+        anyhow::bail!("listing round trip differs");
+    }
+    Ok(())
+}
+
+fn read_module(path: &Path) -> Result<ScriptModule> {
+    let bytes = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    ScriptModule::read_from_buffer(&bytes).with_context(|| format!("parse {}", path.display()))
+}
+
+fn write_module(path: &Path, module: &ScriptModule) -> Result<()> {
+    let bytes = module.write_to_vec()?;
+    std::fs::write(path, &bytes).with_context(|| format!("write {}", path.display()))?;
+    eprintln!("wrote {} ({} bytes)", path.display(), bytes.len());
+    Ok(())
+}
+
+fn write_text(path: Option<&Path>, text: &str) -> Result<()> {
+    match path {
+        Some(path) => {
+            std::fs::write(path, text).with_context(|| format!("write {}", path.display()))
+        }
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+fn function_names(module: &ScriptModule) -> String {
+    module
+        .functions
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
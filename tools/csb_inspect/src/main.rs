@@ -15,7 +15,7 @@ use anyhow::{Context, Result};
 use clap::Parser;
 use common::store_ext::StoreExt2;
 use packfs::init_virtual_fs;
-use shared::scripting::angelscript::{ScriptModule, disasm};
+use shared::scripting::angelscript::{ScriptModule, assemble_module, disasm, module_listing};
 
 #[derive(Parser)]
 #[command(about = "Diagnose PAL4 .csb parse failures")]
@@ -77,6 +77,12 @@ struct Cli {
     /// abort the script when reached from `LinkObj01`.
     #[arg(long)]
     probe: Vec<String>,
+
+    /// Also check that each parsed module is written back byte for
+    /// byte, both directly and through the `csb_asm` listing form.
+    /// Mismatches count as failures.
+    #[arg(long)]
+    roundtrip: bool,
 }
 
 const KNOWN_FAILING: &[&str] = &["M02", "M07", "M09", "M16", "Q04", "Q05", "Q11"];
@@ -224,6 +230,15 @@ fn main() -> Result<()> {
                 if let Some(target_fn) = &cli.disasm {
                     print_disasm(&module, target_fn);
                 }
+                if cli.roundtrip {
+                    match check_roundtrip(&module, &bytes) {
+                        Ok(()) => eprintln!("  roundtrip: OK"),
+                        Err(e) => {
+                            failures += 1;
+                            eprintln!("  roundtrip FAIL: {:#}", e);
+                        }
+                    }
+                }
             }
             Err(e) => {
                 failures += 1;
@@ -289,6 +304,25 @@ fn print_disasm(module: &ScriptModule, target_fn: &str) {
     }
 }
 
+fn check_roundtrip(module: &ScriptModule, bytes: &[u8]) -> Result<()> {
+    let written = module.write_to_vec()?;
+    if let Some(off) = first_difference(&written, bytes) {
+        anyhow::bail!("binary rewrite differs at offset {:#x}", off);
+    }
+    let assembled = assemble_module(&module_listing(module))?.write_to_vec()?;
+    if let Some(off) = first_difference(&assembled, bytes) {
+        anyhow::bail!("listing round trip differs at offset {:#x}", off);
+    }
+    Ok(())
+}
+
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(x, y)| x != y)
+        .or_else(|| (a.len() != b.len()).then(|| a.len().min(b.len())))
+}
+
 /// Best-effort scan of the structured error chain for the first
 /// `module-offset 0x…` mention added by the parser instrumentation.
 fn parse_offset(err: &anyhow::Error) -> Option<u64> {
@@ -0,0 +1,405 @@
+//! Prints the block tree of a Gamebryo `.nif` model.
+//!
+//! Starts from the footer's roots and follows the references each
+//! typed block owns (node children, properties, controllers, geometry
+//! data, skin instances, texture sources, data streams, interpolators),
+//! printing one line per block with its index, type, name and a short
+//! summary. Blocks still decoded as `UnknownBlock` show their byte size
+//! so unhandled types are easy to spot. Bones, skeleton roots and
+//! controller targets point back into the tree and are printed as
+//! plain indices rather than followed.
+
+use std::{io::Cursor, path::PathBuf};
+
+use anyhow::{Context, Result, bail};
+use binrw::{BinRead, BinWrite};
+use clap::Parser;
+use common::store_ext::StoreExt2;
+use fileformats::nif::{
+    NiObject, NifModel,
+    blocks::{
+        NiAVObject, NiAlphaProperty, NiDataStream, NiGeometry, NiKeyframeData, NiMaterialProperty,
+        NiMesh, NiNode, NiObjectNET, NiSkinData, NiSkinInstance, NiSkinningMeshModifier,
+        NiSourceTexture, NiStringPalette, NiTexturingProperty, NiTransformController,
+        NiTransformData, NiTransformInterpolator, NiTriShape, NiTriShapeData, NiTriStrips,
+        NiTriStripsData, TexSlot, UnknownBlock,
+    },
+};
+use packfs::init_virtual_fs;
+
+#[derive(Parser)]
+#[command(about = "Print the block tree of a Gamebryo .nif model")]
+struct Cli {
+    /// Model to inspect: a vfs path (e.g. `/Model/npc/npc01.nif`) when
+    /// `--root` is given, a local file otherwise.
+    path: PathBuf,
+
+    /// Game install root, mounted the same way the engine does
+    /// (`packfs::init_virtual_fs`), so models inside `.zpk` packages
+    /// can be read directly.
+    #[arg(long)]
+    root: Option<PathBuf>,
+
+    /// Also list every block in file order with its type and size,
+    /// including blocks the tree walk never reaches.
+    #[arg(long)]
+    blocks: bool,
+
+    /// Check that writing the parsed model reproduces the file byte
+    /// for byte. A mismatch reports the first differing offset and
+    /// exits with an error.
+    #[arg(long)]
+    roundtrip: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let data = match &cli.root {
+        Some(root) => {
+            let root = root
+                .canonicalize()
+                .with_context(|| format!("resolve --root {}", root.display()))?;
+            let vfs = init_virtual_fs(&root, None);
+            StoreExt2::read_to_end(&vfs, &cli.path)
+        }
+        None => std::fs::read(&cli.path),
+    }
+    .with_context(|| format!("read {}", cli.path.display()))?;
+
+    let model = NifModel::read(&mut Cursor::new(data.clone()))
+        .with_context(|| format!("parse {}", cli.path.display()))?;
+
+    let header = model.header();
+    println!(
+        "{}: version {:#010x}, user version {}, {} blocks, {} block types, {} strings",
+        cli.path.display(),
+        header.version,
+        header.user_version,
+        header.num_block,
+        header.num_block_types,
+        header.num_strings,
+    );
+
+    let mut inspector = Inspector::new(&model);
+    for &root in model.roots() {
+        inspector.print("", root, 0);
+    }
+
+    if cli.blocks {
+        println!();
+        for index in 0..model.blocks().len() {
+            let reached = if inspector.visited[index] { ' ' } else { '*' };
+            println!(
+                "{reached}#{index:<5} {:<32} {:>8} bytes",
+                inspector.type_name(index),
+                header.block_size[index]
+            );
+        }
+    }
+
+    if cli.roundtrip {
+        let mut out = Cursor::new(vec![]);
+        model.write(&mut out).context("write model")?;
+        let out = out.into_inner();
+        if out != data {
+            let offset = out
+                .iter()
+                .zip(&data)
+                .position(|(a, b)| a != b)
+                .unwrap_or(out.len().min(data.len()));
+            bail!(
+                "round trip differs at offset {:#x} ({} bytes read, {} written)",
+                offset,
+                data.len(),
+                out.len()
+            );
+        }
+        println!("round trip: {} bytes identical", data.len());
+    }
+
+    Ok(())
+}
+
+struct Inspector<'a> {
+    model: &'a NifModel,
+    visited: Vec<bool>,
+}
+
+impl<'a> Inspector<'a> {
+    fn new(model: &'a NifModel) -> Self {
+        Self {
+            model,
+            visited: vec![false; model.blocks().len()],
+        }
+    }
+
+    /// The block's type name as stored in the header. `NiDataStream`
+    /// carries its usage and access after `\x01` separators.
+    fn type_name(&self, index: usize) -> String {
+        let header = self.model.header();
+        header
+            .block_type_index
+            .get(index)
+            .and_then(|&ty| header.block_types.get(ty as usize))
+            .and_then(|name| name.to_string().ok())
+            .map(|name| name.replace('\x01', ":"))
+            .unwrap_or_else(|| "?".to_string())
+    }
+
+    fn print(&mut self, label: &str, index: i32, depth: usize) {
+        let Some(block) = usize::try_from(index)
+            .ok()
+            .and_then(|i| self.model.blocks().get(i))
+        else {
+            return;
+        };
+
+        let indent = "  ".repeat(depth);
+        let slot = index as usize;
+        if self.visited[slot] {
+            println!("{indent}{label}#{index} (see above)");
+            return;
+        }
+        self.visited[slot] = true;
+
+        let mut line = format!("{indent}{label}#{index} {}", self.type_name(slot));
+        if let Some(name) = self.name(block.as_ref()) {
+            line.push_str(&format!(" \"{name}\""));
+        }
+        let summary = self.summary(block.as_ref());
+        if !summary.is_empty() {
+            line.push_str(&format!(" - {summary}"));
+        }
+        println!("{line}");
+
+        for (label, child) in owned_refs(block.as_ref()) {
+            self.print(&format!("{label}: "), child, depth + 1);
+        }
+    }
+
+    fn name(&self, block: &dyn NiObject) -> Option<String> {
+        let name = object_net(block)?.name;
+        self.model.string(name).filter(|name| !name.is_empty())
+    }
+
+    fn summary(&self, block: &dyn NiObject) -> String {
+        let any = block.as_any();
+        if let Some(node) = any.downcast_ref::<NiNode>() {
+            format!("{} children", node.children.len())
+        } else if let Some(mesh) = any.downcast_ref::<NiMesh>() {
+            format!(
+                "primitive {}, {} sub-meshes, {} streams",
+                mesh.primitive_type.0,
+                mesh.num_sub_meshes,
+                mesh.data_stream_ref.len()
+            )
+        } else if let Some(stream) = any.downcast_ref::<NiDataStream>() {
+            let formats: Vec<String> = stream
+                .component_formats
+                .iter()
+                .map(|f| format!("{:#x}", f.0))
+                .collect();
+            format!(
+                "{} bytes, {} regions, formats [{}]",
+                stream.num_bytes,
+                stream.regions.len(),
+                formats.join(", ")
+            )
+        } else if let Some(material) = any.downcast_ref::<NiMaterialProperty>() {
+            let d = &material.diffuse;
+            format!(
+                "diffuse ({:.2}, {:.2}, {:.2}), alpha {:.2}, glossiness {:.1}",
+                d.r, d.g, d.b, material.alpha, material.glossiness
+            )
+        } else if let Some(alpha) = any.downcast_ref::<NiAlphaProperty>() {
+            format!(
+                "flags {:#06x}, blend {}, test {} (threshold {})",
+                alpha.flags,
+                alpha.blend_enabled(),
+                alpha.test_enabled(),
+                alpha.threshold
+            )
+        } else if let Some(texture) = any.downcast_ref::<NiSourceTexture>() {
+            if texture.use_external != 0 {
+                self.model
+                    .string(texture.file_name)
+                    .map(|file| format!("external \"{file}\""))
+                    .unwrap_or_default()
+            } else {
+                format!("embedded pixel data #{}", texture.pixel_data)
+            }
+        } else if let Some(shape) = any.downcast_ref::<NiTriShapeData>() {
+            format!(
+                "{} vertices, {} triangles, {} uv sets",
+                shape.geometry_data.num_vertices,
+                shape.num_triangles,
+                shape.geometry_data.uv_sets.len()
+            )
+        } else if let Some(strips) = any.downcast_ref::<NiTriStripsData>() {
+            format!(
+                "{} vertices, {} triangles in {} strips, {} uv sets",
+                strips.geometry_data.num_vertices,
+                strips.num_triangles,
+                strips.num_strips,
+                strips.geometry_data.uv_sets.len()
+            )
+        } else if let Some(skin) = any.downcast_ref::<NiSkinInstance>() {
+            format!(
+                "skeleton root #{}, bones {:?}",
+                skin.skeleton_root, skin.bones
+            )
+        } else if let Some(modifier) = any.downcast_ref::<NiSkinningMeshModifier>() {
+            format!(
+                "skeleton root #{}, bones {:?}",
+                modifier.skeleton_root, modifier.bones
+            )
+        } else if let Some(skin) = any.downcast_ref::<NiSkinData>() {
+            format!(
+                "{} bones, vertex weights {}",
+                skin.num_bones,
+                skin.has_vertex_weights != 0
+            )
+        } else if let Some(controller) = any.downcast_ref::<NiTransformController>() {
+            let c = &controller.controller;
+            format!(
+                "target #{}, time {:.2}..{:.2}",
+                c.target, c.start_time, c.stop_time
+            )
+        } else if let Some(data) = any.downcast_ref::<NiKeyframeData>() {
+            keyframe_summary(&data.keyframes)
+        } else if let Some(data) = any.downcast_ref::<NiTransformData>() {
+            keyframe_summary(&data.keyframes)
+        } else if let Some(palette) = any.downcast_ref::<NiStringPalette>() {
+            format!("{} bytes", palette.length)
+        } else if let Some(unknown) = any.downcast_ref::<UnknownBlock>() {
+            format!("{} bytes, not decoded", unknown.data.len())
+        } else {
+            String::new()
+        }
+    }
+}
+
+fn keyframe_summary(keys: &fileformats::nif::blocks::KeyframeData) -> String {
+    let rotations = match &keys.xyz_rotations {
+        Some(axes) => axes.iter().map(|axis| axis.keys.len()).sum(),
+        None => keys.quaternion_keys.len(),
+    };
+    format!(
+        "{} rotation, {} translation, {} scale keys",
+        rotations,
+        keys.translations.keys.len(),
+        keys.scales.keys.len()
+    )
+}
+
+fn av_object(block: &dyn NiObject) -> Option<&NiAVObject> {
+    let any = block.as_any();
+    if let Some(node) = any.downcast_ref::<NiNode>() {
+        Some(&node.av_object)
+    } else if let Some(mesh) = any.downcast_ref::<NiMesh>() {
+        Some(&mesh.render_object.av_object)
+    } else {
+        geometry(block).map(|g| &g.av_object)
+    }
+}
+
+fn geometry(block: &dyn NiObject) -> Option<&NiGeometry> {
+    let any = block.as_any();
+    if let Some(shape) = any.downcast_ref::<NiTriShape>() {
+        Some(&shape.geometry)
+    } else {
+        any.downcast_ref::<NiTriStrips>().map(|s| &s.geometry)
+    }
+}
+
+fn object_net(block: &dyn NiObject) -> Option<&NiObjectNET> {
+    if let Some(av_object) = av_object(block) {
+        return Some(&av_object.object_net);
+    }
+
+    let any = block.as_any();
+    if let Some(material) = any.downcast_ref::<NiMaterialProperty>() {
+        Some(&material.object_net)
+    } else if let Some(alpha) = any.downcast_ref::<NiAlphaProperty>() {
+        Some(&alpha.object_net)
+    } else if let Some(texturing) = any.downcast_ref::<NiTexturingProperty>() {
+        Some(&texturing.object_net)
+    } else {
+        any.downcast_ref::<NiSourceTexture>().map(|t| &t.object_net)
+    }
+}
+
+/// References `block` owns, in file order, labelled by role.
+fn owned_refs(block: &dyn NiObject) -> Vec<(String, i32)> {
+    let mut refs = vec![];
+    if let Some(net) = object_net(block) {
+        refs.push(("controller".to_string(), net.controller as i32));
+    }
+    if let Some(av_object) = av_object(block) {
+        for &property in &av_object.properties {
+            refs.push(("property".to_string(), property as i32));
+        }
+    }
+
+    let any = block.as_any();
+    if let Some(node) = any.downcast_ref::<NiNode>() {
+        for &effect in &node.effects {
+            refs.push(("effect".to_string(), effect as i32));
+        }
+        for &child in &node.children {
+            refs.push(("child".to_string(), child as i32));
+        }
+    } else if let Some(mesh) = any.downcast_ref::<NiMesh>() {
+        for stream in &mesh.data_stream_ref {
+            refs.push(("stream".to_string(), stream.stream));
+        }
+        for &modifier in &mesh.modifiers {
+            refs.push(("modifier".to_string(), modifier));
+        }
+    } else if let Some(geometry) = geometry(block) {
+        refs.push(("data".to_string(), geometry.data));
+        refs.push(("skin".to_string(), geometry.skin_instance));
+    } else if let Some(skin) = any.downcast_ref::<NiSkinInstance>() {
+        refs.push(("data".to_string(), skin.data));
+        refs.push(("partition".to_string(), skin.skin_partition));
+    } else if let Some(texturing) = any.downcast_ref::<NiTexturingProperty>() {
+        let slots = [
+            ("base", &texturing.base_texture),
+            ("dark", &texturing.dark_texture),
+            ("detail", &texturing.detail_texture),
+            ("gloss", &texturing.gloss_texture),
+            ("glow", &texturing.glow_texture),
+            ("normal", &texturing.normal_texture),
+        ];
+        for (label, slot) in slots {
+            push_slot(&mut refs, label, slot);
+        }
+        if let Some(bump) = &texturing.bump_map {
+            refs.push(("bump".to_string(), bump.texture.source));
+        }
+        if let Some(parallax) = &texturing.parallax_map {
+            refs.push(("parallax".to_string(), parallax.texture.source));
+        }
+        for (i, slot) in texturing.decal_textures.iter().enumerate() {
+            push_slot(&mut refs, &format!("decal{i}"), slot);
+        }
+        for (i, slot) in texturing.shader_textures.iter().enumerate() {
+            if let Some(map) = &slot.map {
+                refs.push((format!("shader{i}"), map.texture.source));
+            }
+        }
+    } else if let Some(controller) = any.downcast_ref::<NiTransformController>() {
+        refs.push(("interpolator".to_string(), controller.interpolator));
+        refs.push(("next".to_string(), controller.controller.next_controller));
+    } else if let Some(interpolator) = any.downcast_ref::<NiTransformInterpolator>() {
+        refs.push(("data".to_string(), interpolator.data));
+    }
+
+    refs
+}
+
+fn push_slot(refs: &mut Vec<(String, i32)>, label: &str, slot: &TexSlot) {
+    if let Some(texture) = &slot.texture {
+        refs.push((label.to_string(), texture.source));
+    }
+}
@@ -0,0 +1,579 @@
+//! PAL3 / PAL3A plot catalog generator.
+//!
+//! Emits the same JSON catalog as `pal4_plot_dump` (see the
+//! `plot_catalog` crate), built from each scene's `.sce` script and
+//! its sub-scenes' `.scn` files:
+//!
+//! ```text
+//! scenes.<cpk>.blocks.<scn> = { entry_fn, triggers, npcs, objects }
+//! scenes.<cpk>.fns.<proc>   = { reads, writes, sysfns, calls, cmp_literals }
+//! ```
+//!
+//! - `entry_fn` is the `_<cpk>_<scn>` proc `LoadScene` runs.
+//! - Triggers are the `.scn` nodes and roles with an SCE proc bound,
+//!   classified the way `ScnScene` tests them (nav rectangles, AABBs,
+//!   ladders, role talk). Item nodes become `objects`.
+//! - Globals are SCE variables with negative indices, reported as
+//!   `|var|` like the execution trace does. `Let` always writes the
+//!   shared table (so does the VM), whatever the sign.
+//! - Transitions are the literal `LoadScene cpk scn` operands.
+//!
+//! Only the current scene's `.sce` is visible to `Call`, so every
+//! closure stays inside one scene.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use common::store_ext::StoreExt2;
+use fileformats::pal3::sce::{OPCODES, SceArg, SceFile, decode, read_sce};
+use mini_fs::{EntryKind, MiniFs, StoreExt};
+use packfs::init_virtual_fs;
+use plot_catalog::{
+    Block, CATALOG_VERSION, Catalog, FunctionSummary, Npc, Object, Scene, Transition, Trigger,
+    ValueWrite, build_plot_index, build_trigger_summary, synthesise_missing_blocks, write_catalog,
+};
+use shared::{
+    GameType,
+    openpal3::loaders::scn_loader::{ScnFile, ScnNode, scn_load_from_file},
+};
+
+#[derive(Parser)]
+#[command(about = "Generate a PAL3 / PAL3A plot catalog (JSON)")]
+struct Cli {
+    /// Game install root (the directory containing `basedata/` and
+    /// `scene/`).
+    #[arg(long)]
+    root: PathBuf,
+
+    /// Output JSON path.
+    #[arg(long, default_value = "pal3_plot.json")]
+    out: PathBuf,
+
+    /// Pretty-print the JSON.
+    #[arg(long)]
+    pretty: bool,
+
+    /// The install is PAL3A, which keeps every `.sce` in `sce.cpk`
+    /// and every `.scn` in `scn.cpk`.
+    #[arg(long)]
+    pal3a: bool,
+}
+
+/// `.scn` node types, as classified by `ScnScene::load_objects`.
+const NODE_NAV_TRIGGER: u16 = 14;
+const NODE_ITEM_TRIGGERS: &[u16] = &[11, 16, 23, 33];
+const NODE_LADDERS: &[u16] = &[15, 40];
+const NODE_AABB_TRIGGER: u16 = 20;
+
+/// Radius `ScnScene` uses for role and item proximity triggers.
+const PROXIMITY_RADIUS: f32 = 100.;
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let game = if cli.pal3a {
+        GameType::PAL3A
+    } else {
+        GameType::PAL3
+    };
+
+    let root = cli
+        .root
+        .canonicalize()
+        .with_context(|| format!("resolve root: {}", cli.root.display()))?;
+    eprintln!(
+        "Mounting {} vfs from {}",
+        if cli.pal3a { "PAL3A" } else { "PAL3" },
+        root.display()
+    );
+    let vfs = init_virtual_fs(&root, None);
+
+    let scene_names = list_scenes(&vfs, game)?;
+    eprintln!("Found {} scenes", scene_names.len());
+
+    let mut catalog = Catalog {
+        version: CATALOG_VERSION,
+        sysfn_count: OPCODES.len(),
+        ..Catalog::default()
+    };
+    let mut globals = BTreeSet::new();
+
+    for cpk in &scene_names {
+        let sce = match load_sce(&vfs, game, cpk) {
+            Ok(sce) => sce,
+            Err(e) => {
+                eprintln!("WARN: scene {} skipped: {:#}", cpk, e);
+                continue;
+            }
+        };
+
+        let scene_name = cpk.to_ascii_lowercase();
+        let mut scene_out = Scene::default();
+        let proc_names: HashMap<u32, String> = sce
+            .procs
+            .iter()
+            .map(|proc| (proc.id, proc_label(proc.id, &proc.header_name_string())))
+            .collect();
+        for proc in &sce.procs {
+            let name = &proc_names[&proc.id];
+            let summary = walk_proc(&proc.code, &proc_names, name, &mut scene_out.transitions);
+            globals.extend(summary.reads.iter().copied());
+            globals.extend(summary.writes.iter().map(|w| w.global));
+            scene_out.fns.insert(name.clone(), summary);
+        }
+
+        let blocks = match list_blocks(&vfs, game, cpk) {
+            Ok(blocks) => blocks,
+            Err(e) => {
+                eprintln!("WARN: list sub-scenes of {} failed: {:#}", cpk, e);
+                vec![]
+            }
+        };
+        eprintln!(
+            "  {}: {} procs, {} sub-scenes",
+            cpk,
+            sce.procs.len(),
+            blocks.len()
+        );
+        if blocks.is_empty() {
+            scene_out.note = Some("no .scn files; reachable only via LoadScene".to_string());
+        }
+
+        for scn in &blocks {
+            let scn_file = scn_load_from_file(&vfs, scn_path(game, cpk, scn), game);
+            let entry_fn = format!("_{}_{}", cpk, scn);
+            let entry_fn = scene_out
+                .fns
+                .keys()
+                .find(|name| name.eq_ignore_ascii_case(&entry_fn))
+                .cloned();
+            let block = build_block(&scn_file, entry_fn, &proc_names, &scene_out);
+            scene_out.blocks.insert(scn.to_ascii_lowercase(), block);
+        }
+        catalog.scenes.insert(scene_name, scene_out);
+    }
+
+    catalog.global_count = globals.len();
+    synthesise_missing_blocks(&mut catalog);
+    build_plot_index(&mut catalog);
+    eprintln!(
+        "Catalog: {} scenes / {} blocks / {} shared globals touched / {} plot_index entries",
+        catalog.scenes.len(),
+        catalog
+            .scenes
+            .values()
+            .map(|s| s.blocks.len())
+            .sum::<usize>(),
+        catalog.global_count,
+        catalog.plot_index.values().map(|v| v.len()).sum::<usize>(),
+    );
+
+    write_catalog(&catalog, &cli.out, cli.pretty)?;
+    eprintln!("Wrote {}", cli.out.display());
+    Ok(())
+}
+
+// ---- vfs layout ---------------------------------------------------------
+
+/// Scene cpk names. PAL3 mounts each `scene/<cpk>.cpk` at
+/// `/scene/<cpk>`; PAL3A has one `.sce` per scene in `sce.cpk`.
+fn list_scenes(vfs: &MiniFs, game: GameType) -> Result<Vec<String>> {
+    let mut names: Vec<String> = match game {
+        GameType::PAL3A => list_dir(vfs, "/scene/sce/Sce")?
+            .into_iter()
+            .filter(|(_, kind)| matches!(kind, EntryKind::File))
+            .filter_map(|(name, _)| strip_ext(&name, "sce"))
+            .collect(),
+        _ => list_dir(vfs, "/scene")?
+            .into_iter()
+            .filter_map(|(name, kind)| match kind {
+                EntryKind::Dir => Some(name),
+                // Depending on the store the mount point may only show
+                // up as the package file itself.
+                EntryKind::File => strip_ext(&name, "cpk"),
+            })
+            .filter(|name| vfs.open(sce_path(game, name)).is_ok())
+            .collect(),
+    };
+    names.sort_by_key(|name| name.to_ascii_lowercase());
+    names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    Ok(names)
+}
+
+/// Sub-scene names of `cpk`, from its `.scn` files.
+fn list_blocks(vfs: &MiniFs, game: GameType, cpk: &str) -> Result<Vec<String>> {
+    let mut names: Vec<String> = match game {
+        GameType::PAL3A => {
+            let prefix = format!("{}_", cpk.to_ascii_lowercase());
+            list_dir(vfs, &format!("/scene/scn/Scn/{}", cpk))?
+                .into_iter()
+                .filter_map(|(name, _)| strip_ext(&name, "scn"))
+                .filter_map(|stem| {
+                    stem.to_ascii_lowercase()
+                        .starts_with(&prefix)
+                        .then(|| stem[prefix.len()..].to_string())
+                })
+                .collect()
+        }
+        _ => list_dir(vfs, &format!("/scene/{}", cpk))?
+            .into_iter()
+            .filter_map(|(name, _)| strip_ext(&name, "scn"))
+            .collect(),
+    };
+    names.sort();
+    Ok(names)
+}
+
+/// Basenames of the entries under `dir`. Stores disagree on whether
+/// `Entry.name` is a basename or a path from their root, so normalise.
+fn list_dir(vfs: &MiniFs, dir: &str) -> Result<Vec<(String, EntryKind)>> {
+    let mut out = vec![];
+    for entry in vfs
+        .entries(dir)
+        .with_context(|| format!("list vfs {}", dir))?
+    {
+        let entry = entry?;
+        let name = Path::new(&entry.name)
+            .file_name()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| entry.name.to_string_lossy().into_owned());
+        out.push((name, entry.kind));
+    }
+    Ok(out)
+}
+
+fn strip_ext(name: &str, ext: &str) -> Option<String> {
+    let (stem, e) = name.rsplit_once('.')?;
+    (e.eq_ignore_ascii_case(ext) && !stem.is_empty()).then(|| stem.to_string())
+}
+
+fn sce_path(game: GameType, cpk: &str) -> String {
+    match game {
+        GameType::PAL3A => format!("/scene/sce/Sce/{}.sce", cpk),
+        _ => format!("/scene/{}/{}.sce", cpk, cpk),
+    }
+}
+
+fn scn_path(game: GameType, cpk: &str, scn: &str) -> String {
+    match game {
+        GameType::PAL3A => format!("/scene/scn/Scn/{}/{}_{}.scn", cpk, cpk, scn),
+        _ => format!("/scene/{}/{}.scn", cpk, scn),
+    }
+}
+
+fn load_sce(vfs: &MiniFs, game: GameType, cpk: &str) -> Result<SceFile> {
+    let path = sce_path(game, cpk);
+    let bytes = vfs
+        .read_to_end(&path)
+        .with_context(|| format!("read {}", path))?;
+    read_sce(&mut &bytes[..]).with_context(|| format!("parse {}", path))
+}
+
+/// The name the VM reports for a proc (`SceProcContext::label`).
+fn proc_label(id: u32, header_name: &str) -> String {
+    if header_name.is_empty() {
+        id.to_string()
+    } else {
+        header_name.to_string()
+    }
+}
+
+// ---- per-block assembly -------------------------------------------------
+
+fn build_block(
+    scn: &ScnFile,
+    entry_fn: Option<String>,
+    proc_names: &HashMap<u32, String>,
+    scene: &Scene,
+) -> Block {
+    let handler = |proc_id: u32| {
+        proc_names
+            .get(&proc_id)
+            .cloned()
+            .unwrap_or_else(|| proc_id.to_string())
+    };
+
+    let mut triggers = vec![];
+    let mut objects = vec![];
+    for node in scn.nodes.iter().filter(|n| n.sce_proc_id != 0) {
+        let function = handler(node.sce_proc_id);
+        let summary = build_trigger_summary(&function, &scene.fns, &scene.transitions);
+        let position = [node.position.x, node.position.y, node.position.z];
+        if NODE_ITEM_TRIGGERS.contains(&node.node_type) {
+            objects.push(Object {
+                name: node_name(node),
+                kind: "item",
+                position,
+                research_function: function,
+                summary,
+            });
+            continue;
+        }
+
+        let Some((shape, center, half_size)) = node_shape(node) else {
+            continue;
+        };
+        triggers.push(Trigger {
+            name: node_name(node),
+            function,
+            center,
+            half_size,
+            shape,
+            kind: "trigger",
+            summary,
+        });
+    }
+
+    let mut npcs = vec![];
+    for role in &scn.roles {
+        let position = [role.position_x, 0., role.position_z];
+        // Mirrors the role controller: scripted roles and patrolling
+        // ambient roles are activated on load, the rest stay hidden
+        // until a script shows them.
+        npcs.push(Npc {
+            name: role.name.clone(),
+            position,
+            default_visible: role.sce_proc_id != 0 || role.patrol_path.len() >= 2,
+        });
+        if role.sce_proc_id != 0 {
+            let function = handler(role.sce_proc_id);
+            triggers.push(Trigger {
+                name: format!("ROLE_{}", role.index),
+                summary: build_trigger_summary(&function, &scene.fns, &scene.transitions),
+                function,
+                center: position,
+                half_size: [PROXIMITY_RADIUS, 0., PROXIMITY_RADIUS],
+                shape: "role",
+                kind: "trigger",
+            });
+        }
+    }
+
+    Block {
+        entry_fn,
+        triggers,
+        npcs,
+        objects,
+        synthesized: false,
+    }
+}
+
+fn node_name(node: &ScnNode) -> String {
+    format!("NODE_{}", node.index)
+}
+
+/// Shape, center and half size of a node the scene tests as a trigger.
+/// Nav rectangles are in nav-map cells, which need the `.nav` file to
+/// place in the world, so they report the node position and no extent.
+fn node_shape(node: &ScnNode) -> Option<(&'static str, [f32; 3], [f32; 3])> {
+    let position = [node.position.x, node.position.y, node.position.z];
+    if node.node_type == NODE_AABB_TRIGGER {
+        let (a, b) = (&node.aabb_trigger_coord1, &node.aabb_trigger_coord2);
+        let center = [(a.x + b.x) * 0.5, (a.y + b.y) * 0.5, (a.z + b.z) * 0.5];
+        let half_size = [
+            (a.x - b.x).abs() * 0.5,
+            (a.y - b.y).abs() * 0.5,
+            (a.z - b.z).abs() * 0.5,
+        ];
+        return Some(("aabb", center, half_size));
+    }
+    if NODE_LADDERS.contains(&node.node_type) {
+        return Some(("ladder", position, [0.; 3]));
+    }
+
+    let has_nav_rect = node.nav_trigger_coord_min != (0, 0) || node.nav_trigger_coord_max != (0, 0);
+    if has_nav_rect && (node.node_type == NODE_NAV_TRIGGER || node.node_type == 0) {
+        return Some(("nav", position, [0.; 3]));
+    }
+
+    None
+}
+
+// ---- proc walker --------------------------------------------------------
+
+/// Opcodes whose `Var` operand receives a value.
+const WRITES_VAR: &[i16] = &[13, 17, 49, 50, 52, 66, 77, 108, 126, 132];
+const OP_FOP: i16 = 5;
+const OP_TEST_GOTO: i16 = 12;
+const OP_LET: i16 = 13;
+const OP_CALL: i16 = 16;
+const OP_BETWEEN: i16 = 19;
+const OP_LOAD_SCENE: i16 = 63;
+
+fn is_cmp(code: i16) -> bool {
+    (6..=11).contains(&code) || code == OP_BETWEEN
+}
+
+/// Summarise one proc. `cmp_literals` is keyed by the `TestGoto`
+/// address, which is the `pc` its trace `Branch` event reports, and
+/// holds the literal a shared global was compared against when the
+/// test is a single comparison.
+fn walk_proc(
+    code: &[u8],
+    proc_names: &HashMap<u32, String>,
+    fn_name: &str,
+    transitions: &mut Vec<Transition>,
+) -> FunctionSummary {
+    let mut summary = FunctionSummary::default();
+    // The literal of the comparison feeding the next `TestGoto`; the
+    // outer `Option` is whether there is one at all.
+    let mut pending_cmp: Option<Option<i32>> = None;
+
+    for inst in decode(code).insts {
+        let Some(op) = inst.opcode() else { continue };
+        if !summary.sysfns.iter().any(|s| s == op.name) {
+            summary.sysfns.push(op.name.to_string());
+        }
+
+        for (i, arg) in inst.args.iter().enumerate() {
+            let SceArg::Var(var) = *arg else { continue };
+            let slot = var.unsigned_abs() as u32;
+            let is_write = i == 0 && WRITES_VAR.contains(&inst.code);
+            if is_write {
+                if var < 0 || inst.code == OP_LET {
+                    let value = match (inst.code, inst.args.get(1)) {
+                        (OP_LET, Some(SceArg::Int(v))) => Some(*v),
+                        _ => None,
+                    };
+                    let write = ValueWrite {
+                        global: slot,
+                        value,
+                    };
+                    if !summary.writes.contains(&write) {
+                        summary.writes.push(write);
+                    }
+                }
+            } else if var < 0 && !summary.reads.contains(&slot) {
+                summary.reads.push(slot);
+            }
+        }
+
+        pending_cmp = match inst.code {
+            code if is_cmp(code) => match inst.args.as_slice() {
+                [SceArg::Var(var), SceArg::Int(v), ..] if *var < 0 => Some(Some(*v)),
+                [SceArg::Var(var), ..] if *var < 0 => Some(None),
+                _ => None,
+            },
+            // A combined test has no single literal.
+            OP_FOP => pending_cmp.map(|_| None),
+            OP_TEST_GOTO => {
+                if let Some(literal) = pending_cmp {
+                    summary.cmp_literals.entry(inst.addr).or_insert(literal);
+                }
+                None
+            }
+            _ => None,
+        };
+
+        match (inst.code, inst.args.as_slice()) {
+            (OP_CALL, [target, ..]) => {
+                let id = target.as_u32();
+                let callee = proc_names
+                    .get(&id)
+                    .cloned()
+                    .unwrap_or_else(|| id.to_string());
+                if !summary.calls.contains(&callee) {
+                    summary.calls.push(callee);
+                }
+            }
+            (OP_LOAD_SCENE, [cpk, scn]) => transitions.push(Transition {
+                to: [
+                    cpk.as_text().to_ascii_lowercase(),
+                    scn.as_text().to_ascii_lowercase(),
+                ],
+                via_fn: fn_name.to_string(),
+            }),
+            _ => {}
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fileformats::pal3::sce::{SceInst, encode_inst};
+
+    fn assemble(insts: &[(i16, i16, Vec<SceArg>)]) -> Vec<u8> {
+        let mut code = vec![];
+        for (op, flags, args) in insts {
+            let inst = SceInst {
+                addr: 0,
+                code: *op,
+                flags: *flags,
+                args: args.clone(),
+            };
+            encode_inst(&inst, &mut code).unwrap();
+        }
+        code
+    }
+
+    #[test]
+    fn walk_proc_collects_globals_calls_and_transitions() {
+        let code = assemble(&[
+            // EQ $-7 3 ; TestGoto ; Let $-7 4 ; Rnd $2 10 ; Call 5 ; LoadScene
+            (8, 0, vec![SceArg::Var(-7), SceArg::Int(3)]),
+            (12, 0, vec![SceArg::Addr(0)]),
+            (13, 0, vec![SceArg::Var(-7), SceArg::Int(4)]),
+            (17, 0, vec![SceArg::Var(2), SceArg::Int(10)]),
+            (16, 0, vec![SceArg::UInt(5)]),
+            (
+                63,
+                0,
+                vec![
+                    SceArg::Str(b"Q02\0".to_vec()),
+                    SceArg::Str(b"Q02a\0".to_vec()),
+                ],
+            ),
+        ]);
+        let names = HashMap::from([(5, "helper".to_string())]);
+        let mut transitions = vec![];
+
+        let s = walk_proc(&code, &names, "_q01_q01", &mut transitions);
+
+        assert_eq!(s.reads, vec![7]);
+        assert_eq!(
+            s.writes,
+            vec![ValueWrite {
+                global: 7,
+                value: Some(4)
+            }]
+        );
+        assert_eq!(s.calls, vec!["helper"]);
+        // EQ is 4 + 2 + 4 bytes, so TestGoto sits at 10.
+        assert_eq!(s.cmp_literals.get(&10), Some(&Some(3)));
+        assert_eq!(s.sysfns[..3], ["EQ", "TestGoto", "Let"]);
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].to, ["q02".to_string(), "q02a".to_string()]);
+        assert_eq!(transitions[0].via_fn, "_q01_q01");
+    }
+
+    #[test]
+    fn combined_and_local_tests_have_no_literal() {
+        let code = assemble(&[
+            (8, 0, vec![SceArg::Var(-1), SceArg::Int(1)]),
+            (8, 0, vec![SceArg::Var(-2), SceArg::Int(2)]),
+            (5, 0, vec![SceArg::Int(0)]),
+            (12, 0, vec![SceArg::Addr(0)]),
+            (8, 0, vec![SceArg::Var(3), SceArg::Int(1)]),
+            (12, 0, vec![SceArg::Addr(0)]),
+        ]);
+
+        let s = walk_proc(&code, &HashMap::new(), "p", &mut vec![]);
+
+        assert_eq!(s.cmp_literals.len(), 1);
+        assert_eq!(s.cmp_literals.values().next(), Some(&None));
+        assert_eq!(s.reads, vec![1, 2]);
+    }
+
+    #[test]
+    fn strip_ext_is_case_insensitive() {
+        assert_eq!(strip_ext("Q01A.SCN", "scn").as_deref(), Some("Q01A"));
+        assert_eq!(strip_ext("q01.sce", "scn"), None);
+        assert_eq!(strip_ext(".scn", "scn"), None);
+    }
+}
@@ -21,7 +21,7 @@
 //! Anything more complex flushes the stack; we'd rather emit an empty
 //! `transitions` list than a false one.
 
-use std::{collections::BTreeMap, fs::File, io::BufWriter, path::PathBuf};
+use std::{collections::BTreeMap, path::PathBuf};
 
 use anyhow::{Context, Result};
 use clap::Parser;
@@ -35,7 +35,10 @@ use fileformats::{
     },
 };
 use packfs::init_virtual_fs;
-use serde::Serialize;
+use plot_catalog::{
+    Block, CATALOG_VERSION, Catalog, FunctionSummary, Npc, Object, Scene, Transition, Trigger,
+    ValueWrite, build_plot_index, build_trigger_summary, synthesise_missing_blocks, write_catalog,
+};
 use shared::{
     openpal4::{scripting::create_context, vm_context::Pal4VmContext},
     scripting::angelscript::{
@@ -67,205 +70,6 @@ struct Cli {
     debug_fn: Option<String>,
 }
 
-// ---- output schema ------------------------------------------------------
-
-/// Schema version recorded in the catalog's top-level `version` field.
-/// Bump on every shape-breaking change so downstream loaders can
-/// detect stale dumps. v2 adds `fns[..].cmp_literals` for gate-
-/// predicate inference; see `docs/pal4_plot_catalog.md`.
-const CATALOG_VERSION: u32 = 2;
-
-#[derive(Serialize, Default)]
-struct Catalog {
-    version: u32,
-    sysfn_count: usize,
-    global_count: usize,
-    scenes: BTreeMap<String, Scene>,
-    /// Reverse index "to advance global[i] to value V, fire one of
-    /// these triggers" — see the `Plot advancement` section of
-    /// `docs/pal4_plot_catalog.md`. Built from the per-trigger /
-    /// per-research_function call-graph closure (`TriggerSummary.writes`).
-    /// Keys are stringified slot indices (JSON object keys are
-    /// strings); values are sorted by `(scene, block, trigger, fn)`.
-    plot_index: BTreeMap<String, Vec<PlotIndexEntry>>,
-}
-
-#[derive(Serialize, Default)]
-struct Scene {
-    blocks: BTreeMap<String, Block>,
-    /// Per-function summary for every function in this scene's
-    /// module. Blocks reference these by name via
-    /// `Block.triggers[..].function` / `Block.objects[..].research_function`
-    /// — kept at scene level because PAL4 scripts mostly use
-    /// generic `func1234` names that don't carry a block prefix.
-    fns: BTreeMap<String, FunctionSummary>,
-    /// All `giArenaLoad(scene, block, …)` transitions recovered
-    /// from this scene's module. Aggregated at scene level for the
-    /// same reason as `fns`.
-    transitions: Vec<Transition>,
-    /// Set when a `.csb` parsed cleanly but carried no `*_init`
-    /// fns — i.e. the scene is reachable from elsewhere via
-    /// `giArenaLoad` but doesn't define its own playable blocks
-    /// (worldMap, M02, …). Lets agents following the transition
-    /// list see *something* under the scene key instead of nothing.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    note: Option<String>,
-}
-
-#[derive(Serialize, Default)]
-struct Block {
-    entry_fn: Option<String>,
-    triggers: Vec<Trigger>,
-    npcs: Vec<Npc>,
-    objects: Vec<Object>,
-    /// `true` when this block entry was synthesised because some
-    /// other block's `transitions` referenced `(scene, block)` but
-    /// no `*_init` fn produced an entry. The block's
-    /// `triggers/npcs/objects` are intentionally empty — consumers
-    /// should fall back to live `/v1/scene/triggers` data once the
-    /// engine actually loads the block. Resolves the "missing
-    /// blocks" half of issue C1 in `generated/issues.md`.
-    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
-    synthesized: bool,
-}
-
-#[derive(Serialize)]
-struct Trigger {
-    name: String,
-    function: String,
-    center: [f32; 3],
-    half_size: [f32; 3],
-    shape: &'static str,
-    /// Coarse classification: `"trigger"` for entries with a bound
-    /// script function, `"wall"` for collision / camera-helper
-    /// volumes (empty function + `shape == "other"`). The live
-    /// engine itself skips `"wall"` entries when building collision
-    /// — agents should use this field rather than re-deriving the
-    /// same heuristic on the consumer side.
-    kind: &'static str,
-    /// Aggregated call-graph closure starting from `function`. See
-    /// `TriggerSummary` for field semantics; flattened inline here so
-    /// agents only need to inspect one struct per trigger.
-    #[serde(flatten)]
-    summary: TriggerSummary,
-}
-
-#[derive(Serialize)]
-struct Npc {
-    name: String,
-    position: [f32; 3],
-    default_visible: bool,
-}
-
-#[derive(Serialize)]
-struct Object {
-    name: String,
-    kind: &'static str,
-    position: [f32; 3],
-    research_function: String,
-    /// Same call-graph closure as `Trigger.summary`, computed from
-    /// `research_function` when it's non-empty. Empty `TriggerSummary`
-    /// (no called_fns / no reads / no writes / no transitions) when
-    /// the entry has no examine handler.
-    #[serde(flatten)]
-    summary: TriggerSummary,
-}
-
-#[derive(Serialize, Clone)]
-struct Transition {
-    /// `[scene, block]` of the destination, when both literals were
-    /// recovered. Lone-literal cases (only scene OR only block) are
-    /// dropped.
-    to: [String; 2],
-    /// The function the transition lives in (so the agent can match
-    /// it back to a trigger or scripted cutscene).
-    via_fn: String,
-}
-
-#[derive(Serialize, Default)]
-struct FunctionSummary {
-    /// Shared-global slot indices read by this fn (decoded from
-    /// negative `Rdga4` indices via `(-index - 1)`; positive indices
-    /// reference module-local globals and are *not* part of the plot
-    /// vector, so they are skipped).
-    reads: Vec<u32>,
-    /// Shared-global writes. `value` is `Some(v)` when the value
-    /// stored is a literal `Set4 v` / `PushZero` immediately preceding
-    /// the `Movga4` — i.e. when the catalog can prove what the fn
-    /// writes. `None` when the value is computed (read from another
-    /// global, arithmetic, function return, …).
-    writes: Vec<ValueWrite>,
-    /// Distinct sysfn names called in this function, in encounter
-    /// order. Useful for spotting `giNewArenaLoad`, `giPlayMov`,
-    /// `giTalk`, … patterns at a glance.
-    sysfns: Vec<String>,
-    /// Distinct module-local function names called via `Call`
-    /// (opcode 12). Used to seed the per-trigger closure walker.
-    calls: Vec<String>,
-    /// Recovered "RHS literal" for `Jz` / `Jnz` instructions that
-    /// follow the standard `Rdga4 slot ; Set4 V ; Subi|Cmpi ; Jcc`
-    /// gate pattern. Map key is the **PC of the `Jcc` instruction**
-    /// (matches `TraceEvent::Branch.pc`); value is the literal `V`
-    /// the script compared the global against, or `None` when the
-    /// RHS was computed. Lets the planner populate
-    /// `gates.inferred_required_value` without re-reading the
-    /// bytecode at agent-runtime. See `docs/pal4_planner.md`
-    /// "Gate inference".
-    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
-    cmp_literals: BTreeMap<u32, Option<i32>>,
-}
-
-/// A single concrete write to a shared global.
-#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
-struct ValueWrite {
-    global: u32,
-    value: Option<i32>,
-}
-
-/// Aggregated view of the call-graph closure starting from a trigger's
-/// entry fn (or an object's `research_function`). All fields are the
-/// union over the reachable fn set, computed by BFS-following `Call`
-/// edges within the same module (capped at `CALL_CLOSURE_DEPTH` fns to
-/// bound runtime — the catalog is a planning hint, not a simulator).
-#[derive(Serialize, Default)]
-struct TriggerSummary {
-    /// Fn names visited during the closure walk, in BFS order.
-    /// Always non-empty when a handler exists; first entry is the
-    /// entry fn itself.
-    called_fns: Vec<String>,
-    /// Union of `FunctionSummary.reads` across `called_fns`.
-    reads: Vec<u32>,
-    /// Union of `FunctionSummary.writes` across `called_fns`,
-    /// deduped by `(global, value)`.
-    writes: Vec<ValueWrite>,
-    /// `[scene, block]` destinations reachable via a `giArenaLoad`
-    /// somewhere in the closure (deduped).
-    transitions: Vec<[String; 2]>,
-}
-
-/// One row of `Catalog.plot_index` — answers "this trigger advances
-/// `global[i]` to value `v`". `trigger` is `None` for entries seeded
-/// from a fn that no live trigger references (init handlers, scripted
-/// cutscenes invoked only by other fns); chase the `fn` field through
-/// the per-scene `transitions` / `fns` tables to find a fireable
-/// ancestor.
-#[derive(Serialize, Clone, Debug)]
-struct PlotIndexEntry {
-    value: Option<i32>,
-    scene: String,
-    block: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    trigger: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    object: Option<String>,
-    r#fn: String,
-}
-
-/// BFS depth cap for the per-trigger call-graph closure. PAL4 trigger
-/// handlers chain at most 3–4 deep in practice; 16 leaves plenty of
-/// headroom while bounding worst-case fan-out on weird modules.
-const CALL_CLOSURE_DEPTH: usize = 16;
-
 // ---- main ---------------------------------------------------------------
 
 fn main() -> Result<()> {
@@ -517,13 +321,7 @@ fn main() -> Result<()> {
         catalog.plot_index.values().map(|v| v.len()).sum::<usize>(),
     );
 
-    let file = File::create(&cli.out).with_context(|| format!("create {}", cli.out.display()))?;
-    let mut writer = BufWriter::new(file);
-    if cli.pretty {
-        serde_json::to_writer_pretty(&mut writer, &catalog)?;
-    } else {
-        serde_json::to_writer(&mut writer, &catalog)?;
-    }
+    write_catalog(&catalog, &cli.out, cli.pretty)?;
     eprintln!("Wrote {}", cli.out.display());
     Ok(())
 }
@@ -956,200 +754,6 @@ fn walk_function(
     summary
 }
 
-// ---- per-trigger closure ------------------------------------------------
-
-/// BFS the intra-module call graph starting from `entry_fn`, unioning
-/// `FunctionSummary` fields and `giArenaLoad` destinations across every
-/// reachable fn. Bounded by `CALL_CLOSURE_DEPTH` fns total (not depth)
-/// to keep runtime predictable on weird modules.
-fn build_trigger_summary(
-    entry_fn: &str,
-    fns: &BTreeMap<String, FunctionSummary>,
-    scene_transitions: &[Transition],
-) -> TriggerSummary {
-    let mut summary = TriggerSummary::default();
-    if entry_fn.is_empty() || !fns.contains_key(entry_fn) {
-        return summary;
-    }
-
-    let mut visited: BTreeMap<String, ()> = BTreeMap::new();
-    let mut queue: std::collections::VecDeque<String> = std::collections::VecDeque::new();
-    queue.push_back(entry_fn.to_string());
-    visited.insert(entry_fn.to_string(), ());
-
-    while let Some(name) = queue.pop_front() {
-        if summary.called_fns.len() >= CALL_CLOSURE_DEPTH {
-            break;
-        }
-        summary.called_fns.push(name.clone());
-        let Some(fs) = fns.get(&name) else { continue };
-
-        for r in &fs.reads {
-            if !summary.reads.contains(r) {
-                summary.reads.push(*r);
-            }
-        }
-        for w in &fs.writes {
-            if !summary.writes.contains(w) {
-                summary.writes.push(w.clone());
-            }
-        }
-        for callee in &fs.calls {
-            if !visited.contains_key(callee) {
-                visited.insert(callee.clone(), ());
-                queue.push_back(callee.clone());
-            }
-        }
-        for t in scene_transitions {
-            if t.via_fn == name {
-                let pair = t.to.clone();
-                if !summary.transitions.contains(&pair) {
-                    summary.transitions.push(pair);
-                }
-            }
-        }
-    }
-
-    summary
-}
-
-// ---- block synthesis ----------------------------------------------------
-
-/// Add stub entries to `Catalog.scenes` for every `(scene, block)`
-/// pair some other block's recorded `transitions` references but no
-/// `*_init` discovery surfaced. Resolves the "missing blocks" half
-/// of issue C1 from `generated/issues.md` — without this, agents
-/// chasing the catalog's own transition list dead-end on, e.g.,
-/// `Q01/N03` (which is reachable via `Q01/Q01/ev_Q01_Q01_6` but had
-/// no `Q01_N03_init` fn to discover it from).
-///
-/// Synthesised blocks set `synthesized: true` and have empty
-/// `triggers/npcs/objects` vectors; consumers should fall back to
-/// live `/v1/scene/triggers` data once the engine actually loads
-/// the block.
-fn synthesise_missing_blocks(catalog: &mut Catalog) {
-    // Snapshot every referenced (scene, block) pair before
-    // mutating the catalog, so the loop's reads don't race the
-    // writes.
-    let mut needed: Vec<(String, String)> = Vec::new();
-    for (_, scene) in &catalog.scenes {
-        for (_, block) in &scene.blocks {
-            for trig in &block.triggers {
-                for dest in &trig.summary.transitions {
-                    needed.push((dest[0].clone(), dest[1].clone()));
-                }
-            }
-        }
-    }
-    let mut added = 0usize;
-    for (scn, blk) in needed {
-        // Existing scene keys are lower-cased (see `let scene_name
-        // = stem.to_ascii_lowercase()` at the discovery sites);
-        // match that so we don't create case-split duplicates.
-        let scn = scn.to_ascii_lowercase();
-        let scene = catalog
-            .scenes
-            .entry(scn.clone())
-            .or_insert_with(Scene::default);
-        if scene.blocks.contains_key(&blk) {
-            continue;
-        }
-        scene.blocks.insert(
-            blk.clone(),
-            Block {
-                synthesized: true,
-                ..Block::default()
-            },
-        );
-        added += 1;
-    }
-    if added > 0 {
-        eprintln!("Synthesised {} stub block(s) from transitions", added);
-    }
-}
-
-// ---- plot_index ---------------------------------------------------------
-
-/// Build `Catalog.plot_index` from the per-trigger / per-object closure
-/// `writes`. Entries are sorted by `(scene, block, trigger.unwrap_or(""),
-/// object.unwrap_or(""), fn)` so successive runs of the dumper produce
-/// stable diffs.
-fn build_plot_index(catalog: &mut Catalog) {
-    let mut index: BTreeMap<String, Vec<PlotIndexEntry>> = BTreeMap::new();
-
-    for (scene_name, scene) in &catalog.scenes {
-        for (block_name, block) in &scene.blocks {
-            for trig in &block.triggers {
-                if trig.function.is_empty() {
-                    continue;
-                }
-                for w in &trig.summary.writes {
-                    index
-                        .entry(w.global.to_string())
-                        .or_default()
-                        .push(PlotIndexEntry {
-                            value: w.value,
-                            scene: scene_name.clone(),
-                            block: block_name.clone(),
-                            trigger: Some(trig.name.clone()),
-                            object: None,
-                            r#fn: trig.function.clone(),
-                        });
-                }
-            }
-            for obj in &block.objects {
-                if obj.research_function.is_empty() {
-                    continue;
-                }
-                for w in &obj.summary.writes {
-                    index
-                        .entry(w.global.to_string())
-                        .or_default()
-                        .push(PlotIndexEntry {
-                            value: w.value,
-                            scene: scene_name.clone(),
-                            block: block_name.clone(),
-                            trigger: None,
-                            object: Some(obj.name.clone()),
-                            r#fn: obj.research_function.clone(),
-                        });
-                }
-            }
-        }
-    }
-
-    for entries in index.values_mut() {
-        entries.sort_by(|a, b| {
-            (
-                &a.scene,
-                &a.block,
-                a.trigger.as_deref().unwrap_or(""),
-                a.object.as_deref().unwrap_or(""),
-                &a.r#fn,
-                a.value,
-            )
-                .cmp(&(
-                    &b.scene,
-                    &b.block,
-                    b.trigger.as_deref().unwrap_or(""),
-                    b.object.as_deref().unwrap_or(""),
-                    &b.r#fn,
-                    b.value,
-                ))
-        });
-        entries.dedup_by(|a, b| {
-            a.scene == b.scene
-                && a.block == b.block
-                && a.trigger == b.trigger
-                && a.object == b.object
-                && a.r#fn == b.r#fn
-                && a.value == b.value
-        });
-    }
-
-    catalog.plot_index = index;
-}
-
 fn parse_init_name(name: &str) -> Option<(&str, &str)> {
     let body = name.strip_suffix("_init")?;
     let (scene, block) = body.rsplit_once('_')?;
@@ -1180,126 +784,6 @@ mod tests {
         assert_eq!(parse_init_name("setup_init"), None); // no scene/block split
     }
 
-    fn fn_with_writes(writes: Vec<(u32, Option<i32>)>) -> FunctionSummary {
-        FunctionSummary {
-            reads: vec![],
-            writes: writes
-                .into_iter()
-                .map(|(global, value)| ValueWrite { global, value })
-                .collect(),
-            sysfns: vec![],
-            calls: vec![],
-            cmp_literals: BTreeMap::new(),
-        }
-    }
-
-    fn fn_with_calls(calls: Vec<&str>) -> FunctionSummary {
-        FunctionSummary {
-            reads: vec![],
-            writes: vec![],
-            sysfns: vec![],
-            calls: calls.into_iter().map(|s| s.to_string()).collect(),
-            cmp_literals: BTreeMap::new(),
-        }
-    }
-
-    #[test]
-    fn closure_unions_reads_writes_calls_and_transitions() {
-        // entry -> child -> leaf; entry sets g[5]=1, leaf sets g[7]=Some(2)
-        // and a giArenaLoad-derived transition lives on `leaf`.
-        let mut fns: BTreeMap<String, FunctionSummary> = BTreeMap::new();
-        let mut entry = fn_with_calls(vec!["child"]);
-        entry.writes.push(ValueWrite {
-            global: 5,
-            value: Some(1),
-        });
-        entry.reads.push(3);
-        fns.insert("entry".into(), entry);
-        let mut child = fn_with_calls(vec!["leaf"]);
-        child.reads.push(11);
-        fns.insert("child".into(), child);
-        let leaf = fn_with_writes(vec![(7, Some(2))]);
-        fns.insert("leaf".into(), leaf);
-        let transitions = vec![Transition {
-            to: ["q02".into(), "Q01".into()],
-            via_fn: "leaf".into(),
-        }];
-
-        let s = build_trigger_summary("entry", &fns, &transitions);
-
-        assert_eq!(s.called_fns, vec!["entry", "child", "leaf"]);
-        assert!(s.reads.contains(&3) && s.reads.contains(&11));
-        assert!(s.writes.contains(&ValueWrite {
-            global: 5,
-            value: Some(1)
-        }));
-        assert!(s.writes.contains(&ValueWrite {
-            global: 7,
-            value: Some(2)
-        }));
-        assert_eq!(s.transitions, vec![["q02".to_string(), "Q01".to_string()]]);
-    }
-
-    #[test]
-    fn closure_empty_for_missing_fn() {
-        let fns: BTreeMap<String, FunctionSummary> = BTreeMap::new();
-        let s = build_trigger_summary("does_not_exist", &fns, &[]);
-        assert!(s.called_fns.is_empty());
-        assert!(s.reads.is_empty());
-        assert!(s.writes.is_empty());
-        assert!(s.transitions.is_empty());
-    }
-
-    #[test]
-    fn closure_is_dedup_and_terminates_on_cycle() {
-        // a -> b -> a (cycle): closure must include {a, b} exactly once and return.
-        let mut fns: BTreeMap<String, FunctionSummary> = BTreeMap::new();
-        fns.insert("a".into(), fn_with_calls(vec!["b"]));
-        fns.insert("b".into(), fn_with_calls(vec!["a"]));
-        let s = build_trigger_summary("a", &fns, &[]);
-        assert_eq!(s.called_fns, vec!["a", "b"]);
-    }
-
-    #[test]
-    fn plot_index_groups_by_global_and_dedupes() {
-        let mut catalog = Catalog {
-            version: CATALOG_VERSION,
-            sysfn_count: 0,
-            global_count: 0,
-            scenes: BTreeMap::new(),
-            plot_index: BTreeMap::new(),
-        };
-
-        let mut scene = Scene::default();
-        scene
-            .fns
-            .insert("f1".into(), fn_with_writes(vec![(5, Some(1))]));
-        let mut block = Block::default();
-        let trig_summary = build_trigger_summary("f1", &scene.fns, &[]);
-        block.triggers.push(Trigger {
-            name: "ev01".into(),
-            function: "f1".into(),
-            center: [0.0; 3],
-            half_size: [0.0; 3],
-            shape: "plane",
-            kind: "trigger",
-            summary: trig_summary,
-        });
-        scene.blocks.insert("01".into(), block);
-        catalog.scenes.insert("q01".into(), scene);
-
-        build_plot_index(&mut catalog);
-
-        let entries = catalog.plot_index.get("5").expect("global 5 not indexed");
-        assert_eq!(entries.len(), 1);
-        let e = &entries[0];
-        assert_eq!(e.value, Some(1));
-        assert_eq!(e.scene, "q01");
-        assert_eq!(e.block, "01");
-        assert_eq!(e.trigger.as_deref(), Some("ev01"));
-        assert_eq!(e.r#fn, "f1");
-    }
-
     /// `extract_cmp_literals` must recover the RHS literal from both
     /// forms of the AS gate pattern, keyed by the trace-visible PC
     /// (`addr + 8` — opcode is 4 bytes, the offset operand another 4).
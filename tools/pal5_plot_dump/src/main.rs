@@ -0,0 +1,652 @@
+//! PAL5 plot catalog generator.
+//!
+//! Emits the same JSON catalog as `pal4_plot_dump` (see the
+//! `plot_catalog` crate), built from `scriptlist.ini` and the
+//! (transparently decrypted) Lua scripts it lists.
+//!
+//! PAL5 has no per-map script modules: every script is loaded into one
+//! Lua state by `CallScript(id)` / `Include(id)`. So the catalog has a
+//! single `script` scene with one block per listed script:
+//!
+//! ```text
+//! scenes.script.blocks.<name> = { entry_fn, triggers: [<id>] }
+//! scenes.script.fns.<fn>      = { reads, writes, sysfns, calls }
+//! ```
+//!
+//! - Each block carries one trigger named after the script id, i.e.
+//!   what to pass to `CallScript` to run it.
+//! - Function names are the ones the Lua execution trace reports: the
+//!   global name for named functions, `[string "script:<name>"]:0`
+//!   for a script's top-level chunk.
+//! - `calls` holds direct calls to functions defined in any script and
+//!   `CallScript`/`Include` edges (to the callee's chunk and entry).
+//! - `flag.SetValue(id, v)` / `flag.GetValue(id)` with a literal `id`
+//!   are the global writes / reads.
+//! - `map.ChangeNoScript(map, sub)` with literal ids is a transition to
+//!   `[map, sub]`; those show up as synthesized scenes keyed by map id.
+//!
+//! The Lua is scanned with a small tokenizer rather than parsed, which
+//! is enough for the flat command-list style the game's scripts use.
+//! Calls through variables or with computed ids are not recovered.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use common::store_ext::StoreExt2;
+use packfs::init_virtual_fs;
+use plot_catalog::{
+    Block, CATALOG_VERSION, Catalog, FunctionSummary, Scene, Transition, Trigger, ValueWrite,
+    build_plot_index, build_trigger_summary, synthesise_missing_blocks, write_catalog,
+};
+use shared::openpal5::script::ScriptIndex;
+
+#[derive(Parser)]
+#[command(about = "Generate a PAL5 plot catalog (JSON)")]
+struct Cli {
+    /// PAL5 install root (the directory containing `Config/` and the
+    /// `.pkg` packages).
+    #[arg(long)]
+    root: PathBuf,
+
+    /// Output JSON path.
+    #[arg(long, default_value = "pal5_plot.json")]
+    out: PathBuf,
+
+    /// Pretty-print the JSON.
+    #[arg(long)]
+    pretty: bool,
+}
+
+const SCENE_NAME: &str = "script";
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let root = cli
+        .root
+        .canonicalize()
+        .with_context(|| format!("resolve root: {}", cli.root.display()))?;
+    eprintln!("Mounting PAL5 vfs from {}", root.display());
+    let vfs = init_virtual_fs(&root, None);
+    let index = ScriptIndex::load(&vfs).context("load scriptlist.ini")?;
+    let entries = index.entries();
+    eprintln!("scriptlist.ini: {} scripts", entries.len());
+
+    let script_names: BTreeMap<u32, String> = entries
+        .iter()
+        .map(|(id, entry)| (*id, entry.name.clone()))
+        .collect();
+
+    let mut scene = Scene {
+        note: Some(
+            "PAL5 scripts share one Lua state; blocks are scriptlist.ini scripts, \
+             transitions are map.ChangeNoScript map ids"
+                .to_string(),
+        ),
+        ..Scene::default()
+    };
+    let mut entry_fns = BTreeMap::new();
+    for (id, entry) in &entries {
+        let source = match vfs.read_to_end(&entry.vfs_path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("WARN: read {} failed: {:#}", entry.vfs_path, e);
+                continue;
+            }
+        };
+        let script = scan_script(&entry.name, &source, &script_names);
+        eprintln!("  {} {}: {} fns", id, entry.name, script.fns.len());
+        if script.fns.contains_key(&entry.name) {
+            entry_fns.insert(*id, entry.name.clone());
+        }
+        scene.transitions.extend(script.transitions);
+        for (name, summary) in script.fns {
+            // Later definitions win, as they do in the shared Lua state.
+            scene.fns.insert(name, summary);
+        }
+    }
+
+    // Only keep direct calls to functions some script defines; the rest
+    // are Lua builtins or locals.
+    let defined: BTreeSet<String> = scene.fns.keys().cloned().collect();
+    let mut sysfns = BTreeSet::new();
+    let mut globals = BTreeSet::new();
+    for summary in scene.fns.values_mut() {
+        summary.calls.retain(|callee| defined.contains(callee));
+        sysfns.extend(summary.sysfns.iter().cloned());
+        globals.extend(summary.reads.iter().copied());
+        globals.extend(summary.writes.iter().map(|w| w.global));
+    }
+
+    for (id, entry) in &entries {
+        let chunk = chunk_name(&entry.name);
+        if !scene.fns.contains_key(&chunk) {
+            continue;
+        }
+        let entry_fn = entry_fns.get(id).cloned();
+        let function = entry_fn.clone().unwrap_or(chunk);
+        let summary = build_trigger_summary(&function, &scene.fns, &scene.transitions);
+        let block = Block {
+            entry_fn,
+            triggers: vec![Trigger {
+                name: id.to_string(),
+                function,
+                center: [0.; 3],
+                half_size: [0.; 3],
+                shape: "script",
+                kind: "trigger",
+                summary,
+            }],
+            ..Block::default()
+        };
+        scene.blocks.insert(entry.name.clone(), block);
+    }
+
+    let mut catalog = Catalog {
+        version: CATALOG_VERSION,
+        sysfn_count: sysfns.len(),
+        global_count: globals.len(),
+        ..Catalog::default()
+    };
+    catalog.scenes.insert(SCENE_NAME.to_string(), scene);
+    synthesise_missing_blocks(&mut catalog);
+    build_plot_index(&mut catalog);
+    eprintln!(
+        "Catalog: {} scripts / {} fns / {} commands / {} flags touched / {} plot_index entries",
+        catalog.scenes[SCENE_NAME].blocks.len(),
+        catalog.scenes[SCENE_NAME].fns.len(),
+        catalog.sysfn_count,
+        catalog.global_count,
+        catalog.plot_index.values().map(|v| v.len()).sum::<usize>(),
+    );
+
+    write_catalog(&catalog, &cli.out, cli.pretty)?;
+    eprintln!("Wrote {}", cli.out.display());
+    Ok(())
+}
+
+/// Name the Lua trace gives a script's top-level chunk: `__pal5_load`
+/// names the chunk `script:<name>`, Lua shows that as a string source,
+/// and a chunk is defined on line 0.
+fn chunk_name(script: &str) -> String {
+    format!("[string \"script:{}\"]:0", script)
+}
+
+// ---- scanner ------------------------------------------------------------
+
+#[derive(Default)]
+struct ScriptSummary {
+    fns: BTreeMap<String, FunctionSummary>,
+    transitions: Vec<Transition>,
+}
+
+/// Summarise every function `source` defines, plus its top-level chunk.
+/// `scripts` resolves `CallScript`/`Include` ids to script names.
+fn scan_script(name: &str, source: &[u8], scripts: &BTreeMap<u32, String>) -> ScriptSummary {
+    let tokens = tokenize(source);
+    let mut out = ScriptSummary::default();
+    let chunk = chunk_name(name);
+    out.fns.insert(chunk.clone(), FunctionSummary::default());
+
+    // Open blocks; a named function's block remembers its name so the
+    // code inside it is attributed to it.
+    let mut blocks: Vec<Option<String>> = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        let current = blocks
+            .iter()
+            .rev()
+            .find_map(|b| b.clone())
+            .unwrap_or_else(|| chunk.clone());
+        match &tokens[i] {
+            Token::Name(word) if word == "function" => {
+                let (fn_name, len) = function_name(&tokens[i + 1..]);
+                if let Some(fn_name) = &fn_name {
+                    out.fns.entry(fn_name.clone()).or_default();
+                }
+                blocks.push(fn_name);
+                i += 1 + len;
+                continue;
+            }
+            Token::Name(word) if matches!(word.as_str(), "if" | "do" | "repeat") => {
+                blocks.push(None);
+            }
+            Token::Name(word) if matches!(word.as_str(), "end" | "until") => {
+                blocks.pop();
+            }
+            Token::Name(_) => {
+                if let Some(next) = scan_call(&tokens, i) {
+                    let summary = out.fns.entry(current.clone()).or_default();
+                    apply_call(summary, &mut out.transitions, &current, next.call, scripts);
+                    i = next.resume;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    out
+}
+
+/// The name a `function` keyword just before `rest` defines, with the
+/// number of tokens it spans: `f` for `function f(`, `function t.f(`
+/// and `function t:f(`; `None` for anonymous functions.
+fn function_name(rest: &[Token]) -> (Option<String>, usize) {
+    let mut name = None;
+    let mut len = 0;
+    while let Some(Token::Name(part)) = rest.get(len) {
+        name = Some(part.clone());
+        len += 1;
+        match rest.get(len) {
+            Some(Token::Sym(s)) if s == "." || s == ":" => len += 1,
+            _ => break,
+        }
+    }
+    (name, len)
+}
+
+enum Call {
+    /// `ns.Name(...)`.
+    Command {
+        name: String,
+        args: Vec<Option<i32>>,
+    },
+    /// `name(...)`.
+    Direct(String),
+}
+
+struct ScannedCall {
+    call: Call,
+    /// Token to continue scanning at. Arguments are not skipped, so
+    /// nested calls are still seen.
+    resume: usize,
+}
+
+/// Recognise a call starting at the name `tokens[i]`.
+fn scan_call(tokens: &[Token], i: usize) -> Option<ScannedCall> {
+    let Token::Name(first) = &tokens[i] else {
+        return None;
+    };
+    if is_keyword(first) {
+        return None;
+    }
+    // Field access on something else (`a.b.c(`) is not a command.
+    if let Some(Token::Sym(s)) = i.checked_sub(1).and_then(|p| tokens.get(p))
+        && (s == "." || s == ":")
+    {
+        return None;
+    }
+
+    match (tokens.get(i + 1), tokens.get(i + 2), tokens.get(i + 3)) {
+        (Some(Token::Sym(dot)), Some(Token::Name(second)), Some(Token::Sym(open)))
+            if (dot == "." || dot == ":") && open == "(" =>
+        {
+            Some(ScannedCall {
+                call: Call::Command {
+                    name: format!("{}.{}", first, second),
+                    args: literal_args(&tokens[i + 4..]),
+                },
+                resume: i + 4,
+            })
+        }
+        (Some(Token::Sym(open)), _, _) if open == "(" => Some(ScannedCall {
+            call: Call::Direct(first.clone()),
+            resume: i + 2,
+        }),
+        (Some(Token::Str), _, _) => Some(ScannedCall {
+            call: Call::Direct(first.clone()),
+            resume: i + 1,
+        }),
+        _ => None,
+    }
+}
+
+/// The arguments of a call whose `(` was just consumed: `Some(v)` for
+/// integer literals (optionally negated), `None` for anything else.
+fn literal_args(tokens: &[Token]) -> Vec<Option<i32>> {
+    let mut args = vec![];
+    let mut current: Vec<&Token> = vec![];
+    let mut depth = 0;
+    for token in tokens {
+        match token {
+            Token::Sym(s) if s == "(" || s == "{" || s == "[" => depth += 1,
+            Token::Sym(s) if (s == ")" || s == "}" || s == "]") && depth > 0 => depth -= 1,
+            Token::Sym(s) if s == ")" => break,
+            Token::Sym(s) if s == "," && depth == 0 => {
+                args.push(literal(&current));
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(token);
+    }
+    if !current.is_empty() {
+        args.push(literal(&current));
+    }
+    args
+}
+
+fn literal(tokens: &[&Token]) -> Option<i32> {
+    let (sign, number) = match tokens {
+        [Token::Number(n)] => (1., *n),
+        [Token::Sym(minus), Token::Number(n)] if minus == "-" => (-1., *n),
+        _ => return None,
+    };
+    let value = sign * number;
+    (value.fract() == 0. && value.abs() <= i32::MAX as f64).then_some(value as i32)
+}
+
+fn apply_call(
+    summary: &mut FunctionSummary,
+    transitions: &mut Vec<Transition>,
+    fn_name: &str,
+    call: Call,
+    scripts: &BTreeMap<u32, String>,
+) {
+    let push_unique = |list: &mut Vec<String>, name: String| {
+        if !list.contains(&name) {
+            list.push(name);
+        }
+    };
+
+    let (name, args) = match call {
+        Call::Direct(name) => {
+            push_unique(&mut summary.calls, name);
+            return;
+        }
+        Call::Command { name, args } => (name, args),
+    };
+    let arg = |i: usize| args.get(i).copied().flatten();
+
+    match name.as_str() {
+        // `Include` runs the script's chunk; `CallScript` then also
+        // calls its entry function.
+        "global.Include" | "global.CallScript" => {
+            if let Some(script) = arg(0).and_then(|id| scripts.get(&(id as u32))) {
+                push_unique(&mut summary.calls, chunk_name(script));
+                if name == "global.CallScript" {
+                    push_unique(&mut summary.calls, script.clone());
+                }
+            }
+        }
+        "flag.SetValue" => {
+            if let Some(flag) = arg(0) {
+                let write = ValueWrite {
+                    global: flag as u32,
+                    value: arg(1),
+                };
+                if !summary.writes.contains(&write) {
+                    summary.writes.push(write);
+                }
+            }
+        }
+        "flag.GetValue" => {
+            if let Some(flag) = arg(0) {
+                let flag = flag as u32;
+                if !summary.reads.contains(&flag) {
+                    summary.reads.push(flag);
+                }
+            }
+        }
+        "map.ChangeNoScript" => {
+            if let (Some(map), Some(sub)) = (arg(0), arg(1)) {
+                transitions.push(Transition {
+                    to: [map.to_string(), sub.to_string()],
+                    via_fn: fn_name.to_string(),
+                });
+            }
+        }
+        _ => {}
+    }
+    push_unique(&mut summary.sysfns, name);
+}
+
+// ---- tokenizer ----------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifier or keyword.
+    Name(String),
+    Number(f64),
+    /// Any string literal; the contents don't matter here.
+    Str,
+    /// Operator or punctuation.
+    Sym(String),
+}
+
+fn is_keyword(word: &str) -> bool {
+    matches!(
+        word,
+        "and"
+            | "break"
+            | "do"
+            | "else"
+            | "elseif"
+            | "end"
+            | "false"
+            | "for"
+            | "function"
+            | "if"
+            | "in"
+            | "local"
+            | "nil"
+            | "not"
+            | "or"
+            | "repeat"
+            | "return"
+            | "then"
+            | "true"
+            | "until"
+            | "while"
+    )
+}
+
+/// Split Lua 5.1 source into tokens, dropping comments. Works on bytes
+/// like the Lua lexer does, so GBK text inside strings and comments is
+/// passed over untouched.
+fn tokenize(src: &[u8]) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < src.len() {
+        let c = src[i];
+        match c {
+            b'-' if src.get(i + 1) == Some(&b'-') => {
+                i += 2;
+                if let Some(end) = long_bracket_end(src, i) {
+                    i = end;
+                } else {
+                    while i < src.len() && src[i] != b'\n' {
+                        i += 1;
+                    }
+                }
+            }
+            b'[' if long_bracket_end(src, i).is_some() => {
+                i = long_bracket_end(src, i).unwrap();
+                tokens.push(Token::Str);
+            }
+            b'"' | b'\'' => {
+                i += 1;
+                while i < src.len() && src[i] != c && src[i] != b'\n' {
+                    i += if src[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+                tokens.push(Token::Str);
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = i;
+                while i < src.len() && (src[i].is_ascii_alphanumeric() || src[i] == b'_') {
+                    i += 1;
+                }
+                let word = String::from_utf8_lossy(&src[start..i]).into_owned();
+                tokens.push(Token::Name(word));
+            }
+            c if c.is_ascii_digit()
+                || (c == b'.' && src.get(i + 1).is_some_and(u8::is_ascii_digit)) =>
+            {
+                let start = i;
+                while i < src.len()
+                    && (src[i].is_ascii_alphanumeric()
+                        || src[i] == b'.'
+                        || ((src[i] == b'-' || src[i] == b'+')
+                            && matches!(src[i - 1], b'e' | b'E')))
+                {
+                    i += 1;
+                }
+                let text = String::from_utf8_lossy(&src[start..i]);
+                let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                    Some(hex) => i64::from_str_radix(hex, 16).map(|v| v as f64).ok(),
+                    None => text.parse().ok(),
+                };
+                tokens.push(value.map_or(Token::Str, Token::Number));
+            }
+            c if c.is_ascii_whitespace() => i += 1,
+            _ => {
+                let sym = [b"...".as_slice(), b"..", b"==", b"~=", b"<=", b">="]
+                    .into_iter()
+                    .find(|op| src[i..].starts_with(op))
+                    .unwrap_or(&src[i..i + 1]);
+                i += sym.len();
+                tokens.push(Token::Sym(String::from_utf8_lossy(sym).into_owned()));
+            }
+        }
+    }
+    tokens
+}
+
+/// If a long bracket (`[[`, `[==[`) opens at `i`, the index just past
+/// its matching close (or the end of input).
+fn long_bracket_end(src: &[u8], i: usize) -> Option<usize> {
+    if src.get(i) != Some(&b'[') {
+        return None;
+    }
+    let level = src[i + 1..].iter().take_while(|&&b| b == b'=').count();
+    if src.get(i + 1 + level) != Some(&b'[') {
+        return None;
+    }
+
+    let mut close = vec![b']'];
+    close.extend(std::iter::repeat_n(b'=', level));
+    close.push(b']');
+    let body = i + 2 + level;
+    Some(
+        src[body..]
+            .windows(close.len())
+            .position(|w| w == close.as_slice())
+            .map_or(src.len(), |p| body + p + close.len()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+-- m001_1: [[ not a string ]]
+function m001_1()
+  if flag.GetValue(100) == 2 then
+    flag.SetValue(100, 3)
+    helper("x")
+  end
+  for i = 1, 3 do
+    global.Wait(0.5)
+  end
+  --[[ flag.SetValue(999, 1) ]]
+  local s = "flag.SetValue(998, 1)"
+  global.CallScript(7002)
+  map.ChangeNoScript(12, -1)
+end
+
+function helper(t)
+  flag.SetValue(101, flag.GetValue(100))
+end
+
+flag.SetValue(1, 0)
+"#;
+
+    #[test]
+    fn scan_script_attributes_calls_to_functions() {
+        let scripts = BTreeMap::from([(7002, "m001_2".to_string())]);
+        let s = scan_script("m001_1", SCRIPT.as_bytes(), &scripts);
+
+        assert_eq!(
+            s.fns.keys().collect::<Vec<_>>(),
+            vec![&chunk_name("m001_1"), "helper", "m001_1"]
+        );
+
+        let main = &s.fns["m001_1"];
+        assert_eq!(main.reads, vec![100]);
+        assert_eq!(
+            main.writes,
+            vec![ValueWrite {
+                global: 100,
+                value: Some(3)
+            }]
+        );
+        assert_eq!(
+            main.calls,
+            vec!["helper".to_string(), chunk_name("m001_2"), "m001_2".into()]
+        );
+        assert_eq!(
+            main.sysfns,
+            vec![
+                "flag.GetValue",
+                "flag.SetValue",
+                "global.Wait",
+                "global.CallScript",
+                "map.ChangeNoScript"
+            ]
+        );
+        assert_eq!(s.transitions.len(), 1);
+        assert_eq!(s.transitions[0].to, ["12".to_string(), "-1".to_string()]);
+        assert_eq!(s.transitions[0].via_fn, "m001_1");
+
+        let helper = &s.fns["helper"];
+        assert_eq!(
+            helper.writes,
+            vec![ValueWrite {
+                global: 101,
+                value: None
+            }]
+        );
+        assert_eq!(helper.reads, vec![100]);
+
+        assert_eq!(
+            s.fns[&chunk_name("m001_1")].writes,
+            vec![ValueWrite {
+                global: 1,
+                value: Some(0)
+            }]
+        );
+    }
+
+    #[test]
+    fn include_only_runs_the_chunk() {
+        let scripts = BTreeMap::from([(9601, "macro".to_string())]);
+        let s = scan_script("a", b"function a() global.Include(9601) end", &scripts);
+        assert_eq!(s.fns["a"].calls, vec![chunk_name("macro")]);
+    }
+
+    #[test]
+    fn tokenize_skips_comments_and_long_strings() {
+        let tokens = tokenize(b"a = [==[ ]] end ]==] --[[ x\n y ]] b 0x10 1e2 ..");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Name("a".into()),
+                Token::Sym("=".into()),
+                Token::Str,
+                Token::Name("b".into()),
+                Token::Number(16.),
+                Token::Number(100.),
+                Token::Sym("..".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Assembler / disassembler for PAL3 and PAL3A `.sce` event scripts.
+//!
+//! Inputs may be loose `.sce` files or whole CPK archives, in which
+//! case every `.sce` inside is processed. Typical modding loop:
+//!
+//! ```text
+//! sce_asm dump scene/Q01.cpk -o q01
+//! $EDITOR q01/Q01/Q01.sce.asm
+//! sce_asm asm q01/Q01/Q01.sce.asm -o Q01.sce
+//! ```
+//!
+//! `verify` checks that every script survives both the binary and the
+//! listing round trip byte for byte.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use fileformats::pal3::sce::{assemble_sce, read_sce, sce_listing, write_sce};
+use mini_fs::{MiniFs, StoreExt};
+use packfs::cpk::CpkFs;
+
+#[derive(Parser)]
+#[command(about = "Assemble and disassemble PAL3 .sce scripts")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write the listing of a `.sce`, or of every `.sce` in a CPK.
+    Dump {
+        input: PathBuf,
+        /// Output listing for a `.sce` (stdout when omitted), or the
+        /// output directory for a CPK.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Assemble a listing back into a `.sce`.
+    Asm {
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Check that every given script round-trips byte for byte.
+    Verify { inputs: Vec<PathBuf> },
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Dump { input, output } => {
+            if !is_cpk(&input) {
+                let (_, bytes) = &read_scripts(&input)?[0];
+                let text = listing(bytes).with_context(|| format!("parse {}", input.display()))?;
+                return match output {
+                    Some(output) => std::fs::write(&output, text)
+                        .with_context(|| format!("write {}", output.display())),
+                    None => {
+                        print!("{}", text);
+                        Ok(())
+                    }
+                };
+            }
+
+            let Some(output) = output else {
+                anyhow::bail!("dumping a cpk needs an output directory (-o)");
+            };
+            let scripts = read_scripts(&input)?;
+            for (name, bytes) in &scripts {
+                let text = listing(bytes).with_context(|| format!("parse {}", name))?;
+                let path = output.join(format!("{}.asm", name));
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)
+                        .with_context(|| format!("create {}", parent.display()))?;
+                }
+                std::fs::write(&path, text).with_context(|| format!("write {}", path.display()))?;
+            }
+            eprintln!("dumped {} scripts to {}", scripts.len(), output.display());
+            Ok(())
+        }
+        Command::Asm { input, output } => {
+            let text = std::fs::read_to_string(&input)
+                .with_context(|| format!("read {}", input.display()))?;
+            let file =
+                assemble_sce(&text).with_context(|| format!("assemble {}", input.display()))?;
+            let mut bytes = vec![];
+            write_sce(&mut bytes, &file)?;
+            std::fs::write(&output, bytes).with_context(|| format!("write {}", output.display()))
+        }
+        Command::Verify { inputs } => {
+            let mut total = 0;
+            let mut failures = 0;
+            for input in &inputs {
+                let scripts = match read_scripts(input) {
+                    Ok(scripts) => scripts,
+                    Err(e) => {
+                        total += 1;
+                        failures += 1;
+                        eprintln!("FAIL {}: {:#}", input.display(), e);
+                        continue;
+                    }
+                };
+                for (name, bytes) in &scripts {
+                    total += 1;
+                    match verify(bytes) {
+                        Ok(()) => eprintln!("OK   {}", name),
+                        Err(e) => {
+                            failures += 1;
+                            eprintln!("FAIL {}: {:#}", name, e);
+                        }
+                    }
+                }
+            }
+            eprintln!("--- {}/{} scripts failed ---", failures, total);
+            if failures > 0 {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn listing(bytes: &[u8]) -> Result<String> {
+    Ok(sce_listing(&read_sce(&mut &bytes[..])?))
+}
+
+fn verify(bytes: &[u8]) -> Result<()> {
+    let file = read_sce(&mut &bytes[..])?;
+    let mut rewritten = vec![];
+    write_sce(&mut rewritten, &file)?;
+    if rewritten != bytes {
+        anyhow::bail!("binary rewrite differs");
+    }
+
+    let assembled = assemble_sce(&sce_listing(&file))?;
+    let mut reassembled = vec![];
+    write_sce(&mut reassembled, &assembled)?;
+    if reassembled != bytes {
+        anyhow::bail!("listing round trip differs");
+    }
+    Ok(())
+}
+
+fn is_cpk(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cpk"))
+}
+
+/// `(name, bytes)` of the input itself, or of every `.sce` in a CPK with
+/// names relative to the archive root.
+fn read_scripts(input: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    if !is_cpk(input) {
+        let bytes = std::fs::read(input).with_context(|| format!("read {}", input.display()))?;
+        return Ok(vec![(input.display().to_string(), bytes)]);
+    }
+
+    let fs = CpkFs::new(input).with_context(|| format!("open {}", input.display()))?;
+    let vfs = MiniFs::new(false).mount("/", fs);
+    let mut scripts = vec![];
+    collect_sce(&vfs, Path::new("/"), &mut scripts)?;
+    scripts.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(scripts)
+}
+
+fn collect_sce(vfs: &MiniFs, dir: &Path, out: &mut Vec<(String, Vec<u8>)>) -> Result<()> {
+    for entry in vfs.entries(dir)?.flatten() {
+        let path = dir.join(&entry.name);
+        match entry.kind {
+            mini_fs::EntryKind::Dir => collect_sce(vfs, &path, out)?,
+            mini_fs::EntryKind::File => {
+                if !entry
+                    .name
+                    .to_string_lossy()
+                    .to_lowercase()
+                    .ends_with(".sce")
+                {
+                    continue;
+                }
+                let mut bytes = vec![];
+                vfs.open(&path)
+                    .and_then(|mut f| f.read_to_end(&mut bytes))
+                    .with_context(|| format!("read {}", path.display()))?;
+                let name = path.to_string_lossy();
+                out.push((name.trim_start_matches('/').to_string(), bytes));
+            }
+        }
+    }
+    Ok(())
+}
@@ -159,6 +159,46 @@ pub enum AgentCommand {
     /// / assertions) without forging a synthetic avatar click. Requires
     /// an active adventure director with the player in control.
     SetStatusMenu(StatusMenuParams),
+
+    /// Script debugger: current stop (if any), location, breakpoints
+    /// and watches of the running game's script VM.
+    DebugStatus,
+    /// Script debugger: procedures a breakpoint can name.
+    DebugProcedures,
+    /// Script debugger: variables the VM can read, in display order.
+    DebugVariables,
+    /// Script debugger: set or clear a breakpoint. `offset` is the
+    /// VM's own unit — SCE code offset, AngelScript `pc`, Lua line (0
+    /// for function entry).
+    DebugBreakpoint(DebugBreakpointParams),
+    /// Script debugger: add or remove a watch; the VM stops when a
+    /// watched value changes.
+    DebugWatch(DebugWatchParams),
+    /// Script debugger: stop before the next instruction (or, when no
+    /// script is running, right away).
+    DebugPause,
+    /// Script debugger: resume a stopped VM.
+    DebugContinue,
+    /// Script debugger: resume and stop again at the next instruction.
+    DebugStep,
+}
+
+impl AgentCommand {
+    /// `true` for the `Debug*` commands, which every game routes to
+    /// its script VM's debugger.
+    pub fn is_debug(&self) -> bool {
+        matches!(
+            self,
+            Self::DebugStatus
+                | Self::DebugProcedures
+                | Self::DebugVariables
+                | Self::DebugBreakpoint(_)
+                | Self::DebugWatch(_)
+                | Self::DebugPause
+                | Self::DebugContinue
+                | Self::DebugStep
+        )
+    }
 }
 
 /// Top-level agent response. Mirrors [`AgentCommand`] roughly but with
@@ -191,6 +231,12 @@ pub enum AgentResponse {
     TraceDrain(TraceDrainResponse),
     /// Snapshot reply for [`AgentCommand::GetPerfMetrics`].
     PerfMetrics(PerfMetricsResponse),
+    /// Snapshot reply for [`AgentCommand::DebugStatus`].
+    DebugStatus(DebugStatusResponse),
+    /// Snapshot reply for [`AgentCommand::DebugProcedures`].
+    DebugProcedures(DebugProceduresResponse),
+    /// Snapshot reply for [`AgentCommand::DebugVariables`].
+    DebugVariables(DebugVariablesResponse),
     /// Operation failed.
     Error(AgentError),
 }
@@ -403,6 +449,14 @@ pub struct StateSnapshot {
     /// "no items" state, not a missing field.
     #[serde(default)]
     pub inventory: Vec<InventoryEntry>,
+    /// Equipment held in the bag as `{id, count}` pairs, sorted by
+    /// `id`. Only games that keep equipment apart from items (PAL5)
+    /// fill it; elsewhere equipment is part of [`Self::inventory`].
+    #[serde(default)]
+    pub equipment: Vec<InventoryEntry>,
+    /// Learned crafting formula ids, sorted. PAL5 only.
+    #[serde(default)]
+    pub formulas: Vec<i32>,
     /// `true` while a `giShowWorldMap` continuation is waiting for
     /// a destination pick. When `true`, `script_running` will also
     /// be `true` (the VM is suspended in the Yield) — fire
@@ -444,6 +498,10 @@ pub struct PartyMember {
     pub mp: i32,
     pub max_mp: i32,
     pub in_team: bool,
+    /// Learned magic ids, sorted. Empty for games that don't track
+    /// magic per member.
+    #[serde(default)]
+    pub magic: Vec<i32>,
 }
 
 /// Current dialog state.
@@ -860,6 +918,14 @@ pub enum TraceEventKindPayload {
         fn_name: String,
         pc: u32,
     },
+    /// SCE instruction or Lua engine-command call.
+    Command {
+        fn_name: String,
+        pc: u32,
+        name: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
 }
 
 /// Wire form of the VM `BranchKind`.
@@ -872,6 +938,8 @@ pub enum TraceBranchKind {
     JnsJlz,
     JpJlez,
     JnpJgz,
+    /// PAL3 SCE `TestGoto`.
+    TestGoto,
 }
 
 /// Wire form of the VM `GlobalScope`.
@@ -884,6 +952,8 @@ pub enum TraceGlobalScope {
     /// Module-local global. PAL4 scripts almost never touch these
     /// — observed writes here are an RE signal worth surfacing.
     Module,
+    /// PAL3 SCE procedure-local variable.
+    Local,
 }
 
 /// Parameters for [`AgentCommand::ChooseDialog`].
@@ -951,3 +1021,89 @@ pub enum PerfMetric {
     /// recorded value; `max` is the highest value seen since boot.
     Gauge { name: String, last: u64, max: u64 },
 }
+
+// ---- script debugger types ----------------------------------------------
+//
+// Wire forms of `yaobow/shared/src/scripting/debugger`. Values travel as
+// JSON, marshalled like [`NamedGlobal::value`].
+
+/// Parameters for [`AgentCommand::DebugBreakpoint`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugBreakpointParams {
+    /// Procedure name, matched case-insensitively.
+    pub procedure: String,
+    /// Offset within the procedure. Defaults to 0 (procedure entry).
+    #[serde(default)]
+    pub offset: u32,
+    /// `false` clears the breakpoint. Defaults to `true`.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// Parameters for [`AgentCommand::DebugWatch`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugWatchParams {
+    /// Variable name as listed by [`AgentCommand::DebugVariables`].
+    pub name: String,
+    /// `false` removes the watch. Defaults to `true`.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A procedure + offset pair: a breakpoint, or where the VM is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DebugLocationPayload {
+    pub procedure: String,
+    pub offset: u32,
+}
+
+/// Response payload for [`AgentCommand::DebugStatus`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugStatusResponse {
+    /// Which VM answered (`"sce"`, `"angelscript"`, `"lua"`).
+    pub vm: String,
+    /// `true` while the VM is held by the debugger.
+    pub stopped: bool,
+    /// `true` for VMs that can only hold at their next yield (Lua 5.0,
+    /// whose debug hooks can't yield): the script may run past
+    /// `location` before it actually stops.
+    #[serde(default)]
+    pub deferred_stops: bool,
+    /// Why the VM stopped, e.g. `"breakpoint"` or
+    /// `"watch $-1234 changed: 0 -> 1"`. `None` while running.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Where the VM is (or stopped). `None` when no script is running.
+    #[serde(default)]
+    pub location: Option<DebugLocationPayload>,
+    #[serde(default)]
+    pub breakpoints: Vec<DebugLocationPayload>,
+    /// Watched variables with their current values.
+    #[serde(default)]
+    pub watches: Vec<NamedGlobal>,
+}
+
+/// One entry of [`DebugProceduresResponse::procedures`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DebugProcedurePayload {
+    pub name: String,
+    /// Numeric id for VMs that have one (SCE procedure ids).
+    #[serde(default)]
+    pub id: Option<u32>,
+}
+
+/// Response payload for [`AgentCommand::DebugProcedures`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugProceduresResponse {
+    pub procedures: Vec<DebugProcedurePayload>,
+}
+
+/// Response payload for [`AgentCommand::DebugVariables`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugVariablesResponse {
+    pub variables: Vec<NamedGlobal>,
+}
@@ -114,6 +114,8 @@ impl AgentSession for NullAgentSession {
             fps: 0.0,
             dt: 0.0,
             inventory: Vec::new(),
+            equipment: Vec::new(),
+            formulas: Vec::new(),
             world_map_open: false,
             debug_camera: false,
             camera_eye: [0.0; 3],
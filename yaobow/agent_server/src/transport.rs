@@ -249,6 +249,9 @@ fn handle_request(
         (Method::Get, "/v1/scene/triggers") => Ok(AgentCommand::GetSceneTriggers),
         (Method::Get, "/v1/scene/objects") => Ok(AgentCommand::GetSceneObjects),
         (Method::Get, "/v1/perf") => Ok(AgentCommand::GetPerfMetrics),
+        (Method::Get, "/v1/debug/status") => Ok(AgentCommand::DebugStatus),
+        (Method::Get, "/v1/debug/procedures") => Ok(AgentCommand::DebugProcedures),
+        (Method::Get, "/v1/debug/variables") => Ok(AgentCommand::DebugVariables),
         (Method::Get, url_str) if url_str.starts_with("/v1/script/globals") => {
             parse_script_globals_query(url_str)
                 .map(AgentCommand::GetScriptGlobals)
@@ -477,6 +480,15 @@ fn parse_post_command(url: &str, req: &mut Request) -> Result<AgentCommand, Agen
         "/v1/menu/status" => {
             AgentCommand::SetStatusMenu(parse::<crate::protocol::StatusMenuParams>(&body)?)
         }
+        "/v1/debug/breakpoint" => {
+            AgentCommand::DebugBreakpoint(parse::<crate::protocol::DebugBreakpointParams>(&body)?)
+        }
+        "/v1/debug/watch" => {
+            AgentCommand::DebugWatch(parse::<crate::protocol::DebugWatchParams>(&body)?)
+        }
+        "/v1/debug/pause" => AgentCommand::DebugPause,
+        "/v1/debug/continue" => AgentCommand::DebugContinue,
+        "/v1/debug/step" => AgentCommand::DebugStep,
         _ => {
             return Err(AgentError::bad_request(format!(
                 "unknown POST route: {url}"
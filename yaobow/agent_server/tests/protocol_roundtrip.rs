@@ -91,6 +91,21 @@ fn every_command_roundtrips() {
             eye: [10.0, 20.0, 30.0],
             target: [1.0, 2.0, 3.0],
         }),
+        AgentCommand::DebugStatus,
+        AgentCommand::DebugProcedures,
+        AgentCommand::DebugVariables,
+        AgentCommand::DebugBreakpoint(agent_server::protocol::DebugBreakpointParams {
+            procedure: "q01_01_main".into(),
+            offset: 0x12,
+            enabled: true,
+        }),
+        AgentCommand::DebugWatch(agent_server::protocol::DebugWatchParams {
+            name: "$-1234".into(),
+            enabled: false,
+        }),
+        AgentCommand::DebugPause,
+        AgentCommand::DebugContinue,
+        AgentCommand::DebugStep,
     ];
     for c in &cases {
         roundtrip_command(c);
@@ -115,6 +130,7 @@ fn every_response_roundtrips() {
                 mp: 80,
                 max_mp: 100,
                 in_team: true,
+                magic: vec![12, 40],
             }],
             money: 1234,
             quest_percentage: 33,
@@ -132,6 +148,8 @@ fn every_response_roundtrips() {
             fps: 60.0,
             dt: 0.0167,
             inventory: vec![agent_server::protocol::InventoryEntry { id: 101, count: 3 }],
+            equipment: vec![agent_server::protocol::InventoryEntry { id: 7, count: 1 }],
+            formulas: vec![3],
             world_map_open: true,
             debug_camera: true,
             camera_eye: [10.0, 20.0, 30.0],
@@ -216,6 +234,36 @@ fn every_response_roundtrips() {
                 },
             ],
         }),
+        AgentResponse::DebugStatus(agent_server::protocol::DebugStatusResponse {
+            vm: "sce".into(),
+            stopped: true,
+            deferred_stops: false,
+            reason: Some("breakpoint".into()),
+            location: Some(agent_server::protocol::DebugLocationPayload {
+                procedure: "q01_01_main".into(),
+                offset: 0x12,
+            }),
+            breakpoints: vec![agent_server::protocol::DebugLocationPayload {
+                procedure: "q01_01_main".into(),
+                offset: 0x12,
+            }],
+            watches: vec![agent_server::protocol::NamedGlobal {
+                name: "$-1234".into(),
+                value: serde_json::json!(1),
+            }],
+        }),
+        AgentResponse::DebugProcedures(agent_server::protocol::DebugProceduresResponse {
+            procedures: vec![agent_server::protocol::DebugProcedurePayload {
+                name: "q01_01_main".into(),
+                id: Some(1001),
+            }],
+        }),
+        AgentResponse::DebugVariables(agent_server::protocol::DebugVariablesResponse {
+            variables: vec![agent_server::protocol::NamedGlobal {
+                name: "g12".into(),
+                value: serde_json::json!(-3),
+            }],
+        }),
         AgentResponse::Error(AgentError {
             kind: AgentErrorKind::Conflict,
             message: "step while running".into(),
@@ -235,3 +283,18 @@ fn unknown_response_variant_via_tag_layout_is_rejected() {
     let s = err.to_string();
     assert!(s.contains("unknown variant"), "got: {s}");
 }
+
+#[test]
+fn debug_params_default_to_enabled_entry_breakpoints() {
+    let bp: agent_server::protocol::DebugBreakpointParams =
+        serde_json::from_str(r#"{"procedure":"main"}"#).unwrap();
+    assert_eq!(bp.offset, 0);
+    assert!(bp.enabled);
+
+    let watch: agent_server::protocol::DebugWatchParams =
+        serde_json::from_str(r#"{"name":"g3"}"#).unwrap();
+    assert!(watch.enabled);
+
+    assert!(AgentCommand::DebugStep.is_debug());
+    assert!(!AgentCommand::GetState.is_debug());
+}
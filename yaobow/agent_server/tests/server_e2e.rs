@@ -235,6 +235,42 @@ fn load_route_is_the_single_load_endpoint() {
     assert!(matches!(resp, AgentResponse::Error(_)), "got {resp:?}");
 }
 
+#[test]
+fn debug_routes_reach_the_worker() {
+    let (queue, consumer) = AgentCommandQueue::new();
+    let server = AgentServer::start(AgentServerConfig::loopback(0), &queue, None)
+        .expect("start agent server");
+    let addr = server.local_addr();
+    let _worker = spawn_worker(consumer);
+    wait_for_server(&addr);
+
+    for (method, url, body) in [
+        ("GET", "/v1/debug/status", ""),
+        ("GET", "/v1/debug/procedures", ""),
+        ("GET", "/v1/debug/variables", ""),
+        (
+            "POST",
+            "/v1/debug/breakpoint",
+            r#"{"procedure":"main","offset":4}"#,
+        ),
+        (
+            "POST",
+            "/v1/debug/watch",
+            r#"{"name":"g3","enabled":false}"#,
+        ),
+        ("POST", "/v1/debug/pause", ""),
+        ("POST", "/v1/debug/continue", ""),
+        ("POST", "/v1/debug/step", ""),
+    ] {
+        let (status, resp_body) = http_request(&addr, method, url, body);
+        assert_eq!(status, 200, "{method} {url}: body={resp_body}");
+    }
+
+    // A breakpoint needs a procedure.
+    let (status, body) = http_request(&addr, "POST", "/v1/debug/breakpoint", "");
+    assert_eq!(status, 400, "body={body}");
+}
+
 #[test]
 fn unknown_route_returns_error() {
     let (queue, _consumer) = AgentCommandQueue::new();
@@ -17,7 +17,8 @@ use std::thread;
 use std::time::Duration;
 
 use agent_server::protocol::{
-    AgentCommand, AgentResponse, TraceDrainResponse, TraceEventKindPayload, TraceEventPayload,
+    AgentCommand, AgentResponse, TraceBranchKind, TraceDrainResponse, TraceEventKindPayload,
+    TraceEventPayload, TraceGlobalScope,
 };
 use agent_server::{
     AgentCommandConsumer, AgentCommandQueue, AgentServer, AgentServerConfig, AgentTraceSink,
@@ -205,3 +206,55 @@ fn trace_drain_reports_dropped_when_ring_wraps() {
     // Only the two most-recently pushed events survive in a cap-2 ring.
     assert_eq!(drain.events.len(), 2);
 }
+
+#[test]
+fn sce_and_lua_event_kinds_keep_their_wire_shape() {
+    let (queue, consumer) = AgentCommandQueue::new();
+    let sink = Arc::new(AgentTraceSink::with_default_capacity());
+    let _worker = spawn_trace_worker(consumer, sink.clone());
+    let server = AgentServer::start(AgentServerConfig::loopback(0), &queue, None)
+        .expect("start agent server");
+    let addr = server.local_addr();
+
+    let (_, _) = http_request(&addr, "POST", "/v1/script/trace/start", "{}");
+    for kind in [
+        TraceEventKindPayload::Command {
+            fn_name: "NewGame".into(),
+            pc: 12,
+            name: "npc.Create".into(),
+            args: vec!["101".into(), "\"lin\"".into()],
+        },
+        TraceEventKindPayload::GlobalWrite {
+            fn_name: "q01_01_main".into(),
+            pc: 0x40,
+            scope: TraceGlobalScope::Local,
+            slot: 3,
+            value: 1,
+        },
+        TraceEventKindPayload::Branch {
+            fn_name: "q01_01_main".into(),
+            pc: 0x48,
+            branch: TraceBranchKind::TestGoto,
+            operand: 0,
+            offset: 0x80,
+            taken: true,
+        },
+    ] {
+        sink.push(TraceEventPayload { seq: 0, kind });
+    }
+
+    let (status, body) = http_request(&addr, "GET", "/v1/script/trace/drain?after_seq=0", "");
+    assert_eq!(status, 200, "drain body: {body}");
+    assert!(body.contains(r#""type":"command""#), "body: {body}");
+    assert!(body.contains(r#""scope":"local""#), "body: {body}");
+    assert!(body.contains(r#""branch":"test_goto""#), "body: {body}");
+
+    let drain = parse_drain(&body);
+    match &drain.events[0].kind {
+        TraceEventKindPayload::Command { name, args, .. } => {
+            assert_eq!(name, "npc.Create");
+            assert_eq!(args.len(), 2);
+        }
+        other => panic!("unexpected kind: {:?}", other),
+    }
+}
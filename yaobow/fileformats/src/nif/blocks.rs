@@ -8,9 +8,14 @@ use common::read_ext::ReadExt;
 use dashmap::DashMap;
 use yaobow_macros::NiObjectType;
 
-use crate::{nif::NiType, utils::SizedString};
+use crate::{
+    nif::NiType,
+    utils::{SizedString, to_gbk_string},
+};
 
-use super::{Matrix33, NiObject, Vector3};
+use super::{
+    Color3, Color4, Matrix22, Matrix33, NiObject, NiTransform, Quaternion, TexCoord, Vector3,
+};
 
 lazy_static::lazy_static! {
     static ref NI_OBJECT_TYPES: DashMap<&'static str, &'static NiType> = init_ni_object_types();
@@ -26,6 +31,22 @@ fn init_ni_object_types() -> DashMap<&'static str, &'static NiType> {
     add(&NiTypeNiNode);
     add(&NiTypeNiMesh);
     add(&NiTypeNiDataStream);
+    add(&NiTypeNiMaterialProperty);
+    add(&NiTypeNiAlphaProperty);
+    add(&NiTypeNiTexturingProperty);
+    add(&NiTypeNiSourceTexture);
+    add(&NiTypeNiSkinningMeshModifier);
+    add(&NiTypeNiTransformController);
+    add(&NiTypeNiTransformInterpolator);
+    add(&NiTypeNiTransformData);
+    add(&NiTypeNiKeyframeData);
+    add(&NiTypeNiTriShape);
+    add(&NiTypeNiTriStrips);
+    add(&NiTypeNiTriShapeData);
+    add(&NiTypeNiTriStripsData);
+    add(&NiTypeNiSkinInstance);
+    add(&NiTypeNiSkinData);
+    add(&NiTypeNiStringPalette);
 
     map
 }
@@ -67,15 +88,33 @@ impl BinRead for NiBlocks {
                 .get(name)
                 .map_or(&NiTypeUnknownBlock, |v| v.value());
 
-            let data = reader.read_u8_vec(size as usize)?;
+            let pos = reader.stream_position()?;
+            let mut data = Cursor::new(reader.read_u8_vec(size as usize)?);
             let block = (ty.read)(
-                &mut Cursor::new(data),
+                &mut data,
                 NiObjectArgs {
                     block_size: size,
+                    version: args.version,
                     generic_value1,
                     generic_value2,
                 },
             )?;
+
+            // Writing only emits the decoded fields, so bytes a decoder
+            // skipped would be lost on the way back out.
+            if data.position() != size as u64 {
+                return Err(binrw::Error::AssertFail {
+                    pos,
+                    message: format!(
+                        "block {} ({}) decoded {} of {} bytes",
+                        i,
+                        name,
+                        data.position(),
+                        size
+                    ),
+                });
+            }
+
             blocks.push(block);
         }
 
@@ -96,7 +135,7 @@ impl BinWrite for NiBlocks {
             let mut w = Cursor::new(vec![]);
             (block.ni_type().write)(block.as_ref(), &mut w)?;
 
-            writer.write(w.get_ref())?;
+            writer.write_all(w.get_ref())?;
         }
 
         Ok(())
@@ -105,6 +144,7 @@ impl BinWrite for NiBlocks {
 
 #[derive(Clone, Default, NamedArgs)]
 pub struct NiBlocksArgs<'a> {
+    pub version: u32,
     pub block_sizes: &'a [u32],
     pub block_types: &'a [SizedString],
     pub block_type_index: &'a [u16],
@@ -113,13 +153,15 @@ pub struct NiBlocksArgs<'a> {
 #[derive(Clone, Default, NamedArgs)]
 pub struct NiObjectArgs {
     pub block_size: u32,
+    /// Header version, e.g. `0x14060000` for 20.6.0.0.
+    pub version: u32,
     pub generic_value1: u32,
     pub generic_value2: u32,
 }
 
 #[derive(Debug, NiObjectType)]
 pub struct UnknownBlock {
-    data: Vec<u8>,
+    pub data: Vec<u8>,
 }
 
 impl ReadEndian for UnknownBlock {
@@ -153,7 +195,7 @@ impl BinWrite for UnknownBlock {
         _: binrw::Endian,
         _: Self::Args<'_>,
     ) -> binrw::BinResult<()> {
-        let _ = writer.write(&self.data)?;
+        writer.write_all(&self.data)?;
         Ok(())
     }
 }
@@ -162,30 +204,30 @@ impl BinWrite for UnknownBlock {
 #[brw(little)]
 #[derive(Debug)]
 pub struct NiObjectNET {
-    name: u32,
-    num_extra_data: u32,
+    pub name: u32,
+    pub num_extra_data: u32,
 
     #[br(count = num_extra_data)]
-    extra_data: Vec<u32>,
+    pub extra_data: Vec<u32>,
 
-    controller: u32,
+    pub controller: u32,
 }
 
 #[binrw]
 #[brw(little)]
 #[derive(Debug)]
 pub struct NiAVObject {
-    object_net: NiObjectNET,
-    flags: u16,
-    translation: Vector3,
-    rotation: Matrix33,
-    scale: f32,
-    num_properties: u32,
+    pub object_net: NiObjectNET,
+    pub flags: u16,
+    pub translation: Vector3,
+    pub rotation: Matrix33,
+    pub scale: f32,
+    pub num_properties: u32,
 
     #[br(count = num_properties)]
-    properties: Vec<u32>,
+    pub properties: Vec<u32>,
 
-    collision_object: i32,
+    pub collision_object: i32,
 }
 
 #[binrw]
@@ -193,32 +235,32 @@ pub struct NiAVObject {
 #[br(import_raw(_args: NiObjectArgs))]
 #[derive(Debug, NiObjectType)]
 pub struct NiNode {
-    av_object: NiAVObject,
-    num_children: u32,
+    pub av_object: NiAVObject,
+    pub num_children: u32,
 
     #[br(count = num_children)]
-    children: Vec<u32>,
+    pub children: Vec<u32>,
 
-    num_effects: u32,
+    pub num_effects: u32,
 
     #[br(count = num_effects)]
-    effects: Vec<u32>,
+    pub effects: Vec<u32>,
 }
 
 #[binrw]
 #[brw(little)]
 #[derive(Debug)]
 pub struct MaterialData {
-    num_materials: u32,
+    pub num_materials: u32,
 
     #[br(count = num_materials)]
-    material_names: Vec<u32>,
+    pub material_names: Vec<u32>,
 
     #[br(count = num_materials)]
-    material_extra_data: Vec<i32>,
+    pub material_extra_data: Vec<i32>,
 
-    active_material: i32,
-    always_update: u8,
+    pub active_material: i32,
+    pub always_update: u8,
 }
 
 #[binrw]
@@ -226,13 +268,13 @@ pub struct MaterialData {
 #[br(import_raw(_args: NiObjectArgs))]
 #[derive(Debug, NiObjectType)]
 pub struct NiRenderObject {
-    av_object: NiAVObject,
-    material_data: MaterialData,
+    pub av_object: NiAVObject,
+    pub material_data: MaterialData,
 }
 
 #[binrw]
 #[brw(little)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MeshPrimitiveType(pub u32);
 impl MeshPrimitiveType {
     pub const MESH_PRIMITIVE_TRIANGLES: Self = Self(0);
@@ -248,33 +290,33 @@ impl MeshPrimitiveType {
 #[br(import_raw(_args: NiObjectArgs))]
 #[derive(Debug, NiObjectType)]
 pub struct NiBound {
-    center: Vector3,
-    radius: f32,
+    pub center: Vector3,
+    pub radius: f32,
 }
 
 #[binrw]
 #[brw(little)]
 #[derive(Debug)]
 pub struct SemanticData {
-    name: i32,
-    index: u32,
+    pub name: i32,
+    pub index: u32,
 }
 
 #[binrw]
 #[brw(little)]
 #[derive(Debug)]
 pub struct DataStreamRef {
-    stream: i32,
-    per_instance: u8,
-    num_sub_meshes: u16,
+    pub stream: i32,
+    pub per_instance: u8,
+    pub num_sub_meshes: u16,
 
     #[br(count = num_sub_meshes)]
-    sub_mesh_to_region_map: Vec<u16>,
+    pub sub_mesh_to_region_map: Vec<u16>,
 
-    num_components: u32,
+    pub num_components: u32,
 
     #[br(count = num_components)]
-    semantic_data: Vec<SemanticData>,
+    pub semantic_data: Vec<SemanticData>,
 }
 
 #[binrw]
@@ -282,25 +324,25 @@ pub struct DataStreamRef {
 #[br(import_raw(_args: NiObjectArgs))]
 #[derive(Debug, NiObjectType)]
 pub struct NiMesh {
-    render_object: NiRenderObject,
-    primitive_type: MeshPrimitiveType,
-    num_sub_meshes: u16,
-    instancing: u8,
-    bound: NiBound,
-    num_data_streams: u32,
+    pub render_object: NiRenderObject,
+    pub primitive_type: MeshPrimitiveType,
+    pub num_sub_meshes: u16,
+    pub instancing: u8,
+    pub bound: NiBound,
+    pub num_data_streams: u32,
 
     #[br(count = num_data_streams)]
-    data_stream_ref: Vec<DataStreamRef>,
+    pub data_stream_ref: Vec<DataStreamRef>,
 
-    num_modifiers: u32,
+    pub num_modifiers: u32,
 
     #[br(count = num_modifiers)]
-    modifiers: Vec<i32>,
+    pub modifiers: Vec<i32>,
 }
 
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct DataStreamUsage(pub u32);
 impl DataStreamUsage {
     pub const USAGE_VERTEX_INDEX: Self = Self(0);
@@ -335,9 +377,24 @@ impl CloningBehavior {
 
 #[binrw]
 #[brw(little)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ComponentFormat(pub u32);
 impl ComponentFormat {
+    /// Number of scalars in one element (the `_N` suffix).
+    pub fn count(&self) -> usize {
+        ((self.0 >> 16) & 0xff) as usize
+    }
+
+    /// Size of one scalar in bytes.
+    pub fn scalar_size(&self) -> usize {
+        ((self.0 >> 8) & 0xff) as usize
+    }
+
+    /// Size of one element in bytes.
+    pub fn size(&self) -> usize {
+        self.count() * self.scalar_size()
+    }
+
     pub const UNKNOWN: Self = Self(0x00000000);
     pub const INT8_1: Self = Self(0x00010101);
     pub const INT8_2: Self = Self(0x00020102);
@@ -407,8 +464,8 @@ impl ComponentFormat {
 #[brw(little)]
 #[derive(Debug)]
 pub struct Region {
-    start_index: u32,
-    num_indices: u32,
+    pub start_index: u32,
+    pub num_indices: u32,
 }
 
 #[binrw]
@@ -416,26 +473,662 @@ pub struct Region {
 #[br(import_raw(args: NiObjectArgs))]
 #[derive(Debug, NiObjectType)]
 pub struct NiDataStream {
+    /// Carried in the block type name (`NiDataStream\x01<usage>\x01<access>`),
+    /// not in the block body.
     #[br(calc(DataStreamUsage(args.generic_value1)))]
-    usage: DataStreamUsage,
+    #[bw(ignore)]
+    pub usage: DataStreamUsage,
 
     #[br(calc(DataStreamAccess(args.generic_value2)))]
-    access: DataStreamAccess,
+    #[bw(ignore)]
+    pub access: DataStreamAccess,
 
-    num_bytes: u32,
-    cloning_behavior: CloningBehavior,
-    num_regions: u32,
+    pub num_bytes: u32,
+    pub cloning_behavior: CloningBehavior,
+    pub num_regions: u32,
 
     #[br(count = num_regions)]
-    regions: Vec<Region>,
+    pub regions: Vec<Region>,
 
-    num_components: u32,
+    pub num_components: u32,
 
     #[br(count = num_components)]
-    component_formats: Vec<ComponentFormat>,
+    pub component_formats: Vec<ComponentFormat>,
 
     #[br(count = num_bytes)]
-    data: Vec<u8>,
+    pub data: Vec<u8>,
+
+    pub streamable: u8,
+}
+
+/// Base of every property block: `NiProperty` adds nothing to
+/// `NiObjectNET` from 20.x on.
+pub type NiProperty = NiObjectNET;
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiMaterialProperty {
+    pub object_net: NiProperty,
+    pub ambient: Color3,
+    pub diffuse: Color3,
+    pub specular: Color3,
+    pub emissive: Color3,
+    pub glossiness: f32,
+    pub alpha: f32,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiAlphaProperty {
+    pub object_net: NiProperty,
+    pub flags: u16,
+    pub threshold: u8,
+}
+
+impl NiAlphaProperty {
+    pub fn blend_enabled(&self) -> bool {
+        self.flags & 0x0001 != 0
+    }
+
+    pub fn test_enabled(&self) -> bool {
+        self.flags & 0x0200 != 0
+    }
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct TexTransform {
+    pub translation: TexCoord,
+    pub scale: TexCoord,
+    pub rotation: f32,
+    pub transform_method: u32,
+    pub center: TexCoord,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import(version: u32))]
+#[derive(Debug)]
+pub struct TexDesc {
+    /// `NiSourceTexture` block.
+    pub source: i32,
+    /// Clamp mode (bits 12..14), filter mode (bits 8..12) and UV set
+    /// (bits 0..8).
+    pub flags: u16,
+
+    /// Stored from 20.5.0.4 on.
+    #[br(if(version >= 0x14050004))]
+    pub max_anisotropy: Option<u16>,
+
+    pub has_texture_transform: u8,
+
+    #[br(if(has_texture_transform != 0))]
+    pub texture_transform: Option<TexTransform>,
+}
+
+impl TexDesc {
+    pub fn uv_set(&self) -> u32 {
+        (self.flags & 0xff) as u32
+    }
+}
+
+/// A texture slot: a presence flag followed by the slot's [`TexDesc`].
+#[binrw]
+#[brw(little)]
+#[br(import(version: u32))]
+#[derive(Debug)]
+pub struct TexSlot {
+    pub has_texture: u8,
+
+    #[br(if(has_texture != 0), args(version))]
+    pub texture: Option<TexDesc>,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import(version: u32))]
+#[derive(Debug)]
+pub struct BumpMap {
+    #[br(args(version))]
+    pub texture: TexDesc,
+    pub luma_scale: f32,
+    pub luma_offset: f32,
+    pub matrix: Matrix22,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import(version: u32))]
+#[derive(Debug)]
+pub struct ParallaxMap {
+    #[br(args(version))]
+    pub texture: TexDesc,
+    pub offset: f32,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import(version: u32))]
+#[derive(Debug)]
+pub struct ShaderTexSlot {
+    pub has_map: u8,
+
+    #[br(if(has_map != 0), args(version))]
+    pub map: Option<ShaderMap>,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import(version: u32))]
+#[derive(Debug)]
+pub struct ShaderMap {
+    #[br(args(version))]
+    pub texture: TexDesc,
+    pub map_id: u32,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiTexturingProperty {
+    pub object_net: NiProperty,
+    pub flags: u16,
+    pub texture_count: u32,
+
+    #[br(args(args.version))]
+    pub base_texture: TexSlot,
+    #[br(args(args.version))]
+    pub dark_texture: TexSlot,
+    #[br(args(args.version))]
+    pub detail_texture: TexSlot,
+    #[br(args(args.version))]
+    pub gloss_texture: TexSlot,
+    #[br(args(args.version))]
+    pub glow_texture: TexSlot,
+
+    pub has_bump_map: u8,
+
+    #[br(if(has_bump_map != 0), args(args.version))]
+    pub bump_map: Option<BumpMap>,
+
+    #[br(args(args.version))]
+    pub normal_texture: TexSlot,
+
+    pub has_parallax_map: u8,
+
+    #[br(if(has_parallax_map != 0), args(args.version))]
+    pub parallax_map: Option<ParallaxMap>,
+
+    /// Up to four decal slots; slot `n` is present when
+    /// `texture_count > 8 + n`.
+    #[br(
+        count = texture_count.saturating_sub(8).min(4),
+        args { inner: (args.version,) }
+    )]
+    pub decal_textures: Vec<TexSlot>,
+
+    pub num_shader_textures: u32,
+
+    #[br(count = num_shader_textures, args { inner: (args.version,) })]
+    pub shader_textures: Vec<ShaderTexSlot>,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiSourceTexture {
+    pub object_net: NiObjectNET,
+    pub use_external: u8,
+    /// Header string holding the texture's file name.
+    pub file_name: u32,
+    /// Embedded `NiPixelData` block, when not external.
+    pub pixel_data: i32,
+    pub pixel_layout: u32,
+    pub use_mipmaps: u32,
+    pub alpha_format: u32,
+    pub is_static: u8,
+    pub direct_render: u8,
+
+    /// Stored from 20.2.0.4 on.
+    #[br(if(args.version >= 0x14020004))]
+    pub persist_render_data: Option<u8>,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct NiMeshModifier {
+    pub num_submit_points: u32,
+
+    #[br(count = num_submit_points)]
+    pub submit_points: Vec<u16>,
+
+    pub num_complete_points: u32,
+
+    #[br(count = num_complete_points)]
+    pub complete_points: Vec<u16>,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiSkinningMeshModifier {
+    pub modifier: NiMeshModifier,
+    /// Bit 1: software skinning; bit 2: per-bone bounds follow.
+    pub flags: u16,
+    pub skeleton_root: i32,
+    pub skeleton_transform: NiTransform,
+    pub num_bones: u32,
+
+    #[br(count = num_bones)]
+    pub bones: Vec<i32>,
+
+    /// Bind-pose transform of each bone relative to the skeleton root.
+    #[br(count = num_bones)]
+    pub bone_transforms: Vec<NiTransform>,
+
+    #[br(if(flags & 2 != 0), count = num_bones)]
+    pub bone_bounds: Vec<NiBound>,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct NiTimeController {
+    pub next_controller: i32,
+    pub flags: u16,
+    pub frequency: f32,
+    pub phase: f32,
+    pub start_time: f32,
+    pub stop_time: f32,
+    pub target: i32,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiTransformController {
+    pub controller: NiTimeController,
+    pub interpolator: i32,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiTransformInterpolator {
+    pub translation: Vector3,
+    pub rotation: Quaternion,
+    pub scale: f32,
+    pub data: i32,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyType(pub u32);
+impl KeyType {
+    pub const LINEAR: Self = Self(1);
+    pub const QUADRATIC: Self = Self(2);
+    pub const TBC: Self = Self(3);
+    pub const XYZ_ROTATION: Self = Self(4);
+    pub const CONST: Self = Self(5);
+}
 
-    streamable: u8,
+#[binrw]
+#[brw(little)]
+#[br(import(key_type: KeyType))]
+#[derive(Debug)]
+pub struct FloatKey {
+    pub time: f32,
+    pub value: f32,
+
+    #[br(if(key_type == KeyType::QUADRATIC))]
+    pub tangents: Option<[f32; 2]>,
+
+    #[br(if(key_type == KeyType::TBC))]
+    pub tbc: Option<[f32; 3]>,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import(key_type: KeyType))]
+#[derive(Debug)]
+pub struct Vector3Key {
+    pub time: f32,
+    pub value: Vector3,
+
+    #[br(if(key_type == KeyType::QUADRATIC))]
+    pub tangents: Option<[Vector3; 2]>,
+
+    #[br(if(key_type == KeyType::TBC))]
+    pub tbc: Option<[f32; 3]>,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import(key_type: KeyType))]
+#[derive(Debug)]
+pub struct QuatKey {
+    pub time: f32,
+    pub value: Quaternion,
+
+    #[br(if(key_type == KeyType::TBC))]
+    pub tbc: Option<[f32; 3]>,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct FloatKeyGroup {
+    pub num_keys: u32,
+
+    #[br(if(num_keys > 0))]
+    pub interpolation: Option<KeyType>,
+
+    #[br(count = num_keys, args { inner: (interpolation.unwrap_or(KeyType::LINEAR),) })]
+    pub keys: Vec<FloatKey>,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct Vector3KeyGroup {
+    pub num_keys: u32,
+
+    #[br(if(num_keys > 0))]
+    pub interpolation: Option<KeyType>,
+
+    #[br(count = num_keys, args { inner: (interpolation.unwrap_or(KeyType::LINEAR),) })]
+    pub keys: Vec<Vector3Key>,
+}
+
+/// Rotation, translation and scale curves shared by [`NiKeyframeData`]
+/// and [`NiTransformData`].
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct KeyframeData {
+    pub num_rotation_keys: u32,
+
+    #[br(if(num_rotation_keys > 0))]
+    pub rotation_type: Option<KeyType>,
+
+    #[br(
+        if(rotation_type != Some(KeyType::XYZ_ROTATION)),
+        count = num_rotation_keys,
+        args { inner: (rotation_type.unwrap_or(KeyType::LINEAR),) }
+    )]
+    pub quaternion_keys: Vec<QuatKey>,
+
+    /// Per-axis Euler rotation curves, used instead of quaternion keys
+    /// when `rotation_type` is [`KeyType::XYZ_ROTATION`].
+    #[br(if(rotation_type == Some(KeyType::XYZ_ROTATION)))]
+    pub xyz_rotations: Option<[FloatKeyGroup; 3]>,
+
+    pub translations: Vector3KeyGroup,
+    pub scales: FloatKeyGroup,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiKeyframeData {
+    pub keyframes: KeyframeData,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiTransformData {
+    pub keyframes: KeyframeData,
+}
+
+/// Base of `NiTriShape` / `NiTriStrips`, the pre-`NiMesh` geometry.
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct NiGeometry {
+    pub av_object: NiAVObject,
+    pub data: i32,
+    pub skin_instance: i32,
+    pub material_data: MaterialData,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiTriShape {
+    pub geometry: NiGeometry,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiTriStrips {
+    pub geometry: NiGeometry,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import(num_vertices: u16))]
+#[derive(Debug)]
+pub struct UvSet {
+    #[br(count = num_vertices)]
+    pub coords: Vec<TexCoord>,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct NiGeometryData {
+    pub group_id: i32,
+    pub num_vertices: u16,
+    pub keep_flags: u8,
+    pub compress_flags: u8,
+    pub has_vertices: u8,
+
+    #[br(if(has_vertices != 0), count = num_vertices)]
+    pub vertices: Vec<Vector3>,
+
+    /// UV set count (bits 0..6) and whether tangents follow the
+    /// normals (bit 12).
+    pub data_flags: u16,
+    pub has_normals: u8,
+
+    #[br(if(has_normals != 0), count = num_vertices)]
+    pub normals: Vec<Vector3>,
+
+    #[br(if(has_normals != 0 && data_flags & 0x1000 != 0), count = num_vertices)]
+    pub tangents: Vec<Vector3>,
+
+    #[br(if(has_normals != 0 && data_flags & 0x1000 != 0), count = num_vertices)]
+    pub bitangents: Vec<Vector3>,
+
+    pub center: Vector3,
+    pub radius: f32,
+    pub has_vertex_colors: u8,
+
+    #[br(if(has_vertex_colors != 0), count = num_vertices)]
+    pub vertex_colors: Vec<Color4>,
+
+    #[br(count = data_flags & 0x3f, args { inner: (num_vertices,) })]
+    pub uv_sets: Vec<UvSet>,
+
+    pub consistency_flags: u16,
+    pub additional_data: i32,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct Triangle {
+    pub v1: u16,
+    pub v2: u16,
+    pub v3: u16,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct MatchGroup {
+    pub num_vertices: u16,
+
+    #[br(count = num_vertices)]
+    pub vertex_indices: Vec<u16>,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiTriShapeData {
+    pub geometry_data: NiGeometryData,
+    pub num_triangles: u16,
+    pub num_triangle_points: u32,
+    pub has_triangles: u8,
+
+    #[br(if(has_triangles != 0), count = num_triangles)]
+    pub triangles: Vec<Triangle>,
+
+    pub num_match_groups: u16,
+
+    #[br(count = num_match_groups)]
+    pub match_groups: Vec<MatchGroup>,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import(length: u16))]
+#[derive(Debug)]
+pub struct Strip {
+    #[br(count = length)]
+    pub points: Vec<u16>,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiTriStripsData {
+    pub geometry_data: NiGeometryData,
+    pub num_triangles: u16,
+    pub num_strips: u16,
+
+    #[br(count = num_strips)]
+    pub strip_lengths: Vec<u16>,
+
+    pub has_points: u8,
+
+    #[br(
+        if(has_points != 0),
+        parse_with = binrw::helpers::args_iter(strip_lengths.clone().into_iter().map(|l| (l,)))
+    )]
+    pub strips: Vec<Strip>,
+}
+
+impl NiTriStripsData {
+    /// The strips expanded to a triangle list, skipping the degenerate
+    /// triangles used to stitch strips together.
+    pub fn triangles(&self) -> Vec<[u16; 3]> {
+        let mut triangles = vec![];
+        for strip in &self.strips {
+            for i in 2..strip.points.len() {
+                let (a, b, c) = if i % 2 == 0 {
+                    (strip.points[i - 2], strip.points[i - 1], strip.points[i])
+                } else {
+                    (strip.points[i - 1], strip.points[i - 2], strip.points[i])
+                };
+
+                if a != b && b != c && a != c {
+                    triangles.push([a, b, c]);
+                }
+            }
+        }
+
+        triangles
+    }
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiSkinInstance {
+    pub data: i32,
+    pub skin_partition: i32,
+    pub skeleton_root: i32,
+    pub num_bones: u32,
+
+    #[br(count = num_bones)]
+    pub bones: Vec<i32>,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct BoneVertData {
+    pub index: u16,
+    pub weight: f32,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import(has_vertex_weights: u8))]
+#[derive(Debug)]
+pub struct BoneData {
+    /// Mesh space to bone space in the bind pose.
+    pub skin_transform: NiTransform,
+    pub bounding_sphere_offset: Vector3,
+    pub bounding_sphere_radius: f32,
+    pub num_vertices: u16,
+
+    #[br(if(has_vertex_weights != 0), count = num_vertices)]
+    pub vertex_weights: Vec<BoneVertData>,
+}
+
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiSkinData {
+    pub skin_transform: NiTransform,
+    pub num_bones: u32,
+    pub has_vertex_weights: u8,
+
+    #[br(count = num_bones, args { inner: (has_vertex_weights,) })]
+    pub bone_list: Vec<BoneData>,
+}
+
+/// NUL-separated strings addressed by byte offset, used by controller
+/// sequences to name their targets.
+#[binrw]
+#[brw(little)]
+#[br(import_raw(_args: NiObjectArgs))]
+#[derive(Debug, NiObjectType)]
+pub struct NiStringPalette {
+    pub palette: SizedString,
+    pub length: u32,
+}
+
+impl NiStringPalette {
+    /// The string starting at byte `offset`, up to the next NUL.
+    pub fn string_at(&self, offset: u32) -> Option<String> {
+        let data = self.palette.data().get(offset as usize..)?;
+        let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        to_gbk_string(&data[..end]).ok()
+    }
 }
@@ -27,6 +27,37 @@ pub struct NifModel {
     footer: NiFooter,
 }
 
+impl NifModel {
+    pub fn header(&self) -> &NiHeader {
+        &self.header
+    }
+
+    pub fn blocks(&self) -> &[Box<dyn NiObject>] {
+        &self.blocks.0
+    }
+
+    /// Block indices of the scene roots listed in the footer.
+    pub fn roots(&self) -> &[i32] {
+        &self.footer.roots
+    }
+
+    /// The block at `index` if it is a `T`. Negative indices are the
+    /// format's null reference and resolve to `None`.
+    pub fn block<T: NiObject>(&self, index: i32) -> Option<&T> {
+        let block = self.blocks.0.get(usize::try_from(index).ok()?)?;
+        block.as_any().downcast_ref::<T>()
+    }
+
+    /// Header string table entry `index`; `0xffffffff` is the null
+    /// string.
+    pub fn string(&self, index: u32) -> Option<String> {
+        self.header
+            .strings
+            .get(index as usize)
+            .and_then(|s| s.to_string().ok())
+    }
+}
+
 impl ReadEndian for NifModel {
     const ENDIAN: binrw::meta::EndianKind = binrw::meta::EndianKind::None;
 }
@@ -43,6 +74,7 @@ impl BinRead for NifModel {
         let blocks = NiBlocks::read_args(
             reader,
             NiBlocksArgs {
+                version: header.version,
                 block_sizes: &header.block_size,
                 block_types: &header.block_types,
                 block_type_index: &header.block_type_index,
@@ -124,24 +156,479 @@ impl std::fmt::Debug for HeaderString {
 #[brw(little)]
 #[derive(Debug, Clone)]
 pub struct Vector3 {
-    x: f32,
-    y: f32,
-    z: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
 }
 
 #[binrw]
 #[brw(little)]
 #[derive(Debug, Clone)]
 pub struct Matrix33 {
-    m11: f32,
-    m21: f32,
-    m31: f32,
+    pub m11: f32,
+    pub m21: f32,
+    pub m31: f32,
+
+    pub m12: f32,
+    pub m22: f32,
+    pub m32: f32,
+
+    pub m13: f32,
+    pub m23: f32,
+    pub m33: f32,
+}
 
-    m12: f32,
-    m22: f32,
-    m32: f32,
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone)]
+pub struct Matrix22 {
+    pub m11: f32,
+    pub m21: f32,
+    pub m12: f32,
+    pub m22: f32,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone)]
+pub struct Color3 {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone)]
+pub struct Color4 {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone)]
+pub struct TexCoord {
+    pub u: f32,
+    pub v: f32,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone)]
+pub struct Quaternion {
+    pub w: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/// Rotation, translation and uniform scale, in the order the format
+/// stores them.
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone)]
+pub struct NiTransform {
+    pub rotation: Matrix33,
+    pub translation: Vector3,
+    pub scale: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::{BinRead, BinWrite};
+
+    use super::{
+        NifModel,
+        blocks::{
+            ComponentFormat, DataStreamUsage, KeyType, NiDataStream, NiMaterialProperty,
+            NiSkinData, NiSkinInstance, NiSourceTexture, NiStringPalette, NiTexturingProperty,
+            NiTransformData, NiTriShapeData, NiTriStripsData,
+        },
+    };
+
+    #[derive(Default)]
+    struct Bytes(Vec<u8>);
+
+    impl Bytes {
+        fn u8(mut self, v: u8) -> Self {
+            self.0.push(v);
+            self
+        }
+
+        fn u16s(mut self, v: &[u16]) -> Self {
+            v.iter()
+                .for_each(|v| self.0.extend_from_slice(&v.to_le_bytes()));
+            self
+        }
+
+        fn u32(mut self, v: u32) -> Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        fn i32(self, v: i32) -> Self {
+            self.u32(v as u32)
+        }
+
+        fn f32s(mut self, v: &[f32]) -> Self {
+            v.iter()
+                .for_each(|f| self.0.extend_from_slice(&f.to_le_bytes()));
+            self
+        }
+
+        fn str(mut self, s: &str) -> Self {
+            self = self.u32(s.len() as u32);
+            self.0.extend_from_slice(s.as_bytes());
+            self
+        }
+
+        /// Identity rotation, zero translation, unit scale.
+        fn identity_transform(self) -> Self {
+            self.f32s(&[1., 0., 0., 0., 1., 0., 0., 0., 1.])
+                .f32s(&[0., 0., 0.])
+                .f32s(&[1.])
+        }
+    }
+
+    const V20_3: u32 = 0x14030009;
+    const V20_6: u32 = 0x14060000;
+
+    /// A 20.6 file holding `blocks` (type name, body) with the first one
+    /// as the only root.
+    fn nif(blocks: &[(&str, Vec<u8>)], strings: &[&str]) -> Vec<u8> {
+        nif_version(V20_6, blocks, strings)
+    }
 
-    m13: f32,
-    m23: f32,
-    m33: f32,
+    fn nif_version(version: u32, blocks: &[(&str, Vec<u8>)], strings: &[&str]) -> Vec<u8> {
+        let mut types: Vec<&str> = vec![];
+        let mut type_index = vec![];
+        for (name, _) in blocks {
+            let index = types.iter().position(|t| t == name).unwrap_or_else(|| {
+                types.push(name);
+                types.len() - 1
+            });
+            type_index.push(index as u16);
+        }
+
+        let [d, c, b, a] = version.to_le_bytes();
+        let header = format!("Gamebryo File Format, Version {a}.{b}.{c}.{d}\n");
+        let mut buf = Bytes(header.into_bytes())
+            .u32(version)
+            .u8(1)
+            .u32(0)
+            .u32(blocks.len() as u32)
+            .u16s(&[types.len() as u16]);
+        for name in &types {
+            buf = buf.str(name);
+        }
+        buf = buf.u16s(&type_index);
+        for (_, body) in blocks {
+            buf = buf.u32(body.len() as u32);
+        }
+        let max_len = strings.iter().map(|s| s.len()).max().unwrap_or(0);
+        buf = buf.u32(strings.len() as u32).u32(max_len as u32);
+        for string in strings {
+            buf = buf.str(string);
+        }
+        buf = buf.u32(0);
+
+        for (_, body) in blocks {
+            buf.0.extend_from_slice(body);
+        }
+
+        buf.u32(1).u32(0).0
+    }
+
+    fn material() -> Vec<u8> {
+        Bytes::default()
+            .u32(0)
+            .u32(0)
+            .i32(-1)
+            .f32s(&[0.5; 9])
+            .f32s(&[0.0, 0.0, 0.0, 10.0, 0.75])
+            .0
+    }
+
+    /// A texturing property whose base slot points at block 1, with
+    /// `max_anisotropy` written when `version` stores it.
+    fn texturing(version: u32) -> Vec<u8> {
+        let mut buf = Bytes::default()
+            .u32(0)
+            .u32(0)
+            .i32(-1)
+            .u16s(&[0])
+            .u32(7)
+            .u8(1)
+            .i32(1)
+            .u16s(&[0x3200]);
+        if version >= 0x14050004 {
+            buf = buf.u16s(&[4]);
+        }
+        buf.u8(0).u8(0).u8(0).u8(0).u8(0).u8(0).u8(0).u8(0).u32(0).0
+    }
+
+    fn source_texture() -> Vec<u8> {
+        Bytes::default()
+            .u32(0)
+            .u32(0)
+            .i32(-1)
+            .u8(1)
+            .u32(0)
+            .i32(-1)
+            .u32(6)
+            .u32(2)
+            .u32(3)
+            .u8(1)
+            .u8(1)
+            .u8(0)
+            .0
+    }
+
+    fn texturing_nif(version: u32) -> Vec<u8> {
+        nif_version(
+            version,
+            &[
+                ("NiTexturingProperty", texturing(version)),
+                ("NiSourceTexture", source_texture()),
+            ],
+            &["skin.dds"],
+        )
+    }
+
+    fn transform_data() -> Vec<u8> {
+        Bytes::default()
+            .u32(1)
+            .u32(KeyType::LINEAR.0)
+            .f32s(&[0.0, 1.0, 0.0, 0.0, 0.0])
+            .u32(0)
+            .u32(0)
+            .0
+    }
+
+    /// Three vertices with normals, tangents, colours and one UV set,
+    /// one triangle and one match group.
+    fn tri_shape_data() -> Vec<u8> {
+        Bytes::default()
+            .i32(0)
+            .u16s(&[3])
+            .u8(0)
+            .u8(0)
+            .u8(1)
+            .f32s(&[0., 0., 0., 1., 0., 0., 0., 1., 0.])
+            .u16s(&[0x1001])
+            .u8(1)
+            .f32s(&[0., 0., 1., 0., 0., 1., 0., 0., 1.])
+            .f32s(&[1., 0., 0., 1., 0., 0., 1., 0., 0.])
+            .f32s(&[0., 1., 0., 0., 1., 0., 0., 1., 0.])
+            .f32s(&[0.3, 0.3, 0., 0.7])
+            .u8(1)
+            .f32s(&[1.; 12])
+            .f32s(&[0., 0., 1., 0., 0., 1.])
+            .u16s(&[0])
+            .i32(-1)
+            .u16s(&[1])
+            .u32(3)
+            .u8(1)
+            .u16s(&[0, 1, 2])
+            .u16s(&[1, 2, 0, 1])
+            .0
+    }
+
+    fn tri_strips_data() -> Vec<u8> {
+        Bytes::default()
+            .i32(0)
+            .u16s(&[4])
+            .u8(0)
+            .u8(0)
+            .u8(1)
+            .f32s(&[0.; 12])
+            .u16s(&[0])
+            .u8(0)
+            .f32s(&[0., 0., 0., 1.])
+            .u8(0)
+            .u16s(&[0])
+            .i32(-1)
+            .u16s(&[2, 1, 4])
+            .u8(1)
+            .u16s(&[0, 1, 2, 3])
+            .0
+    }
+
+    fn skin_data() -> Vec<u8> {
+        Bytes::default()
+            .identity_transform()
+            .u32(1)
+            .u8(1)
+            .identity_transform()
+            .f32s(&[0., 0., 0., 2.])
+            .u16s(&[2])
+            .u16s(&[0])
+            .f32s(&[0.25])
+            .u16s(&[1])
+            .f32s(&[0.75])
+            .0
+    }
+
+    fn skin_instance() -> Vec<u8> {
+        Bytes::default().i32(3).i32(-1).i32(0).u32(1).i32(0).0
+    }
+
+    fn string_palette() -> Vec<u8> {
+        Bytes::default().str("Bip01\0Head\0").u32(11).0
+    }
+
+    fn position_stream() -> Vec<u8> {
+        Bytes::default()
+            .u32(12)
+            .u32(1)
+            .u32(1)
+            .u32(0)
+            .u32(1)
+            .u32(1)
+            .u32(ComponentFormat::FLOAT32_3.0)
+            .f32s(&[1., 2., 3.])
+            .u8(1)
+            .0
+    }
+
+    fn sample_nif() -> Vec<u8> {
+        nif(
+            &[
+                ("NiMaterialProperty", material()),
+                ("NiTransformData", transform_data()),
+            ],
+            &["Skin"],
+        )
+    }
+
+    fn geometry_nif() -> Vec<u8> {
+        nif(
+            &[
+                ("NiTriShapeData", tri_shape_data()),
+                ("NiTriStripsData", tri_strips_data()),
+                ("NiSkinInstance", skin_instance()),
+                ("NiSkinData", skin_data()),
+                ("NiStringPalette", string_palette()),
+                ("NiDataStream\x011\x0116", position_stream()),
+            ],
+            &[],
+        )
+    }
+
+    fn assert_round_trip(bytes: Vec<u8>) {
+        let model = NifModel::read(&mut Cursor::new(bytes.clone())).unwrap();
+
+        let mut out = Cursor::new(vec![]);
+        model.write(&mut out).unwrap();
+        assert_eq!(out.into_inner(), bytes);
+    }
+
+    #[test]
+    fn reads_material_and_transform_data() {
+        let model = NifModel::read(&mut Cursor::new(sample_nif())).unwrap();
+
+        assert_eq!(model.roots(), &[0]);
+        let material = model.block::<NiMaterialProperty>(0).unwrap();
+        assert_eq!(
+            model.string(material.object_net.name).as_deref(),
+            Some("Skin")
+        );
+        assert_eq!(material.glossiness, 10.0);
+        assert_eq!(material.alpha, 0.75);
+        assert!(model.block::<NiTransformData>(0).is_none());
+
+        let data = model.block::<NiTransformData>(1).unwrap();
+        let keys = &data.keyframes;
+        assert_eq!(keys.rotation_type, Some(KeyType::LINEAR));
+        assert_eq!(keys.quaternion_keys.len(), 1);
+        assert_eq!(keys.quaternion_keys[0].value.w, 1.0);
+        assert!(keys.xyz_rotations.is_none());
+        assert!(keys.translations.keys.is_empty());
+        assert!(model.block::<NiTransformData>(-1).is_none());
+    }
+
+    #[test]
+    fn reads_geometry_skin_and_palette_blocks() {
+        let model = NifModel::read(&mut Cursor::new(geometry_nif())).unwrap();
+
+        let shape = model.block::<NiTriShapeData>(0).unwrap();
+        let geometry = &shape.geometry_data;
+        assert_eq!(geometry.vertices.len(), 3);
+        assert_eq!(geometry.tangents.len(), 3);
+        assert_eq!(geometry.vertex_colors.len(), 3);
+        assert_eq!(geometry.uv_sets.len(), 1);
+        assert_eq!(geometry.uv_sets[0].coords[2].v, 1.0);
+        assert_eq!(geometry.additional_data, -1);
+        assert_eq!(
+            (
+                shape.triangles[0].v1,
+                shape.triangles[0].v2,
+                shape.triangles[0].v3
+            ),
+            (0, 1, 2)
+        );
+        assert_eq!(shape.match_groups[0].vertex_indices, vec![0, 1]);
+
+        let strips = model.block::<NiTriStripsData>(1).unwrap();
+        assert_eq!(strips.strips[0].points, vec![0, 1, 2, 3]);
+        assert_eq!(strips.triangles(), vec![[0, 1, 2], [2, 1, 3]]);
+
+        let instance = model.block::<NiSkinInstance>(2).unwrap();
+        assert_eq!(instance.data, 3);
+        assert_eq!(instance.bones, vec![0]);
+
+        let skin = model.block::<NiSkinData>(3).unwrap();
+        let weights = &skin.bone_list[0].vertex_weights;
+        assert_eq!((weights[1].index, weights[1].weight), (1, 0.75));
+
+        let palette = model.block::<NiStringPalette>(4).unwrap();
+        assert_eq!(palette.string_at(0).as_deref(), Some("Bip01"));
+        assert_eq!(palette.string_at(6).as_deref(), Some("Head"));
+        assert_eq!(palette.string_at(64), None);
+
+        let stream = model.block::<NiDataStream>(5).unwrap();
+        assert_eq!(stream.usage, DataStreamUsage::USAGE_VERTEX);
+        assert_eq!(stream.access.0, 16);
+        assert_eq!(stream.component_formats[0], ComponentFormat::FLOAT32_3);
+    }
+
+    #[test]
+    fn reads_texturing_fields_for_the_header_version() {
+        for (version, max_anisotropy) in [(V20_6, Some(4)), (V20_3, None)] {
+            let model = NifModel::read(&mut Cursor::new(texturing_nif(version))).unwrap();
+
+            let texturing = model.block::<NiTexturingProperty>(0).unwrap();
+            let base = texturing.base_texture.texture.as_ref().unwrap();
+            assert_eq!(base.source, 1);
+            assert_eq!(base.max_anisotropy, max_anisotropy);
+            assert!(texturing.dark_texture.texture.is_none());
+
+            let source = model.block::<NiSourceTexture>(base.source).unwrap();
+            assert_eq!(model.string(source.file_name).as_deref(), Some("skin.dds"));
+            assert_eq!(source.persist_render_data, Some(0));
+        }
+    }
+
+    #[test]
+    fn rejects_blocks_with_undecoded_bytes() {
+        let mut material = material();
+        material.push(0);
+        let bytes = nif(&[("NiMaterialProperty", material)], &["Skin"]);
+
+        let err = NifModel::read(&mut Cursor::new(bytes)).unwrap_err();
+        assert!(matches!(err, binrw::Error::AssertFail { .. }), "{err}");
+    }
+
+    #[test]
+    fn writes_back_identical_bytes() {
+        assert_round_trip(sample_nif());
+        assert_round_trip(geometry_nif());
+        assert_round_trip(texturing_nif(V20_6));
+        assert_round_trip(texturing_nif(V20_3));
+    }
 }
@@ -2,7 +2,7 @@ use std::borrow::Cow;
 
 use binrw::binrw;
 
-use crate::utils::{Pal4NodeSection, SizedString};
+use crate::utils::{Pal4Node, Pal4NodeSection, SizedString};
 
 #[binrw]
 #[brw(little)]
@@ -35,6 +35,12 @@ pub struct NpcInfo {
 }
 
 impl NpcInfo {
+    /// Root of the NPC's behaviour node tree (patrol / wander /
+    /// monster settings), when the record carries one.
+    pub fn behaviour(&self) -> Option<&Pal4Node> {
+        self.behaviour.root.as_ref()
+    }
+
     pub fn get_default_act(&self) -> Option<Cow<'_, str>> {
         Some(
             self.buffer_cache
@@ -1,4 +1,7 @@
-//! Pure-Rust decoders for PAL3 scene-lighting asset formats.
+//! Pure-Rust codecs for PAL3 asset formats.
+//!
+//! * [`cvd`] — keyframed models.
+//! * [`sce`] — event script procedures and their bytecode.
 //!
 //! Each PAL3 scene block ships a `<index>.lgt` / `<index>.dkl` / `<index>.DKM`
 //! triple alongside its `.scn` / `.pol` data:
@@ -11,5 +14,6 @@
 
 pub mod cvd;
 pub mod lgt;
+pub mod sce;
 // pub mod dkl;  // added in the static-scenery baked-lighting phase
 // pub mod dkm;  // added in the static-scenery baked-lighting phase
@@ -0,0 +1,369 @@
+//! Decoding and encoding of SCE procedure code.
+//!
+//! An instruction is
+//!
+//! ```text
+//! i16   opcode
+//! i16   flags
+//! ...   operands, last declared operand first
+//! ```
+//!
+//! The engine pushed operands in declaration order and popped them off
+//! the end, so the encoded order is the reverse of the order listed in
+//! [`super::opcodes`]. [`SceInst::args`] is always in declaration order.
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use encoding::{DecoderTrap, Encoding};
+use serde::Serialize;
+
+use super::opcodes::{SceOpcode, SceParam, opcode};
+use super::{Result, SceError};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SceListItem {
+    /// Byte ahead of each entry. Always 1 in shipped scripts; the engine
+    /// skips it.
+    pub tag: u8,
+    pub text: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum SceArg {
+    Int(i32),
+    UInt(u32),
+    Addr(u32),
+    Float(f32),
+    Var(i16),
+    /// Raw bytes as encoded, including the trailing NUL.
+    Str(Vec<u8>),
+    List(Vec<SceListItem>),
+}
+
+impl SceArg {
+    pub fn as_i16(&self) -> i16 {
+        self.as_i32() as i16
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        match *self {
+            SceArg::Int(v) => v,
+            SceArg::UInt(v) | SceArg::Addr(v) => v as i32,
+            SceArg::Float(v) => v as i32,
+            SceArg::Var(v) => v as i32,
+            SceArg::Str(_) | SceArg::List(_) => 0,
+        }
+    }
+
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            SceArg::UInt(v) | SceArg::Addr(v) => v,
+            _ => self.as_i32() as u32,
+        }
+    }
+
+    pub fn as_f32(&self) -> f32 {
+        match *self {
+            SceArg::Float(v) => v,
+            _ => self.as_i32() as f32,
+        }
+    }
+
+    /// String operand decoded from GBK, without its terminator.
+    pub fn as_text(&self) -> String {
+        match self {
+            SceArg::Str(bytes) => decode_text(bytes),
+            _ => String::new(),
+        }
+    }
+
+    /// List operand entries decoded from GBK.
+    pub fn as_list(&self) -> Vec<String> {
+        match self {
+            SceArg::List(items) => items.iter().map(|item| decode_text(&item.text)).collect(),
+            _ => vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SceInst {
+    /// Offset of the opcode inside the procedure's code.
+    pub addr: u32,
+    pub code: i16,
+    pub flags: i16,
+    pub args: Vec<SceArg>,
+}
+
+impl SceInst {
+    pub fn opcode(&self) -> Option<&'static SceOpcode> {
+        opcode(self.code)
+    }
+
+    /// Encoded size in bytes.
+    pub fn encoded_len(&self) -> usize {
+        4 + self
+            .args
+            .iter()
+            .map(|arg| match arg {
+                SceArg::Var(_) => 2,
+                SceArg::Int(_) | SceArg::UInt(_) | SceArg::Addr(_) | SceArg::Float(_) => 4,
+                SceArg::Str(bytes) => 2 + bytes.len(),
+                SceArg::List(items) => 2 + items.iter().map(|i| 3 + i.text.len()).sum::<usize>(),
+            })
+            .sum::<usize>()
+    }
+}
+
+/// A decoded procedure. Decoding stops at the first instruction that
+/// can't be decoded; whatever follows is kept in `tail` so nothing is
+/// lost on re-encoding.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SceCode {
+    pub insts: Vec<SceInst>,
+    pub tail: Vec<u8>,
+    /// Why decoding stopped early, if it did.
+    #[serde(skip)]
+    pub tail_reason: Option<String>,
+}
+
+/// Decode the instruction at `addr`, returning it with the address of
+/// the next one.
+pub fn decode_inst(code: &[u8], addr: usize) -> Result<(SceInst, usize)> {
+    let mut reader = code.get(addr..).ok_or(SceError::Truncated { addr })?;
+    let truncated = |_| SceError::Truncated { addr };
+
+    let op = reader.read_i16::<LittleEndian>().map_err(truncated)?;
+    let flags = reader.read_i16::<LittleEndian>().map_err(truncated)?;
+    let params = opcode(op)
+        .ok_or(SceError::UnknownOpcode { code: op, addr })?
+        .params_for(flags);
+
+    let mut args = Vec::with_capacity(params.len());
+    for param in params.iter().rev() {
+        args.push(read_arg(&mut reader, *param).map_err(truncated)?);
+    }
+    args.reverse();
+
+    let next = code.len() - reader.len();
+    Ok((
+        SceInst {
+            addr: addr as u32,
+            code: op,
+            flags,
+            args,
+        },
+        next,
+    ))
+}
+
+/// Decode a whole procedure.
+pub fn decode(code: &[u8]) -> SceCode {
+    let mut insts = vec![];
+    let mut addr = 0;
+    while addr < code.len() {
+        match decode_inst(code, addr) {
+            Ok((inst, next)) => {
+                insts.push(inst);
+                addr = next;
+            }
+            Err(e) => {
+                return SceCode {
+                    insts,
+                    tail: code[addr..].to_vec(),
+                    tail_reason: Some(e.to_string()),
+                };
+            }
+        }
+    }
+
+    SceCode {
+        insts,
+        tail: vec![],
+        tail_reason: None,
+    }
+}
+
+/// Append the encoding of `inst` to `out`. `inst.addr` is ignored.
+pub fn encode_inst(inst: &SceInst, out: &mut Vec<u8>) -> Result<()> {
+    out.write_i16::<LittleEndian>(inst.code)?;
+    out.write_i16::<LittleEndian>(inst.flags)?;
+    for arg in inst.args.iter().rev() {
+        match arg {
+            SceArg::Int(v) => out.write_i32::<LittleEndian>(*v)?,
+            SceArg::UInt(v) | SceArg::Addr(v) => out.write_u32::<LittleEndian>(*v)?,
+            SceArg::Float(v) => out.write_f32::<LittleEndian>(*v)?,
+            SceArg::Var(v) => out.write_i16::<LittleEndian>(*v)?,
+            SceArg::Str(bytes) => write_str(out, bytes)?,
+            SceArg::List(items) => {
+                out.write_u16::<LittleEndian>(u16_len(items.len(), "string list")?)?;
+                for item in items {
+                    out.write_u8(item.tag)?;
+                    write_str(out, &item.text)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Encode a whole procedure, the inverse of [`decode`].
+pub fn encode(code: &SceCode) -> Result<Vec<u8>> {
+    let mut out = vec![];
+    for inst in &code.insts {
+        encode_inst(inst, &mut out)?;
+    }
+    out.extend_from_slice(&code.tail);
+    Ok(out)
+}
+
+fn read_arg(reader: &mut &[u8], param: SceParam) -> std::io::Result<SceArg> {
+    Ok(match param {
+        SceParam::Int => SceArg::Int(reader.read_i32::<LittleEndian>()?),
+        SceParam::UInt => SceArg::UInt(reader.read_u32::<LittleEndian>()?),
+        SceParam::Addr => SceArg::Addr(reader.read_u32::<LittleEndian>()?),
+        SceParam::Float => SceArg::Float(reader.read_f32::<LittleEndian>()?),
+        SceParam::Var => SceArg::Var(reader.read_i16::<LittleEndian>()?),
+        SceParam::Str => SceArg::Str(read_str(reader)?),
+        SceParam::List => {
+            let count = reader.read_u16::<LittleEndian>()?;
+            let mut items = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let tag = reader.read_u8()?;
+                let text = read_str(reader)?;
+                items.push(SceListItem { tag, text });
+            }
+            SceArg::List(items)
+        }
+    })
+}
+
+fn read_str(reader: &mut &[u8]) -> std::io::Result<Vec<u8>> {
+    let len = reader.read_u16::<LittleEndian>()? as usize;
+    if reader.len() < len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(bytes.to_vec())
+}
+
+fn write_str(out: &mut Vec<u8>, bytes: &[u8]) -> Result<()> {
+    out.write_u16::<LittleEndian>(u16_len(bytes.len(), "string")?)?;
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+fn u16_len(len: usize, what: &str) -> Result<u16> {
+    u16::try_from(len).map_err(|_| SceError::TooLong {
+        what: what.to_string(),
+        len,
+    })
+}
+
+/// The engine always drops the last byte of a string, which is the NUL
+/// terminator in every shipped script.
+fn decode_text(bytes: &[u8]) -> String {
+    let text = &bytes[..bytes.len().saturating_sub(1)];
+    encoding::all::GBK
+        .decode(text, DecoderTrap::Ignore)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operands_are_encoded_in_reverse() {
+        // Let $-32768 7
+        let code = [13, 0, 0, 0, 7, 0, 0, 0, 0, 0x80];
+        let (inst, next) = decode_inst(&code, 0).unwrap();
+        assert_eq!(next, code.len());
+        assert_eq!(inst.args, vec![SceArg::Var(i16::MIN), SceArg::Int(7)]);
+        assert_eq!(inst.encoded_len(), code.len());
+
+        let mut out = vec![];
+        encode_inst(&inst, &mut out).unwrap();
+        assert_eq!(out, code);
+    }
+
+    #[test]
+    fn strings_lists_and_geq_variants() {
+        let insts = vec![
+            SceInst {
+                addr: 0,
+                code: 22,
+                flags: 0,
+                args: vec![
+                    SceArg::Int(3),
+                    SceArg::Str(b"j01\0".to_vec()),
+                    SceArg::Int(1),
+                ],
+            },
+            SceInst {
+                addr: 0,
+                code: 65,
+                flags: 0,
+                args: vec![SceArg::List(vec![
+                    SceListItem {
+                        tag: 1,
+                        text: b"yes\0".to_vec(),
+                    },
+                    SceListItem {
+                        tag: 1,
+                        text: b"no\0".to_vec(),
+                    },
+                ])],
+            },
+            SceInst {
+                addr: 0,
+                code: 10,
+                flags: 3,
+                args: vec![SceArg::Var(1), SceArg::Var(-2)],
+            },
+            SceInst {
+                addr: 0,
+                code: 10,
+                flags: 0,
+                args: vec![],
+            },
+        ];
+        let mut code = SceCode {
+            insts,
+            tail: vec![],
+            tail_reason: None,
+        };
+        let bytes = encode(&code).unwrap();
+
+        let mut addr = 0;
+        for inst in &mut code.insts {
+            inst.addr = addr as u32;
+            addr += inst.encoded_len();
+        }
+        assert_eq!(addr, bytes.len());
+        assert_eq!(decode(&bytes), code);
+
+        assert_eq!(code.insts[0].args[1].as_text(), "j01");
+        assert_eq!(code.insts[1].args[0].as_list(), vec!["yes", "no"]);
+    }
+
+    #[test]
+    fn undecodable_tail_is_kept() {
+        // Idle 1.0, then an unknown opcode.
+        let mut bytes = vec![1, 0, 0, 0];
+        bytes.extend_from_slice(&1.0f32.to_le_bytes());
+        bytes.extend_from_slice(&[4, 0, 0, 0, 9, 9]);
+
+        let code = decode(&bytes);
+        assert_eq!(code.insts.len(), 1);
+        assert_eq!(code.tail, &bytes[8..]);
+        assert!(code.tail_reason.unwrap().contains("unknown opcode 4"));
+        assert!(matches!(
+            decode_inst(&bytes[..6], 0),
+            Err(SceError::Truncated { addr: 0 })
+        ));
+        assert_eq!(encode(&decode(&bytes)).unwrap(), bytes);
+    }
+}
@@ -0,0 +1,377 @@
+//! Codec for PAL3 / PAL3A `.sce` event scripts.
+//!
+//! Layout (little-endian):
+//!
+//! ```text
+//! 0x00  [u8; 4]   magic "SCE\0"
+//! 0x04  u8        version, always 1
+//! 0x05  u16       proc_count
+//! repeat proc_count times (directory):
+//!       u32       id
+//!       u32       offset of the body from the start of the file
+//!       [u8; 64]  name, GBK, NUL padded
+//! repeat proc_count times (bodies, in directory order):
+//!       u32       id
+//!       u16       name_len
+//!       [u8]      name, GBK
+//!       u16       local_count
+//!       repeat local_count times:
+//!             u8    kind
+//!             u16   len
+//!             [u8]  data
+//!       u32       code_len
+//!       [u8]      code, see [`inst`]
+//! ```
+//!
+//! Like the other modules in this crate, everything is kept exactly as
+//! encoded — names stay raw GBK bytes and the directory keeps its own
+//! copy of each name and offset — so [`write_sce`] reproduces the input
+//! byte for byte. [`opcodes`] describes every known instruction,
+//! [`inst`] decodes and encodes procedure code against that table and
+//! [`text`] is an editable listing syntax on top of both.
+
+pub mod inst;
+pub mod opcodes;
+pub mod text;
+
+use std::io::{Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::utils::to_gbk_string;
+
+pub use inst::{SceArg, SceCode, SceInst, SceListItem, decode, decode_inst, encode, encode_inst};
+pub use opcodes::{SceOpcode, SceParam, opcode, opcode_by_name};
+pub use text::{assemble_sce, sce_listing};
+
+const MAGIC: [u8; 4] = *b"SCE\0";
+const VERSION: u8 = 1;
+
+/// Fixed on-disk width of a directory entry's name.
+pub const HEADER_NAME_LEN: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum SceError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("sce magic {0:?} is not recognized (expected \"SCE\\0\")")]
+    InvalidMagic([u8; 4]),
+
+    #[error("unsupported sce version {0} (expected 1)")]
+    UnsupportedVersion(u8),
+
+    #[error("proc body #{index} has id {body_id}, but the directory lists {header_id}")]
+    ProcIdMismatch {
+        index: usize,
+        header_id: u32,
+        body_id: u32,
+    },
+
+    #[error("proc {id}: directory name is {len} bytes; it must be exactly {HEADER_NAME_LEN}")]
+    HeaderNameLength { id: u32, len: usize },
+
+    #[error("{what} is {len} bytes, which doesn't fit its length field")]
+    TooLong { what: String, len: usize },
+
+    #[error("unknown opcode {code} at {addr:#06x}")]
+    UnknownOpcode { code: i16, addr: usize },
+
+    #[error("instruction at {addr:#06x} runs past the end of the code")]
+    Truncated { addr: usize },
+
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+}
+
+pub type Result<T> = std::result::Result<T, SceError>;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SceLocal {
+    pub kind: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SceProc {
+    pub id: u32,
+    /// Body offset as recorded in the directory. Nothing reads it, but
+    /// it is kept so the file is written back unchanged; see
+    /// [`SceFile::layout_offsets`] for the value a fresh file gets.
+    pub offset: u32,
+    /// The directory's copy of the name, [`HEADER_NAME_LEN`] raw bytes.
+    pub header_name: Vec<u8>,
+    pub name: Vec<u8>,
+    pub locals: Vec<SceLocal>,
+    pub code: Vec<u8>,
+}
+
+impl SceProc {
+    /// The name decoded from GBK.
+    pub fn name_string(&self) -> String {
+        gbk_until_nul(&self.name)
+    }
+
+    /// The directory name decoded from GBK.
+    pub fn header_name_string(&self) -> String {
+        gbk_until_nul(&self.header_name)
+    }
+
+    /// `name` NUL padded (or truncated) to [`HEADER_NAME_LEN`] bytes,
+    /// the directory name every shipped file is expected to carry.
+    pub fn default_header_name(name: &[u8]) -> Vec<u8> {
+        let mut header_name = name.to_vec();
+        header_name.resize(HEADER_NAME_LEN, 0);
+        header_name
+    }
+
+    fn body_len(&self) -> usize {
+        4 + 2
+            + self.name.len()
+            + 2
+            + self.locals.iter().map(|l| 3 + l.data.len()).sum::<usize>()
+            + 4
+            + self.code.len()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SceFile {
+    pub procs: Vec<SceProc>,
+}
+
+impl SceFile {
+    /// Body offsets as [`write_sce`] lays them out, in proc order.
+    pub fn layout_offsets(&self) -> Vec<u32> {
+        let mut offset = 4 + 1 + 2 + self.procs.len() * (8 + HEADER_NAME_LEN);
+        self.procs
+            .iter()
+            .map(|proc| {
+                let this = offset as u32;
+                offset += proc.body_len();
+                this
+            })
+            .collect()
+    }
+}
+
+/// Parse a complete `.sce` file from `reader`.
+pub fn read_sce(reader: &mut (impl Read + ?Sized)) -> Result<SceFile> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(SceError::InvalidMagic(magic));
+    }
+
+    let version = reader.read_u8()?;
+    if version != VERSION {
+        return Err(SceError::UnsupportedVersion(version));
+    }
+
+    let proc_count = reader.read_u16::<LittleEndian>()?;
+    let mut headers = Vec::with_capacity(proc_count as usize);
+    for _ in 0..proc_count {
+        let id = reader.read_u32::<LittleEndian>()?;
+        let offset = reader.read_u32::<LittleEndian>()?;
+        let mut header_name = vec![0u8; HEADER_NAME_LEN];
+        reader.read_exact(&mut header_name)?;
+        headers.push((id, offset, header_name));
+    }
+
+    let mut procs = Vec::with_capacity(headers.len());
+    for (index, (id, offset, header_name)) in headers.into_iter().enumerate() {
+        let body_id = reader.read_u32::<LittleEndian>()?;
+        if body_id != id {
+            return Err(SceError::ProcIdMismatch {
+                index,
+                header_id: id,
+                body_id,
+            });
+        }
+
+        let name = read_u16_prefixed(reader)?;
+        let local_count = reader.read_u16::<LittleEndian>()?;
+        let mut locals = Vec::with_capacity(local_count as usize);
+        for _ in 0..local_count {
+            let kind = reader.read_u8()?;
+            let data = read_u16_prefixed(reader)?;
+            locals.push(SceLocal { kind, data });
+        }
+        let code_len = reader.read_u32::<LittleEndian>()? as usize;
+        let code = read_bytes(reader, code_len)?;
+
+        procs.push(SceProc {
+            id,
+            offset,
+            header_name,
+            name,
+            locals,
+            code,
+        });
+    }
+
+    Ok(SceFile { procs })
+}
+
+/// Serialize a [`SceFile`] to `writer`, matching the exact binary layout
+/// produced by [`read_sce`].
+pub fn write_sce(writer: &mut impl Write, file: &SceFile) -> Result<()> {
+    let proc_count = u16::try_from(file.procs.len()).map_err(|_| SceError::TooLong {
+        what: "proc list".to_string(),
+        len: file.procs.len(),
+    })?;
+    for proc in &file.procs {
+        if proc.header_name.len() != HEADER_NAME_LEN {
+            return Err(SceError::HeaderNameLength {
+                id: proc.id,
+                len: proc.header_name.len(),
+            });
+        }
+    }
+
+    writer.write_all(&MAGIC)?;
+    writer.write_u8(VERSION)?;
+    writer.write_u16::<LittleEndian>(proc_count)?;
+    for proc in &file.procs {
+        writer.write_u32::<LittleEndian>(proc.id)?;
+        writer.write_u32::<LittleEndian>(proc.offset)?;
+        writer.write_all(&proc.header_name)?;
+    }
+
+    for proc in &file.procs {
+        writer.write_u32::<LittleEndian>(proc.id)?;
+        write_u16_len(writer, &proc.name, || format!("proc {} name", proc.id))?;
+        writer.write_all(&proc.name)?;
+        write_u16_len(writer, &proc.locals, || {
+            format!("proc {} local list", proc.id)
+        })?;
+        for (i, local) in proc.locals.iter().enumerate() {
+            writer.write_u8(local.kind)?;
+            write_u16_len(writer, &local.data, || {
+                format!("proc {} local #{}", proc.id, i)
+            })?;
+            writer.write_all(&local.data)?;
+        }
+        let code_len = u32::try_from(proc.code.len()).map_err(|_| SceError::TooLong {
+            what: format!("proc {} code", proc.id),
+            len: proc.code.len(),
+        })?;
+        writer.write_u32::<LittleEndian>(code_len)?;
+        writer.write_all(&proc.code)?;
+    }
+
+    Ok(())
+}
+
+fn read_u16_prefixed(reader: &mut (impl Read + ?Sized)) -> Result<Vec<u8>> {
+    let len = reader.read_u16::<LittleEndian>()? as usize;
+    read_bytes(reader, len)
+}
+
+fn gbk_until_nul(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    to_gbk_string(&bytes[..end]).unwrap_or_default()
+}
+
+fn read_bytes(reader: &mut (impl Read + ?Sized), len: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn write_u16_len<T>(
+    writer: &mut impl Write,
+    items: &[T],
+    what: impl FnOnce() -> String,
+) -> Result<()> {
+    let len = u16::try_from(items.len()).map_err(|_| SceError::TooLong {
+        what: what(),
+        len: items.len(),
+    })?;
+    writer.write_u16::<LittleEndian>(len)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    pub(super) fn sample_file() -> SceFile {
+        let mut file = SceFile {
+            procs: vec![
+                SceProc {
+                    id: 1001,
+                    offset: 0,
+                    header_name: SceProc::default_header_name(b"main"),
+                    name: b"main".to_vec(),
+                    locals: vec![SceLocal {
+                        kind: 2,
+                        data: vec![1, 2, 3],
+                    }],
+                    // Let $-32768 7 ; Goto 0
+                    code: vec![13, 0, 0, 0, 7, 0, 0, 0, 0, 0x80, 3, 0, 0, 0, 0, 0, 0, 0],
+                },
+                SceProc {
+                    id: 1002,
+                    offset: 0,
+                    header_name: SceProc::default_header_name(b"other"),
+                    name: b"other".to_vec(),
+                    locals: vec![],
+                    code: vec![],
+                },
+            ],
+        };
+        let offsets = file.layout_offsets();
+        for (proc, offset) in file.procs.iter_mut().zip(offsets) {
+            proc.offset = offset;
+        }
+        file
+    }
+
+    #[test]
+    fn write_read_round_trip() {
+        let file = sample_file();
+        let mut bytes = Vec::new();
+        write_sce(&mut bytes, &file).unwrap();
+
+        let reread = read_sce(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(reread, file);
+
+        let mut rewritten = Vec::new();
+        write_sce(&mut rewritten, &reread).unwrap();
+        assert_eq!(rewritten, bytes);
+
+        // The directory offsets point at the bodies' id fields.
+        for proc in &file.procs {
+            let at = proc.offset as usize;
+            assert_eq!(&bytes[at..at + 4], &proc.id.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_mismatched_ids() {
+        assert!(matches!(
+            read_sce(&mut Cursor::new(b"SCN\0\x01\0\0")),
+            Err(SceError::InvalidMagic(_))
+        ));
+
+        let mut file = sample_file();
+        let mut bytes = Vec::new();
+        write_sce(&mut bytes, &file).unwrap();
+        let body = file.procs[1].offset as usize;
+        bytes[body..body + 4].copy_from_slice(&7u32.to_le_bytes());
+        assert!(matches!(
+            read_sce(&mut Cursor::new(&bytes)),
+            Err(SceError::ProcIdMismatch { index: 1, .. })
+        ));
+
+        file.procs[0].header_name.pop();
+        assert!(matches!(
+            write_sce(&mut Vec::new(), &file),
+            Err(SceError::HeaderNameLength { id: 1001, len: 63 })
+        ));
+    }
+}
@@ -0,0 +1,248 @@
+//! The SCE instruction set.
+//!
+//! Every instruction starts with an `i16` opcode and an `i16` flags
+//! word, followed by the opcode's operands. Operands are listed here in
+//! declaration order (the order the VM's commands take them); on the
+//! wire they appear reversed, see [`super::inst`].
+//!
+//! Names follow the original engine's command names. The table is
+//! shared by the decoder, the text syntax and `scripting::sce::vm`.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SceParam {
+    /// `i32`
+    Int,
+    /// `u32`
+    UInt,
+    /// `u32` code address inside the current procedure.
+    Addr,
+    /// `f32`
+    Float,
+    /// `i16` variable index; negative indices are globals.
+    Var,
+    /// `u16` length, then that many GBK bytes including the trailing NUL.
+    Str,
+    /// `u16` count, then per item a `u8` tag and a [`SceParam::Str`].
+    List,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SceOpcode {
+    pub code: i16,
+    pub name: &'static str,
+    pub params: &'static [SceParam],
+    /// Operand lists that replace `params` when the flags word matches.
+    /// Only used by `GEQ`, whose second operand is either a constant or
+    /// a variable.
+    pub flag_variants: &'static [(i16, &'static [SceParam])],
+}
+
+impl SceOpcode {
+    /// The operands of this opcode under `flags`.
+    pub fn params_for(&self, flags: i16) -> &'static [SceParam] {
+        if self.flag_variants.is_empty() {
+            return self.params;
+        }
+
+        self.flag_variants
+            .iter()
+            .find(|(f, _)| *f == flags)
+            .map_or(&[], |(_, params)| params)
+    }
+}
+
+use SceParam::{Addr, Float as F, Int as I, List, Str, UInt, Var};
+
+const fn op(code: i16, name: &'static str, params: &'static [SceParam]) -> SceOpcode {
+    SceOpcode {
+        code,
+        name,
+        params,
+        flag_variants: &[],
+    }
+}
+
+/// Every opcode the engine understands, ordered by code.
+pub const OPCODES: &[SceOpcode] = &[
+    op(1, "Idle", &[F]),
+    op(2, "ScriptRunMode", &[I]),
+    op(3, "Goto", &[Addr]),
+    op(5, "FOP", &[I]),
+    op(6, "GT", &[Var, I]),
+    op(7, "LS", &[Var, I]),
+    op(8, "EQ", &[Var, I]),
+    op(9, "NEQ", &[Var, I]),
+    SceOpcode {
+        code: 10,
+        name: "GEQ",
+        params: &[Var, I],
+        flag_variants: &[(1, &[Var, I]), (3, &[Var, Var])],
+    },
+    op(11, "LEQ", &[Var, I]),
+    op(12, "TestGoto", &[Addr]),
+    op(13, "Let", &[Var, I]),
+    op(16, "Call", &[UInt]),
+    op(17, "Rnd", &[Var, I]),
+    op(19, "Between", &[Var, I, I]),
+    op(20, "RolePathTo", &[I, I, I, I]),
+    op(21, "RoleSetPos", &[I, I, I]),
+    op(22, "RoleShowAction", &[I, Str, I]),
+    op(23, "RoleSetFace", &[I, I]),
+    op(24, "RoleTurnFace", &[I, I]),
+    op(25, "TeamOpen", &[]),
+    op(26, "TeamClose", &[]),
+    op(27, "RoleInput", &[I]),
+    op(28, "RoleActive", &[I, I]),
+    op(29, "RoleScript", &[I, I]),
+    op(30, "CameraFocusRole", &[I]),
+    op(31, "CameraFocusPoint", &[F, F, F]),
+    op(32, "CameraPush", &[F, F, I]),
+    op(33, "CameraRotate", &[F, F, F, I]),
+    op(34, "CameraMove", &[F, F, F, F, F]),
+    op(35, "CameraWag", &[F, F, F, I]),
+    op(36, "CameraSet", &[F, F, F, F, F, F]),
+    op(37, "CameraDefault", &[I]),
+    op(38, "CameraPushState", &[]),
+    op(39, "CameraPopState", &[]),
+    op(42, "LK_Ghost", &[I]),
+    op(43, "FavorAdd", &[I, I]),
+    op(46, "AddItem", &[I, I]),
+    op(47, "RemoveItem", &[I]),
+    op(48, "AddMoney", &[I]),
+    op(49, "GetMoney", &[Var]),
+    op(50, "GetFavor", &[Var, I]),
+    op(51, "AddSkill", &[I, I]),
+    op(52, "GetFavorite", &[Var]),
+    op(54, "FullRoleAtt", &[I, I]),
+    op(62, "Dlg", &[Str]),
+    op(63, "LoadScene", &[Str, Str]),
+    op(65, "DlgSel", &[List]),
+    op(66, "GetDlgSel", &[Var]),
+    op(67, "DlgFace", &[I, Str, I]),
+    op(68, "Note", &[Str]),
+    op(69, "FadeOut", &[]),
+    op(70, "FadeIn", &[]),
+    op(71, "RoleStop", &[I]),
+    op(72, "RoleEmote", &[I, I]),
+    op(74, "Climb", &[I, I]),
+    op(76, "DlgTime", &[Str]),
+    op(77, "GetTimeSel", &[Var]),
+    op(78, "HaveItem", &[I]),
+    op(79, "PlaySound", &[Str, I]),
+    op(80, "CombatBoss", &[I, I, I, I, I, I]),
+    op(81, "FadeOutWhite", &[]),
+    op(82, "CombatMaxRound", &[I]),
+    op(83, "CombatMustFail", &[]),
+    op(85, "ObjectActive", &[I, I]),
+    op(86, "Caption", &[Str, I]),
+    op(87, "OpenDoor", &[I]),
+    op(88, "HY_Mode", &[I]),
+    op(89, "HY_FLY", &[F, F, F]),
+    op(90, "ObjectMove", &[I, F, F, F, F]),
+    op(91, "FadeInWhite", &[]),
+    op(102, "SwitchRS", &[I]),
+    op(104, "APPR_Entry", &[]),
+    op(106, "ENCAMP_Entry", &[I]),
+    op(107, "SKEE_Entry", &[I]),
+    op(108, "GetAppr", &[Var]),
+    op(109, "Enable_Sword", &[I]),
+    op(111, "Specify_Compos", &[I]),
+    op(113, "Start_HideFight", &[]),
+    op(115, "Movie", &[Str]),
+    op(116, "SetRoleTexture", &[I, Str]),
+    op(117, "Rotate", &[I, I, I]),
+    op(118, "Quake", &[F, F]),
+    op(119, "ShowChatRest", &[Str, UInt, UInt, UInt]),
+    op(124, "Trigger", &[I]),
+    op(125, "SetBigMapElement", &[I, I]),
+    op(126, "GetSwitch", &[Str, I, Var]),
+    op(127, "EntryRow", &[I, I]),
+    op(128, "RotateInv", &[I, I, I]),
+    op(130, "Dist", &[Var, Var]),
+    op(131, "CombatNotGameOver", &[]),
+    op(132, "GetCombat", &[Var]),
+    op(133, "Music", &[Str, I]),
+    op(134, "StopMusic", &[]),
+    op(135, "RoleFadeOut", &[I]),
+    op(136, "RoleFadeIn", &[I]),
+    op(137, "IfInTeam", &[I]),
+    op(138, "Enable_SwordSkill", &[I]),
+    op(140, "Snow", &[I]),
+    op(141, "ScrEft", &[I]),
+    op(142, "CEft_Pos", &[F, F, F]),
+    op(143, "CEft", &[I]),
+    op(144, "CEft_Role", &[I]),
+    op(145, "AverageLv", &[I, I]),
+    op(147, "Switch2Menu", &[]),
+    op(148, "CEft_Load", &[I]),
+    op(149, "GiveCloth", &[Var]),
+    op(150, "LoadAct", &[I, Str]),
+    op(152, "WaterMagic", &[I]),
+    op(153, "FullTeamAtt", &[]),
+    op(155, "CameraYaw", &[F]),
+    op(156, "XJ_Pic", &[]),
+    op(158, "ObjNotLoad", &[I]),
+    op(159, "InitFlower", &[]),
+    op(201, "RolePathOut", &[I, I, I, I]),
+    op(202, "InTeam", &[I, I]),
+    op(203, "RoleSetLayer", &[I, I]),
+    op(204, "RoleCtrl", &[I]),
+    op(205, "RoleOverlap", &[I, I]),
+    op(206, "RoleScale", &[I, F]),
+    op(207, "RoleActAutoStand", &[I, I]),
+    op(208, "RoleMoveBack", &[I, F]),
+    op(209, "RoleFaceRole", &[I, I]),
+    op(210, "RoleTurnFaceA", &[I, I]),
+    op(211, "TeamOpenA", &[]),
+    op(212, "TeamCloseA", &[]),
+    op(214, "RoleMovTo", &[I, I, I, I]),
+    op(221, "RoleEndAction", &[I]),
+    op(250, "CameraFree", &[I]),
+    // Shares its name and operands with 90 in the original engine.
+    op(251, "ObjectMove2", &[I, F, F, F, F]),
+];
+
+/// Look up an opcode by its code.
+pub fn opcode(code: i16) -> Option<&'static SceOpcode> {
+    OPCODES
+        .binary_search_by_key(&code, |op| op.code)
+        .ok()
+        .map(|i| &OPCODES[i])
+}
+
+/// Look up an opcode by name, ignoring ASCII case.
+pub fn opcode_by_name(name: &str) -> Option<&'static SceOpcode> {
+    OPCODES.iter().find(|op| op.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn table_is_sorted_and_names_are_unique() {
+        assert!(OPCODES.windows(2).all(|w| w[0].code < w[1].code));
+
+        let names: HashSet<_> = OPCODES
+            .iter()
+            .map(|op| op.name.to_ascii_lowercase())
+            .collect();
+        assert_eq!(names.len(), OPCODES.len());
+
+        assert_eq!(opcode(62).unwrap().name, "Dlg");
+        assert_eq!(opcode_by_name("testgoto").unwrap().code, 12);
+        assert!(opcode(4).is_none());
+    }
+
+    #[test]
+    fn geq_operands_depend_on_flags() {
+        let geq = opcode(10).unwrap();
+        assert_eq!(geq.params_for(1), &[Var, I]);
+        assert_eq!(geq.params_for(3), &[Var, Var]);
+        assert!(geq.params_for(0).is_empty());
+        assert_eq!(opcode(13).unwrap().params_for(3), &[Var, I]);
+    }
+}
@@ -0,0 +1,755 @@
+//! Editable text syntax for `.sce` files.
+//!
+//! ```text
+//! .proc 1001 "main"
+//!     .local 2 x"010203"
+//! L_0000:
+//!     RoleShowAction 3 "j01" 1
+//!     GEQ/3 $1 $-2
+//!     DlgSel [1 "yes", 1 "no"]
+//!     Goto L_0000
+//! .end
+//! ```
+//!
+//! * One instruction per line: the opcode name from [`super::opcodes`]
+//!   (case-insensitive), `/flags` when the flags word isn't zero, then
+//!   the operands in declaration order.
+//! * Integers are decimal, floats use Rust's shortest round-trip form
+//!   (`bits:0x...` for NaNs), variables are `$index` and addresses are
+//!   `L_xxxx` labels, or `@offset` for targets that aren't the start of
+//!   an instruction.
+//! * `"..."` is GBK text (`\\`, `\"`, `\n`, `\r`, `\t`, `\0` and `\xNN`
+//!   escapes); as a string operand it gets the NUL terminator appended.
+//!   `x"..."` is raw hex and is used verbatim.
+//! * Lists are `[tag text, ...]`.
+//! * `;` starts a comment.
+//!
+//! Directives inside a `.proc` / `.end` block: `.local kind bytes`, and
+//! only when they differ from what a fresh file would get, `.offset n`
+//! and `.header_name bytes`. `.bytes x"..."` holds code that didn't
+//! decode; it must come after the last instruction.
+//!
+//! [`sce_listing`] followed by [`assemble_sce`] reproduces the original
+//! file byte for byte.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use encoding::{DecoderTrap, EncoderTrap, Encoding};
+
+use super::inst::{SceArg, SceCode, SceInst, SceListItem, decode, encode};
+use super::opcodes::{SceParam, opcode_by_name};
+use super::{HEADER_NAME_LEN, Result, SceError, SceFile, SceLocal, SceProc};
+
+/// Render `file` in the text syntax.
+pub fn sce_listing(file: &SceFile) -> String {
+    let offsets = file.layout_offsets();
+    let mut out = String::new();
+    for (i, (proc, offset)) in file.procs.iter().zip(offsets).enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_proc(&mut out, proc, offset);
+    }
+    out
+}
+
+fn write_proc(out: &mut String, proc: &SceProc, layout_offset: u32) {
+    let _ = writeln!(out, ".proc {} {}", proc.id, bytes_literal(&proc.name));
+    if proc.offset != layout_offset {
+        let _ = writeln!(out, "    .offset {}", proc.offset);
+    }
+    if proc.header_name != SceProc::default_header_name(&proc.name) {
+        let _ = writeln!(out, "    .header_name {}", hex_literal(&proc.header_name));
+    }
+    for local in &proc.locals {
+        let _ = writeln!(
+            out,
+            "    .local {} {}",
+            local.kind,
+            bytes_literal(&local.data)
+        );
+    }
+
+    let code = decode(&proc.code);
+    let tail_start = proc.code.len() - code.tail.len();
+    let targets: HashSet<u32> = code
+        .insts
+        .iter()
+        .flat_map(|inst| inst.args.iter())
+        .filter_map(|arg| match arg {
+            SceArg::Addr(addr) => Some(*addr),
+            _ => None,
+        })
+        .collect();
+    let boundaries: HashSet<u32> = code
+        .insts
+        .iter()
+        .map(|inst| inst.addr)
+        .chain([tail_start as u32, proc.code.len() as u32])
+        .collect();
+    let label = |addr: u32| {
+        if boundaries.contains(&addr) {
+            format!("L_{:04x}", addr)
+        } else {
+            format!("@{}", addr)
+        }
+    };
+    let write_label = |out: &mut String, addr: usize| {
+        if targets.contains(&(addr as u32)) {
+            let _ = writeln!(out, "L_{:04x}:", addr);
+        }
+    };
+
+    for inst in &code.insts {
+        write_label(out, inst.addr as usize);
+        let _ = writeln!(out, "    {}", format_inst(inst, &label));
+    }
+    if !code.tail.is_empty() {
+        write_label(out, tail_start);
+        let _ = write!(out, "    .bytes {}", hex_literal(&code.tail));
+        if let Some(reason) = &code.tail_reason {
+            let _ = write!(out, " ; {}", reason);
+        }
+        out.push('\n');
+    }
+    write_label(out, proc.code.len());
+    out.push_str(".end\n");
+}
+
+fn format_inst(inst: &SceInst, label: &impl Fn(u32) -> String) -> String {
+    let mut line = match inst.opcode() {
+        Some(op) => op.name.to_string(),
+        None => format!("op{}", inst.code),
+    };
+    if inst.flags != 0 {
+        let _ = write!(line, "/{}", inst.flags);
+    }
+
+    for arg in &inst.args {
+        line.push(' ');
+        match arg {
+            SceArg::Int(v) => line.push_str(&v.to_string()),
+            SceArg::UInt(v) => line.push_str(&v.to_string()),
+            SceArg::Addr(addr) => line.push_str(&label(*addr)),
+            SceArg::Float(v) if v.is_nan() => {
+                let _ = write!(line, "bits:{:#010x}", v.to_bits());
+            }
+            SceArg::Float(v) => {
+                let _ = write!(line, "{:?}", v);
+            }
+            SceArg::Var(v) => {
+                let _ = write!(line, "${}", v);
+            }
+            SceArg::Str(bytes) => line.push_str(&str_literal(bytes)),
+            SceArg::List(items) => {
+                line.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        line.push_str(", ");
+                    }
+                    let _ = write!(line, "{} {}", item.tag, str_literal(&item.text));
+                }
+                line.push(']');
+            }
+        }
+    }
+
+    line
+}
+
+/// A string operand: text with an implied terminator when possible.
+fn str_literal(bytes: &[u8]) -> String {
+    bytes
+        .strip_suffix(&[0])
+        .and_then(text_literal)
+        .unwrap_or_else(|| hex_literal(bytes))
+}
+
+/// Arbitrary bytes: text when they are clean GBK, hex otherwise.
+fn bytes_literal(bytes: &[u8]) -> String {
+    text_literal(bytes).unwrap_or_else(|| hex_literal(bytes))
+}
+
+fn text_literal(bytes: &[u8]) -> Option<String> {
+    let text = encoding::all::GBK.decode(bytes, DecoderTrap::Strict).ok()?;
+    let reencoded = encoding::all::GBK.encode(&text, EncoderTrap::Strict).ok()?;
+    if reencoded != bytes {
+        return None;
+    }
+
+    let mut literal = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '\\' => literal.push_str("\\\\"),
+            '"' => literal.push_str("\\\""),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            '\0' => literal.push_str("\\0"),
+            c if c.is_ascii_control() => {
+                let _ = write!(literal, "\\x{:02x}", c as u32);
+            }
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    Some(literal)
+}
+
+fn hex_literal(bytes: &[u8]) -> String {
+    let mut literal = String::from("x\"");
+    for b in bytes {
+        let _ = write!(literal, "{:02x}", b);
+    }
+    literal.push('"');
+    literal
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    /// A `"..."` literal, already GBK encoded.
+    Text(Vec<u8>),
+    /// A `x"..."` literal.
+    Hex(Vec<u8>),
+    Punct(char),
+}
+
+fn tokenize(line: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            ';' => break,
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '[' | ']' | ',' => {
+                chars.next();
+                tokens.push(Token::Punct(c));
+            }
+            '"' => {
+                chars.next();
+                let text = read_quoted(&mut chars)?;
+                let bytes = encoding::all::GBK
+                    .encode(&text, EncoderTrap::Strict)
+                    .map_err(|_| format!("\"{}\" can't be encoded as GBK", text))?;
+                tokens.push(Token::Text(bytes));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '[' | ']' | ',' | ';' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+
+                if word == "x" && chars.peek() == Some(&'"') {
+                    chars.next();
+                    tokens.push(Token::Hex(parse_hex(&read_quoted(&mut chars)?)?));
+                } else {
+                    tokens.push(Token::Word(word));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn read_quoted(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+) -> std::result::Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string".to_string()),
+            Some('"') => return Ok(text),
+            Some('\\') => match chars.next() {
+                Some('\\') => text.push('\\'),
+                Some('"') => text.push('"'),
+                Some('n') => text.push('\n'),
+                Some('r') => text.push('\r'),
+                Some('t') => text.push('\t'),
+                Some('0') => text.push('\0'),
+                Some('x') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    let value = u8::from_str_radix(&hex, 16)
+                        .ok()
+                        .filter(|v| v.is_ascii())
+                        .ok_or_else(|| format!("bad escape \\x{}", hex))?;
+                    text.push(value as char);
+                }
+                other => return Err(format!("bad escape \\{}", other.unwrap_or(' '))),
+            },
+            Some(c) => text.push(c),
+        }
+    }
+}
+
+fn parse_hex(text: &str) -> std::result::Result<Vec<u8>, String> {
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err("hex literal has an odd number of digits".to_string());
+    }
+
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair: String = pair.iter().collect();
+            u8::from_str_radix(&pair, 16).map_err(|_| format!("bad hex byte \"{}\"", pair))
+        })
+        .collect()
+}
+
+struct ProcBuilder {
+    line: usize,
+    id: u32,
+    name: Vec<u8>,
+    offset: Option<u32>,
+    header_name: Option<Vec<u8>>,
+    locals: Vec<SceLocal>,
+    insts: Vec<SceInst>,
+    tail: Vec<u8>,
+    addr: u32,
+    labels: HashMap<String, u32>,
+    /// (instruction index, operand index, label, line)
+    fixups: Vec<(usize, usize, String, usize)>,
+}
+
+impl ProcBuilder {
+    fn finish(mut self) -> Result<(SceProc, Option<u32>)> {
+        for (inst, arg, label, line) in std::mem::take(&mut self.fixups) {
+            let target = *self
+                .labels
+                .get(&label)
+                .ok_or_else(|| syntax(line, format!("undefined label {}", label)))?;
+            self.insts[inst].args[arg] = SceArg::Addr(target);
+        }
+
+        let code = encode(&SceCode {
+            insts: self.insts,
+            tail: self.tail,
+            tail_reason: None,
+        })?;
+        let header_name = self
+            .header_name
+            .unwrap_or_else(|| SceProc::default_header_name(&self.name));
+        if header_name.len() != HEADER_NAME_LEN {
+            return Err(syntax(
+                self.line,
+                format!("header name must be {} bytes", HEADER_NAME_LEN),
+            ));
+        }
+
+        Ok((
+            SceProc {
+                id: self.id,
+                offset: 0,
+                header_name,
+                name: self.name,
+                locals: self.locals,
+                code,
+            },
+            self.offset,
+        ))
+    }
+}
+
+/// Parse the text syntax back into a [`SceFile`].
+pub fn assemble_sce(text: &str) -> Result<SceFile> {
+    let mut procs = vec![];
+    let mut offsets = vec![];
+    let mut current: Option<ProcBuilder> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let tokens = tokenize(line).map_err(|message| syntax(line_no, message))?;
+        let mut cursor = Cursor {
+            tokens: &tokens,
+            pos: 0,
+            line: line_no,
+        };
+        let Some(first) = cursor.word_opt() else {
+            if tokens.is_empty() {
+                continue;
+            }
+            return Err(syntax(line_no, "expected a directive or instruction"));
+        };
+
+        if first == ".proc" {
+            if current.is_some() {
+                return Err(syntax(line_no, "missing .end before .proc"));
+            }
+            let id = cursor.number()?;
+            let name = cursor.bytes()?;
+            current = Some(ProcBuilder {
+                line: line_no,
+                id,
+                name,
+                offset: None,
+                header_name: None,
+                locals: vec![],
+                insts: vec![],
+                tail: vec![],
+                addr: 0,
+                labels: HashMap::new(),
+                fixups: vec![],
+            });
+            cursor.end()?;
+            continue;
+        }
+
+        let Some(proc) = current.as_mut() else {
+            return Err(syntax(
+                line_no,
+                format!("{} outside of a .proc block", first),
+            ));
+        };
+        match first.as_str() {
+            ".end" => {
+                cursor.end()?;
+                let (proc, offset) = current.take().unwrap().finish()?;
+                procs.push(proc);
+                offsets.push(offset);
+                continue;
+            }
+            ".offset" => proc.offset = Some(cursor.number()?),
+            ".header_name" => proc.header_name = Some(cursor.bytes()?),
+            ".local" => {
+                let kind = cursor.number()?;
+                let data = cursor.bytes()?;
+                proc.locals.push(SceLocal { kind, data });
+            }
+            ".bytes" => {
+                if !proc.tail.is_empty() {
+                    return Err(syntax(line_no, "only one .bytes line is allowed"));
+                }
+                proc.tail = cursor.bytes()?;
+                proc.addr += proc.tail.len() as u32;
+            }
+            label if label.ends_with(':') => {
+                let label = label.trim_end_matches(':').to_string();
+                if proc.labels.insert(label.clone(), proc.addr).is_some() {
+                    return Err(syntax(line_no, format!("label {} defined twice", label)));
+                }
+            }
+            mnemonic => {
+                if !proc.tail.is_empty() {
+                    return Err(syntax(line_no, "instruction after .bytes"));
+                }
+                let (name, flags) = match mnemonic.split_once('/') {
+                    Some((name, flags)) => (name, parse_number(flags, line_no)?),
+                    None => (mnemonic, 0),
+                };
+                let op = opcode_by_name(name)
+                    .ok_or_else(|| syntax(line_no, format!("unknown opcode {}", name)))?;
+
+                let mut args = vec![];
+                for param in op.params_for(flags) {
+                    if *param == SceParam::Addr && !cursor.peek_is_address() {
+                        let label = cursor.word()?;
+                        proc.fixups
+                            .push((proc.insts.len(), args.len(), label, line_no));
+                        args.push(SceArg::Addr(0));
+                    } else {
+                        args.push(cursor.arg(*param)?);
+                    }
+                }
+
+                let inst = SceInst {
+                    addr: proc.addr,
+                    code: op.code,
+                    flags,
+                    args,
+                };
+                proc.addr += inst.encoded_len() as u32;
+                proc.insts.push(inst);
+            }
+        }
+        cursor.end()?;
+    }
+
+    if let Some(proc) = current {
+        return Err(syntax(proc.line, "missing .end"));
+    }
+
+    let mut file = SceFile { procs };
+    let layout = file.layout_offsets();
+    for ((proc, offset), layout) in file.procs.iter_mut().zip(offsets).zip(layout) {
+        proc.offset = offset.unwrap_or(layout);
+    }
+    Ok(file)
+}
+
+struct Cursor<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    line: usize,
+}
+
+impl Cursor<'_> {
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn word_opt(&mut self) -> Option<String> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Word(word)) => {
+                self.pos += 1;
+                Some(word.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn word(&mut self) -> Result<String> {
+        self.word_opt()
+            .ok_or_else(|| syntax(self.line, "expected an operand"))
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T> {
+        let word = self.word()?;
+        parse_number(&word, self.line)
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>> {
+        match self.next() {
+            Some(Token::Text(bytes) | Token::Hex(bytes)) => Ok(bytes.clone()),
+            _ => Err(syntax(self.line, "expected a \"text\" or x\"hex\" literal")),
+        }
+    }
+
+    fn str_operand(&mut self) -> Result<Vec<u8>> {
+        match self.next() {
+            Some(Token::Text(bytes)) => Ok([bytes.as_slice(), &[0]].concat()),
+            Some(Token::Hex(bytes)) => Ok(bytes.clone()),
+            _ => Err(syntax(self.line, "expected a \"text\" or x\"hex\" literal")),
+        }
+    }
+
+    fn punct(&mut self, c: char) -> Result<()> {
+        match self.next() {
+            Some(Token::Punct(p)) if *p == c => Ok(()),
+            _ => Err(syntax(self.line, format!("expected '{}'", c))),
+        }
+    }
+
+    fn peek_is_address(&self) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Word(w)) if w.starts_with('@'))
+    }
+
+    fn arg(&mut self, param: SceParam) -> Result<SceArg> {
+        Ok(match param {
+            SceParam::Int => SceArg::Int(self.number()?),
+            SceParam::UInt => SceArg::UInt(self.number()?),
+            SceParam::Addr => {
+                let word = self.word()?;
+                SceArg::Addr(parse_number(&word[1..], self.line)?)
+            }
+            SceParam::Float => {
+                let word = self.word()?;
+                match word.strip_prefix("bits:0x") {
+                    Some(bits) => SceArg::Float(f32::from_bits(
+                        u32::from_str_radix(bits, 16)
+                            .map_err(|_| syntax(self.line, format!("bad float {}", word)))?,
+                    )),
+                    None => SceArg::Float(parse_number(&word, self.line)?),
+                }
+            }
+            SceParam::Var => {
+                let word = self.word()?;
+                let index = word
+                    .strip_prefix('$')
+                    .ok_or_else(|| syntax(self.line, format!("expected $var, got {}", word)))?;
+                SceArg::Var(parse_number(index, self.line)?)
+            }
+            SceParam::Str => SceArg::Str(self.str_operand()?),
+            SceParam::List => {
+                self.punct('[')?;
+                let mut items = vec![];
+                if self.tokens.get(self.pos) == Some(&Token::Punct(']')) {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        let tag = self.number()?;
+                        let text = self.str_operand()?;
+                        items.push(SceListItem { tag, text });
+                        match self.next() {
+                            Some(Token::Punct(',')) => continue,
+                            Some(Token::Punct(']')) => break,
+                            _ => return Err(syntax(self.line, "expected ',' or ']'")),
+                        }
+                    }
+                }
+                SceArg::List(items)
+            }
+        })
+    }
+
+    fn end(&self) -> Result<()> {
+        if self.pos < self.tokens.len() {
+            return Err(syntax(self.line, "unexpected trailing input"));
+        }
+        Ok(())
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(word: &str, line: usize) -> Result<T> {
+    word.parse()
+        .map_err(|_| syntax(line, format!("bad number \"{}\"", word)))
+}
+
+fn syntax(line: usize, message: impl Into<String>) -> SceError {
+    SceError::Syntax {
+        line,
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::sample_file;
+    use super::super::{read_sce, write_sce};
+    use super::*;
+    use std::io::Cursor as IoCursor;
+
+    fn round_trip(file: &SceFile) -> String {
+        let listing = sce_listing(file);
+        let assembled = assemble_sce(&listing).unwrap();
+        assert_eq!(&assembled, file, "{}", listing);
+
+        let mut original = vec![];
+        let mut rebuilt = vec![];
+        write_sce(&mut original, file).unwrap();
+        write_sce(&mut rebuilt, &assembled).unwrap();
+        assert_eq!(original, rebuilt);
+        assert_eq!(read_sce(&mut IoCursor::new(&rebuilt)).unwrap(), *file);
+        listing
+    }
+
+    #[test]
+    fn listing_round_trips() {
+        let mut file = sample_file();
+        let listing = round_trip(&file);
+        assert!(listing.contains("L_0000:\n    Let $-32768 7\n    Goto L_0000\n"));
+        assert!(!listing.contains(".offset"));
+
+        // Odd directory data, strings that aren't clean text, jumps into
+        // the middle of an instruction and an undecodable tail.
+        let code = encode(&SceCode {
+            insts: vec![
+                SceInst {
+                    addr: 0,
+                    code: 22,
+                    flags: 0,
+                    args: vec![
+                        SceArg::Int(-1),
+                        SceArg::Str("\u{4f60}\u{597d}\t\"q\"\0".as_bytes().to_vec()),
+                        SceArg::Int(0),
+                    ],
+                },
+                SceInst {
+                    addr: 0,
+                    code: 65,
+                    flags: 0,
+                    args: vec![SceArg::List(vec![
+                        SceListItem {
+                            tag: 1,
+                            text: vec![0xc4, 0xe3, 0],
+                        },
+                        SceListItem {
+                            tag: 2,
+                            text: vec![0xff, 0x01],
+                        },
+                    ])],
+                },
+                SceInst {
+                    addr: 0,
+                    code: 10,
+                    flags: 3,
+                    args: vec![SceArg::Var(1), SceArg::Var(-2)],
+                },
+                SceInst {
+                    addr: 0,
+                    code: 31,
+                    flags: 0,
+                    args: vec![
+                        SceArg::Float(-0.0),
+                        SceArg::Float(f32::from_bits(0x7fc0_0001)),
+                        SceArg::Float(1e-7),
+                    ],
+                },
+                SceInst {
+                    addr: 0,
+                    code: 12,
+                    flags: 0,
+                    args: vec![SceArg::Addr(3)],
+                },
+            ],
+            tail: vec![4, 0, 0, 0],
+            tail_reason: None,
+        })
+        .unwrap();
+        file.procs[1].code = code;
+        file.procs[1].header_name[63] = 7;
+        file.procs[0].offset += 1;
+        file.procs[1].locals.push(SceLocal {
+            kind: 0,
+            data: vec![0x80],
+        });
+
+        let listing = round_trip(&file);
+        assert!(listing.contains("    .offset "));
+        assert!(listing.contains("    .header_name x\"6f74686572"));
+        assert!(listing.contains("DlgSel [1 \"\u{4f60}\", 2 x\"ff01\"]"));
+        assert!(listing.contains("GEQ/3 $1 $-2"));
+        assert!(listing.contains("TestGoto @3"));
+        assert!(listing.contains("bits:0x7fc00001"));
+        assert!(listing.contains(".bytes x\"04000000\" ; unknown opcode 4"));
+    }
+
+    #[test]
+    fn hand_written_listing_assembles() {
+        let file = assemble_sce(
+            r#"
+            ; labels may be named freely and jump forward
+            .proc 7 "loop"
+                dlg "hi\x01"
+                goto done
+            again:
+                idle 0.5
+            done:
+                testgoto again
+            .end
+            "#,
+        )
+        .unwrap();
+
+        let proc = &file.procs[0];
+        let code = decode(&proc.code);
+        assert_eq!(code.insts[0].args[0], SceArg::Str(b"hi\x01\0".to_vec()));
+        assert_eq!(code.insts[1].args[0], SceArg::Addr(code.insts[3].addr));
+        assert_eq!(code.insts[3].args[0], SceArg::Addr(code.insts[2].addr));
+        assert_eq!(proc.offset, file.layout_offsets()[0]);
+    }
+
+    #[test]
+    fn syntax_errors_report_their_line() {
+        let err = |text: &str| match assemble_sce(text) {
+            Err(SceError::Syntax { line, message }) => (line, message),
+            other => panic!("{:?}", other),
+        };
+
+        assert_eq!(err(".proc 1 \"a\"\n  Bogus\n.end").0, 2);
+        assert_eq!(err(".proc 1 \"a\"\n  Let 1 2\n.end").0, 2);
+        assert_eq!(err(".proc 1 \"a\"\n  Goto nowhere\n.end").0, 2);
+        assert_eq!(err(".proc 1 \"a\"\n  Idle 1.0").0, 1);
+        assert!(err("Idle 1.0").1.contains("outside"));
+    }
+}
@@ -3,3 +3,4 @@ pub mod cam;
 pub mod evf;
 pub mod gob;
 pub mod ltmap;
+pub mod quest;
@@ -0,0 +1,58 @@
+//! PAL4 quest description files, the ones `giShowQuestDialog` names.
+//!
+//! GBK text: the first non-blank line is the quest title, the lines
+//! after it are the description shown under it in the offer dialog and
+//! the journal.
+
+use crate::utils::to_gbk_string;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuestFile {
+    pub title: String,
+    pub text: String,
+}
+
+impl QuestFile {
+    pub fn read(data: &[u8]) -> anyhow::Result<Self> {
+        let content = to_gbk_string(data)?;
+        let content = content.trim_start_matches('\u{feff}');
+        let mut lines = content
+            .lines()
+            .map(|line| line.trim_end())
+            .skip_while(|line| line.trim().is_empty());
+
+        let title = lines.next().unwrap_or_default().trim().to_string();
+        let text = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+
+        Ok(Self { title, text })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_title_and_text() {
+        let data = "\r\n  Title  \r\nFirst line\r\n\r\nSecond line\r\n\r\n".as_bytes();
+        let quest = QuestFile::read(data).unwrap();
+        assert_eq!(quest.title, "Title");
+        assert_eq!(quest.text, "First line\n\nSecond line");
+    }
+
+    #[test]
+    fn reads_gbk_text() {
+        // "任务" / "说明" in GBK.
+        let data = [0xc8, 0xce, 0xce, 0xf1, b'\n', 0xcb, 0xb5, 0xc3, 0xf7];
+        let quest = QuestFile::read(&data).unwrap();
+        assert_eq!(quest.title, "任务");
+        assert_eq!(quest.text, "说明");
+    }
+
+    #[test]
+    fn title_only() {
+        let quest = QuestFile::read(b"Title").unwrap();
+        assert_eq!(quest.title, "Title");
+        assert!(quest.text.is_empty());
+    }
+}
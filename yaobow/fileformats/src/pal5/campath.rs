@@ -0,0 +1,128 @@
+//! Camera path table for `camera.ChangeCameraPath(id)`.
+//!
+//! The retail data behind PAL5's camera paths hasn't been identified
+//! yet, so this is a provisional stand-in rather than a decoder of a
+//! shipped file: an INI in `MapInfo.ini`'s shape that a patched install
+//! can provide. One numbered `[N]` section per path id holds the run
+//! time in milliseconds and one `Key=` line per control point, in order.
+//! Each key is the camera eye followed by its look-at target:
+//!
+//! ```ini
+//! [104]
+//! Time=4000
+//! Key=3200,420,1800,3400,120,1500
+//! Key=3350,380,1650,3400,120,1500
+//! Key=3500,300,1500,3420,110,1480
+//! ```
+//!
+//! Sections without a key are dropped; malformed keys are skipped.
+
+use std::collections::HashMap;
+
+/// One control point of a camera path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraPathKey {
+    pub eye: [f32; 3],
+    pub look_at: [f32; 3],
+}
+
+/// A camera path: control points and the time to run them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraPath {
+    /// Run time in seconds; `0` when the section gives none.
+    pub duration: f32,
+    pub keys: Vec<CameraPathKey>,
+}
+
+/// Parsed camera path table, keyed by path id.
+pub struct CameraPathFile {
+    paths: HashMap<i32, CameraPath>,
+}
+
+impl CameraPathFile {
+    /// Parse the whole table (only ASCII keys / numbers are read, so lossy
+    /// UTF-8 is fine).
+    pub fn parse(text: &str) -> Self {
+        let mut paths = HashMap::new();
+        let mut section: Option<i32> = None;
+        let mut path = CameraPath {
+            duration: 0.0,
+            keys: Vec::new(),
+        };
+
+        let mut flush = |section: Option<i32>, path: &mut CameraPath| {
+            let path = std::mem::replace(
+                path,
+                CameraPath {
+                    duration: 0.0,
+                    keys: Vec::new(),
+                },
+            );
+            if let Some(id) = section
+                && !path.keys.is_empty()
+            {
+                paths.insert(id, path);
+            }
+        };
+
+        for line in text.lines() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            if let Some(header) = line.strip_prefix('[') {
+                flush(section, &mut path);
+                section = header.trim_end_matches(']').trim().parse().ok();
+                continue;
+            }
+            let Some((k, v)) = line.split_once('=') else {
+                continue;
+            };
+            match k.trim() {
+                "Time" => path.duration = v.trim().parse::<f32>().unwrap_or(0.0).max(0.0) / 1000.0,
+                "Key" => {
+                    let n: Vec<f32> = v.split(',').filter_map(|s| s.trim().parse().ok()).collect();
+                    if n.len() == 6 {
+                        path.keys.push(CameraPathKey {
+                            eye: [n[0], n[1], n[2]],
+                            look_at: [n[3], n[4], n[5]],
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        flush(section, &mut path);
+        Self { paths }
+    }
+
+    /// The path with script id `id`, if listed.
+    pub fn path(&self, id: i32) -> Option<&CameraPath> {
+        self.paths.get(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_become_paths_in_key_order() {
+        let ini = "[104]\nTime=4000\nKey=0,100,0,0,0,50\nKey=10,100,0,0,0,50 ; end\n[7]\nKey=1,2,3,4,5,6\n";
+        let file = CameraPathFile::parse(ini);
+
+        let path = file.path(104).unwrap();
+        assert_eq!(path.duration, 4.0);
+        assert_eq!(path.keys.len(), 2);
+        assert_eq!(path.keys[1].eye, [10.0, 100.0, 0.0]);
+        assert_eq!(path.keys[1].look_at, [0.0, 0.0, 50.0]);
+
+        assert_eq!(file.path(7).unwrap().duration, 0.0);
+    }
+
+    #[test]
+    fn empty_sections_and_malformed_keys_are_dropped() {
+        let ini = "[1]\nTime=1000\n[2]\nKey=1,2,3\nKey=1,2,3,4,5,x\n[3]\nKey=1,2,3,4,5,6\n";
+        let file = CameraPathFile::parse(ini);
+        assert!(file.path(1).is_none());
+        assert!(file.path(2).is_none());
+        assert!(file.path(3).is_some());
+    }
+}
@@ -62,6 +62,13 @@
 //! `_skybox_shushan`, `mojiedidong` → 2009 `_skybox_mojie`, …). The values
 //! `2001..=2026` merely *look* like calendar years, which is why an
 //! earlier "build year" reading of this field appeared plausible.
+//!
+//! ## Water
+//!
+//! No dedicated water record was identified: neither the fixed header nor
+//! the recognisable parts of the trailing region carry a colour or
+//! scalar that varies with whether a map has rivers/lakes, so nothing
+//! here describes water.
 
 use serde::Serialize;
 
@@ -31,9 +31,11 @@ pub struct MapSun {
     pub direction: Option<[f32; 3]>,
 }
 
-/// Parsed `MapInfo.ini`: per-map name -> sun config.
+/// Parsed `MapInfo.ini`: per-map name -> sun config, plus the map id
+/// (section number) -> name table scripts address maps by.
 pub struct MapInfoFile {
     maps: HashMap<String, MapSun>,
+    names: HashMap<i32, String>,
 }
 
 impl MapInfoFile {
@@ -42,6 +44,8 @@ impl MapInfoFile {
     /// its `Name=` value.
     pub fn parse(text: &str) -> Self {
         let mut maps = HashMap::new();
+        let mut names = HashMap::new();
+        let mut section: Option<i32> = None;
         let mut name: Option<String> = None;
         let mut sun = [None, None, None];
         let mut center = (2560.0f32, 2560.0f32);
@@ -68,9 +72,10 @@ impl MapInfoFile {
 
         for line in text.lines() {
             let line = line.trim();
-            if line.starts_with('[') {
+            if let Some(header) = line.strip_prefix('[') {
                 flush(&mut name, &mut sun, center);
                 center = (2560.0, 2560.0);
+                section = header.trim_end_matches(']').trim().parse().ok();
                 continue;
             }
             let Some((k, v)) = line.split_once('=') else {
@@ -78,7 +83,12 @@ impl MapInfoFile {
             };
             let (k, v) = (k.trim(), v.trim());
             match k {
-                "Name" => name = Some(v.to_string()),
+                "Name" => {
+                    if let Some(id) = section {
+                        names.insert(id, v.to_string());
+                    }
+                    name = Some(v.to_string());
+                }
                 "sunX" => sun[0] = v.parse().ok(),
                 "sunY" => sun[1] = v.parse().ok(),
                 "sunZ" => sun[2] = v.parse().ok(),
@@ -92,13 +102,19 @@ impl MapInfoFile {
             }
         }
         flush(&mut name, &mut sun, center);
-        Self { maps }
+        Self { maps, names }
     }
 
     /// Sun config for a map by `Name`, if listed.
     pub fn sun(&self, map_name: &str) -> Option<&MapSun> {
         self.maps.get(map_name)
     }
+
+    /// Map name for a script map id (the `[N]` section number), as used
+    /// by `map.ChangeNoScript` / `map.GetCurrentMapID`.
+    pub fn map_name(&self, map_id: i32) -> Option<&str> {
+        self.names.get(&map_id).map(String::as_str)
+    }
 }
 
 #[cfg(test)]
@@ -121,4 +137,14 @@ mod tests {
         let mi = MapInfoFile::parse(ini);
         assert!(mi.sun("kuangfengzhai").unwrap().direction.is_none());
     }
+
+    #[test]
+    fn section_numbers_map_ids_to_names() {
+        let ini = "[12]\nName=qingmucun\n[22]\nName=kuangfengzhai\n[x]\nName=bad\n";
+        let mi = MapInfoFile::parse(ini);
+        assert_eq!(mi.map_name(12), Some("qingmucun"));
+        assert_eq!(mi.map_name(22), Some("kuangfengzhai"));
+        assert_eq!(mi.map_name(1), None);
+        assert!(mi.sun("bad").is_some());
+    }
 }
@@ -1,6 +1,7 @@
 //! PAL5 (Chinese Paladin 5) asset format decoders.
 
 pub mod alp;
+pub mod campath;
 pub mod ctr;
 pub mod env;
 pub mod mapinfo;
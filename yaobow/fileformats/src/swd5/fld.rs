@@ -7,5 +7,51 @@ use super::Sized32Big5String;
 pub struct Fld {
     pub name: Sized32Big5String,
     pub map_file: Sized32Big5String,
-    // TODO: parse all the fields
+
+    // TODO: parse the remaining fields. Role placements are in here
+    // somewhere, but no layout has been checked against shipped files.
+    /// Everything after `map_file`, kept verbatim so a field
+    /// round-trips unchanged.
+    #[br(parse_with = binrw::helpers::until_eof)]
+    pub unknown: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn header() -> Vec<u8> {
+        let mut data = vec![];
+        for s in [&b"test"[..], &b"test.map"[..]] {
+            data.extend_from_slice(&(s.len() as u32).to_le_bytes());
+            data.extend_from_slice(s);
+        }
+        data
+    }
+
+    fn round_trip(data: &[u8]) -> Fld {
+        let fld = Fld::read(&mut Cursor::new(data)).unwrap();
+        let mut out = Cursor::new(vec![]);
+        fld.write(&mut out).unwrap();
+        assert_eq!(out.into_inner(), data);
+        fld
+    }
+
+    #[test]
+    fn header_only_field_round_trips() {
+        let fld = round_trip(&header());
+        assert_eq!(fld.map_file.to_string(), "test.map");
+        assert!(fld.unknown.is_empty());
+    }
+
+    #[test]
+    fn trailer_is_kept_undecoded() {
+        let mut data = header();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[0u8; 20]);
+        let fld = round_trip(&data);
+        assert_eq!(fld.unknown.len(), 24);
+    }
 }
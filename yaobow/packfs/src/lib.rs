@@ -128,13 +128,27 @@ fn mount_packages_recursive(
             },
             Some("zpk") => {
                 log::debug!("Mounting {:?} <- {:?}", &vfs_path, &path);
-                vfs = vfs.mount(vfs_path.clone(), ZpkFs::create(path).unwrap());
-                catalog.record(physical_relative_path, vfs_path, PackageType::Zpk);
+                match ZpkFs::create(&path) {
+                    Ok(zpk) => {
+                        vfs = vfs.mount(vfs_path.clone(), zpk);
+                        catalog.record(physical_relative_path, vfs_path, PackageType::Zpk);
+                    }
+                    Err(error) => {
+                        log::error!("Skipping unreadable ZPK package {:?}: {error:#}", &path);
+                    }
+                }
             }
             Some("zpkg") => {
                 log::debug!("Mounting {:?} <- {:?}", &vfs_path, &path);
-                vfs = vfs.mount(vfs_path.clone(), ZpkgFs::create(path).unwrap());
-                catalog.record(physical_relative_path, vfs_path, PackageType::Zpkg);
+                match ZpkgFs::create(&path) {
+                    Ok(zpkg) => {
+                        vfs = vfs.mount(vfs_path.clone(), zpkg);
+                        catalog.record(physical_relative_path, vfs_path, PackageType::Zpkg);
+                    }
+                    Err(error) => {
+                        log::error!("Skipping unreadable ZPKG package {:?}: {error:#}", &path);
+                    }
+                }
             }
             Some("zip") => {
                 log::debug!("Mounting {:?} <- {:?}", &vfs_path, &path);
@@ -79,10 +79,10 @@ impl ZpkArchive {
     }
 
     fn decompress_data(&self, file: &ZpkEntry, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        // Compression type 1 has no known sample, so it is reported as
+        // unsupported rather than guessed at.
         if file.compression_type == 0 {
             Ok(data.to_vec())
-        } else if file.compression_type == 1 {
-            unimplemented!("compression type 1 not implemented for zpk");
         } else if file.compression_type == 2 {
             let mut output = vec![];
             // lzma_rs::lzma_decompress(&mut Cursor::new(&data[4..]), &mut output)?;
@@ -102,12 +102,12 @@ impl ZpkArchive {
 
 impl PlainArchive for ZpkArchive {
     fn open<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<MemoryFile> {
-        let path = path.as_ref().to_str().unwrap();
+        let path = normalize_entry_name(path.as_ref().to_str().unwrap()).to_lowercase();
 
         if let Some(file) = self
             .entries
             .iter()
-            .find(|item| item.name == path.to_string())
+            .find(|item| item.name.to_lowercase() == path)
         {
             self.reader.seek(SeekFrom::Start(file.offset as u64))?;
 
@@ -135,6 +135,13 @@ impl PlainArchive for ZpkArchive {
     }
 }
 
+/// Gujian stores entry names with Windows separators and, for some
+/// packages, a leading separator. Entries are kept as `a/b/c.ext` so the
+/// flat [`PlainFs`](crate::plain_fs::PlainFs) listing round-trips them.
+fn normalize_entry_name(name: &str) -> String {
+    name.replace('\\', "/").trim_start_matches('/').to_string()
+}
+
 #[derive(Debug)]
 pub struct ZpkHeader {
     key: Vec<u8>,
@@ -205,7 +212,7 @@ impl ZpkEntry {
 
     fn read_one(reader: &mut Cursor<Vec<u8>>) -> anyhow::Result<Self> {
         let len = reader.read_u16_le()?;
-        let name = reader.read_string(len as usize)?;
+        let name = normalize_entry_name(&reader.read_string(len as usize)?);
         let encryption_type = reader.read_u8()?;
         let compression_type = reader.read_u8()?;
 
@@ -222,3 +229,18 @@ impl ZpkEntry {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_entry_name;
+
+    #[test]
+    fn entry_names_use_forward_slashes_without_leading_separator() {
+        assert_eq!(
+            normalize_entry_name("\\Model\\npc\\a.nif"),
+            "Model/npc/a.nif"
+        );
+        assert_eq!(normalize_entry_name("/texture/a.dds"), "texture/a.dds");
+        assert_eq!(normalize_entry_name("plain.ini"), "plain.ini");
+    }
+}
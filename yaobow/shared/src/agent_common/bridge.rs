@@ -8,9 +8,8 @@
 //!
 //! ## What does *not* live here
 //!
-//! * VM-side trace adapters (AngelScript for PAL4, future SceVm
-//!   adapter for PAL3) — they are game-specific and stay next to the
-//!   VM they bridge.
+//! * The VM-side trace adapter — see [`super::trace`]; the bridge
+//!   only owns the ring it pushes into.
 //! * Snapshot construction and the `AgentCommand` -> `AgentResponse`
 //!   dispatch — handled by `shared::openpalN::agent` modules that
 //!   know the per-game state shape.
@@ -1,5 +1,6 @@
 //! Game-agnostic handlers for the *generic* agent-server command
-//! subset (input, time/pause/step, screenshot, perf metrics).
+//! subset (input, time/pause/step, screenshot, perf metrics, script
+//! debugger, execution trace).
 //!
 //! These operate purely on the shared [`AgentBridge`] and have no
 //! per-game state, so every adapter (`openswd5::agent`,
@@ -10,12 +11,17 @@
 use std::rc::Rc;
 
 use agent_server::protocol::{
-    AgentError, AgentResponse, AxisInputParams, KeyAction, KeyInputParams, ScreenshotResponse,
-    StepTimeParams,
+    AgentCommand, AgentError, AgentResponse, AxisInputParams, DebugLocationPayload,
+    DebugProcedurePayload, DebugProceduresResponse, DebugStatusResponse, DebugVariablesResponse,
+    KeyAction, KeyInputParams, NamedGlobal, ScreenshotResponse, StepTimeParams, TraceDrainParams,
+    TraceDrainResponse, TraceStartParams,
 };
 use radiance::input::{Axis, Key};
 
 use crate::agent_common::AgentBridge;
+use crate::agent_common::trace::AgentTraceAdapter;
+use crate::scripting::debugger::{Breakpoint, DebugLocation, DebugValue, ScriptDebugTarget};
+use crate::scripting::trace::TraceSink;
 
 /// `/v1/input/key` — inject a synthetic key down/up/tap.
 pub fn handle_key_input(bridge: &Rc<AgentBridge>, params: KeyInputParams) -> AgentResponse {
@@ -117,3 +123,148 @@ pub fn handle_perf_metrics() -> AgentResponse {
         metrics,
     })
 }
+
+/// `/v1/script/trace/start` — arm the bridge's trace ring and hand a
+/// fresh [`AgentTraceAdapter`] to `install`, which puts it on the
+/// game's script VM. Re-installing on every start is harmless.
+pub fn handle_trace_start(
+    bridge: &Rc<AgentBridge>,
+    params: TraceStartParams,
+    install: impl FnOnce(Rc<dyn TraceSink>),
+) -> AgentResponse {
+    bridge.trace_sink.start(params.reset, params.capacity);
+    install(Rc::new(AgentTraceAdapter::new(bridge.trace_sink.clone())));
+    AgentResponse::Ok
+}
+
+/// `/v1/script/trace/stop` — stop capturing. Buffered events stay
+/// drainable until the next resetting start.
+pub fn handle_trace_stop(bridge: &Rc<AgentBridge>) -> AgentResponse {
+    bridge.trace_sink.stop();
+    AgentResponse::Ok
+}
+
+/// `/v1/script/trace/drain` — drain buffered events with `seq > after_seq`.
+pub fn handle_trace_drain(bridge: &Rc<AgentBridge>, params: TraceDrainParams) -> AgentResponse {
+    let n = params.n.unwrap_or(1024);
+    let result = bridge.trace_sink.drain(params.after_seq, n);
+    AgentResponse::TraceDrain(TraceDrainResponse {
+        next_seq: result.next_seq,
+        dropped: result.dropped,
+        capturing: bridge.trace_sink.is_capturing(),
+        events: result.events,
+    })
+}
+
+/// `/v1/debug/*` — drive the script debugger of the game's running VM.
+/// `target` is `None` when no script VM is up yet (title menu, loading).
+pub fn handle_debug_command(
+    target: Option<&dyn ScriptDebugTarget>,
+    command: AgentCommand,
+) -> AgentResponse {
+    let Some(target) = target else {
+        return AgentResponse::err(AgentError::conflict("no script VM is running"));
+    };
+
+    let state = target.debug_state();
+    match command {
+        AgentCommand::DebugStatus => {
+            let state = state.borrow();
+            let stop = state.stop();
+            AgentResponse::DebugStatus(DebugStatusResponse {
+                vm: target.debug_vm_name().to_string(),
+                stopped: stop.is_some(),
+                deferred_stops: target.debug_stops_deferred(),
+                reason: stop.map(|stop| stop.reason.to_string()),
+                location: stop
+                    .and_then(|stop| stop.location.clone())
+                    .or_else(|| target.debug_location())
+                    .map(location_payload),
+                breakpoints: state
+                    .breakpoints()
+                    .map(|bp| DebugLocationPayload {
+                        procedure: bp.procedure.clone(),
+                        offset: bp.offset,
+                    })
+                    .collect(),
+                watches: state
+                    .watches()
+                    .map(|(name, value)| named(name.to_string(), value.cloned()))
+                    .collect(),
+            })
+        }
+        AgentCommand::DebugProcedures => AgentResponse::DebugProcedures(DebugProceduresResponse {
+            procedures: target
+                .debug_procedures()
+                .into_iter()
+                .map(|p| DebugProcedurePayload {
+                    name: p.name,
+                    id: p.id,
+                })
+                .collect(),
+        }),
+        AgentCommand::DebugVariables => AgentResponse::DebugVariables(DebugVariablesResponse {
+            variables: target
+                .debug_variables()
+                .into_iter()
+                .map(|(name, value)| named(name, Some(value)))
+                .collect(),
+        }),
+        AgentCommand::DebugBreakpoint(params) => {
+            let breakpoint = Breakpoint {
+                procedure: params.procedure,
+                offset: params.offset,
+            };
+            if params.enabled {
+                state.borrow_mut().set_breakpoint(breakpoint);
+            } else {
+                state.borrow_mut().clear_breakpoint(&breakpoint);
+            }
+            AgentResponse::Ok
+        }
+        AgentCommand::DebugWatch(params) => {
+            if params.enabled {
+                target.add_watch(&params.name);
+            } else {
+                state.borrow_mut().remove_watch(&params.name);
+            }
+            AgentResponse::Ok
+        }
+        AgentCommand::DebugPause => {
+            state.borrow_mut().pause();
+            AgentResponse::Ok
+        }
+        AgentCommand::DebugContinue => {
+            state.borrow_mut().resume();
+            AgentResponse::Ok
+        }
+        AgentCommand::DebugStep => {
+            state.borrow_mut().step();
+            AgentResponse::Ok
+        }
+        other => AgentResponse::err(AgentError::bad_request(format!(
+            "not a debug command: {other:?}"
+        ))),
+    }
+}
+
+fn location_payload(location: DebugLocation) -> DebugLocationPayload {
+    DebugLocationPayload {
+        procedure: location.procedure,
+        offset: location.offset,
+    }
+}
+
+/// Marshal like [`NamedGlobal::value`]: unwalked values become a
+/// `"<tag>"` string, unreadable ones `null`.
+fn named(name: String, value: Option<DebugValue>) -> NamedGlobal {
+    let value = match value {
+        None | Some(DebugValue::Nil) => serde_json::Value::Null,
+        Some(DebugValue::Bool(v)) => v.into(),
+        Some(DebugValue::Int(v)) => v.into(),
+        Some(DebugValue::Float(v)) => v.into(),
+        Some(DebugValue::Str(v)) => v.into(),
+        Some(DebugValue::Other(tag)) => format!("<{tag}>").into(),
+    };
+    NamedGlobal { name, value }
+}
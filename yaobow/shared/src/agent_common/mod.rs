@@ -2,13 +2,14 @@
 //! (PAL3, PAL4, …).
 //!
 //! See the module docs in `bridge.rs` and `launch.rs` for details.
-//! Game-specific concerns (VM trace conversion, command dispatch,
-//! snapshot building) stay in each `openpalN::agent` module so this
-//! crate has no compile-time dependency on any particular VM.
+//! Game-specific concerns (command dispatch, snapshot building) stay
+//! in each `openpalN::agent` module. Trace conversion lives here: every
+//! VM emits the same `scripting::trace` events.
 
 pub mod bridge;
 pub mod handlers;
 pub mod launch;
+pub mod trace;
 
 pub use bridge::{AgentBridge, DEFAULT_STEP_DT};
 pub use launch::{AgentBootOptions, install_global_log_sink, start_agent_server};
@@ -248,6 +248,16 @@ impl YaobowConfig {
     pub fn set_scene_scale_mode(&mut self, mode: SceneScaleMode) {
         self.render.scene_scale_mode = mode;
     }
+
+    /// Persist a new master volume, clamped like [`Self::master_volume`].
+    /// Callers apply it to the live audio engine themselves.
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.audio.master_volume = if volume.is_finite() {
+            volume.clamp(0.0, 1.0)
+        } else {
+            default_master_volume()
+        };
+    }
 }
 
 #[cfg(test)]
@@ -430,4 +440,15 @@ mod tests {
         cfg.audio.master_volume = f32::NAN;
         assert_eq!(cfg.master_volume(), 0.7);
     }
+
+    #[test]
+    fn set_master_volume_sanitises_input() {
+        let mut cfg = YaobowConfig::default();
+        cfg.set_master_volume(0.25);
+        assert_eq!(cfg.audio.master_volume, 0.25);
+        cfg.set_master_volume(3.0);
+        assert_eq!(cfg.audio.master_volume, 1.0);
+        cfg.set_master_volume(f32::INFINITY);
+        assert_eq!(cfg.audio.master_volume, 0.7);
+    }
 }
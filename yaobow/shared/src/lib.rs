@@ -12,6 +12,7 @@ pub mod config_service;
 pub mod exporters;
 pub mod importers;
 pub mod loaders;
+pub mod opengujian;
 pub mod openpal3;
 pub mod openpal4;
 pub mod openpal5;
@@ -252,6 +253,7 @@ lazy_static::lazy_static! {
         dynamic_lighting: false,
         fog_exempt: false,
         foliage_resolver: None,
+        water: None,
     };
 
     static ref PAL5_DFF_LOADER_CONFIG: DffLoaderConfig::<'static> = DffLoaderConfig {
@@ -264,6 +266,7 @@ lazy_static::lazy_static! {
         dynamic_lighting: true,
         fog_exempt: false,
         foliage_resolver: None,
+        water: None,
     };
 
     static ref SWD5_DFF_LOADER_CONFIG: DffLoaderConfig::<'static> = DffLoaderConfig {
@@ -276,5 +279,6 @@ lazy_static::lazy_static! {
         dynamic_lighting: false,
         fog_exempt: false,
         foliage_resolver: None,
+        water: None,
     };
 }
@@ -140,6 +140,7 @@ fn create_geometry_from_atomic_sector<P: AsRef<Path>>(
         false,
         false,
         config.fog_exempt,
+        config.water,
         false,
     );
 }
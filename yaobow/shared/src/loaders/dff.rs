@@ -20,7 +20,7 @@ use radiance::{
     math::{Mat44, Vec3},
     rendering::{
         AddressMode, AlphaKind, BlendMode, ComponentFactory, CullMode, FilterMode, MaterialDef,
-        SamplerDef,
+        SamplerDef, WaterMaterialDef, WaterParams,
     },
     scene::CoreEntity,
 };
@@ -92,6 +92,13 @@ pub struct DffLoaderConfig<'a> {
     /// games leave it `None` (their DFFs carry no `prt` tag, so the path is a
     /// no-op for them anyway). See `generated/pal5_leaf_re.md`.
     pub foliage_resolver: Option<&'a dyn FoliageResolver>,
+
+    /// When set, every textured material of this DFF is rebuilt as a
+    /// [`WaterMaterialDef`] over its resolved base texture: alpha-blended,
+    /// reflective, with scrolling wave normals. Used for PAL5 river / lake
+    /// surfaces. Works with or without `dynamic_lighting`: the water material
+    /// follows the mesh's vertex layout. Default `None`.
+    pub water: Option<WaterParams>,
 }
 
 impl<'a> DffLoaderConfig<'a> {
@@ -106,6 +113,7 @@ impl<'a> DffLoaderConfig<'a> {
             dynamic_lighting: false,
             fog_exempt: false,
             foliage_resolver: None,
+            water: None,
         }
     }
 }
@@ -420,6 +428,7 @@ fn load_clump(
                         config.force_unique_materials,
                         config.dynamic_lighting,
                         config.fog_exempt,
+                        config.water,
                     );
                     let component =
                         BillboardComponent::create(entity.clone(), billboard_scale_pct(frame));
@@ -447,6 +456,7 @@ fn load_clump(
             two_sided,
             config.dynamic_lighting,
             config.fog_exempt,
+            config.water,
         );
 
         if billboard {
@@ -619,6 +629,7 @@ fn create_foliage_geometry(
     force_unique_materials: bool,
     dynamic_lighting: bool,
     fog_exempt: bool,
+    water: Option<WaterParams>,
 ) {
     let Some(vertices) = geometry
         .morph_targets
@@ -729,6 +740,7 @@ fn create_foliage_geometry(
         true, // two_sided
         dynamic_lighting,
         fog_exempt,
+        water,
         true, // force_alpha_test: leaf cards cast cutout shadows
     );
 }
@@ -747,6 +759,7 @@ fn create_geometry(
     two_sided: bool,
     dynamic_lighting: bool,
     fog_exempt: bool,
+    water: Option<WaterParams>,
 ) {
     if geometry.morph_targets.len() == 0 {
         return;
@@ -899,6 +912,7 @@ fn create_geometry(
         two_sided,
         dynamic_lighting,
         fog_exempt,
+        water,
         false,
     );
 }
@@ -920,6 +934,7 @@ pub(crate) fn create_geometry_internal(
     two_sided: bool,
     dynamic_lighting: bool,
     fog_exempt: bool,
+    water: Option<WaterParams>,
     force_alpha_test: bool,
 ) {
     let mut r_vertices = vec![];
@@ -1066,6 +1081,18 @@ pub(crate) fn create_geometry_internal(
                     md = md.with_params(p);
                 }
 
+                // Water surfaces keep the resolved base texture but swap the
+                // whole material (program, blend, cull, params) for the
+                // reflective water one. Untextured materials stay as-is.
+                let water_base = material
+                    .texture
+                    .as_ref()
+                    .and_then(|_| md.textures().first().cloned());
+                if let Some((water, base)) = water.zip(water_base) {
+                    let name = md.debug_name().to_string();
+                    md = WaterMaterialDef::create(&name, base, water, has_normals);
+                }
+
                 material_to_indices.push((
                     t.material,
                     MaterialGroupedIndices {
@@ -11,6 +11,7 @@ pub mod anm;
 pub mod bsp;
 pub mod cegui;
 pub mod dff;
+pub mod nif;
 pub mod smp;
 pub mod video_handle;
 
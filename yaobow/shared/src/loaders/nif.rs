@@ -0,0 +1,451 @@
+use std::{
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::Context;
+use binrw::BinRead;
+use common::store_ext::StoreExt2;
+use crosscom::ComRc;
+use fileformats::nif::{
+    NifModel,
+    blocks::{
+        ComponentFormat, DataStreamRef, MeshPrimitiveType, NiAVObject, NiAlphaProperty,
+        NiDataStream, NiMesh, NiNode, NiSourceTexture, NiTexturingProperty,
+    },
+};
+use mini_fs::{MiniFs, StoreExt};
+use radiance::{
+    comdef::{IEntity, IStaticMeshComponent},
+    components::mesh::{Geometry, StaticMeshComponent, TexCoord},
+    math::{Mat44, Vec3},
+    rendering::{BlendMode, ComponentFactory, MaterialDef, SimpleMaterialDef},
+    scene::CoreEntity,
+};
+
+/// Loads a Gamebryo NIF scene into an entity tree.
+///
+/// Every `NiNode` becomes an entity carrying the node's local transform
+/// and every `NiMesh` becomes an entity with a static mesh component, one
+/// geometry per submesh. Skinned meshes are shown in their bind pose.
+/// Gamebryo content is Z-up, so the returned root is rotated to the
+/// engine's Y-up convention.
+pub fn create_entity_from_nif_model<P: AsRef<Path>>(
+    component_factory: &Rc<dyn ComponentFactory>,
+    vfs: &MiniFs,
+    path: P,
+    name: String,
+    visible: bool,
+) -> anyhow::Result<ComRc<IEntity>> {
+    let path = path.as_ref();
+    let mut data = vec![];
+    vfs.open(path)
+        .with_context(|| format!("opening NIF {}", path.display()))?
+        .read_to_end(&mut data)
+        .with_context(|| format!("reading NIF {}", path.display()))?;
+    let model = NifModel::read(&mut Cursor::new(data))
+        .with_context(|| format!("parsing NIF {}", path.display()))?;
+
+    let entity = CoreEntity::create(name, visible);
+    entity
+        .transform()
+        .as_ref()
+        .borrow_mut()
+        .rotate_axis_angle_local(&Vec3::new(1., 0., 0.), -std::f32::consts::FRAC_PI_2);
+
+    let loader = NifLoader {
+        model: &model,
+        component_factory,
+        vfs,
+        path,
+    };
+    for &root in model.roots() {
+        if let Some(child) = loader.load_block(root, &[]) {
+            entity.attach(child);
+        }
+    }
+
+    Ok(entity)
+}
+
+struct NifLoader<'a> {
+    model: &'a NifModel,
+    component_factory: &'a Rc<dyn ComponentFactory>,
+    vfs: &'a MiniFs,
+    path: &'a Path,
+}
+
+impl<'a> NifLoader<'a> {
+    /// Builds the entity for block `index`. `inherited` holds the
+    /// property refs of the ancestors; Gamebryo properties apply to the
+    /// whole subtree unless a descendant overrides them.
+    fn load_block(&self, index: i32, inherited: &[i32]) -> Option<ComRc<IEntity>> {
+        if let Some(node) = self.model.block::<NiNode>(index) {
+            let entity = self.create_entity(index, &node.av_object);
+            let properties = merge_properties(inherited, &node.av_object);
+            for &child in &node.children {
+                if let Some(child) = self.load_block(child as i32, &properties) {
+                    entity.attach(child);
+                }
+            }
+
+            Some(entity)
+        } else if let Some(mesh) = self.model.block::<NiMesh>(index) {
+            let av_object = &mesh.render_object.av_object;
+            let entity = self.create_entity(index, av_object);
+            let properties = merge_properties(inherited, av_object);
+            let geometries = self.create_geometries(mesh, &properties);
+            if !geometries.is_empty() {
+                let mesh_component = StaticMeshComponent::new(
+                    entity.clone(),
+                    geometries,
+                    self.component_factory.clone(),
+                );
+                entity.add_component(
+                    IStaticMeshComponent::uuid(),
+                    ComRc::from_object(mesh_component),
+                );
+            }
+
+            Some(entity)
+        } else {
+            None
+        }
+    }
+
+    fn create_entity(&self, index: i32, av_object: &NiAVObject) -> ComRc<IEntity> {
+        let name = self
+            .model
+            .string(av_object.object_net.name)
+            .unwrap_or_else(|| format!("block_{}", index));
+        let entity = CoreEntity::create(name, av_object.flags & 1 == 0);
+        entity
+            .transform()
+            .as_ref()
+            .borrow_mut()
+            .set_matrix(create_matrix(av_object));
+
+        entity
+    }
+
+    fn create_geometries(&self, mesh: &NiMesh, properties: &[i32]) -> Vec<Geometry> {
+        let primitive_type = mesh.primitive_type;
+        if primitive_type != MeshPrimitiveType::MESH_PRIMITIVE_TRIANGLES
+            && primitive_type != MeshPrimitiveType::MESH_PRIMITIVE_TRISTRIPS
+        {
+            log::debug!(
+                "Skipping NiMesh with primitive type {} in {:?}",
+                primitive_type.0,
+                self.path
+            );
+            return vec![];
+        }
+
+        let material = self.create_material(properties);
+        let mut geometries = vec![];
+        for sub_mesh in 0..mesh.num_sub_meshes as usize {
+            let positions = self
+                .read_semantic(mesh, "POSITION", sub_mesh)
+                .or_else(|| self.read_semantic(mesh, "POSITION_BP", sub_mesh));
+            let Some(positions) = positions else {
+                continue;
+            };
+
+            let vertices: Vec<Vec3> = positions
+                .iter()
+                .map(|p| Vec3::new(p[0], p[1], p[2]))
+                .collect();
+            let normals: Option<Vec<Vec3>> = self
+                .read_semantic(mesh, "NORMAL", sub_mesh)
+                .or_else(|| self.read_semantic(mesh, "NORMAL_BP", sub_mesh))
+                .filter(|n| n.len() == vertices.len())
+                .map(|n| n.iter().map(|n| Vec3::new(n[0], n[1], n[2])).collect());
+            let texcoords: Vec<TexCoord> = self
+                .read_semantic(mesh, "TEXCOORD", sub_mesh)
+                .filter(|t| t.len() == vertices.len())
+                .map(|t| t.iter().map(|t| TexCoord::new(t[0], t[1])).collect())
+                .unwrap_or_else(|| vec![TexCoord::new(0., 0.); vertices.len()]);
+
+            let indices: Vec<u32> = match self.read_semantic(mesh, "INDEX", sub_mesh) {
+                Some(indices) => indices.iter().map(|i| i[0] as u32).collect(),
+                None => (0..vertices.len() as u32).collect(),
+            };
+            let indices = if primitive_type == MeshPrimitiveType::MESH_PRIMITIVE_TRISTRIPS {
+                strip_to_triangles(&indices)
+            } else {
+                indices
+            };
+
+            let indices: Vec<u32> = indices
+                .chunks_exact(3)
+                .filter(|t| t.iter().all(|&i| (i as usize) < vertices.len()))
+                .flatten()
+                .copied()
+                .collect();
+            if indices.len() < 3 {
+                continue;
+            }
+
+            geometries.push(Geometry::new(
+                &vertices,
+                normals.as_deref(),
+                &[texcoords],
+                indices,
+                material.clone(),
+            ));
+        }
+
+        geometries
+    }
+
+    /// Decodes the elements of `semantic` (index 0) that belong to
+    /// `sub_mesh`, one `Vec<f32>` of scalars per element.
+    fn read_semantic(
+        &self,
+        mesh: &NiMesh,
+        semantic: &str,
+        sub_mesh: usize,
+    ) -> Option<Vec<Vec<f32>>> {
+        mesh.data_stream_ref.iter().find_map(|stream_ref| {
+            let component = stream_ref.semantic_data.iter().position(|s| {
+                s.index == 0 && self.model.string(s.name as u32).as_deref() == Some(semantic)
+            })?;
+            let stream = self.model.block::<NiDataStream>(stream_ref.stream)?;
+            read_component(stream, stream_ref, component, sub_mesh)
+        })
+    }
+
+    fn create_material(&self, properties: &[i32]) -> MaterialDef {
+        let texture = properties
+            .iter()
+            .find_map(|&p| self.model.block::<NiTexturingProperty>(p))
+            .and_then(|t| t.base_texture.texture.as_ref())
+            .and_then(|desc| self.model.block::<NiSourceTexture>(desc.source))
+            .and_then(|source| self.model.string(source.file_name));
+
+        let md = match texture {
+            Some(name) => {
+                let data = self.resolve_texture(&name);
+                if data.is_none() {
+                    log::warn!("Failed to resolve texture {} for {:?}", name, self.path);
+                }
+                SimpleMaterialDef::create2(&name, data)
+            }
+            None => SimpleMaterialDef::create2("missing", None),
+        };
+
+        let blend = properties
+            .iter()
+            .find_map(|&p| self.model.block::<NiAlphaProperty>(p))
+            .map(|alpha| {
+                if alpha.blend_enabled() {
+                    BlendMode::AlphaBlend
+                } else if alpha.test_enabled() {
+                    BlendMode::AlphaTest
+                } else {
+                    BlendMode::Opaque
+                }
+            })
+            .unwrap_or(BlendMode::Opaque);
+
+        md.with_blend(blend)
+    }
+
+    fn resolve_texture(&self, texture_name: &str) -> Option<Vec<u8>> {
+        let candidates = nif_texture_candidates(self.path, texture_name);
+        let mut data = vec![];
+        let _ = self
+            .vfs
+            .try_open_files(&candidates)
+            .ok()?
+            .read_to_end(&mut data)
+            .ok()?;
+
+        Some(data)
+    }
+}
+
+/// The object's own properties followed by its ancestors', so a lookup
+/// by block type finds the closest one first.
+fn merge_properties(inherited: &[i32], av_object: &NiAVObject) -> Vec<i32> {
+    av_object
+        .properties
+        .iter()
+        .map(|&p| p as i32)
+        .chain(inherited.iter().copied())
+        .collect()
+}
+
+/// Node transforms are stored as translation, rotation and uniform scale.
+fn create_matrix(av_object: &NiAVObject) -> Mat44 {
+    let r = &av_object.rotation;
+    let t = &av_object.translation;
+    let s = av_object.scale;
+
+    let mut mat = Mat44::new_identity();
+    let rows = [
+        [r.m11, r.m12, r.m13, t.x],
+        [r.m21, r.m22, r.m23, t.y],
+        [r.m31, r.m32, r.m33, t.z],
+    ];
+    for (i, row) in rows.iter().enumerate() {
+        for j in 0..3 {
+            mat.floats_mut()[i][j] = row[j] * s;
+        }
+        mat.floats_mut()[i][3] = row[3];
+    }
+
+    mat
+}
+
+/// Ordered VFS paths to probe for a texture referenced from the NIF at
+/// `model_path`. Gujian writes texture names relative to the data root,
+/// with Windows separators and sometimes `..\` prefixes, so the name is
+/// tried as-is, next to the model, and by file name alone.
+fn nif_texture_candidates(model_path: &Path, texture_name: &str) -> Vec<PathBuf> {
+    let normalised = texture_name.replace('\\', "/");
+    let trimmed = normalised.trim_start_matches("../").trim_start_matches('/');
+    let model_dir = model_path.parent().unwrap_or_else(|| Path::new("/"));
+
+    let mut paths = vec![PathBuf::from("/").join(trimmed), model_dir.join(trimmed)];
+    if let Some(file_name) = Path::new(trimmed).file_name() {
+        paths.push(model_dir.join(file_name));
+    }
+
+    paths.dedup();
+    paths
+}
+
+/// Reads component `component` of every element in the stream region
+/// mapped to `sub_mesh`.
+fn read_component(
+    stream: &NiDataStream,
+    stream_ref: &DataStreamRef,
+    component: usize,
+    sub_mesh: usize,
+) -> Option<Vec<Vec<f32>>> {
+    let formats = &stream.component_formats;
+    let stride: usize = formats.iter().map(|f| f.size()).sum();
+    let offset: usize = formats[..component].iter().map(|f| f.size()).sum();
+    let format = *formats.get(component)?;
+    if stride == 0 {
+        return None;
+    }
+
+    let region_index = *stream_ref.sub_mesh_to_region_map.get(sub_mesh)? as usize;
+    let region = stream.regions.get(region_index)?;
+    let start = region.start_index as usize;
+    let end = start + region.num_indices as usize;
+
+    (start..end)
+        .map(|i| {
+            let begin = i * stride + offset;
+            let bytes = stream.data.get(begin..begin + format.size())?;
+            decode_element(format, bytes)
+        })
+        .collect()
+}
+
+fn decode_element(format: ComponentFormat, bytes: &[u8]) -> Option<Vec<f32>> {
+    let kind = format.0 & 0xff;
+    let size = format.scalar_size();
+    let scalars = bytes.chunks_exact(size).map(|b| match (kind, size) {
+        (0x35..=0x38, 4) => Some(f32::from_le_bytes(b.try_into().ok()?)),
+        (0x31..=0x34, 2) => Some(f16_to_f32(u16::from_le_bytes(b.try_into().ok()?))),
+        (0x25..=0x28, 4) => Some(u32::from_le_bytes(b.try_into().ok()?) as f32),
+        (0x21..=0x24, 4) => Some(i32::from_le_bytes(b.try_into().ok()?) as f32),
+        (0x15..=0x18, 2) => Some(u16::from_le_bytes(b.try_into().ok()?) as f32),
+        (0x11..=0x14, 2) => Some(i16::from_le_bytes(b.try_into().ok()?) as f32),
+        (0x1d..=0x20, 2) => Some(u16::from_le_bytes(b.try_into().ok()?) as f32 / 65535.),
+        (0x19..=0x1c, 2) => Some(i16::from_le_bytes(b.try_into().ok()?) as f32 / 32767.),
+        (0x05..=0x08, 1) => Some(b[0] as f32),
+        (0x01..=0x04, 1) => Some(b[0] as i8 as f32),
+        (0x0d..=0x10, 1) | (0x3c, 1) => Some(b[0] as f32 / 255.),
+        (0x09..=0x0c, 1) => Some(b[0] as i8 as f32 / 127.),
+        _ => None,
+    });
+
+    scalars.collect()
+}
+
+fn f16_to_f32(h: u16) -> f32 {
+    let sign = if h & 0x8000 != 0 { -1. } else { 1. };
+    let exponent = ((h >> 10) & 0x1f) as i32;
+    let mantissa = (h & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0. => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1. + mantissa / 1024.) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// Expands a triangle strip into a triangle list, keeping a consistent
+/// winding and dropping the degenerate triangles used to join strips.
+fn strip_to_triangles(strip: &[u32]) -> Vec<u32> {
+    let mut indices = vec![];
+    for i in 2..strip.len() {
+        let (a, b, c) = if i % 2 == 0 {
+            (strip[i - 2], strip[i - 1], strip[i])
+        } else {
+            (strip[i - 1], strip[i - 2], strip[i])
+        };
+
+        if a != b && b != c && a != c {
+            indices.extend_from_slice(&[a, b, c]);
+        }
+    }
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_keeps_winding_and_drops_degenerates() {
+        assert_eq!(
+            strip_to_triangles(&[0, 1, 2, 3, 3, 4]),
+            vec![0, 1, 2, 2, 1, 3]
+        );
+    }
+
+    #[test]
+    fn decodes_float_and_half_components() {
+        let bytes: Vec<u8> = [1.5f32, -2.0]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        assert_eq!(
+            decode_element(ComponentFormat::FLOAT32_2, &bytes),
+            Some(vec![1.5, -2.0])
+        );
+
+        let half = [0x3c00u16, 0xc000]
+            .iter()
+            .flat_map(|h| h.to_le_bytes())
+            .collect::<Vec<u8>>();
+        assert_eq!(
+            decode_element(ComponentFormat::FLOAT16_2, &half),
+            Some(vec![1.0, -2.0])
+        );
+        assert_eq!(
+            decode_element(ComponentFormat::UINT16_1, &7u16.to_le_bytes()),
+            Some(vec![7.0])
+        );
+    }
+
+    #[test]
+    fn texture_candidates_try_root_model_dir_and_file_name() {
+        assert_eq!(
+            nif_texture_candidates(Path::new("/model/npc/a.nif"), "..\\texture\\npc\\a.dds"),
+            vec![
+                PathBuf::from("/texture/npc/a.dds"),
+                PathBuf::from("/model/npc/texture/npc/a.dds"),
+                PathBuf::from("/model/npc/a.dds"),
+            ]
+        );
+    }
+}
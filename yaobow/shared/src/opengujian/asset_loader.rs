@@ -0,0 +1,100 @@
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crosscom::ComRc;
+use mini_fs::{EntryKind, MiniFs, StoreExt};
+use radiance::{
+    comdef::{IEntity, IScene},
+    rendering::ComponentFactory,
+    scene::CoreScene,
+};
+
+use crate::{GameType, loaders::nif::create_entity_from_nif_model};
+
+pub struct AssetLoader {
+    game: GameType,
+    vfs: Rc<MiniFs>,
+    component_factory: Rc<dyn ComponentFactory>,
+}
+
+impl AssetLoader {
+    pub fn new(
+        component_factory: Rc<dyn ComponentFactory>,
+        vfs: Rc<MiniFs>,
+        game: GameType,
+    ) -> Rc<Self> {
+        Rc::new(Self {
+            game,
+            vfs,
+            component_factory,
+        })
+    }
+
+    pub fn game(&self) -> GameType {
+        self.game
+    }
+
+    pub fn component_factory(&self) -> Rc<dyn ComponentFactory> {
+        self.component_factory.clone()
+    }
+
+    pub fn vfs(&self) -> &MiniFs {
+        &self.vfs
+    }
+
+    pub fn vfs_rc(&self) -> Rc<MiniFs> {
+        self.vfs.clone()
+    }
+
+    pub fn load_model(&self, path: &str) -> anyhow::Result<ComRc<IEntity>> {
+        let name = Path::new(path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or(path)
+            .to_string();
+        create_entity_from_nif_model(&self.component_factory, &self.vfs, path, name, true)
+    }
+
+    /// A scene holding only the model at `path`, for free viewing.
+    pub fn load_model_scene(&self, path: &str) -> anyhow::Result<ComRc<IScene>> {
+        let model = self.load_model(path)?;
+        let scene = CoreScene::create();
+        scene.add_entity(model);
+        Ok(scene)
+    }
+
+    /// Every `.nif` in the mounted install, sorted. Packages are flat
+    /// archives, so files inside them show up with `$` in place of the
+    /// directory separator; those names open as listed.
+    pub fn model_paths(&self) -> Vec<String> {
+        let mut paths = vec![];
+        let mut stack = vec![PathBuf::from("/")];
+        while let Some(dir) = stack.pop() {
+            let Ok(entries) = self.vfs.entries(&dir) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let Some(name) = Path::new(&entry.name).file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+
+                let child = dir.join(name);
+                match entry.kind {
+                    EntryKind::Dir => stack.push(child),
+                    EntryKind::File if name.to_ascii_lowercase().ends_with(".nif") => {
+                        if let Some(child) = child.to_str() {
+                            paths.push(child.to_string());
+                        }
+                    }
+                    EntryKind::File => {}
+                }
+            }
+        }
+
+        paths.sort();
+        paths
+    }
+}
@@ -32,8 +32,9 @@ use radiance::comdef::ISceneManager;
 use radiance::input::{Axis, Key};
 use radiance::math::Vec3;
 
-use crate::agent_common::AgentBridge;
+use crate::agent_common::{AgentBridge, handlers};
 use crate::openpal3::directors::AdventureDirector;
+use crate::scripting::debugger::ScriptDebugTarget;
 
 /// Default size of the dense window returned by `/v1/script/globals`
 /// when the caller omits `limit`. PAL3 globals are written sparsely
@@ -140,10 +141,24 @@ pub fn dispatch_pal3_command(ctx: &Pal3DispatchCtx, command: AgentCommand) -> Ag
         C::ScriptEval(_) => AgentResponse::err(AgentError::not_implemented(
             "PAL3 script_eval is not supported (no AngelScript VM)",
         )),
-        C::TraceStart(_) | C::TraceStop | C::TraceDrain(_) => {
-            AgentResponse::err(AgentError::not_implemented(
-                "PAL3 SceVm has no trace adapter yet; /v1/script/trace/* unavailable",
-            ))
+        // The sink lives on the director's SceVm, so a new game or a
+        // load needs another start.
+        C::TraceStart(p) => match ctx.director {
+            Some(director) => handlers::handle_trace_start(ctx.bridge, p, |sink| {
+                director.sce_vm_mut().set_trace_sink(Some(sink))
+            }),
+            None => AgentResponse::err(AgentError::conflict(
+                "no SCE VM is running; start or load a game before tracing",
+            )),
+        },
+        C::TraceStop => handlers::handle_trace_stop(ctx.bridge),
+        C::TraceDrain(p) => handlers::handle_trace_drain(ctx.bridge, p),
+        c if c.is_debug() => {
+            let sce_vm = ctx.director.map(|d| d.sce_vm());
+            handlers::handle_debug_command(
+                sce_vm.as_deref().map(|vm| vm as &dyn ScriptDebugTarget),
+                c,
+            )
         }
 
         // AgentCommand is `#[non_exhaustive]` — surface any future
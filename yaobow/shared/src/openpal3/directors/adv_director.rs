@@ -360,9 +360,11 @@ impl AdventureDirectorProps {
         // update a no-op, so NPC patrols, role animations and world
         // transforms all hold their pose; we also skip the world sim
         // below so movement/triggers stop too.
+        // The script debugger holding the VM freezes the world the same
+        // way, so roles don't walk on while a proc is stopped.
         let adv_enabled = self.sce_vm.global_state().adv_input_enabled();
         let menu_open = self.sce_vm.state().status_renderer().is_menu_open();
-        let freeze = adv_enabled && menu_open;
+        let freeze = (adv_enabled && menu_open) || self.sce_vm.debug_held();
         if let Some(scene) = self.scene_manager.scene() {
             scene.set_active(!freeze);
         }
@@ -1,11 +1,15 @@
-use byteorder::{LittleEndian, ReadBytesExt};
-use common::read_ext::ReadExt;
+use fileformats::pal3::sce as raw;
 use mini_fs::{MiniFs, StoreExt};
 use serde::Serialize;
 use std::collections::HashMap;
-use std::io::{BufReader, Read};
+use std::io::BufReader;
 use std::path::Path;
 
+// Byte-level parsing lives in `fileformats::pal3::sce`, which keeps every
+// field exactly as encoded and also owns the opcode table and instruction
+// decoder the VM runs on. This module only maps the raw file onto the
+// id-keyed view the VM and the asset manager use.
+
 #[derive(Debug, Serialize)]
 pub struct SceLocalVar {
     pub unknown: u8,
@@ -36,31 +40,43 @@ pub struct SceFile {
 
 pub fn sce_load_from_file<P: AsRef<Path>>(vfs: &MiniFs, path: P) -> SceFile {
     let mut reader = BufReader::new(vfs.open(path).unwrap());
-    let mut magic = [0u8; 4];
-    reader.read_exact(&mut magic).unwrap();
-
-    match magic {
-        [0x53, 0x43, 0x45, 0x00] => (), // "SCE"
-        _ => panic!("Not a valid sce file"),
-    }
-
-    let magic2 = reader.read_u8().unwrap();
-    if magic2 != 1 {
-        panic!("Not a valid sce file");
-    }
-
-    let proc_num = reader.read_u16::<LittleEndian>().unwrap();
-    let mut proc_headers = vec![];
-    for _ in 0..proc_num {
-        let header = read_sce_proc_header(&mut reader);
-        proc_headers.push(header);
-    }
-
-    let mut procs = HashMap::new();
-    for _ in 0..proc_num {
-        let proc = read_sce_proc(&mut reader);
-        procs.insert(proc.id, proc);
-    }
+    let file = raw::read_sce(&mut reader).unwrap();
+
+    let proc_headers = file
+        .procs
+        .iter()
+        .map(|proc| SceProcHeader {
+            id: proc.id,
+            offset: proc.offset,
+            name: proc.header_name_string(),
+        })
+        .collect();
+
+    let proc_num = file.procs.len() as u16;
+    let procs = file
+        .procs
+        .into_iter()
+        .map(|proc| {
+            let name = proc.name_string();
+            let local_vars = proc
+                .locals
+                .into_iter()
+                .map(|local| SceLocalVar {
+                    unknown: local.kind,
+                    unknown_vec: local.data,
+                })
+                .collect();
+            (
+                proc.id,
+                SceProc {
+                    id: proc.id,
+                    name,
+                    local_vars,
+                    inst: proc.code,
+                },
+            )
+        })
+        .collect();
 
     SceFile {
         proc_num,
@@ -68,39 +84,3 @@ pub fn sce_load_from_file<P: AsRef<Path>>(vfs: &MiniFs, path: P) -> SceFile {
         procs,
     }
 }
-
-fn read_sce_proc_header(reader: &mut dyn Read) -> SceProcHeader {
-    let id = reader.read_u32::<LittleEndian>().unwrap();
-    let offset = reader.read_u32::<LittleEndian>().unwrap();
-    let name = reader.read_gbk_string(64).unwrap();
-
-    SceProcHeader { id, offset, name }
-}
-
-fn read_sce_proc(reader: &mut dyn Read) -> SceProc {
-    let id = reader.read_u32::<LittleEndian>().unwrap();
-    let name_len = reader.read_u16::<LittleEndian>().unwrap();
-    let name = reader.read_gbk_string(name_len as usize).unwrap();
-    let local_var_num = reader.read_u16::<LittleEndian>().unwrap();
-
-    let mut local_vars = vec![];
-    for _ in 0..local_var_num {
-        let u = reader.read_u8().unwrap();
-        let size = reader.read_u16::<LittleEndian>().unwrap();
-        let unknown_vec = reader.read_u8_vec(size as usize).unwrap();
-        local_vars.push(SceLocalVar {
-            unknown: u,
-            unknown_vec,
-        });
-    }
-
-    let inst_size = reader.read_u32::<LittleEndian>().unwrap();
-    let inst = reader.read_u8_vec(inst_size as usize).unwrap();
-
-    SceProc {
-        id,
-        name,
-        local_vars,
-        inst,
-    }
-}
@@ -73,6 +73,13 @@ impl PersistentState {
         self.global_vars.get(&var).and_then(|v| Some(*v))
     }
 
+    /// Every written global, ordered by variable index.
+    pub fn globals(&self) -> Vec<(i16, i32)> {
+        let mut globals: Vec<_> = self.global_vars.iter().map(|(k, v)| (*k, *v)).collect();
+        globals.sort();
+        globals
+    }
+
     pub fn position(&mut self) -> Vec3 {
         self.position
     }
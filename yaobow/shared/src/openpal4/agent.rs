@@ -3,8 +3,8 @@
 //! Combines the shared, game-agnostic [`AgentBridge`] (queue,
 //! synthetic input, frame counters, pause/step cells, trace sink,
 //! rendering engine slot) with the PAL4-specific
-//! [`AgentTraceAdapter`] that forwards AngelScript-VM
-//! [`TraceEvent`]s into the shared `AgentTraceSink` ring buffer.
+//! [`AgentTraceAdapter`] that forwards AngelScript-VM trace events
+//! into the shared `AgentTraceSink` ring buffer.
 //!
 //! `Pal4AgentBridge` exists purely to bolt the PAL4 trace adapter
 //! onto the shared bridge; it transparently delegates every other
@@ -16,20 +16,14 @@
 use std::ops::Deref;
 use std::rc::Rc;
 
-use agent_server::AgentTraceSink;
-use agent_server::protocol::{
-    TraceBranchKind, TraceEventKindPayload, TraceEventPayload, TraceGlobalScope,
-};
 use radiance::input::SyntheticInputBridge;
 
 use crate::agent_common::bridge::AgentBridge;
-use crate::scripting::angelscript::{
-    BranchKind, GlobalScope, TraceEvent, TraceEventKind, TraceSink,
-};
 
-// Re-export the shared default so existing call-sites importing it
-// from this module keep compiling.
+// Re-export the shared default and adapter so existing call-sites
+// importing them from this module keep compiling.
 pub use crate::agent_common::bridge::DEFAULT_STEP_DT;
+pub use crate::agent_common::trace::AgentTraceAdapter;
 
 /// PAL4-flavoured [`AgentBridge`]: the shared bridge plus the
 /// AngelScript [`AgentTraceAdapter`]. Held by the director and
@@ -67,135 +61,3 @@ impl Deref for Pal4AgentBridge {
         &self.inner
     }
 }
-
-/// VM-side trace sink that forwards every [`TraceEvent`] into the
-/// agent-server's [`AgentTraceSink`] ring buffer.
-///
-/// Construct one via [`AgentTraceAdapter::new`] and install it on
-/// `ScriptVm::set_trace_sink` via a `Rc<dyn TraceSink>` coercion.
-/// The conversion is a straight one-to-one mapping; field renames
-/// (e.g. `usize` → `u32` for the JSON wire form) happen here so the
-/// VM-side enum stays free of agent-server concerns.
-pub struct AgentTraceAdapter {
-    sink: Rc<AgentTraceSink>,
-}
-
-impl AgentTraceAdapter {
-    pub fn new(sink: Rc<AgentTraceSink>) -> Self {
-        Self { sink }
-    }
-
-    pub fn sink(&self) -> &AgentTraceSink {
-        &self.sink
-    }
-}
-
-impl TraceSink for AgentTraceAdapter {
-    fn record(&self, event: TraceEvent) {
-        // The ring buffer assigns its own monotonic seq; the
-        // VM-side seq is ignored on the wire. The payload's `seq`
-        // field is overwritten by `AgentTraceSink::push`.
-        self.sink.push(TraceEventPayload {
-            seq: event.seq,
-            kind: convert_kind(event.kind),
-        });
-    }
-}
-
-fn convert_kind(kind: TraceEventKind) -> TraceEventKindPayload {
-    match kind {
-        TraceEventKind::FnEnter {
-            name,
-            function_index,
-            depth,
-        } => TraceEventKindPayload::FnEnter {
-            name,
-            function_index: function_index as u32,
-            depth: depth as u32,
-        },
-        TraceEventKind::FnExit { name, depth } => TraceEventKindPayload::FnExit {
-            name,
-            depth: depth as u32,
-        },
-        TraceEventKind::Branch {
-            fn_name,
-            pc,
-            kind,
-            operand,
-            offset,
-            taken,
-        } => TraceEventKindPayload::Branch {
-            fn_name,
-            pc: pc as u32,
-            branch: convert_branch(kind),
-            operand,
-            offset,
-            taken,
-        },
-        TraceEventKind::CallSys {
-            fn_name,
-            pc,
-            sysfn_index,
-            sysfn_name,
-            sp_before,
-            sp_after,
-            r1_after,
-        } => TraceEventKindPayload::CallSys {
-            fn_name,
-            pc: pc as u32,
-            sysfn_index: sysfn_index as u32,
-            sysfn_name,
-            sp_before: sp_before as u32,
-            sp_after: sp_after as u32,
-            r1_after,
-        },
-        TraceEventKind::GlobalRead {
-            fn_name,
-            pc,
-            scope,
-            slot,
-            value,
-        } => TraceEventKindPayload::GlobalRead {
-            fn_name,
-            pc: pc as u32,
-            scope: convert_scope(scope),
-            slot,
-            value,
-        },
-        TraceEventKind::GlobalWrite {
-            fn_name,
-            pc,
-            scope,
-            slot,
-            value,
-        } => TraceEventKindPayload::GlobalWrite {
-            fn_name,
-            pc: pc as u32,
-            scope: convert_scope(scope),
-            slot,
-            value,
-        },
-        TraceEventKind::Suspend { fn_name, pc } => TraceEventKindPayload::Suspend {
-            fn_name,
-            pc: pc as u32,
-        },
-    }
-}
-
-fn convert_branch(kind: BranchKind) -> TraceBranchKind {
-    match kind {
-        BranchKind::Jz => TraceBranchKind::Jz,
-        BranchKind::Jnz => TraceBranchKind::Jnz,
-        BranchKind::JsJgez => TraceBranchKind::JsJgez,
-        BranchKind::JnsJlz => TraceBranchKind::JnsJlz,
-        BranchKind::JpJlez => TraceBranchKind::JpJlez,
-        BranchKind::JnpJgz => TraceBranchKind::JnpJgz,
-    }
-}
-
-fn convert_scope(scope: GlobalScope) -> TraceGlobalScope {
-    match scope {
-        GlobalScope::Shared => TraceGlobalScope::Shared,
-        GlobalScope::Module => TraceGlobalScope::Module,
-    }
-}
@@ -11,7 +11,7 @@ use crosscom::ComRc;
 use fileformats::{
     binrw::BinRead,
     npc::NpcInfoFile,
-    pal4::{cam::CameraDataFile, evf::EvfFile, gob::GobFile, ltmap::LtMapCfg},
+    pal4::{cam::CameraDataFile, evf::EvfFile, gob::GobFile, ltmap::LtMapCfg, quest::QuestFile},
     rwbs::uva::UvAnimDict,
 };
 use mini_fs::{MiniFs, StoreExt};
@@ -134,6 +134,7 @@ impl AssetLoader {
             dynamic_lighting: false,
             fog_exempt: false,
             foliage_resolver: None,
+            water: None,
         };
         let bsp = create_entity_from_bsp_model(
             &self.component_factory,
@@ -158,6 +159,7 @@ impl AssetLoader {
             dynamic_lighting: false,
             fog_exempt: false,
             foliage_resolver: None,
+            water: None,
         };
         self.attach_uv_overlay(
             &scene,
@@ -264,6 +266,7 @@ impl AssetLoader {
             dynamic_lighting: false,
             fog_exempt: false,
             foliage_resolver: None,
+            water: None,
         };
         if self.vfs.exists(&path) {
             create_entity_from_dff_model(
@@ -303,6 +306,7 @@ impl AssetLoader {
                 dynamic_lighting: false,
                 fog_exempt: false,
                 foliage_resolver: None,
+                water: None,
             },
         )?;
 
@@ -493,6 +497,7 @@ impl AssetLoader {
                 dynamic_lighting: false,
                 fog_exempt: false,
                 foliage_resolver: None,
+                water: None,
             },
         )?;
 
@@ -607,6 +612,7 @@ impl AssetLoader {
                 dynamic_lighting: false,
                 fog_exempt: false,
                 foliage_resolver: None,
+                water: None,
             },
         )
         .map_err(|e| log::warn!("try_load_scene_water: failed to load {}: {:#}", path, e))
@@ -718,6 +724,7 @@ impl AssetLoader {
                     dynamic_lighting: false,
                     fog_exempt: false,
                     foliage_resolver: None,
+                    water: None,
                 },
             )
             .map_err(|e| log::warn!("try_load_dff: failed to load {}: {:#}", path, e))
@@ -759,6 +766,25 @@ impl AssetLoader {
         Ok(NpcInfoFile::read(&mut cursor).with_context(|| format!("parsing npcInfo {}", path))?)
     }
 
+    /// Load the quest description `giShowQuestDialog` names. The
+    /// script's path may use backslashes and may or may not start with
+    /// `gamedata`; it is resolved under `/gamedata` either way.
+    pub fn load_quest(&self, quest_file: &str) -> anyhow::Result<QuestFile> {
+        let path = quest_file.replace('\\', "/");
+        let path = path.trim_start_matches('/');
+        let path = if path.to_lowercase().starts_with("gamedata/") {
+            format!("/{}", path)
+        } else {
+            format!("/gamedata/{}", path)
+        };
+
+        let data = self
+            .vfs
+            .read_to_end(&path)
+            .with_context(|| format!("opening quest {}", path))?;
+        QuestFile::read(&data).with_context(|| format!("parsing quest {}", path))
+    }
+
     pub fn load_video(&self, video_name: &str) -> anyhow::Result<Box<dyn SeekRead>> {
         let video_folder = match video_name.to_lowercase().as_str() {
             "1a.bik" | "end2.bik" | "pal4a.bik" => "VideoA",
@@ -24,6 +24,7 @@ use radiance::{
 };
 
 use crate::scripting::angelscript::ScriptVm;
+use crate::scripting::debugger::DebuggerWindow;
 
 use super::{
     agent::Pal4AgentBridge,
@@ -32,10 +33,13 @@ use super::{
     comdef::pal4_debug::{IPal4DebugContext, IPal4DebugOverlay},
     object_component::Pal4ObjectComponent,
     pal4_debug::Pal4DebugState,
+    quest_journal::render_quest_journal,
     scene::Pal4Scene,
     scene::object_component,
     scripting::create_script_vm,
     session::{Pal4Session, RuntimeSnapshot},
+    system_ui::{Pal4SystemUi, SystemPage, SystemUiAction},
+    ui_timer::render_countdown,
     vm_context::{
         ActorId, DialogAvatarSide, MOTION_FAST_FORWARD_SCALE, MovingEntity, Pal4VmContext,
         RotatingEntity, wrap_deg,
@@ -65,6 +69,19 @@ pub struct OpenPAL4Director {
     debug: RefCell<Option<Pal4DebugBundle>>,
     debug_visible: Cell<bool>,
     debug_prev_tilde: Cell<bool>,
+    // Script debugger window, shown alongside the overlay.
+    script_debugger: RefCell<DebuggerWindow>,
+
+    // Quest journal window, toggled by `J` independently of the debug
+    // overlay.
+    journal_visible: Cell<bool>,
+    journal_prev_key: Cell<bool>,
+
+    // Status / skills / equipment / smith / magic pages. Which page is
+    // open lives on the session (`giOpenSystemUi` waits on it); `C`
+    // opens the status page while nothing else is up.
+    system_ui: Pal4SystemUi,
+    system_ui_prev_key: Cell<bool>,
     fps_smoothed: Cell<f32>,
 
     // Perf-metric display throttle: the FPS/dt readouts are republished
@@ -200,6 +217,11 @@ impl OpenPAL4Director {
             debug: RefCell::new(None),
             debug_visible: Cell::new(false),
             debug_prev_tilde: Cell::new(false),
+            script_debugger: RefCell::new(DebuggerWindow::new()),
+            journal_visible: Cell::new(false),
+            journal_prev_key: Cell::new(false),
+            system_ui: Pal4SystemUi::default(),
+            system_ui_prev_key: Cell::new(false),
             fps_smoothed: Cell::new(0.0),
             debug_metric_accum: Cell::new(0.0),
             fps_display: Cell::new(0.0),
@@ -497,6 +519,72 @@ impl OpenPAL4Director {
         pressed && !prev
     }
 
+    fn poll_journal_key(&self) -> bool {
+        let vm = self.vm.borrow();
+        let input = vm.vm_context.input.borrow();
+        let pressed = input.get_key_state(Key::J).pressed();
+        let prev = self.journal_prev_key.get();
+        self.journal_prev_key.set(pressed);
+        pressed && !prev
+    }
+
+    fn poll_system_ui_key(&self) -> bool {
+        let vm = self.vm.borrow();
+        let input = vm.vm_context.input.borrow();
+        let pressed = input.get_key_state(Key::C).pressed();
+        let prev = self.system_ui_prev_key.get();
+        self.system_ui_prev_key.set(pressed);
+        pressed && !prev
+    }
+
+    fn render_system_ui(&self, ui: &ComRc<IUiHost>) {
+        let vm = self.vm.borrow();
+        let page = vm.vm_context.session().system_ui_page();
+        let page = match page {
+            Some(page) => page,
+            None if self.poll_system_ui_key() => SystemPage::Status,
+            None => return,
+        };
+
+        let action = {
+            let input = vm.vm_context.input.borrow();
+            let mut state = vm.vm_context.persistent_state_mut();
+            self.system_ui.render(ui, page, &mut state, &*input)
+        };
+
+        let session = vm.vm_context.session();
+        match action {
+            SystemUiAction::Stay => session.set_system_ui_page(Some(page)),
+            SystemUiAction::Switch(next) => session.set_system_ui_page(Some(next)),
+            SystemUiAction::Close => session.set_system_ui_page(None),
+        }
+    }
+
+    fn render_script_debugger(&self) {
+        let vm = self.vm.borrow();
+        let ui = vm.vm_context.ui.clone();
+        self.script_debugger.borrow_mut().render(ui.ui(), &*vm);
+    }
+
+    fn render_journal(&self) {
+        let vm = self.vm.borrow();
+        let ui = vm.vm_context.ui.clone();
+        let state = vm.vm_context.persistent_state();
+        render_quest_journal(ui.ui(), &state);
+    }
+
+    /// Draw the `giStartUiTimer` countdown. Done from the UI layer
+    /// rather than `Pal4VmContext::update` so the widget stays on
+    /// screen while the simulation is paused or held by the debugger.
+    fn render_ui_timer(&self) {
+        let vm = self.vm.borrow();
+        let ui = vm.vm_context.ui.clone();
+        let state = vm.vm_context.persistent_state();
+        if let Some(timer) = state.ui_timer() {
+            render_countdown(ui.ui(), timer);
+        }
+    }
+
     /// Persist the current game state to `slot` as JSON. Snapshots the
     /// shared angelscript globals (story-plot flags) plus the leader's
     /// live position / facing and camera so a later load resumes at the
@@ -698,6 +786,14 @@ impl IDirectorImpl for OpenPAL4Director {
         // pause stays sensible; that's handled below).
         let (advance, effective_dt) = self.compute_effective_dt(delta_sec);
 
+        // An attached script debugger holding the VM at a breakpoint
+        // freezes the whole director, not just the bytecode, so
+        // motion tweens and triggers don't run ahead of the script.
+        if self.vm.borrow_mut().poll_debugger() {
+            self.end_agent_frame();
+            return None;
+        }
+
         if !advance {
             self.end_agent_frame();
             return None;
@@ -842,11 +938,21 @@ impl IUiLayerImpl for OpenPAL4Director {
             self.debug_visible.set(!self.debug_visible.get());
         }
 
+        if self.poll_journal_key() {
+            self.journal_visible.set(!self.journal_visible.get());
+        }
+        if self.journal_visible.get() {
+            self.render_journal();
+        }
+        self.render_ui_timer();
+        self.render_system_ui(&ui);
+
         if !self.debug_visible.get() {
             return;
         }
 
         self.refresh_debug_snapshot(dt);
+        self.render_script_debugger();
 
         // Clone the COM handles out of the RefCell first so we drop the
         // borrow before re-entering script land (the script may call
@@ -1051,6 +1157,10 @@ impl OpenPAL4Director {
             AgentCommand::TraceStart(params) => self.handle_trace_start(params),
             AgentCommand::TraceStop => self.handle_trace_stop(),
             AgentCommand::TraceDrain(params) => self.handle_trace_drain(params),
+            command if command.is_debug() => crate::agent_common::handlers::handle_debug_command(
+                Some(&*self.vm.borrow()),
+                command,
+            ),
             AgentCommand::ChooseDialog(_) | AgentCommand::ChooseWorldMap(_) => {
                 // Session-only commands are now dispatched by
                 // `Pal4Service::pump_agent` directly to the shared
@@ -1407,6 +1517,7 @@ impl OpenPAL4Director {
                 mp: p.mp,
                 max_mp: p.max_mp,
                 in_team: p.in_team,
+                magic: Vec::new(),
             })
             .collect();
 
@@ -19,13 +19,17 @@ pub mod director;
 pub mod game_context;
 pub mod launch;
 pub mod modes;
+pub mod npc_behavior;
 pub mod object_component;
 pub mod pal4_debug;
+pub mod quest_journal;
 pub mod scene;
 pub mod scene_editor_access;
 pub mod scripting;
 pub mod service;
 pub mod session;
 pub mod states;
+pub mod system_ui;
 pub mod transition;
+pub mod ui_timer;
 pub mod uv_anim;
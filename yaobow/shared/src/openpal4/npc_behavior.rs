@@ -0,0 +1,541 @@
+//! Ambient behaviour for PAL4 block NPCs and field monsters: patrol
+//! routes, idle wandering around the spawn point, and monsters that
+//! chase the leader once they see them.
+//!
+//! [`NpcBehavior`] is pure decision logic. Each frame the VM context
+//! hands it the NPC's position and the leader's, and it answers with a
+//! [`BehaviorCommand`]. The context carries out moves through the same
+//! `MovingEntity` tween that scripted `giNpcWalkTo`-style moves use, so
+//! walking, turning and the idle animation on arrival are shared.
+//!
+//! Settings come from the `behaviour` node tree of each `npcInfo.npc`
+//! record. The fields of that tree are not decoded, so
+//! [`BehaviorSpec::from_node`] only guesses: it matches on keywords
+//! (`patrol` / `wander` / `monster` …) and, for patrols, reads
+//! `X`/`PosX`/`Point_X`-style float triples as route points. None of
+//! this has been checked against shipped data, so the guess is **off by
+//! default**; enable with `PAL4_NPC_BEHAVIOR_HEURISTIC=1`. Without it
+//! every NPC stands still, as before.
+
+use fileformats::utils::{Pal4Node, Pal4NodeProperty};
+use radiance::math::Vec3;
+
+/// How close (XZ, world units) a monster has to get to the leader to
+/// count as contact.
+const CONTACT_DISTANCE: f32 = 30.0;
+/// Arrival tolerance when deciding whether an NPC is back home.
+const ARRIVE_DISTANCE: f32 = 5.0;
+/// Default sight radius for monsters whose data carries none.
+const DEFAULT_SIGHT: f32 = 250.0;
+/// Default wander radius around the spawn point.
+const DEFAULT_WANDER_RADIUS: f32 = 80.0;
+/// A pursuing monster gives up once it is this many sight radii away
+/// from home.
+const LEASH_FACTOR: f32 = 2.0;
+/// Idle pause between moves, in seconds.
+const REST_MIN_SEC: f32 = 2.0;
+const REST_MAX_SEC: f32 = 5.0;
+/// After an encounter (or a scripted stop) a monster ignores the
+/// leader for this long, so one contact does not chain into another.
+const MONSTER_COOLDOWN_SEC: f32 = 5.0;
+
+/// Whether [`BehaviorSpec::from_node`] should be consulted at all.
+pub fn heuristic_enabled() -> bool {
+    std::env::var("PAL4_NPC_BEHAVIOR_HEURISTIC")
+        .is_ok_and(|s| !matches!(s.trim(), "0" | "false" | "off" | "no" | ""))
+}
+
+/// What kind of ambient behaviour an NPC runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BehaviorSpec {
+    /// Walk the route points in order, looping.
+    Patrol { waypoints: Vec<[f32; 3]> },
+    /// Stroll to random points within `radius` of the spawn point.
+    Wander { radius: f32 },
+    /// Wander like [`BehaviorSpec::Wander`], but chase the leader once
+    /// they come within `sight`.
+    Monster { radius: f32, sight: f32 },
+}
+
+impl BehaviorSpec {
+    /// Classify an `npcInfo.npc` behaviour tree. `None` unless it names
+    /// a moving behaviour; coordinates alone don't make a patrol.
+    pub fn from_node(root: &Pal4Node) -> Option<Self> {
+        let mut scan = NodeScan::default();
+        scan.visit(root);
+
+        let sight = scan.sight.unwrap_or(DEFAULT_SIGHT);
+        let radius = scan.radius.unwrap_or(DEFAULT_WANDER_RADIUS);
+        match scan.keyword {
+            Some(Keyword::Monster) => Some(BehaviorSpec::Monster { radius, sight }),
+            Some(Keyword::Patrol) if scan.points.len() >= 2 => Some(BehaviorSpec::Patrol {
+                waypoints: scan.points,
+            }),
+            Some(Keyword::Patrol | Keyword::Wander) => Some(BehaviorSpec::Wander { radius }),
+            None => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Keyword {
+    Patrol,
+    Wander,
+    Monster,
+}
+
+impl Keyword {
+    fn classify(text: &str) -> Option<Self> {
+        let text = text.to_ascii_lowercase();
+        if ["monster", "pursu", "chase", "enemy"]
+            .iter()
+            .any(|k| text.contains(k))
+        {
+            Some(Keyword::Monster)
+        } else if ["patrol", "path", "route"].iter().any(|k| text.contains(k)) {
+            Some(Keyword::Patrol)
+        } else if ["wander", "random", "stroll"]
+            .iter()
+            .any(|k| text.contains(k))
+        {
+            Some(Keyword::Wander)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Default)]
+struct NodeScan {
+    keyword: Option<Keyword>,
+    points: Vec<[f32; 3]>,
+    radius: Option<f32>,
+    sight: Option<f32>,
+}
+
+impl NodeScan {
+    fn visit(&mut self, node: &Pal4Node) {
+        self.note_keyword(&String::from_utf8_lossy(node.name.data()));
+
+        let mut point: [Option<f32>; 3] = [None; 3];
+        for property in &node.properties {
+            let name = String::from_utf8_lossy(property.name().data()).to_ascii_lowercase();
+            match property {
+                Pal4NodeProperty::String(_) => {
+                    if let Some(value) = property.string() {
+                        self.note_keyword(&value);
+                    }
+                }
+                Pal4NodeProperty::Int(_) => self.note_keyword(&name),
+                Pal4NodeProperty::Float(_) => {
+                    let value = property.f32().unwrap_or_default();
+                    if name.contains("sight") || name.contains("view") || name.contains("alert") {
+                        self.sight.get_or_insert(value);
+                    } else if name.contains("radius") || name.contains("range") {
+                        self.radius.get_or_insert(value);
+                    } else if let Some(axis) = coordinate_axis(&name) {
+                        point[axis] = Some(value);
+                    }
+                }
+            }
+        }
+        if let [Some(x), Some(y), Some(z)] = point {
+            self.points.push([x, y, z]);
+        }
+
+        for child in &node.children {
+            self.visit(child);
+        }
+    }
+
+    fn note_keyword(&mut self, text: &str) {
+        // The strongest match wins: a monster that also patrols is
+        // still a monster.
+        match (self.keyword, Keyword::classify(text)) {
+            (_, None) | (Some(Keyword::Monster), _) => {}
+            (_, found) => self.keyword = found,
+        }
+    }
+}
+
+/// Axis index of a route-point coordinate property: a bare `x`/`y`/`z`,
+/// or one following a separator or a `pos`/`point` prefix. Names that
+/// merely end in the letter (`index`, `delay`) don't count.
+fn coordinate_axis(name: &str) -> Option<usize> {
+    let axis = ["x", "y", "z"].iter().position(|a| name.ends_with(a))?;
+    let prefix = &name[..name.len() - 1];
+    let is_coordinate = prefix.is_empty()
+        || prefix.ends_with(|c: char| !c.is_ascii_alphabetic())
+        || ["pos", "point", "position"]
+            .iter()
+            .any(|p| prefix.ends_with(p));
+    is_coordinate.then_some(axis)
+}
+
+/// What the driver should do with the NPC this frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BehaviorCommand {
+    /// Nothing new; keep whatever tween is running.
+    Wait,
+    /// Walk (or run) towards `target`. Re-issued every frame while
+    /// pursuing; the driver retargets the running tween in place.
+    MoveTo { target: [f32; 3], run: bool },
+    /// A monster touched the leader. PAL4 has no battle mode, so the
+    /// driver only stops the monster.
+    Contact,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Rest(f32),
+    Walk,
+    Pursue,
+    Return,
+}
+
+/// Per-NPC behaviour state.
+#[derive(Debug, Clone)]
+pub struct NpcBehavior {
+    spec: BehaviorSpec,
+    home: Vec3,
+    phase: Phase,
+    next_waypoint: usize,
+    /// Set by `giNpcPauseBeh`; cleared by `giNpcResumeBeh`.
+    paused: bool,
+    /// Seconds left before a monster may notice the leader again.
+    cooldown: f32,
+    rng: u32,
+}
+
+impl NpcBehavior {
+    pub fn new(spec: BehaviorSpec, home: Vec3, seed: &str) -> Self {
+        // FNV-1a over the NPC name: deterministic per NPC, different
+        // between NPCs, so a block's wanderers don't move in lockstep.
+        let rng = seed.bytes().fold(0x811c_9dc5_u32, |h, b| {
+            (h ^ b as u32).wrapping_mul(0x0100_0193)
+        }) | 1;
+        let mut behavior = Self {
+            spec,
+            home,
+            phase: Phase::Rest(0.),
+            next_waypoint: 0,
+            paused: false,
+            cooldown: 0.,
+            rng,
+        };
+        behavior.phase = Phase::Rest(behavior.rest_duration());
+        behavior
+    }
+
+    pub fn is_monster(&self) -> bool {
+        matches!(self.spec, BehaviorSpec::Monster { .. })
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if paused && self.phase != Phase::Return {
+            self.phase = Phase::Rest(self.rest_duration());
+        }
+    }
+
+    /// `giMonsterStopPursuit`: drop the chase and head home. The
+    /// cooldown keeps the monster from re-acquiring the leader
+    /// immediately.
+    pub fn stop_pursuit(&mut self) {
+        if self.phase == Phase::Pursue {
+            self.phase = Phase::Return;
+        }
+        self.cooldown = MONSTER_COOLDOWN_SEC;
+    }
+
+    /// Decide this frame's action. `moving` is whether the NPC still
+    /// has a movement tween in flight.
+    pub fn tick(&mut self, pos: Vec3, leader: Vec3, moving: bool, dt: f32) -> BehaviorCommand {
+        if self.paused {
+            return BehaviorCommand::Wait;
+        }
+        self.cooldown = (self.cooldown - dt).max(0.);
+
+        if let BehaviorSpec::Monster { sight, .. } = self.spec {
+            if let Some(command) = self.tick_monster(pos, leader, sight) {
+                return command;
+            }
+        }
+
+        match self.phase {
+            Phase::Rest(remaining) => {
+                let remaining = remaining - dt;
+                if remaining > 0. {
+                    self.phase = Phase::Rest(remaining);
+                    return BehaviorCommand::Wait;
+                }
+                match self.next_target() {
+                    Some(target) => {
+                        self.phase = Phase::Walk;
+                        BehaviorCommand::MoveTo { target, run: false }
+                    }
+                    None => {
+                        self.phase = Phase::Rest(self.rest_duration());
+                        BehaviorCommand::Wait
+                    }
+                }
+            }
+            Phase::Walk => {
+                if !moving {
+                    self.phase = Phase::Rest(self.rest_duration());
+                }
+                BehaviorCommand::Wait
+            }
+            Phase::Return => {
+                if xz_distance(&pos, &self.home) <= ARRIVE_DISTANCE {
+                    self.phase = Phase::Rest(self.rest_duration());
+                    BehaviorCommand::Wait
+                } else {
+                    BehaviorCommand::MoveTo {
+                        target: [self.home.x, self.home.y, self.home.z],
+                        run: false,
+                    }
+                }
+            }
+            // Only monsters pursue; a non-monster can't get here.
+            Phase::Pursue => {
+                self.phase = Phase::Return;
+                BehaviorCommand::Wait
+            }
+        }
+    }
+
+    fn tick_monster(&mut self, pos: Vec3, leader: Vec3, sight: f32) -> Option<BehaviorCommand> {
+        let to_leader = xz_distance(&pos, &leader);
+        if self.phase == Phase::Pursue {
+            if to_leader <= CONTACT_DISTANCE {
+                self.phase = Phase::Return;
+                self.cooldown = MONSTER_COOLDOWN_SEC;
+                return Some(BehaviorCommand::Contact);
+            }
+            let leashed = xz_distance(&pos, &self.home) > sight * LEASH_FACTOR;
+            if leashed || to_leader > sight * 1.5 {
+                self.phase = Phase::Return;
+                return None;
+            }
+            return Some(BehaviorCommand::MoveTo {
+                target: [leader.x, leader.y, leader.z],
+                run: true,
+            });
+        }
+
+        if self.cooldown <= 0. && to_leader <= sight {
+            self.phase = Phase::Pursue;
+            return Some(BehaviorCommand::MoveTo {
+                target: [leader.x, leader.y, leader.z],
+                run: true,
+            });
+        }
+        None
+    }
+
+    fn next_target(&mut self) -> Option<[f32; 3]> {
+        match &self.spec {
+            BehaviorSpec::Patrol { waypoints } => {
+                let target = *waypoints.get(self.next_waypoint % waypoints.len().max(1))?;
+                self.next_waypoint = (self.next_waypoint + 1) % waypoints.len();
+                Some(target)
+            }
+            BehaviorSpec::Wander { radius } | BehaviorSpec::Monster { radius, .. } => {
+                let radius = *radius;
+                let angle = self.next_unit() * std::f32::consts::TAU;
+                let distance = radius * (0.3 + 0.7 * self.next_unit());
+                Some([
+                    self.home.x + distance * angle.cos(),
+                    self.home.y,
+                    self.home.z + distance * angle.sin(),
+                ])
+            }
+        }
+    }
+
+    fn rest_duration(&mut self) -> f32 {
+        REST_MIN_SEC + (REST_MAX_SEC - REST_MIN_SEC) * self.next_unit()
+    }
+
+    /// xorshift32 in `[0, 1)`.
+    fn next_unit(&mut self) -> f32 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+fn xz_distance(a: &Vec3, b: &Vec3) -> f32 {
+    let dx = a.x - b.x;
+    let dz = a.z - b.z;
+    (dx * dx + dz * dz).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use fileformats::utils::Pal4NodePropertyValue;
+
+    use super::*;
+
+    const FAR: Vec3 = Vec3 {
+        x: 10_000.,
+        y: 0.,
+        z: 10_000.,
+    };
+
+    fn origin() -> Vec3 {
+        Vec3::new(0., 0., 0.)
+    }
+
+    #[test]
+    fn patrol_walks_waypoints_in_order() {
+        let spec = BehaviorSpec::Patrol {
+            waypoints: vec![[10., 0., 0.], [20., 0., 0.]],
+        };
+        let mut npc = NpcBehavior::new(spec, origin(), "npc01");
+
+        let mut targets = Vec::new();
+        for _ in 0..200 {
+            if let BehaviorCommand::MoveTo { target, run } = npc.tick(origin(), FAR, false, 0.5) {
+                assert!(!run);
+                targets.push(target[0]);
+            }
+        }
+        assert!(targets.len() >= 3);
+        assert_eq!(&targets[..3], &[10., 20., 10.]);
+    }
+
+    #[test]
+    fn wander_stays_within_radius() {
+        let mut npc = NpcBehavior::new(BehaviorSpec::Wander { radius: 50. }, origin(), "npc02");
+        for _ in 0..500 {
+            if let BehaviorCommand::MoveTo { target, .. } = npc.tick(origin(), FAR, false, 0.5) {
+                assert!(xz_distance(&Vec3::from(target), &origin()) <= 50.);
+            }
+        }
+    }
+
+    #[test]
+    fn monster_pursues_and_reports_contact() {
+        let spec = BehaviorSpec::Monster {
+            radius: 50.,
+            sight: 100.,
+        };
+        let mut monster = NpcBehavior::new(spec, origin(), "m01");
+        let leader = Vec3::new(80., 0., 0.);
+
+        assert_eq!(
+            monster.tick(origin(), leader, false, 0.1),
+            BehaviorCommand::MoveTo {
+                target: [80., 0., 0.],
+                run: true
+            }
+        );
+        assert_eq!(
+            monster.tick(Vec3::new(60., 0., 0.), leader, true, 0.1),
+            BehaviorCommand::Contact
+        );
+        // Cooldown: the leader standing right there is ignored for now.
+        assert_ne!(
+            monster.tick(Vec3::new(60., 0., 0.), leader, false, 0.1),
+            BehaviorCommand::Contact
+        );
+    }
+
+    #[test]
+    fn paused_and_stopped_monsters_do_not_chase() {
+        let spec = BehaviorSpec::Monster {
+            radius: 50.,
+            sight: 100.,
+        };
+        let mut monster = NpcBehavior::new(spec, origin(), "m02");
+        let leader = Vec3::new(50., 0., 0.);
+
+        monster.set_paused(true);
+        assert_eq!(
+            monster.tick(origin(), leader, false, 0.1),
+            BehaviorCommand::Wait
+        );
+
+        monster.set_paused(false);
+        assert!(matches!(
+            monster.tick(origin(), leader, false, 0.1),
+            BehaviorCommand::MoveTo { run: true, .. }
+        ));
+        monster.stop_pursuit();
+        assert!(!matches!(
+            monster.tick(Vec3::new(10., 0., 0.), leader, true, 0.1),
+            BehaviorCommand::MoveTo { run: true, .. } | BehaviorCommand::Contact
+        ));
+    }
+
+    #[test]
+    fn keywords_classify_behaviour() {
+        assert_eq!(Keyword::classify("NPC_Patrol_Path"), Some(Keyword::Patrol));
+        assert_eq!(Keyword::classify("MonsterAI"), Some(Keyword::Monster));
+        assert_eq!(Keyword::classify("RandomWalk"), Some(Keyword::Wander));
+        assert_eq!(Keyword::classify("NPCINFO_Behaviour"), None);
+    }
+
+    #[test]
+    fn coordinates_need_a_point_like_name() {
+        assert_eq!(coordinate_axis("x"), Some(0));
+        assert_eq!(coordinate_axis("posy"), Some(1));
+        assert_eq!(coordinate_axis("point_z"), Some(2));
+        assert_eq!(coordinate_axis("index"), None);
+        assert_eq!(coordinate_axis("delay"), None);
+    }
+
+    #[test]
+    fn points_without_keyword_stand_still() {
+        let point = |x: f32| {
+            Box::new(node(
+                "Point",
+                vec![float("PosX", x), float("PosY", 0.), float("PosZ", 0.)],
+                vec![],
+            ))
+        };
+        let points = vec![point(10.), point(20.)];
+        assert_eq!(
+            BehaviorSpec::from_node(&node("Behaviour", vec![], points)),
+            None
+        );
+
+        let points = vec![point(10.), point(20.)];
+        assert_eq!(
+            BehaviorSpec::from_node(&node("PatrolPath", vec![], points)),
+            Some(BehaviorSpec::Patrol {
+                waypoints: vec![[10., 0., 0.], [20., 0., 0.]],
+            })
+        );
+    }
+
+    fn node(
+        name: &str,
+        properties: Vec<Pal4NodeProperty>,
+        children: Vec<Box<Pal4Node>>,
+    ) -> Pal4Node {
+        Pal4Node {
+            name: name.into(),
+            property_count: properties.len() as u32,
+            properties,
+            children_count: children.len() as u32,
+            children,
+        }
+    }
+
+    fn float(name: &str, value: f32) -> Pal4NodeProperty {
+        Pal4NodeProperty::Float(Pal4NodePropertyValue {
+            name: name.into(),
+            value,
+        })
+    }
+}
@@ -0,0 +1,51 @@
+use imgui::{Condition, Ui};
+
+use super::states::persistent_state::{Pal4PersistentState, QuestStatus};
+
+/// Read-only quest journal window. Lists every quest accepted through
+/// `giShowQuestDialog`, newest first, with the title and text from its
+/// quest file, its status and the completion percentage reported by
+/// `giAddQuestCompletePercentage`.
+/// The director toggles it with `J` and draws it from `render`.
+pub fn render_quest_journal(ui: &Ui, state: &Pal4PersistentState) {
+    let [window_width, window_height] = ui.io().display_size;
+    let width = (window_width * 0.45).max(320.);
+    let height = window_height * 0.6;
+
+    let _font_token =
+        radiance::imgui::game_font(radiance::imgui::GameFontSize::LARGE).map(|f| ui.push_font(f));
+    ui.window("任务日志")
+        .collapsible(false)
+        .resizable(false)
+        .movable(false)
+        .position(
+            [(window_width - width) / 2., (window_height - height) / 2.],
+            Condition::Always,
+        )
+        .size([width, height], Condition::Always)
+        .build(|| {
+            ui.text(format!("总完成度：{}%", state.quest_percentage()));
+            ui.separator();
+
+            if state.quests().is_empty() {
+                ui.text_disabled("暂无任务");
+                return;
+            }
+
+            for quest in state.quests().iter().rev() {
+                let title = quest.display_title();
+                match quest.status {
+                    QuestStatus::Active => ui.text_wrapped(format!("[进行中] {}", title)),
+                    QuestStatus::Completed => {
+                        ui.text_disabled(format!("[已完成 +{}%] {}", quest.percentage, title))
+                    }
+                }
+                if !quest.text.is_empty() {
+                    ui.indent();
+                    ui.text_wrapped(&quest.text);
+                    ui.unindent();
+                }
+                ui.spacing();
+            }
+        });
+}
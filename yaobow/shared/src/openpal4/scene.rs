@@ -30,6 +30,7 @@ use super::{
         IPal4ObjectComponent, IPal4ScriptFactory,
     },
     game_context::Pal4GameContext,
+    npc_behavior::{self, BehaviorSpec, NpcBehavior},
     object_component::Pal4ObjectComponent,
     uv_anim::attach_uv_anim,
 };
@@ -100,6 +101,11 @@ pub struct Pal4Scene {
     /// record's `script_function`. Talking to an NPC (proximity + "F")
     /// dispatches through the same path as a GOB examine handler.
     pub(crate) npc_functions: HashMap<String, String>,
+    /// NPC name -> ambient behaviour (patrol / wander / monster),
+    /// guessed from each record's behaviour node tree when
+    /// `PAL4_NPC_BEHAVIOR_HEURISTIC` is set. Ticked by
+    /// `Pal4VmContext::tick_npc_behaviors`.
+    pub(crate) npc_behaviors: HashMap<String, NpcBehavior>,
 }
 
 /// Fallback `trigger_distance` for SOUND emitters whose entry has
@@ -135,6 +141,7 @@ impl Pal4Scene {
             game_context: None,
             actor_controller: None,
             npc_functions: HashMap::new(),
+            npc_behaviors: HashMap::new(),
         }
     }
 
@@ -216,6 +223,8 @@ pub struct Pal4SceneLoader {
     module: Option<Rc<RefCell<ScriptModule>>>,
     /// NPC name -> talk script function, collected by `stage_npcs`.
     npc_functions: HashMap<String, String>,
+    /// NPC name -> ambient behaviour, collected by `stage_npcs`.
+    npc_behaviors: HashMap<String, NpcBehavior>,
 }
 
 /// One stage's outcome: the cumulative post-stage progress fraction
@@ -252,6 +261,7 @@ impl Pal4SceneLoader {
             actor_controller: None,
             module: None,
             npc_functions: HashMap::new(),
+            npc_behaviors: HashMap::new(),
         }
     }
 
@@ -644,6 +654,7 @@ impl Pal4SceneLoader {
                 NpcInfoFile::default()
             }
         };
+        let behavior_heuristic = npc_behavior::heuristic_enabled();
         for npc in &npc_info.data {
             let actor_name = npc.model_name.to_string();
             match actor_name {
@@ -678,6 +689,17 @@ impl Pal4SceneLoader {
                             self.npc_functions
                                 .insert(npc_name.clone(), script_function);
                         }
+                        let spec = npc
+                            .behaviour()
+                            .filter(|_| behavior_heuristic)
+                            .and_then(BehaviorSpec::from_node);
+                        if let Some(spec) = spec {
+                            log::debug!("Pal4Scene::load: npc '{}' behaviour {:?}", npc_name, spec);
+                            self.npc_behaviors.insert(
+                                npc_name.clone(),
+                                NpcBehavior::new(spec, Vec3::from(npc.position), &npc_name),
+                            );
+                        }
                         entity
                             .transform()
                             .borrow_mut()
@@ -986,6 +1008,7 @@ impl Pal4SceneLoader {
             game_context: Some(game_context),
             actor_controller: self.actor_controller.take(),
             npc_functions: std::mem::take(&mut self.npc_functions),
+            npc_behaviors: std::mem::take(&mut self.npc_behaviors),
         })
     }
 }
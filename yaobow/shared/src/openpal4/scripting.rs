@@ -1,5 +1,6 @@
 use std::{cell::RefCell, rc::Rc};
 
+use fileformats::pal4::quest::QuestFile;
 use imgui::MouseButton;
 use radiance::{input::Key, math::Vec3, utils::interp_value::InterpValue, video::VideoStreamState};
 
@@ -9,7 +10,10 @@ use crate::{
         ContinuationState, GlobalFunctionState, ScriptGlobalContext, ScriptGlobalFunction,
         ScriptModule, ScriptVm, not_implemented,
     },
-    ui::dialog_box::DialogBoxPresenter,
+    scripting::debugger::{
+        DebugLocation, DebugProcedure, DebugState, DebugValue, ScriptDebugTarget,
+    },
+    ui::{choice_prompt::ChoicePrompt, dialog_box::DialogBoxPresenter},
     utils,
 };
 
@@ -1049,6 +1053,16 @@ pub fn create_context() -> ScriptGlobalContext<Pal4VmContext> {
     context
 }
 
+/// Sysfn names indexed by the ordinal `CallSys` encodes, for tools
+/// that read PAL4 bytecode without running it.
+pub fn sysfn_names() -> Vec<String> {
+    create_context()
+        .functions()
+        .iter()
+        .map(|f| f.name.clone())
+        .collect()
+}
+
 fn imm_begin(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
     vm.imm = true;
     Pal4FunctionState::Completed
@@ -1310,7 +1324,8 @@ fn system_exchange(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionSta
     Pal4FunctionState::Completed
 }
 
-fn monster_stop_pursuit(_: &str, _vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
+fn monster_stop_pursuit(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
+    vm.vm_context.stop_monster_pursuit();
     Pal4FunctionState::Completed
 }
 
@@ -1326,12 +1341,22 @@ fn get_goods_open_condition(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4Fu
 }
 
 fn npc_pause_beh(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm,_npc_name:i32);
+    as_params!(vm, npc_name: i32);
+
+    if let Some(npc_name) = get_str(vm, npc_name as usize) {
+        vm.vm_context.set_npc_behavior_paused(&npc_name, true);
+    }
+
     Pal4FunctionState::Completed
 }
 
 fn npc_resume_beh(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm,_npc_name:i32);
+    as_params!(vm, npc_name: i32);
+
+    if let Some(npc_name) = get_str(vm, npc_name as usize) {
+        vm.vm_context.set_npc_behavior_paused(&npc_name, false);
+    }
+
     Pal4FunctionState::Completed
 }
 
@@ -1498,7 +1523,7 @@ fn add_quest_complete_percentage(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> P
     as_params!(vm, percentage: i32);
     vm.vm_context
         .persistent_state_mut()
-        .add_quest_percentage(percentage);
+        .complete_quest(percentage);
     Pal4FunctionState::Completed
 }
 
@@ -1566,7 +1591,12 @@ fn camera_set_dist_opt_enable(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4
 }
 
 fn monster_set_hide(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm,_file_str:i32,_hide_monster :i32);
+    as_params!(vm, file_str: i32, hide_monster: i32);
+
+    if let Some(monster_name) = get_str(vm, file_str as usize) {
+        vm.vm_context.enable_npc(&monster_name, hide_monster == 0);
+    }
+
     Pal4FunctionState::Completed
 }
 
@@ -1715,11 +1745,14 @@ fn show_hint(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
 }
 
 fn player_add_skill(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm, player_id: i32, skill_id: i32, _add_skill: i32);
+    as_params!(vm, player_id: i32, skill_id: i32, add_skill: i32);
     let slot = map_player_slot(vm, player_id);
-    vm.vm_context
-        .persistent_state_mut()
-        .add_skill(slot, skill_id);
+    let mut state = vm.vm_context.persistent_state_mut();
+    if add_skill != 0 {
+        state.add_skill(slot, skill_id);
+    } else {
+        state.remove_skill(slot, skill_id);
+    }
     Pal4FunctionState::Completed
 }
 
@@ -1807,17 +1840,29 @@ fn grant_system_ui(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionSta
 }
 
 fn open_system_ui(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm,_ui_id:i32);
+    // Which system window each id opens is not identified yet, so the
+    // pages are only reachable from the menu hotkey.
+    as_params!(vm, ui_id: i32);
+    log::warn!("giOpenSystemUi({}): unimplemented, no known page", ui_id);
     Pal4FunctionState::Completed
 }
 
 fn grant_smith_system(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm,_smith1:i32,_smith2:i32,_smith3:i32,_smith4:i32);
+    as_params!(vm, smith1: i32, smith2: i32, smith3: i32, smith4: i32);
+    vm.vm_context
+        .persistent_state_mut()
+        .grant_smith_system([smith1, smith2, smith3, smith4]);
     Pal4FunctionState::Completed
 }
 
 fn grant_magic_system(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm,_magic1:i32,_magic2:i32);
+    // Both operands are magic skill ids the magic page offers for
+    // learning; `0` marks an unused slot.
+    as_params!(vm, magic1: i32, magic2: i32);
+    let offered: Vec<i32> = [magic1, magic2].into_iter().filter(|&id| id != 0).collect();
+    vm.vm_context
+        .persistent_state_mut()
+        .grant_magic_system(&offered);
     Pal4FunctionState::Completed
 }
 
@@ -1832,15 +1877,13 @@ fn check_magic_mastered(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4Functi
     //   61c8: Ub              ; zero-extend low byte
     //   61cc: Jnz off=228     ; if non-zero, exit the magic-tutorial loop
     //
-    // We hard-code "mastered" (return 1) so the loop exits
-    // immediately and the plot can progress past the magic-grant
-    // sequence in M01/1 without us having to implement the magic
-    // tutorial UI. The prior implementation popped a phantom arg
-    // and `stack_push`ed 1 — but the script never read the
-    // stack, just r1 (which stayed 0), so the loop ran forever
-    // showing the same dialog. See CLAUDE.md AS calling-convention
-    // notes.
-    vm.set_ret_value(1);
+    // The answer comes from the persistent state: mastered once a
+    // party member has learned one of the magics offered by
+    // `giGrantMagicSystem`, on the system UI's magic page. (The return
+    // value must go through `set_ret_value` — the script reads r1, not
+    // the stack.)
+    let mastered = vm.vm_context.persistent_state().magic_mastered();
+    vm.set_ret_value(if mastered { 1 } else { 0 });
     Pal4FunctionState::Completed
 }
 
@@ -2030,17 +2073,98 @@ fn arena_skill_enable(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4Function
 }
 
 fn show_inn_dialog(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm, _show: i32);
-    Pal4FunctionState::Completed
+    // The single operand is the lodging fee. The prompt blocks the
+    // script until the player (or an agent via `/v1/dialog/choose`,
+    // 1 = rest, 2 = leave) decides; the fee is only charged when the
+    // party can afford it. `giGetInnDialogResult` reports the outcome
+    // and the script follows up with `giPlayerTakeARest`.
+    as_params!(vm, price: i32);
+
+    let title = format!("住宿一晚需 {} 文钱，是否休息？", price);
+    let items = vec!["休息".to_string(), "离开".to_string()];
+    let prompt = open_choice_prompt(vm, title, items);
+
+    block_on_choice_prompt(prompt, move |vm, choice| {
+        let rest = choice == 0 && {
+            let mut state = vm.vm_context.persistent_state_mut();
+            if state.money() >= price {
+                state.pay_money(price);
+                true
+            } else {
+                log::info!(
+                    "giShowInnDialog: cannot afford {} (have {})",
+                    price,
+                    state.money()
+                );
+                false
+            }
+        };
+        vm.vm_context
+            .session()
+            .set_inn_dialog_result(if rest { 1 } else { 0 });
+    })
 }
 
 fn get_inn_dialog_result(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    vm.set_ret_value(1);
+    let result = vm.vm_context.session().inn_dialog_result();
+    vm.set_ret_value(result);
     Pal4FunctionState::Completed
 }
 
-fn player_take_a_rest(_: &str, _vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    Pal4FunctionState::Completed
+/// Seconds spent fading to / from black around an inn rest.
+const REST_FADE_SEC: f32 = 1.0;
+/// Seconds the screen stays black while the party sleeps.
+const REST_HOLD_SEC: f32 = 1.0;
+
+fn player_take_a_rest(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
+    // Fade out, refill the party while the screen is black, hold for a
+    // beat and fade back in. Fast-forward collapses the whole
+    // transition into a single frame.
+    enum RestPhase {
+        FadeOut,
+        Hold(f32),
+        FadeIn,
+    }
+
+    vm.vm_context
+        .set_actdrop(InterpValue::new(0., 1., REST_FADE_SEC));
+
+    let mut phase = RestPhase::FadeOut;
+    Pal4FunctionState::Yield(Box::new(move |vm, delta_sec| {
+        if vm.vm_context().fast_forward() {
+            if matches!(phase, RestPhase::FadeOut) {
+                vm.vm_context.persistent_state_mut().rest_party();
+            }
+            vm.vm_context.set_actdrop(InterpValue::new(0., 0., 0.));
+            return ContinuationState::Completed;
+        }
+
+        match &mut phase {
+            RestPhase::FadeOut => {
+                if vm.vm_context.get_actdrop().current() == 1. {
+                    vm.vm_context.persistent_state_mut().rest_party();
+                    phase = RestPhase::Hold(0.);
+                }
+                ContinuationState::Loop
+            }
+            RestPhase::Hold(elapsed) => {
+                *elapsed += delta_sec;
+                if *elapsed >= REST_HOLD_SEC {
+                    vm.vm_context
+                        .set_actdrop(InterpValue::new(1., 0., REST_FADE_SEC));
+                    phase = RestPhase::FadeIn;
+                }
+                ContinuationState::Loop
+            }
+            RestPhase::FadeIn => {
+                if vm.vm_context.get_actdrop().current() == 0. {
+                    ContinuationState::Completed
+                } else {
+                    ContinuationState::Loop
+                }
+            }
+        }
+    }))
 }
 
 fn is_night_time(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
@@ -2064,12 +2188,24 @@ fn player_set_pos_rot_npc(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4Func
 }
 
 fn start_ui_timer(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm, _timer_id: i32, _timer_file_str: i32);
+    // `giStartUiTimer(seconds, "handler")`: start the on-screen
+    // countdown for a timed segment. `handler` is a function in the
+    // current block module, run once the clock reaches zero.
+    as_params!(vm, seconds: i32, callback_str: i32);
+    let callback = get_str(vm, callback_str as usize).unwrap_or_default();
+    vm.vm_context.clear_ui_timer();
+    vm.vm_context
+        .persistent_state_mut()
+        .start_ui_timer(seconds as f32, &callback);
     Pal4FunctionState::Completed
 }
 
 fn player_forbiden_skill(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm, _player_id: i32, _skill_id: i32, _forbiden_skill: i32);
+    as_params!(vm, player_id: i32, skill_id: i32, forbiden_skill: i32);
+    let slot = map_player_slot(vm, player_id);
+    vm.vm_context
+        .persistent_state_mut()
+        .set_skill_forbidden(slot, skill_id, forbiden_skill != 0);
     Pal4FunctionState::Completed
 }
 
@@ -2088,15 +2224,100 @@ fn select_dialog_set_default_select(
 }
 
 fn show_quest_dialog(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm,_quest_file_str:i32);
-    Pal4FunctionState::Completed
+    // Quest offer: show the quest file's title and text and let the
+    // player accept (added to the journal in `Pal4PersistentState`) or
+    // decline. Agents pick through `/v1/dialog/choose` (1 = accept,
+    // 2 = decline).
+    as_params!(vm, quest_file_str: i32);
+
+    let quest_file = get_str(vm, quest_file_str as usize).unwrap_or_default();
+    let quest = match vm.vm_context.loader.load_quest(&quest_file) {
+        Ok(quest) => quest,
+        Err(e) => {
+            log::warn!("giShowQuestDialog: {:?}", e);
+            QuestFile {
+                title: quest_file.clone(),
+                text: String::new(),
+            }
+        }
+    };
+
+    let prompt_text = if quest.text.is_empty() {
+        quest.title.clone()
+    } else {
+        format!("{}\n\n{}", quest.title, quest.text)
+    };
+    let items = vec!["接受".to_string(), "放弃".to_string()];
+    let prompt = open_choice_prompt(vm, prompt_text, items);
+
+    block_on_choice_prompt(prompt, move |vm, choice| {
+        let accepted = choice == 0;
+        if accepted && !quest_file.is_empty() {
+            vm.vm_context.persistent_state_mut().accept_quest(
+                &quest_file,
+                &quest.title,
+                &quest.text,
+            );
+        }
+        vm.vm_context
+            .session()
+            .set_quest_dialog_result(if accepted { 1 } else { 0 });
+    })
 }
 
 fn get_quest_dialog_result(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    vm.set_ret_value(1);
+    let result = vm.vm_context.session().quest_dialog_result();
+    vm.set_ret_value(result);
     Pal4FunctionState::Completed
 }
 
+/// Build a [`ChoicePrompt`] and publish its items on the session's
+/// dialog-choice list, so `/v1/state.dialog.choices` shows what an
+/// agent can pick while the prompt is up.
+fn open_choice_prompt(
+    vm: &mut ScriptVm<Pal4VmContext>,
+    title: String,
+    items: Vec<String>,
+) -> ChoicePrompt {
+    let session = vm.vm_context.session();
+    session.clear_dialog_choices();
+    for item in &items {
+        session.push_dialog_choice(item.clone());
+    }
+    ChoicePrompt::new(title, items)
+}
+
+/// Yield until `prompt` is answered, then hand the 0-based choice to
+/// `on_choice`. A choice buffered through `/v1/dialog/choose` (1-based)
+/// wins over the on-screen prompt; fast-forward picks the first item.
+fn block_on_choice_prompt(
+    mut prompt: ChoicePrompt,
+    on_choice: impl Fn(&mut ScriptVm<Pal4VmContext>, usize) + 'static,
+) -> Pal4FunctionState {
+    Pal4FunctionState::Yield(Box::new(move |vm, _delta_sec| {
+        let buffered = vm.vm_context.session().take_buffered_dialog_choice();
+        let choice = if let Some(choice) = buffered {
+            Some((choice as usize - 1).min(prompt.items().len().saturating_sub(1)))
+        } else if vm.vm_context().fast_forward() {
+            Some(0)
+        } else {
+            let ui = vm.vm_context.ui.clone();
+            let input = vm.vm_context.input.clone();
+            let input = input.borrow();
+            prompt.update(ui.ui(), &*input)
+        };
+
+        match choice {
+            Some(choice) => {
+                vm.vm_context.session().clear_dialog_choices();
+                on_choice(vm, choice);
+                ContinuationState::Completed
+            }
+            None => ContinuationState::Loop,
+        }
+    }))
+}
+
 fn reset_player_to_jump_start(_: &str, _vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
     Pal4FunctionState::Completed
 }
@@ -2129,7 +2350,10 @@ fn remove_equipment(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionSt
 }
 
 fn add_prescription(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    as_params!(vm,_prescription_id:i32,_add_prescription :i32);
+    as_params!(vm, prescription_id: i32, add_prescription: i32);
+    vm.vm_context
+        .persistent_state_mut()
+        .set_prescription(prescription_id, add_prescription != 0);
     Pal4FunctionState::Completed
 }
 
@@ -2230,15 +2454,22 @@ fn enable_sts(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
     Pal4FunctionState::Completed
 }
 
-fn clear_ui_timer(_: &str, _vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
+fn clear_ui_timer(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
+    vm.vm_context.clear_ui_timer();
     Pal4FunctionState::Completed
 }
 
-fn pause_ui_timer(_: &str, _vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
+fn pause_ui_timer(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
+    vm.vm_context
+        .persistent_state_mut()
+        .set_ui_timer_paused(true);
     Pal4FunctionState::Completed
 }
 
-fn resume_ui_timer(_: &str, _vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
+fn resume_ui_timer(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
+    vm.vm_context
+        .persistent_state_mut()
+        .set_ui_timer_paused(false);
     Pal4FunctionState::Completed
 }
 
@@ -2247,7 +2478,13 @@ fn set_full_rage(_: &str, _vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionStat
 }
 
 fn ui_timer_get_save_data(_: &str, vm: &mut ScriptVm<Pal4VmContext>) -> Pal4FunctionState {
-    vm.set_ret_value(1);
+    // Whole seconds left on the running timer, `0` when none is active.
+    let remaining = vm
+        .vm_context
+        .persistent_state()
+        .ui_timer()
+        .map_or(0, |timer| timer.remaining_whole_sec());
+    vm.set_ret_value(remaining);
     Pal4FunctionState::Completed
 }
 
@@ -3256,3 +3493,56 @@ fn get_str(vm: &mut ScriptVm<Pal4VmContext>, index: usize) -> Option<String> {
     let index = vm.resolve_object_index(index as u32)?;
     vm.get_object(index).cloned()
 }
+
+/// Procedures are the current scene module's functions, plus those of
+/// any other module on the call stack (e.g. `Music.csb`).
+impl ScriptDebugTarget for ScriptVm<Pal4VmContext> {
+    fn debug_vm_name(&self) -> &'static str {
+        "angelscript"
+    }
+
+    fn debug_state(&self) -> &RefCell<DebugState> {
+        self.debugger_state()
+    }
+
+    fn debug_procedures(&self) -> Vec<DebugProcedure> {
+        let mut modules: Vec<Rc<RefCell<ScriptModule>>> = vec![];
+        let scene_module = self.vm_context.scene.borrow().module.clone();
+        let running = self.context.iter().chain(self.call_stack.iter());
+        for module in scene_module
+            .into_iter()
+            .chain(running.map(|ctx| ctx.module.clone()))
+        {
+            if !modules.iter().any(|m| Rc::ptr_eq(m, &module)) {
+                modules.push(module);
+            }
+        }
+
+        modules
+            .iter()
+            .flat_map(|module| {
+                module
+                    .borrow()
+                    .functions
+                    .iter()
+                    .map(|f| DebugProcedure {
+                        name: f.name.clone(),
+                        id: None,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn debug_location(&self) -> Option<DebugLocation> {
+        self.debugger_location()
+    }
+
+    fn debug_variables(&self) -> Vec<(String, DebugValue)> {
+        self.debugger_variables()
+    }
+
+    fn debug_read(&self, name: &str) -> Option<DebugValue> {
+        self.debugger_read(name)
+    }
+}
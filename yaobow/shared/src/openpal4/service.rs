@@ -9,6 +9,8 @@
 //!  * the debug-overlay bundle attached (assembled here around the
 //!    script-built overlay from [`Pal4Service::set_script_factory`])
 //!  * the actor controller factory attached (the same script factory)
+//!  * the script debugger attached (if `--script-debug-port` started
+//!    one via [`Pal4Service::start_script_debugger`])
 //!
 //! ## Ownership graph
 //!
@@ -59,6 +61,7 @@ use crate::openpal4::modes::{
 use crate::openpal4::pal4_debug::create_debug_session;
 use crate::openpal4::session::Pal4Session;
 use crate::openpal4::states::persistent_state::{PAL4_APP_NAME, Pal4PersistentState};
+use crate::scripting::angelscript::ScriptDebugger;
 use common::store_ext::StoreExt2;
 
 /// PAL4 save namespace lives in
@@ -133,6 +136,12 @@ pub struct Pal4Service {
     /// returns immediately and the loading layout paints on the next
     /// frame instead of after a multi-second freeze.
     loading_overlay: RefCell<Option<ComRc<IPal4LoadingOverlay>>>,
+
+    /// DAP debugger for the story VM, started by
+    /// `--script-debug-port`. App-lifetime so an attached editor keeps
+    /// its connection and breakpoints when the story director is
+    /// rebuilt (New Game, Load, return to title).
+    script_debugger: RefCell<Option<Rc<RefCell<ScriptDebugger>>>>,
 }
 
 ComObject_Pal4Service!(super::Pal4Service);
@@ -154,6 +163,7 @@ impl Pal4Service {
             session: Rc::new(RefCell::new(Pal4Session::new())),
             launch_asset_path: RefCell::new(None),
             loading_overlay: RefCell::new(None),
+            script_debugger: RefCell::new(None),
         })
     }
 
@@ -193,6 +203,19 @@ impl Pal4Service {
         *slot = Some(bridge);
     }
 
+    /// Start the DAP script debugger on a loopback `port`. Called by
+    /// `YaobowApplicationLoader` during `on_loading` when the binary
+    /// was started with `--pal4 --script-debug-port`. Every story
+    /// director built afterwards has it installed on its VM.
+    pub fn start_script_debugger(&self, port: u16) -> std::io::Result<std::net::SocketAddr> {
+        let debugger = ScriptDebugger::listen((std::net::Ipv4Addr::LOCALHOST, port))?;
+        let addr = debugger
+            .local_addr()
+            .unwrap_or_else(|| (std::net::Ipv4Addr::LOCALHOST, port).into());
+        *self.script_debugger.borrow_mut() = Some(Rc::new(RefCell::new(debugger)));
+        Ok(addr)
+    }
+
     /// Install the script app's PAL4 factory (QI'd from the
     /// reverse-wrapped app root). Called by
     /// `YaobowApplicationLoader::on_loading` after the script root is
@@ -954,6 +977,12 @@ impl Pal4Service {
             bridge.set_rendering_engine(rendering_engine);
             director.set_agent_bridge(bridge);
         }
+        if let Some(debugger) = self.script_debugger.borrow().clone() {
+            director
+                .vm_handle()
+                .borrow_mut()
+                .set_debugger(Some(debugger));
+        }
 
         director
     }
@@ -23,6 +23,7 @@ use std::rc::Rc;
 use radiance::math::{Transform, Vec3};
 
 use super::states::persistent_state::{PAL4_APP_NAME, Pal4PersistentState};
+use super::system_ui::SystemPage;
 
 /// Plain-data snapshot of the live runtime world captured at save time
 /// (and produced at load time for the director to re-apply).
@@ -84,6 +85,19 @@ pub struct Pal4SessionTransient {
     /// behaviour. Consumed (taken) on the next read.
     next_dialog_choice: Cell<Option<i32>>,
 
+    /// Outcome of the last `giShowInnDialog` prompt (`1` = rested,
+    /// `0` = declined or could not pay). Read by
+    /// `giGetInnDialogResult`.
+    inn_dialog_result: Cell<i32>,
+    /// Outcome of the last `giShowQuestDialog` prompt (`1` =
+    /// accepted, `0` = declined). Read by `giGetQuestDialogResult`.
+    quest_dialog_result: Cell<i32>,
+
+    /// System UI page currently on screen, if any. Opened by the
+    /// player's menu hotkey; drawn and closed by the director's UI
+    /// layer.
+    system_ui_page: Cell<Option<SystemPage>>,
+
     /// Deferred scene-transition request, set by callers that want
     /// the loading overlay to cover the synchronous `load_scene`
     /// rather than blocking the game thread mid-frame.
@@ -216,6 +230,48 @@ impl Pal4Session {
         self.transient.next_dialog_choice.set(Some(index));
     }
 
+    /// Take a choice buffered through `/v1/dialog/choose`, if any,
+    /// clearing the pending-items list alongside. Unlike the
+    /// `*_get_last_select` readers this does not fall back to a
+    /// default: blocking prompts (inn / quest) keep waiting for the
+    /// player when `None` is returned.
+    pub fn take_buffered_dialog_choice(&self) -> Option<i32> {
+        let choice = self.transient.next_dialog_choice.take()?;
+        self.transient.pending_dialog_choices.borrow_mut().clear();
+        Some(choice.max(1))
+    }
+
+    /// Drop the pending-items list without consuming a buffered
+    /// choice. Called when a blocking prompt is dismissed by the
+    /// on-screen UI.
+    pub fn clear_dialog_choices(&self) {
+        self.transient.pending_dialog_choices.borrow_mut().clear();
+    }
+
+    pub fn inn_dialog_result(&self) -> i32 {
+        self.transient.inn_dialog_result.get()
+    }
+
+    pub fn set_inn_dialog_result(&self, result: i32) {
+        self.transient.inn_dialog_result.set(result);
+    }
+
+    pub fn system_ui_page(&self) -> Option<SystemPage> {
+        self.transient.system_ui_page.get()
+    }
+
+    pub fn set_system_ui_page(&self, page: Option<SystemPage>) {
+        self.transient.system_ui_page.set(page);
+    }
+
+    pub fn quest_dialog_result(&self) -> i32 {
+        self.transient.quest_dialog_result.get()
+    }
+
+    pub fn set_quest_dialog_result(&self, result: i32) {
+        self.transient.quest_dialog_result.set(result);
+    }
+
     /// Take the buffered 1-based choice (defaulting to the first item)
     /// and clear the pending-items list.
     fn take_dialog_choice_1_based(&self) -> i32 {
@@ -561,6 +617,22 @@ mod tests {
         assert_eq!(session.deferred_load_generation(), gen0.wrapping_add(1));
     }
 
+    #[test]
+    fn buffered_dialog_choice_has_no_default() {
+        // Blocking prompts (inn / quest) must keep waiting when the
+        // agent has not buffered anything, instead of silently picking
+        // the first item like the `*_get_last_select` readers do.
+        let session = Pal4Session::new();
+        session.push_dialog_choice("rest".to_string());
+        assert_eq!(session.take_buffered_dialog_choice(), None);
+        assert_eq!(session.dialog_choices(), vec!["rest".to_string()]);
+
+        session.buffer_dialog_choice(2);
+        assert_eq!(session.take_buffered_dialog_choice(), Some(2));
+        assert!(session.dialog_choices().is_empty());
+        assert_eq!(session.take_buffered_dialog_choice(), None);
+    }
+
     #[test]
     fn load_slot_clears_transient_but_preserves_generation() {
         // load_slot must drop stale queued coordination so a queued
@@ -5,6 +5,7 @@ use radiance::math::Transform;
 use radiance::math::Vec3;
 use serde::{Deserialize, Serialize};
 
+use crate::openpal4::ui_timer::UiTimer;
 use crate::ydirs;
 
 /// Number of fixed party slots in PAL4 (YunTianhe / HanLingsha /
@@ -39,6 +40,11 @@ pub struct PlayerState {
     pub skills: Vec<i32>,
     #[serde(default)]
     pub equipment: Vec<i32>,
+    /// Skills a script has sealed with `giPlayerForbidenSkill`. They
+    /// stay in `skills` (the player still knows them) but the skills
+    /// screen shows them as unusable.
+    #[serde(default)]
+    pub forbidden_skills: Vec<i32>,
 }
 
 impl Default for PlayerState {
@@ -52,10 +58,66 @@ impl Default for PlayerState {
             in_team: false,
             skills: Vec::new(),
             equipment: Vec::new(),
+            forbidden_skills: Vec::new(),
         }
     }
 }
 
+/// Lifecycle of a quest-journal entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuestStatus {
+    Active,
+    Completed,
+}
+
+/// One quest-journal entry. Added when the player accepts a quest
+/// offered through `giShowQuestDialog`, closed when the script credits
+/// its completion with `giAddQuestCompletePercentage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestEntry {
+    /// The quest file named by `giShowQuestDialog`; the entry's
+    /// identity.
+    pub key: String,
+    /// Title and description read from the quest file. Empty in older
+    /// saves, which only kept the key.
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub text: String,
+    pub status: QuestStatus,
+    /// Completion percentage credited when the quest was closed; `0`
+    /// while the quest is still active.
+    #[serde(default)]
+    pub percentage: i32,
+}
+
+impl QuestEntry {
+    /// The title to show: the quest file's own title, or the file name
+    /// for entries from older saves.
+    pub fn display_title(&self) -> &str {
+        if self.title.is_empty() {
+            &self.key
+        } else {
+            &self.title
+        }
+    }
+}
+
+/// In-game systems unlocked by the story. Both start locked; the magic
+/// (五灵 learning) page of the system UI stays hidden until its grant
+/// runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SystemGrants {
+    /// Operands of `giGrantSmithSystem`, kept verbatim. `None` while
+    /// the smith is still locked.
+    #[serde(default)]
+    pub smith: Option<[i32; 4]>,
+    /// Magic skill ids offered by `giGrantMagicSystem`. Empty while the
+    /// magic system is still locked.
+    #[serde(default)]
+    pub magic: Vec<i32>,
+}
+
 /// Serializable snapshot of PAL4 game progress. Saved as slot-based
 /// JSON under `<save_dir>/<app_name>/Save/<slot>.json`, mirroring the
 /// OpenPAL3 `PersistentState` convention.
@@ -66,6 +128,9 @@ pub struct Pal4PersistentState {
     money: i32,
     #[serde(default)]
     quest_percentage: i32,
+    /// Quest journal in acceptance order. Absent in older saves.
+    #[serde(default)]
+    quests: Vec<QuestEntry>,
     #[serde(default)]
     leader: usize,
     #[serde(default)]
@@ -111,6 +176,21 @@ pub struct Pal4PersistentState {
     /// which hold cross-scene story-plot flags.
     #[serde(default)]
     script_globals: Vec<u32>,
+    /// Running `giStartUiTimer` countdown, if any. Absent in older saves.
+    #[serde(default)]
+    ui_timer: Option<UiTimer>,
+    /// Handler of a countdown that has run out but not been dispatched
+    /// yet (the VM was busy). Saved with the timer so a slot taken in
+    /// between still runs it after loading.
+    #[serde(default)]
+    ui_timer_expired: Option<String>,
+    /// Smith / magic unlocks. Absent in older saves.
+    #[serde(default)]
+    systems: SystemGrants,
+    /// Forging recipes learned through `giAddPrescription`, in the
+    /// order they were learned.
+    #[serde(default)]
+    prescriptions: Vec<i32>,
 }
 
 impl Pal4PersistentState {
@@ -128,6 +208,7 @@ impl Pal4PersistentState {
             app_name,
             money: 0,
             quest_percentage: 0,
+            quests: Vec::new(),
             leader: 0,
             scene_name: String::new(),
             block_name: String::new(),
@@ -143,6 +224,10 @@ impl Pal4PersistentState {
             players,
             inventory: HashMap::new(),
             script_globals: Vec::new(),
+            ui_timer: None,
+            ui_timer_expired: None,
+            systems: SystemGrants::default(),
+            prescriptions: Vec::new(),
         }
     }
 
@@ -247,6 +332,48 @@ impl Pal4PersistentState {
         self.quest_percentage = (self.quest_percentage + delta).clamp(0, 100);
     }
 
+    pub fn quests(&self) -> &[QuestEntry] {
+        &self.quests
+    }
+
+    /// Record the quest file `key` as accepted, with the title and text
+    /// read from it. Re-accepting a quest already in the journal
+    /// (active or completed) is a no-op; returns `true` only when a new
+    /// entry was added.
+    pub fn accept_quest(&mut self, key: &str, title: &str, text: &str) -> bool {
+        if self.quests.iter().any(|q| q.key == key) {
+            return false;
+        }
+
+        self.quests.push(QuestEntry {
+            key: key.to_string(),
+            title: title.to_string(),
+            text: text.to_string(),
+            status: QuestStatus::Active,
+            percentage: 0,
+        });
+        true
+    }
+
+    /// Close the most recently accepted active quest, crediting it with
+    /// `percentage`, and bump the overall completion. PAL4 scripts call
+    /// `giAddQuestCompletePercentage` right after a side quest's reward,
+    /// so the newest open entry is the one being finished. With no
+    /// active quest only the overall number moves.
+    pub fn complete_quest(&mut self, percentage: i32) {
+        if let Some(quest) = self
+            .quests
+            .iter_mut()
+            .rev()
+            .find(|q| q.status == QuestStatus::Active)
+        {
+            quest.status = QuestStatus::Completed;
+            quest.percentage = percentage;
+        }
+
+        self.add_quest_percentage(percentage);
+    }
+
     // --- Players -------------------------------------------------------
 
     pub fn player(&self, slot: usize) -> Option<&PlayerState> {
@@ -276,6 +403,22 @@ impl Pal4PersistentState {
         }
     }
 
+    pub fn remove_skill(&mut self, slot: usize, skill_id: i32) {
+        let player = self.player_mut(slot);
+        player.skills.retain(|&id| id != skill_id);
+        player.forbidden_skills.retain(|&id| id != skill_id);
+    }
+
+    pub fn set_skill_forbidden(&mut self, slot: usize, skill_id: i32, forbidden: bool) {
+        let player = self.player_mut(slot);
+        let listed = player.forbidden_skills.contains(&skill_id);
+        if forbidden && !listed {
+            player.forbidden_skills.push(skill_id);
+        } else if !forbidden && listed {
+            player.forbidden_skills.retain(|&id| id != skill_id);
+        }
+    }
+
     /// Attach an equipment id to a specific player slot.
     pub fn add_player_equip(&mut self, slot: usize, equip_id: i32) {
         let player = self.player_mut(slot);
@@ -294,6 +437,19 @@ impl Pal4PersistentState {
         player.mp = player.max_mp;
     }
 
+    /// Inn rest: refill HP/MP of every party member in the team. The
+    /// leader is always included, even before a script has flagged
+    /// anyone as in-team.
+    pub fn rest_party(&mut self) {
+        for slot in 0..PLAYER_COUNT {
+            let in_team = self.players.get(&slot).map(|p| p.in_team).unwrap_or(false);
+            if in_team || slot == self.leader {
+                self.set_full_hp(slot);
+                self.set_full_mp(slot);
+            }
+        }
+    }
+
     // --- Inventory / equipment ----------------------------------------
 
     pub fn add_equipment(&mut self, equip_id: i32, count: i32) {
@@ -405,6 +561,99 @@ impl Pal4PersistentState {
     pub fn set_script_globals(&mut self, globals: Vec<u32>) {
         self.script_globals = globals;
     }
+
+    // --- Smith / magic systems ----------------------------------------
+
+    pub fn systems(&self) -> &SystemGrants {
+        &self.systems
+    }
+
+    pub fn grant_smith_system(&mut self, args: [i32; 4]) {
+        self.systems.smith = Some(args);
+    }
+
+    pub fn grant_magic_system(&mut self, magic_ids: &[i32]) {
+        for &id in magic_ids {
+            if !self.systems.magic.contains(&id) {
+                self.systems.magic.push(id);
+            }
+        }
+    }
+
+    /// `true` once some party member has learned one of the magic skills
+    /// offered by `giGrantMagicSystem`. Trivially `true` while no magic
+    /// has been offered, so scripts that ask before the grant don't
+    /// block.
+    pub fn magic_mastered(&self) -> bool {
+        self.systems.magic.is_empty()
+            || self
+                .players
+                .values()
+                .any(|p| p.skills.iter().any(|id| self.systems.magic.contains(id)))
+    }
+
+    pub fn prescriptions(&self) -> &[i32] {
+        &self.prescriptions
+    }
+
+    pub fn set_prescription(&mut self, prescription_id: i32, learned: bool) {
+        let known = self.prescriptions.contains(&prescription_id);
+        if learned && !known {
+            self.prescriptions.push(prescription_id);
+        } else if !learned && known {
+            self.prescriptions.retain(|&id| id != prescription_id);
+        }
+    }
+
+    // --- UI timer (timed story segments) ------------------------------
+
+    pub fn ui_timer(&self) -> Option<&UiTimer> {
+        self.ui_timer.as_ref()
+    }
+
+    /// Start (or restart) the countdown. PAL4 only ever runs one UI
+    /// timer at a time, so a second `giStartUiTimer` replaces the first.
+    pub fn start_ui_timer(&mut self, seconds: f32, callback: &str) {
+        self.ui_timer = Some(UiTimer::new(seconds, callback));
+    }
+
+    /// Drop the countdown along with any expiry not dispatched yet
+    /// (`giClearUiTimer`).
+    pub fn clear_ui_timer(&mut self) {
+        self.ui_timer = None;
+        self.ui_timer_expired = None;
+    }
+
+    pub fn set_ui_timer_paused(&mut self, paused: bool) {
+        if let Some(timer) = self.ui_timer.as_mut() {
+            timer.paused = paused;
+        }
+    }
+
+    /// Advance the countdown. On expiry the timer is removed and its
+    /// callback, if it has one, is kept until
+    /// [`Self::take_expired_ui_timer`] collects it. Returns `true` on
+    /// the frame the timer runs out.
+    pub fn tick_ui_timer(&mut self, delta_sec: f32) -> bool {
+        let Some(timer) = self.ui_timer.as_mut() else {
+            return false;
+        };
+        if !timer.tick(delta_sec) {
+            return false;
+        }
+
+        self.ui_timer_expired = self
+            .ui_timer
+            .take()
+            .map(|timer| timer.callback)
+            .filter(|callback| !callback.is_empty());
+        true
+    }
+
+    /// The handler of an expired countdown, once.
+    pub fn take_expired_ui_timer(&mut self) -> Option<String> {
+        self.ui_timer_expired.take()
+    }
 }
 
 #[cfg(test)]
@@ -467,6 +716,52 @@ mod tests {
         assert!(state.camera().is_none());
     }
 
+    #[test]
+    fn quest_journal_accept_and_complete() {
+        let mut state = Pal4PersistentState::new("OpenPAL4".to_string());
+        assert!(state.accept_quest("q1", "Title 1", "Text 1"));
+        assert!(state.accept_quest("q2", "Title 2", "Text 2"));
+        // Re-offering a known quest does not duplicate it.
+        assert!(!state.accept_quest("q1", "Title 1", "Text 1"));
+        assert_eq!(state.quests()[1].title, "Title 2");
+
+        // Completion closes the newest active entry.
+        state.complete_quest(5);
+        assert_eq!(state.quests()[1].status, QuestStatus::Completed);
+        assert_eq!(state.quests()[1].percentage, 5);
+        assert_eq!(state.quests()[0].status, QuestStatus::Active);
+        assert_eq!(state.quest_percentage(), 5);
+
+        state.complete_quest(3);
+        assert_eq!(state.quests()[0].status, QuestStatus::Completed);
+        assert_eq!(state.quest_percentage(), 8);
+
+        // Nothing left open: only the overall number moves.
+        state.complete_quest(2);
+        assert_eq!(state.quest_percentage(), 10);
+    }
+
+    #[test]
+    fn rest_party_refills_team_and_leader() {
+        let mut state = Pal4PersistentState::new("OpenPAL4".to_string());
+        for slot in 0..PLAYER_COUNT {
+            let p = state.player_mut(slot);
+            p.max_hp = 100;
+            p.max_mp = 50;
+        }
+        state.set_leader(0);
+        state.set_in_team(2, true);
+
+        state.rest_party();
+
+        assert_eq!(state.player(0).unwrap().hp, 100);
+        assert_eq!(state.player(0).unwrap().mp, 50);
+        assert_eq!(state.player(2).unwrap().hp, 100);
+        // Out-of-team members are not refilled.
+        assert_eq!(state.player(1).unwrap().hp, 0);
+        assert_eq!(state.player(3).unwrap().mp, 0);
+    }
+
     #[test]
     fn new_game_starts_player_locked() {
         // A fresh playthrough must start control-locked so the new-game
@@ -486,4 +781,69 @@ mod tests {
         let state: Pal4PersistentState = serde_json::from_str(json).unwrap();
         assert!(!state.player_locked());
     }
+
+    #[test]
+    fn ui_timer_survives_save_round_trip_and_fires_once() {
+        let mut state = Pal4PersistentState::new("OpenPAL4".to_string());
+        state.start_ui_timer(2.0, "OnTimeUp");
+        state.set_ui_timer_paused(true);
+        assert!(!state.tick_ui_timer(5.0));
+
+        let json = serde_json::to_string(&state).unwrap();
+        let mut loaded: Pal4PersistentState = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.ui_timer(), state.ui_timer());
+
+        loaded.set_ui_timer_paused(false);
+        assert!(loaded.tick_ui_timer(2.0));
+        assert!(loaded.ui_timer().is_none());
+        assert!(!loaded.tick_ui_timer(2.0));
+        assert_eq!(loaded.take_expired_ui_timer(), Some("OnTimeUp".to_string()));
+        assert_eq!(loaded.take_expired_ui_timer(), None);
+    }
+
+    #[test]
+    fn expired_ui_timer_handler_survives_save_round_trip() {
+        let mut state = Pal4PersistentState::new("OpenPAL4".to_string());
+        state.start_ui_timer(1.0, "OnTimeUp");
+        assert!(state.tick_ui_timer(1.0));
+
+        // Saved after expiry but before the handler was dispatched.
+        let json = serde_json::to_string(&state).unwrap();
+        let mut loaded: Pal4PersistentState = serde_json::from_str(&json).unwrap();
+        assert!(loaded.ui_timer().is_none());
+        assert_eq!(loaded.take_expired_ui_timer(), Some("OnTimeUp".to_string()));
+
+        state.clear_ui_timer();
+        assert_eq!(state.take_expired_ui_timer(), None);
+    }
+
+    #[test]
+    fn magic_mastered_tracks_granted_magic() {
+        let mut state = Pal4PersistentState::new("OpenPAL4".to_string());
+        assert!(state.magic_mastered());
+
+        state.grant_magic_system(&[301, 302]);
+        state.add_skill(1, 17);
+        assert!(!state.magic_mastered());
+
+        state.add_skill(2, 302);
+        assert!(state.magic_mastered());
+    }
+
+    #[test]
+    fn forbidden_skill_toggles_and_clears_with_skill() {
+        let mut state = Pal4PersistentState::new("OpenPAL4".to_string());
+        state.add_skill(0, 12);
+        state.set_skill_forbidden(0, 12, true);
+        state.set_skill_forbidden(0, 12, true);
+        assert_eq!(state.player(0).unwrap().forbidden_skills, vec![12]);
+
+        state.set_skill_forbidden(0, 12, false);
+        assert!(state.player(0).unwrap().forbidden_skills.is_empty());
+
+        state.set_skill_forbidden(0, 12, true);
+        state.remove_skill(0, 12);
+        let player = state.player(0).unwrap();
+        assert!(player.skills.is_empty() && player.forbidden_skills.is_empty());
+    }
 }
@@ -0,0 +1,382 @@
+//! PAL4 in-game system UI: status, skills and magic-learning pages.
+//!
+//! Drawn by the director's UI layer through the `IUiHost` background
+//! draw-list primitives (`fill_rect` / `text_at`), the same path the
+//! scripted PAL3 dialog box uses, so it composes on the engine's imgui
+//! frame without owning any imgui windows.
+//!
+//! Only pages that need nothing beyond `Pal4PersistentState` are here.
+//! Equipment and smith (forging) pages need the item and recipe tables
+//! PAL4 ships with, which are not decoded yet; the CEGUI layout of each
+//! page and the `giOpenSystemUi` ids are not identified either, so the
+//! pages draw on a flat panel and are opened from the menu hotkey only.
+//! Skills are listed by id.
+//!
+//! Everything shown is read from, and every action writes to,
+//! `Pal4PersistentState` (`PlayerState.skills` and the magic grant), so
+//! scripted checks such as `giCheckMagicMastered` observe what the
+//! player did here.
+
+use std::cell::Cell;
+
+use crosscom::ComRc;
+use radiance::comdef::IUiHost;
+use radiance::input::{InputEngine, Key};
+
+use super::states::persistent_state::{PLAYER_COUNT, Pal4PersistentState};
+
+const PLAYER_NAMES: [&str; PLAYER_COUNT] = ["云天河", "韩菱纱", "柳梦璃", "慕容紫英"];
+
+const TEXT: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const TEXT_DIM: [f32; 4] = [0.55, 0.55, 0.55, 1.0];
+const TEXT_ACCENT: [f32; 4] = [1.0, 0.85, 0.4, 1.0];
+const PANEL: [f32; 4] = [0.05, 0.06, 0.1, 0.88];
+const BUTTON: [f32; 4] = [0.2, 0.22, 0.3, 0.9];
+const BUTTON_HOVER: [f32; 4] = [0.32, 0.35, 0.48, 0.95];
+const BUTTON_ACTIVE: [f32; 4] = [0.55, 0.42, 0.18, 0.95];
+
+/// One page of the system UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SystemPage {
+    Status,
+    Skills,
+    Magic,
+}
+
+impl SystemPage {
+    pub const ALL: [SystemPage; 3] = [SystemPage::Status, SystemPage::Skills, SystemPage::Magic];
+
+    fn title(self) -> &'static str {
+        match self {
+            SystemPage::Status => "状态",
+            SystemPage::Skills => "技能",
+            SystemPage::Magic => "五灵",
+        }
+    }
+
+    /// The magic page only appears once the story has granted the
+    /// magic system.
+    pub fn is_available(self, state: &Pal4PersistentState) -> bool {
+        match self {
+            SystemPage::Magic => !state.systems().magic.is_empty(),
+            _ => true,
+        }
+    }
+
+    fn has_player_row(self) -> bool {
+        self != SystemPage::Status
+    }
+}
+
+/// What the director should do with the page after a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemUiAction {
+    Stay,
+    Switch(SystemPage),
+    Close,
+}
+
+#[derive(Clone, Copy)]
+struct Rect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+impl Rect {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.w && y >= self.y && y < self.y + self.h
+    }
+}
+
+/// Per-frame pointer state shared by the widget helpers.
+struct Pointer {
+    x: f32,
+    y: f32,
+    clicked: bool,
+}
+
+#[derive(Default)]
+pub struct Pal4SystemUi {
+    selected_player: Cell<usize>,
+    prev_mouse_down: Cell<bool>,
+}
+
+impl Pal4SystemUi {
+    /// Draw one frame of `page` and apply whatever the player clicked.
+    pub fn render(
+        &self,
+        ui: &ComRc<IUiHost>,
+        page: SystemPage,
+        state: &mut Pal4PersistentState,
+        input: &dyn InputEngine,
+    ) -> SystemUiAction {
+        if input.get_key_state(Key::Escape).pressed() {
+            return SystemUiAction::Close;
+        }
+
+        let tabs: Vec<SystemPage> = SystemPage::ALL
+            .into_iter()
+            .filter(|p| p.is_available(state))
+            .collect();
+        if !tabs.contains(&page) {
+            return SystemUiAction::Switch(SystemPage::Status);
+        }
+
+        let current = tabs.iter().position(|&p| p == page).unwrap_or(0);
+        if input.get_key_state(Key::Left).pressed() {
+            return SystemUiAction::Switch(tabs[(current + tabs.len() - 1) % tabs.len()]);
+        }
+        if input.get_key_state(Key::Right).pressed() {
+            return SystemUiAction::Switch(tabs[(current + 1) % tabs.len()]);
+        }
+
+        let mouse_down = ui.mouse_down(0);
+        let pointer = Pointer {
+            x: ui.mouse_pos_x(),
+            y: ui.mouse_pos_y(),
+            clicked: mouse_down && !self.prev_mouse_down.get(),
+        };
+        self.prev_mouse_down.set(mouse_down);
+
+        let display_w = ui.display_size_x() as f32;
+        let display_h = ui.display_size_y() as f32;
+        let panel_w = (display_w * 0.85).min(800.);
+        let panel_h = (display_h * 0.85).min(600.);
+        let panel = Rect {
+            x: (display_w - panel_w) / 2.,
+            y: (display_h - panel_h) / 2.,
+            w: panel_w,
+            h: panel_h,
+        };
+
+        fill(ui, panel, PANEL);
+
+        let line_h = ui.game_font_size().max(18.) + 6.;
+        let mut action = SystemUiAction::Stay;
+
+        // Tab row.
+        let tab_w = 96.;
+        for (i, &tab) in tabs.iter().enumerate() {
+            let rect = Rect {
+                x: panel.x + 16. + i as f32 * (tab_w + 8.),
+                y: panel.y + 12.,
+                w: tab_w,
+                h: line_h,
+            };
+            if button(ui, &pointer, rect, tab.title(), true, tab == page) && tab != page {
+                action = SystemUiAction::Switch(tab);
+            }
+        }
+        let close = Rect {
+            x: panel.x + panel.w - 16. - tab_w,
+            y: panel.y + 12.,
+            w: tab_w,
+            h: line_h,
+        };
+        if button(ui, &pointer, close, "关闭", true, false) {
+            action = SystemUiAction::Close;
+        }
+
+        let mut y = panel.y + 24. + line_h;
+        if page.has_player_row() {
+            for slot in 0..PLAYER_COUNT {
+                let rect = Rect {
+                    x: panel.x + 16. + slot as f32 * (tab_w + 8.),
+                    y,
+                    w: tab_w,
+                    h: line_h,
+                };
+                let selected = self.selected_player.get() == slot;
+                if button(ui, &pointer, rect, PLAYER_NAMES[slot], true, selected) {
+                    self.selected_player.set(slot);
+                }
+            }
+            y += line_h + 12.;
+        }
+
+        let body = Rect {
+            x: panel.x + 24.,
+            y,
+            w: panel.w - 48.,
+            h: panel.y + panel.h - y - 16.,
+        };
+        let mut page_ui = PageUi {
+            ui,
+            pointer: &pointer,
+            body,
+            line_h,
+            y: body.y,
+        };
+        let slot = self.selected_player.get();
+        match page {
+            SystemPage::Status => page_status(&mut page_ui, state),
+            SystemPage::Skills => page_skills(&mut page_ui, state, slot),
+            SystemPage::Magic => page_magic(&mut page_ui, state, slot),
+        }
+
+        action
+    }
+}
+
+/// Cursor over a page's body rect.
+struct PageUi<'a> {
+    ui: &'a ComRc<IUiHost>,
+    pointer: &'a Pointer,
+    body: Rect,
+    line_h: f32,
+    y: f32,
+}
+
+impl PageUi<'_> {
+    fn line(&mut self, text: &str, color: [f32; 4]) {
+        self.line_at(0., text, color);
+        self.y += self.line_h;
+    }
+
+    fn line_at(&self, x: f32, text: &str, color: [f32; 4]) {
+        if self.y + self.line_h <= self.body.y + self.body.h {
+            text_at(self.ui, self.body.x + x, self.y, text, color);
+        }
+    }
+
+    /// A text row with a trailing button at column `button_x`; returns
+    /// `true` when the button was clicked.
+    fn row_with_button(&mut self, text: &str, color: [f32; 4], label: &str, enabled: bool) -> bool {
+        self.line_at(0., text, color);
+        let rect = Rect {
+            x: self.body.x + self.body.w - 96.,
+            y: self.y,
+            w: 96.,
+            h: self.line_h - 4.,
+        };
+        let clicked = button(self.ui, self.pointer, rect, label, enabled, false);
+        self.y += self.line_h;
+        clicked
+    }
+}
+
+fn page_status(page: &mut PageUi, state: &Pal4PersistentState) {
+    page.line(&format!("金钱：{}", state.money()), TEXT_ACCENT);
+    page.y += 8.;
+    for slot in 0..PLAYER_COUNT {
+        let Some(player) = state.player(slot) else {
+            continue;
+        };
+        if !player.in_team && slot != state.leader() {
+            continue;
+        }
+
+        page.line(
+            &format!("{}  等级 {}", PLAYER_NAMES[slot], player.level),
+            TEXT_ACCENT,
+        );
+        page.line(
+            &format!(
+                "  精 {}/{}    神 {}/{}",
+                player.hp, player.max_hp, player.mp, player.max_mp
+            ),
+            TEXT,
+        );
+    }
+}
+
+fn page_skills(page: &mut PageUi, state: &Pal4PersistentState, slot: usize) {
+    let Some(player) = state.player(slot).filter(|p| !p.skills.is_empty()) else {
+        page.line("尚未习得技能", TEXT_DIM);
+        return;
+    };
+
+    for &skill in &player.skills {
+        if player.forbidden_skills.contains(&skill) {
+            page.line(&format!("技能 #{}  （封印）", skill), TEXT_DIM);
+        } else {
+            page.line(&format!("技能 #{}", skill), TEXT);
+        }
+    }
+}
+
+fn page_magic(page: &mut PageUi, state: &mut Pal4PersistentState, slot: usize) {
+    page.line(
+        &format!("选择要让 {} 修习的五灵仙术", PLAYER_NAMES[slot]),
+        TEXT_ACCENT,
+    );
+    let offered = state.systems().magic.clone();
+    for id in offered {
+        let learned = state.player(slot).is_some_and(|p| p.skills.contains(&id));
+        let (label, color) = if learned {
+            ("已习得", TEXT_DIM)
+        } else {
+            ("修习", TEXT)
+        };
+        if page.row_with_button(&format!("  仙术 #{}", id), color, label, !learned) {
+            state.add_skill(slot, id);
+        }
+    }
+}
+
+fn fill(ui: &ComRc<IUiHost>, rect: Rect, color: [f32; 4]) {
+    ui.fill_rect(
+        rect.x,
+        rect.y,
+        rect.x + rect.w,
+        rect.y + rect.h,
+        color[0],
+        color[1],
+        color[2],
+        color[3],
+    );
+}
+
+fn text_at(ui: &ComRc<IUiHost>, x: f32, y: f32, text: &str, color: [f32; 4]) {
+    ui.text_at(x, y, color[0], color[1], color[2], color[3], text);
+}
+
+/// Flat button drawn on the background list. Returns `true` on the
+/// frame an enabled button is clicked.
+fn button(
+    ui: &ComRc<IUiHost>,
+    pointer: &Pointer,
+    rect: Rect,
+    label: &str,
+    enabled: bool,
+    active: bool,
+) -> bool {
+    let hovered = enabled && rect.contains(pointer.x, pointer.y);
+    let color = if active {
+        BUTTON_ACTIVE
+    } else if hovered {
+        BUTTON_HOVER
+    } else {
+        BUTTON
+    };
+    fill(ui, rect, color);
+
+    let text_w = ui.calc_text_size_x(label);
+    let text_h = ui.calc_text_size_y(label);
+    text_at(
+        ui,
+        rect.x + (rect.w - text_w).max(0.) / 2.,
+        rect.y + (rect.h - text_h).max(0.) / 2.,
+        label,
+        if enabled { TEXT } else { TEXT_DIM },
+    );
+
+    hovered && pointer.clicked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_page_needs_grant() {
+        let mut state = Pal4PersistentState::new("OpenPAL4".to_string());
+        assert!(SystemPage::Status.is_available(&state));
+        assert!(!SystemPage::Magic.is_available(&state));
+
+        state.grant_magic_system(&[301]);
+        assert!(SystemPage::Magic.is_available(&state));
+    }
+}
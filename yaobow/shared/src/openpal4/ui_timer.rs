@@ -0,0 +1,111 @@
+use imgui::{Condition, Ui};
+use serde::{Deserialize, Serialize};
+
+/// Countdown behind PAL4's timed story segments (`giStartUiTimer`).
+///
+/// Lives in `Pal4PersistentState` so a save taken mid-challenge resumes
+/// with the same remaining time. `Pal4VmContext::update` ticks it with
+/// the agent-adjusted frame delta, so `/v1/time/pause` freezes the clock
+/// along with the rest of the simulation. When it reaches zero the
+/// named AngelScript function is scheduled on the current block module
+/// (the script's "time is up" handler).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiTimer {
+    /// Script function run on expiry. Empty when the script passed no
+    /// handler; the timer then just disappears.
+    pub callback: String,
+    pub remaining_sec: f32,
+    /// Set by `giPauseUiTimer`, cleared by `giResumeUiTimer`. A paused
+    /// timer stays on screen but does not count down.
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl UiTimer {
+    pub fn new(seconds: f32, callback: &str) -> Self {
+        Self {
+            callback: callback.to_string(),
+            remaining_sec: seconds.max(0.),
+            paused: false,
+        }
+    }
+
+    /// Advance the countdown by `delta_sec`. Returns `true` on the
+    /// frame the timer runs out; the caller is expected to drop it.
+    pub fn tick(&mut self, delta_sec: f32) -> bool {
+        if self.paused {
+            return false;
+        }
+
+        self.remaining_sec = (self.remaining_sec - delta_sec).max(0.);
+        self.remaining_sec == 0.
+    }
+
+    /// Whole seconds left, rounded up so the widget only shows `0:00`
+    /// once the timer has actually expired.
+    pub fn remaining_whole_sec(&self) -> i32 {
+        self.remaining_sec.ceil() as i32
+    }
+}
+
+/// Draw the countdown in the top-right corner of the screen. Blinks
+/// red during the last ten seconds, like the original HUD.
+pub fn render_countdown(ui: &Ui, timer: &UiTimer) {
+    let [window_width, _] = ui.io().display_size;
+    let seconds = timer.remaining_whole_sec();
+    let text = format!("{}:{:02}", seconds / 60, seconds % 60);
+
+    let color = if seconds <= 10 && (timer.remaining_sec.fract() > 0.5 || timer.paused) {
+        [1.0, 0.25, 0.25, 1.0]
+    } else {
+        [1.0, 1.0, 1.0, 1.0]
+    };
+
+    let _font_token =
+        radiance::imgui::game_font(radiance::imgui::GameFontSize::LARGE).map(|f| ui.push_font(f));
+    ui.window("ui_timer")
+        .collapsible(false)
+        .title_bar(false)
+        .resizable(false)
+        .movable(false)
+        .focused(false)
+        .no_decoration()
+        .always_auto_resize(true)
+        .position([window_width - 20., 20.], Condition::Always)
+        .position_pivot([1., 0.])
+        .build(|| {
+            ui.text_colored(color, text);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_expires_once_time_runs_out() {
+        let mut timer = UiTimer::new(1.0, "OnTimeUp");
+        assert!(!timer.tick(0.6));
+        assert_eq!(timer.remaining_whole_sec(), 1);
+        assert!(timer.tick(0.6));
+        assert_eq!(timer.remaining_sec, 0.);
+    }
+
+    #[test]
+    fn paused_timer_does_not_count_down() {
+        let mut timer = UiTimer::new(5.0, "OnTimeUp");
+        timer.paused = true;
+        assert!(!timer.tick(10.0));
+        assert_eq!(timer.remaining_sec, 5.0);
+
+        timer.paused = false;
+        assert!(timer.tick(10.0));
+    }
+
+    #[test]
+    fn older_timer_json_defaults_to_running() {
+        let timer: UiTimer =
+            serde_json::from_str(r#"{"callback":"f","remaining_sec":3.0}"#).unwrap();
+        assert!(!timer.paused);
+    }
+}
@@ -77,6 +77,7 @@ pub struct PartySnapshot {
 use super::{
     actor::{IPal4ActorAnimationControllerExt, Pal4ActorAnimation, Pal4ActorAnimationConfig},
     asset_loader::AssetLoader,
+    npc_behavior::BehaviorCommand,
     scene::{Pal4Scene, object_armature, play_object_animation},
     session::Pal4Session,
     states::persistent_state::Pal4PersistentState,
@@ -269,6 +270,8 @@ impl Pal4VmContext {
         // paused partway through (the planner can still see the visual
         // state in `/v1/screenshot`).
         self.tick_camera_run(delta_sec);
+        self.tick_ui_timer(delta_sec);
+        self.tick_npc_behaviors(delta_sec);
 
         // Ambient SOUND emitters (GOB tag 3) are now self-driving
         // `AudioSourceComponent`s attached to per-emitter entities in
@@ -325,6 +328,18 @@ impl Pal4VmContext {
     }
 
     pub fn event_triggered(&mut self, delta_sec: f32) -> Option<String> {
+        // A timed segment running out preempts everything else: the
+        // script's failure handler must run before the player gets
+        // another chance to interact.
+        let expired = self
+            .session
+            .borrow_mut()
+            .state_mut()
+            .take_expired_ui_timer();
+        if let Some(callback) = expired {
+            return Some(callback);
+        }
+
         let leader = self.session.borrow().state().leader();
         let scene = self.scene.borrow();
         let from_trigger = scene
@@ -396,6 +411,107 @@ impl Pal4VmContext {
         Some(script.function.clone())
     }
 
+    /// Count the `giStartUiTimer` timer down. `delta_sec` is the
+    /// agent-adjusted frame delta, so the countdown freezes under
+    /// `/v1/time/pause` (the director skips `update` entirely on paused
+    /// frames). The expiry handler is kept in the persistent state
+    /// until [`Self::event_triggered`] hands it to the director; the
+    /// widget is drawn by the director's UI layer.
+    fn tick_ui_timer(&mut self, delta_sec: f32) {
+        let mut session = self.session.borrow_mut();
+        if session.state_mut().tick_ui_timer(delta_sec) {
+            log::debug!("giStartUiTimer: expired");
+        }
+    }
+
+    /// Drop the running UI timer along with any expiry that has not
+    /// been dispatched yet (`giClearUiTimer`).
+    pub fn clear_ui_timer(&mut self) {
+        self.session.borrow_mut().state_mut().clear_ui_timer();
+    }
+
+    /// Drive ambient NPC behaviour (patrols, wandering, monster
+    /// pursuit). Frozen while the player is locked so cutscenes keep
+    /// full control of their actors.
+    fn tick_npc_behaviors(&mut self, delta_sec: f32) {
+        if self.is_player_locked() {
+            return;
+        }
+
+        let mut behaviors = std::mem::take(&mut self.scene.borrow_mut().npc_behaviors);
+        if behaviors.is_empty() {
+            return;
+        }
+
+        let leader = self.get_player_pos(-1);
+        for (name, behavior) in behaviors.iter_mut() {
+            let Some(entity) = self.scene.borrow().get_npc(name) else {
+                continue;
+            };
+            if !entity.enabled() {
+                continue;
+            }
+
+            let pos = entity.transform().borrow().position();
+            let moving = self.npc_moving(name);
+            match behavior.tick(pos, leader, moving, delta_sec) {
+                BehaviorCommand::Wait => {}
+                BehaviorCommand::MoveTo { target, run } => {
+                    let target = Vec3::from(target);
+                    let id = ActorId::Npc(name.clone());
+                    let mut moving_entities = self.moving_entities.borrow_mut();
+                    match moving_entities.get_mut(&id) {
+                        // Retarget in place so a pursuing monster doesn't
+                        // restart its run cycle every frame.
+                        Some(entry) if entry.run == run => entry.target = target,
+                        _ => {
+                            drop(moving_entities);
+                            self.npc_to(name, &target, run);
+                        }
+                    }
+                }
+                BehaviorCommand::Contact => {
+                    // No battle mode yet: the monster just stops.
+                    self.moving_entities
+                        .borrow_mut()
+                        .remove(&ActorId::Npc(name.clone()));
+                    self.npc_play_animation(name, Pal4ActorAnimation::Idle);
+                    log::debug!("monster '{}' reached the leader; no battle mode", name);
+                }
+            }
+        }
+
+        self.scene.borrow_mut().npc_behaviors = behaviors;
+    }
+
+    /// `giNpcPauseBeh` / `giNpcResumeBeh`. Pausing also stops any
+    /// behaviour-driven move in flight.
+    pub fn set_npc_behavior_paused(&mut self, name: &str, paused: bool) {
+        let found = match self.scene.borrow_mut().npc_behaviors.get_mut(name) {
+            Some(behavior) => {
+                behavior.set_paused(paused);
+                true
+            }
+            None => false,
+        };
+        if found && paused && self.npc_moving(name) {
+            self.moving_entities
+                .borrow_mut()
+                .remove(&ActorId::Npc(name.to_string()));
+            self.npc_play_animation(name, Pal4ActorAnimation::Idle);
+        }
+    }
+
+    /// `giMonsterStopPursuit`: every chasing monster gives up and walks
+    /// back to its spawn point.
+    pub fn stop_monster_pursuit(&mut self) {
+        for behavior in self.scene.borrow_mut().npc_behaviors.values_mut() {
+            if behavior.is_monster() {
+                behavior.stop_pursuit();
+            }
+        }
+    }
+
     pub fn set_actdrop(&mut self, darkness: InterpValue<f32>) {
         self.actdrop.set_darkness(darkness);
     }
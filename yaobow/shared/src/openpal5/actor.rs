@@ -0,0 +1,240 @@
+//! A scripted PAL5 character: the loaded DFF entity, its armature (when
+//! the model is skinned), movement state and animation playback.
+//!
+//! Animation follows the script model: `npc.SetAnim` plays one clip,
+//! `npc.AddAnimChain` queues more to follow it, and when nothing
+//! scripted is playing the actor falls back to a locomotion clip chosen
+//! from its gait (stand / walk / run). Locomotion clips are found by
+//! name among the `.anm` files shipped next to the model.
+
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crosscom::ComRc;
+use radiance::comdef::{IArmatureComponent, IEntity, IEntityExt};
+use radiance::components::mesh::IArmatureComponentExt;
+use radiance::components::mesh::skinned_mesh::{AnimKeyFrame, AnimationState};
+use radiance::math::Vec3;
+
+use super::asset_loader::AssetLoader;
+use super::npc_motion::{Gait, NpcMotion};
+
+/// Locomotion clips, picked by file stem among the model's `.anm` files.
+#[derive(Default)]
+struct LocomotionClips {
+    stand: Option<String>,
+    walk: Option<String>,
+    run: Option<String>,
+}
+
+impl LocomotionClips {
+    fn from_paths(paths: &[String]) -> Self {
+        let find = |keys: &[&str]| {
+            paths
+                .iter()
+                .find(|p| {
+                    let stem = p.rsplit('/').next().unwrap_or(p).to_ascii_lowercase();
+                    keys.iter().any(|k| stem.contains(k))
+                })
+                .cloned()
+        };
+        Self {
+            stand: find(&["stand", "idle", "wait"]),
+            walk: find(&["walk"]),
+            run: find(&["run"]),
+        }
+    }
+
+    fn for_gait(&self, gait: Gait) -> Option<&String> {
+        match gait {
+            Gait::Stand => self.stand.as_ref(),
+            Gait::Walk => self.walk.as_ref().or(self.stand.as_ref()),
+            Gait::Run => self.run.as_ref().or(self.walk.as_ref()),
+            Gait::Float => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ScriptedClip {
+    anim_id: u32,
+    looping: bool,
+}
+
+pub struct Pal5Actor {
+    entity: ComRc<IEntity>,
+    armature: Option<ComRc<IArmatureComponent>>,
+    asset_loader: Rc<AssetLoader>,
+    motion: NpcMotion,
+
+    locomotion: LocomotionClips,
+    /// Gait whose locomotion clip is on the armature, if any.
+    locomotion_playing: Option<Gait>,
+    scripted: Option<ScriptedClip>,
+    chain: VecDeque<ScriptedClip>,
+}
+
+impl Pal5Actor {
+    pub fn new(
+        entity: ComRc<IEntity>,
+        asset_loader: Rc<AssetLoader>,
+        model_path: &str,
+        position: Vec3,
+    ) -> Self {
+        let armature = entity
+            .get_component(IArmatureComponent::uuid())
+            .and_then(|c| c.query_interface::<IArmatureComponent>());
+        let locomotion = if armature.is_some() {
+            LocomotionClips::from_paths(&asset_loader.model_clips(model_path))
+        } else {
+            LocomotionClips::default()
+        };
+
+        let mut actor = Self {
+            entity,
+            armature,
+            asset_loader,
+            motion: NpcMotion::new(position),
+            locomotion,
+            locomotion_playing: None,
+            scripted: None,
+            chain: VecDeque::new(),
+        };
+        actor.apply_transform();
+        actor.update_locomotion();
+        actor
+    }
+
+    pub fn entity(&self) -> &ComRc<IEntity> {
+        &self.entity
+    }
+
+    pub fn motion(&self) -> &NpcMotion {
+        &self.motion
+    }
+
+    pub fn motion_mut(&mut self) -> &mut NpcMotion {
+        &mut self.motion
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        self.entity.set_visible(visible);
+    }
+
+    /// `npc.SetAnim`: play a clip from the role index now, dropping any
+    /// queued chain. Unknown ids and unskinned models are ignored.
+    pub fn set_anim(&mut self, anim_id: u32, looping: bool) {
+        self.chain.clear();
+        self.play_scripted(ScriptedClip { anim_id, looping });
+    }
+
+    /// `npc.AddAnimChain`: queue a clip after the current one. Starts
+    /// immediately when nothing scripted is playing. A looping clip only
+    /// loops while it is the last link: once something is queued behind
+    /// it, it finishes its current cycle and hands over.
+    pub fn add_anim_chain(&mut self, anim_id: u32, looping: bool) {
+        let clip = ScriptedClip { anim_id, looping };
+        if self.scripted.is_some() {
+            self.chain.push_back(clip);
+            if let Some(armature) = &self.armature {
+                armature.set_looping(false);
+            }
+        } else {
+            self.play_scripted(clip);
+        }
+    }
+
+    /// The scripted animation (and its chain) has played out. Looping
+    /// clips never end on their own, so like PAL4's
+    /// `animation_completed` they count as done.
+    pub fn anim_done(&self) -> bool {
+        match self.scripted {
+            None => true,
+            Some(clip) => clip.looping && self.chain.is_empty(),
+        }
+    }
+
+    pub fn update(&mut self, delta_sec: f32) {
+        self.motion.update(delta_sec);
+        self.apply_transform();
+
+        if self.scripted.is_some() && self.armature_finished() {
+            self.scripted = None;
+            while let Some(next) = self.chain.pop_front() {
+                self.play_scripted(next);
+                if self.scripted.is_some() {
+                    break;
+                }
+            }
+        }
+        if self.scripted.is_none() {
+            self.update_locomotion();
+        }
+    }
+
+    fn play_scripted(&mut self, clip: ScriptedClip) {
+        let Some(armature) = &self.armature else {
+            return;
+        };
+        let Some(keyframes) = self.asset_loader.load_anim_by_id(clip.anim_id) else {
+            log::debug!("PAL5: anim {} not found; ignored", clip.anim_id);
+            return;
+        };
+        play_clip(armature, keyframes, clip.looping && self.chain.is_empty());
+        self.scripted = Some(clip);
+        self.locomotion_playing = None;
+    }
+
+    fn update_locomotion(&mut self) {
+        let gait = self.motion.gait();
+        if gait == Gait::Float || self.locomotion_playing == Some(gait) {
+            return;
+        }
+        let Some(armature) = &self.armature else {
+            return;
+        };
+        let Some(path) = self.locomotion.for_gait(gait) else {
+            return;
+        };
+        match self.asset_loader.load_anim(path) {
+            Ok(keyframes) => {
+                play_clip(armature, keyframes, true);
+                self.locomotion_playing = Some(gait);
+            }
+            Err(err) => {
+                log::warn!("PAL5: locomotion clip '{}' failed: {}", path, err);
+                // Don't retry every frame; the next gait change will.
+                self.locomotion_playing = Some(gait);
+            }
+        }
+    }
+
+    fn armature_finished(&self) -> bool {
+        self.armature.as_ref().is_none_or(|a| {
+            matches!(
+                a.animation_state(),
+                AnimationState::Stopped | AnimationState::NoAnimation
+            )
+        })
+    }
+
+    fn apply_transform(&self) {
+        let position = self.motion.position();
+        self.entity
+            .transform()
+            .borrow_mut()
+            .set_position(&position)
+            .clear_rotation()
+            .rotate_axis_angle_local(&Vec3::UP, self.motion.heading().to_radians());
+    }
+}
+
+fn play_clip(
+    armature: &ComRc<IArmatureComponent>,
+    keyframes: Vec<Vec<AnimKeyFrame>>,
+    looping: bool,
+) {
+    armature.set_animation(keyframes, vec![]);
+    armature.set_looping(looping);
+    armature.play();
+}
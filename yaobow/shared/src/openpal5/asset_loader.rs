@@ -1,24 +1,81 @@
-use std::{collections::HashMap, io::Cursor, rc::Rc};
+use std::{cell::OnceCell, collections::HashMap, io::Cursor, rc::Rc};
 
 use common::store_ext::StoreExt2;
 use crosscom::ComRc;
 use fileformats::{
     binrw::BinRead,
     nod::NodFile,
+    pal5::{
+        campath::{CameraPath, CameraPathFile},
+        mapinfo::MapInfoFile,
+    },
     role_bin::{AssetItem, RoleBinFile},
 };
-use mini_fs::MiniFs;
+use mini_fs::{EntryKind, MiniFs, StoreExt};
 use radiance::{
     comdef::{IComponent, IEntity, ISkyboxComponent},
-    components::skybox::SkyboxComponent,
-    rendering::ComponentFactory,
+    components::{mesh::skinned_mesh::AnimKeyFrame, skybox::SkyboxComponent},
+    rendering::{ComponentFactory, WaterParams},
 };
 
-use crate::loaders::{
-    FoliageCard, FoliageResolver, Pal5TextureResolver,
-    dff::{DffLoaderConfig, create_entity_from_dff_model},
+use crate::{
+    GameType,
+    loaders::{
+        FoliageCard, FoliageResolver, Pal5TextureResolver,
+        anm::load_anm,
+        dff::{DffLoaderConfig, create_entity_from_dff_model},
+    },
+    openpal5::script::ScriptIndex,
 };
 
+/// `scriptlist.ini` name of the "start a new game" entry script. Both
+/// games ship `NewGame.lua` defining `NewGame()`; the prequel only
+/// renumbers it, so it is looked up by name.
+const ENTRY_SCRIPT_NAME: &str = "NewGame";
+
+/// Per-game data layout of the PAL5-engine games, for what differs
+/// between PAL5 and the prequel.
+///
+/// Not covered yet: the prequel's CG movie and UI ids. `openpal5` plays
+/// no movies (`global.PlayCg` is skipped) and reads no UI tables for
+/// either game, so there is nothing to branch on until those land.
+struct GameLayout {
+    /// Number of `/Config/role_NN.bin` asset index files, or `None` to
+    /// read them in order until the first missing number.
+    role_bins: Option<u32>,
+    /// Scene shown when the story begins one before any map change.
+    bootstrap_map: Option<&'static str>,
+}
+
+/// PAL5: six role index files; the 狂风寨 intro begins a scene before
+/// any `map.ChangeNoScript`.
+const PAL5_LAYOUT: GameLayout = GameLayout {
+    role_bins: Some(6),
+    bootstrap_map: Some("kuangfengzhai"),
+};
+
+/// PAL5Q: role index files are read by number up to the first gap (the
+/// count isn't pinned down), and no scene is known before the entry
+/// script's first map change.
+const PAL5Q_LAYOUT: GameLayout = GameLayout {
+    role_bins: None,
+    bootstrap_map: None,
+};
+
+impl GameLayout {
+    fn for_game(game: GameType) -> Option<&'static Self> {
+        match game {
+            GameType::PAL5 => Some(&PAL5_LAYOUT),
+            GameType::PAL5Q => Some(&PAL5Q_LAYOUT),
+            _ => None,
+        }
+    }
+}
+
+/// Where the provisional camera path table is read from; see
+/// `fileformats::pal5::campath`.
+const CAMERA_PATH_TABLE: &str = "/Config/Data/CameraPath.ini";
+
 /// PAL5 leaf/sprite-card resolver backed by `Config/uvlist.tb`. Maps a model's
 /// `[W]/[w]{t<id>}` foliage-quad tag to its atlas texture + UV rect so the DFF
 /// loader can render the otherwise texture-less leaf cards. The `{t<id>}` tag
@@ -75,24 +132,58 @@ pub struct MapBlock {
 }
 
 pub struct AssetLoader {
+    game: GameType,
+    layout: &'static GameLayout,
     vfs: Rc<MiniFs>,
     component_factory: Rc<dyn ComponentFactory>,
     pub index: HashMap<u32, AssetItem>,
     texture_resolver: Pal5TextureResolver,
     foliage_resolver: Option<Pal5FoliageResolver>,
+    /// [`CAMERA_PATH_TABLE`], read on the first `ChangeCameraPath`;
+    /// `None` when the install has no such file.
+    camera_paths: OnceCell<Option<CameraPathFile>>,
 }
 
 impl AssetLoader {
-    pub fn new(component_factory: Rc<dyn ComponentFactory>, vfs: Rc<MiniFs>) -> Rc<Self> {
-        let index = load_index(&vfs);
+    pub fn new(
+        component_factory: Rc<dyn ComponentFactory>,
+        vfs: Rc<MiniFs>,
+        game: GameType,
+    ) -> anyhow::Result<Rc<Self>> {
+        let Some(layout) = GameLayout::for_game(game) else {
+            anyhow::bail!("{} is not a PAL5-engine game", game.full_name());
+        };
+        let index = load_index(&vfs, layout);
         let foliage_resolver = load_foliage_resolver(&vfs);
-        Rc::new(Self {
+        Ok(Rc::new(Self {
+            game,
+            layout,
             component_factory,
             vfs,
             index,
             texture_resolver: Pal5TextureResolver {},
             foliage_resolver,
-        })
+            camera_paths: OnceCell::new(),
+        }))
+    }
+
+    pub fn game(&self) -> GameType {
+        self.game
+    }
+
+    /// Script id the story starts from (`NewGame`, id 1 in PAL5 and
+    /// renumbered in the prequel). `None` when `scriptlist.ini` doesn't
+    /// list it.
+    pub fn entry_script_id(&self, scripts: &ScriptIndex) -> Option<u32> {
+        scripts.id_by_name(ENTRY_SCRIPT_NAME)
+    }
+
+    /// Scene loaded when the story begins a scene before any
+    /// `map.ChangeNoScript`, or changes to a map id `MapInfo.ini`
+    /// doesn't list. `None` for the prequel, whose scene before the
+    /// first map change isn't known.
+    pub fn bootstrap_map(&self) -> Option<String> {
+        self.layout.bootstrap_map.map(str::to_string)
     }
 
     pub fn component_factory(&self) -> Rc<dyn ComponentFactory> {
@@ -316,21 +407,77 @@ impl AssetLoader {
     /// **not** in `envinfo.env`. Most maps ship no sun and use the near-
     /// overhead engine default; `None` means "use the overhead default".
     pub fn load_map_sun(&self, map_name: &str) -> Option<[f32; 3]> {
-        use fileformats::pal5::mapinfo::MapInfoFile;
+        self.map_info()?.sun(map_name)?.direction
+    }
+
+    /// Map (scene) name for a script map id, from the same
+    /// `MapInfo.ini`. `None` when the id isn't listed.
+    pub fn map_name(&self, map_id: i32) -> Option<String> {
+        self.map_info()?.map_name(map_id).map(str::to_string)
+    }
+
+    /// Scene to show for script map id `map_id` (`0` before any map
+    /// change): its `MapInfo.ini` name, else the bootstrap map. `None`
+    /// when neither is known, as for the prequel before its first map
+    /// change; no scene should be loaded then.
+    pub fn scene_for_map(&self, map_id: i32) -> Option<String> {
+        (map_id != 0)
+            .then(|| self.map_name(map_id))
+            .flatten()
+            .or_else(|| self.bootstrap_map())
+    }
+
+    fn map_info(&self) -> Option<MapInfoFile> {
         let raw = self.vfs.read_to_end("/Config/Data/MapInfo.ini").ok()?;
-        let text = String::from_utf8_lossy(&raw);
-        MapInfoFile::parse(&text).sun(map_name)?.direction
+        Some(MapInfoFile::parse(&String::from_utf8_lossy(&raw)))
+    }
+
+    /// Camera path `id` from the camera path table, as run by
+    /// `camera.ChangeCameraPath`. The table is parsed once and kept.
+    /// `None` when the table or the path is missing.
+    pub fn load_camera_path(&self, id: i32) -> Option<CameraPath> {
+        self.camera_paths
+            .get_or_init(|| {
+                let raw = self.vfs.read_to_end(CAMERA_PATH_TABLE).ok();
+                if raw.is_none() {
+                    log::warn!(
+                        "PAL5: no camera path table at {}; camera paths keep the current shot",
+                        CAMERA_PATH_TABLE
+                    );
+                }
+                raw.map(|raw| CameraPathFile::parse(&String::from_utf8_lossy(&raw)))
+            })
+            .as_ref()?
+            .path(id)
+            .cloned()
     }
 
     pub fn load_model(&self, model_path: &str) -> anyhow::Result<ComRc<IEntity>> {
-        self.load_model_ex(model_path, false)
+        self.load_model_ex(model_path, false, None)
+    }
+
+    /// Load a river / lake surface model with its textured materials
+    /// rebuilt as reflective water (see [`WaterMaterialDef`]).
+    ///
+    /// [`WaterMaterialDef`]: radiance::rendering::WaterMaterialDef
+    pub fn load_water_model(
+        &self,
+        model_path: &str,
+        water: WaterParams,
+    ) -> anyhow::Result<ComRc<IEntity>> {
+        self.load_model_ex(model_path, false, Some(water))
     }
 
     /// Like [`load_model`](Self::load_model) but lets the caller opt the
-    /// model's materials out of scene fog (`fog_exempt`). Used by
-    /// [`load_skybox`](Self::load_skybox): the camera-locked sky dome must
-    /// never fade to the fog color.
-    fn load_model_ex(&self, model_path: &str, fog_exempt: bool) -> anyhow::Result<ComRc<IEntity>> {
+    /// model's materials out of scene fog (`fog_exempt`) or turn them into
+    /// water (`water`). Used by [`load_skybox`](Self::load_skybox): the
+    /// camera-locked sky dome must never fade to the fog color.
+    fn load_model_ex(
+        &self,
+        model_path: &str,
+        fog_exempt: bool,
+        water: Option<WaterParams>,
+    ) -> anyhow::Result<ComRc<IEntity>> {
         // PAL5's `role_*.bin` stores Windows backslash separators in
         // `file_path`; normalise to forward slashes so downstream log
         // lines (and `Pal5TextureResolver`'s path math) see a uniform
@@ -357,10 +504,70 @@ impl AssetLoader {
                     .foliage_resolver
                     .as_ref()
                     .map(|r| r as &dyn FoliageResolver),
+                water,
             },
         )
     }
 
+    /// Decode an `.anm` clip given its `role_*.bin`-style path (relative
+    /// to `/Model`, either separator).
+    pub fn load_anim(&self, anim_path: &str) -> anyhow::Result<Vec<Vec<AnimKeyFrame>>> {
+        let anim_path = format!("/Model/{}", anim_path.replace('\\', "/"));
+        load_anm(&self.vfs, anim_path)
+    }
+
+    /// Decode the `.anm` clip registered in the role index under
+    /// `asset_id`. `None` when the id is unknown or not an animation.
+    pub fn load_anim_by_id(&self, asset_id: u32) -> Option<Vec<Vec<AnimKeyFrame>>> {
+        let file_path = self.index.get(&asset_id)?.file_path.to_string();
+        if !file_path.to_ascii_lowercase().ends_with(".anm") {
+            return None;
+        }
+        match self.load_anim(&file_path) {
+            Ok(anim) => Some(anim),
+            Err(err) => {
+                log::warn!(
+                    "Pal5 anim '{}' (asset {}) failed to load: {}",
+                    file_path,
+                    asset_id,
+                    err,
+                );
+                None
+            }
+        }
+    }
+
+    /// List the `.anm` clips shipped next to a model, as paths in the
+    /// same `/Model`-relative form [`load_anim`](Self::load_anim) takes.
+    /// Character folders keep the skin and its clips side by side.
+    pub fn model_clips(&self, model_path: &str) -> Vec<String> {
+        let model_path = model_path.replace('\\', "/");
+        let dir = match model_path.rfind('/') {
+            Some(pos) => &model_path[..pos],
+            None => "",
+        };
+        let Ok(entries) = self.vfs.entries(&format!("/Model/{}", dir)) else {
+            return Vec::new();
+        };
+        let mut clips: Vec<String> = entries
+            .flatten()
+            .filter(|e| matches!(e.kind, EntryKind::File))
+            .filter_map(|e| {
+                let name = std::path::Path::new(&e.name).file_name()?.to_str()?;
+                if !name.to_ascii_lowercase().ends_with(".anm") {
+                    return None;
+                }
+                Some(if dir.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}/{}", dir, name)
+                })
+            })
+            .collect();
+        clips.sort();
+        clips
+    }
+
     /// Load the scene's skybox model by its `role_*.bin` asset id (the
     /// `SkyBoxID` carried in `envinfo.env`) and tag it with a
     /// [`SkyboxComponent`] so it stays centred on the camera every frame.
@@ -380,7 +587,7 @@ impl AssetLoader {
             return None;
         }
 
-        let entity = match self.load_model_ex(&file_path, true) {
+        let entity = match self.load_model_ex(&file_path, true, None) {
             Ok(entity) => entity,
             Err(err) => {
                 log::warn!(
@@ -431,15 +638,14 @@ fn load_foliage_resolver(vfs: &MiniFs) -> Option<Pal5FoliageResolver> {
     }
 }
 
-fn load_index(vfs: &MiniFs) -> HashMap<u32, AssetItem> {
-    let index_files = [
-        "/Config/role_00.bin",
-        "/Config/role_01.bin",
-        "/Config/role_02.bin",
-        "/Config/role_03.bin",
-        "/Config/role_04.bin",
-        "/Config/role_05.bin",
-    ];
+fn load_index(vfs: &MiniFs, layout: &GameLayout) -> HashMap<u32, AssetItem> {
+    let index_files: Vec<String> = match layout.role_bins {
+        Some(count) => (0..count).map(role_bin_path).collect(),
+        None => (0..)
+            .map(role_bin_path)
+            .take_while(|path| vfs.open(path).is_ok())
+            .collect(),
+    };
 
     let mut index = HashMap::new();
     for path in index_files.iter() {
@@ -454,6 +660,10 @@ fn load_index(vfs: &MiniFs) -> HashMap<u32, AssetItem> {
     index
 }
 
+fn role_bin_path(number: u32) -> String {
+    format!("/Config/role_{:0>2}.bin", number)
+}
+
 fn load_index_single(
     vfs: &MiniFs,
     path: &str,
@@ -0,0 +1,220 @@
+//! Camera motion for PAL5 cutscenes: spline paths and shake.
+//!
+//! [`CameraSpline`] runs a `camera.ChangeCameraPath` path: a uniform
+//! Catmull-Rom curve through the path's eye points, and another through
+//! its look-at points, so the camera passes every key. [`CameraShake`]
+//! is the `camera.Shake` oscillator, an offset that decays to nothing
+//! over the shake's duration. Both are pure math; the story context
+//! samples them every frame and places the scene camera.
+
+use radiance::math::Vec3;
+
+use fileformats::pal5::campath::CameraPath;
+
+/// Camera path playback: the two curves and the run time.
+#[derive(Debug, Clone)]
+pub struct CameraSpline {
+    eyes: Vec<Vec3>,
+    looks: Vec<Vec3>,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl CameraSpline {
+    /// `None` when the path has no keys. A `duration` of zero or less
+    /// jumps straight to the last key.
+    pub fn new(path: &CameraPath, duration: f32) -> Option<Self> {
+        if path.keys.is_empty() {
+            return None;
+        }
+        let to_vec3 = |p: [f32; 3]| Vec3::new(p[0], p[1], p[2]);
+        Some(Self {
+            eyes: path.keys.iter().map(|k| to_vec3(k.eye)).collect(),
+            looks: path.keys.iter().map(|k| to_vec3(k.look_at)).collect(),
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+        })
+    }
+
+    pub fn update(&mut self, delta_sec: f32) {
+        self.elapsed += delta_sec;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn remaining(&self) -> f32 {
+        (self.duration - self.elapsed).max(0.0)
+    }
+
+    /// Eye and look-at at the current time.
+    pub fn pose(&self) -> (Vec3, Vec3) {
+        let t = if self.duration > 0.0 {
+            (self.elapsed / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        (sample(&self.eyes, t), sample(&self.looks, t))
+    }
+}
+
+/// Point at `t` in `[0, 1]` on the uniform Catmull-Rom curve through
+/// `points`. The outer controls mirror the second and next-to-last
+/// points, so the curve leaves and reaches the ends at full speed.
+fn sample(points: &[Vec3], t: f32) -> Vec3 {
+    let last = points.len() - 1;
+    if last == 0 {
+        return points[0];
+    }
+    let scaled = t * last as f32;
+    let i = (scaled.floor() as usize).min(last - 1);
+    let u = scaled - i as f32;
+
+    let mirror = |end: &Vec3, inner: &Vec3| Vec3::sub(&Vec3::scalar_mul(2.0, end), inner);
+    let p1 = points[i];
+    let p2 = points[i + 1];
+    let p0 = match i {
+        0 => mirror(&p1, &p2),
+        _ => points[i - 1],
+    };
+    let p3 = match points.get(i + 2) {
+        Some(p) => *p,
+        None => mirror(&p2, &p1),
+    };
+
+    let u2 = u * u;
+    let u3 = u2 * u;
+    let blend = |a: f32, b: f32, c: f32, d: f32| {
+        0.5 * (2.0 * b
+            + (c - a) * u
+            + (2.0 * a - 5.0 * b + 4.0 * c - d) * u2
+            + (3.0 * b - a - 3.0 * c + d) * u3)
+    };
+    Vec3::new(
+        blend(p0.x, p1.x, p2.x, p3.x),
+        blend(p0.y, p1.y, p2.y, p3.y),
+        blend(p0.z, p1.z, p2.z, p3.z),
+    )
+}
+
+/// Horizontal and vertical shake frequencies, in Hz. Different so the
+/// camera wobbles rather than sliding along a line.
+const SHAKE_FREQ_X: f32 = 17.0;
+const SHAKE_FREQ_Y: f32 = 23.0;
+
+/// `camera.Shake(amplitude, ms)`: a sine wobble of up to `amplitude`
+/// world units that fades out linearly over the duration.
+#[derive(Debug, Clone)]
+pub struct CameraShake {
+    amplitude: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl CameraShake {
+    pub fn new(amplitude: f32, duration: f32) -> Self {
+        Self {
+            amplitude: amplitude.abs(),
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, delta_sec: f32) {
+        self.elapsed += delta_sec;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Offset to add to both the eye and the look-at target.
+    pub fn offset(&self) -> Vec3 {
+        if self.is_done() {
+            return Vec3::new_zeros();
+        }
+        let strength = self.amplitude * (1.0 - self.elapsed / self.duration);
+        let phase = std::f32::consts::TAU * self.elapsed;
+        Vec3::new(
+            strength * (phase * SHAKE_FREQ_X).sin(),
+            strength * (phase * SHAKE_FREQ_Y).sin(),
+            0.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fileformats::pal5::campath::CameraPathKey;
+
+    fn path(eyes: &[[f32; 3]]) -> CameraPath {
+        CameraPath {
+            duration: 0.0,
+            keys: eyes
+                .iter()
+                .map(|eye| CameraPathKey {
+                    eye: *eye,
+                    look_at: [0.0, 0.0, 0.0],
+                })
+                .collect(),
+        }
+    }
+
+    fn assert_near(actual: Vec3, expected: [f32; 3]) {
+        let d = Vec3::sub(&actual, &Vec3::new(expected[0], expected[1], expected[2]));
+        assert!(d.norm() < 0.01, "expected {expected:?}, got {actual:?}");
+    }
+
+    #[test]
+    fn spline_passes_through_every_key() {
+        let mut spline = CameraSpline::new(
+            &path(&[[0.0; 3], [100.0, 0.0, 0.0], [100.0, 0.0, 100.0]]),
+            2.0,
+        )
+        .unwrap();
+        assert_near(spline.pose().0, [0.0, 0.0, 0.0]);
+
+        spline.update(1.0);
+        assert_near(spline.pose().0, [100.0, 0.0, 0.0]);
+        assert!(!spline.is_done());
+        assert_eq!(spline.remaining(), 1.0);
+
+        spline.update(1.5);
+        assert!(spline.is_done());
+        assert_near(spline.pose().0, [100.0, 0.0, 100.0]);
+    }
+
+    #[test]
+    fn straight_path_is_followed_evenly() {
+        let mut spline = CameraSpline::new(
+            &path(&[[0.0; 3], [100.0, 0.0, 0.0], [200.0, 0.0, 0.0]]),
+            4.0,
+        )
+        .unwrap();
+        spline.update(1.0);
+        assert_near(spline.pose().0, [50.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn empty_path_and_zero_duration() {
+        assert!(CameraSpline::new(&path(&[]), 1.0).is_none());
+        let spline = CameraSpline::new(&path(&[[0.0; 3], [5.0, 0.0, 0.0]]), 0.0).unwrap();
+        assert!(spline.is_done());
+        assert_near(spline.pose().0, [5.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn shake_stays_within_amplitude_and_settles() {
+        let mut shake = CameraShake::new(8.0, 0.5);
+        for _ in 0..9 {
+            shake.update(0.05);
+            let o = shake.offset();
+            assert!(o.x.abs() <= 8.0 && o.y.abs() <= 8.0 && o.z == 0.0);
+        }
+        shake.update(0.1);
+        assert!(shake.is_done());
+        assert_near(shake.offset(), [0.0, 0.0, 0.0]);
+    }
+}
@@ -1,9 +1,14 @@
+pub mod actor;
 pub mod asset_loader;
+pub mod camera;
 #[macro_use]
 pub mod comdef {
     include!(concat!(env!("OUT_DIR"), "/shared_openpal5_comdef.rs"));
 }
 pub mod grass;
+pub mod npc_motion;
+pub mod party;
+pub mod persistent_state;
 pub mod scene;
 pub mod script;
 pub mod terrain;
@@ -0,0 +1,548 @@
+//! Movement for PAL5 NPCs: scripted walks / runs / floats, turning,
+//! patrol routes and speed control.
+//!
+//! [`NpcMotion`] is pure kinematics. The story context drives one per
+//! NPC from `npc.MoveTo` / `RunTo` / `FloatTo` / `TurnTo*` /
+//! `AddPatrolPoint` / `SetPatrolType` / `SetSpeed`, ticks it every
+//! frame, and copies the resulting position and heading onto the
+//! entity. The `global.WaitForNpc*` commands poll
+//! [`NpcMotion::is_moving`] / [`NpcMotion::is_turning`].
+//!
+//! Players reuse it: the leader is steered by directional input through
+//! [`NpcMotion::steer`], and scripted moves work the same as for NPCs.
+//!
+//! Headings are degrees around +Y, with 0 facing +Z — the same
+//! convention as the `rotate_axis_angle_local(UP, ang)` that the PAL4
+//! runtime uses for `giNpcSetAng`.
+
+use radiance::math::Vec3;
+
+/// Default walking speed, world units per second. PAL5 renders at true
+/// scale (a terrain block is 5120 units across).
+pub const DEFAULT_WALK_SPEED: f32 = 150.0;
+/// Running is this many times faster than walking.
+pub const RUN_FACTOR: f32 = 2.5;
+/// Turning rate, degrees per second.
+pub const TURN_SPEED: f32 = 540.0;
+/// Moves shorter than this (XZ, world units) arrive immediately.
+const ARRIVE_DISTANCE: f32 = 1.0;
+
+/// `npc.SetPatrolType` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatrolType {
+    /// Patrol stopped; the NPC stays where it is.
+    None,
+    /// Walk the points in order and start over from the first.
+    Loop,
+    /// Walk the points to the end, then back to the start.
+    PingPong,
+    /// Walk the points once and stop at the last one.
+    Once,
+}
+
+impl PatrolType {
+    pub fn from_i32(value: i32) -> Self {
+        match value {
+            1 => PatrolType::Loop,
+            2 => PatrolType::PingPong,
+            3 => PatrolType::Once,
+            _ => PatrolType::None,
+        }
+    }
+}
+
+/// How the NPC is currently getting around, used to pick a locomotion
+/// clip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gait {
+    Stand,
+    Walk,
+    Run,
+    /// `FloatTo`: a straight 3D glide that keeps whatever clip is
+    /// playing.
+    Float,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Move {
+    dest: Vec3,
+    gait: Gait,
+    /// World units per second.
+    speed: f32,
+    /// Part of the patrol route rather than a scripted move.
+    patrol: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct NpcMotion {
+    position: Vec3,
+    heading: f32,
+    walk_speed: f32,
+    current: Option<Move>,
+    turn_target: Option<f32>,
+    /// Steered by input this frame (see [`NpcMotion::steer`]).
+    steering: bool,
+
+    patrol_points: Vec<Vec3>,
+    patrol_type: PatrolType,
+    patrol_index: usize,
+    patrol_forward: bool,
+}
+
+impl NpcMotion {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            heading: 0.0,
+            walk_speed: DEFAULT_WALK_SPEED,
+            current: None,
+            turn_target: None,
+            steering: false,
+            patrol_points: Vec::new(),
+            patrol_type: PatrolType::None,
+            patrol_index: 0,
+            patrol_forward: true,
+        }
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.position
+    }
+
+    /// Heading in degrees, `[0, 360)`.
+    pub fn heading(&self) -> f32 {
+        self.heading
+    }
+
+    pub fn gait(&self) -> Gait {
+        match self.current {
+            Some(m) => m.gait,
+            None if self.steering => Gait::Walk,
+            None => Gait::Stand,
+        }
+    }
+
+    /// A scripted move (`MoveTo` / `RunTo` / `FloatTo`) is in flight.
+    /// Patrol legs don't count: a looping patrol never finishes, so
+    /// `WaitForNpcPos` must not wait on it.
+    pub fn is_moving(&self) -> bool {
+        self.current.is_some_and(|m| !m.patrol)
+    }
+
+    pub fn is_turning(&self) -> bool {
+        self.turn_target.is_some()
+    }
+
+    /// Teleport. Cancels any scripted move; a running patrol picks up
+    /// again from the new spot.
+    pub fn set_position(&mut self, position: Vec3) {
+        self.position = position;
+        self.current = None;
+    }
+
+    pub fn set_heading(&mut self, degrees: f32) {
+        self.heading = normalize_degrees(degrees);
+        self.turn_target = None;
+    }
+
+    /// `npc.SetSpeed`: walking speed in world units per second. Zero or
+    /// negative restores the default.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.walk_speed = if speed > 0.0 {
+            speed
+        } else {
+            DEFAULT_WALK_SPEED
+        };
+        if let Some(m) = &mut self.current {
+            m.speed = match m.gait {
+                Gait::Run => self.walk_speed * RUN_FACTOR,
+                Gait::Walk => self.walk_speed,
+                Gait::Stand | Gait::Float => m.speed,
+            };
+        }
+    }
+
+    /// Walk (or with `run`, run) across the ground to `(x, z)`. Height
+    /// is left alone.
+    pub fn move_to(&mut self, x: f32, z: f32, run: bool) {
+        let dest = Vec3::new(x, self.position.y, z);
+        let (gait, speed) = if run {
+            (Gait::Run, self.walk_speed * RUN_FACTOR)
+        } else {
+            (Gait::Walk, self.walk_speed)
+        };
+        self.start_move(Move {
+            dest,
+            gait,
+            speed,
+            patrol: false,
+        });
+    }
+
+    /// Glide in a straight line to `dest`. With a positive `duration`
+    /// (seconds) the speed is chosen to arrive on time; otherwise the
+    /// walking speed is used.
+    pub fn float_to(&mut self, dest: Vec3, duration: f32) {
+        let distance = Vec3::sub(&dest, &self.position).norm();
+        let speed = if duration > 0.0 {
+            distance / duration
+        } else {
+            self.walk_speed
+        };
+        self.current = Some(Move {
+            dest,
+            gait: Gait::Float,
+            speed,
+            patrol: false,
+        });
+        if distance <= ARRIVE_DISTANCE {
+            self.arrive();
+        }
+    }
+
+    /// Turn in place to an absolute heading.
+    pub fn turn_to(&mut self, degrees: f32) {
+        self.turn_target = Some(normalize_degrees(degrees));
+    }
+
+    /// Turn in place to face `(x, z)`. No-op when already standing on it.
+    pub fn turn_to_point(&mut self, x: f32, z: f32) {
+        if let Some(heading) = heading_towards(&self.position, x, z) {
+            self.turn_to(heading);
+        }
+    }
+
+    pub fn add_patrol_point(&mut self, x: f32, z: f32) {
+        self.patrol_points.push(Vec3::new(x, self.position.y, z));
+    }
+
+    /// `npc.SetPatrolType`. Changing the type restarts the route from
+    /// its first point; [`PatrolType::None`] also drops the points so a
+    /// later route starts from scratch.
+    pub fn set_patrol_type(&mut self, patrol_type: PatrolType) {
+        self.patrol_type = patrol_type;
+        self.patrol_index = 0;
+        self.patrol_forward = true;
+        if patrol_type == PatrolType::None {
+            self.patrol_points.clear();
+        }
+        if self.current.is_some_and(|m| m.patrol) {
+            self.current = None;
+        }
+    }
+
+    pub fn patrol_type(&self) -> PatrolType {
+        self.patrol_type
+    }
+
+    /// Walk one frame along `direction` (XZ, unit length or zero) at
+    /// walking speed, facing it. Input steering cancels any scripted
+    /// move or turn; a zero direction just stands still.
+    pub fn steer(&mut self, direction: &Vec3, delta_sec: f32) {
+        let Some(heading) = heading_towards(&Vec3::new_zeros(), direction.x, direction.z) else {
+            self.steering = false;
+            return;
+        };
+        self.current = None;
+        self.turn_target = None;
+        self.heading = heading;
+        self.position = Vec3::add(
+            &self.position,
+            &Vec3::scalar_mul(
+                self.walk_speed * delta_sec,
+                &Vec3::new(direction.x, 0.0, direction.z),
+            ),
+        );
+        self.steering = true;
+    }
+
+    /// Halt on the spot: drop any scripted move, turn or steering. A
+    /// patrol picks up again on the next update.
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.turn_target = None;
+        self.steering = false;
+    }
+
+    /// Advance by `delta_sec`.
+    pub fn update(&mut self, delta_sec: f32) {
+        if self.current.is_none() {
+            self.next_patrol_leg(true);
+        }
+
+        if let Some(m) = self.current {
+            let to_dest = Vec3::sub(&m.dest, &self.position);
+            let distance = to_dest.norm();
+            let step = m.speed * delta_sec;
+            if distance <= step.max(ARRIVE_DISTANCE) {
+                self.arrive();
+            } else {
+                let dir = Vec3::scalar_mul(1.0 / distance, &to_dest);
+                self.position = Vec3::add(&self.position, &Vec3::scalar_mul(step, &dir));
+            }
+        }
+
+        if let Some(target) = self.turn_target {
+            let diff = shortest_turn(self.heading, target);
+            let step = TURN_SPEED * delta_sec;
+            if diff.abs() <= step {
+                self.heading = target;
+                self.turn_target = None;
+            } else {
+                self.heading = normalize_degrees(self.heading + step * diff.signum());
+            }
+        }
+    }
+
+    fn start_move(&mut self, m: Move) {
+        self.current = Some(m);
+        if let Some(heading) = heading_towards(&self.position, m.dest.x, m.dest.z) {
+            self.turn_to(heading);
+        }
+        if Vec3::sub(&m.dest, &self.position).norm() <= ARRIVE_DISTANCE {
+            self.arrive();
+        }
+    }
+
+    fn arrive(&mut self) {
+        let Some(m) = self.current.take() else {
+            return;
+        };
+        self.position = m.dest;
+        if m.patrol {
+            self.advance_patrol();
+        }
+    }
+
+    fn next_patrol_leg(&mut self, retry: bool) {
+        if self.patrol_type == PatrolType::None {
+            return;
+        }
+        let Some(dest) = self.patrol_points.get(self.patrol_index).copied() else {
+            return;
+        };
+        if Vec3::sub(&dest, &self.position).norm() <= ARRIVE_DISTANCE {
+            // Already there (e.g. the route starts at the spawn point):
+            // head for the following point instead.
+            self.advance_patrol();
+            if retry {
+                self.next_patrol_leg(false);
+            }
+            return;
+        }
+        self.start_move(Move {
+            dest,
+            gait: Gait::Walk,
+            speed: self.walk_speed,
+            patrol: true,
+        });
+    }
+
+    fn advance_patrol(&mut self) {
+        let count = self.patrol_points.len();
+        if count < 2 {
+            self.patrol_type = PatrolType::None;
+            return;
+        }
+        let last = count - 1;
+        match self.patrol_type {
+            PatrolType::None => {}
+            PatrolType::Loop => self.patrol_index = (self.patrol_index + 1) % count,
+            PatrolType::Once => {
+                if self.patrol_index >= last {
+                    self.patrol_type = PatrolType::None;
+                } else {
+                    self.patrol_index += 1;
+                }
+            }
+            PatrolType::PingPong => {
+                if self.patrol_forward && self.patrol_index >= last {
+                    self.patrol_forward = false;
+                } else if !self.patrol_forward && self.patrol_index == 0 {
+                    self.patrol_forward = true;
+                }
+                if self.patrol_forward {
+                    self.patrol_index += 1;
+                } else {
+                    self.patrol_index -= 1;
+                }
+            }
+        }
+    }
+}
+
+/// Heading (degrees) from `from` towards `(x, z)`, or `None` when the
+/// two coincide on the XZ plane.
+pub fn heading_towards(from: &Vec3, x: f32, z: f32) -> Option<f32> {
+    let dx = x - from.x;
+    let dz = z - from.z;
+    if dx.abs() < f32::EPSILON && dz.abs() < f32::EPSILON {
+        None
+    } else {
+        Some(normalize_degrees(dx.atan2(dz).to_degrees()))
+    }
+}
+
+fn normalize_degrees(degrees: f32) -> f32 {
+    degrees.rem_euclid(360.0)
+}
+
+/// Signed shortest rotation from `from` to `to`, in `(-180, 180]`.
+fn shortest_turn(from: f32, to: f32) -> f32 {
+    let diff = (to - from).rem_euclid(360.0);
+    if diff > 180.0 { diff - 360.0 } else { diff }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(motion: &mut NpcMotion, seconds: f32) {
+        let steps = (seconds / 0.05).round() as usize;
+        for _ in 0..steps {
+            motion.update(0.05);
+        }
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn walks_to_destination_at_speed_and_faces_it() {
+        let mut motion = NpcMotion::new(Vec3::new(0.0, 10.0, 0.0));
+        motion.move_to(300.0, 0.0, false);
+        assert!(motion.is_moving());
+        assert_eq!(motion.gait(), Gait::Walk);
+
+        run(&mut motion, 1.0);
+        assert_near(motion.position().x, DEFAULT_WALK_SPEED);
+        assert_near(motion.heading(), 90.0);
+        assert!(!motion.is_turning());
+
+        run(&mut motion, 1.5);
+        assert!(!motion.is_moving());
+        assert_eq!(motion.gait(), Gait::Stand);
+        assert_near(motion.position().x, 300.0);
+        assert_near(motion.position().y, 10.0);
+    }
+
+    #[test]
+    fn run_and_set_speed_scale_the_pace() {
+        let mut motion = NpcMotion::new(Vec3::new_zeros());
+        motion.set_speed(100.0);
+        motion.move_to(0.0, 1000.0, true);
+        assert_eq!(motion.gait(), Gait::Run);
+        run(&mut motion, 1.0);
+        assert_near(motion.position().z, 100.0 * RUN_FACTOR);
+    }
+
+    #[test]
+    fn float_to_arrives_after_the_requested_duration() {
+        let mut motion = NpcMotion::new(Vec3::new_zeros());
+        motion.float_to(Vec3::new(0.0, 200.0, 0.0), 2.0);
+        assert_eq!(motion.gait(), Gait::Float);
+        run(&mut motion, 1.0);
+        assert_near(motion.position().y, 100.0);
+        run(&mut motion, 1.0);
+        assert!(!motion.is_moving());
+    }
+
+    #[test]
+    fn turns_the_short_way_round() {
+        let mut motion = NpcMotion::new(Vec3::new_zeros());
+        motion.set_heading(350.0);
+        motion.turn_to(10.0);
+        motion.update(10.0 / TURN_SPEED);
+        assert_near(motion.heading(), 0.0);
+        assert!(motion.is_turning());
+        motion.update(1.0);
+        assert_near(motion.heading(), 10.0);
+        assert!(!motion.is_turning());
+    }
+
+    #[test]
+    fn turn_to_point_faces_it() {
+        let mut motion = NpcMotion::new(Vec3::new(10.0, 0.0, 10.0));
+        motion.turn_to_point(10.0, -50.0);
+        motion.update(1.0);
+        assert_near(motion.heading(), 180.0);
+    }
+
+    #[test]
+    fn looping_patrol_cycles_and_does_not_block_waits() {
+        let mut motion = NpcMotion::new(Vec3::new_zeros());
+        motion.add_patrol_point(0.0, 0.0);
+        motion.add_patrol_point(150.0, 0.0);
+        motion.set_patrol_type(PatrolType::Loop);
+
+        run(&mut motion, 0.5);
+        assert_eq!(motion.gait(), Gait::Walk);
+        assert!(!motion.is_moving());
+        run(&mut motion, 0.5);
+        assert_near(motion.position().x, 150.0);
+        run(&mut motion, 1.0);
+        assert_near(motion.position().x, 0.0);
+        assert_eq!(motion.patrol_type(), PatrolType::Loop);
+    }
+
+    #[test]
+    fn ping_pong_patrol_reverses_at_the_ends() {
+        let mut motion = NpcMotion::new(Vec3::new_zeros());
+        for x in [0.0, 150.0, 300.0] {
+            motion.add_patrol_point(x, 0.0);
+        }
+        motion.set_patrol_type(PatrolType::PingPong);
+        run(&mut motion, 2.0);
+        assert_near(motion.position().x, 300.0);
+        run(&mut motion, 1.0);
+        assert_near(motion.position().x, 150.0);
+    }
+
+    #[test]
+    fn once_patrol_stops_at_the_last_point() {
+        let mut motion = NpcMotion::new(Vec3::new_zeros());
+        motion.add_patrol_point(150.0, 0.0);
+        motion.add_patrol_point(300.0, 0.0);
+        motion.set_patrol_type(PatrolType::Once);
+        run(&mut motion, 5.0);
+        assert_near(motion.position().x, 300.0);
+        assert_eq!(motion.patrol_type(), PatrolType::None);
+        assert_eq!(motion.gait(), Gait::Stand);
+    }
+
+    #[test]
+    fn scripted_move_takes_over_from_patrol() {
+        let mut motion = NpcMotion::new(Vec3::new_zeros());
+        motion.add_patrol_point(0.0, 0.0);
+        motion.add_patrol_point(150.0, 0.0);
+        motion.set_patrol_type(PatrolType::Loop);
+        run(&mut motion, 0.5);
+
+        motion.move_to(0.0, 300.0, false);
+        assert!(motion.is_moving());
+        run(&mut motion, 3.0);
+        assert!(!motion.is_moving());
+        // The patrol resumes once the scripted move is done.
+        assert_eq!(motion.gait(), Gait::Walk);
+    }
+
+    #[test]
+    fn steering_walks_along_the_input_and_overrides_scripted_moves() {
+        let mut motion = NpcMotion::new(Vec3::new_zeros());
+        motion.move_to(0.0, 500.0, false);
+
+        motion.steer(&Vec3::new(1.0, 0.0, 0.0), 1.0);
+        assert!(!motion.is_moving());
+        assert_eq!(motion.gait(), Gait::Walk);
+        assert_near(motion.position().x, DEFAULT_WALK_SPEED);
+        assert_near(motion.heading(), 90.0);
+
+        motion.steer(&Vec3::new_zeros(), 1.0);
+        assert_eq!(motion.gait(), Gait::Stand);
+        assert_near(motion.position().x, DEFAULT_WALK_SPEED);
+    }
+}
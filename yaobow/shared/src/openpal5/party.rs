@@ -0,0 +1,203 @@
+//! The PAL5 / PAL5Q party: who has joined, each role's HP / MP and
+//! magic, and the shared bag of items, equipment and crafting formulas.
+//!
+//! [`Pal5Party`] is plain data. The story context updates it from the
+//! `player.*` grant commands (`AddItem`, `AddEquip`, `AddMagic`,
+//! `AddFormula`, `ChangeHP`, `ChangeMP`), the agent snapshot reads it,
+//! and [`Pal5PersistentState`] stores it as is.
+//!
+//! Role stat tables aren't read yet, so a role starts at
+//! [`BASE_MAX_HP`] / [`BASE_MAX_MP`] with full HP and MP.
+//!
+//! [`Pal5PersistentState`]: super::persistent_state::Pal5PersistentState
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+/// Max HP a role starts with.
+pub const BASE_MAX_HP: i32 = 100;
+/// Max MP a role starts with.
+pub const BASE_MAX_MP: i32 = 100;
+
+/// One role's stats and learned magic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pal5PartyMember {
+    /// `player.Create` brought the role into the team. A role whose
+    /// stats were touched before it joined is tracked but not in team.
+    #[serde(default)]
+    pub in_team: bool,
+    pub level: i32,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub mp: i32,
+    pub max_mp: i32,
+    /// Learned magic ids, sorted.
+    #[serde(default)]
+    pub magic: BTreeSet<i32>,
+}
+
+impl Default for Pal5PartyMember {
+    fn default() -> Self {
+        Self {
+            in_team: false,
+            level: 1,
+            hp: BASE_MAX_HP,
+            max_hp: BASE_MAX_HP,
+            mp: BASE_MAX_MP,
+            max_mp: BASE_MAX_MP,
+            magic: BTreeSet::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pal5Party {
+    /// Members by role id.
+    #[serde(default)]
+    members: BTreeMap<i32, Pal5PartyMember>,
+    /// Item counts by item id; never holds a zero count.
+    #[serde(default)]
+    items: BTreeMap<i32, i32>,
+    /// Equipment counts by equipment id; never holds a zero count.
+    #[serde(default)]
+    equipment: BTreeMap<i32, i32>,
+    /// Learned crafting formula ids.
+    #[serde(default)]
+    formulas: BTreeSet<i32>,
+}
+
+impl Pal5Party {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn members(&self) -> &BTreeMap<i32, Pal5PartyMember> {
+        &self.members
+    }
+
+    pub fn member(&self, role_id: i32) -> Option<&Pal5PartyMember> {
+        self.members.get(&role_id)
+    }
+
+    fn member_mut(&mut self, role_id: i32) -> &mut Pal5PartyMember {
+        self.members.entry(role_id).or_default()
+    }
+
+    /// `player.Create`: the role joins the team, keeping any stats it
+    /// already has.
+    pub fn join(&mut self, role_id: i32) {
+        self.member_mut(role_id).in_team = true;
+    }
+
+    pub fn is_in_team(&self, role_id: i32) -> bool {
+        self.member(role_id).is_some_and(|m| m.in_team)
+    }
+
+    pub fn items(&self) -> &BTreeMap<i32, i32> {
+        &self.items
+    }
+
+    pub fn item_count(&self, item_id: i32) -> i32 {
+        self.items.get(&item_id).copied().unwrap_or(0)
+    }
+
+    /// `player.AddItem(id, count)`: a negative count takes items away,
+    /// never below zero.
+    pub fn add_item(&mut self, item_id: i32, count: i32) {
+        add_count(&mut self.items, item_id, count);
+    }
+
+    pub fn equipment(&self) -> &BTreeMap<i32, i32> {
+        &self.equipment
+    }
+
+    pub fn equipment_count(&self, equip_id: i32) -> i32 {
+        self.equipment.get(&equip_id).copied().unwrap_or(0)
+    }
+
+    /// `player.AddEquip(id, count)`, with the same rules as
+    /// [`add_item`](Self::add_item).
+    pub fn add_equipment(&mut self, equip_id: i32, count: i32) {
+        add_count(&mut self.equipment, equip_id, count);
+    }
+
+    /// `player.AddMagic(role, magic)`.
+    pub fn add_magic(&mut self, role_id: i32, magic_id: i32) {
+        self.member_mut(role_id).magic.insert(magic_id);
+    }
+
+    pub fn formulas(&self) -> &BTreeSet<i32> {
+        &self.formulas
+    }
+
+    /// `player.AddFormula(formula)`.
+    pub fn add_formula(&mut self, formula_id: i32) {
+        self.formulas.insert(formula_id);
+    }
+
+    /// `player.ChangeHP(role, delta)`, clamped to `[0, max_hp]`.
+    pub fn change_hp(&mut self, role_id: i32, delta: i32) {
+        let member = self.member_mut(role_id);
+        member.hp = member.hp.saturating_add(delta).clamp(0, member.max_hp);
+    }
+
+    /// `player.ChangeMP(role, delta)`, clamped to `[0, max_mp]`.
+    pub fn change_mp(&mut self, role_id: i32, delta: i32) {
+        let member = self.member_mut(role_id);
+        member.mp = member.mp.saturating_add(delta).clamp(0, member.max_mp);
+    }
+}
+
+fn add_count(counts: &mut BTreeMap<i32, i32>, id: i32, delta: i32) {
+    let count = counts.get(&id).copied().unwrap_or(0).saturating_add(delta);
+    if count > 0 {
+        counts.insert(id, count);
+    } else {
+        counts.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_and_equipment_never_go_negative() {
+        let mut party = Pal5Party::new();
+        party.add_item(30, 5);
+        party.add_item(30, -2);
+        party.add_equipment(7, 1);
+        party.add_equipment(7, -4);
+
+        assert_eq!(party.item_count(30), 3);
+        assert_eq!(party.equipment_count(7), 0);
+        assert!(party.equipment().is_empty());
+
+        party.add_item(30, -3);
+        assert!(party.items().is_empty());
+    }
+
+    #[test]
+    fn hp_and_mp_clamp_to_the_member_range() {
+        let mut party = Pal5Party::new();
+        party.change_hp(1, -30);
+        party.change_mp(1, -500);
+        assert_eq!(party.member(1).unwrap().hp, BASE_MAX_HP - 30);
+        assert_eq!(party.member(1).unwrap().mp, 0);
+
+        party.change_hp(1, 1000);
+        assert_eq!(party.member(1).unwrap().hp, BASE_MAX_HP);
+    }
+
+    #[test]
+    fn stats_before_joining_keep_the_role_out_of_the_team() {
+        let mut party = Pal5Party::new();
+        party.add_magic(2, 11);
+        assert!(!party.is_in_team(2));
+
+        party.join(2);
+        assert!(party.is_in_team(2));
+        assert_eq!(party.member(2).unwrap().magic, BTreeSet::from([11]));
+    }
+}
@@ -0,0 +1,282 @@
+//! PAL5 / PAL5Q save slots.
+//!
+//! PAL5's story runs inside a Lua coroutine that can't be serialized,
+//! so a save records where the story *was* rather than the VM itself:
+//! the `flag` table, the [`Pal5Party`], the players on the map, the
+//! current map and the leader's position, and the map's event volumes.
+//! Saves are only taken between scripts, while the story
+//! waits in free roam, so loading rebuilds that host state and goes
+//! back to waiting for an event; no script is run twice.
+//!
+//! Slots live at `<save_dir>/<app_name>/Save/<slot>.json`, the layout
+//! PAL3 and PAL4 use, with [`GameType::app_name`] (`OpenPAL5` /
+//! `OpenPAL5Q`) as the namespace.
+//!
+//! [`GameType::app_name`]: crate::GameType::app_name
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ydirs;
+
+use super::party::Pal5Party;
+
+/// Camera eye and look-at target, as set by `camera.ChangeCameraStatic`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pal5SavedCamera {
+    pub eye: [f32; 3],
+    pub look_at: [f32; 3],
+}
+
+/// A `map.AddEvent` volume: an XZ rectangle that calls `script_id`
+/// when the leader walks in.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pal5MapEvent {
+    pub script_id: u32,
+    /// `(min_x, max_x, min_z, max_z)`.
+    pub rect: (f32, f32, f32, f32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pal5PersistentState {
+    app_name: String,
+    /// `map.ChangeNoScript` map id; `0` while the story is still on the
+    /// bootstrap scene.
+    #[serde(default)]
+    map_id: i32,
+    #[serde(default)]
+    sub_map_id: i32,
+    /// Scene the map id resolved to, kept for the load screen.
+    #[serde(default)]
+    scene_name: String,
+    /// Leader (player 1) position, `None` before the player exists.
+    #[serde(default)]
+    position: Option<[f32; 3]>,
+    #[serde(default)]
+    camera: Option<Pal5SavedCamera>,
+    /// `flag.SetValue` values by flag id.
+    #[serde(default)]
+    flags: BTreeMap<i32, i32>,
+    /// Role ids of the players on the map, sorted.
+    #[serde(default)]
+    players: Vec<i32>,
+    /// Party stats, magic, items, equipment and formulas.
+    #[serde(default)]
+    party: Pal5Party,
+    /// `player.Stop` was in effect: input doesn't move the leader.
+    #[serde(default)]
+    player_locked: bool,
+    /// Event volumes registered on the current map.
+    #[serde(default)]
+    events: Vec<Pal5MapEvent>,
+}
+
+impl Pal5PersistentState {
+    /// Number of save slots the title menu's load screen lists.
+    pub const SLOT_COUNT: i32 = 4;
+
+    pub fn new(app_name: String) -> Self {
+        Self {
+            app_name,
+            map_id: 0,
+            sub_map_id: 0,
+            scene_name: String::new(),
+            position: None,
+            camera: None,
+            flags: BTreeMap::new(),
+            players: Vec::new(),
+            party: Pal5Party::new(),
+            player_locked: false,
+            events: Vec::new(),
+        }
+    }
+
+    fn slot_path(app_name: &str, slot: i32) -> PathBuf {
+        ydirs::save_dir()
+            .join(app_name)
+            .join("Save")
+            .join(format!("{}.json", slot))
+    }
+
+    /// Load the state for `app_name` from the given slot. Fails when the
+    /// slot file is missing or malformed.
+    pub fn load(app_name: &str, slot: i32) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(Self::slot_path(app_name, slot))?;
+        let state = serde_json::from_str(&content)?;
+        Ok(state)
+    }
+
+    /// Read a save slot for display purposes only. `None` when the slot
+    /// file is missing or malformed.
+    pub fn peek(app_name: &str, slot: i32) -> Option<Self> {
+        Self::load(app_name, slot).ok()
+    }
+
+    /// Write this state to the given slot. Failures are returned so the
+    /// agent and the title menu can report them.
+    pub fn save(&self, slot: i32) -> anyhow::Result<()> {
+        if slot < 0 {
+            anyhow::bail!("invalid save slot {}", slot);
+        }
+
+        let path = Self::slot_path(&self.app_name, slot);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        log::info!("Game saved to slot {}", slot);
+        Ok(())
+    }
+
+    pub fn app_name(&self) -> &str {
+        &self.app_name
+    }
+
+    /// One-line label for the load screen: the scene name, or the map id
+    /// when the scene wasn't recorded.
+    pub fn summary(&self) -> String {
+        match (self.scene_name.is_empty(), self.map_id) {
+            (false, _) => self.scene_name.clone(),
+            (true, 0) => "No map".to_string(),
+            (true, id) => format!("Map {}", id),
+        }
+    }
+
+    pub fn map_id(&self) -> i32 {
+        self.map_id
+    }
+
+    pub fn sub_map_id(&self) -> i32 {
+        self.sub_map_id
+    }
+
+    pub fn scene_name(&self) -> &str {
+        &self.scene_name
+    }
+
+    pub fn set_map(&mut self, map_id: i32, sub_map_id: i32, scene_name: String) {
+        self.map_id = map_id;
+        self.sub_map_id = sub_map_id;
+        self.scene_name = scene_name;
+    }
+
+    pub fn position(&self) -> Option<[f32; 3]> {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Option<[f32; 3]>) {
+        self.position = position;
+    }
+
+    pub fn camera(&self) -> Option<Pal5SavedCamera> {
+        self.camera
+    }
+
+    pub fn set_camera(&mut self, camera: Option<Pal5SavedCamera>) {
+        self.camera = camera;
+    }
+
+    pub fn flags(&self) -> &BTreeMap<i32, i32> {
+        &self.flags
+    }
+
+    pub fn set_flags(&mut self, flags: impl IntoIterator<Item = (i32, i32)>) {
+        self.flags = flags.into_iter().collect();
+    }
+
+    pub fn players(&self) -> &[i32] {
+        &self.players
+    }
+
+    pub fn set_players(&mut self, players: impl IntoIterator<Item = i32>) {
+        self.players = players.into_iter().collect();
+        self.players.sort_unstable();
+        self.players.dedup();
+    }
+
+    pub fn party(&self) -> &Pal5Party {
+        &self.party
+    }
+
+    pub fn set_party(&mut self, party: Pal5Party) {
+        self.party = party;
+    }
+
+    pub fn player_locked(&self) -> bool {
+        self.player_locked
+    }
+
+    pub fn set_player_locked(&mut self, locked: bool) {
+        self.player_locked = locked;
+    }
+
+    pub fn events(&self) -> &[Pal5MapEvent] {
+        &self.events
+    }
+
+    pub fn set_events(&mut self, events: Vec<Pal5MapEvent>) {
+        self.events = events;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_survives_json_round_trip() {
+        let mut state = Pal5PersistentState::new("OpenPAL5Q".to_string());
+        state.set_map(22, 1, "kuangfengzhai".to_string());
+        state.set_position(Some([100., 0., -50.]));
+        state.set_camera(Some(Pal5SavedCamera {
+            eye: [0., 300., -400.],
+            look_at: [0., 0., 0.],
+        }));
+        state.set_flags([(1001, 3), (7, -1)]);
+        state.set_players([2, 1, 2]);
+        let mut party = Pal5Party::new();
+        party.join(1);
+        party.add_item(30, 5);
+        party.change_hp(1, -20);
+        state.set_party(party);
+        state.set_player_locked(true);
+        state.set_events(vec![Pal5MapEvent {
+            script_id: 7002,
+            rect: (0., 200., -100., 100.),
+        }]);
+
+        let json = serde_json::to_string(&state).unwrap();
+        let loaded: Pal5PersistentState = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.app_name(), "OpenPAL5Q");
+        assert_eq!((loaded.map_id(), loaded.sub_map_id()), (22, 1));
+        assert_eq!(loaded.summary(), "kuangfengzhai");
+        assert_eq!(loaded.position(), Some([100., 0., -50.]));
+        assert_eq!(loaded.camera(), state.camera());
+        assert_eq!(loaded.flags(), &BTreeMap::from([(7, -1), (1001, 3)]));
+        assert_eq!(loaded.players(), &[1, 2]);
+        assert_eq!(loaded.party(), state.party());
+        assert_eq!(loaded.party().items(), &BTreeMap::from([(30, 5)]));
+        assert!(loaded.player_locked());
+        assert_eq!(loaded.events(), state.events());
+    }
+
+    #[test]
+    fn minimal_save_deserializes_with_defaults() {
+        let json = r#"{"app_name":"OpenPAL5","map_id":3}"#;
+        let state: Pal5PersistentState = serde_json::from_str(json).unwrap();
+        assert_eq!(state.summary(), "Map 3");
+        assert!(state.flags().is_empty());
+        assert!(state.players().is_empty());
+        assert!(state.party().members().is_empty());
+        assert!(!state.player_locked());
+        assert!(state.events().is_empty());
+        assert!(state.position().is_none());
+        assert_eq!(
+            Pal5PersistentState::new("OpenPAL5".to_string()).summary(),
+            "No map"
+        );
+    }
+}
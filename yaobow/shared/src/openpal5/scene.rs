@@ -1,18 +1,80 @@
 use crosscom::ComRc;
-use radiance::{comdef::IScene, math::Vec3, scene::CoreScene};
+use radiance::{
+    comdef::{IComponent, IScene, ITriggerVolumeComponent},
+    components::collision::{TriggerShape, TriggerVolumeComponent},
+    math::Vec3,
+    rendering::WaterParams,
+    scene::{CoreEntity, CoreScene},
+};
 
 use super::asset_loader::AssetLoader;
+use super::persistent_state::Pal5MapEvent;
 use radiance::comdef::{IEntityExt, ISceneExt};
 
+/// Lower-cased name fragments marking a `.nod` node or model path as a
+/// river / lake surface: English `water` plus the pinyin the map art uses
+/// (`shuimian` 水面 "water surface", `heshui` 河水 "river water", `hushui`
+/// 湖水 "lake water", `shuitan` 水潭 "pool"). Bare `shui` is avoided — it
+/// also names crystals (`shuijing`) and waterwheels (`shuiche`).
+///
+/// This is a naming heuristic, not file data: no `.nod` or `envinfo.env`
+/// field marking water has been identified. It is therefore **off by
+/// default**; enable with `PAL5_WATER_HEURISTIC=1`.
+const WATER_NAME_TOKENS: [&str; 5] = ["water", "shuimian", "heshui", "hushui", "shuitan"];
+
 pub struct Pal5Scene {
     pub scene: ComRc<IScene>,
+    /// `map.AddEvent` volumes (payload id = script id), each with
+    /// whether the leader stood inside at the last check.
+    events: Vec<(ComRc<ITriggerVolumeComponent>, bool)>,
 }
 
 impl Pal5Scene {
     pub fn new_empty() -> Self {
         Self {
             scene: CoreScene::create(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Register a `map.AddEvent` volume on a holder entity. An event
+    /// placed under the leader's feet (`leader`) waits until they step
+    /// out and back in.
+    pub fn add_event(&mut self, event: &Pal5MapEvent, leader: Option<&Vec3>) {
+        let holder = CoreEntity::create(format!("event_{}", event.script_id), false);
+        self.scene.add_entity(holder.clone());
+        let shape = TriggerShape::ProximityAabb {
+            aabb: event.rect,
+            radius: 0.0,
+        };
+        let Some(volume) = TriggerVolumeComponent::create(
+            holder.clone(),
+            shape,
+            event.script_id as i64,
+            String::new(),
+        ) else {
+            return;
+        };
+        holder.add_component(
+            ITriggerVolumeComponent::uuid(),
+            volume.query_interface::<IComponent>().unwrap(),
+        );
+        let inside = leader.is_some_and(|p| contains(&volume, p));
+        self.events.push((volume, inside));
+    }
+
+    /// Script ids of the events the leader at `position` walked into
+    /// since the last call, in registration order.
+    pub fn entered_events(&mut self, position: &Vec3) -> Vec<u32> {
+        let mut entered = Vec::new();
+        for (volume, inside) in &mut self.events {
+            let now = contains(volume, position);
+            if now && !*inside {
+                entered.push(volume.inner::<TriggerVolumeComponent>().id() as u32);
+            }
+            *inside = now;
         }
+        entered
     }
 
     pub fn load(asset_loader: &AssetLoader, scene_name: &str) -> anyhow::Result<Self> {
@@ -141,6 +203,11 @@ impl Pal5Scene {
         let mut loaded = 0usize;
         let mut skipped = 0usize;
         let mut failed = 0usize;
+        let mut water_surfaces = 0usize;
+        // Water surfaces are only guessed from names (see
+        // `WATER_NAME_TOKENS`), so the guess is opt-in.
+        let water_heuristic = std::env::var("PAL5_WATER_HEURISTIC")
+            .is_ok_and(|s| !matches!(s.trim(), "0" | "false" | "off" | "no" | ""));
 
         for node in &nod.nodes {
             // Resolve the node's asset entry from the role index. Many
@@ -171,7 +238,14 @@ impl Pal5Scene {
             // model must not abort the whole scene (foliage is
             // interleaved with buildings, so an early `?` would hide
             // everything after the first bad node).
-            let model = match asset_loader.load_model(&file_path) {
+            let node_name = node.name.as_str().unwrap_or_default();
+            let is_water = water_heuristic && is_water_surface(&node_name, &file_path);
+            let model = if is_water {
+                asset_loader.load_water_model(&file_path, WaterParams::default())
+            } else {
+                asset_loader.load_model(&file_path)
+            };
+            let model = match model {
                 Ok(model) => model,
                 Err(err) => {
                     failed += 1;
@@ -199,12 +273,16 @@ impl Pal5Scene {
                 ));
             scene.add_entity(model);
             loaded += 1;
+            if is_water {
+                water_surfaces += 1;
+            }
         }
 
         log::info!(
-            "Pal5Scene '{}': {} models loaded, {} skipped (non-model/unindexed), {} failed of {} nodes",
+            "Pal5Scene '{}': {} models loaded ({} water), {} skipped (non-model/unindexed), {} failed of {} nodes",
             scene_name,
             loaded,
+            water_surfaces,
             skipped,
             failed,
             nod.nodes.len(),
@@ -234,6 +312,25 @@ impl Pal5Scene {
             );
         }
 
-        Ok(Self { scene })
+        Ok(Self {
+            scene,
+            events: Vec::new(),
+        })
     }
 }
+
+/// Whether a `.nod` node is a river / lake surface, by its node name or
+/// model file name (see [`WATER_NAME_TOKENS`]). The `.nod` record has no
+/// known surface-type field, so naming is the only signal available.
+fn is_water_surface(node_name: &str, file_path: &str) -> bool {
+    let file_name = file_path.rsplit(['/', '\\']).next().unwrap_or(file_path);
+    [node_name, file_name].iter().any(|name| {
+        let name = name.to_ascii_lowercase();
+        WATER_NAME_TOKENS.iter().any(|token| name.contains(token))
+    })
+}
+
+fn contains(volume: &ComRc<ITriggerVolumeComponent>, point: &Vec3) -> bool {
+    let inner = volume.inner::<TriggerVolumeComponent>();
+    inner.distance_xz(point) <= inner.radius()
+}
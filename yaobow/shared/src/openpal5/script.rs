@@ -92,6 +92,23 @@ impl ScriptIndex {
         self.by_id.get(&id)
     }
 
+    /// Id of the script whose entry function is `name`. Several ids
+    /// naming the same script resolve to the lowest.
+    pub fn id_by_name(&self, name: &str) -> Option<u32> {
+        self.by_id
+            .iter()
+            .filter(|(_, e)| e.name == name)
+            .map(|(id, _)| *id)
+            .min()
+    }
+
+    /// Every listed script, ordered by id.
+    pub fn entries(&self) -> Vec<(u32, &ScriptEntry)> {
+        let mut entries: Vec<_> = self.by_id.iter().map(|(id, e)| (*id, e)).collect();
+        entries.sort_by_key(|(id, _)| *id);
+        entries
+    }
+
     /// Load the (already SDFA-decrypted) Lua source for `id`.
     pub fn load_source(&self, vfs: &MiniFs, id: u32) -> anyhow::Result<(String, Vec<u8>)> {
         let entry = self
@@ -117,5 +134,10 @@ mod tests {
             "/script/mainline/m001_1.lua"
         );
         assert_eq!(idx.entry(9601).unwrap().name, "macro");
+        assert_eq!(idx.id_by_name("m001_1"), Some(7001));
+        assert_eq!(idx.id_by_name("missing"), None);
+
+        let ids: Vec<u32> = idx.entries().iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1, 2, 7001, 9601]);
     }
 }
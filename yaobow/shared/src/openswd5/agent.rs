@@ -13,25 +13,26 @@
 //! handlers live in [`crate::agent_common::handlers`]. This module:
 //!
 //! * Builds [`StateSnapshot`]s from SWD5-family state ([`SWD5Context`]):
-//!   map id, VM busy/movie flags, live dialog text and camera pose.
+//!   map id, leader position, VM busy/movie flags, live dialog text and
+//!   camera pose.
 //! * Serves the gameplay endpoints this game layer can actually back —
-//!   dialog advance, camera pose, map change (routed from
-//!   `/v1/player/teleport`), Lua script globals and a narrow
-//!   `script_eval` allow-list.
+//!   dialog advance, camera pose, leader teleport, the role list
+//!   (`/v1/scene/objects`), Lua script globals and a narrow
+//!   `script_eval` allow-list (which includes `chang_map` for map
+//!   changes).
+//! * Saves and loads slots
+//!   ([`Swd5PersistentState`](crate::openswd5::persistent_state::Swd5PersistentState)).
+//!   Loading and `/v1/menu/new_game` restart the main script, so they
+//!   take effect on the next frame.
+//! * Records the Lua VM's engine calls for `/v1/script/trace/*`.
 //! * Returns `NotImplemented` for endpoints with no counterpart in the
-//!   game layer (save/load, menu control, dialog choice, scene
-//!   triggers/objects, VM trace, world map).
+//!   game layer (menu exit, dialog choice, scene triggers, role
+//!   interaction, world map).
 //!
-//! ## Why several endpoints stay unimplemented
-//!
-//! The SWD5-family game layer has **no role/actor entities**:
-//! `chang_role_map`, `set_motion`, `set_walks` and
-//! `set_role_face_motion` are empty stubs in
-//! [`crate::openswd5::scripting`], and
-//! [`Swd5Scene`](crate::openswd5::scene::Swd5Scene) holds only the map
-//! DFF plus a camera. There is therefore no player position to report
-//! or teleport to, and no NPC list to enumerate. Those endpoints are
-//! blocked on game-layer role loading, not on the agent surface.
+//! Roles ([`Swd5Role`](crate::openswd5::role::Swd5Role)) are the
+//! script-spawned leader (`chang_role_map`) plus the current map's
+//! placed roles, of which there are none until the `.fld` role table is
+//! decoded; see [`crate::openswd5::role`].
 //!
 //! The dispatcher is invoked from `Swd5Service::pump_agent`; it never
 //! crosses the HTTP↔game thread boundary directly (everything goes
@@ -41,8 +42,9 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use agent_server::protocol::{
-    AgentCommand, AgentError, AgentResponse, DialogSnapshot, NamedGlobal, ScriptEvalParams,
-    ScriptEvalResponse, ScriptGlobalsParams, ScriptGlobalsResponse, StateSnapshot, TeleportParams,
+    AgentCommand, AgentError, AgentResponse, DialogSnapshot, NamedGlobal, NpcEntry,
+    SceneObjectsResponse, ScriptEvalParams, ScriptEvalResponse, ScriptGlobalsParams,
+    ScriptGlobalsResponse, SlotParams, StateSnapshot, TeleportParams,
 };
 use radiance::input::Key;
 
@@ -55,14 +57,14 @@ use crate::scripting::lua50_32::LuaValue;
 /// Stitched together once per command by `Swd5Service::pump_agent`.
 ///
 /// `context` / `director` are `None` only in the brief window before
-/// the first `OpenSWD5Director` is installed; once present they stay
-/// for the rest of the launch (the SWD5 family has no start-menu /
-/// title mode).
+/// the first `OpenSWD5Director` is installed; from then on there is
+/// always one (the SWD5 family has no start-menu / title mode, and a
+/// load or new game swaps in its replacement the same frame).
 pub struct Swd5DispatchCtx<'a> {
     pub bridge: &'a Rc<AgentBridge>,
     pub context: Option<Rc<RefCell<SWD5Context>>>,
     /// Borrowed active director, used for Lua VM introspection
-    /// (`/v1/script/globals`). Kept separate from `context` because the
+    /// (`/v1/script/globals`, `/v1/script/trace/*`). Kept separate from `context` because the
     /// VM lives on the director, not the script context.
     pub director: Option<&'a OpenSWD5Director>,
 }
@@ -109,17 +111,24 @@ pub fn dispatch_swd5_command(ctx: &Swd5DispatchCtx, command: AgentCommand) -> Ag
         // --- gameplay surface ---------------------------------------------
         C::SetCamera(p) => handle_set_camera(ctx, p),
         C::TeleportPlayer(p) => handle_teleport(ctx, p),
+        C::GetSceneObjects => handle_scene_objects(ctx),
         C::GetScriptGlobals(p) => handle_script_globals(ctx, p),
         C::ScriptEval(p) => handle_script_eval(ctx, p),
 
+        // --- save / load -------------------------------------------------
+        // With no menu mode, `/v1/load` and the menu load intent are the
+        // same in-game restart.
+        C::SaveSlot(p) => handle_save_slot(ctx, p),
+        C::LoadSlot(p) | C::EnterLoadGame(p) => handle_load_slot(ctx, p),
+        C::EnterNewGame => match ctx.director {
+            Some(director) => {
+                director.new_game();
+                AgentResponse::Ok
+            }
+            None => AgentResponse::err(no_context_err()),
+        },
+
         // --- blocked on absent game-layer features ------------------------
-        C::SaveSlot(_) | C::EnterNewGame | C::EnterLoadGame(_) | C::LoadSlot(_) => {
-            AgentResponse::err(AgentError::not_implemented(
-                "SWD5 family has no save format support and boots straight into a \
-                 single bootstrap script (no menu mode), so save/load and mode \
-                 control have nothing to drive",
-            ))
-        }
         C::ExitGame => AgentResponse::err(AgentError::not_implemented(
             "SWD5 family has no menu mode to exit to",
         )),
@@ -136,20 +145,22 @@ pub fn dispatch_swd5_command(ctx: &Swd5DispatchCtx, command: AgentCommand) -> Ag
                  script drives all transitions directly",
             ))
         }
-        C::GetSceneObjects | C::InteractObject(_) => {
-            AgentResponse::err(AgentError::not_implemented(
-                "SWD5 family has no role/actor entities (chang_role_map, set_motion \
-                 and set_walks are stubs), so there is nothing to enumerate or \
-                 interact with until game-layer role loading lands",
-            ))
-        }
+        C::InteractObject(_) => AgentResponse::err(AgentError::not_implemented(
+            "SWD5-family roles carry no talk / examine handler; the Lua script \
+             drives every conversation directly",
+        )),
         C::SetDebugCamera(_) => AgentResponse::err(AgentError::not_implemented(
             "SWD5 family has no free-fly debug-camera mode; place the camera \
              directly with /v1/camera/pose",
         )),
-        C::TraceStart(_) | C::TraceStop | C::TraceDrain(_) => AgentResponse::err(
-            AgentError::not_implemented("SWD5-family Lua VM has no trace adapter yet"),
-        ),
+        C::TraceStart(p) => match ctx.director {
+            Some(director) => handlers::handle_trace_start(ctx.bridge, p, |sink| {
+                director.set_trace_sink(Some(sink))
+            }),
+            None => AgentResponse::err(no_context_err()),
+        },
+        C::TraceStop => handlers::handle_trace_stop(ctx.bridge),
+        C::TraceDrain(p) => handlers::handle_trace_drain(ctx.bridge, p),
 
         // AgentCommand is `#[non_exhaustive]` — surface any future
         // variants as `not_implemented` rather than panicking.
@@ -184,20 +195,17 @@ fn handle_set_camera(
     }
 }
 
-/// `POST /v1/player/teleport` — reinterpreted for the SWD5 family.
-///
-/// There is no player entity to move, so `pos[0]` is taken as the
-/// target **map id** (the same value `/v1/state` reports as `scene`)
-/// and routed through the engine's own `chang_map` path. `player` and
-/// `pos[1..]` are ignored.
+/// `POST /v1/player/teleport` — move the leader to `pos` on the
+/// current map. The SWD5 family has a single controllable role, so
+/// `player` is ignored. Change maps with `script_eval("chang_map", …)`.
 fn handle_teleport(ctx: &Swd5DispatchCtx, params: TeleportParams) -> AgentResponse {
     // Validate the payload before resolving the director, so a
     // malformed request is a 400 regardless of whether a map is
     // loaded yet.
-    let map_id = params.pos[0];
-    if !map_id.is_finite() || map_id < 0. {
+    if !params.pos.iter().all(|v| v.is_finite()) {
         return AgentResponse::err(AgentError::bad_request(format!(
-            "pos[0] must be a non-negative map id for the SWD5 family, got {map_id}"
+            "pos must be finite, got {:?}",
+            params.pos
         )));
     }
 
@@ -206,11 +214,87 @@ fn handle_teleport(ctx: &Swd5DispatchCtx, params: TeleportParams) -> AgentRespon
         None => return AgentResponse::err(no_context_err()),
     };
 
-    match context.borrow_mut().agent_change_map(map_id as i32) {
+    if context.borrow_mut().agent_teleport_leader(params.pos) {
+        AgentResponse::Ok
+    } else {
+        AgentResponse::err(no_leader_err())
+    }
+}
+
+/// `GET /v1/scene/objects` — every role on the current map, leader
+/// first. SWD5-family maps have no separate object list.
+fn handle_scene_objects(ctx: &Swd5DispatchCtx) -> AgentResponse {
+    let context = match ctx.context.as_ref() {
+        Some(c) => c,
+        None => return AgentResponse::err(no_context_err()),
+    };
+
+    let context = context.borrow();
+    let npcs = context
+        .roles()
+        .map(|role| {
+            let p = role.position();
+            NpcEntry {
+                name: role_name(role.role_id()),
+                position: [p.x, p.y, p.z],
+                visible: role.visible(),
+                talk_function: String::new(),
+            }
+        })
+        .collect();
+
+    AgentResponse::SceneObjects(SceneObjectsResponse {
+        scene: context.current_map_id().to_string(),
+        block: String::new(),
+        npcs,
+        objects: Vec::new(),
+    })
+}
+
+/// Name a role by its ATP id, the handle scripts use for it.
+fn role_name(role_id: i32) -> String {
+    format!("role{role_id}")
+}
+
+/// `POST /v1/save` — write the running game to `slot`.
+fn handle_save_slot(ctx: &Swd5DispatchCtx, params: SlotParams) -> AgentResponse {
+    if params.slot < 0 {
+        return AgentResponse::err(bad_slot_err(params.slot));
+    }
+    let (Some(director), Some(context)) = (ctx.director, ctx.context.as_ref()) else {
+        return AgentResponse::err(no_context_err());
+    };
+    if context.borrow().current_map_id() == 0 {
+        return AgentResponse::err(AgentError::conflict(
+            "no map is loaded yet; there is nothing to save",
+        ));
+    }
+
+    match director.save_state(params.slot) {
+        Ok(()) => AgentResponse::Ok,
+        Err(e) => AgentResponse::err(AgentError::internal(format!(
+            "cannot save slot {}: {e}",
+            params.slot
+        ))),
+    }
+}
+
+/// `POST /v1/load` — restart the main script from `slot`. The slot is
+/// read now, so a missing one is reported here; the restored game is
+/// live from the next frame.
+fn handle_load_slot(ctx: &Swd5DispatchCtx, params: SlotParams) -> AgentResponse {
+    if params.slot < 0 {
+        return AgentResponse::err(bad_slot_err(params.slot));
+    }
+    let Some(director) = ctx.director else {
+        return AgentResponse::err(no_context_err());
+    };
+
+    match director.load_state(params.slot) {
         Ok(()) => AgentResponse::Ok,
-        Err(e) => AgentResponse::err(AgentError::conflict(format!(
-            "failed to load map {}: {e:?}",
-            map_id as i32
+        Err(e) => AgentResponse::err(AgentError::bad_request(format!(
+            "cannot load slot {}: {e}",
+            params.slot
         ))),
     }
 }
@@ -285,6 +369,14 @@ fn no_context_err() -> AgentError {
     AgentError::conflict("no SWD5 director is active yet; retry once /v1/state responds")
 }
 
+fn bad_slot_err(slot: i32) -> AgentError {
+    AgentError::bad_request(format!("save slot must be non-negative, got {slot}"))
+}
+
+fn no_leader_err() -> AgentError {
+    AgentError::conflict("no leader role is spawned yet; the script places one with chang_role_map")
+}
+
 /// Marshal a [`LuaValue`] into the JSON carried by [`NamedGlobal`].
 fn lua_value_to_json(value: &LuaValue) -> serde_json::Value {
     match value {
@@ -317,6 +409,9 @@ pub fn build_snapshot(ctx: &Swd5DispatchCtx) -> StateSnapshot {
         // SWD5 scenes are numeric map ids; expose the current one as
         // the `scene` field so callers can correlate map changes.
         snap.scene = context.current_map_id().to_string();
+        if let Some(pos) = context.leader_position() {
+            snap.leader_pos = pos;
+        }
         // The VM is "running" whenever it isn't parked in a `sleep`.
         snap.script_running = !context.is_sleeping();
         snap.movie_playing = context.is_movie_playing();
@@ -485,13 +580,13 @@ mod tests {
     }
 
     #[test]
-    fn teleport_rejects_negative_map_id() {
+    fn teleport_rejects_non_finite_position() {
         let bridge = bridge();
         let resp = handle_teleport(
             &ctx(&bridge),
             TeleportParams {
                 player: 0,
-                pos: [-1., 0., 0.],
+                pos: [f32::NAN, 0., 0.],
             },
         );
 
@@ -507,7 +602,7 @@ mod tests {
     fn gameplay_commands_conflict_before_a_director_exists() {
         let bridge = bridge();
 
-        // A valid map id, but no director is installed yet.
+        // A valid position, but no director is installed yet.
         let resp = handle_teleport(
             &ctx(&bridge),
             TeleportParams {
@@ -528,6 +623,9 @@ mod tests {
 
         let resp = handle_script_globals(&ctx(&bridge), ScriptGlobalsParams::default());
         assert!(matches!(resp, AgentResponse::Error(_)));
+
+        let resp = handle_scene_objects(&ctx(&bridge));
+        assert!(matches!(resp, AgentResponse::Error(_)));
     }
 
     #[test]
@@ -555,9 +653,67 @@ mod tests {
     }
 
     #[test]
-    fn role_dependent_commands_report_the_real_blocker() {
+    fn trace_needs_a_director_to_start_but_always_drains() {
+        let bridge = bridge();
+
+        let resp =
+            dispatch_swd5_command(&ctx(&bridge), AgentCommand::TraceStart(Default::default()));
+        assert!(matches!(resp, AgentResponse::Error(_)));
+        assert!(!bridge.trace_sink.is_capturing());
+
+        match dispatch_swd5_command(&ctx(&bridge), AgentCommand::TraceDrain(Default::default())) {
+            AgentResponse::TraceDrain(drain) => {
+                assert!(!drain.capturing);
+                assert!(drain.events.is_empty());
+            }
+            other => panic!("expected trace_drain, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn save_and_load_need_a_director_and_a_valid_slot() {
         let bridge = bridge();
-        let resp = dispatch_swd5_command(&ctx(&bridge), AgentCommand::GetSceneObjects);
+
+        for command in [
+            AgentCommand::SaveSlot(SlotParams { slot: 1 }),
+            AgentCommand::LoadSlot(SlotParams { slot: 1 }),
+            AgentCommand::EnterLoadGame(SlotParams { slot: 1 }),
+            AgentCommand::EnterNewGame,
+        ] {
+            match dispatch_swd5_command(&ctx(&bridge), command) {
+                AgentResponse::Error(e) => {
+                    assert_eq!(e.kind, agent_server::protocol::AgentErrorKind::Conflict)
+                }
+                other => panic!("expected conflict, got {other:?}"),
+            }
+        }
+
+        for command in [
+            AgentCommand::SaveSlot(SlotParams { slot: -1 }),
+            AgentCommand::LoadSlot(SlotParams { slot: -1 }),
+        ] {
+            match dispatch_swd5_command(&ctx(&bridge), command) {
+                AgentResponse::Error(e) => {
+                    assert_eq!(e.kind, agent_server::protocol::AgentErrorKind::BadRequest)
+                }
+                other => panic!("expected bad_request, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn roles_are_named_by_atp_id() {
+        assert_eq!(role_name(1), "role1");
+        assert_eq!(role_name(42), "role42");
+    }
+
+    #[test]
+    fn role_interaction_reports_the_real_blocker() {
+        let bridge = bridge();
+        let resp = dispatch_swd5_command(
+            &ctx(&bridge),
+            AgentCommand::InteractObject(agent_server::protocol::NameParams { name: role_name(1) }),
+        );
 
         match resp {
             AgentResponse::Error(e) => {
@@ -566,7 +722,7 @@ mod tests {
                     agent_server::protocol::AgentErrorKind::NotImplemented
                 );
                 assert!(
-                    e.message.contains("role"),
+                    e.message.contains("talk"),
                     "message should name the missing feature: {}",
                     e.message
                 );
@@ -19,7 +19,8 @@ use fileformats::{
 };
 use mini_fs::{MiniFs, StoreExt};
 use radiance::{
-    comdef::IScene,
+    comdef::{IEntity, IScene},
+    components::mesh::skinned_mesh::AnimKeyFrame,
     rendering::{ComponentFactory, Sprite},
     scene::CoreScene,
     utils::SeekRead,
@@ -29,8 +30,10 @@ use crate::{
     GameType,
     loaders::{
         Swd5TextureResolver,
+        anm::load_anm,
         dff::{DffLoaderConfig, create_entity_from_dff_model},
     },
+    openswd5::role::{Swd5RoleAsset, role_vfs_path},
 };
 
 pub struct AssetLoader {
@@ -61,6 +64,10 @@ impl AssetLoader {
         })
     }
 
+    pub fn game(&self) -> GameType {
+        self.game
+    }
+
     pub fn component_factory(&self) -> Rc<dyn ComponentFactory> {
         self.component_factory.clone()
     }
@@ -118,6 +125,7 @@ impl AssetLoader {
                 dynamic_lighting: false,
                 fog_exempt: false,
                 foliage_resolver: None,
+                water: None,
             },
         )?;
 
@@ -166,6 +174,57 @@ impl AssetLoader {
         }
     }
 
+    /// Resolve a role's model and motions from its 1-based ATP id.
+    pub fn load_role_asset(&self, role_id: i32) -> anyhow::Result<Swd5RoleAsset> {
+        let entry = usize::try_from(role_id - 1)
+            .ok()
+            .and_then(|i| self.index.get(i))
+            .and_then(|e| e.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("No such role {role_id}"))?;
+
+        Swd5RoleAsset::from_atp(entry)
+            .ok_or_else(|| anyhow::anyhow!("ATP entry {role_id} is not a role"))
+    }
+
+    pub fn load_role_model(&self, model: &str, role_id: i32) -> anyhow::Result<ComRc<IEntity>> {
+        let path = role_vfs_path(model);
+        log::debug!("loading role {}: {}", role_id, path);
+
+        create_entity_from_dff_model(
+            &self.component_factory,
+            &self.vfs,
+            &path,
+            format!("role_{}", role_id),
+            true,
+            &DffLoaderConfig {
+                texture_resolver: &self.texture_resolver,
+                keep_right_to_render_only: false,
+                force_unique_materials: false,
+                ignore_root_frame_translation: false,
+
+                bsp_lightmap_tint: None,
+                dynamic_lighting: false,
+                fog_exempt: false,
+                foliage_resolver: None,
+                water: None,
+            },
+        )
+    }
+
+    pub fn load_role_motion(&self, motion: &str) -> anyhow::Result<Vec<Vec<AnimKeyFrame>>> {
+        load_anm(&self.vfs, role_vfs_path(motion))
+    }
+
+    /// ATP id of the role `chang_role_map` spawns as the party leader.
+    /// `None` for titles whose leader entry isn't identified yet; those
+    /// enter maps without a leader.
+    pub fn leader_role_id(&self) -> Option<i32> {
+        match self.game {
+            GameType::SWD5 => Some(1),
+            _ => None,
+        }
+    }
+
     pub fn load_movie_data(&self, movie_id: u32) -> anyhow::Result<Box<dyn SeekRead>> {
         let path = format!("/movie/movie{:0>2}.bik", movie_id);
 
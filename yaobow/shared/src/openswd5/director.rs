@@ -12,12 +12,22 @@ use radiance::{
 
 use crate::agent_common::AgentBridge;
 use crate::scripting::lua50_32::{Lua5032Vm, LuaValue};
+use crate::scripting::trace::TraceSink;
 
 use super::{
     asset_loader::AssetLoader,
+    persistent_state::Swd5PersistentState,
     scripting::{RESERVED_GLOBAL_NAMES, SWD5Context, create_lua_vm},
 };
 
+/// How the next `update` restarts the main script. The Lua coroutine
+/// can't be rewound in place, so both cases build a new director with
+/// a fresh VM and hand it to the scene manager.
+enum Swd5Restart {
+    NewGame,
+    Load(Box<Swd5PersistentState>),
+}
+
 pub struct OpenSWD5Director {
     vm: Lua5032Vm<SWD5Context>,
     context: Rc<RefCell<SWD5Context>>,
@@ -29,6 +39,9 @@ pub struct OpenSWD5Director {
     /// `Some(_)` when `--swd5 --agent-port` was passed, in which case
     /// `update` honours pause / fixed-step and fast-forward.
     agent_bridge: Option<Rc<AgentBridge>>,
+    /// Set by [`Self::new_game`] / [`Self::load_state`], consumed by the
+    /// next `update`.
+    restart: RefCell<Option<Swd5Restart>>,
 }
 
 impl OpenSWD5Director {
@@ -44,18 +57,28 @@ impl OpenSWD5Director {
         let context = Rc::new(RefCell::new(SWD5Context::new(
             asset_loader.clone(),
             audio_engine,
-            input.clone(),
+            input,
             component_factory,
             scene_manager,
             ui,
         )));
         let vm = create_lua_vm(&asset_loader, context.clone()).unwrap();
 
+        Self::with_vm(vm, context, agent_bridge)
+    }
+
+    fn with_vm(
+        vm: Lua5032Vm<SWD5Context>,
+        context: Rc<RefCell<SWD5Context>>,
+        agent_bridge: Option<Rc<AgentBridge>>,
+    ) -> Self {
+        let input = context.borrow().input_engine();
         Self {
             vm,
             context,
             control: FreeViewController::new(input),
             agent_bridge,
+            restart: RefCell::new(None),
         }
     }
 
@@ -66,6 +89,12 @@ impl OpenSWD5Director {
         self.context.clone()
     }
 
+    /// Install (or clear) the Lua VM's execution-trace sink. Backs
+    /// `/v1/script/trace/start` for the SWD5 family.
+    pub fn set_trace_sink(&self, sink: Option<Rc<dyn TraceSink>>) {
+        self.vm.set_trace_sink(sink);
+    }
+
     /// Enumerate the Lua global table, dropping the host functions and
     /// stdlib entries listed in
     /// [`RESERVED_GLOBAL_NAMES`](super::scripting::RESERVED_GLOBAL_NAMES)
@@ -78,6 +107,51 @@ impl OpenSWD5Director {
             .filter(|(name, _)| !RESERVED_GLOBAL_NAMES.contains(&name.as_str()))
             .collect()
     }
+
+    /// Save the running game to `slot`: the context's host-side state
+    /// plus the script globals.
+    pub fn save_state(&self, slot: i32) -> anyhow::Result<()> {
+        let mut state = self.context.borrow().persistent_state();
+        state.set_globals(&self.script_globals());
+        state.save(slot)
+    }
+
+    /// Read `slot` and schedule a restart into it on the next `update`.
+    /// A missing or malformed slot fails here and leaves the running
+    /// game untouched.
+    pub fn load_state(&self, slot: i32) -> anyhow::Result<()> {
+        let app_name = self.context.borrow().asset_loader().game().app_name();
+        let state = Swd5PersistentState::load(app_name, slot)?;
+        self.restart
+            .replace(Some(Swd5Restart::Load(Box::new(state))));
+        Ok(())
+    }
+
+    /// Schedule a restart of the main script from scratch.
+    pub fn new_game(&self) {
+        self.restart.replace(Some(Swd5Restart::NewGame));
+    }
+
+    /// Build the director that replaces this one. On a load, the saved
+    /// globals are written over the ones the main chunk just defined,
+    /// and the context rebuilds the map and roles, before the entry
+    /// function first runs.
+    fn restarted(&self, restart: Swd5Restart) -> anyhow::Result<Self> {
+        let context = Rc::new(RefCell::new(self.context.borrow().fresh()));
+        let asset_loader = context.borrow().asset_loader().clone();
+        let vm = create_lua_vm(&asset_loader, context.clone())?;
+
+        if let Swd5Restart::Load(state) = restart {
+            for global in state.globals() {
+                if !vm.set_global(&global.name, &global.value.to_lua()) {
+                    log::warn!("SWD5: can't restore global {:?}", global.name);
+                }
+            }
+            context.borrow_mut().restore(&state)?;
+        }
+
+        Ok(Self::with_vm(vm, context, self.agent_bridge.clone()))
+    }
 }
 
 ComObject_OpenSWD5Director!(super::OpenSWD5Director);
@@ -86,6 +160,13 @@ impl IDirectorImpl for OpenSWD5Director {
     fn activate(&self) {}
 
     fn update(&self, delta_sec: f32) -> Option<ComRc<IDirector>> {
+        if let Some(restart) = self.restart.take() {
+            match self.restarted(restart) {
+                Ok(director) => return Some(ComRc::<IDirector>::from_object(director)),
+                Err(e) => log::error!("SWD5: restart failed: {:?}", e),
+            }
+        }
+
         // Pause / fixed-step gating: when an agent bridge is present
         // and paused, `advance` is false and `effective_dt` is 0, so
         // the script clock freezes until a `/v1/time/step` is queued.
@@ -0,0 +1,305 @@
+//! SWD5-family save slots.
+//!
+//! The Lua game layer keeps its progress in the VM, so a save is a
+//! snapshot taken from both sides: the script's scalar globals (read
+//! with `enumerate_globals`) and the `fon` / `foff` flag set, plus the
+//! host-side state the script can't rebuild on its own — current map,
+//! camera pose, player lock and every role's placement and motions.
+//!
+//! Slots live at `<save_dir>/<app_name>/Save/<slot>.json`, the layout
+//! PAL3 and PAL4 use, with the per-game [`GameType::app_name`]
+//! (`OpenSWD5` / `OpenSWDHC` / `OpenSWDCF`) as the namespace.
+//!
+//! [`GameType::app_name`]: crate::GameType::app_name
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::scripting::lua50_32::LuaValue;
+use crate::ydirs;
+
+use super::role::Swd5RoleState;
+
+/// A Lua global value that survives a save. Only scalars are stored: a
+/// `nil` global is simply absent, functions are redefined by the main
+/// chunk, and tables are *not* saved — a table global comes back with
+/// the value the main chunk gives it, losing any changes made while
+/// playing. [`Swd5PersistentState::set_globals`] reports the tables it
+/// drops.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SavedValue {
+    Bool(bool),
+    Number(f64),
+    Str(String),
+}
+
+impl SavedValue {
+    pub fn from_lua(value: &LuaValue) -> Option<Self> {
+        match value {
+            LuaValue::Bool(b) => Some(Self::Bool(*b)),
+            LuaValue::Number(n) if n.is_finite() => Some(Self::Number(*n)),
+            LuaValue::Str(s) => Some(Self::Str(s.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_lua(&self) -> LuaValue {
+        match self {
+            Self::Bool(b) => LuaValue::Bool(*b),
+            Self::Number(n) => LuaValue::Number(*n),
+            Self::Str(s) => LuaValue::Str(s.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedGlobal {
+    pub name: String,
+    pub value: SavedValue,
+}
+
+/// Camera eye and look-at target, the two points the scripts drive
+/// through `set_camera_src_pos` / `chang_camera_view`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedCamera {
+    pub eye: [f32; 3],
+    pub look_at: [f32; 3],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Swd5PersistentState {
+    app_name: String,
+    /// Map the player was on; `0` when saved before the first map load.
+    #[serde(default)]
+    map_id: i32,
+    /// Flags raised with `fon`, sorted.
+    #[serde(default)]
+    flags: Vec<i32>,
+    #[serde(default)]
+    globals: Vec<SavedGlobal>,
+    #[serde(default)]
+    camera: Option<SavedCamera>,
+    #[serde(default)]
+    player_locked: bool,
+    /// The party leader, `None` before `chang_role_map` spawned it.
+    #[serde(default)]
+    leader: Option<Swd5RoleState>,
+    /// The map's `.fld` roles, in placement order.
+    #[serde(default)]
+    roles: Vec<Swd5RoleState>,
+}
+
+impl Swd5PersistentState {
+    pub fn new(app_name: String) -> Self {
+        Self {
+            app_name,
+            map_id: 0,
+            flags: Vec::new(),
+            globals: Vec::new(),
+            camera: None,
+            player_locked: false,
+            leader: None,
+            roles: Vec::new(),
+        }
+    }
+
+    fn slot_path(app_name: &str, slot: i32) -> PathBuf {
+        ydirs::save_dir()
+            .join(app_name)
+            .join("Save")
+            .join(format!("{}.json", slot))
+    }
+
+    /// Load the state for `app_name` from the given slot. Fails when the
+    /// slot file is missing or malformed.
+    pub fn load(app_name: &str, slot: i32) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(Self::slot_path(app_name, slot))?;
+        let state = serde_json::from_str(&content)?;
+        Ok(state)
+    }
+
+    /// Write this state to the given slot. Unlike PAL4's fire-and-forget
+    /// save, failures are returned so the agent can report them.
+    pub fn save(&self, slot: i32) -> anyhow::Result<()> {
+        if slot < 0 {
+            anyhow::bail!("invalid save slot {}", slot);
+        }
+
+        let path = Self::slot_path(&self.app_name, slot);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        log::info!("Game saved to slot {}", slot);
+        Ok(())
+    }
+
+    pub fn app_name(&self) -> &str {
+        &self.app_name
+    }
+
+    pub fn summary(&self) -> String {
+        if self.map_id == 0 {
+            "No map".to_string()
+        } else {
+            format!("Map {}", self.map_id)
+        }
+    }
+
+    pub fn map_id(&self) -> i32 {
+        self.map_id
+    }
+
+    pub fn set_map_id(&mut self, map_id: i32) {
+        self.map_id = map_id;
+    }
+
+    pub fn flags(&self) -> &[i32] {
+        &self.flags
+    }
+
+    pub fn set_flags(&mut self, flags: impl IntoIterator<Item = i32>) {
+        self.flags = flags.into_iter().collect();
+        self.flags.sort_unstable();
+        self.flags.dedup();
+    }
+
+    pub fn globals(&self) -> &[SavedGlobal] {
+        &self.globals
+    }
+
+    /// Keep the globals that can be stored; see [`SavedValue`]. Table
+    /// globals are logged, since their contents are lost.
+    pub fn set_globals(&mut self, globals: &[(String, LuaValue)]) {
+        let tables: Vec<&str> = globals
+            .iter()
+            .filter(|(_, value)| *value == LuaValue::Other("table"))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if !tables.is_empty() {
+            log::warn!("save: table globals are not stored: {}", tables.join(", "));
+        }
+
+        self.globals = globals
+            .iter()
+            .filter_map(|(name, value)| {
+                SavedValue::from_lua(value).map(|value| SavedGlobal {
+                    name: name.clone(),
+                    value,
+                })
+            })
+            .collect();
+    }
+
+    pub fn camera(&self) -> Option<SavedCamera> {
+        self.camera
+    }
+
+    pub fn set_camera(&mut self, camera: Option<SavedCamera>) {
+        self.camera = camera;
+    }
+
+    pub fn player_locked(&self) -> bool {
+        self.player_locked
+    }
+
+    pub fn set_player_locked(&mut self, locked: bool) {
+        self.player_locked = locked;
+    }
+
+    pub fn leader(&self) -> Option<&Swd5RoleState> {
+        self.leader.as_ref()
+    }
+
+    pub fn set_leader(&mut self, leader: Option<Swd5RoleState>) {
+        self.leader = leader;
+    }
+
+    pub fn roles(&self) -> &[Swd5RoleState] {
+        &self.roles
+    }
+
+    pub fn set_roles(&mut self, roles: Vec<Swd5RoleState>) {
+        self.roles = roles;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(role_id: i32) -> Swd5RoleState {
+        Swd5RoleState {
+            role_id,
+            position: [1., 2., 3.],
+            heading: 90.,
+            idle_motion: Some(0),
+            walk_motion: Some(2),
+            face_motion: 1,
+        }
+    }
+
+    #[test]
+    fn state_survives_json_round_trip() {
+        let mut state = Swd5PersistentState::new("OpenSWDHC".to_string());
+        state.set_map_id(12);
+        state.set_flags([30, 4, 30]);
+        state.set_globals(&[
+            ("chapter".to_string(), LuaValue::Number(3.)),
+            ("hero".to_string(), LuaValue::Str("陳輔".to_string())),
+            ("met_anu".to_string(), LuaValue::Bool(true)),
+        ]);
+        state.set_camera(Some(SavedCamera {
+            eye: [0., 100., -200.],
+            look_at: [0., 0., 0.],
+        }));
+        state.set_player_locked(true);
+        state.set_leader(Some(role(1)));
+        state.set_roles(vec![role(7)]);
+
+        let json = serde_json::to_string(&state).unwrap();
+        let loaded: Swd5PersistentState = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.app_name(), "OpenSWDHC");
+        assert_eq!(loaded.map_id(), 12);
+        assert_eq!(loaded.flags(), &[4, 30]);
+        assert_eq!(loaded.globals(), state.globals());
+        assert_eq!(loaded.camera(), state.camera());
+        assert!(loaded.player_locked());
+        assert_eq!(loaded.leader(), Some(&role(1)));
+        assert_eq!(loaded.roles(), &[role(7)]);
+    }
+
+    #[test]
+    fn only_scalar_globals_are_kept() {
+        let mut state = Swd5PersistentState::new("OpenSWD5".to_string());
+        state.set_globals(&[
+            ("a".to_string(), LuaValue::Nil),
+            ("b".to_string(), LuaValue::Other("table")),
+            ("c".to_string(), LuaValue::Number(f64::NAN)),
+            ("d".to_string(), LuaValue::Number(2.5)),
+        ]);
+
+        assert_eq!(
+            state.globals(),
+            &[SavedGlobal {
+                name: "d".to_string(),
+                value: SavedValue::Number(2.5),
+            }]
+        );
+        assert_eq!(state.globals()[0].value.to_lua(), LuaValue::Number(2.5));
+    }
+
+    #[test]
+    fn minimal_save_deserializes_with_defaults() {
+        let json = r#"{"app_name":"OpenSWDCF","map_id":3}"#;
+        let state: Swd5PersistentState = serde_json::from_str(json).unwrap();
+        assert_eq!(state.map_id(), 3);
+        assert!(state.flags().is_empty());
+        assert!(state.leader().is_none());
+        assert!(!state.player_locked());
+        assert_eq!(state.summary(), "Map 3");
+    }
+}
@@ -0,0 +1,310 @@
+//! SWD5-family role (actor) entities.
+//!
+//! Roles are resolved through the shared `ACT/*.atp` index: a type-1
+//! entry ([`AtpEntryData41`]) names the role's skinned DFF in its
+//! `file` record and lists the role's motions (`.anm` clips) as
+//! `sub_files`, in the order scripts address them by index
+//! (`set_motion(role, n)`, `set_walks(role, n)`).
+//!
+//! A [`Swd5Role`] owns the loaded entity plus the little state the
+//! scripts drive: position and facing, the idle motion, and the walk
+//! cycle that plays while the role is moving.
+
+use std::rc::Rc;
+
+use crosscom::ComRc;
+use fileformats::swd5::atp::{AtpEntry, AtpEntryData4, AtpEntryData41};
+use radiance::{
+    comdef::{IArmatureComponent, IEntity, IEntityExt},
+    components::mesh::IArmatureComponentExt,
+    math::Vec3,
+};
+use serde::{Deserialize, Serialize};
+
+use super::asset_loader::AssetLoader;
+
+/// Leader walking speed, world units per second.
+pub const LEADER_WALK_SPEED: f32 = 175.;
+
+/// Model and motion paths of one ATP role entry, still in the raw
+/// (backslash-separated, index-relative) form the ATP stores.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Swd5RoleAsset {
+    pub model: String,
+    pub motions: Vec<String>,
+}
+
+impl Swd5RoleAsset {
+    /// Extract the model and motion list from an ATP entry. `None` for
+    /// entries that aren't roles (pictures, effects, …).
+    pub fn from_atp(entry: &AtpEntry) -> Option<Self> {
+        let AtpEntryData4::Data1(data) = entry.data4.as_ref()? else {
+            return None;
+        };
+        Self::from_data41(data)
+    }
+
+    fn from_data41(data: &AtpEntryData41) -> Option<Self> {
+        let model = data.file.as_ref()?.path.as_ref()?.to_string();
+        if model.is_empty() {
+            return None;
+        }
+
+        let motions = data
+            .sub_files
+            .iter()
+            .flatten()
+            .filter_map(|f| f.path.as_ref().map(|p| p.to_string()))
+            .collect();
+
+        Some(Self { model, motions })
+    }
+}
+
+/// VFS path of a path stored in an ATP entry: the stored path, taken
+/// relative to the game root, with Windows separators normalized.
+pub fn role_vfs_path(raw: &str) -> String {
+    let normalized = raw.replace('\\', "/");
+    let relative = normalized.trim_start_matches("./").trim_start_matches('/');
+    format!("/{}", relative)
+}
+
+/// Facing (degrees around +Y, 0 = +Z) for a movement direction.
+pub fn heading_for_direction(direction: &Vec3) -> Option<f32> {
+    if direction.x.abs() < f32::EPSILON && direction.z.abs() < f32::EPSILON {
+        None
+    } else {
+        Some(direction.x.atan2(direction.z).to_degrees())
+    }
+}
+
+/// The script-visible state of one role, as stored in a save slot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Swd5RoleState {
+    pub role_id: i32,
+    pub position: [f32; 3],
+    #[serde(default)]
+    pub heading: f32,
+    #[serde(default)]
+    pub idle_motion: Option<usize>,
+    #[serde(default)]
+    pub walk_motion: Option<usize>,
+    #[serde(default)]
+    pub face_motion: i32,
+}
+
+pub struct Swd5Role {
+    role_id: i32,
+    entity: ComRc<IEntity>,
+    armature: Option<ComRc<IArmatureComponent>>,
+    asset_loader: Rc<AssetLoader>,
+    motions: Vec<String>,
+
+    position: Vec3,
+    heading: f32,
+    idle_motion: Option<usize>,
+    walk_motion: Option<usize>,
+    face_motion: i32,
+
+    moving: bool,
+    /// Motion currently on the armature, to avoid restarting a clip
+    /// every frame.
+    playing: Option<usize>,
+}
+
+impl Swd5Role {
+    pub fn load(asset_loader: &Rc<AssetLoader>, role_id: i32) -> anyhow::Result<Self> {
+        let asset = asset_loader.load_role_asset(role_id)?;
+        let entity = asset_loader.load_role_model(&asset.model, role_id)?;
+        let armature = entity
+            .get_component(IArmatureComponent::uuid())
+            .and_then(|c| c.query_interface::<IArmatureComponent>());
+
+        let mut role = Self {
+            role_id,
+            entity,
+            armature,
+            asset_loader: asset_loader.clone(),
+            motions: asset.motions,
+            position: Vec3::new_zeros(),
+            heading: 0.,
+            idle_motion: None,
+            walk_motion: None,
+            face_motion: 0,
+            moving: false,
+            playing: None,
+        };
+
+        // Motion 0 is the standing pose until a script says otherwise.
+        if !role.motions.is_empty() {
+            role.idle_motion = Some(0);
+        }
+        role.update_motion();
+        Ok(role)
+    }
+
+    pub fn role_id(&self) -> i32 {
+        self.role_id
+    }
+
+    pub fn entity(&self) -> &ComRc<IEntity> {
+        &self.entity
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.position
+    }
+
+    pub fn visible(&self) -> bool {
+        self.entity.visible()
+    }
+
+    pub fn face_motion(&self) -> i32 {
+        self.face_motion
+    }
+
+    pub fn set_position(&mut self, position: Vec3) {
+        self.position = position;
+        self.apply_transform();
+    }
+
+    pub fn set_heading(&mut self, degrees: f32) {
+        self.heading = degrees;
+        self.apply_transform();
+    }
+
+    pub fn state(&self) -> Swd5RoleState {
+        Swd5RoleState {
+            role_id: self.role_id,
+            position: [self.position.x, self.position.y, self.position.z],
+            heading: self.heading,
+            idle_motion: self.idle_motion,
+            walk_motion: self.walk_motion,
+            face_motion: self.face_motion,
+        }
+    }
+
+    /// Put the role back into a saved state. Motion indices the role
+    /// doesn't have are dropped rather than kept dangling.
+    pub fn apply_state(&mut self, state: &Swd5RoleState) {
+        let [x, y, z] = state.position;
+        self.position = Vec3::new(x, y, z);
+        self.heading = state.heading;
+        self.apply_transform();
+
+        let valid = |m: Option<usize>| m.filter(|&m| m < self.motions.len());
+        self.idle_motion = valid(state.idle_motion);
+        self.walk_motion = valid(state.walk_motion);
+        self.face_motion = state.face_motion;
+        self.moving = false;
+        self.update_motion();
+    }
+
+    /// `set_motion(role, n)`: make motion `n` the role's standing
+    /// motion. Out-of-range indices are ignored.
+    pub fn set_motion(&mut self, motion: usize) {
+        if motion < self.motions.len() {
+            self.idle_motion = Some(motion);
+            self.update_motion();
+        } else {
+            log::warn!("role {}: no motion {}", self.role_id, motion);
+        }
+    }
+
+    /// `set_walks(role, n)`: use motion `n` as the walk cycle.
+    pub fn set_walks(&mut self, motion: usize) {
+        if motion < self.motions.len() {
+            self.walk_motion = Some(motion);
+            self.update_motion();
+        } else {
+            log::warn!("role {}: no walk motion {}", self.role_id, motion);
+        }
+    }
+
+    /// `set_role_face_motion(role, n)`: the facial expression index.
+    /// Role DFFs carry no separate face rig, so it is only recorded.
+    pub fn set_face_motion(&mut self, face_motion: i32) {
+        self.face_motion = face_motion;
+    }
+
+    /// Move along `direction` (unit length on XZ, or zero to stand
+    /// still) for one frame.
+    pub fn walk(&mut self, direction: &Vec3, speed: f32, delta_sec: f32) {
+        let moving = direction.norm() > 0.5;
+        if moving {
+            self.position = Vec3::add(
+                &self.position,
+                &Vec3::scalar_mul(speed * delta_sec, direction),
+            );
+            if let Some(heading) = heading_for_direction(direction) {
+                self.heading = heading;
+            }
+            self.apply_transform();
+        }
+
+        if moving != self.moving {
+            self.moving = moving;
+            self.update_motion();
+        }
+    }
+
+    fn update_motion(&mut self) {
+        let wanted = if self.moving {
+            self.walk_motion.or(self.idle_motion)
+        } else {
+            self.idle_motion
+        };
+        if wanted == self.playing {
+            return;
+        }
+
+        let (Some(armature), Some(index)) = (&self.armature, wanted) else {
+            return;
+        };
+        match self.asset_loader.load_role_motion(&self.motions[index]) {
+            Ok(keyframes) => {
+                armature.set_animation(keyframes, vec![]);
+                armature.set_looping(true);
+                armature.play();
+            }
+            Err(e) => log::warn!(
+                "role {}: motion {} ({}) failed: {:?}",
+                self.role_id,
+                index,
+                self.motions[index],
+                e
+            ),
+        }
+        self.playing = wanted;
+    }
+
+    fn apply_transform(&self) {
+        self.entity
+            .transform()
+            .borrow_mut()
+            .set_position(&self.position)
+            .clear_rotation()
+            .rotate_axis_angle_local(&Vec3::UP, self.heading.to_radians());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vfs_path_normalizes_separators() {
+        assert_eq!(role_vfs_path("Role\\r001\\r001.dff"), "/Role/r001/r001.dff");
+        assert_eq!(role_vfs_path("./r001.dff"), "/r001.dff");
+        assert_eq!(role_vfs_path("\\Role\\r001.anm"), "/Role/r001.anm");
+    }
+
+    #[test]
+    fn heading_points_along_direction() {
+        let east = heading_for_direction(&Vec3::new(1., 0., 0.)).unwrap();
+        assert!((east - 90.).abs() < 1e-4);
+        let north = heading_for_direction(&Vec3::new(0., 0., 1.)).unwrap();
+        assert!(north.abs() < 1e-4);
+        assert!(heading_for_direction(&Vec3::new(0., 0., 0.)).is_none());
+    }
+}
@@ -1,29 +1,48 @@
+use std::rc::Rc;
+
 use crosscom::ComRc;
 use radiance::{comdef::IScene, math::Vec3};
 
-use super::asset_loader::AssetLoader;
+use super::{asset_loader::AssetLoader, role::Swd5Role};
 use radiance::comdef::ISceneExt;
 
 pub struct Swd5Scene {
     pub scene: ComRc<IScene>,
     pub camera_look_at: Vec3,
     pub camera_position: Vec3,
+    /// Roles placed on this map. Always empty for now: the `.fld` role
+    /// table isn't decoded yet. The party leader is owned by the script
+    /// context and carried across maps, not listed here.
+    pub roles: Vec<Swd5Role>,
 }
 
 impl Swd5Scene {
-    pub fn load(asset_loader: &AssetLoader, map_id: i32) -> anyhow::Result<Self> {
+    pub fn load(asset_loader: &Rc<AssetLoader>, map_id: i32) -> anyhow::Result<Self> {
         let fld = asset_loader.load_fld(map_id)?;
         let map = asset_loader.load_map(fld.map_file.to_string())?;
         let scene = asset_loader.load_scene_dff(&map.model_chunk.model_file.to_string())?;
         scene.camera_mut().set_fov43(60_f32.to_radians());
 
+        if !fld.unknown.is_empty() {
+            log::debug!(
+                "map {}: {} undecoded bytes after the field header, no roles placed",
+                map_id,
+                fld.unknown.len()
+            );
+        }
+
         Ok(Self {
             scene,
             camera_look_at: Vec3::new(0., 0., 0.),
             camera_position: Vec3::new(0., 0., 0.),
+            roles: vec![],
         })
     }
 
+    pub fn role_mut(&mut self, role_id: i32) -> Option<&mut Swd5Role> {
+        self.roles.iter_mut().find(|r| r.role_id() == role_id)
+    }
+
     pub fn set_camera_delta(&mut self, dx: f32, dy: f32, dz: f32) {
         {
             let mut c = self.scene.camera_mut();
@@ -1,4 +1,9 @@
-use std::{cell::RefCell, collections::HashMap, os::raw::c_char, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    os::raw::c_char,
+    rc::Rc,
+};
 
 use crosscom::ComRc;
 use encoding::{DecoderTrap, Encoding};
@@ -8,14 +13,21 @@ use radiance::{
     audio::{AudioEngine, AudioMemorySource, AudioSourceState, Codec},
     comdef::ISceneManager,
     input::{InputEngine, Key},
+    math::Vec3,
     radiance::UiManager,
     rendering::{ComponentFactory, Sprite, VideoPlayer},
     utils::{act_drop::ActDrop, interp_value::InterpValue},
 };
 
-use crate::scripting::lua50_32::Lua5032Vm;
+use crate::scripting::lua50_32::{Lua5032Vm, trace_command};
+use crate::utils::get_moving_direction;
 
-use super::{asset_loader::AssetLoader, scene::Swd5Scene};
+use super::{
+    asset_loader::AssetLoader,
+    persistent_state::{SavedCamera, Swd5PersistentState},
+    role::{LEADER_WALK_SPEED, Swd5Role},
+    scene::Swd5Scene,
+};
 
 pub struct SWD5Context {
     asset_loader: Rc<AssetLoader>,
@@ -30,6 +42,13 @@ pub struct SWD5Context {
     /// Current map id (set by `chang_map`). Surfaced to the agent
     /// snapshot as the `scene` field. `0` before the first map load.
     current_map_id: i32,
+    /// The player-controlled role. Spawned by the first
+    /// `chang_role_map` and carried across map changes.
+    leader: Option<Swd5Role>,
+    /// Set by `lock_player`; while set, input doesn't move the leader.
+    player_locked: bool,
+    /// Story flags raised by `fon`, cleared by `foff`, read by `isfon`.
+    flags: BTreeSet<i32>,
 
     bgm_source: Box<dyn AudioMemorySource>,
     sound_sources: HashMap<i32, RefCell<Box<dyn AudioMemorySource>>>,
@@ -69,6 +88,9 @@ impl SWD5Context {
             scene: None,
             sleep_sec: 0.,
             current_map_id: 0,
+            leader: None,
+            player_locked: false,
+            flags: BTreeSet::new(),
             bgm_source,
             sound_sources: HashMap::new(),
             story_msg: None,
@@ -81,6 +103,91 @@ impl SWD5Context {
         }
     }
 
+    /// A blank context on the same engine services, for restarting the
+    /// main script (new game / load).
+    pub fn fresh(&self) -> Self {
+        Self::new(
+            self.asset_loader.clone(),
+            self.audio_engine.clone(),
+            self.input_engine.clone(),
+            self.component_factory.clone(),
+            self.scene_manager.clone(),
+            self.ui.clone(),
+        )
+    }
+
+    pub fn asset_loader(&self) -> &Rc<AssetLoader> {
+        &self.asset_loader
+    }
+
+    pub fn input_engine(&self) -> Rc<RefCell<dyn InputEngine>> {
+        self.input_engine.clone()
+    }
+
+    /// Snapshot the host-side state into a save. Script globals live in
+    /// the VM and are added by the director.
+    pub fn persistent_state(&self) -> Swd5PersistentState {
+        let mut state = Swd5PersistentState::new(self.asset_loader.game().app_name().to_string());
+        state.set_map_id(self.current_map_id);
+        state.set_flags(self.flags.iter().copied());
+        state.set_player_locked(self.player_locked);
+        state.set_leader(self.leader.as_ref().map(|leader| leader.state()));
+
+        if let Some(scene) = self.scene.as_ref() {
+            // Eye and target both stay at the origin until a script (or
+            // the agent) places the camera; that pose isn't worth
+            // restoring over the map's default view.
+            let eye = scene.camera_position;
+            let look_at = scene.camera_look_at;
+            let eye = [eye.x, eye.y, eye.z];
+            let look_at = [look_at.x, look_at.y, look_at.z];
+            if eye != look_at {
+                state.set_camera(Some(SavedCamera { eye, look_at }));
+            }
+            state.set_roles(scene.roles.iter().map(|role| role.state()).collect());
+        }
+
+        state
+    }
+
+    /// Rebuild the host-side state from a save: flags, player lock, the
+    /// leader, the saved map with its roles, and the camera. Run on a
+    /// [`fresh`](Self::fresh) context before the main script resumes.
+    pub fn restore(&mut self, state: &Swd5PersistentState) -> anyhow::Result<()> {
+        self.flags = state.flags().iter().copied().collect();
+        self.player_locked = state.player_locked();
+
+        if let Some(saved) = state.leader() {
+            let mut leader = Swd5Role::load(&self.asset_loader, saved.role_id)?;
+            leader.apply_state(saved);
+            self.leader = Some(leader);
+        }
+
+        if state.map_id() == 0 {
+            return Ok(());
+        }
+        self.switch_map(state.map_id(), None)?;
+
+        let Some(scene) = self.scene.as_mut() else {
+            return Ok(());
+        };
+        // `.fld` placements are fixed per map, so saved roles line up
+        // with the freshly loaded ones by position in the list.
+        for (role, saved) in scene.roles.iter_mut().zip(state.roles()) {
+            if role.role_id() == saved.role_id {
+                role.apply_state(saved);
+            }
+        }
+        if let Some(camera) = state.camera() {
+            let [x, y, z] = camera.look_at;
+            scene.set_camera_lookat(x, y, z);
+            let [x, y, z] = camera.eye;
+            scene.set_camera_pos(x, y, z);
+        }
+
+        Ok(())
+    }
+
     pub fn sleep(&mut self, sleep_sec: f32) {
         self.sleep_sec = sleep_sec;
         self.anykey_down = false;
@@ -150,22 +257,68 @@ impl SWD5Context {
         true
     }
 
+    /// Leader world position, or `None` before a `chang_role_map` has
+    /// spawned one. Surfaced to the agent snapshot's `leader_pos`.
+    pub fn leader_position(&self) -> Option<[f32; 3]> {
+        self.leader.as_ref().map(|leader| {
+            let p = leader.position();
+            [p.x, p.y, p.z]
+        })
+    }
+
+    /// Agent-driven teleport of the leader within the current map.
+    /// Returns `false` when there is no leader to move yet.
+    pub fn agent_teleport_leader(&mut self, pos: [f32; 3]) -> bool {
+        match self.leader.as_mut() {
+            Some(leader) => {
+                leader.set_position(Vec3::new(pos[0], pos[1], pos[2]));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Every role on the current map (leader first) for the agent's
+    /// scene-objects listing.
+    pub fn roles(&self) -> impl Iterator<Item = &Swd5Role> {
+        self.leader
+            .iter()
+            .chain(self.scene.iter().flat_map(|scene| scene.roles.iter()))
+    }
+
     /// Agent-driven map change. Reuses the exact `chang_map` path the
     /// Lua VM takes (load + scene-manager pop/push), but reports
     /// failure to the caller instead of only logging it.
-    ///
-    /// SWDHC has no role entities, so a map id is the only navigable
-    /// "position" the agent surface can offer; `/v1/player/teleport`
-    /// is routed here.
     pub fn agent_change_map(&mut self, map_id: i32) -> anyhow::Result<()> {
+        self.switch_map(map_id, None)
+    }
+
+    /// Load `map_id` and make it the active scene. The leader, if any,
+    /// moves into the new scene, at `leader_pos` when given.
+    fn switch_map(&mut self, map_id: i32, leader_pos: Option<Vec3>) -> anyhow::Result<()> {
         let scene = Swd5Scene::load(&self.asset_loader, map_id)?;
         self.scene_manager.pop_scene();
         self.scene_manager.push_scene(scene.scene.clone());
+
+        if let Some(leader) = self.leader.as_mut() {
+            scene.scene.add_entity(leader.entity().clone());
+            if let Some(pos) = leader_pos {
+                leader.set_position(pos);
+            }
+        }
+
         self.scene = Some(scene);
         self.current_map_id = map_id;
         Ok(())
     }
 
+    fn role_mut(&mut self, role_id: i32) -> Option<&mut Swd5Role> {
+        match self.leader.as_mut() {
+            Some(leader) if leader.role_id() == role_id => Some(leader),
+            _ => self.scene.as_mut()?.role_mut(role_id),
+        }
+    }
+
     /// Host functions `/v1/script/eval` is allowed to invoke. Every
     /// entry is a pure state mutation that is safe to run outside a VM
     /// tick; nothing here resumes or inspects the Lua coroutine.
@@ -247,6 +400,7 @@ impl SWD5Context {
 
         self.actdrop.update(self.ui.ui(), delta_sec);
 
+        self.update_leader(delta_sec);
         self.update_audio();
         self.update_story_pic();
         self.update_intro_pic();
@@ -255,6 +409,26 @@ impl SWD5Context {
         self.update_video();
     }
 
+    /// Turn directional input into leader movement, relative to the
+    /// camera. Input is ignored while the script holds the player
+    /// (`lock_player`) or a message box is up.
+    fn update_leader(&mut self, delta_sec: f32) {
+        if self.player_locked || self.story_msg.is_some() || self.talk_msg.is_some() {
+            return;
+        }
+        let (Some(leader), Some(scene)) = (self.leader.as_mut(), self.scene.as_ref()) else {
+            return;
+        };
+
+        let direction = get_moving_direction(self.input_engine.clone(), scene.scene.clone());
+        let direction = if direction.norm().is_finite() {
+            direction
+        } else {
+            Vec3::new_zeros()
+        };
+        leader.walk(&direction, LEADER_WALK_SPEED, delta_sec);
+    }
+
     fn update_storymsg(&mut self) {
         if self.anykey_down() {
             self.story_msg = None;
@@ -432,15 +606,21 @@ impl SWD5Context {
                 .pressed()
     }
 
-    fn isfon(&mut self, _f: f64) -> i32 {
-        0
+    fn isfon(&mut self, flag: f64) -> i32 {
+        self.flags.contains(&(flag as i32)) as i32
     }
 
-    fn fon(&mut self, _f: f64) {}
+    fn fon(&mut self, flag: f64) {
+        self.flags.insert(flag as i32);
+    }
 
-    fn foff(&mut self, _f: f64) {}
+    fn foff(&mut self, flag: f64) {
+        self.flags.remove(&(flag as i32));
+    }
 
-    fn lock_player(&mut self, _f: f64) {}
+    fn lock_player(&mut self, lock: f64) {
+        self.player_locked = lock != 0.;
+    }
 
     fn dark(&mut self, speed: f64) {
         self.actdrop
@@ -452,18 +632,11 @@ impl SWD5Context {
             .set_darkness(InterpValue::new(1., 0., 0.1 * speed as f32));
     }
 
-    fn chang_map(&mut self, map_id: f64, _x: f64, _y: f64, _z: f64) {
+    fn chang_map(&mut self, map_id: f64, x: f64, y: f64, z: f64) {
         let map_id = map_id as i32;
-        let scene = Swd5Scene::load(&self.asset_loader, map_id);
-        match scene {
-            Ok(scene) => {
-                self.scene_manager.pop_scene();
-                self.scene_manager.push_scene(scene.scene.clone());
-
-                self.scene = Some(scene);
-                self.current_map_id = map_id;
-            }
-            Err(e) => log::error!("chang_map {}: {:?}", map_id, e),
+        let pos = Vec3::new(x as f32, y as f32, z as f32);
+        if let Err(e) = self.switch_map(map_id, Some(pos)) {
+            log::error!("chang_map {}: {:?}", map_id, e);
         }
     }
 
@@ -486,11 +659,31 @@ impl SWD5Context {
         }
     }
 
-    fn chang_role_map(&mut self, _map_id: f64, _f2: f64, _f3: f64, _f4: f64) {}
+    /// Like `chang_map`, but the player enters the map: the leader is
+    /// spawned on first use and placed at `(x, y, z)`. Titles without a
+    /// known leader role just change maps.
+    fn chang_role_map(&mut self, map_id: f64, x: f64, y: f64, z: f64) {
+        if let (None, Some(role_id)) = (&self.leader, self.asset_loader.leader_role_id()) {
+            match Swd5Role::load(&self.asset_loader, role_id) {
+                Ok(leader) => self.leader = Some(leader),
+                Err(e) => log::error!("chang_role_map: leader role: {:?}", e),
+            }
+        }
+
+        self.chang_map(map_id, x, y, z);
+    }
 
-    fn set_motion(&mut self, _f1: f64, _f2: f64) {}
+    fn set_motion(&mut self, role: f64, motion: f64) {
+        if let Some(role) = self.role_mut(role as i32) {
+            role.set_motion(motion as usize);
+        }
+    }
 
-    fn set_walks(&mut self, _f1: f64, _f2: f64) {}
+    fn set_walks(&mut self, role: f64, motion: f64) {
+        if let Some(role) = self.role_mut(role as i32) {
+            role.set_walks(motion as usize);
+        }
+    }
 
     fn play_sound(&mut self, sound_id: f64, _volume: f64) {
         let sound_id = sound_id as i32;
@@ -591,7 +784,11 @@ impl SWD5Context {
         scene.set_camera_delta(dx as f32, dy as f32, dis as f32);
     }
 
-    fn set_role_face_motion(&mut self, _role: f64, _face_motion: f64) {}
+    fn set_role_face_motion(&mut self, role: f64, face_motion: f64) {
+        if let Some(role) = self.role_mut(role as i32) {
+            role.set_face_motion(face_motion as i32);
+        }
+    }
 
     fn play_movie(&mut self, id: f64) {
         let reader = self.asset_loader.load_movie_data(id as u32);
@@ -622,6 +819,11 @@ macro_rules! def_func {
     ($vm: ident, $fn_name: ident $(, [$state: ident])? $(, $param_names: ident : $param_types: ident)* $(-> $ret_type: ident)?) => {
         paste::paste! {
             extern "C" fn $fn_name(state: *mut lua_State) -> i32 {
+                // `anykey` is polled every frame; it would drown the trace.
+                if stringify!($fn_name) != "anykey" {
+                    trace_command(state, stringify!($fn_name));
+                }
+
                 unsafe {
                     let v = lua50_32_sys::lua_touserdata(state, lua50_32_sys::LUA_GLOBALSINDEX - 1);
 
@@ -666,10 +868,10 @@ pub fn create_lua_vm(
     let script = asset_loader.load_main_script()?;
     let vm = Lua5032Vm::new(script, "initiatelua", context)?;
 
-    def_func!(vm, isfon, f: number -> number);
-    def_func!(vm, fon, f: number);
-    def_func!(vm, foff, f: number);
-    def_func!(vm, lock_player, f: number);
+    def_func!(vm, isfon, flag: number -> number);
+    def_func!(vm, fon, flag: number);
+    def_func!(vm, foff, flag: number);
+    def_func!(vm, lock_player, lock: number);
     def_func!(vm, dark, speed: number);
     def_func!(vm, undark, speed: number);
     vm.register("sleep", Some(sleep));
@@ -678,9 +880,9 @@ pub fn create_lua_vm(
     def_func!(vm, camera_mode, f: number);
     def_func!(vm, story_music_off, f1: number, f2: number);
     def_func!(vm, story_music, music_id: number, f2: number, f3: number, f4: number, f5: number, f6: number);
-    def_func!(vm, chang_role_map, map_id: number, f2: number, f3: number, f4: number);
-    def_func!(vm, set_motion, f1: number, f2: number);
-    def_func!(vm, set_walks, f1: number, f2: number);
+    def_func!(vm, chang_role_map, map_id: number, x: number, y: number, z: number);
+    def_func!(vm, set_motion, role: number, motion: number);
+    def_func!(vm, set_walks, role: number, motion: number);
     def_func!(vm, play_sound, sound_id: number, volume: number);
     def_func!(vm, storymsg, text: string);
     def_func!(vm, storymsgpos, text: string, x: number, y: number);
@@ -0,0 +1,1090 @@
+//! Text form of PAL4 `.csb` modules.
+//!
+//! [`module_listing`] renders a [`ScriptModule`] as an editable
+//! listing and [`assemble_module`] turns such a listing back into a
+//! module. Listing an unmodified module and assembling it again
+//! writes the original bytes back out, so a modder can change a
+//! single function and leave the rest of the file untouched.
+//!
+//! ```text
+//! .header 00 00 00 00
+//! .typedef "Foo"
+//! .typeref "string"
+//! .named_global "LL_002" 0x3e
+//! .module_loading ""
+//!     .ret "" 0x00000000 0 0 0 0
+//!     ...
+//! .end
+//! .function "M01_Talk"                ; #3
+//!     .ret "void" 0x00000000 0 0 0 0
+//!     .param "int" 0x00000000 0 0 0 0
+//!     .unknown_dword1 0x00000000
+//!     .fn_type_ref "string" 0x00000004
+//!     .unknown_dword 0x00000000
+//!     .tail_type_ref ""
+//!     .dword 0x00000000
+//!     str 4                           ; 0008 "hello"
+//!     jz L_0016                       ; 000e
+//! L_0016:
+//!     ret 0                           ; 0016
+//! .end
+//! .string "hello"                     ; #4
+//! .aux_function "..."
+//! .end
+//! ```
+//!
+//! Mnemonics are the lower-cased [`AsInst`](super::AsInst) variant
+//! names. Jump operands may be a label or a raw byte offset relative
+//! to the end of the jump instruction; everything after `;` is a
+//! comment. Metadata the parser keeps but doesn't understand yet
+//! (`unknown_*`, data-type bytes) is spelled out so it survives the
+//! round trip. Strings are spelled as stored, so a NUL and whatever
+//! follows it, or bytes that aren't GBK (as `\u{f780}`..`\u{f7ff}`),
+//! show up even though the VM never sees them.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use anyhow::Context;
+
+use super::module::{
+    NamedGlobal, ScriptDataType, ScriptFunction, ScriptModule, ScriptTypeDefinition,
+    ScriptTypeReference, inst_length, listing_text, parse_listing_text,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operand {
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    F32,
+}
+
+impl Operand {
+    fn size(self) -> usize {
+        match self {
+            Operand::U16 | Operand::I16 => 2,
+            Operand::U32 | Operand::I32 | Operand::F32 => 4,
+            Operand::U64 => 8,
+        }
+    }
+}
+
+use Operand::*;
+
+/// Mnemonic and operand layout per opcode, indexed by opcode byte.
+/// Mirrors `disasm` and `INST_LENGTH`; `opcode_table_matches_*` keep
+/// the three in sync.
+const OPCODES: [(&str, &[Operand]); 123] = [
+    ("pop", &[U16]),
+    ("push", &[U16]),
+    ("set4", &[U32]),
+    ("rd4", &[]),
+    ("rdsf4", &[U16]),
+    ("wrt4", &[]),
+    ("mov4", &[]),
+    ("psf", &[U16]),
+    ("movsf4", &[U16]),
+    ("swap4", &[]),
+    ("store4", &[]),
+    ("recall4", &[]),
+    ("call", &[U32]),
+    ("ret", &[U16]),
+    ("jmp", &[I32]),
+    ("jz", &[I32]),
+    ("jnz", &[I32]),
+    ("tz", &[]),
+    ("tnz", &[]),
+    ("ts", &[]),
+    ("tns", &[]),
+    ("tp", &[]),
+    ("tnp", &[]),
+    ("addi", &[]),
+    ("subi", &[]),
+    ("muli", &[]),
+    ("divi", &[]),
+    ("modi", &[]),
+    ("negi", &[]),
+    ("cmpi", &[]),
+    ("inci", &[]),
+    ("deci", &[]),
+    ("i2f", &[]),
+    ("addf", &[]),
+    ("subf", &[]),
+    ("mulf", &[]),
+    ("divf", &[]),
+    ("modf", &[]),
+    ("negf", &[]),
+    ("cmpf", &[]),
+    ("incf", &[]),
+    ("decf", &[]),
+    ("f2i", &[]),
+    ("bnot", &[]),
+    ("band", &[]),
+    ("bor", &[]),
+    ("bxor", &[]),
+    ("bsll", &[]),
+    ("bsrl", &[]),
+    ("bsra", &[]),
+    ("ui2f", &[]),
+    ("f2ui", &[]),
+    ("cmpu", &[]),
+    ("sb", &[]),
+    ("sw", &[]),
+    ("ub", &[]),
+    ("uw", &[]),
+    ("wrt1", &[]),
+    ("wrt2", &[]),
+    ("inci16", &[]),
+    ("inci8", &[]),
+    ("deci16", &[]),
+    ("deci8", &[]),
+    ("pushzero", &[]),
+    ("copy", &[U16]),
+    ("pga", &[I32]),
+    ("set8", &[U64]),
+    ("wrt8", &[]),
+    ("rd8", &[]),
+    ("negd", &[]),
+    ("incd", &[]),
+    ("decd", &[]),
+    ("addd", &[]),
+    ("subd", &[]),
+    ("muld", &[]),
+    ("divd", &[]),
+    ("modd", &[]),
+    ("swapd", &[]),
+    ("cmpd", &[]),
+    ("d2i", &[]),
+    ("d2ui", &[]),
+    ("d2f", &[]),
+    ("i2d", &[]),
+    ("u2d", &[]),
+    ("f2d", &[]),
+    ("jmpp", &[]),
+    ("sret4", &[]),
+    ("sret8", &[]),
+    ("rret4", &[]),
+    ("rret8", &[]),
+    ("str", &[U16]),
+    ("js", &[I32]),
+    ("jns", &[I32]),
+    ("jp", &[I32]),
+    ("jnp", &[I32]),
+    ("cmpii", &[I32]),
+    ("cmpiui", &[U32]),
+    ("callsys", &[I32]),
+    ("callbnd", &[I32]),
+    ("rdga4", &[I32]),
+    ("movga4", &[I32]),
+    ("addii", &[I32]),
+    ("subii", &[I32]),
+    ("cmpif", &[F32]),
+    ("addif", &[F32]),
+    ("subif", &[F32]),
+    ("mulii", &[I32]),
+    ("mulif", &[F32]),
+    ("suspend", &[]),
+    ("alloc", &[I32, I32]),
+    ("free", &[U32]),
+    ("loadobj", &[I16]),
+    ("storeobj", &[I16]),
+    ("getobj", &[I16]),
+    ("refcpy", &[U32]),
+    ("checkref", &[]),
+    ("rd1", &[]),
+    ("rd2", &[]),
+    ("getobjref", &[I16]),
+    ("getref", &[I16]),
+    ("swap48", &[]),
+    ("swap84", &[]),
+    ("objtype", &[U32]),
+];
+
+/// Opcodes whose single `i32` operand is a pc-relative branch offset.
+const JUMP_OPCODES: [u8; 7] = [14, 15, 16, 91, 92, 93, 94];
+const CALL_OPCODE: u8 = 12;
+const STR_OPCODE: u8 = 90;
+
+/// Render a whole module as an assembler listing.
+pub fn module_listing(module: &ScriptModule) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "; PAL4 AngelScript module listing");
+    let _ = writeln!(out, ".header {}", hex_bytes(&module.header));
+    for type_def in &module.type_defs {
+        let _ = writeln!(
+            out,
+            ".typedef {}",
+            quoted(&type_def.name, &type_def.raw_name)
+        );
+    }
+    for type_ref in &module.type_refs {
+        let _ = writeln!(
+            out,
+            ".typeref {}",
+            quoted(&type_ref.name, &type_ref.raw_name)
+        );
+    }
+    for global in &module.named_globals {
+        let _ = writeln!(
+            out,
+            ".named_global {} {:#x}",
+            quoted(&global.name, &global.raw_name),
+            global.kind
+        );
+    }
+
+    write_function(
+        &mut out,
+        module,
+        ".module_loading",
+        &module.module_loading,
+        None,
+    );
+    write_function(
+        &mut out,
+        module,
+        ".module_unloading",
+        &module.module_unloading,
+        None,
+    );
+    for (i, function) in module.functions.iter().enumerate() {
+        write_function(&mut out, module, ".function", function, Some(i));
+    }
+    for (i, string) in module.strings.iter().enumerate() {
+        let raw = module.raw_strings.get(i).cloned();
+        let _ = writeln!(
+            out,
+            "{:<40} ; #{}",
+            format!(".string {}", quoted(string, &raw)),
+            i
+        );
+    }
+    for (i, function) in module.astruct_vec2.iter().enumerate() {
+        write_function(&mut out, module, ".aux_function", function, Some(i));
+    }
+    if !module.trailing.is_empty() {
+        let _ = writeln!(out, ".trailing {}", hex_bytes(&module.trailing));
+    }
+
+    out
+}
+
+/// Render one function as a `.function` block. `module` supplies the
+/// function and string tables used for `call` / `str` comments.
+pub fn function_listing(module: &ScriptModule, function: &ScriptFunction) -> String {
+    let index = module
+        .functions
+        .iter()
+        .position(|f| std::ptr::eq(f.as_ref(), function) || f.name == function.name);
+    let mut out = String::new();
+    write_function(&mut out, module, ".function", function, index);
+    out
+}
+
+/// Assemble a full module listing as produced by [`module_listing`].
+pub fn assemble_module(text: &str) -> anyhow::Result<ScriptModule> {
+    let mut module = ScriptModule {
+        header: [0; 4],
+        type_defs: vec![],
+        type_refs: vec![],
+        named_global_count: 0,
+        globals: vec![],
+        module_loading: empty_function(),
+        module_unloading: empty_function(),
+        functions: vec![],
+        strings: vec![],
+        raw_strings: vec![],
+        astruct_vec2: vec![],
+        named_globals: vec![],
+        trailing: vec![],
+    };
+
+    let mut lines = Lines::new(text);
+    while let Some((line_no, tokens)) = lines.next_tokens()? {
+        let context = || format!("line {}", line_no);
+        match tokens[0].as_str() {
+            ".header" => {
+                let bytes = parse_hex_bytes(&tokens[1..]).with_context(context)?;
+                module.header = bytes
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("line {}: .header takes 4 bytes", line_no))?;
+            }
+            ".typedef" => {
+                let (name, raw_name) = text_arg(&tokens, 1).with_context(context)?;
+                module.type_defs.push(ScriptTypeDefinition {
+                    name,
+                    raw_name: Some(raw_name),
+                })
+            }
+            ".typeref" => module
+                .type_refs
+                .push(type_ref_arg(&tokens, 1).with_context(context)?),
+            ".named_global" => {
+                let (name, raw_name) = text_arg(&tokens, 1).with_context(context)?;
+                let kind = integer_arg(&tokens, 2, 32, false).with_context(context)? as u32;
+                let index = module.named_globals.len() as u32;
+                module.named_globals.push(NamedGlobal {
+                    name,
+                    raw_name: Some(raw_name),
+                    kind,
+                    index,
+                });
+            }
+            ".string" => {
+                let (string, raw) = text_arg(&tokens, 1).with_context(context)?;
+                module.strings.push(string);
+                module.raw_strings.push(raw);
+            }
+            ".trailing" => module
+                .trailing
+                .extend(parse_hex_bytes(&tokens[1..]).with_context(context)?),
+            ".module_loading" => module.module_loading = parse_function(&tokens, &mut lines)?,
+            ".module_unloading" => module.module_unloading = parse_function(&tokens, &mut lines)?,
+            ".function" => module
+                .functions
+                .push(Arc::new(parse_function(&tokens, &mut lines)?)),
+            ".aux_function" => module
+                .astruct_vec2
+                .push(parse_function(&tokens, &mut lines)?),
+            other => anyhow::bail!("line {}: unexpected {:?}", line_no, other),
+        }
+    }
+
+    module.named_global_count = module.named_globals.len();
+    Ok(module)
+}
+
+/// Assemble a single function block (as produced by
+/// [`function_listing`]). Any of the block directives is accepted.
+pub fn assemble_function(text: &str) -> anyhow::Result<ScriptFunction> {
+    let mut lines = Lines::new(text);
+    let Some((line_no, tokens)) = lines.next_tokens()? else {
+        anyhow::bail!("empty listing");
+    };
+    match tokens[0].as_str() {
+        ".function" | ".module_loading" | ".module_unloading" | ".aux_function" => {}
+        other => anyhow::bail!("line {}: expected .function, got {:?}", line_no, other),
+    }
+
+    let function = parse_function(&tokens, &mut lines)?;
+    if let Some((line_no, _)) = lines.next_tokens()? {
+        anyhow::bail!("line {}: trailing content after .end", line_no);
+    }
+    Ok(function)
+}
+
+fn write_function(
+    out: &mut String,
+    module: &ScriptModule,
+    directive: &str,
+    function: &ScriptFunction,
+    index: Option<usize>,
+) {
+    let header = format!(
+        "{} {}",
+        directive,
+        quoted(&function.name, &function.raw_name)
+    );
+    match index {
+        Some(i) => {
+            let _ = writeln!(out, "{:<40} ; #{}", header, i);
+        }
+        None => {
+            let _ = writeln!(out, "{}", header);
+        }
+    }
+
+    let _ = writeln!(out, "    .ret {}", data_type(&function.ret_type));
+    for param in &function.param_types {
+        let _ = writeln!(out, "    .param {}", data_type(param));
+    }
+    let _ = writeln!(out, "    .unknown_dword1 {:#010x}", function.unknown_dword1);
+    for (type_ref, dword) in function.type_refs.iter().zip(&function.dword_with_type_ref) {
+        let _ = writeln!(
+            out,
+            "    .fn_type_ref {} {:#010x}",
+            quoted(&type_ref.name, &type_ref.raw_name),
+            dword
+        );
+    }
+    let _ = writeln!(out, "    .unknown_dword {:#010x}", function.unknown_dword);
+    let _ = writeln!(
+        out,
+        "    .tail_type_ref {}",
+        quoted(&function.type_ref.name, &function.type_ref.raw_name)
+    );
+    for dword in &function.dword_vec {
+        let _ = writeln!(out, "    .dword {:#010x}", dword);
+    }
+
+    write_code(out, module, &function.inst);
+    let _ = writeln!(out, ".end");
+}
+
+fn write_code(out: &mut String, module: &ScriptModule, inst: &[u8]) {
+    // Instruction boundaries first, so branch targets can be labelled.
+    let mut addrs = vec![];
+    let mut pc = 0;
+    while pc < inst.len() {
+        let Ok(len) = inst_length(inst[pc]) else {
+            break;
+        };
+        if pc + len > inst.len() {
+            break;
+        }
+        addrs.push(pc);
+        pc += len;
+    }
+
+    let jump_target = |addr: usize| -> Option<usize> {
+        let op = inst[addr];
+        if !JUMP_OPCODES.contains(&op) {
+            return None;
+        }
+        let offset = i32::from_le_bytes(inst[addr + 4..addr + 8].try_into().unwrap());
+        let target = (addr + 8) as i64 + offset as i64;
+        usize::try_from(target)
+            .ok()
+            .filter(|t| addrs.binary_search(t).is_ok() || *t == pc)
+    };
+    let mut labels: Vec<usize> = addrs.iter().filter_map(|a| jump_target(*a)).collect();
+    labels.sort_unstable();
+    labels.dedup();
+
+    for &addr in &addrs {
+        if labels.binary_search(&addr).is_ok() {
+            let _ = writeln!(out, "L_{:04x}:", addr);
+        }
+
+        let op = inst[addr];
+        let (mnemonic, operands) = OPCODES[op as usize];
+        let mut text = format!("    {}", mnemonic);
+        let mut comment = format!("{:04x}", addr);
+        if let Some(target) = jump_target(addr) {
+            let _ = write!(text, " L_{:04x}", target);
+        } else {
+            let mut at = addr + 4;
+            for operand in operands {
+                let bytes = &inst[at..at + operand.size()];
+                let _ = write!(text, " {}", format_operand(*operand, bytes));
+                at += operand.size();
+            }
+        }
+
+        if op == CALL_OPCODE {
+            let index = u32::from_le_bytes(inst[addr + 4..addr + 8].try_into().unwrap());
+            if let Some(f) = module.functions.get(index as usize) {
+                let _ = write!(comment, " -> {}", f.name);
+            }
+        } else if op == STR_OPCODE {
+            let index = u16::from_le_bytes(inst[addr + 4..addr + 6].try_into().unwrap());
+            if let Some(s) = module.strings.get(index as usize) {
+                let _ = write!(comment, " {:?}", s);
+            }
+        }
+
+        let _ = writeln!(out, "{:<40} ; {}", text, comment);
+    }
+
+    if labels.binary_search(&pc).is_ok() {
+        let _ = writeln!(out, "L_{:04x}:", pc);
+    }
+    // Anything the VM can't decode is kept as raw bytes so it still
+    // survives a round trip.
+    if pc < inst.len() {
+        let _ = writeln!(out, "    .bytes {}", hex_bytes(&inst[pc..]));
+    }
+}
+
+fn format_operand(operand: Operand, bytes: &[u8]) -> String {
+    match operand {
+        U16 => u16::from_le_bytes(bytes.try_into().unwrap()).to_string(),
+        I16 => i16::from_le_bytes(bytes.try_into().unwrap()).to_string(),
+        U32 => u32::from_le_bytes(bytes.try_into().unwrap()).to_string(),
+        I32 => i32::from_le_bytes(bytes.try_into().unwrap()).to_string(),
+        U64 => format!("{:#018x}", u64::from_le_bytes(bytes.try_into().unwrap())),
+        F32 => {
+            let bits = u32::from_le_bytes(bytes.try_into().unwrap());
+            let value = f32::from_bits(bits);
+            // `{:?}` round-trips every finite value; NaN payloads and
+            // infinities fall back to raw bits.
+            if value.is_finite() {
+                format!("{:?}", value)
+            } else {
+                format!("{:#010x}", bits)
+            }
+        }
+    }
+}
+
+fn data_type(data_type: &ScriptDataType) -> String {
+    format!(
+        "{} {:#010x} {} {} {} {}",
+        quoted(&data_type.type_ref.name, &data_type.type_ref.raw_name),
+        data_type.unknown,
+        data_type.unknown2,
+        data_type.unknown3,
+        data_type.unknown4,
+        data_type.unknown5
+    )
+}
+
+/// `{:?}` of a string's listing spelling; see [`listing_text`].
+fn quoted(text: &str, raw: &Option<Vec<u8>>) -> String {
+    format!("{:?}", listing_text(text, raw.as_deref()))
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn empty_data_type() -> ScriptDataType {
+    ScriptDataType {
+        flag: 0,
+        unknown: 0,
+        type_ref: ScriptTypeReference {
+            name: String::new(),
+            raw_name: None,
+        },
+        unknown2: 0,
+        unknown3: 0,
+        unknown4: 0,
+        unknown5: 0,
+    }
+}
+
+fn empty_function() -> ScriptFunction {
+    ScriptFunction {
+        name: String::new(),
+        raw_name: None,
+        ret_type: empty_data_type(),
+        param_types: vec![],
+        unknown_dword1: 0,
+        inst: vec![],
+        inst2: vec![],
+        type_refs: vec![],
+        dword_with_type_ref: vec![],
+        unknown_dword: 0,
+        type_ref: ScriptTypeReference {
+            name: String::new(),
+            raw_name: None,
+        },
+        dword_vec: vec![],
+    }
+}
+
+/// Parse a function block body; `header` holds the directive tokens
+/// (`.function "name"`). Consumes lines up to and including `.end`.
+fn parse_function(header: &[String], lines: &mut Lines) -> anyhow::Result<ScriptFunction> {
+    let header_line = lines.line_no;
+    let mut function = empty_function();
+    let (name, raw_name) = text_arg(header, 1).with_context(|| format!("line {}", header_line))?;
+    function.name = name;
+    function.raw_name = Some(raw_name);
+
+    enum Item {
+        Label(String),
+        Inst { op: u8, operands: Vec<String> },
+        Bytes(Vec<u8>),
+    }
+    let mut items = vec![];
+
+    loop {
+        let Some((line_no, tokens)) = lines.next_tokens()? else {
+            anyhow::bail!(
+                "line {}: function {:?} has no .end",
+                header_line,
+                function.name
+            );
+        };
+        let context = || format!("line {} (fn {:?})", line_no, function.name);
+        let head = tokens[0].as_str();
+        match head {
+            ".end" => break,
+            ".ret" => function.ret_type = parse_data_type(&tokens[1..]).with_context(context)?,
+            ".param" => function
+                .param_types
+                .push(parse_data_type(&tokens[1..]).with_context(context)?),
+            ".unknown_dword1" => {
+                function.unknown_dword1 =
+                    integer_arg(&tokens, 1, 32, false).with_context(context)? as u32
+            }
+            ".fn_type_ref" => {
+                function
+                    .type_refs
+                    .push(type_ref_arg(&tokens, 1).with_context(context)?);
+                function
+                    .dword_with_type_ref
+                    .push(integer_arg(&tokens, 2, 32, false).with_context(context)? as u32);
+            }
+            ".unknown_dword" => {
+                function.unknown_dword =
+                    integer_arg(&tokens, 1, 32, false).with_context(context)? as u32
+            }
+            ".tail_type_ref" => {
+                function.type_ref = type_ref_arg(&tokens, 1).with_context(context)?
+            }
+            ".dword" => function
+                .dword_vec
+                .push(integer_arg(&tokens, 1, 32, false).with_context(context)? as u32),
+            ".bytes" => items.push(Item::Bytes(
+                parse_hex_bytes(&tokens[1..]).with_context(context)?,
+            )),
+            label if label.ends_with(':') && tokens.len() == 1 => {
+                items.push(Item::Label(label.trim_end_matches(':').to_string()))
+            }
+            mnemonic => {
+                let Some(op) = OPCODES.iter().position(|(m, _)| *m == mnemonic) else {
+                    anyhow::bail!("{}: unknown mnemonic {:?}", context(), mnemonic);
+                };
+                let operands = &OPCODES[op].1;
+                if tokens.len() - 1 != operands.len() {
+                    anyhow::bail!(
+                        "{}: {} takes {} operand(s), got {}",
+                        context(),
+                        mnemonic,
+                        operands.len(),
+                        tokens.len() - 1
+                    );
+                }
+                items.push(Item::Inst {
+                    op: op as u8,
+                    operands: tokens[1..].to_vec(),
+                });
+            }
+        }
+    }
+
+    // Pass 1: addresses and labels.
+    let mut labels = HashMap::new();
+    let mut pc = 0;
+    for item in &items {
+        match item {
+            Item::Label(name) => {
+                if labels.insert(name.clone(), pc).is_some() {
+                    anyhow::bail!("fn {:?}: duplicate label {}", function.name, name);
+                }
+            }
+            Item::Inst { op, .. } => pc += inst_length(*op)?,
+            Item::Bytes(bytes) => pc += bytes.len(),
+        }
+    }
+
+    // Pass 2: encode.
+    let mut inst = vec![];
+    for item in &items {
+        match item {
+            Item::Label(_) => {}
+            Item::Bytes(bytes) => inst.extend_from_slice(bytes),
+            Item::Inst { op, operands } => {
+                let addr = inst.len();
+                let end = addr + inst_length(*op)?;
+                inst.extend_from_slice(&[*op, 0, 0, 0]);
+                for (kind, token) in OPCODES[*op as usize].1.iter().zip(operands) {
+                    let is_jump = JUMP_OPCODES.contains(op);
+                    match labels.get(token.as_str()) {
+                        Some(target) if is_jump => {
+                            let offset = *target as i64 - end as i64;
+                            inst.extend_from_slice(&(offset as i32).to_le_bytes());
+                        }
+                        _ => encode_operand(&mut inst, *kind, token).with_context(|| {
+                            format!("fn {:?} at {:04x}: {}", function.name, addr, token)
+                        })?,
+                    }
+                }
+            }
+        }
+    }
+
+    function.inst2 = ScriptFunction::decode_instructions(&inst)
+        .with_context(|| format!("fn {:?}", function.name))?;
+    function.inst = inst;
+    Ok(function)
+}
+
+fn encode_operand(out: &mut Vec<u8>, operand: Operand, token: &str) -> anyhow::Result<()> {
+    match operand {
+        U16 => out.extend_from_slice(&(parse_integer(token, 16, false)? as u16).to_le_bytes()),
+        I16 => out.extend_from_slice(&(parse_integer(token, 16, true)? as u16).to_le_bytes()),
+        U32 => out.extend_from_slice(&(parse_integer(token, 32, false)? as u32).to_le_bytes()),
+        I32 => out.extend_from_slice(&(parse_integer(token, 32, true)? as u32).to_le_bytes()),
+        U64 => out.extend_from_slice(&parse_integer(token, 64, false)?.to_le_bytes()),
+        F32 => {
+            let bits = if token.starts_with("0x") {
+                parse_integer(token, 32, false)? as u32
+            } else {
+                token
+                    .parse::<f32>()
+                    .with_context(|| format!("invalid float {:?}", token))?
+                    .to_bits()
+            };
+            out.extend_from_slice(&bits.to_le_bytes());
+        }
+    }
+    Ok(())
+}
+
+/// Parse a decimal or `0x` hex integer that must fit `bits` bits.
+/// Hex literals are taken as raw bits, so `0xffffffff` is a valid
+/// `i32` operand (-1).
+fn parse_integer(token: &str, bits: u32, signed: bool) -> anyhow::Result<u64> {
+    let (negative, digits) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let magnitude = match digits.strip_prefix("0x") {
+        Some(hex) => i128::from_str_radix(hex, 16),
+        None => digits.parse::<i128>(),
+    }
+    .with_context(|| format!("invalid integer {:?}", token))?;
+    let value = if negative { -magnitude } else { magnitude };
+
+    let unsigned_max = (1i128 << bits) - 1;
+    let signed_min = -(1i128 << (bits - 1));
+    let in_range = if negative {
+        signed && value >= signed_min
+    } else {
+        value <= unsigned_max
+    };
+    if !in_range {
+        anyhow::bail!("{} does not fit a {}-bit operand", token, bits);
+    }
+
+    Ok((value & unsigned_max) as u64)
+}
+
+fn integer_arg(tokens: &[String], index: usize, bits: u32, signed: bool) -> anyhow::Result<u64> {
+    let token = tokens
+        .get(index)
+        .with_context(|| format!("{} is missing operand {}", tokens[0], index))?;
+    parse_integer(token, bits, signed)
+}
+
+fn string_arg(tokens: &[String], index: usize) -> anyhow::Result<String> {
+    let token = tokens
+        .get(index)
+        .with_context(|| format!("{} is missing operand {}", tokens[0], index))?;
+    unquote(token)
+}
+
+/// A listing string argument as runtime text plus stored bytes; see
+/// [`parse_listing_text`].
+fn text_arg(tokens: &[String], index: usize) -> anyhow::Result<(String, Vec<u8>)> {
+    parse_listing_text(&string_arg(tokens, index)?)
+}
+
+fn type_ref_arg(tokens: &[String], index: usize) -> anyhow::Result<ScriptTypeReference> {
+    let (name, raw_name) = text_arg(tokens, index)?;
+    Ok(ScriptTypeReference {
+        name,
+        raw_name: Some(raw_name),
+    })
+}
+
+fn parse_data_type(tokens: &[String]) -> anyhow::Result<ScriptDataType> {
+    if tokens.is_empty() {
+        anyhow::bail!("data type needs a type name");
+    }
+    let number = |i: usize, bits: u32| -> anyhow::Result<u64> {
+        tokens
+            .get(i)
+            .map(|t| parse_integer(t, bits, false))
+            .unwrap_or(Ok(0))
+    };
+    Ok(ScriptDataType {
+        flag: 0,
+        unknown: number(1, 32)? as u32,
+        type_ref: type_ref_arg(tokens, 0)?,
+        unknown2: number(2, 8)? as u8,
+        unknown3: number(3, 8)? as u8,
+        unknown4: number(4, 8)? as u8,
+        unknown5: number(5, 8)? as u8,
+    })
+}
+
+fn parse_hex_bytes(tokens: &[String]) -> anyhow::Result<Vec<u8>> {
+    tokens
+        .iter()
+        .map(|t| u8::from_str_radix(t, 16).with_context(|| format!("invalid byte {:?}", t)))
+        .collect()
+}
+
+/// Reverse of `{:?}` formatting for the string literals in a listing.
+fn unquote(token: &str) -> anyhow::Result<String> {
+    let Some(body) = token
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    else {
+        anyhow::bail!("expected a quoted string, got {}", token);
+    };
+
+    let mut out = String::new();
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('0') => out.push('\0'),
+            Some(c @ ('\\' | '"' | '\'')) => out.push(c),
+            Some('u') => {
+                let hex: String = chars
+                    .by_ref()
+                    .skip_while(|c| *c == '{')
+                    .take_while(|c| *c != '}')
+                    .collect();
+                let code = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .with_context(|| format!("invalid escape \\u{{{}}}", hex))?;
+                out.push(code);
+            }
+            other => anyhow::bail!("invalid escape \\{:?} in {}", other, token),
+        }
+    }
+    Ok(out)
+}
+
+/// Line reader that splits each non-empty line into tokens. Quoted
+/// strings stay one token (quotes included); `;` starts a comment.
+struct Lines<'a> {
+    lines: std::str::Lines<'a>,
+    line_no: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            lines: text.lines(),
+            line_no: 0,
+        }
+    }
+
+    fn next_tokens(&mut self) -> anyhow::Result<Option<(usize, Vec<String>)>> {
+        for line in self.lines.by_ref() {
+            self.line_no += 1;
+            let tokens = tokenize(line).with_context(|| format!("line {}", self.line_no))?;
+            if !tokens.is_empty() {
+                return Ok(Some((self.line_no, tokens)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn tokenize(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut tokens = vec![];
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c == ';' {
+            break;
+        } else if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            let mut token = String::from(chars.next().unwrap());
+            let mut escaped = false;
+            let mut closed = false;
+            for c in chars.by_ref() {
+                token.push(c);
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    closed = true;
+                    break;
+                }
+            }
+            if !closed {
+                anyhow::bail!("unterminated string");
+            }
+            tokens.push(token);
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == ';' {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(code: u8, operands: &[u8]) -> Vec<u8> {
+        let mut v = vec![code, 0, 0, 0];
+        v.extend_from_slice(operands);
+        v
+    }
+
+    fn sample_module() -> ScriptModule {
+        let mut inst = vec![];
+        inst.extend(op(90, &0u16.to_le_bytes())); // str 0
+        inst.extend(op(15, &8i32.to_le_bytes())); // jz +8 -> ret
+        inst.extend(op(103, &1.5f32.to_le_bytes())); // cmpif 1.5
+        inst.extend(op(13, &0u16.to_le_bytes())); // ret 0
+        inst.extend(op(14, &(-36i32).to_le_bytes())); // jmp back to str
+        let mut function = ScriptFunction::test_function("Talk", inst);
+        function.inst2 = ScriptFunction::decode_instructions(&function.inst).unwrap();
+        function.type_refs.push(ScriptTypeReference {
+            name: "string".to_string(),
+            raw_name: None,
+        });
+        function.dword_with_type_ref.push(4);
+        function.dword_vec.push(7);
+
+        let mut module = ScriptModule::test_module(vec![function]);
+        module.header = [1, 2, 3, 4];
+        module.strings = vec!["line \"one\"\n".to_string(), "云天河".to_string()];
+        module.named_globals.push(NamedGlobal {
+            name: "LL_002".to_string(),
+            raw_name: None,
+            kind: 0x3e,
+            index: 0,
+        });
+        module.named_global_count = 1;
+        module
+    }
+
+    #[test]
+    fn opcode_table_matches_instruction_lengths() {
+        for (op, (name, operands)) in OPCODES.iter().enumerate() {
+            let len = 4 + operands.iter().map(|o| o.size()).sum::<usize>();
+            assert_eq!(inst_length(op as u8).unwrap(), len, "{}", name);
+        }
+        assert!(inst_length(OPCODES.len() as u8).is_err());
+    }
+
+    #[test]
+    fn opcode_table_matches_disassembler() {
+        for (op, (name, operands)) in OPCODES.iter().enumerate() {
+            let len = 4 + operands.iter().map(|o| o.size()).sum::<usize>();
+            let mut inst = vec![0; len];
+            inst[0] = op as u8;
+            let function = ScriptFunction::test_function("", inst);
+            let decoded = format!("{:?}", super::super::disasm(&function)[0].inst);
+
+            let variant = decoded.split([' ', '{']).next().unwrap();
+            assert_eq!(variant.to_ascii_lowercase(), *name);
+            assert_eq!(decoded.matches(':').count(), operands.len(), "{}", decoded);
+        }
+    }
+
+    #[test]
+    fn write_then_read_is_byte_identical() {
+        let bytes = sample_module().write_to_vec().unwrap();
+        let reread = ScriptModule::read_from_buffer(&bytes).unwrap();
+        assert_eq!(reread.header, [1, 2, 3, 4]);
+        assert_eq!(reread.strings[1], "云天河");
+        assert_eq!(reread.write_to_vec().unwrap(), bytes);
+    }
+
+    #[test]
+    fn listing_assembles_back_to_the_same_bytes() {
+        let module = sample_module();
+        let listing = module_listing(&module);
+        assert!(listing.contains("jz L_"), "{}", listing);
+        assert!(listing.contains("cmpif 1.5"), "{}", listing);
+
+        let assembled = assemble_module(&listing).unwrap();
+        assert_eq!(
+            assembled.write_to_vec().unwrap(),
+            module.write_to_vec().unwrap()
+        );
+    }
+
+    #[test]
+    fn listing_keeps_bytes_past_a_nul() {
+        let mut module = sample_module();
+        module.strings[1] = "abc".to_string();
+        module.raw_strings = vec![vec![], b"abc\0\x80xyz".to_vec()];
+        let bytes = module.write_to_vec().unwrap();
+        let reread = ScriptModule::read_from_buffer(&bytes).unwrap();
+        assert_eq!(reread.strings[1], "abc");
+
+        let assembled = assemble_module(&module_listing(&reread)).unwrap();
+        assert_eq!(assembled.strings[1], "abc");
+        assert_eq!(assembled.write_to_vec().unwrap(), bytes);
+    }
+
+    #[test]
+    fn edited_function_relinks_labels() {
+        let module = sample_module();
+        let listing = function_listing(&module, &module.functions[0]);
+        // Insert an instruction between the branch and its target.
+        let edited = listing.replacen("    cmpif 1.5", "    pushzero\n    cmpif 1.5", 1);
+        let function = assemble_function(&edited).unwrap();
+
+        assert_eq!(function.inst.len(), module.functions[0].inst.len() + 4);
+        // jz now skips 12 bytes (pushzero + cmpif) instead of 8.
+        assert_eq!(&function.inst[10..14], &12i32.to_le_bytes());
+        assert_eq!(function.inst2.len(), 6);
+    }
+
+    #[test]
+    fn parse_integer_accepts_hex_bits_for_signed_operands() {
+        assert_eq!(parse_integer("0xffffffff", 32, true).unwrap(), 0xffff_ffff);
+        assert_eq!(parse_integer("-1", 16, true).unwrap(), 0xffff);
+        assert!(parse_integer("-1", 16, false).is_err());
+        assert!(parse_integer("65536", 16, false).is_err());
+    }
+
+    /// Every shipped module must survive write and listing round
+    /// trips byte for byte. Same `PAL4_ROOT` convention as
+    /// `module::tests::parses_every_pal4_csb_module`.
+    #[test]
+    #[ignore = "requires PAL4_ROOT env var pointing at a PAL4 install"]
+    fn every_pal4_csb_module_round_trips() {
+        let Ok(root) = std::env::var("PAL4_ROOT") else {
+            eprintln!("PAL4_ROOT not set; skipping every_pal4_csb_module_round_trips");
+            return;
+        };
+        let script_dir = std::path::PathBuf::from(root)
+            .join("gamedata")
+            .join("script");
+        let mut total = 0;
+        for entry in std::fs::read_dir(&script_dir).unwrap() {
+            let path = entry.unwrap().path();
+            if !path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("csb"))
+            {
+                continue;
+            }
+            let bytes = std::fs::read(&path).unwrap();
+            let module = ScriptModule::read_from_buffer(&bytes).unwrap();
+            assert!(
+                module.write_to_vec().unwrap() == bytes,
+                "{}: write is not byte-identical",
+                path.display()
+            );
+            let assembled = assemble_module(&module_listing(&module)).unwrap();
+            assert!(
+                assembled.write_to_vec().unwrap() == bytes,
+                "{}: listing round trip is not byte-identical",
+                path.display()
+            );
+            total += 1;
+        }
+        assert!(total > 0, "no .csb modules under {}", script_dir.display());
+    }
+}
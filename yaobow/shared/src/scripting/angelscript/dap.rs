@@ -0,0 +1,1129 @@
+//! Debug Adapter Protocol server for [`ScriptVm`].
+//!
+//! [`ScriptDebugger::listen`] binds a TCP port and speaks DAP
+//! (`Content-Length`-framed JSON) to one editor at a time. Socket I/O
+//! lives on two background threads; everything that touches the VM
+//! happens on the game thread, in [`ScriptVm::poll_debugger`] and in
+//! the per-instruction check inside [`ScriptVm::execute`].
+//!
+//! The VM exposes a single thread. Each frame's source is the
+//! function's disassembly with one instruction per line, served via
+//! `source` requests (`sourceReference`), so line breakpoints and
+//! stepping are instruction-granular. Instruction addresses are
+//! `sourceReference << 20 | pc`, which is what the `disassemble`
+//! and `setInstructionBreakpoints` requests use as memory references.
+//!
+//! While the debugger holds the VM stopped, `execute` returns
+//! immediately and the PAL4 director skips its update, so the game
+//! world freezes along with the script.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap, HashSet},
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+use serde_json::{Value, json};
+
+use super::{
+    disassembler::{AsInstInstance, disasm},
+    module::ScriptModule,
+    vm::ScriptVm,
+};
+
+const THREAD_ID: i64 = 1;
+const ADDRESS_SHIFT: u32 = 20;
+const ADDRESS_PC_MASK: u64 = (1 << ADDRESS_SHIFT) - 1;
+
+const SCOPE_FRAME: i64 = 1;
+const SCOPE_REGISTERS: i64 = 2;
+const SCOPE_SHARED_GLOBALS: i64 = 3;
+const SCOPE_MODULE_GLOBALS: i64 = 4;
+const SCOPE_OBJECTS: i64 = 5;
+
+/// Upper bound on the `fp`-relative slots listed in the frame scope.
+const MAX_FRAME_SLOTS: usize = 256;
+
+pub(crate) enum ClientEvent {
+    Connected(Sender<Value>),
+    Message(Value),
+    Disconnected,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RunMode {
+    Running,
+    Stopped,
+    PauseRequested,
+    StepIn,
+    StepOver { depth: usize },
+    StepOut { depth: usize },
+}
+
+pub struct ScriptDebugger {
+    local_addr: Option<SocketAddr>,
+    events: Receiver<ClientEvent>,
+    client: Option<Sender<Value>>,
+    seq: i64,
+    mode: RunMode,
+
+    /// Set when resuming from a stop inside a frame, so the first
+    /// instruction check doesn't immediately re-hit the breakpoint
+    /// the VM is sitting on.
+    resumed: bool,
+
+    function_breakpoints: HashSet<String>,
+    source_breakpoints: HashMap<String, BTreeSet<usize>>,
+    instruction_breakpoints: HashMap<String, BTreeSet<usize>>,
+
+    /// `sourceReference - 1` → (module, function index). References
+    /// are handed out on first sight and stay valid for the session,
+    /// so a scene change doesn't make a cached listing show the wrong
+    /// function.
+    sources: Vec<(Rc<RefCell<ScriptModule>>, usize)>,
+}
+
+impl ScriptDebugger {
+    /// Bind `addr` and start accepting editor connections in the
+    /// background. Pass the result to [`ScriptVm::set_debugger`].
+    pub fn listen(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let (tx, rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("as-dap-listener".to_string())
+            .spawn(move || accept_loop(listener, tx))?;
+
+        let mut debugger = Self::new(rx);
+        debugger.local_addr = Some(local_addr);
+        Ok(debugger)
+    }
+
+    pub(crate) fn new(events: Receiver<ClientEvent>) -> Self {
+        Self {
+            local_addr: None,
+            events,
+            client: None,
+            seq: 0,
+            mode: RunMode::Running,
+            resumed: false,
+            function_breakpoints: HashSet::new(),
+            source_breakpoints: HashMap::new(),
+            instruction_breakpoints: HashMap::new(),
+            sources: vec![],
+        }
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// True while an editor holds the VM stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.mode == RunMode::Stopped
+    }
+
+    /// Drain pending editor requests. Called once per frame.
+    pub(crate) fn poll<T: 'static>(&mut self, vm: &ScriptVm<T>) {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                ClientEvent::Connected(client) => {
+                    log::info!("script debugger: client attached");
+                    self.client = Some(client);
+                    self.seq = 0;
+                }
+                ClientEvent::Message(message) => self.handle_message(vm, &message),
+                ClientEvent::Disconnected => {
+                    log::info!("script debugger: client detached");
+                    self.detach();
+                }
+            }
+        }
+
+        // A pause while no script is running stops at once: there is no
+        // instruction to stop on, but the director still freezes.
+        if self.mode == RunMode::PauseRequested && vm.is_idle() {
+            self.stop("pause", None);
+        }
+    }
+
+    /// Per-instruction check. Returns `true` when the VM must stop
+    /// before executing the instruction at the current `pc`.
+    pub(crate) fn should_break<T: 'static>(&mut self, vm: &ScriptVm<T>) -> bool {
+        if std::mem::take(&mut self.resumed) {
+            return false;
+        }
+
+        let depth = vm.call_stack.len();
+        let step = match self.mode {
+            RunMode::PauseRequested => Some("pause"),
+            RunMode::StepIn => Some("step"),
+            RunMode::StepOver { depth: d } if depth <= d => Some("step"),
+            RunMode::StepOut { depth: d } if depth < d => Some("step"),
+            _ => None,
+        };
+
+        let reason = match step {
+            Some(reason) => Some(reason),
+            None => self.breakpoint_hit(vm),
+        };
+
+        match reason {
+            Some(reason) => {
+                let description = vm.context.as_ref().map(|ctx| {
+                    format!(
+                        "{} @ {:#06x}",
+                        function_name(&ctx.module, ctx.function_index),
+                        ctx.pc
+                    )
+                });
+                self.stop(reason, description);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Called when the VM runs out of script. Pending steps don't
+    /// carry over into whichever script the director starts next.
+    pub(crate) fn script_finished(&mut self) {
+        if matches!(
+            self.mode,
+            RunMode::StepIn | RunMode::StepOver { .. } | RunMode::StepOut { .. }
+        ) {
+            self.mode = RunMode::Running;
+        }
+        self.resumed = false;
+    }
+
+    fn breakpoint_hit<T: 'static>(&self, vm: &ScriptVm<T>) -> Option<&'static str> {
+        if self.function_breakpoints.is_empty()
+            && self.source_breakpoints.is_empty()
+            && self.instruction_breakpoints.is_empty()
+        {
+            return None;
+        }
+
+        let ctx = vm.context.as_ref()?;
+        let module = ctx.module.borrow();
+        let name = &module.functions.get(ctx.function_index)?.name;
+        if ctx.pc == 0 && self.function_breakpoints.contains(name) {
+            Some("function breakpoint")
+        } else if self
+            .source_breakpoints
+            .get(name)
+            .is_some_and(|pcs| pcs.contains(&ctx.pc))
+        {
+            Some("breakpoint")
+        } else if self
+            .instruction_breakpoints
+            .get(name)
+            .is_some_and(|pcs| pcs.contains(&ctx.pc))
+        {
+            Some("instruction breakpoint")
+        } else {
+            None
+        }
+    }
+
+    fn stop(&mut self, reason: &str, description: Option<String>) {
+        self.mode = RunMode::Stopped;
+        let mut body = json!({
+            "reason": reason,
+            "threadId": THREAD_ID,
+            "allThreadsStopped": true,
+        });
+        if let Some(description) = description {
+            body["description"] = json!(description);
+        }
+        self.send_event("stopped", body);
+    }
+
+    fn resume<T: 'static>(&mut self, vm: &ScriptVm<T>, mode: RunMode) {
+        if self.mode == RunMode::Stopped {
+            self.resumed = vm.context.is_some();
+        }
+        self.mode = mode;
+    }
+
+    fn detach(&mut self) {
+        self.client = None;
+        self.function_breakpoints.clear();
+        self.source_breakpoints.clear();
+        self.instruction_breakpoints.clear();
+        if self.mode != RunMode::Running {
+            self.resumed = self.mode == RunMode::Stopped;
+            self.mode = RunMode::Running;
+        }
+    }
+
+    fn handle_message<T: 'static>(&mut self, vm: &ScriptVm<T>, message: &Value) {
+        if message["type"] != "request" {
+            return;
+        }
+
+        let command = message["command"].as_str().unwrap_or_default();
+        let args = &message["arguments"];
+        let result = match command {
+            "initialize" => Ok(capabilities()),
+            "launch" | "attach" | "configurationDone" | "setExceptionBreakpoints" => Ok(json!({})),
+            "threads" => Ok(json!({
+                "threads": [{ "id": THREAD_ID, "name": "AngelScript VM" }]
+            })),
+            "setBreakpoints" => self.set_breakpoints(vm, args),
+            "setFunctionBreakpoints" => Ok(self.set_function_breakpoints(args)),
+            "setInstructionBreakpoints" => Ok(self.set_instruction_breakpoints(args)),
+            "continue" => {
+                self.resume(vm, RunMode::Running);
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "next" => {
+                self.resume(
+                    vm,
+                    RunMode::StepOver {
+                        depth: vm.call_stack.len(),
+                    },
+                );
+                Ok(json!({}))
+            }
+            "stepIn" => {
+                self.resume(vm, RunMode::StepIn);
+                Ok(json!({}))
+            }
+            "stepOut" => {
+                self.resume(
+                    vm,
+                    RunMode::StepOut {
+                        depth: vm.call_stack.len(),
+                    },
+                );
+                Ok(json!({}))
+            }
+            "pause" => {
+                if self.mode != RunMode::Stopped {
+                    self.mode = RunMode::PauseRequested;
+                }
+                Ok(json!({}))
+            }
+            "stackTrace" => Ok(self.stack_trace(vm)),
+            "scopes" => Ok(scopes(args["frameId"].as_i64().unwrap_or(0))),
+            "variables" => variables(vm, args["variablesReference"].as_i64().unwrap_or(0)),
+            "source" => self.source(args),
+            "disassemble" => self.disassemble(args),
+            "disconnect" => {
+                self.detach();
+                Ok(json!({}))
+            }
+            _ => Err(format!("unsupported request '{}'", command)),
+        };
+
+        self.respond(message, command, result);
+
+        if command == "initialize" {
+            self.send_event("initialized", json!({}));
+        }
+    }
+
+    fn set_breakpoints<T: 'static>(
+        &mut self,
+        vm: &ScriptVm<T>,
+        args: &Value,
+    ) -> Result<Value, String> {
+        let source = &args["source"];
+        let (module, function_index) = match source["sourceReference"].as_i64() {
+            Some(reference) if reference > 0 => self
+                .source_by_reference(reference)
+                .ok_or_else(|| format!("unknown sourceReference {}", reference))?,
+            _ => {
+                // A source without a live reference (e.g. persisted
+                // from a previous session) is matched by name against
+                // the module that's running now.
+                let name = source["name"].as_str().unwrap_or_default();
+                let name = name.strip_suffix(".asm").unwrap_or(name);
+                let module = vm
+                    .context
+                    .as_ref()
+                    .map(|ctx| ctx.module.clone())
+                    .ok_or_else(|| format!("no script module loaded for '{}'", name))?;
+                let index = module
+                    .borrow()
+                    .functions
+                    .iter()
+                    .position(|f| f.name == name)
+                    .ok_or_else(|| format!("no function '{}' in the running module", name))?;
+                (module, index)
+            }
+        };
+
+        let name = function_name(&module, function_index);
+        let insts = disasm(&module.borrow().functions[function_index]);
+        let mut pcs = BTreeSet::new();
+        let mut breakpoints = vec![];
+        for bp in args["breakpoints"].as_array().into_iter().flatten() {
+            let line = bp["line"].as_i64().unwrap_or(0);
+            match usize::try_from(line - 1).ok().and_then(|i| insts.get(i)) {
+                Some(inst) => {
+                    pcs.insert(inst.addr as usize);
+                    breakpoints.push(json!({ "verified": true, "line": line }));
+                }
+                None => breakpoints.push(json!({
+                    "verified": false,
+                    "line": line,
+                    "message": "line is past the end of the function",
+                })),
+            }
+        }
+
+        if pcs.is_empty() {
+            self.source_breakpoints.remove(&name);
+        } else {
+            self.source_breakpoints.insert(name, pcs);
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn set_function_breakpoints(&mut self, args: &Value) -> Value {
+        self.function_breakpoints.clear();
+        let mut breakpoints = vec![];
+        for bp in args["breakpoints"].as_array().into_iter().flatten() {
+            if let Some(name) = bp["name"].as_str() {
+                self.function_breakpoints.insert(name.to_string());
+            }
+            // Verified up front: the function may live in a scene
+            // module that hasn't been loaded yet.
+            breakpoints.push(json!({ "verified": true }));
+        }
+        json!({ "breakpoints": breakpoints })
+    }
+
+    fn set_instruction_breakpoints(&mut self, args: &Value) -> Value {
+        self.instruction_breakpoints.clear();
+        let mut breakpoints = vec![];
+        for bp in args["breakpoints"].as_array().into_iter().flatten() {
+            let address = bp["instructionReference"]
+                .as_str()
+                .and_then(parse_address)
+                .map(|a| a.wrapping_add_signed(bp["offset"].as_i64().unwrap_or(0)));
+            let resolved = address.and_then(|address| {
+                let (module, index, pc) = self.resolve_address(address)?;
+                let insts = disasm(&module.borrow().functions[index]);
+                insts
+                    .iter()
+                    .any(|i| i.addr as usize == pc)
+                    .then(|| (function_name(&module, index), pc))
+            });
+            match resolved {
+                Some((name, pc)) => {
+                    self.instruction_breakpoints
+                        .entry(name)
+                        .or_default()
+                        .insert(pc);
+                    breakpoints.push(json!({
+                        "verified": true,
+                        "instructionReference": format_address(address.unwrap()),
+                    }));
+                }
+                None => breakpoints.push(json!({
+                    "verified": false,
+                    "message": "not an instruction address",
+                })),
+            }
+        }
+        json!({ "breakpoints": breakpoints })
+    }
+
+    fn stack_trace<T: 'static>(&mut self, vm: &ScriptVm<T>) -> Value {
+        let frames = vm
+            .context
+            .iter()
+            .chain(vm.call_stack.iter().rev())
+            .enumerate()
+            .map(|(id, ctx)| (id, ctx.module.clone(), ctx.function_index, ctx.pc))
+            .collect::<Vec<_>>();
+
+        let mut stack_frames = vec![];
+        for (id, module, function_index, pc) in frames {
+            let reference = self.source_reference(&module, function_index);
+            let insts = disasm(&module.borrow().functions[function_index]);
+            // Parent frames are parked just past their `call`; point
+            // them at the call itself.
+            let line = line_for_pc(&insts, pc, id > 0);
+            let name = function_name(&module, function_index);
+            stack_frames.push(json!({
+                "id": id,
+                "name": name,
+                "source": source_json(&name, reference),
+                "line": line,
+                "column": 1,
+                "instructionPointerReference": format_address(make_address(
+                    reference,
+                    insts.get(line.saturating_sub(1)).map(|i| i.addr as usize).unwrap_or(pc),
+                )),
+            }));
+        }
+
+        let total = stack_frames.len();
+        json!({ "stackFrames": stack_frames, "totalFrames": total })
+    }
+
+    fn source(&self, args: &Value) -> Result<Value, String> {
+        let reference = args["sourceReference"]
+            .as_i64()
+            .or_else(|| args["source"]["sourceReference"].as_i64())
+            .unwrap_or(0);
+        let (module, index) = self
+            .source_by_reference(reference)
+            .ok_or_else(|| format!("unknown sourceReference {}", reference))?;
+        let insts = disasm(&module.borrow().functions[index]);
+        let content = insts
+            .iter()
+            .map(|i| format!("{:04x}  {:?}\n", i.addr, i.inst))
+            .collect::<String>();
+        Ok(json!({ "content": content, "mimeType": "text/x-asm" }))
+    }
+
+    fn disassemble(&self, args: &Value) -> Result<Value, String> {
+        let base = args["memoryReference"]
+            .as_str()
+            .and_then(parse_address)
+            .ok_or("bad memoryReference")?
+            .wrapping_add_signed(args["offset"].as_i64().unwrap_or(0));
+        let (module, index, pc) = self
+            .resolve_address(base)
+            .ok_or("memoryReference does not point into a known function")?;
+        let reference = (base >> ADDRESS_SHIFT) as i64;
+        let name = function_name(&module, index);
+        let insts = disasm(&module.borrow().functions[index]);
+
+        let start = line_for_pc(&insts, pc, false) as i64 - 1
+            + args["instructionOffset"].as_i64().unwrap_or(0);
+        let count = args["instructionCount"].as_i64().unwrap_or(0).max(0);
+        let instructions = (start..start + count)
+            .map(
+                |i| match usize::try_from(i).ok().and_then(|i| insts.get(i)) {
+                    Some(inst) => {
+                        let mut entry = json!({
+                            "address": format_address(make_address(reference, inst.addr as usize)),
+                            "instruction": format!("{:?}", inst.inst),
+                            "location": source_json(&name, reference),
+                            "line": i + 1,
+                        });
+                        if inst.addr == 0 {
+                            entry["symbol"] = json!(name);
+                        }
+                        entry
+                    }
+                    // The client expects exactly `instructionCount` rows;
+                    // pad outside the function with invalid ones on a
+                    // 4-byte stride.
+                    None => {
+                        let pc = (i * 4).clamp(0, ADDRESS_PC_MASK as i64) as usize;
+                        json!({
+                            "address": format_address(make_address(reference, pc)),
+                            "instruction": "??",
+                            "presentationHint": "invalid",
+                        })
+                    }
+                },
+            )
+            .collect::<Vec<_>>();
+        Ok(json!({ "instructions": instructions }))
+    }
+
+    fn source_reference(&mut self, module: &Rc<RefCell<ScriptModule>>, index: usize) -> i64 {
+        let position = self
+            .sources
+            .iter()
+            .position(|(m, i)| Rc::ptr_eq(m, module) && *i == index);
+        let position = position.unwrap_or_else(|| {
+            self.sources.push((module.clone(), index));
+            self.sources.len() - 1
+        });
+        position as i64 + 1
+    }
+
+    fn source_by_reference(&self, reference: i64) -> Option<(Rc<RefCell<ScriptModule>>, usize)> {
+        let position = usize::try_from(reference - 1).ok()?;
+        self.sources.get(position).cloned()
+    }
+
+    fn resolve_address(&self, address: u64) -> Option<(Rc<RefCell<ScriptModule>>, usize, usize)> {
+        let (module, index) = self.source_by_reference((address >> ADDRESS_SHIFT) as i64)?;
+        Some((module, index, (address & ADDRESS_PC_MASK) as usize))
+    }
+
+    fn respond(&mut self, request: &Value, command: &str, result: Result<Value, String>) {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": command,
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response);
+    }
+
+    fn send_event(&mut self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    fn send(&mut self, mut message: Value) {
+        let Some(client) = self.client.as_ref() else {
+            return;
+        };
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        if client.send(message).is_err() {
+            self.client = None;
+        }
+    }
+}
+
+fn capabilities() -> Value {
+    json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsFunctionBreakpoints": true,
+        "supportsInstructionBreakpoints": true,
+        "supportsDisassembleRequest": true,
+    })
+}
+
+fn scopes(frame_id: i64) -> Value {
+    let mut scopes = vec![];
+    // `fp` never moves on `call` in this VM, so the slot view is only
+    // meaningful for the innermost frame.
+    if frame_id == 0 {
+        scopes.push(json!({
+            "name": "Frame (fp-relative)",
+            "presentationHint": "locals",
+            "variablesReference": SCOPE_FRAME,
+            "expensive": false,
+        }));
+    }
+    scopes.push(json!({
+        "name": "Registers",
+        "presentationHint": "registers",
+        "variablesReference": SCOPE_REGISTERS,
+        "expensive": false,
+    }));
+    scopes.push(json!({
+        "name": "Shared globals",
+        "variablesReference": SCOPE_SHARED_GLOBALS,
+        "expensive": false,
+    }));
+    scopes.push(json!({
+        "name": "Module globals",
+        "variablesReference": SCOPE_MODULE_GLOBALS,
+        "expensive": false,
+    }));
+    scopes.push(json!({
+        "name": "Objects",
+        "variablesReference": SCOPE_OBJECTS,
+        "expensive": false,
+    }));
+    json!({ "scopes": scopes })
+}
+
+fn variables<T: 'static>(vm: &ScriptVm<T>, reference: i64) -> Result<Value, String> {
+    let variables: Vec<(String, String)> = match reference {
+        SCOPE_FRAME => (1..=MAX_FRAME_SLOTS)
+            .map_while(|index| {
+                let addr = vm.fp.checked_sub(index * 4)?;
+                (addr >= vm.sp)
+                    .then(|| vm.stack_word_at(addr as u32))
+                    .flatten()
+                    .map(|word| (format!("var{}", index), format_word(word)))
+            })
+            .collect(),
+        SCOPE_REGISTERS => vec![
+            (
+                "pc".to_string(),
+                format!("{:#06x}", vm.context.as_ref().map(|c| c.pc).unwrap_or(0)),
+            ),
+            ("sp".to_string(), format!("{:#x}", vm.sp)),
+            ("fp".to_string(), format!("{:#x}", vm.fp)),
+            ("r1".to_string(), format_word(vm.r1)),
+            ("r2".to_string(), format_word(vm.r2)),
+            ("robj".to_string(), vm.robj.to_string()),
+            ("depth".to_string(), vm.call_stack.len().to_string()),
+        ],
+        SCOPE_SHARED_GLOBALS => {
+            let module = vm.context.as_ref().map(|ctx| ctx.module.borrow());
+            vm.g.borrow()
+                .vars
+                .iter()
+                .enumerate()
+                .map(|(slot, value)| {
+                    let name = module
+                        .as_ref()
+                        .and_then(|m| m.shared_global_name(slot as u32))
+                        .map(|name| format!("g{} ({})", slot, name))
+                        .unwrap_or_else(|| format!("g{}", slot));
+                    (name, format_word(*value))
+                })
+                .collect()
+        }
+        SCOPE_MODULE_GLOBALS => match vm.context.as_ref() {
+            Some(ctx) => {
+                let module = ctx.module.borrow();
+                module
+                    .globals
+                    .iter()
+                    .enumerate()
+                    .map(|(slot, value)| {
+                        let name = module
+                            .named_globals
+                            .iter()
+                            .find(|g| g.index as usize == slot)
+                            .map(|g| format!("{} ({})", slot, g.name))
+                            .unwrap_or_else(|| slot.to_string());
+                        (name, format_word(*value))
+                    })
+                    .collect()
+            }
+            None => vec![],
+        },
+        SCOPE_OBJECTS => vm
+            .heap
+            .iter()
+            .enumerate()
+            .filter_map(|(index, object)| {
+                object
+                    .as_ref()
+                    .map(|s| (format!("#{}", index), format!("{:?}", s)))
+            })
+            .collect(),
+        _ => return Err(format!("unknown variablesReference {}", reference)),
+    };
+
+    let variables = variables
+        .into_iter()
+        .map(|(name, value)| json!({ "name": name, "value": value, "variablesReference": 0 }))
+        .collect::<Vec<_>>();
+    Ok(json!({ "variables": variables }))
+}
+
+fn function_name(module: &Rc<RefCell<ScriptModule>>, index: usize) -> String {
+    module
+        .borrow()
+        .functions
+        .get(index)
+        .map(|f| f.name.clone())
+        .unwrap_or_else(|| format!("#<{}>", index))
+}
+
+fn source_json(function_name: &str, reference: i64) -> Value {
+    json!({
+        "name": format!("{}.asm", function_name),
+        "sourceReference": reference,
+        "presentationHint": "deemphasize",
+    })
+}
+
+/// 1-based listing line of the instruction at `pc`. With `before`,
+/// the instruction that ends at or before `pc` (a parent frame's
+/// resume point sits just past its `call`).
+fn line_for_pc(insts: &[AsInstInstance], pc: usize, before: bool) -> usize {
+    let index = if before {
+        insts.partition_point(|i| (i.addr as usize) < pc)
+    } else {
+        insts.partition_point(|i| (i.addr as usize) <= pc)
+    };
+    index.max(1)
+}
+
+fn format_word(word: u32) -> String {
+    format!(
+        "{} ({:#010x}, {}f)",
+        word as i32,
+        word,
+        f32::from_bits(word)
+    )
+}
+
+fn make_address(reference: i64, pc: usize) -> u64 {
+    ((reference as u64) << ADDRESS_SHIFT) | (pc as u64 & ADDRESS_PC_MASK)
+}
+
+fn format_address(address: u64) -> String {
+    format!("{:#x}", address)
+}
+
+fn parse_address(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+pub(crate) fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once(':')
+            && key.trim().eq_ignore_ascii_case("Content-Length")
+        {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut body = vec![0; content_length.unwrap_or(0)];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub(crate) fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n", body.len())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+fn accept_loop(listener: TcpListener, events: Sender<ClientEvent>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn!("script debugger: accept failed: {}", e);
+                continue;
+            }
+        };
+
+        if let Err(e) = serve_client(stream, &events) {
+            log::warn!("script debugger: connection closed: {}", e);
+        }
+
+        if events.send(ClientEvent::Disconnected).is_err() {
+            break;
+        }
+    }
+}
+
+fn serve_client(stream: TcpStream, events: &Sender<ClientEvent>) -> io::Result<()> {
+    let _ = stream.set_nodelay(true);
+    let mut writer = stream.try_clone()?;
+    let (client, outgoing) = mpsc::channel::<Value>();
+    std::thread::Builder::new()
+        .name("as-dap-writer".to_string())
+        .spawn(move || {
+            for message in outgoing {
+                if write_message(&mut writer, &message).is_err() {
+                    break;
+                }
+            }
+        })?;
+
+    if events.send(ClientEvent::Connected(client)).is_err() {
+        return Ok(());
+    }
+
+    let mut reader = BufReader::new(stream);
+    while let Some(message) = read_message(&mut reader)? {
+        if events.send(ClientEvent::Message(message)).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::mpsc::{self, Receiver, Sender};
+
+    use serde_json::{Value, json};
+
+    use super::super::ScriptGlobalContext;
+    use super::super::module::{ScriptFunction, ScriptModule};
+    use super::{ClientEvent, ScriptDebugger, read_message, write_message};
+    use crate::scripting::angelscript::ScriptVm;
+
+    fn op(code: u8) -> [u8; 4] {
+        [code, 0, 0, 0]
+    }
+
+    fn concat(parts: &[&[u8]]) -> Vec<u8> {
+        parts.iter().flat_map(|p| p.iter().copied()).collect()
+    }
+
+    struct Harness {
+        vm: ScriptVm<()>,
+        debugger: Rc<RefCell<ScriptDebugger>>,
+        events: Sender<ClientEvent>,
+        replies: Receiver<Value>,
+        seq: i64,
+    }
+
+    impl Harness {
+        /// fn 0 "main": Set4 1 ; Call 1 ; Set4 2 ; Ret 0
+        /// fn 1 "callee": Set4 3 ; Ret 0
+        fn new() -> Self {
+            let main = concat(&[
+                &op(2),
+                &1u32.to_le_bytes(),
+                &op(12),
+                &1u32.to_le_bytes(),
+                &op(2),
+                &2u32.to_le_bytes(),
+                &op(13),
+                &0u16.to_le_bytes(),
+            ]);
+            let callee = concat(&[&op(2), &3u32.to_le_bytes(), &op(13), &0u16.to_le_bytes()]);
+            let module = ScriptModule::test_module(vec![
+                ScriptFunction::test_function("main", main),
+                ScriptFunction::test_function("callee", callee),
+            ]);
+            let g = Rc::new(RefCell::new(ScriptGlobalContext::<()>::new()));
+            let mut vm = ScriptVm::new(g, Rc::new(RefCell::new(module)), 0, ());
+
+            let (events, rx) = mpsc::channel();
+            let debugger = Rc::new(RefCell::new(ScriptDebugger::new(rx)));
+            vm.set_debugger(Some(debugger.clone()));
+
+            let (client, replies) = mpsc::channel();
+            events.send(ClientEvent::Connected(client)).unwrap();
+            Self {
+                vm,
+                debugger,
+                events,
+                replies,
+                seq: 0,
+            }
+        }
+
+        fn request(&mut self, command: &str, arguments: Value) -> Value {
+            self.seq += 1;
+            self.events
+                .send(ClientEvent::Message(json!({
+                    "seq": self.seq,
+                    "type": "request",
+                    "command": command,
+                    "arguments": arguments,
+                })))
+                .unwrap();
+            self.vm.poll_debugger();
+            self.replies
+                .try_iter()
+                .find(|m| m["type"] == "response")
+                .unwrap()
+        }
+
+        /// Run frames until the debugger stops the VM or the script
+        /// finishes.
+        fn run(&mut self) {
+            for _ in 0..16 {
+                self.vm.execute(0.0);
+                if self.debugger.borrow().is_stopped() || self.vm.is_idle() {
+                    return;
+                }
+            }
+            panic!("script neither stopped nor finished");
+        }
+
+        fn stopped_events(&self) -> Vec<Value> {
+            self.replies
+                .try_iter()
+                .filter(|m| m["event"] == "stopped")
+                .collect()
+        }
+
+        fn top(&mut self) -> (String, i64) {
+            let trace = self.request("stackTrace", json!({ "threadId": 1 }));
+            let frame = &trace["body"]["stackFrames"][0];
+            (
+                frame["name"].as_str().unwrap().to_string(),
+                frame["line"].as_i64().unwrap(),
+            )
+        }
+    }
+
+    #[test]
+    fn messages_round_trip_through_content_length_framing() {
+        let message = json!({ "seq": 1, "type": "request", "command": "threads" });
+        let mut buffer = vec![];
+        write_message(&mut buffer, &message).unwrap();
+        write_message(&mut buffer, &message).unwrap();
+
+        let mut reader = std::io::Cursor::new(buffer);
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message.clone()));
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn function_breakpoint_stops_before_the_first_instruction() {
+        let mut h = Harness::new();
+        let reply = h.request(
+            "setFunctionBreakpoints",
+            json!({ "breakpoints": [{ "name": "callee" }] }),
+        );
+        assert_eq!(reply["success"], true);
+
+        h.run();
+        assert!(h.debugger.borrow().is_stopped());
+        let stopped = h.stopped_events();
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped[0]["body"]["reason"], "function breakpoint");
+        assert_eq!(h.top(), ("callee".to_string(), 1));
+
+        // Stopped VMs don't run.
+        h.vm.execute(0.0);
+        assert_eq!(h.top(), ("callee".to_string(), 1));
+
+        h.request("continue", json!({ "threadId": 1 }));
+        h.run();
+        assert!(h.vm.is_idle());
+        assert!(!h.debugger.borrow().is_stopped());
+    }
+
+    #[test]
+    fn stepping_over_into_and_out_of_calls() {
+        let mut h = Harness::new();
+        h.request("pause", json!({ "threadId": 1 }));
+        h.run();
+        assert_eq!(h.top(), ("main".to_string(), 1));
+
+        h.request("next", json!({ "threadId": 1 }));
+        h.run();
+        assert_eq!(h.top(), ("main".to_string(), 2));
+
+        // Step over the call: lands on the instruction after it.
+        h.request("next", json!({ "threadId": 1 }));
+        h.run();
+        assert!(h.debugger.borrow().is_stopped());
+        assert_eq!(h.top(), ("main".to_string(), 3));
+
+        let mut h = Harness::new();
+        h.request("pause", json!({ "threadId": 1 }));
+        h.run();
+        h.request("next", json!({ "threadId": 1 }));
+        h.run();
+        h.request("stepIn", json!({ "threadId": 1 }));
+        h.run();
+        assert_eq!(h.top(), ("callee".to_string(), 1));
+
+        let trace = h.request("stackTrace", json!({ "threadId": 1 }));
+        let frames = trace["body"]["stackFrames"].as_array().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1]["name"], "main");
+        assert_eq!(frames[1]["line"], 2, "parent frame points at the call");
+
+        h.request("stepOut", json!({ "threadId": 1 }));
+        h.run();
+        assert_eq!(h.top(), ("main".to_string(), 3));
+    }
+
+    #[test]
+    fn source_and_instruction_breakpoints_resolve_through_the_listing() {
+        let mut h = Harness::new();
+        h.request("pause", json!({ "threadId": 1 }));
+        h.run();
+        let trace = h.request("stackTrace", json!({ "threadId": 1 }));
+        let frame = trace["body"]["stackFrames"][0].clone();
+        let reference = frame["source"]["sourceReference"].as_i64().unwrap();
+
+        let source = h.request("source", json!({ "sourceReference": reference }));
+        let content = source["body"]["content"].as_str().unwrap();
+        assert_eq!(content.lines().count(), 4);
+        assert!(content.lines().nth(1).unwrap().contains("Call"));
+
+        let reply = h.request(
+            "setBreakpoints",
+            json!({
+                "source": { "sourceReference": reference },
+                "breakpoints": [{ "line": 3 }, { "line": 9 }],
+            }),
+        );
+        assert_eq!(reply["body"]["breakpoints"][0]["verified"], true);
+        assert_eq!(reply["body"]["breakpoints"][1]["verified"], false);
+
+        let listing = h.request(
+            "disassemble",
+            json!({
+                "memoryReference": frame["instructionPointerReference"],
+                "instructionOffset": 0,
+                "instructionCount": 6,
+            }),
+        );
+        let rows = listing["body"]["instructions"].as_array().unwrap();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[0]["symbol"], "main");
+        assert_eq!(rows[5]["presentationHint"], "invalid");
+
+        h.request("continue", json!({ "threadId": 1 }));
+        h.run();
+        let stopped = h.stopped_events();
+        assert_eq!(stopped.last().unwrap()["body"]["reason"], "breakpoint");
+        assert_eq!(h.top(), ("main".to_string(), 3));
+
+        h.request(
+            "setBreakpoints",
+            json!({ "source": { "sourceReference": reference }, "breakpoints": [] }),
+        );
+        let reply = h.request(
+            "setInstructionBreakpoints",
+            json!({ "breakpoints": [{ "instructionReference": rows[3]["address"] }] }),
+        );
+        assert_eq!(reply["body"]["breakpoints"][0]["verified"], true);
+        h.request("continue", json!({ "threadId": 1 }));
+        h.run();
+        assert_eq!(
+            h.stopped_events().last().unwrap()["body"]["reason"],
+            "instruction breakpoint"
+        );
+        assert_eq!(h.top(), ("main".to_string(), 4));
+    }
+
+    #[test]
+    fn variables_expose_registers_and_shared_globals() {
+        let mut h = Harness::new();
+        h.vm.g.borrow_mut().set_global(2, 42);
+        h.request("pause", json!({ "threadId": 1 }));
+        h.run();
+
+        let scopes = h.request("scopes", json!({ "frameId": 0 }));
+        assert_eq!(scopes["body"]["scopes"].as_array().unwrap().len(), 5);
+
+        let globals = h.request("variables", json!({ "variablesReference": 3 }));
+        let g2 = &globals["body"]["variables"][2];
+        assert_eq!(g2["name"], "g2");
+        assert!(g2["value"].as_str().unwrap().starts_with("42 "));
+
+        let registers = h.request("variables", json!({ "variablesReference": 2 }));
+        assert_eq!(registers["body"]["variables"][0]["name"], "pc");
+    }
+
+    #[test]
+    fn detaching_resumes_the_vm() {
+        let mut h = Harness::new();
+        h.request("pause", json!({ "threadId": 1 }));
+        h.run();
+        assert!(h.debugger.borrow().is_stopped());
+
+        h.events.send(ClientEvent::Disconnected).unwrap();
+        h.run();
+        assert!(h.vm.is_idle());
+    }
+}
@@ -0,0 +1,1203 @@
+//! AngelScript-like pseudocode for PAL4 `.csb` functions.
+//!
+//! [`decompile_module`] turns each function's bytecode back into
+//! readable source:
+//!
+//! ```text
+//! // #3
+//! void M01_Talk(int arg0) {
+//!     if (g12 >= 11400) {
+//!         giTalk(1, "...");
+//!     } else {
+//!         g12 = 11400;
+//!     }
+//! }
+//! ```
+//!
+//! Expressions are rebuilt by running the stack ops over symbolic
+//! values. Control flow is recovered from the jump graph: forward
+//! conditional jumps become `if`/`else`, back edges become `while` and
+//! `do … while` loops. Whatever doesn't fit those shapes falls back to
+//! labels and `goto`, so the output is always complete even where it
+//! isn't pretty.
+//!
+//! This is a reading aid, not a format that assembles back — use the
+//! listings from `module_listing` for editing. Two things are guessed
+//! rather than known:
+//!
+//! - System functions don't declare their arity, so a `CallSys` takes
+//!   every value pending on the operand stack as its arguments.
+//! - 8-byte values (`set8`, double arithmetic) are tracked as a single
+//!   stack slot.
+//!
+//! Locals are named after their `fp`-relative slot the way the
+//! debugger shows them (`var1`, `var2`, …; parameters are `arg0`,
+//! `arg1`, …). Shared globals take the name the module's named-globals
+//! block declares for their slot, or `g<slot>` when it declares none;
+//! module globals are `mg<slot>`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write as _};
+
+use super::disassembler::{AsInst, AsInstInstance, disasm};
+use super::module::{ScriptDataType, ScriptFunction, ScriptModule};
+
+/// Names the decompiler can't recover from the module itself.
+#[derive(Debug, Clone, Default)]
+pub struct DecompileOptions {
+    /// System function names indexed by `CallSys` ordinal, e.g.
+    /// `openpal4::scripting::sysfn_names()`. Ordinals without a name
+    /// print as `sysfn<N>`.
+    pub sysfn_names: Vec<String>,
+}
+
+/// Decompiles every function of `module`, in index order.
+pub fn decompile_module(module: &ScriptModule, options: &DecompileOptions) -> String {
+    let mut out = String::new();
+    for (index, function) in module.functions.iter().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        let _ = writeln!(out, "// #{}", index);
+        out.push_str(&decompile_function(module, function, options));
+    }
+    out
+}
+
+/// Decompiles a single function of `module`.
+pub fn decompile_function(
+    module: &ScriptModule,
+    function: &ScriptFunction,
+    options: &DecompileOptions,
+) -> String {
+    let insts = disasm(function);
+    let items = Lowering::new(module, options).lower(&insts, function.inst.len() as u32);
+    let structurer = Structurer::new(&items);
+    let mut nodes = structurer.block(0, items.len(), None);
+
+    let mut gotos = HashSet::new();
+    collect_gotos(&nodes, &mut gotos);
+    // The epilogue `ret` is implied by the closing brace.
+    if let Some(last) = nodes
+        .iter()
+        .rposition(|n| !matches!(n, Node::Label(addr) if !gotos.contains(addr)))
+        && matches!(nodes[last], Node::Return(None))
+    {
+        nodes.remove(last);
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{} {{", signature(function));
+    print_block(&mut out, &nodes, 1, &gotos);
+    out.push_str("}\n");
+    out
+}
+
+fn signature(function: &ScriptFunction) -> String {
+    let params = function
+        .param_types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| format!("{} arg{}", type_name(ty, "int"), i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "{} {}({})",
+        type_name(&function.ret_type, "void"),
+        function.name,
+        params
+    )
+}
+
+fn type_name<'a>(ty: &'a ScriptDataType, fallback: &'a str) -> &'a str {
+    if ty.type_ref.name.is_empty() {
+        fallback
+    } else {
+        &ty.type_ref.name
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Int(u32),
+    Float(f32),
+    Wide(u64),
+    Str(String),
+    /// The two words `str` pushes for a `string@` call.
+    StrIndex(u16),
+    StrLen(u16),
+    Var(String),
+    Addr(String),
+    Deref(Box<Expr>),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    /// Three-way `cmp*` result, folded into a relation by the test or
+    /// jump that consumes it.
+    Cmp(Box<Expr>, Box<Expr>),
+    Cast(&'static str, Box<Expr>),
+    Call(String, Vec<Expr>),
+    /// A value the decompiler lost track of.
+    Unknown,
+}
+
+impl Expr {
+    fn binary(op: &'static str, lhs: Expr, rhs: Expr) -> Self {
+        Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+    }
+
+    fn has_call(&self) -> bool {
+        match self {
+            Expr::Call(..) => true,
+            Expr::Deref(e) | Expr::Unary(_, e) | Expr::Cast(_, e) => e.has_call(),
+            Expr::Binary(_, a, b) | Expr::Cmp(a, b) => a.has_call() || b.has_call(),
+            _ => false,
+        }
+    }
+
+    /// Reinterprets a raw `set4` word as the float it encodes.
+    fn float(self) -> Self {
+        match self {
+            Expr::Int(bits) => Expr::Float(f32::from_bits(bits)),
+            e => e,
+        }
+    }
+
+    fn deref(self) -> Self {
+        match self {
+            Expr::Addr(name) => Expr::Var(name),
+            e => Expr::Deref(Box::new(e)),
+        }
+    }
+
+    fn lvalue(&self) -> String {
+        match self {
+            Expr::Addr(name) => name.clone(),
+            e => format!("*{}", Operand(e)),
+        }
+    }
+
+    /// `self <op> 0`, or `a <op> b` when `self` is `cmp(a, b)`.
+    fn relation(self, op: &'static str) -> Self {
+        match self {
+            Expr::Cmp(a, b) => Expr::Binary(op, a, b),
+            e => Expr::binary(op, e, Expr::Int(0)),
+        }
+    }
+
+    /// The value as a condition: non-zero is true.
+    fn truth(self) -> Self {
+        match self {
+            Expr::Cmp(a, b) => Expr::Binary("!=", a, b),
+            e => e,
+        }
+    }
+
+    fn negate(self) -> Self {
+        let flipped = |op| match op {
+            "==" => Some("!="),
+            "!=" => Some("=="),
+            "<" => Some(">="),
+            ">=" => Some("<"),
+            ">" => Some("<="),
+            "<=" => Some(">"),
+            _ => None,
+        };
+        match self {
+            Expr::Binary(op, a, b) if flipped(op).is_some() => {
+                Expr::Binary(flipped(op).unwrap(), a, b)
+            }
+            Expr::Unary("!", e) => *e,
+            e => Expr::Unary("!", Box::new(e)),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Int(v) => write!(f, "{}", *v as i32),
+            Expr::Float(v) => write!(f, "{:?}", v),
+            Expr::Wide(v) => write!(f, "{:#x}", v),
+            Expr::Str(s) => write!(f, "{:?}", s),
+            Expr::StrIndex(index) => write!(f, "str#{}", index),
+            Expr::StrLen(index) => write!(f, "strlen#{}", index),
+            Expr::Var(name) => f.write_str(name),
+            Expr::Addr(name) => write!(f, "&{}", name),
+            Expr::Deref(e) => write!(f, "*{}", Operand(e)),
+            Expr::Unary(op, e) => write!(f, "{}{}", op, Operand(e)),
+            Expr::Binary(op, a, b) => write!(f, "{} {} {}", Operand(a), op, Operand(b)),
+            Expr::Cmp(a, b) => write!(f, "cmp({}, {})", a, b),
+            Expr::Cast(ty, e) => write!(f, "{}({})", ty, e),
+            Expr::Call(name, args) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                f.write_str(")")
+            }
+            Expr::Unknown => f.write_str("?"),
+        }
+    }
+}
+
+/// Parenthesises compound sub-expressions.
+struct Operand<'a>(&'a Expr);
+
+impl fmt::Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            e @ Expr::Binary(..) => write!(f, "({})", e),
+            e => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Straight-line output of [`Lowering`]: statements with the jumps
+/// left in, waiting to be structured.
+#[derive(Debug)]
+enum Item {
+    Label(u32),
+    Stmt(String),
+    /// `cond` is the condition under which the jump is taken, `None`
+    /// for `jmp`. `next` is the address right after the jump.
+    Jump {
+        cond: Option<Expr>,
+        target: u32,
+        next: u32,
+    },
+    Return(Option<Expr>),
+}
+
+struct Lowering<'a> {
+    module: &'a ScriptModule,
+    options: &'a DecompileOptions,
+    stack: Vec<Expr>,
+    /// `store4` / `recall4` register.
+    reg: Expr,
+    robj: Option<Expr>,
+    /// Value staged by `sret4` for the next `ret`.
+    ret: Option<Expr>,
+    /// Object locals holding a string literal. PAL4 builds a temporary
+    /// for every string argument; inlining the literal at the use keeps
+    /// those temporaries out of the output.
+    literals: HashMap<i16, Expr>,
+    items: Vec<Item>,
+}
+
+impl<'a> Lowering<'a> {
+    fn new(module: &'a ScriptModule, options: &'a DecompileOptions) -> Self {
+        Self {
+            module,
+            options,
+            stack: Vec::new(),
+            reg: Expr::Unknown,
+            robj: None,
+            ret: None,
+            literals: HashMap::new(),
+            items: Vec::new(),
+        }
+    }
+
+    fn lower(mut self, insts: &[AsInstInstance], code_len: u32) -> Vec<Item> {
+        let next_addr = |i: usize| insts.get(i + 1).map_or(code_len, |next| next.addr);
+        let targets: HashSet<u32> = insts
+            .iter()
+            .enumerate()
+            .filter_map(|(i, inst)| jump_target(&inst.inst, next_addr(i)))
+            .collect();
+        let ret_addrs: HashSet<u32> = insts
+            .iter()
+            .filter(|inst| matches!(inst.inst, AsInst::Ret { .. }))
+            .map(|inst| inst.addr)
+            .collect();
+
+        let mut i = 0;
+        while i < insts.len() {
+            let inst = &insts[i];
+            if targets.contains(&inst.addr) {
+                self.flush();
+                self.items.push(Item::Label(inst.addr));
+            }
+            let next = next_addr(i);
+            let follow = insts.get(i + 1).map(|next| &next.inst);
+            let prologue = i == 0;
+            i += 1;
+
+            match inst.inst {
+                AsInst::Pop { data } => {
+                    for _ in 0..data {
+                        let e = self.pop();
+                        if e.has_call() {
+                            self.stmt(format!("{};", e));
+                        }
+                    }
+                }
+                // The prologue reserves the locals.
+                AsInst::Push { .. } if prologue => {}
+                AsInst::Push { data } => {
+                    for _ in 0..data {
+                        self.stack.push(Expr::Unknown);
+                    }
+                }
+                AsInst::Set4 { data } => self.stack.push(Expr::Int(data)),
+                AsInst::PushZero => self.stack.push(Expr::Int(0)),
+                AsInst::Set8 { data } => self.stack.push(Expr::Wide(data)),
+                AsInst::Str { index } => {
+                    self.stack.push(Expr::StrIndex(index));
+                    self.stack.push(Expr::StrLen(index));
+                }
+                AsInst::Rd4 | AsInst::Rd1 | AsInst::Rd2 | AsInst::Rd8 => {
+                    let addr = self.pop();
+                    self.stack.push(addr.deref());
+                }
+                AsInst::Rdsf4 { index } => {
+                    let value = self.local(index as i16);
+                    self.stack.push(value);
+                }
+                AsInst::Psf { index } => self.stack.push(Expr::Addr(var_name(index as i16))),
+                AsInst::Movsf4 { index } => {
+                    let value = self.pop();
+                    self.literals.remove(&(index as i16));
+                    self.stmt(format!("{} = {};", var_name(index as i16), value));
+                }
+                AsInst::Wrt4 | AsInst::Wrt1 | AsInst::Wrt2 | AsInst::Wrt8 => {
+                    let addr = self.pop();
+                    let lvalue = addr.lvalue();
+                    let value = self.stack.last().cloned().unwrap_or(Expr::Unknown);
+                    self.stmt(format!("{} = {};", lvalue, value));
+                    // The written value stays on the stack; usually a
+                    // `pop` drops it right after.
+                    if let Some(top) = self.stack.last_mut() {
+                        *top = Expr::Var(lvalue);
+                    }
+                }
+                AsInst::Mov4 => {
+                    let addr = self.pop();
+                    let value = self.pop();
+                    self.stmt(format!("{} = {};", addr.lvalue(), value));
+                }
+                AsInst::Swap4 | AsInst::Swapd => {
+                    let len = self.stack.len();
+                    if len >= 2 {
+                        self.stack.swap(len - 1, len - 2);
+                    }
+                }
+                AsInst::Store4 => self.reg = self.stack.last().cloned().unwrap_or(Expr::Unknown),
+                AsInst::Recall4 => self.stack.push(self.reg.clone()),
+                AsInst::Call { function } => {
+                    let (name, arity) = match self.module.functions.get(function as usize) {
+                        Some(f) => (f.name.clone(), f.param_types.len()),
+                        None => (format!("fn{}", function), 0),
+                    };
+                    let args = (0..arity).map(|_| self.pop()).collect();
+                    if self.call_result(Expr::Call(name, args), follow) {
+                        i += 1;
+                    }
+                }
+                AsInst::CallSys { function_index } => {
+                    let ordinal = -i64::from(function_index) - 1;
+                    let name = usize::try_from(ordinal)
+                        .ok()
+                        .and_then(|ordinal| self.options.sysfn_names.get(ordinal))
+                        .cloned()
+                        .unwrap_or_else(|| format!("sysfn{}", ordinal));
+                    if name == "string@" {
+                        let len = self.pop();
+                        let index = self.pop();
+                        self.robj = Some(match index {
+                            Expr::StrIndex(index) => Expr::Str(self.string(index)),
+                            index => Expr::Call(name, vec![len, index]),
+                        });
+                        continue;
+                    }
+                    let call = Expr::Call(name, self.stack.drain(..).rev().collect());
+                    if self.call_result(call, follow) {
+                        i += 1;
+                    }
+                }
+                AsInst::CallBnd { function_index } => {
+                    let call = Expr::Call(
+                        format!("bnd{}", function_index),
+                        self.stack.drain(..).rev().collect(),
+                    );
+                    if self.call_result(call, follow) {
+                        i += 1;
+                    }
+                }
+                AsInst::Ret { .. } => {
+                    self.flush();
+                    let value = self.ret.take();
+                    self.items.push(Item::Return(value));
+                }
+                AsInst::Sret4 | AsInst::Sret8 => self.ret = Some(self.pop()),
+                AsInst::Rret4 | AsInst::Rret8 => self.stack.push(Expr::Var("r1".to_string())),
+                AsInst::Jmp { offset } => {
+                    self.flush();
+                    let target = next.wrapping_add(offset as u32);
+                    // `return x;` stages `x` and jumps to the epilogue.
+                    // A bare jump there is left for the structurer; it
+                    // may turn out to be a `break` or an `else`.
+                    if ret_addrs.contains(&target) && self.ret.is_some() {
+                        let value = self.ret.take();
+                        self.items.push(Item::Return(value));
+                    } else {
+                        self.items.push(Item::Jump {
+                            cond: None,
+                            target,
+                            next,
+                        });
+                    }
+                }
+                AsInst::Jz { offset } => {
+                    let cond = self.pop().truth().negate();
+                    self.branch(cond, next, offset);
+                }
+                AsInst::Jnz { offset } => {
+                    let cond = self.pop().truth();
+                    self.branch(cond, next, offset);
+                }
+                AsInst::Js { offset } => self.relation_branch("<", next, offset),
+                AsInst::Jns { offset } => self.relation_branch(">=", next, offset),
+                AsInst::Jp { offset } => self.relation_branch(">", next, offset),
+                AsInst::Jnp { offset } => self.relation_branch("<=", next, offset),
+                AsInst::Jmpp => {
+                    let index = self.pop();
+                    self.flush();
+                    self.stmt(format!("goto jump_table[{}];", index));
+                }
+                AsInst::Tz => self.test("=="),
+                AsInst::Tnz => self.test("!="),
+                AsInst::Ts => self.test("<"),
+                AsInst::Tns => self.test(">="),
+                AsInst::Tp => self.test(">"),
+                AsInst::Tnp => self.test("<="),
+                AsInst::Addi | AsInst::Addd => self.binary("+", false),
+                AsInst::Subi | AsInst::Subd => self.binary("-", false),
+                AsInst::Muli | AsInst::Muld => self.binary("*", false),
+                AsInst::Divi | AsInst::Divd => self.binary("/", false),
+                AsInst::Modi | AsInst::Modd => self.binary("%", false),
+                AsInst::Addf => self.binary("+", true),
+                AsInst::Subf => self.binary("-", true),
+                AsInst::Mulf => self.binary("*", true),
+                AsInst::Divf => self.binary("/", true),
+                AsInst::Modf => self.binary("%", true),
+                AsInst::Band => self.binary("&", false),
+                AsInst::Bor => self.binary("|", false),
+                AsInst::Bxor => self.binary("^", false),
+                AsInst::Bsll => self.binary("<<", false),
+                AsInst::Bsrl | AsInst::Bsra => self.binary(">>", false),
+                AsInst::Addii { rhs } => self.binary_imm("+", Expr::Int(rhs as u32)),
+                AsInst::Subii { rhs } => self.binary_imm("-", Expr::Int(rhs as u32)),
+                AsInst::Mulii { rhs } => self.binary_imm("*", Expr::Int(rhs as u32)),
+                AsInst::Addif { rhs } => self.binary_imm("+", Expr::Float(rhs)),
+                AsInst::Subif { rhs } => self.binary_imm("-", Expr::Float(rhs)),
+                AsInst::Mulif { rhs } => self.binary_imm("*", Expr::Float(rhs)),
+                AsInst::Cmpi | AsInst::Cmpu | AsInst::Cmpd => {
+                    let rhs = self.pop();
+                    let lhs = self.pop();
+                    self.stack.push(Expr::Cmp(Box::new(lhs), Box::new(rhs)));
+                }
+                AsInst::Cmpf => {
+                    let rhs = self.pop().float();
+                    let lhs = self.pop().float();
+                    self.stack.push(Expr::Cmp(Box::new(lhs), Box::new(rhs)));
+                }
+                AsInst::Cmpii { rhs } => self.compare_imm(Expr::Int(rhs as u32)),
+                AsInst::Cmpiui { rhs } => self.compare_imm(Expr::Int(rhs)),
+                AsInst::Cmpif { rhs } => self.compare_imm(Expr::Float(rhs)),
+                AsInst::Negi | AsInst::Negd => self.unary("-", false),
+                AsInst::Negf => self.unary("-", true),
+                AsInst::Bnot => self.unary("~", false),
+                AsInst::Inci | AsInst::Inci16 | AsInst::Inci8 | AsInst::Incf | AsInst::Incd => {
+                    self.step("++")
+                }
+                AsInst::Deci | AsInst::Deci16 | AsInst::Deci8 | AsInst::Decf | AsInst::Decd => {
+                    self.step("--")
+                }
+                AsInst::I2f | AsInst::Ui2f | AsInst::D2f => self.cast("float"),
+                AsInst::F2i | AsInst::D2i => self.cast("int"),
+                AsInst::F2ui | AsInst::D2ui => self.cast("uint"),
+                AsInst::I2d | AsInst::U2d | AsInst::F2d => self.cast("double"),
+                AsInst::Sb => self.cast("int8"),
+                AsInst::Sw => self.cast("int16"),
+                AsInst::Ub => self.cast("uint8"),
+                AsInst::Uw => self.cast("uint16"),
+                AsInst::Copy { count } => {
+                    let dst = self.pop();
+                    let src = self.stack.last().cloned().unwrap_or(Expr::Unknown);
+                    self.stmt(format!(
+                        "{} = {}; // copy {}",
+                        dst.lvalue(),
+                        src.deref(),
+                        count
+                    ));
+                }
+                AsInst::Pga { index } => self.stack.push(Expr::Addr(self.global_name(index))),
+                AsInst::Rdga4 { index } => self.stack.push(Expr::Var(self.global_name(index))),
+                AsInst::Movga4 { index } => {
+                    let value = self.pop();
+                    self.stmt(format!("{} = {};", self.global_name(index), value));
+                }
+                // `alloc` fills the slot on top of the stack in place;
+                // `free` drops a temporary. Neither reads as source.
+                AsInst::Alloc { .. } => {}
+                AsInst::Free { .. } => {
+                    self.pop();
+                }
+                AsInst::StoreObj { param_index } => {
+                    let value = self
+                        .robj
+                        .take()
+                        .unwrap_or_else(|| Expr::Var("robj".to_string()));
+                    if matches!(value, Expr::Str(_)) {
+                        self.literals.insert(param_index, value);
+                    } else {
+                        self.literals.remove(&param_index);
+                        self.stmt(format!("{} = {};", var_name(param_index), value));
+                    }
+                }
+                AsInst::GetObjRef { offset } => {
+                    let slot = self.stack.len().checked_sub(1 + offset.max(0) as usize);
+                    if let Some(slot) = slot
+                        && let Expr::Int(index) = self.stack[slot]
+                    {
+                        self.stack[slot] = self.local(index as i16);
+                    }
+                }
+                AsInst::Suspend | AsInst::CheckRef | AsInst::ObjType { .. } => {}
+                AsInst::LoadObj { .. }
+                | AsInst::GetObj { .. }
+                | AsInst::RefCpy { .. }
+                | AsInst::GetRef { .. }
+                | AsInst::Swap48
+                | AsInst::Swap84 => {
+                    self.stmt(format!("// {:?}", inst.inst));
+                }
+            }
+        }
+
+        self.flush();
+        self.items.push(Item::Label(code_len));
+        self.items
+    }
+
+    fn pop(&mut self) -> Expr {
+        self.stack.pop().unwrap_or(Expr::Unknown)
+    }
+
+    fn stmt(&mut self, text: String) {
+        self.items.push(Item::Stmt(text));
+    }
+
+    /// Emits pending calls as statements and forgets the rest of the
+    /// stack; used wherever control flow may join.
+    fn flush(&mut self) {
+        for e in std::mem::take(&mut self.stack) {
+            if e.has_call() {
+                self.stmt(format!("{};", e));
+            }
+        }
+    }
+
+    /// Places a call's result: on the stack when the next instruction
+    /// picks up the return register (returns `true` so the caller skips
+    /// it), into the object register before a `storeobj`, or as a
+    /// statement otherwise.
+    fn call_result(&mut self, call: Expr, follow: Option<&AsInst>) -> bool {
+        match follow {
+            Some(AsInst::Rret4 | AsInst::Rret8) => {
+                self.stack.push(call);
+                true
+            }
+            Some(AsInst::StoreObj { .. }) => {
+                self.robj = Some(call);
+                false
+            }
+            _ => {
+                self.stmt(format!("{};", call));
+                false
+            }
+        }
+    }
+
+    fn branch(&mut self, cond: Expr, next: u32, offset: i32) {
+        self.flush();
+        self.items.push(Item::Jump {
+            cond: Some(cond),
+            target: next.wrapping_add(offset as u32),
+            next,
+        });
+    }
+
+    fn relation_branch(&mut self, op: &'static str, next: u32, offset: i32) {
+        let cond = self.pop().relation(op);
+        self.branch(cond, next, offset);
+    }
+
+    fn test(&mut self, op: &'static str) {
+        let value = self.pop().relation(op);
+        self.stack.push(value);
+    }
+
+    fn binary(&mut self, op: &'static str, float: bool) {
+        let (mut rhs, mut lhs) = (self.pop(), self.pop());
+        if float {
+            (rhs, lhs) = (rhs.float(), lhs.float());
+        }
+        self.stack.push(Expr::binary(op, lhs, rhs));
+    }
+
+    fn binary_imm(&mut self, op: &'static str, rhs: Expr) {
+        let mut lhs = self.pop();
+        if matches!(rhs, Expr::Float(_)) {
+            lhs = lhs.float();
+        }
+        self.stack.push(Expr::binary(op, lhs, rhs));
+    }
+
+    fn compare_imm(&mut self, rhs: Expr) {
+        let mut lhs = self.pop();
+        if matches!(rhs, Expr::Float(_)) {
+            lhs = lhs.float();
+        }
+        self.stack.push(Expr::Cmp(Box::new(lhs), Box::new(rhs)));
+    }
+
+    fn unary(&mut self, op: &'static str, float: bool) {
+        let mut value = self.pop();
+        if float {
+            value = value.float();
+        }
+        self.stack.push(Expr::Unary(op, Box::new(value)));
+    }
+
+    fn cast(&mut self, ty: &'static str) {
+        let value = self.pop();
+        self.stack.push(Expr::Cast(ty, Box::new(value)));
+    }
+
+    /// `inc*` / `dec*` update the variable whose address is on top of
+    /// the stack and leave the address there.
+    fn step(&mut self, op: &str) {
+        let addr = self.stack.last().cloned().unwrap_or(Expr::Unknown);
+        self.stmt(format!("{}{};", op, addr.lvalue()));
+    }
+
+    fn local(&self, index: i16) -> Expr {
+        self.literals
+            .get(&index)
+            .cloned()
+            .unwrap_or_else(|| Expr::Var(var_name(index)))
+    }
+
+    fn string(&self, index: u16) -> String {
+        self.module
+            .strings
+            .get(index as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("<string #{}>", index))
+    }
+
+    /// See `ScriptVm::pga` for the index encoding.
+    fn global_name(&self, index: i32) -> String {
+        if index >= 0 {
+            return format!("mg{}", index);
+        }
+        let slot = (-index - 1) as u32;
+        self.module
+            .shared_global_name(slot)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("g{}", slot))
+    }
+}
+
+fn var_name(index: i16) -> String {
+    if index > 0 {
+        format!("var{}", index)
+    } else {
+        format!("arg{}", -i32::from(index))
+    }
+}
+
+fn jump_target(inst: &AsInst, next: u32) -> Option<u32> {
+    match *inst {
+        AsInst::Jmp { offset }
+        | AsInst::Jz { offset }
+        | AsInst::Jnz { offset }
+        | AsInst::Js { offset }
+        | AsInst::Jns { offset }
+        | AsInst::Jp { offset }
+        | AsInst::Jnp { offset } => Some(next.wrapping_add(offset as u32)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Stmt(String),
+    Label(u32),
+    Goto(u32),
+    If(Expr, Vec<Node>, Vec<Node>),
+    While(Expr, Vec<Node>),
+    DoWhile(Vec<Node>, Expr),
+    Break,
+    Continue,
+    Return(Option<Expr>),
+}
+
+/// Header and exit address of the innermost enclosing loop.
+type Loop = (u32, u32);
+
+struct Structurer<'a> {
+    items: &'a [Item],
+    labels: HashMap<u32, usize>,
+}
+
+impl<'a> Structurer<'a> {
+    fn new(items: &'a [Item]) -> Self {
+        let labels = items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| match item {
+                Item::Label(addr) => Some((*addr, i)),
+                _ => None,
+            })
+            .collect();
+        Self { items, labels }
+    }
+
+    fn block(&self, mut i: usize, end: usize, lp: Option<Loop>) -> Vec<Node> {
+        let mut out = Vec::new();
+        while i < end {
+            match &self.items[i] {
+                Item::Label(header) => {
+                    out.push(Node::Label(*header));
+                    // The last back edge inside this block closes the loop.
+                    let back_edge = (i + 1..end).rev().find(|&j| {
+                        matches!(&self.items[j], Item::Jump { target, .. } if target == header)
+                    });
+                    let Some(j) = back_edge else {
+                        i += 1;
+                        continue;
+                    };
+                    let Item::Jump { cond, next, .. } = &self.items[j] else {
+                        unreachable!();
+                    };
+                    let inner = Some((*header, *next));
+                    let node = match cond {
+                        Some(cond) => Node::DoWhile(self.block(i + 1, j, inner), cond.clone()),
+                        None => match &self.items[i + 1] {
+                            Item::Jump {
+                                cond: Some(exit_cond),
+                                target,
+                                ..
+                            } if i + 1 < j && target == next => {
+                                Node::While(exit_cond.clone().negate(), self.block(i + 2, j, inner))
+                            }
+                            _ => Node::While(
+                                Expr::Var("true".to_string()),
+                                self.block(i + 1, j, inner),
+                            ),
+                        },
+                    };
+                    out.push(node);
+                    i = j + 1;
+                }
+                Item::Stmt(text) => {
+                    out.push(Node::Stmt(text.clone()));
+                    i += 1;
+                }
+                Item::Return(value) => {
+                    out.push(Node::Return(value.clone()));
+                    i += 1;
+                }
+                Item::Jump {
+                    cond: None, target, ..
+                } => {
+                    let node = escape(*target, lp).unwrap_or_else(|| {
+                        if self.is_epilogue(*target) {
+                            Node::Return(None)
+                        } else {
+                            Node::Goto(*target)
+                        }
+                    });
+                    out.push(node);
+                    i += 1;
+                }
+                Item::Jump {
+                    cond: Some(cond),
+                    target,
+                    next,
+                } => {
+                    if let Some(node) = escape(*target, lp) {
+                        out.push(Node::If(cond.clone(), vec![node], vec![]));
+                        i += 1;
+                        continue;
+                    }
+                    let then_end = self
+                        .labels
+                        .get(target)
+                        .copied()
+                        .filter(|&t| target > next && t <= end);
+                    let Some(t) = then_end else {
+                        out.push(Node::If(cond.clone(), vec![Node::Goto(*target)], vec![]));
+                        i += 1;
+                        continue;
+                    };
+                    // A `jmp` closing the then-branch over more code
+                    // marks that code as the else-branch.
+                    let else_end = match &self.items[t - 1] {
+                        Item::Jump {
+                            cond: None,
+                            target: e,
+                            ..
+                        } if t - 1 > i && e > target && escape(*e, lp).is_none() => {
+                            self.labels.get(e).copied().filter(|&ei| ei <= end)
+                        }
+                        _ => None,
+                    };
+                    let cond = cond.clone().negate();
+                    match else_end {
+                        Some(ei) => {
+                            out.push(Node::If(
+                                cond,
+                                self.block(i + 1, t - 1, lp),
+                                self.block(t, ei, lp),
+                            ));
+                            i = ei;
+                        }
+                        None => {
+                            out.push(Node::If(cond, self.block(i + 1, t, lp), vec![]));
+                            i = t;
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Whether `target` is a bare `ret`.
+    fn is_epilogue(&self, target: u32) -> bool {
+        self.labels
+            .get(&target)
+            .is_some_and(|&t| matches!(self.items.get(t + 1), Some(Item::Return(None))))
+    }
+}
+
+fn escape(target: u32, lp: Option<Loop>) -> Option<Node> {
+    match lp {
+        Some((_, exit)) if target == exit => Some(Node::Break),
+        Some((header, _)) if target == header => Some(Node::Continue),
+        _ => None,
+    }
+}
+
+fn collect_gotos(nodes: &[Node], gotos: &mut HashSet<u32>) {
+    for node in nodes {
+        match node {
+            Node::Goto(target) => {
+                gotos.insert(*target);
+            }
+            Node::If(_, then, otherwise) => {
+                collect_gotos(then, gotos);
+                collect_gotos(otherwise, gotos);
+            }
+            Node::While(_, body) | Node::DoWhile(body, _) => collect_gotos(body, gotos),
+            _ => {}
+        }
+    }
+}
+
+fn print_block(out: &mut String, nodes: &[Node], depth: usize, gotos: &HashSet<u32>) {
+    let indent = "    ".repeat(depth);
+    for node in nodes {
+        match node {
+            Node::Stmt(text) => {
+                let _ = writeln!(out, "{}{}", indent, text);
+            }
+            Node::Label(addr) => {
+                if gotos.contains(addr) {
+                    let _ = writeln!(out, "{}L_{:04x}:", "    ".repeat(depth - 1), addr);
+                }
+            }
+            Node::Goto(addr) => {
+                let _ = writeln!(out, "{}goto L_{:04x};", indent, addr);
+            }
+            Node::If(cond, then, otherwise) => {
+                let _ = writeln!(out, "{}if ({}) {{", indent, cond);
+                print_block(out, then, depth + 1, gotos);
+                print_else(out, otherwise, depth, gotos);
+            }
+            Node::While(cond, body) => {
+                let _ = writeln!(out, "{}while ({}) {{", indent, cond);
+                print_block(out, body, depth + 1, gotos);
+                let _ = writeln!(out, "{}}}", indent);
+            }
+            Node::DoWhile(body, cond) => {
+                let _ = writeln!(out, "{}do {{", indent);
+                print_block(out, body, depth + 1, gotos);
+                let _ = writeln!(out, "{}}} while ({});", indent, cond);
+            }
+            Node::Break => {
+                let _ = writeln!(out, "{}break;", indent);
+            }
+            Node::Continue => {
+                let _ = writeln!(out, "{}continue;", indent);
+            }
+            Node::Return(None) => {
+                let _ = writeln!(out, "{}return;", indent);
+            }
+            Node::Return(Some(value)) => {
+                let _ = writeln!(out, "{}return {};", indent, value);
+            }
+        }
+    }
+}
+
+/// Closes an `if`, chaining a lone nested `if` as `else if`.
+fn print_else(out: &mut String, otherwise: &[Node], depth: usize, gotos: &HashSet<u32>) {
+    let indent = "    ".repeat(depth);
+    match otherwise {
+        [] => {
+            let _ = writeln!(out, "{}}}", indent);
+        }
+        [Node::If(cond, then, rest)] => {
+            let _ = writeln!(out, "{}}} else if ({}) {{", indent, cond);
+            print_block(out, then, depth + 1, gotos);
+            print_else(out, rest, depth, gotos);
+        }
+        _ => {
+            let _ = writeln!(out, "{}}} else {{", indent);
+            print_block(out, otherwise, depth + 1, gotos);
+            let _ = writeln!(out, "{}}}", indent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::module::NamedGlobal;
+    use super::*;
+
+    // Opcodes, see `disasm`.
+    const SET4: u8 = 2;
+    const RDSF4: u8 = 4;
+    const MOVSF4: u8 = 8;
+    const RET: u8 = 13;
+    const JMP: u8 = 14;
+    const JZ: u8 = 15;
+    const JNZ: u8 = 16;
+    const ADDI: u8 = 23;
+    const STR: u8 = 90;
+    const JNP: u8 = 94;
+    const CMPII: u8 = 95;
+    const CALLSYS: u8 = 97;
+    const RDGA4: u8 = 99;
+    const MOVGA4: u8 = 100;
+    const STOREOBJ: u8 = 112;
+    const GETOBJREF: u8 = 118;
+
+    /// Bytecode builder with named jump targets.
+    #[derive(Default)]
+    struct Code {
+        bytes: Vec<u8>,
+        labels: HashMap<&'static str, usize>,
+        fixups: Vec<(usize, &'static str)>,
+    }
+
+    impl Code {
+        fn op(mut self, opcode: u8) -> Self {
+            self.bytes.extend_from_slice(&[opcode, 0, 0, 0]);
+            self
+        }
+
+        fn u16(mut self, value: u16) -> Self {
+            self.bytes.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn i32(mut self, value: i32) -> Self {
+            self.bytes.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn label(mut self, name: &'static str) -> Self {
+            self.labels.insert(name, self.bytes.len());
+            self
+        }
+
+        fn jump(mut self, opcode: u8, label: &'static str) -> Self {
+            self = self.op(opcode);
+            self.fixups.push((self.bytes.len(), label));
+            self.i32(0)
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            for (at, label) in &self.fixups {
+                let offset = self.labels[label] as i32 - (*at as i32 + 4);
+                self.bytes[*at..*at + 4].copy_from_slice(&offset.to_le_bytes());
+            }
+            self.bytes
+        }
+    }
+
+    fn decompile(code: Code, strings: &[&str], sysfns: &[&str]) -> String {
+        let mut module =
+            ScriptModule::test_module(vec![ScriptFunction::test_function("f", code.finish())]);
+        module.strings = strings.iter().map(|s| s.to_string()).collect();
+        decompile_in(&module, sysfns)
+    }
+
+    fn decompile_in(module: &ScriptModule, sysfns: &[&str]) -> String {
+        let options = DecompileOptions {
+            sysfn_names: sysfns.iter().map(|s| s.to_string()).collect(),
+        };
+        decompile_function(module, &module.functions[0], &options)
+    }
+
+    fn sysfn(ordinal: i32) -> i32 {
+        -ordinal - 1
+    }
+
+    #[test]
+    fn rebuilds_expressions_and_sysfn_calls() {
+        let code = Code::default()
+            .op(RDSF4)
+            .u16(1)
+            .op(SET4)
+            .i32(2)
+            .op(ADDI)
+            .op(MOVGA4)
+            .i32(-4)
+            // Arguments go in last to first; the string one through a
+            // temporary in var2.
+            .op(SET4)
+            .i32(7)
+            .op(SET4)
+            .i32(2)
+            .op(STR)
+            .u16(0)
+            .op(CALLSYS)
+            .i32(sysfn(0))
+            .op(STOREOBJ)
+            .u16(2)
+            .op(GETOBJREF)
+            .u16(0)
+            .op(CALLSYS)
+            .i32(sysfn(1))
+            .op(RET)
+            .u16(0);
+        assert_eq!(
+            decompile(code, &["hello"], &["string@", "giTalk"]),
+            "void f() {\n    g3 = var1 + 2;\n    giTalk(\"hello\", 7);\n}\n"
+        );
+    }
+
+    #[test]
+    fn names_shared_globals_from_module_declarations() {
+        let code = Code::default()
+            .op(SET4)
+            .i32(1)
+            .op(MOVGA4)
+            .i32(-2)
+            .op(SET4)
+            .i32(2)
+            .op(MOVGA4)
+            .i32(-3)
+            .op(RET)
+            .u16(0);
+        let mut module =
+            ScriptModule::test_module(vec![ScriptFunction::test_function("f", code.finish())]);
+        module.named_globals.push(NamedGlobal {
+            name: "LL_shu".to_string(),
+            raw_name: None,
+            kind: 0x3c,
+            index: 1,
+        });
+        assert_eq!(
+            decompile_in(&module, &[]),
+            "void f() {\n    LL_shu = 1;\n    g2 = 2;\n}\n"
+        );
+    }
+
+    #[test]
+    fn recovers_if_else() {
+        let code = Code::default()
+            .op(RDGA4)
+            .i32(-1)
+            .op(CMPII)
+            .i32(5)
+            .jump(JNP, "else")
+            .op(SET4)
+            .i32(1)
+            .op(MOVSF4)
+            .u16(1)
+            .jump(JMP, "end")
+            .label("else")
+            .op(SET4)
+            .i32(2)
+            .op(MOVSF4)
+            .u16(1)
+            .label("end")
+            .op(RET)
+            .u16(0);
+        assert_eq!(
+            decompile(code, &[], &[]),
+            "void f() {\n    if (g0 > 5) {\n        var1 = 1;\n    } else {\n        var1 = 2;\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn recovers_while_loop_with_break() {
+        let code = Code::default()
+            .label("head")
+            .op(RDSF4)
+            .u16(1)
+            .jump(JZ, "exit")
+            .op(RDGA4)
+            .i32(-1)
+            .op(CMPII)
+            .i32(3)
+            .jump(JNZ, "skip")
+            .jump(JMP, "exit")
+            .label("skip")
+            .op(CALLSYS)
+            .i32(sysfn(0))
+            .jump(JMP, "head")
+            .label("exit")
+            .op(RET)
+            .u16(0);
+        assert_eq!(
+            decompile(code, &[], &[]),
+            "void f() {\n    while (var1) {\n        if (g0 == 3) {\n            break;\n        }\n        sysfn0();\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_goto() {
+        // The inner jump leaves the outer `if` for a label past its end.
+        let code = Code::default()
+            .op(RDSF4)
+            .u16(1)
+            .jump(JZ, "a")
+            .op(RDSF4)
+            .u16(2)
+            .jump(JZ, "b")
+            .op(CALLSYS)
+            .i32(sysfn(0))
+            .label("a")
+            .op(CALLSYS)
+            .i32(sysfn(1))
+            .label("b")
+            .op(RET)
+            .u16(0);
+        assert_eq!(
+            decompile(code, &[], &["giWait", "giEnd"]),
+            "void f() {\n    if (var1) {\n        if (!var2) {\n            goto L_002c;\n        }\n        giWait();\n    }\n    giEnd();\nL_002c:\n}\n"
+        );
+    }
+}
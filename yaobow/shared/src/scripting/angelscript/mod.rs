@@ -1,12 +1,18 @@
 #[cfg(any(windows, linux, macos))]
 pub mod debug;
 
+mod assembler;
+mod dap;
+mod decompiler;
 mod disassembler;
 mod global_context;
 mod module;
-pub mod trace;
 mod vm;
 
+pub use crate::scripting::trace;
+pub use assembler::{assemble_function, assemble_module, function_listing, module_listing};
+pub use dap::ScriptDebugger;
+pub use decompiler::{DecompileOptions, decompile_function, decompile_module};
 pub use disassembler::{AsInst, AsInstInstance, disasm};
 pub use global_context::{
     ContinuationState, GlobalFunctionContinuation, GlobalFunctionState, ScriptGlobalContext,
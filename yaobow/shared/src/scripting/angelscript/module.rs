@@ -1,58 +1,82 @@
 use std::{
-    io::{Cursor, Read},
+    io::{Cursor, Read, Write},
     sync::Arc,
 };
 
 use anyhow::Context;
-use byteorder::ReadBytesExt;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use common::read_ext::ReadExt;
+use encoding::{DecoderTrap, EncoderTrap, Encoding};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScriptTypeDefinition {
-    name: String,
+    pub name: String,
+    /// Bytes `name` was stored as, so [`ScriptModule::write`] can
+    /// reproduce them while `name` is unchanged.
+    #[serde(skip)]
+    pub raw_name: Option<Vec<u8>>,
 }
 
 impl ScriptTypeDefinition {
     fn read(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<Self> {
-        let name = read_string(cursor).with_context(|| {
+        let (name, raw_name) = read_string(cursor).with_context(|| {
             format!(
                 "reading ScriptTypeDefinition.name at {:#x}",
                 cursor.position()
             )
         })?;
 
-        Ok(Self { name })
+        Ok(Self {
+            name,
+            raw_name: Some(raw_name),
+        })
+    }
+
+    fn write(&self, writer: &mut dyn Write) -> anyhow::Result<()> {
+        write_string(writer, &self.name, self.raw_name.as_deref())
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScriptTypeReference {
-    name: String,
+    pub name: String,
+    /// Bytes `name` was stored as, so [`ScriptModule::write`] can
+    /// reproduce them while `name` is unchanged.
+    #[serde(skip)]
+    pub raw_name: Option<Vec<u8>>,
 }
 
 impl ScriptTypeReference {
     fn read(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<Self> {
-        let name = read_string(cursor).with_context(|| {
+        let (name, raw_name) = read_string(cursor).with_context(|| {
             format!(
                 "reading ScriptTypeReference.name at {:#x}",
                 cursor.position()
             )
         })?;
 
-        Ok(Self { name })
+        Ok(Self {
+            name,
+            raw_name: Some(raw_name),
+        })
+    }
+
+    fn write(&self, writer: &mut dyn Write) -> anyhow::Result<()> {
+        write_string(writer, &self.name, self.raw_name.as_deref())
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScriptDataType {
-    flag: u8,
-    unknown: u32,
-    type_ref: ScriptTypeReference,
-    unknown2: u8,
-    unknown3: u8,
-    unknown4: u8,
-    unknown5: u8,
+    /// Always 0; the parser rejects anything else.
+    pub flag: u8,
+    pub unknown: u32,
+    pub type_ref: ScriptTypeReference,
+    pub unknown2: u8,
+    pub unknown3: u8,
+    pub unknown4: u8,
+    pub unknown5: u8,
 }
 
 impl ScriptDataType {
@@ -81,6 +105,14 @@ impl ScriptDataType {
             unknown5,
         })
     }
+
+    fn write(&self, writer: &mut dyn Write) -> anyhow::Result<()> {
+        writer.write_u8(self.flag)?;
+        writer.write_u32::<LittleEndian>(self.unknown)?;
+        self.type_ref.write(writer)?;
+        writer.write_all(&[self.unknown2, self.unknown3, self.unknown4, self.unknown5])?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -92,6 +124,10 @@ pub struct Instruction {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScriptFunction {
     pub name: String,
+    /// Bytes `name` was stored as, so [`ScriptModule::write`] can
+    /// reproduce them while `name` is unchanged.
+    #[serde(skip)]
+    pub raw_name: Option<Vec<u8>>,
     pub ret_type: ScriptDataType,
     pub param_types: Vec<ScriptDataType>,
     pub unknown_dword1: u32,
@@ -107,7 +143,7 @@ pub struct ScriptFunction {
 impl ScriptFunction {
     fn read(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<Self> {
         let fn_start = cursor.position();
-        let name = read_string(cursor)
+        let (name, raw_name) = read_string(cursor)
             .with_context(|| format!("ScriptFunction.name at {:#x}", fn_start))?;
 
         let ret_type = ScriptDataType::read(cursor)
@@ -160,6 +196,7 @@ impl ScriptFunction {
 
         Ok(Self {
             name,
+            raw_name: Some(raw_name),
             ret_type,
             param_types,
             unknown_dword1,
@@ -173,6 +210,92 @@ impl ScriptFunction {
         })
     }
 
+    fn write(&self, writer: &mut dyn Write) -> anyhow::Result<()> {
+        write_string(writer, &self.name, self.raw_name.as_deref())?;
+        self.ret_type.write(writer)?;
+        writer.write_u32::<LittleEndian>(self.param_types.len() as u32)?;
+        for param in &self.param_types {
+            param.write(writer)?;
+        }
+
+        writer.write_u32::<LittleEndian>(self.unknown_dword1)?;
+        writer.write_u32::<LittleEndian>(self.inst.len() as u32)?;
+        Self::write_instructions(writer, &self.inst)
+            .with_context(|| format!("fn '{}' instructions", self.name))?;
+
+        if self.type_refs.len() != self.dword_with_type_ref.len() {
+            anyhow::bail!(
+                "fn '{}': {} type_refs but {} dword_with_type_ref entries",
+                self.name,
+                self.type_refs.len(),
+                self.dword_with_type_ref.len()
+            );
+        }
+        writer.write_u32::<LittleEndian>(self.type_refs.len() as u32)?;
+        for (type_ref, dword) in self.type_refs.iter().zip(&self.dword_with_type_ref) {
+            type_ref.write(writer)?;
+            writer.write_u32::<LittleEndian>(*dword)?;
+        }
+
+        writer.write_u32::<LittleEndian>(self.unknown_dword)?;
+        self.type_ref.write(writer)?;
+        writer.write_u32::<LittleEndian>(self.dword_vec.len() as u32)?;
+        for dword in &self.dword_vec {
+            writer.write_u32::<LittleEndian>(*dword)?;
+        }
+
+        Ok(())
+    }
+
+    /// Inverse of [`Self::read_instructions`]: the in-memory stream
+    /// pads every opcode to a 4-byte word, the file stores only the
+    /// opcode byte followed by its operands.
+    fn write_instructions(writer: &mut dyn Write, inst: &[u8]) -> anyhow::Result<()> {
+        let mut pc = 0;
+        while pc < inst.len() {
+            let len = inst_length(inst[pc])?;
+            if pc + len > inst.len() {
+                anyhow::bail!(
+                    "opcode {:#04x} at inst-byte {} runs past the end of the stream ({} bytes)",
+                    inst[pc],
+                    pc,
+                    inst.len()
+                );
+            }
+            writer.write_u8(inst[pc])?;
+            writer.write_all(&inst[pc + 4..pc + len])?;
+            pc += len;
+        }
+
+        Ok(())
+    }
+
+    /// Rebuild the decoded [`Instruction`] list (`inst2`) from the
+    /// padded instruction stream. Used after `inst` has been
+    /// assembled or edited in place.
+    pub fn decode_instructions(inst: &[u8]) -> anyhow::Result<Vec<Instruction>> {
+        let mut pc = 0;
+        let mut instructions = vec![];
+        while pc < inst.len() {
+            let len = inst_length(inst[pc])?;
+            if pc + len > inst.len() {
+                anyhow::bail!(
+                    "opcode {:#04x} at inst-byte {} runs past the end of the stream ({} bytes)",
+                    inst[pc],
+                    pc,
+                    inst.len()
+                );
+            }
+            instructions.push(Instruction {
+                inst: inst[pc] as u32,
+                params: inst[pc + 4..pc + len].to_vec(),
+            });
+            pc += len;
+        }
+
+        Ok(instructions)
+    }
+
     /// Minimal `ScriptFunction` constructor for VM unit tests.
     ///
     /// Real functions are deserialised from PAL4 `.csb` modules; this
@@ -186,6 +309,7 @@ impl ScriptFunction {
             unknown: 0,
             type_ref: ScriptTypeReference {
                 name: String::new(),
+                raw_name: None,
             },
             unknown2: 0,
             unknown3: 0,
@@ -194,6 +318,7 @@ impl ScriptFunction {
         };
         Self {
             name: name.to_string(),
+            raw_name: None,
             ret_type: stub_type.clone(),
             param_types: Vec::new(),
             unknown_dword1: 0,
@@ -204,6 +329,7 @@ impl ScriptFunction {
             unknown_dword: 0,
             type_ref: ScriptTypeReference {
                 name: String::new(),
+                raw_name: None,
             },
             dword_vec: Vec::new(),
         }
@@ -284,6 +410,11 @@ impl ScriptFunction {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScriptModule {
+    /// Leading 4 bytes of the file. The parser has never needed them;
+    /// they are kept verbatim so [`ScriptModule::write`] reproduces
+    /// the input byte for byte.
+    #[serde(default)]
+    pub header: [u8; 4],
     pub type_defs: Vec<ScriptTypeDefinition>,
     pub type_refs: Vec<ScriptTypeReference>,
     pub named_global_count: usize,
@@ -300,6 +431,10 @@ pub struct ScriptModule {
 
     pub functions: Vec<Arc<ScriptFunction>>,
     pub strings: Vec<String>,
+    /// Bytes each of `strings` was stored as, by index. Strings past
+    /// the end of this list, or edited since, are encoded afresh.
+    #[serde(skip)]
+    pub raw_strings: Vec<Vec<u8>>,
     pub astruct_vec2: Vec<ScriptFunction>,
 
     /// Per-class global variable declarations exported by this
@@ -308,6 +443,11 @@ pub struct ScriptModule {
     /// M16, Q04, Q05, Q11). See `NamedGlobal` for layout.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub named_globals: Vec<NamedGlobal>,
+
+    /// Bytes after the last `astruct_vec2` entry. Empty in every
+    /// shipped module; kept so unexpected padding survives a rewrite.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trailing: Vec<u8>,
 }
 
 /// One entry in a PAL4 module's named-globals block. The block is a
@@ -338,6 +478,10 @@ pub struct ScriptModule {
 pub struct NamedGlobal {
     /// Variable / class name (e.g. `"LL_002"`, `"LL_yan"`).
     pub name: String,
+    /// Bytes `name` was stored as, so [`ScriptModule::write`] can
+    /// reproduce them while `name` is unchanged.
+    #[serde(skip)]
+    pub raw_name: Option<Vec<u8>>,
     /// AngelScript type tag. Observed values are `0x3E` ('>') and
     /// `0x3C` ('<'); the exact AS-type-id semantics haven't been
     /// fully decoded yet, so the field is surfaced verbatim.
@@ -354,8 +498,87 @@ impl ScriptModule {
         Self::read(&mut cursor)
     }
 
+    /// Name the module's named-globals block declares for shared
+    /// global `slot`, if any.
+    pub fn shared_global_name(&self, slot: u32) -> Option<&str> {
+        self.named_globals
+            .iter()
+            .find(|global| global.index == slot)
+            .map(|global| global.name.as_str())
+    }
+
+    /// Serialise the module back into `.csb` form. For a module
+    /// produced by [`Self::read_from_buffer`] and left untouched the
+    /// output is byte-identical to the input.
+    pub fn write_to_vec(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = vec![];
+        self.write(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    pub fn write(&self, writer: &mut dyn Write) -> anyhow::Result<()> {
+        writer.write_all(&self.header)?;
+
+        writer.write_u32::<LittleEndian>(self.type_defs.len() as u32)?;
+        for (t, type_def) in self.type_defs.iter().enumerate() {
+            type_def
+                .write(writer)
+                .with_context(|| format!("type_defs[{}]", t))?;
+        }
+
+        writer.write_u32::<LittleEndian>(self.type_refs.len() as u32)?;
+        for (t, type_ref) in self.type_refs.iter().enumerate() {
+            type_ref
+                .write(writer)
+                .with_context(|| format!("type_refs[{}]", t))?;
+        }
+
+        // Named-globals block; see `read` for the layout.
+        writer.write_u32::<LittleEndian>(self.named_globals.len() as u32)?;
+        for (i, global) in self.named_globals.iter().enumerate() {
+            write_string(writer, &global.name, global.raw_name.as_deref())
+                .with_context(|| format!("named_globals[{}].name", i))?;
+            writer.write_u8(0)?;
+            writer.write_u32::<LittleEndian>(global.kind)?;
+            writer.write_all(&[0u8; 8])?;
+            writer.write_u32::<LittleEndian>(i as u32)?;
+        }
+        writer.write_u32::<LittleEndian>(self.named_globals.len() as u32)?;
+
+        self.module_loading
+            .write(writer)
+            .context("module_loading")?;
+        self.module_unloading
+            .write(writer)
+            .context("module_unloading")?;
+
+        writer.write_u32::<LittleEndian>(self.functions.len() as u32)?;
+        for (f, function) in self.functions.iter().enumerate() {
+            function
+                .write(writer)
+                .with_context(|| format!("functions[{}]", f))?;
+        }
+
+        writer.write_u32::<LittleEndian>(self.strings.len() as u32)?;
+        for (s, string) in self.strings.iter().enumerate() {
+            let raw = self.raw_strings.get(s).map(Vec::as_slice);
+            write_string(writer, string, raw).with_context(|| format!("strings[{}]", s))?;
+        }
+
+        writer.write_u32::<LittleEndian>(self.astruct_vec2.len() as u32)?;
+        for (f, function) in self.astruct_vec2.iter().enumerate() {
+            function
+                .write(writer)
+                .with_context(|| format!("astruct_vec2[{}]", f))?;
+        }
+
+        writer.write_all(&self.trailing)?;
+        Ok(())
+    }
+
     fn read(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<Self> {
-        cursor.set_position(4);
+        let mut header = [0u8; 4];
+        cursor.read_exact(&mut header).context("header")?;
 
         let type_def_count = cursor.read_u32_le()?;
         let mut type_defs = vec![];
@@ -402,7 +625,7 @@ impl ScriptModule {
                 .read_u32_le()
                 .with_context(|| format!("named_globals[{}].strlen", i))?
                 as usize;
-            let name = read_raw_string(cursor, strlen)
+            let (name, raw_name) = read_raw_string(cursor, strlen)
                 .with_context(|| format!("named_globals[{}].name", i))?;
             let null = cursor
                 .read_u8()
@@ -435,7 +658,12 @@ impl ScriptModule {
                     index
                 );
             }
-            named_globals.push(NamedGlobal { name, kind, index });
+            named_globals.push(NamedGlobal {
+                name,
+                raw_name: Some(raw_name),
+                kind,
+                index,
+            });
         }
 
         // Duplicate count terminator. Always present in the file
@@ -470,8 +698,11 @@ impl ScriptModule {
 
         let string_count = cursor.read_u32_le()? as usize;
         let mut strings = vec![];
+        let mut raw_strings = vec![];
         for s in 0..string_count {
-            strings.push(read_string(cursor).with_context(|| format!("strings[{}]", s))?);
+            let (string, raw) = read_string(cursor).with_context(|| format!("strings[{}]", s))?;
+            strings.push(string);
+            raw_strings.push(raw);
         }
 
         let astruct_count2 = cursor.read_u32_le()? as usize;
@@ -483,7 +714,10 @@ impl ScriptModule {
             );
         }
 
+        let trailing = cursor.get_ref()[cursor.position() as usize..].to_vec();
+
         Ok(Self {
+            header,
             type_defs,
             type_refs,
             named_global_count: named_globals_count,
@@ -492,8 +726,10 @@ impl ScriptModule {
             module_unloading,
             functions,
             strings,
+            raw_strings,
             astruct_vec2,
             named_globals,
+            trailing,
         })
     }
 
@@ -506,6 +742,7 @@ impl ScriptModule {
     pub(crate) fn test_module(functions: Vec<ScriptFunction>) -> Self {
         let stub_fn = ScriptFunction::test_function("", Vec::new());
         Self {
+            header: [0; 4],
             type_defs: Vec::new(),
             type_refs: Vec::new(),
             named_global_count: 0,
@@ -514,28 +751,151 @@ impl ScriptModule {
             module_unloading: stub_fn,
             functions: functions.into_iter().map(std::sync::Arc::new).collect(),
             strings: Vec::new(),
+            raw_strings: Vec::new(),
             astruct_vec2: Vec::new(),
             named_globals: Vec::new(),
+            trailing: Vec::new(),
         }
     }
 }
 
-fn read_string(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<String> {
+/// Read a length-prefixed string: the text the VM sees (see
+/// [`runtime_text`]) and the bytes it was stored as.
+fn read_string(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<(String, Vec<u8>)> {
     let pos = cursor.position();
     let len = cursor.read_u32_le()?;
-    cursor
-        .read_gbk_string(len as usize)
+    read_raw_string(cursor, len as usize)
         .with_context(|| format!("read_string len={} at {:#x}", len, pos))
 }
 
-/// Read exactly `len` bytes and decode them as GBK. Used for named-
-/// globals entries where the strlen is consumed separately from the
-/// name bytes (split across an outer field for entry 0).
-fn read_raw_string(cursor: &mut Cursor<&[u8]>, len: usize) -> anyhow::Result<String> {
+/// Read exactly `len` bytes, returning them with their [`runtime_text`].
+/// Used directly for named-globals entries where the strlen is consumed
+/// separately from the name bytes (split across an outer field for
+/// entry 0).
+fn read_raw_string(cursor: &mut Cursor<&[u8]>, len: usize) -> anyhow::Result<(String, Vec<u8>)> {
     let pos = cursor.position();
-    cursor
-        .read_gbk_string(len)
-        .with_context(|| format!("read_raw_string len={} at {:#x}", len, pos))
+    let bytes = cursor
+        .read_u8_vec(len)
+        .with_context(|| format!("read_raw_string len={} at {:#x}", len, pos))?;
+    Ok((runtime_text(&bytes), bytes))
+}
+
+/// Write `text`, reusing `raw` (the bytes it was read from) while it
+/// still decodes to `text`, so untouched strings keep their NULs and
+/// stray bytes.
+fn write_string(writer: &mut dyn Write, text: &str, raw: Option<&[u8]>) -> anyhow::Result<()> {
+    let bytes = match raw {
+        Some(raw) if runtime_text(raw) == text => raw.to_vec(),
+        _ => encode_gbk(text)?,
+    };
+    writer.write_u32::<LittleEndian>(bytes.len() as u32)?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Decode a stored string the way the VM has always seen it, same as
+/// `ReadExt::read_gbk_string`: everything from the first NUL on is
+/// dropped, and so are bytes that aren't GBK.
+pub(crate) fn runtime_text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    encoding::all::GBK
+        .decode(&bytes[..end], DecoderTrap::Ignore)
+        .unwrap_or_default()
+}
+
+/// How a string is spelled in an assembler listing: the stored bytes
+/// through [`decode_gbk`] while they still match `text`, so the
+/// listing reassembles byte for byte.
+pub(crate) fn listing_text(text: &str, raw: Option<&[u8]>) -> String {
+    match raw {
+        Some(raw) if runtime_text(raw) == text => decode_gbk(raw),
+        _ => text.to_string(),
+    }
+}
+
+/// Inverse of [`listing_text`]: the runtime text and stored bytes for
+/// a string spelled in a listing.
+pub(crate) fn parse_listing_text(listing: &str) -> anyhow::Result<(String, Vec<u8>)> {
+    let bytes = encode_gbk(listing)?;
+    Ok((runtime_text(&bytes), bytes))
+}
+
+/// First of the private-use code points standing in for raw bytes
+/// `0x80..=0xff` that aren't part of a GBK character.
+const RAW_BYTE_BASE: u32 = 0xf700;
+
+fn raw_byte_char(byte: u8) -> char {
+    char::from_u32(RAW_BYTE_BASE + byte as u32).unwrap()
+}
+
+/// Decode GBK without losing bytes: NULs are kept as `'\0'`, and bytes
+/// that don't form a GBK character come out as `U+F780..=U+F7FF`, which
+/// [`encode_gbk`] turns back into the same byte. Only listings use
+/// this; the VM sees [`runtime_text`].
+fn decode_gbk(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if byte < 0x80 {
+            out.push(byte as char);
+            i += 1;
+            continue;
+        }
+
+        let pair = bytes.get(i..i + 2).and_then(|pair| {
+            let text = encoding::all::GBK.decode(pair, DecoderTrap::Strict).ok()?;
+            let mut chars = text.chars();
+            let c = chars.next()?;
+            let round_trips = chars.next().is_none()
+                && !is_raw_byte_char(c)
+                && encoding::all::GBK
+                    .encode(&text, EncoderTrap::Strict)
+                    .is_ok_and(|encoded| encoded == pair);
+            round_trips.then_some(c)
+        });
+        match pair {
+            Some(c) => {
+                out.push(c);
+                i += 2;
+            }
+            None => {
+                out.push(raw_byte_char(byte));
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn encode_gbk(s: &str) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut buf = [0u8; 4];
+    for c in s.chars() {
+        if is_raw_byte_char(c) {
+            bytes.push((c as u32 - RAW_BYTE_BASE) as u8);
+            continue;
+        }
+
+        let encoded = encoding::all::GBK
+            .encode(c.encode_utf8(&mut buf), EncoderTrap::Strict)
+            .map_err(|e| anyhow::anyhow!("cannot encode {:?} as GBK: {}", s, e))?;
+        bytes.extend(encoded);
+    }
+    Ok(bytes)
+}
+
+fn is_raw_byte_char(c: char) -> bool {
+    (RAW_BYTE_BASE + 0x80..=RAW_BYTE_BASE + 0xff).contains(&(c as u32))
+}
+
+/// In-memory length (opcode word + operands) of `opcode`, or an error
+/// for opcodes the VM doesn't know.
+pub(crate) fn inst_length(opcode: u8) -> anyhow::Result<usize> {
+    match INST_LENGTH[opcode as usize] {
+        len if len >= 4 => Ok(len),
+        _ => anyhow::bail!("unknown opcode {:#04x} ({0})", opcode),
+    }
 }
 
 const INST_LENGTH: [usize; 256] = [
@@ -561,6 +921,59 @@ const INST_LENGTH: [usize; 256] = [
 mod tests {
     use super::*;
 
+    fn stored(bytes: &[u8]) -> Vec<u8> {
+        let mut buffer = (bytes.len() as u32).to_le_bytes().to_vec();
+        buffer.extend_from_slice(bytes);
+        buffer
+    }
+
+    #[test]
+    fn strings_decode_for_the_vm_as_before() {
+        // "云天河", a NUL and garbage after it.
+        let with_nul = [0xd4, 0xc6, 0xcc, 0xec, 0xba, 0xd3, 0x00, b'a', 0x80];
+        // "ab", a byte GBK never uses, "c".
+        let with_bad_byte = [b'a', b'b', 0xff, b'c'];
+
+        for (bytes, text) in [(&with_nul[..], "云天河"), (&with_bad_byte[..], "abc")] {
+            let buffer = stored(bytes);
+            let mut cursor = Cursor::new(buffer.as_slice());
+            let (read, raw) = read_string(&mut cursor).unwrap();
+            assert_eq!(read, text);
+            assert_eq!(
+                read,
+                Cursor::new(bytes).read_gbk_string(bytes.len()).unwrap()
+            );
+
+            let mut written = vec![];
+            write_string(&mut written, &read, Some(&raw)).unwrap();
+            assert_eq!(written, buffer);
+        }
+    }
+
+    #[test]
+    fn edited_strings_are_encoded_afresh() {
+        let raw = [b'a', 0x00, b'b'];
+        let mut written = vec![];
+        write_string(&mut written, "云", Some(&raw)).unwrap();
+        assert_eq!(written, stored(&[0xd4, 0xc6]));
+    }
+
+    #[test]
+    fn listings_keep_raw_bytes() {
+        // "云天河", a NUL, a lone lead byte, an invalid trail and a
+        // trailing lead byte.
+        let bytes = [
+            0xd4, 0xc6, 0xcc, 0xec, 0xba, 0xd3, 0x00, b'a', 0x80, 0x81, 0x20, 0xff,
+        ];
+        let listing = listing_text("云天河", Some(&bytes));
+        assert!(listing.starts_with("云天河\0a"));
+        assert_eq!(
+            parse_listing_text(&listing).unwrap(),
+            ("云天河".to_string(), bytes.to_vec())
+        );
+        assert_eq!(listing_text("edited", Some(&bytes)), "edited");
+    }
+
     /// End-to-end parse of every PAL4 `.csb` reachable through the
     /// vfs at `PAL4_ROOT`. Skipped when the env var is unset so the
     /// test suite stays runnable on hosts without the game install
@@ -4,16 +4,18 @@ use std::{cell::RefCell, rc::Rc};
 use super::debug::{DebugIpcClient, Notification, Request};
 
 use super::{
+    dap::ScriptDebugger,
     global_context::{GlobalFunctionContinuation, ScriptGlobalContext},
     module::{ScriptFunction, ScriptModule},
     trace::{BranchKind, GlobalScope, TraceEvent, TraceEventKind, TraceSink},
 };
+use crate::scripting::debugger::{DebugLocation, DebugState, DebugValue};
 
 #[derive(Clone)]
 pub(crate) struct ScriptFunctionContext {
     pub(crate) module: Rc<RefCell<ScriptModule>>,
-    function_index: usize,
-    pc: usize,
+    pub(crate) function_index: usize,
+    pub(crate) pc: usize,
 }
 
 impl ScriptFunctionContext {
@@ -34,16 +36,16 @@ pub struct ScriptVm<TAppContext: 'static> {
     #[cfg(enable_debug)]
     debug_client: DebugIpcClient,
 
-    call_stack: Vec<ScriptFunctionContext>,
+    pub(crate) call_stack: Vec<ScriptFunctionContext>,
 
     pub(crate) heap: Vec<Option<String>>,
     pub(crate) robj: usize,
 
     stack: Vec<u8>,
-    sp: usize,
-    fp: usize,
-    r1: u32,
-    r2: u32,
+    pub(crate) sp: usize,
+    pub(crate) fp: usize,
+    pub(crate) r1: u32,
+    pub(crate) r2: u32,
 
     /// Set when a stack access goes out of bounds. The execution loop
     /// checks this and aborts the current script gracefully instead of
@@ -63,6 +65,14 @@ pub struct ScriptVm<TAppContext: 'static> {
     /// when a sink is installed; held in a `Cell` so the read-only
     /// emit path doesn't need `&mut self`.
     trace_seq: std::cell::Cell<u64>,
+
+    /// Optional DAP debugger. Checked before every instruction when
+    /// installed; see `super::dap`.
+    debugger: Option<Rc<RefCell<ScriptDebugger>>>,
+
+    /// In-game / agent debugger state, see `crate::scripting::debugger`.
+    /// Checked before every instruction like the DAP debugger.
+    debug_state: RefCell<DebugState>,
 }
 
 impl<TAppContext: 'static> ScriptVm<TAppContext> {
@@ -134,6 +144,9 @@ impl<TAppContext: 'static> ScriptVm<TAppContext> {
 
             trace_sink: None,
             trace_seq: std::cell::Cell::new(0),
+
+            debugger: None,
+            debug_state: RefCell::new(DebugState::new()),
         };
 
         vm.debug_update_module();
@@ -207,6 +220,83 @@ impl<TAppContext: 'static> ScriptVm<TAppContext> {
         self.trace_sink.is_some()
     }
 
+    /// Install (or remove) the DAP debugger. The same debugger can be
+    /// handed to each VM a host creates so breakpoints survive a
+    /// director rebuild.
+    pub fn set_debugger(&mut self, debugger: Option<Rc<RefCell<ScriptDebugger>>>) {
+        self.debugger = debugger;
+    }
+
+    /// Handle pending debugger requests. Returns `true` while either
+    /// the DAP debugger or the in-game debugger holds the VM stopped;
+    /// hosts should skip their own per-frame simulation then, so the
+    /// world freezes with the script.
+    pub fn poll_debugger(&mut self) -> bool {
+        if self.is_idle() {
+            self.debug_state.borrow_mut().poll_idle();
+        }
+        let held = self.debug_state.borrow().is_stopped();
+
+        let Some(debugger) = self.debugger.clone() else {
+            return held;
+        };
+        let mut debugger = debugger.borrow_mut();
+        debugger.poll(self);
+        debugger.is_stopped() || held
+    }
+
+    pub(crate) fn debugger_state(&self) -> &RefCell<DebugState> {
+        &self.debug_state
+    }
+
+    /// The function and `pc` about to run, for the in-game debugger.
+    pub(crate) fn debugger_location(&self) -> Option<DebugLocation> {
+        let ctx = self.context.as_ref()?;
+        Some(DebugLocation {
+            procedure: self.current_fn_name(),
+            offset: ctx.pc as u32,
+        })
+    }
+
+    /// Shared globals, named `g{slot}` as in the DAP variables view and
+    /// the decompiler.
+    pub(crate) fn debugger_variables(&self) -> Vec<(String, DebugValue)> {
+        self.g
+            .borrow()
+            .vars
+            .iter()
+            .enumerate()
+            .map(|(slot, word)| (format!("g{}", slot), DebugValue::Int(*word as i32 as i64)))
+            .collect()
+    }
+
+    pub(crate) fn debugger_read(&self, name: &str) -> Option<DebugValue> {
+        let slot: usize = name.strip_prefix('g')?.parse().ok()?;
+        let word = *self.g.borrow().vars.get(slot)?;
+        Some(DebugValue::Int(word as i32 as i64))
+    }
+
+    /// Per-instruction debugger check, for an attached DAP client and
+    /// then the in-game debugger. `true` when the VM must hold before
+    /// the instruction at the current `pc`.
+    fn debugger_break(&self) -> bool {
+        if let Some(debugger) = &self.debugger
+            && debugger.borrow_mut().should_break(self)
+        {
+            return true;
+        }
+        if !self.debug_state.borrow().is_active() {
+            return false;
+        }
+
+        let Some(location) = self.debugger_location() else {
+            return false;
+        };
+        self.debug_state
+            .borrow_mut()
+            .check(location, |name| self.debugger_read(name))
+    }
+
     /// Emit a trace event. Inlined and self-no-op when the sink is
     /// absent so the unobserved path is a single `Option::is_none`.
     #[inline]
@@ -414,8 +504,15 @@ impl<TAppContext: 'static> ScriptVm<TAppContext> {
     }
 
     pub fn execute(&mut self, delta_sec: f32) {
+        if self.poll_debugger() {
+            return;
+        }
+
         loop {
             if self.context.is_none() {
+                if let Some(debugger) = self.debugger.as_ref() {
+                    debugger.borrow_mut().script_finished();
+                }
                 return;
             }
 
@@ -487,6 +584,10 @@ impl<TAppContext: 'static> ScriptVm<TAppContext> {
                 continue;
             }
 
+            if self.debugger_break() {
+                return;
+            }
+
             let inst = self.read_inst(&function);
             macro_rules! command {
                 ($cmd_name: ident $(, $param_name: ident : $param_type: ident)*) => {{
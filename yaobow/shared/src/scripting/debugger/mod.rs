@@ -0,0 +1,466 @@
+//! Debugger core shared by every script VM.
+//!
+//! Each VM owns a [`DebugState`] and implements [`ScriptDebugTarget`] on
+//! top of it. The VM calls [`DebugState::check`] at its own execution
+//! granularity and holds (skips its update) while the state reports a
+//! stop:
+//!
+//! * `SceVm` checks before every instruction. Offsets are code offsets
+//!   inside the procedure, the addresses `sce_asm dump` prints.
+//! * `ScriptVm` checks before every instruction. Offsets are `pc`
+//!   values, the addresses `csb_asm` prints.
+//! * `Lua5032Vm` checks from a line / call debug hook. Offsets are line
+//!   numbers, with 0 for the call itself. Lua 5.0 can't yield from a
+//!   hook, so a stop takes effect at the coroutine's next yield; the
+//!   reported location is still the one that triggered it.
+//!
+//! The in-game window ([`DebuggerWindow`]) and the agent server's
+//! `/v1/debug/*` routes both drive a VM through this trait only.
+
+mod window;
+
+pub use window::DebuggerWindow;
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::Serialize;
+
+/// A scalar read out of a VM for display or watch comparison.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum DebugValue {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    /// Type tag for values that aren't walked (Lua tables, functions…).
+    Other(String),
+}
+
+impl fmt::Display for DebugValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebugValue::Nil => write!(f, "nil"),
+            DebugValue::Bool(v) => write!(f, "{}", v),
+            DebugValue::Int(v) => write!(f, "{}", v),
+            DebugValue::Float(v) => write!(f, "{}", v),
+            DebugValue::Str(v) => write!(f, "{:?}", v),
+            DebugValue::Other(tag) => write!(f, "<{}>", tag),
+        }
+    }
+}
+
+/// A procedure / function the debugger can break in.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DebugProcedure {
+    pub name: String,
+    /// Numeric id, for VMs that call procedures by id (SCE).
+    pub id: Option<u32>,
+}
+
+/// Where a VM is about to execute. See the module docs for what
+/// `offset` means per VM.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DebugLocation {
+    pub procedure: String,
+    pub offset: u32,
+}
+
+impl fmt::Display for DebugLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} @ {:#06x}", self.procedure, self.offset)
+    }
+}
+
+/// Procedure names are matched ignoring ASCII case, like the SCE VM
+/// resolves procedures by name.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Breakpoint {
+    pub procedure: String,
+    pub offset: u32,
+}
+
+impl Breakpoint {
+    fn matches(&self, location: &DebugLocation) -> bool {
+        self.offset == location.offset && self.procedure.eq_ignore_ascii_case(&location.procedure)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StopReason {
+    Pause,
+    Step,
+    Breakpoint,
+    WatchChanged {
+        name: String,
+        old: Option<DebugValue>,
+        new: Option<DebugValue>,
+    },
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |v: &Option<DebugValue>| match v {
+            Some(v) => v.to_string(),
+            None => "-".to_string(),
+        };
+        match self {
+            StopReason::Pause => write!(f, "pause"),
+            StopReason::Step => write!(f, "step"),
+            StopReason::Breakpoint => write!(f, "breakpoint"),
+            StopReason::WatchChanged { name, old, new } => {
+                write!(f, "{}: {} -> {}", name, value(old), value(new))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DebugStop {
+    pub reason: StopReason,
+    /// `None` when a pause stopped a VM that had no script running.
+    pub location: Option<DebugLocation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum RunMode {
+    #[default]
+    Running,
+    PauseRequested,
+    Step,
+    Stopped,
+}
+
+/// Breakpoints, watches and run mode of one VM.
+#[derive(Debug, Default)]
+pub struct DebugState {
+    breakpoints: BTreeSet<Breakpoint>,
+    /// Watched names with the value seen at the last check.
+    watches: BTreeMap<String, Option<DebugValue>>,
+    mode: RunMode,
+    stop: Option<DebugStop>,
+    /// Set when leaving a stop so the VM doesn't stop again on the
+    /// location it is sitting on.
+    resumed: bool,
+}
+
+impl DebugState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.breakpoints.iter()
+    }
+
+    /// Returns `false` if the breakpoint was already set.
+    pub fn set_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+        self.breakpoints.insert(breakpoint)
+    }
+
+    /// Returns `false` if no such breakpoint was set.
+    pub fn clear_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        self.breakpoints.remove(breakpoint)
+    }
+
+    /// Watched names with the value seen at the last check.
+    pub fn watches(&self) -> impl Iterator<Item = (&str, Option<&DebugValue>)> {
+        self.watches.iter().map(|(k, v)| (k.as_str(), v.as_ref()))
+    }
+
+    /// Watch `name`, starting from its `current` value so only later
+    /// changes stop the VM.
+    pub fn add_watch(&mut self, name: String, current: Option<DebugValue>) {
+        self.watches.insert(name, current);
+    }
+
+    /// Returns `false` if `name` wasn't watched.
+    pub fn remove_watch(&mut self, name: &str) -> bool {
+        self.watches.remove(name).is_some()
+    }
+
+    /// Stop at the next check. No-op while already stopped.
+    pub fn pause(&mut self) {
+        if self.mode != RunMode::Stopped {
+            self.mode = RunMode::PauseRequested;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        self.leave_stop();
+        self.mode = RunMode::Running;
+    }
+
+    /// Run to the next check and stop there.
+    pub fn step(&mut self) {
+        self.leave_stop();
+        self.mode = RunMode::Step;
+    }
+
+    /// True while the VM must hold.
+    pub fn is_stopped(&self) -> bool {
+        self.mode == RunMode::Stopped
+    }
+
+    /// Why the VM is stopped, while it is.
+    pub fn stop(&self) -> Option<&DebugStop> {
+        self.stop.as_ref()
+    }
+
+    /// Whether a check can do anything at all. VMs skip building the
+    /// location (and Lua its hooks) while it can't. A pending `resumed`
+    /// counts, so the check that clears it isn't skipped.
+    pub fn is_active(&self) -> bool {
+        self.mode != RunMode::Running
+            || self.resumed
+            || !self.breakpoints.is_empty()
+            || !self.watches.is_empty()
+    }
+
+    /// Called by a VM with no script to run: a pending pause stops at
+    /// once, there being no instruction to stop on.
+    pub fn poll_idle(&mut self) {
+        if self.mode == RunMode::PauseRequested {
+            self.enter_stop(StopReason::Pause, None);
+        }
+    }
+
+    /// Per-instruction (or per-line) check before the VM executes
+    /// `location`. `read` looks up watched names. Returns `true` when
+    /// the VM must hold.
+    pub fn check(
+        &mut self,
+        location: DebugLocation,
+        read: impl Fn(&str) -> Option<DebugValue>,
+    ) -> bool {
+        if self.mode == RunMode::Stopped {
+            return true;
+        }
+
+        let changed = self.refresh_watches(&read);
+        if std::mem::take(&mut self.resumed) {
+            return false;
+        }
+
+        let reason = match self.mode {
+            RunMode::PauseRequested => Some(StopReason::Pause),
+            RunMode::Step => Some(StopReason::Step),
+            _ if self.breakpoints.iter().any(|b| b.matches(&location)) => {
+                Some(StopReason::Breakpoint)
+            }
+            _ => changed,
+        };
+
+        match reason {
+            Some(reason) => {
+                self.enter_stop(reason, Some(location));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Update every watch, returning the first one that changed.
+    fn refresh_watches(
+        &mut self,
+        read: &impl Fn(&str) -> Option<DebugValue>,
+    ) -> Option<StopReason> {
+        let mut changed = None;
+        for (name, last) in self.watches.iter_mut() {
+            let value = read(name);
+            if value != *last {
+                let old = std::mem::replace(last, value.clone());
+                changed.get_or_insert(StopReason::WatchChanged {
+                    name: name.clone(),
+                    old,
+                    new: value,
+                });
+            }
+        }
+
+        changed
+    }
+
+    fn enter_stop(&mut self, reason: StopReason, location: Option<DebugLocation>) {
+        log::info!(
+            "script debugger: stopped ({}) at {}",
+            reason,
+            location
+                .as_ref()
+                .map_or("<idle>".to_string(), |l| l.to_string())
+        );
+        self.mode = RunMode::Stopped;
+        self.stop = Some(DebugStop { reason, location });
+    }
+
+    fn leave_stop(&mut self) {
+        if self.mode == RunMode::Stopped {
+            self.resumed = self.stop.as_ref().is_some_and(|s| s.location.is_some());
+        }
+        self.stop = None;
+    }
+}
+
+/// A script VM the shared debugger can drive.
+///
+/// Everything takes `&self`: the debug state sits behind a `RefCell`
+/// so the agent dispatchers and the debug window can work through the
+/// shared references they hold.
+pub trait ScriptDebugTarget {
+    /// Short VM name for display (`"sce"`, `"angelscript"`, `"lua"`).
+    fn debug_vm_name(&self) -> &'static str;
+
+    /// `true` when a stop only holds the VM at its next yield, so the
+    /// script may run past the reported location first. Only
+    /// `Lua5032Vm` does this; see the module docs.
+    fn debug_stops_deferred(&self) -> bool {
+        false
+    }
+
+    fn debug_state(&self) -> &RefCell<DebugState>;
+
+    /// Procedures / functions that can hold a breakpoint.
+    fn debug_procedures(&self) -> Vec<DebugProcedure>;
+
+    /// Where the VM is, or `None` when it has no script running.
+    fn debug_location(&self) -> Option<DebugLocation>;
+
+    /// Every readable global and flag, in display order.
+    fn debug_variables(&self) -> Vec<(String, DebugValue)>;
+
+    /// Read one variable by the name [`Self::debug_variables`] lists it
+    /// under.
+    fn debug_read(&self, name: &str) -> Option<DebugValue>;
+
+    /// Watch `name` from its current value on.
+    fn add_watch(&self, name: &str) {
+        let value = self.debug_read(name);
+        self.debug_state()
+            .borrow_mut()
+            .add_watch(name.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn at(procedure: &str, offset: u32) -> DebugLocation {
+        DebugLocation {
+            procedure: procedure.to_string(),
+            offset,
+        }
+    }
+
+    fn none(_: &str) -> Option<DebugValue> {
+        None
+    }
+
+    #[test]
+    fn breakpoints_stop_once_and_resume_past_the_location() {
+        let mut state = DebugState::new();
+        state.set_breakpoint(Breakpoint {
+            procedure: "Main".to_string(),
+            offset: 8,
+        });
+
+        assert!(!state.check(at("main", 0), none));
+        assert!(state.check(at("main", 8), none));
+        assert_eq!(state.stop().unwrap().reason, StopReason::Breakpoint);
+
+        // Holding: the VM keeps asking and keeps being told to wait.
+        assert!(state.check(at("main", 8), none));
+
+        state.resume();
+        assert!(state.stop().is_none());
+        assert!(!state.check(at("main", 8), none));
+        assert!(!state.check(at("main", 12), none));
+        assert!(state.check(at("MAIN", 8), none));
+    }
+
+    #[test]
+    fn pause_and_step_stop_at_the_next_check() {
+        let mut state = DebugState::new();
+        state.pause();
+        assert!(state.check(at("f", 4), none));
+        assert_eq!(state.stop().unwrap().reason, StopReason::Pause);
+
+        state.step();
+        assert!(!state.check(at("f", 4), none));
+        assert!(state.check(at("f", 6), none));
+        assert_eq!(
+            state.stop().unwrap(),
+            &DebugStop {
+                reason: StopReason::Step,
+                location: Some(at("f", 6)),
+            }
+        );
+
+        state.resume();
+        assert!(state.is_active());
+        assert!(!state.check(at("f", 6), none));
+        assert!(!state.is_active());
+    }
+
+    #[test]
+    fn pausing_an_idle_vm_stops_without_a_location() {
+        let mut state = DebugState::new();
+        state.poll_idle();
+        assert!(!state.is_stopped());
+
+        state.pause();
+        state.poll_idle();
+        assert!(state.is_stopped());
+        assert_eq!(state.stop().unwrap().location, None);
+
+        // Nothing to skip past when the next script starts.
+        state.resume();
+        state.set_breakpoint(Breakpoint {
+            procedure: "f".to_string(),
+            offset: 0,
+        });
+        assert!(state.check(at("f", 0), none));
+    }
+
+    #[test]
+    fn watches_stop_when_the_value_changes() {
+        let vars = RefCell::new(HashMap::from([("$-1".to_string(), 0i64)]));
+        let read = |name: &str| vars.borrow().get(name).map(|v| DebugValue::Int(*v));
+
+        let mut state = DebugState::new();
+        state.add_watch("$-1".to_string(), read("$-1"));
+        state.add_watch("$3".to_string(), read("$3"));
+        assert!(!state.check(at("p", 0), read));
+
+        vars.borrow_mut().insert("$-1".to_string(), 5);
+        assert!(state.check(at("p", 4), read));
+        assert_eq!(
+            state.stop().unwrap().reason,
+            StopReason::WatchChanged {
+                name: "$-1".to_string(),
+                old: Some(DebugValue::Int(0)),
+                new: Some(DebugValue::Int(5)),
+            }
+        );
+        assert_eq!(
+            state.watches().collect::<Vec<_>>(),
+            vec![("$-1", Some(&DebugValue::Int(5))), ("$3", None)]
+        );
+
+        // A change made while stopped is absorbed on resume rather than
+        // stopping again straight away.
+        vars.borrow_mut().insert("$-1".to_string(), 6);
+        state.resume();
+        assert!(!state.check(at("p", 4), read));
+        assert!(!state.check(at("p", 8), read));
+
+        assert!(state.remove_watch("$-1"));
+        vars.borrow_mut().insert("$3".to_string(), 1);
+        assert!(state.check(at("p", 12), read));
+    }
+}
@@ -0,0 +1,190 @@
+use imgui::{TreeNodeFlags, Ui};
+
+use super::{Breakpoint, ScriptDebugTarget};
+
+/// Cap on the rows the procedure / variable lists draw per frame;
+/// filter to narrow them down.
+const MAX_LIST_ROWS: usize = 300;
+
+/// The in-game script debugger window. Holds only input buffers; all
+/// debugger state lives on the target VM.
+#[derive(Default)]
+pub struct DebuggerWindow {
+    breakpoint_procedure: String,
+    breakpoint_offset: String,
+    watch_name: String,
+    procedure_filter: String,
+    variable_filter: String,
+}
+
+impl DebuggerWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn render(&mut self, ui: &Ui, target: &dyn ScriptDebugTarget) {
+        ui.window("Script Debugger")
+            .size([420., 560.], imgui::Condition::FirstUseEver)
+            .build(|| {
+                self.render_status(ui, target);
+                if ui.collapsing_header("Breakpoints", TreeNodeFlags::DEFAULT_OPEN) {
+                    self.render_breakpoints(ui, target);
+                }
+                if ui.collapsing_header("Watches", TreeNodeFlags::DEFAULT_OPEN) {
+                    self.render_watches(ui, target);
+                }
+                if ui.collapsing_header("Procedures", TreeNodeFlags::empty()) {
+                    self.render_procedures(ui, target);
+                }
+                if ui.collapsing_header("Variables", TreeNodeFlags::empty()) {
+                    self.render_variables(ui, target);
+                }
+            });
+    }
+
+    fn render_status(&mut self, ui: &Ui, target: &dyn ScriptDebugTarget) {
+        let state = target.debug_state();
+        ui.text(format!("VM: {}", target.debug_vm_name()));
+        if target.debug_stops_deferred() {
+            ui.text_disabled("Stops take effect at the script's next yield.");
+        }
+        match state.borrow().stop() {
+            Some(stop) => ui.text_colored(
+                [1., 0.6, 0.2, 1.],
+                format!(
+                    "Stopped: {} at {}",
+                    stop.reason,
+                    stop.location
+                        .as_ref()
+                        .map_or("<idle>".to_string(), |l| l.to_string())
+                ),
+            ),
+            None => ui.text("Running"),
+        }
+        match target.debug_location() {
+            Some(location) => ui.text(format!("Location: {}", location)),
+            None => ui.text("Location: <idle>"),
+        }
+
+        if ui.button("Pause") {
+            state.borrow_mut().pause();
+        }
+        ui.same_line();
+        if ui.button("Continue") {
+            state.borrow_mut().resume();
+        }
+        ui.same_line();
+        if ui.button("Step") {
+            state.borrow_mut().step();
+        }
+        ui.separator();
+    }
+
+    fn render_breakpoints(&mut self, ui: &Ui, target: &dyn ScriptDebugTarget) {
+        let state = target.debug_state();
+        let breakpoints: Vec<Breakpoint> = state.borrow().breakpoints().cloned().collect();
+        for (i, breakpoint) in breakpoints.iter().enumerate() {
+            if ui.small_button(format!("x##bp{}", i)) {
+                state.borrow_mut().clear_breakpoint(breakpoint);
+            }
+            ui.same_line();
+            ui.text(format!(
+                "{} @ {:#06x}",
+                breakpoint.procedure, breakpoint.offset
+            ));
+        }
+
+        ui.input_text("Procedure##bp", &mut self.breakpoint_procedure)
+            .build();
+        ui.input_text("Offset##bp", &mut self.breakpoint_offset)
+            .build();
+        if ui.button("Add breakpoint") {
+            let procedure = self.breakpoint_procedure.trim();
+            match parse_offset(&self.breakpoint_offset) {
+                Some(offset) if !procedure.is_empty() => {
+                    state.borrow_mut().set_breakpoint(Breakpoint {
+                        procedure: procedure.to_string(),
+                        offset,
+                    });
+                }
+                _ => log::warn!(
+                    "script debugger: bad breakpoint {:?} @ {:?}",
+                    self.breakpoint_procedure,
+                    self.breakpoint_offset
+                ),
+            }
+        }
+    }
+
+    fn render_watches(&mut self, ui: &Ui, target: &dyn ScriptDebugTarget) {
+        let watches: Vec<String> = target
+            .debug_state()
+            .borrow()
+            .watches()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        for (i, name) in watches.iter().enumerate() {
+            if ui.small_button(format!("x##watch{}", i)) {
+                target.debug_state().borrow_mut().remove_watch(name);
+            }
+            ui.same_line();
+            let value = target
+                .debug_read(name)
+                .map_or("-".to_string(), |v| v.to_string());
+            ui.text(format!("{} = {}", name, value));
+        }
+
+        ui.input_text("Name##watch", &mut self.watch_name).build();
+        if ui.button("Add watch") && !self.watch_name.trim().is_empty() {
+            target.add_watch(self.watch_name.trim());
+        }
+    }
+
+    fn render_procedures(&mut self, ui: &Ui, target: &dyn ScriptDebugTarget) {
+        ui.input_text("Filter##procedures", &mut self.procedure_filter)
+            .build();
+        ui.text_disabled("Click to fill in an entry breakpoint.");
+        let filter = self.procedure_filter.to_lowercase();
+        for procedure in target
+            .debug_procedures()
+            .into_iter()
+            .filter(|p| p.name.to_lowercase().contains(&filter))
+            .take(MAX_LIST_ROWS)
+        {
+            let label = match procedure.id {
+                Some(id) => format!("{} ({})", procedure.name, id),
+                None => procedure.name.clone(),
+            };
+            if ui.selectable(label) {
+                self.breakpoint_procedure = procedure.name;
+                self.breakpoint_offset = "0".to_string();
+            }
+        }
+    }
+
+    fn render_variables(&mut self, ui: &Ui, target: &dyn ScriptDebugTarget) {
+        ui.input_text("Filter##variables", &mut self.variable_filter)
+            .build();
+        ui.text_disabled("Click to watch.");
+        let filter = self.variable_filter.to_lowercase();
+        for (name, value) in target
+            .debug_variables()
+            .into_iter()
+            .filter(|(name, _)| name.to_lowercase().contains(&filter))
+            .take(MAX_LIST_ROWS)
+        {
+            if ui.selectable(format!("{} = {}", name, value)) {
+                target.add_watch(&name);
+            }
+        }
+    }
+}
+
+/// Decimal, or hex with a `0x` prefix like the listings print.
+fn parse_offset(text: &str) -> Option<u32> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
@@ -1,8 +1,25 @@
-use std::{cell::RefCell, os::raw::c_char, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::CStr,
+    os::raw::c_char,
+    rc::Rc,
+};
 
 use anyhow::bail;
-use encoding::{DecoderTrap, Encoding};
-use lua50_32_sys::lua_State;
+use encoding::{DecoderTrap, EncoderTrap, Encoding};
+use lua50_32_sys::{lua_Debug, lua_State};
+
+use crate::scripting::debugger::{
+    DebugLocation, DebugProcedure, DebugState, DebugValue, ScriptDebugTarget,
+};
+use crate::scripting::trace::{GlobalScope, TraceEventKind, TraceSink, Tracer};
+
+// `lua.h` defines the hook masks as `1 << LUA_HOOK*` macros, which
+// don't make it through the bindings.
+const LUA_HOOKCALL: i32 = 0;
+const LUA_MASKCALL: i32 = 1 << LUA_HOOKCALL;
+const LUA_MASKLINE: i32 = 1 << 2;
 
 pub struct Lua5032Vm<TContext> {
     // The Lua VM keeps raw pointers into this buffer; the field must stay
@@ -12,6 +29,23 @@ pub struct Lua5032Vm<TContext> {
     lua: *mut lua_State,
     thread: *mut lua_State,
     context: Rc<RefCell<TContext>>,
+    debug: Rc<LuaDebugHook>,
+}
+
+/// Debugger and trace state reachable from [`debug_hook`] and the
+/// engine's C functions, which only get the `lua_State` they run on.
+#[derive(Default)]
+struct LuaDebugHook {
+    state: RefCell<DebugState>,
+    /// Whether [`debug_hook`] is installed on the coroutine thread.
+    installed: Cell<bool>,
+    tracer: RefCell<Tracer>,
+}
+
+thread_local! {
+    /// Main state and coroutine thread pointers -> their VM's debugger
+    /// and trace state.
+    static DEBUG_HOOKS: RefCell<HashMap<usize, Rc<LuaDebugHook>>> = RefCell::new(HashMap::new());
 }
 
 impl<TContext> Lua5032Vm<TContext> {
@@ -56,6 +90,7 @@ impl<TContext> Lua5032Vm<TContext> {
                 lua,
                 thread,
                 context,
+                debug: register_debug_hook(lua, thread),
             })
         }
     }
@@ -87,6 +122,7 @@ impl<TContext> Lua5032Vm<TContext> {
                 lua,
                 thread,
                 context,
+                debug: register_debug_hook(lua, thread),
             }
         }
     }
@@ -186,18 +222,106 @@ impl<TContext> Lua5032Vm<TContext> {
         }
     }
 
+    /// Resume the coroutine until its next yield, returning the yielded
+    /// number. While the script debugger holds the VM this returns
+    /// `Ok(0.0)` without resuming.
     pub fn execute(&self) -> anyhow::Result<f32> {
+        if self.debug_held() {
+            return Ok(0.);
+        }
+
+        self.sync_debug_hook();
         unsafe {
             let ret = lua50_32_sys::lua_resume(self.thread, 0);
             if ret != 0 {
                 bail!(get_error(self.thread));
             }
 
+            let tracer = self.debug.tracer.borrow();
+            if tracer.is_enabled() {
+                if let Some(location) = self.suspended_location() {
+                    tracer.record(TraceEventKind::Suspend {
+                        fn_name: location.procedure,
+                        pc: location.offset as usize,
+                    });
+                }
+            }
+
             let param = lua50_32_sys::lua_tonumber(self.thread, -1);
             Ok(param as f32)
         }
     }
 
+    /// Install (or clear) the execution-trace sink. Engine commands
+    /// report themselves through [`trace_command`] and [`trace_flag`];
+    /// yields are recorded by [`Self::execute`].
+    pub fn set_trace_sink(&self, sink: Option<Rc<dyn TraceSink>>) {
+        self.debug.tracer.borrow_mut().set_sink(sink);
+    }
+
+    /// Whether the script debugger holds the VM stopped. Directors skip
+    /// their frame then so the world freezes with the script.
+    pub fn debug_held(&self) -> bool {
+        self.debug.state.borrow().is_stopped()
+    }
+
+    /// Line + call hooks cost on every line, so they are only installed
+    /// while the debugger has something to check.
+    fn sync_debug_hook(&self) {
+        let active = self.debug.state.borrow().is_active();
+        if active == self.debug.installed.get() {
+            return;
+        }
+
+        unsafe {
+            if active {
+                lua50_32_sys::lua_sethook(
+                    self.thread,
+                    Some(debug_hook),
+                    LUA_MASKCALL | LUA_MASKLINE,
+                    0,
+                );
+            } else {
+                lua50_32_sys::lua_sethook(self.thread, None, 0, 0);
+            }
+        }
+        self.debug.installed.set(active);
+    }
+
+    /// Global Lua (not C) functions, sorted by name. Walked on the main
+    /// state for the same reason as [`Self::enumerate_globals`].
+    pub fn script_functions(&self) -> Vec<String> {
+        let mut out = Vec::new();
+
+        unsafe {
+            let l = self.lua;
+            let top = lua50_32_sys::lua_gettop(l);
+
+            lua50_32_sys::lua_pushnil(l);
+            while lua50_32_sys::lua_next(l, lua50_32_sys::LUA_GLOBALSINDEX) != 0 {
+                if lua50_32_sys::lua_type(l, -2) == lua50_32_sys::LUA_TSTRING as i32
+                    && lua50_32_sys::lua_type(l, -1) == lua50_32_sys::LUA_TFUNCTION as i32
+                    && lua50_32_sys::lua_iscfunction(l, -1) == 0
+                {
+                    if let Some(name) = read_lua_string(l, -2) {
+                        out.push(name);
+                    }
+                }
+                lua50_32_sys::lua_settop(l, lua50_32_sys::lua_gettop(l) - 1);
+            }
+
+            lua50_32_sys::lua_settop(l, top);
+        }
+
+        out.sort();
+        out
+    }
+
+    /// Innermost Lua function on the suspended coroutine and its line.
+    fn suspended_location(&self) -> Option<DebugLocation> {
+        script_location(self.thread)
+    }
+
     /// Enumerate the script's global table as `(name, value)` pairs,
     /// sorted by name.
     ///
@@ -245,6 +369,44 @@ impl<TContext> Lua5032Vm<TContext> {
         out.sort_by(|a, b| a.0.cmp(&b.0));
         out
     }
+
+    /// Assign a global on the main state — the inverse of
+    /// [`enumerate_globals`](Self::enumerate_globals) for scalar
+    /// values. The name and string values are re-encoded to BIG5. A
+    /// `LuaValue::Other` has no value to write, and a name with an
+    /// embedded NUL can't be passed to Lua; both are rejected with
+    /// `false`.
+    ///
+    /// Like `enumerate_globals` this works on the main state, which
+    /// shares its globals table with the coroutine thread, so it is
+    /// safe while the script is suspended.
+    pub fn set_global(&self, name: &str, value: &LuaValue) -> bool {
+        let name = encoding::all::BIG5_2003
+            .encode(name, EncoderTrap::Replace)
+            .unwrap_or_default();
+        let Ok(cname) = std::ffi::CString::new(name) else {
+            return false;
+        };
+
+        unsafe {
+            let l = self.lua;
+            match value {
+                LuaValue::Nil => lua50_32_sys::lua_pushnil(l),
+                LuaValue::Bool(b) => lua50_32_sys::lua_pushboolean(l, *b as i32),
+                LuaValue::Number(n) => lua50_32_sys::lua_pushnumber(l, *n),
+                LuaValue::Str(s) => {
+                    let bytes = encoding::all::BIG5_2003
+                        .encode(s, EncoderTrap::Replace)
+                        .unwrap_or_default();
+                    lua50_32_sys::lua_pushlstring(l, bytes.as_ptr() as *const c_char, bytes.len());
+                }
+                LuaValue::Other(_) => return false,
+            }
+            lua50_32_sys::lsetglobal(l, cname.as_ptr());
+        }
+
+        true
+    }
 }
 
 /// A marshalled Lua value read out of the global table by
@@ -305,8 +467,234 @@ fn read_lua_value(state: *mut lua_State, idx: i32) -> LuaValue {
     }
 }
 
+/// Procedures are the script's global Lua functions; variables are its
+/// global table minus functions.
+impl<TContext> ScriptDebugTarget for Lua5032Vm<TContext> {
+    fn debug_vm_name(&self) -> &'static str {
+        "lua"
+    }
+
+    fn debug_stops_deferred(&self) -> bool {
+        true
+    }
+
+    fn debug_state(&self) -> &RefCell<DebugState> {
+        &self.debug.state
+    }
+
+    fn debug_procedures(&self) -> Vec<DebugProcedure> {
+        self.script_functions()
+            .into_iter()
+            .map(|name| DebugProcedure { name, id: None })
+            .collect()
+    }
+
+    fn debug_location(&self) -> Option<DebugLocation> {
+        let state = self.debug.state.borrow();
+        match state.stop().and_then(|stop| stop.location.clone()) {
+            Some(location) => Some(location),
+            None => self.suspended_location(),
+        }
+    }
+
+    fn debug_variables(&self) -> Vec<(String, DebugValue)> {
+        self.enumerate_globals()
+            .into_iter()
+            .filter(|(_, value)| *value != LuaValue::Other("function"))
+            .map(|(name, value)| (name, value.into()))
+            .collect()
+    }
+
+    fn debug_read(&self, name: &str) -> Option<DebugValue> {
+        read_global(self.lua, name)
+    }
+}
+
+impl From<LuaValue> for DebugValue {
+    fn from(value: LuaValue) -> Self {
+        match value {
+            LuaValue::Nil => DebugValue::Nil,
+            LuaValue::Bool(v) => DebugValue::Bool(v),
+            LuaValue::Number(v) => DebugValue::Float(v),
+            LuaValue::Str(v) => DebugValue::Str(v),
+            LuaValue::Other(tag) => DebugValue::Other(tag.to_string()),
+        }
+    }
+}
+
+fn register_debug_hook(lua: *mut lua_State, thread: *mut lua_State) -> Rc<LuaDebugHook> {
+    let hook = Rc::new(LuaDebugHook::default());
+    DEBUG_HOOKS.with(|hooks| {
+        let mut hooks = hooks.borrow_mut();
+        hooks.insert(lua as usize, hook.clone());
+        hooks.insert(thread as usize, hook.clone());
+    });
+    hook
+}
+
+fn debug_hook_of(state: *mut lua_State) -> Option<Rc<LuaDebugHook>> {
+    DEBUG_HOOKS.with(|hooks| hooks.borrow().get(&(state as usize)).cloned())
+}
+
+/// Record a call into the engine C function `name` from the script
+/// running on `state`. Call it first thing, while the arguments are
+/// still on the stack.
+pub fn trace_command(state: *mut lua_State, name: &str) {
+    trace(state, |fn_name, pc| {
+        let top = unsafe { lua50_32_sys::lua_gettop(state) };
+        let args = (1..=top)
+            .map(|idx| match read_lua_value(state, idx) {
+                LuaValue::Nil => "nil".to_string(),
+                LuaValue::Bool(v) => v.to_string(),
+                LuaValue::Number(v) => v.to_string(),
+                LuaValue::Str(v) => format!("{:?}", v),
+                LuaValue::Other(tag) => tag.to_string(),
+            })
+            .collect();
+        TraceEventKind::Command {
+            fn_name,
+            pc,
+            name: name.to_string(),
+            args,
+        }
+    });
+}
+
+/// Record a read or write of game flag `slot` from the script running on
+/// `state`. Flags are the Lua games' plot state, so they are reported as
+/// shared globals.
+pub fn trace_flag(state: *mut lua_State, write: bool, slot: u32, value: u32) {
+    trace(state, |fn_name, pc| {
+        let scope = GlobalScope::Shared;
+        if write {
+            TraceEventKind::GlobalWrite {
+                fn_name,
+                pc,
+                scope,
+                slot,
+                value,
+            }
+        } else {
+            TraceEventKind::GlobalRead {
+                fn_name,
+                pc,
+                scope,
+                slot,
+                value,
+            }
+        }
+    });
+}
+
+/// `kind` gets the calling script function and line.
+fn trace(state: *mut lua_State, kind: impl FnOnce(String, usize) -> TraceEventKind) {
+    let Some(hook) = debug_hook_of(state) else {
+        return;
+    };
+    let tracer = hook.tracer.borrow();
+    if !tracer.is_enabled() {
+        return;
+    }
+
+    let location = script_location(state).unwrap_or(DebugLocation {
+        procedure: String::new(),
+        offset: 0,
+    });
+    tracer.record(kind(location.procedure, location.offset as usize));
+}
+
+/// Innermost Lua (not C) function on `state`'s stack and its line.
+fn script_location(state: *mut lua_State) -> Option<DebugLocation> {
+    unsafe {
+        let mut ar: lua_Debug = std::mem::zeroed();
+        let mut level = 0;
+        while lua50_32_sys::lua_getstack(state, level, &mut ar) != 0 {
+            lua50_32_sys::lua_getinfo(state, c"nSl".as_ptr(), &mut ar);
+            if c_str(ar.what) != "C" {
+                return Some(DebugLocation {
+                    procedure: function_name(&ar),
+                    offset: ar.currentline.max(0) as u32,
+                });
+            }
+            level += 1;
+        }
+    }
+
+    None
+}
+
+/// Line / call hook installed by [`Lua5032Vm::sync_debug_hook`]. A hook
+/// can't yield in Lua 5.0, so a stop recorded here takes effect when the
+/// coroutine next yields and [`Lua5032Vm::execute`] declines to resume.
+unsafe extern "C" fn debug_hook(l: *mut lua_State, ar: *mut lua_Debug) {
+    let Some(hook) = debug_hook_of(l) else {
+        return;
+    };
+
+    unsafe {
+        if lua50_32_sys::lua_getinfo(l, c"nSl".as_ptr(), ar) == 0 {
+            return;
+        }
+        let ar = &*ar;
+        if c_str(ar.what) == "C" {
+            return;
+        }
+
+        let offset = if ar.event == LUA_HOOKCALL {
+            0
+        } else {
+            ar.currentline.max(0) as u32
+        };
+        let location = DebugLocation {
+            procedure: function_name(ar),
+            offset,
+        };
+        hook.state
+            .borrow_mut()
+            .check(location, |name| read_global(l, name));
+    }
+}
+
+/// Read the global `name` on `state`, leaving its stack as it was.
+fn read_global(state: *mut lua_State, name: &str) -> Option<DebugValue> {
+    let cname = std::ffi::CString::new(name).ok()?;
+    unsafe {
+        let top = lua50_32_sys::lua_gettop(state);
+        lua50_32_sys::lgetglobal(state, cname.as_ptr());
+        let value = read_lua_value(state, -1);
+        lua50_32_sys::lua_settop(state, top);
+        Some(value.into())
+    }
+}
+
+/// Name of the function `ar` describes: its global name when the caller
+/// knows one, else `source:line` of its definition.
+fn function_name(ar: &lua_Debug) -> String {
+    let name = c_str(ar.name);
+    if !name.is_empty() {
+        return name;
+    }
+
+    let source = unsafe { CStr::from_ptr(ar.short_src.as_ptr()) }.to_string_lossy();
+    format!("{}:{}", source, ar.linedefined)
+}
+
+fn c_str(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned()
+}
+
 impl<TContext> Drop for Lua5032Vm<TContext> {
     fn drop(&mut self) {
+        DEBUG_HOOKS.with(|hooks| {
+            let mut hooks = hooks.borrow_mut();
+            hooks.remove(&(self.lua as usize));
+            hooks.remove(&(self.thread as usize));
+        });
         unsafe {
             lua50_32_sys::lua_close(self.lua);
         }
@@ -451,6 +839,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn set_global_round_trips_scalars_and_rejects_bad_names() {
+        let vm = vm_with("");
+
+        assert!(vm.set_global("g_num", &LuaValue::Number(3.0)));
+        assert!(vm.set_global("g_zh", &LuaValue::Str("中".into())));
+        assert!(!vm.set_global("g_bad\0name", &LuaValue::Number(1.0)));
+        assert!(!vm.set_global("g_table", &LuaValue::Other("table")));
+
+        let globals = vm.enumerate_globals();
+        assert_eq!(lookup(&globals, "g_num"), Some(LuaValue::Number(3.0)));
+        assert_eq!(lookup(&globals, "g_zh"), Some(LuaValue::Str("中".into())));
+        assert_eq!(lookup(&globals, "g_table"), None);
+    }
+
     extern "C" fn noop_cfunction(_state: *mut lua_State) -> i32 {
         0
     }
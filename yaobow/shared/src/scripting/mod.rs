@@ -1,3 +1,5 @@
 pub mod angelscript;
+pub mod debugger;
 pub mod lua50_32;
 pub mod sce;
+pub mod trace;
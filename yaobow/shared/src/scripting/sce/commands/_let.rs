@@ -17,10 +17,7 @@ impl SceCommand for SceCommandLet {
         state: &mut SceState,
         _delta_sec: f32,
     ) -> bool {
-        state
-            .global_state_mut()
-            .persistent_state_mut()
-            .set_global(self.var, self.value);
+        state.set_global(self.var, self.value);
         true
     }
 }
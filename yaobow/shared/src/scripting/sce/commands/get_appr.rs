@@ -16,14 +16,7 @@ impl SceCommand for SceCommandGetAppr {
         state: &mut SceState,
         _delta_sec: f32,
     ) -> bool {
-        if self.var < 0 {
-            state
-                .global_state_mut()
-                .persistent_state_mut()
-                .set_global(self.var, 1)
-        } else {
-            state.context_mut().set_local(self.var, 1)
-        }
+        state.set_var(self.var, 1);
 
         true
     }
@@ -17,10 +17,7 @@ impl SceCommand for SceCommandGetDlgSel {
         _delta_sec: f32,
     ) -> bool {
         let dlgsel = state.context_mut().current_proc_context_mut().get_dlgsel();
-        state
-            .context_mut()
-            .current_proc_context_mut()
-            .set_local(self.var, dlgsel);
+        state.set_local(self.var, dlgsel);
         true
     }
 }
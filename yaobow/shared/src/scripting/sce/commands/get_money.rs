@@ -16,10 +16,7 @@ impl SceCommand for SceCommandGetMoney {
         state: &mut SceState,
         _delta_sec: f32,
     ) -> bool {
-        state
-            .context_mut()
-            .current_proc_context_mut()
-            .set_local(self.var, 99999);
+        state.set_local(self.var, 99999);
         true
     }
 }
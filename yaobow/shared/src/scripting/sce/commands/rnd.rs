@@ -20,14 +20,7 @@ impl SceCommand for SceCommandRnd {
         _delta_sec: f32,
     ) -> bool {
         let value = self.rng.gen_range(0..self.max_value);
-        if self.var < 0 {
-            state
-                .global_state_mut()
-                .persistent_state_mut()
-                .set_global(self.var, value)
-        } else {
-            state.context_mut().set_local(self.var, value)
-        }
+        state.set_var(self.var, value);
 
         true
     }
@@ -1,4 +1,5 @@
 use crate::scripting::sce::{SceCommand, SceState};
+use crate::scripting::trace::{BranchKind, TraceEventKind};
 use crosscom::ComRc;
 use imgui::Ui;
 use radiance::comdef::ISceneManager;
@@ -16,7 +17,16 @@ impl SceCommand for SceCommandTestGoto {
         state: &mut SceState,
         _delta_sec: f32,
     ) -> bool {
-        if !state.global_state_mut().fop_state_mut().value().unwrap() {
+        let value = state.global_state_mut().fop_state_mut().value().unwrap();
+        state.context().trace(|fn_name, pc| TraceEventKind::Branch {
+            fn_name,
+            pc,
+            kind: BranchKind::TestGoto,
+            operand: value as i32,
+            offset: self.addr as i32,
+            taken: !value,
+        });
+        if !value {
             state.context_mut().jump_to(self.addr);
         }
         true
@@ -20,6 +20,7 @@ use crate::openpal3::{
 };
 
 use self::vm::{SceExecutionContext, SceExecutionOptions};
+use crate::scripting::trace::{GlobalScope, TraceEventKind};
 
 pub mod commands;
 pub mod vm;
@@ -188,6 +189,66 @@ impl SceState {
         &self.asset_mgr
     }
 
+    /// Value of `$var`: a global when negative, otherwise a local of
+    /// the running proc. Unset variables read as 0.
+    pub fn get_var(&self, var: i16) -> i32 {
+        let (scope, value) = if var < 0 {
+            let value = self.global_state.persistent_state().get_global(var);
+            (GlobalScope::Shared, value)
+        } else {
+            (GlobalScope::Local, self.context.get_local(var))
+        };
+        let value = value.unwrap_or(0);
+        self.trace_var(false, scope, var, value);
+        value
+    }
+
+    /// Write `$var`, picking global or local by sign like [`Self::get_var`].
+    pub fn set_var(&mut self, var: i16, value: i32) {
+        if var < 0 {
+            self.set_global(var, value);
+        } else {
+            self.set_local(var, value);
+        }
+    }
+
+    pub fn set_global(&mut self, var: i16, value: i32) {
+        self.global_state
+            .persistent_state_mut()
+            .set_global(var, value);
+        self.trace_var(true, GlobalScope::Shared, var, value);
+    }
+
+    pub fn set_local(&mut self, var: i16, value: i32) {
+        self.context.set_local(var, value);
+        self.trace_var(true, GlobalScope::Local, var, value);
+    }
+
+    /// Slots are `|var|`, so `$-32768` is shared slot 32768.
+    fn trace_var(&self, write: bool, scope: GlobalScope, var: i16, value: i32) {
+        let slot = var.unsigned_abs() as u32;
+        let value = value as u32;
+        self.context.trace(|fn_name, pc| {
+            if write {
+                TraceEventKind::GlobalWrite {
+                    fn_name,
+                    pc,
+                    scope,
+                    slot,
+                    value,
+                }
+            } else {
+                TraceEventKind::GlobalRead {
+                    fn_name,
+                    pc,
+                    scope,
+                    slot,
+                    value,
+                }
+            }
+        });
+    }
+
     pub fn get_next_cmd(&mut self) -> Option<Box<dyn SceCommand>> {
         self.context.get_next_cmd(&mut self.global_state)
     }
@@ -1,11 +1,15 @@
 use crate::openpal3::asset_manager::AssetManager;
 use crate::openpal3::loaders::sce_loader::SceFile;
 use crate::openpal3::states::global_state::GlobalState;
+use crate::scripting::debugger::{
+    DebugLocation, DebugProcedure, DebugState, DebugValue, ScriptDebugTarget,
+};
 use crate::scripting::sce::SceCommandDebug;
+use crate::scripting::trace::{TraceEventKind, TraceSink, Tracer};
 
 use super::{SceCommand, SceState, commands::*};
 use crosscom::ComRc;
-use encoding::{DecoderTrap, Encoding};
+use fileformats::pal3::sce::{SceArg, decode_inst};
 use imgui::*;
 use log::{debug, error, warn};
 use radiance::comdef::{IDirector, ISceneManager};
@@ -32,6 +36,7 @@ pub struct SceVm {
     debug_scn_name: String,
     debug_scn_subname: String,
     debug_main_story: String,
+    debug_state: RefCell<DebugState>,
 }
 
 impl SceVm {
@@ -70,6 +75,7 @@ impl SceVm {
             debug_scn_name: String::from(""),
             debug_scn_subname: String::from(""),
             debug_main_story: String::from(""),
+            debug_state: RefCell::new(DebugState::new()),
         }
     }
 
@@ -77,9 +83,18 @@ impl SceVm {
         self.state.global_state_mut().update(delta_sec);
         self.draw_curtain();
 
+        // Stopped in the script debugger: in-flight commands freeze too.
+        if self.debug_held() {
+            return None;
+        }
+
         let ui = self.ui.ui();
         if self.active_commands.len() == 0 {
             loop {
+                if self.debug_break() {
+                    return None;
+                }
+
                 match self.state.get_next_cmd() {
                     Some(mut cmd) => {
                         cmd.initialize(self.scene_manager.clone(), &mut self.state);
@@ -88,6 +103,7 @@ impl SceVm {
                         }
                     }
                     None => {
+                        self.debug_state.borrow_mut().poll_idle();
                         self.state.global_state_mut().set_adv_input_enabled(true);
                         self.state.render_status(delta_sec);
                         return None;
@@ -107,6 +123,25 @@ impl SceVm {
         None
     }
 
+    /// Whether the script debugger holds the VM stopped. The adventure
+    /// director freezes the world with it.
+    pub fn debug_held(&self) -> bool {
+        self.debug_state.borrow().is_stopped()
+    }
+
+    fn debug_break(&self) -> bool {
+        if !self.debug_state.borrow().is_active() {
+            return false;
+        }
+
+        let Some(location) = self.debug_location() else {
+            return false;
+        };
+        self.debug_state
+            .borrow_mut()
+            .check(location, |name| self.debug_read(name))
+    }
+
     fn draw_curtain(&mut self) {
         let curtain = self.state().curtain();
         if curtain == 0. {
@@ -158,6 +193,12 @@ impl SceVm {
         self.state.call_proc(proc_id)
     }
 
+    /// Install (or clear) the execution-trace sink. See
+    /// [`crate::scripting::trace`] for how SCE maps onto the events.
+    pub fn set_trace_sink(&mut self, sink: Option<Rc<dyn TraceSink>>) {
+        self.state.context_mut().set_trace_sink(sink);
+    }
+
     pub fn state(&self) -> &SceState {
         &self.state
     }
@@ -175,54 +216,110 @@ impl SceVm {
     }
 }
 
-macro_rules! command {
-    ($self: ident, $cmd_name: ident $(, $param_names: ident : $param_types: ident)* $(,)*) => {
-        command! {@reverse_arg $self, $cmd_name $(, $param_names : $param_types)* [] [$(, $param_names)*]}
-    };
+/// Variables are named like the `sce_asm` listing: `$N`, with negative
+/// `N` for globals and the rest for the running proc's locals.
+impl ScriptDebugTarget for SceVm {
+    fn debug_vm_name(&self) -> &'static str {
+        "sce"
+    }
 
-    (@reverse_arg $self: ident, $cmd_name: ident, $param_name: ident : $param_type: ident $(, $param_names: ident : $param_types: ident)* [$(, $reversed_names: ident : $reversed_types: ident)*] [$(, $asc_param_names :ident)*]) => {
-        command! {@reverse_arg $self, $cmd_name $(, $param_names : $param_types)* [, $param_name : $param_type $(, $reversed_names : $reversed_types)*] [$(, $asc_param_names)*]}
-    };
+    fn debug_state(&self) -> &RefCell<DebugState> {
+        &self.debug_state
+    }
 
-    (@reverse_arg $self: ident, $cmd_name: ident [$(, $reversed_names: ident : $reversed_types: ident)*] [$(, $asc_param_names :ident)*]) => {
-        command! {@inner $self, $cmd_name $(, $reversed_names : $reversed_types)* [] [$(, $asc_param_names)*]}
-    };
+    fn debug_procedures(&self) -> Vec<DebugProcedure> {
+        self.state
+            .context()
+            .sce()
+            .proc_headers
+            .iter()
+            .map(|h| DebugProcedure {
+                name: h.name.clone(),
+                id: Some(h.id),
+            })
+            .collect()
+    }
 
-    (@inner $self: ident, $cmd_name: ident $(, $param_names: ident : $param_types: ident)* [$(, $evaluated: ident)*] [$(, $asc_param_names :ident)*]) => {
-        {
-            $(let $param_names = data_read::$param_types($self);)*
-            debug!(concat!("{} ", $(concat!("{", stringify!($asc_param_names), ":?} "), )*), stringify!($cmd_name), $($asc_param_names=$asc_param_names, )*);
-            Some(Box::new($cmd_name::new($($asc_param_names),*)))
-        }
-    };
-}
+    fn debug_location(&self) -> Option<DebugLocation> {
+        let context = self.state.context();
+        let proc = context.next_proc()?;
+        Some(DebugLocation {
+            procedure: context
+                .proc_name(proc.proc_id())
+                .unwrap_or_else(|| proc.proc_id().to_string()),
+            offset: proc.program_counter() as u32,
+        })
+    }
 
-macro_rules! nop_command {
-    ($self: ident, $cmd_name: ident $(, $param_types: ident)* $(,)*) => {
-        nop_command! {@reverse_arg $self, $cmd_name $(, $param_types)* []}
-    };
+    fn debug_variables(&self) -> Vec<(String, DebugValue)> {
+        let mut vars: Vec<_> = self
+            .global_state()
+            .persistent_state()
+            .globals()
+            .into_iter()
+            .filter(|(var, _)| *var < 0)
+            .collect();
+        if let Some(proc) = self.state.context().next_proc() {
+            vars.extend(proc.locals());
+        }
 
-    (@reverse_arg $self: ident, $cmd_name: ident, $param_type: ident $(, $param_types: ident)* [$(, $reversed_types: ident)*]) => {
-        nop_command! {@reverse_arg $self, $cmd_name $(, $param_types)* [, $param_type $(, $reversed_types)*]}
-    };
+        vars.into_iter()
+            .map(|(var, value)| (format!("${}", var), DebugValue::Int(value as i64)))
+            .collect()
+    }
 
-    (@reverse_arg $self: ident, $cmd_name: ident [$(, $reversed_types: ident)*]) => {
-        nop_command! {@inner $self, $cmd_name $(, $reversed_types)*}
-    };
+    fn debug_read(&self, name: &str) -> Option<DebugValue> {
+        let var: i16 = name.strip_prefix('$')?.parse().ok()?;
+        let value = if var < 0 {
+            self.global_state().persistent_state().get_global(var)
+        } else {
+            self.state.context().next_proc()?.get_local(var)
+        };
+        value.map(|v| DebugValue::Int(v as i64))
+    }
+}
 
-    (@inner $self: ident, $cmd_name: ident $(, $param_types: ident)*) => {
+// Operand layouts come from `fileformats::pal3::sce::opcodes`; by the time
+// these run, `inst.args` is already decoded in declaration order. A
+// command declaring more operands than the table gave it is a bug in one
+// of the two, treated like an undecodable instruction.
+macro_rules! command {
+    ($inst: ident, $cmd_name: ident $(, $param_names: ident : $param_types: ident)* $(,)*) => {
         {
-            $(let _ = data_read::$param_types($self); )*
-            warn!("Unimplemented command: {}", stringify!($cmd_name));
-            Some(Box::new(SceCommandNop::new()))
+            #[allow(unused_mut, unused_variables)]
+            let mut args = $inst.args.iter();
+            $(let $param_names = match data_read::$param_types(args.next()) {
+                Ok(value) => value,
+                Err(e) => {
+                    error!(
+                        "{} at {}: operand {}: {}",
+                        stringify!($cmd_name),
+                        $inst.addr,
+                        stringify!($param_names),
+                        e
+                    );
+                    panic!();
+                }
+            };)*
+            debug!(concat!("{} ", $(concat!("{", stringify!($param_names), ":?} "), )*), stringify!($cmd_name), $($param_names=$param_names, )*);
+            Some(Box::new($cmd_name::new($($param_names),*)))
         }
     };
 }
 
+macro_rules! nop_command {
+    ($inst: ident, $cmd_name: ident) => {{
+        warn!("Unimplemented command: {}", stringify!($cmd_name));
+        Some(Box::new(SceCommandNop::new()))
+    }};
+}
+
 pub struct SceProcContext {
     sce: Rc<SceFile>,
     proc_id: u32,
     program_counter: usize,
+    /// Address of the instruction decoded last, i.e. the one running.
+    inst_addr: usize,
     local_vars: HashMap<i16, i32>,
     dlgsel: i32,
 }
@@ -256,6 +353,7 @@ impl SceProcContext {
             sce,
             proc_id,
             program_counter: 0,
+            inst_addr: 0,
             local_vars: HashMap::new(),
             dlgsel: 0,
         }
@@ -265,10 +363,17 @@ impl SceProcContext {
         self.local_vars.insert(var, value);
     }
 
-    pub fn get_local(&mut self, var: i16) -> Option<i32> {
+    pub fn get_local(&self, var: i16) -> Option<i32> {
         self.local_vars.get(&var).and_then(|v| Some(*v))
     }
 
+    /// Locals written so far, ordered by variable index.
+    pub fn locals(&self) -> Vec<(i16, i32)> {
+        let mut locals: Vec<_> = self.local_vars.iter().map(|(k, v)| (*k, *v)).collect();
+        locals.sort();
+        locals
+    }
+
     pub fn set_dlgsel(&mut self, value: i32) {
         self.dlgsel = value;
     }
@@ -283,82 +388,117 @@ impl SceProcContext {
         self.proc_id
     }
 
-    fn get_next_cmd(&mut self) -> Option<Box<dyn SceCommand>> {
+    /// Offset of the next instruction inside the procedure's code.
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    /// The proc's name, or its id when the header table has none.
+    pub fn label(&self) -> String {
+        self.sce
+            .proc_headers
+            .iter()
+            .find(|h| h.id == self.proc_id)
+            .map_or_else(|| self.proc_id.to_string(), |h| h.name.clone())
+    }
+
+    fn get_next_cmd(&mut self, tracer: &Tracer) -> Option<Box<dyn SceCommand>> {
         if self.proc_completed() {
             return None;
         }
 
-        let cmd = data_read::i16(self);
-        let access_local_var = data_read::i16(self);
-        match cmd {
+        let sce = self.sce.clone();
+        let proc = sce.procs.get(&self.proc_id).unwrap();
+        let inst = match decode_inst(&proc.inst, self.program_counter) {
+            Ok((inst, next)) => {
+                self.inst_addr = self.program_counter;
+                self.program_counter = next;
+                inst
+            }
+            Err(e) => {
+                error!("Unsupported command: {}", e);
+                panic!();
+            }
+        };
+
+        if tracer.is_enabled() {
+            tracer.record(TraceEventKind::Command {
+                fn_name: self.label(),
+                pc: self.inst_addr,
+                name: inst.opcode().map_or("", |op| op.name).to_string(),
+                args: inst.args.iter().map(format_arg).collect(),
+            });
+        }
+
+        match inst.code {
             1 => {
                 // Idle
-                command!(self, SceCommandIdle, length: f32)
+                command!(inst, SceCommandIdle, length: f32)
             }
             2 => {
                 // ScriptRunMode
-                command!(self, SceCommandScriptRunMode, mode: i32)
+                command!(inst, SceCommandScriptRunMode, mode: i32)
             }
             3 => {
                 // Goto
-                command!(self, SceCommandGoto, offset: u32)
+                command!(inst, SceCommandGoto, offset: u32)
             }
             5 => {
                 // FOP
-                command!(self, SceCommandFop, op: i32)
+                command!(inst, SceCommandFop, op: i32)
             }
             6 => {
                 // GT
-                command!(self, SceCommandGt, var: i16, value: i32)
+                command!(inst, SceCommandGt, var: i16, value: i32)
             }
             7 => {
                 // LS
-                command!(self, SceCommandLs, var: i16, value: i32)
+                command!(inst, SceCommandLs, var: i16, value: i32)
             }
             8 => {
                 // EQ
-                command!(self, SceCommandEq, var: i16, value: i32)
+                command!(inst, SceCommandEq, var: i16, value: i32)
             }
             9 => {
                 // NEQ
-                command!(self, SceCommandNeq, var: i16, value: i32)
+                command!(inst, SceCommandNeq, var: i16, value: i32)
             }
             10 => {
                 // GEQ
-                match access_local_var {
-                    1 => command!(self, SceCommandGeq, var: i16, value: i32),
-                    3 => command!(self, SceCommandGeq2, var: i16, var2: i16),
-                    _ => nop_command!(self, GeqNotSupported),
+                match inst.flags {
+                    1 => command!(inst, SceCommandGeq, var: i16, value: i32),
+                    3 => command!(inst, SceCommandGeq2, var: i16, var2: i16),
+                    _ => nop_command!(inst, GeqNotSupported),
                 }
             }
             11 => {
                 // LEQ
-                command!(self, SceCommandLeq, var: i16, value: i32)
+                command!(inst, SceCommandLeq, var: i16, value: i32)
             }
             12 => {
                 // TestGoto
-                command!(self, SceCommandTestGoto, offset: u32)
+                command!(inst, SceCommandTestGoto, offset: u32)
             }
             13 => {
                 // Let
-                command!(self, SceCommandLet, var: i16, value: i32)
+                command!(inst, SceCommandLet, var: i16, value: i32)
             }
             16 => {
                 //Call
-                command!(self, SceCommandCall, proc_id: u32)
+                command!(inst, SceCommandCall, proc_id: u32)
             }
             17 => {
                 // Rnd
-                command!(self, SceCommandRnd, var: i16, value: i32)
+                command!(inst, SceCommandRnd, var: i16, value: i32)
             }
             19 => {
                 // Between
-                command!(self, SceCommandBetween, var: i16, lb: i32, rh: i32)
+                command!(inst, SceCommandBetween, var: i16, lb: i32, rh: i32)
             }
             20 => {
                 // RolePathTo
                 command!(
-                    self,
+                    inst,
                     SceCommandRolePathTo,
                     role_id: i32,
                     x: i32,
@@ -368,12 +508,12 @@ impl SceProcContext {
             }
             21 => {
                 // RoleSetPos
-                command!(self, SceCommandRoleSetPos, role_id: i32, x: i32, y: i32)
+                command!(inst, SceCommandRoleSetPos, role_id: i32, x: i32, y: i32)
             }
             22 => {
                 // RoleShowAction
                 command!(
-                    self,
+                    inst,
                     SceCommandRoleShowAction,
                     role_id: i32,
                     action_name: string,
@@ -382,52 +522,52 @@ impl SceProcContext {
             }
             23 => {
                 // RoleSetFace
-                command!(self, SceCommandRoleSetFace, role_id: i32, direction: i32)
+                command!(inst, SceCommandRoleSetFace, role_id: i32, direction: i32)
             }
             24 => {
                 // RoleTurnFace
-                command!(self, SceCommandRoleTurnFace, role_id: i32, degree: i32)
+                command!(inst, SceCommandRoleTurnFace, role_id: i32, degree: i32)
             }
             25 => {
                 // TeamOpen
-                nop_command!(self, TeamOpen)
+                nop_command!(inst, TeamOpen)
             }
             26 => {
                 // TeamClose
-                nop_command!(self, TeamClose)
+                nop_command!(inst, TeamClose)
             }
             27 => {
                 // RoleInput
-                command!(self, SceCommandRoleInput, enable_input: i32)
+                command!(inst, SceCommandRoleInput, enable_input: i32)
             }
             28 => {
                 // RoleActive
-                command!(self, SceCommandRoleActive, role: i32, active: i32)
+                command!(inst, SceCommandRoleActive, role: i32, active: i32)
             }
             29 => {
                 // RoleScript
-                command!(self, SceCommandRoleScript, role: i32, proc_id: i32)
+                command!(inst, SceCommandRoleScript, role: i32, proc_id: i32)
             }
             30 => {
                 // CameraFocusRole
-                nop_command!(self, CameraFocusRole, i32)
+                nop_command!(inst, CameraFocusRole)
             }
             31 => {
                 // CameraFocusPoint
-                nop_command!(self, CameraFocusPoint, f32, f32, f32)
+                nop_command!(inst, CameraFocusPoint)
             }
             32 => {
                 // CameraPush
-                nop_command!(self, CameraPush, f32, f32, i32)
+                nop_command!(inst, CameraPush)
             }
             33 => {
                 // CameraRotate
-                command!(self, SceCommandCameraRotate, to_rot_x: f32, to_rot_y: f32, duration: f32, _unknown: i32)
+                command!(inst, SceCommandCameraRotate, to_rot_x: f32, to_rot_y: f32, duration: f32, _unknown: i32)
             }
             34 => {
                 // CameraMove
                 command!(
-                    self,
+                    inst,
                     SceCommandCameraMove,
                     position_x: f32,
                     position_y: f32,
@@ -438,12 +578,12 @@ impl SceProcContext {
             }
             35 => {
                 //CameraWag
-                nop_command!(self, CameraWag, f32, f32, f32, i32)
+                nop_command!(inst, CameraWag)
             }
             36 => {
                 // CameraSet
                 command!(
-                    self,
+                    inst,
                     SceCommandCameraSet,
                     y_rot: f32,
                     x_rot: f32,
@@ -455,151 +595,151 @@ impl SceProcContext {
             }
             37 => {
                 // CameraDefault
-                command!(self, SceCommandCameraDefault, unknown: i32)
+                command!(inst, SceCommandCameraDefault, unknown: i32)
             }
             38 => {
                 // CameraPushState
-                nop_command!(self, CameraPushState)
+                nop_command!(inst, CameraPushState)
             }
             39 => {
                 // CameraPopState
-                nop_command!(self, CameraPopState)
+                nop_command!(inst, CameraPopState)
             }
             42 => {
                 // LK_Ghost
-                nop_command!(self, LK_Ghost, i32)
+                nop_command!(inst, LK_Ghost)
             }
             43 => {
                 // FavorAdd
-                nop_command!(self, FavorAdd, i32, i32)
+                nop_command!(inst, FavorAdd)
             }
             46 => {
                 // AddItem
-                nop_command!(self, AddItem, i32, i32)
+                nop_command!(inst, AddItem)
             }
             47 => {
                 // RemoveItem
-                nop_command!(self, RemoveItem, i32)
+                nop_command!(inst, RemoveItem)
             }
             48 => {
                 // AddMoney
-                nop_command!(self, AddMoney, i32)
+                nop_command!(inst, AddMoney)
             }
             49 => {
                 // GetMoney
-                command!(self, SceCommandGetMoney, var: i16)
+                command!(inst, SceCommandGetMoney, var: i16)
             }
             50 => {
                 // GetFavor
-                nop_command!(self, GetFavor, i16, i32)
+                nop_command!(inst, GetFavor)
             }
             51 => {
                 // AddSkill
-                nop_command!(self, AddSkill, i32, i32)
+                nop_command!(inst, AddSkill)
             }
             52 => {
                 // GetFavorite
-                nop_command!(self, GetFavorite, i16)
+                nop_command!(inst, GetFavorite)
             }
             54 => {
                 // FullRoleAtt
-                nop_command!(self, FullRoleAtt, i32, i32)
+                nop_command!(inst, FullRoleAtt)
             }
             62 => {
                 // Dlg
-                command!(self, SceCommandDlg, text: string)
+                command!(inst, SceCommandDlg, text: string)
             }
             63 => {
                 // LoadScene
-                command!(self, SceCommandLoadScene, name: string, sub_name: string)
+                command!(inst, SceCommandLoadScene, name: string, sub_name: string)
             }
             65 => {
                 // DlgSel
-                command!(self, SceCommandDlgSel, list: list)
+                command!(inst, SceCommandDlgSel, list: list)
             }
             66 => {
                 // GetDlgSel
-                command!(self, SceCommandGetDlgSel, var: i16)
+                command!(inst, SceCommandGetDlgSel, var: i16)
             }
             67 => {
                 // DlgFace
-                command!(self, SceCommandDlgFace, id: i32, face_name: string, left_or_right: i32)
+                command!(inst, SceCommandDlgFace, id: i32, face_name: string, left_or_right: i32)
             }
             68 => {
                 // Note
-                nop_command!(self, Note, string)
+                nop_command!(inst, Note)
             }
             69 => {
                 // FadeOut
-                command!(self, SceCommandFadeOut)
+                command!(inst, SceCommandFadeOut)
             }
             70 => {
                 // FadeIn
-                command!(self, SceCommandFadeIn)
+                command!(inst, SceCommandFadeIn)
             }
             71 => {
                 // RoleStop
-                command!(self, SceCommandRoleStop, role_id: i32)
+                command!(inst, SceCommandRoleStop, role_id: i32)
             }
             72 => {
                 // RoleEmote
-                nop_command!(self, RoleEmote, i32, i32)
+                nop_command!(inst, RoleEmote)
             }
             74 => {
                 // Climb
-                nop_command!(self, Climb, i32, i32)
+                nop_command!(inst, Climb)
             }
             76 => {
                 // DlgTime
-                command!(self, SceCommandDlgTime, text: string)
+                command!(inst, SceCommandDlgTime, text: string)
             }
             77 => {
                 // GetTimeSel - temporarily use GetDlgSel
-                command!(self, SceCommandGetTimeSel, var: i16)
+                command!(inst, SceCommandGetTimeSel, var: i16)
             }
             78 => {
-                command!(self, SceCommandHaveItem, item_id: i32)
+                command!(inst, SceCommandHaveItem, item_id: i32)
             }
             79 => {
                 // PlaySound
-                command!(self, SceCommandPlaySound, name: string, repeat: i32)
+                command!(inst, SceCommandPlaySound, name: string, repeat: i32)
             }
             80 => {
                 // CombatBoss
-                nop_command!(self, CombatBoss, i32, i32, i32, i32, i32, i32)
+                nop_command!(inst, CombatBoss)
             }
             81 => {
                 // FadeOutWhite
-                command!(self, SceCommandFadeOutWhite)
+                command!(inst, SceCommandFadeOutWhite)
             }
             82 => {
                 // CombatBoss
-                nop_command!(self, CombatMaxRound, i32)
+                nop_command!(inst, CombatMaxRound)
             }
             83 => {
                 // CombatMustFail
-                nop_command!(self, CombatMustFail)
+                nop_command!(inst, CombatMustFail)
             }
             85 => {
                 // ObjectActive
-                command!(self, SceCommandObjectActive, object_id: i32, active: i32)
+                command!(inst, SceCommandObjectActive, object_id: i32, active: i32)
             }
             86 => {
                 // Caption
-                nop_command!(self, Caption, string, i32)
+                nop_command!(inst, Caption)
             }
             87 => {
                 // OpenDoor
-                nop_command!(self, OpenDoor, i32)
+                nop_command!(inst, OpenDoor)
             }
             88 => {
                 // HY_Mode
-                nop_command!(self, HY_Mode, i32)
+                nop_command!(inst, HY_Mode)
             }
             89 => {
                 // HY_FLY
                 command!(
-                    self,
+                    inst,
                     SceCommandHyFly,
                     position_x: f32,
                     position_y: f32,
@@ -608,64 +748,64 @@ impl SceProcContext {
             }
             90 => {
                 // ObjectMove
-                nop_command!(self, ObjectMove, i32, f32, f32, f32, f32)
+                nop_command!(inst, ObjectMove)
             }
             91 => {
                 // FadeInWhite
-                command!(self, SceCommandFadeInWhite)
+                command!(inst, SceCommandFadeInWhite)
             }
             102 => {
                 // SwitchRS
-                nop_command!(self, SwitchRS, i32)
+                nop_command!(inst, SwitchRS)
             }
             104 => {
                 // APPR Entry
-                nop_command!(self, APPREntry)
+                nop_command!(inst, APPREntry)
             }
             106 => {
                 // ENCAMP_Entry
-                nop_command!(self, ENCAMP_Entry, i32)
+                nop_command!(inst, ENCAMP_Entry)
             }
             107 => {
                 // SKEE_Entry
-                nop_command!(self, SKEE_Entry, i32)
+                nop_command!(inst, SKEE_Entry)
             }
             108 => {
                 // Get Appr
-                command!(self, SceCommandGetAppr, var: i16)
+                command!(inst, SceCommandGetAppr, var: i16)
             }
             109 => {
                 // Enable_Sword
-                nop_command!(self, Enable_Sword, i32)
+                nop_command!(inst, Enable_Sword)
             }
             111 => {
                 // Specify_Compos
-                nop_command!(self, Specify_Compos, i32)
+                nop_command!(inst, Specify_Compos)
             }
             113 => {
                 // Start_HideFight
-                command!(self, SceCommandStartHideFight)
+                command!(inst, SceCommandStartHideFight)
             }
             115 => {
                 // Movie
-                command!(self, SceCommandMovie, name: string)
+                command!(inst, SceCommandMovie, name: string)
             }
             116 => {
                 // SetRoleTexture
-                nop_command!(self, SetRoleTexture, i32, string)
+                nop_command!(inst, SetRoleTexture)
             }
             117 => {
                 // Rotate
-                nop_command!(self, Rotate, i32, i32, i32)
+                nop_command!(inst, Rotate)
             }
             118 => {
                 // Quake
-                command!(self, SceCommandQuake, duration: f32, amplitude: f32)
+                command!(inst, SceCommandQuake, duration: f32, amplitude: f32)
             }
             119 => {
                 // ShowChatRest
                 command!(
-                    self,
+                    inst,
                     SceCommandShowChatRest,
                     config_file: string,
                     enough_money_proc: u32,
@@ -675,126 +815,126 @@ impl SceProcContext {
             }
             124 => {
                 // Trigger
-                nop_command!(self, Trigger, i32)
+                nop_command!(inst, Trigger)
             }
             125 => {
                 // SetBigMapElement
-                command!(self, SceCommandSetBigMapElement, id: i32, option: i32)
+                command!(inst, SceCommandSetBigMapElement, id: i32, option: i32)
             }
             126 => {
                 // GetSwitch
-                nop_command!(self, GetSwitch, string, i32, i16)
+                nop_command!(inst, GetSwitch)
             }
             127 => {
-                command!(self, SceCommandEntryRow, id: i32, proc_id: i32)
+                command!(inst, SceCommandEntryRow, id: i32, proc_id: i32)
             }
             128 => {
-                nop_command!(self, RotateInv, i32, i32, i32)
+                nop_command!(inst, RotateInv)
             }
             130 => {
                 // Dist
-                nop_command!(self, Dist, i16, i16)
+                nop_command!(inst, Dist)
             }
             131 => {
                 // GetSwitch
-                nop_command!(self, CombatNotGameOver)
+                nop_command!(inst, CombatNotGameOver)
             }
             132 => {
                 // GetCombat
-                command!(self, SceCommandGetCombat, var: i16)
+                command!(inst, SceCommandGetCombat, var: i16)
             }
             133 => {
                 // Music
-                command!(self, SceCommandMusic, name: string, unknown: i32)
+                command!(inst, SceCommandMusic, name: string, unknown: i32)
             }
             134 => {
                 // StopMusic
-                command!(self, SceCommandStopMusic)
+                command!(inst, SceCommandStopMusic)
             }
             135 => {
                 // RoleFadeOut
-                nop_command!(self, RoleFadeOut, i32)
+                nop_command!(inst, RoleFadeOut)
             }
             136 => {
                 // RoleFadeIn
-                nop_command!(self, RoleFadeIn, i32)
+                nop_command!(inst, RoleFadeIn)
             }
             137 => {
                 // IfInTeam
-                command!(self, SceCommandIfInTeam, role_id: i32)
+                command!(inst, SceCommandIfInTeam, role_id: i32)
             }
             138 => {
                 // Enable_SwordSkill
-                nop_command!(self, Enable_SwordSkill, i32)
+                nop_command!(inst, Enable_SwordSkill)
             }
             140 => {
                 // Snow
-                nop_command!(self, Snow, i32)
+                nop_command!(inst, Snow)
             }
             141 => {
                 // ScrEft
-                nop_command!(self, ScrEft, i32)
+                nop_command!(inst, ScrEft)
             }
             142 => {
                 // CEft_Pos
-                nop_command!(self, CEft_Pos, f32, f32, f32)
+                nop_command!(inst, CEft_Pos)
             }
             143 => {
                 // CEft
-                nop_command!(self, CEft, i32)
+                nop_command!(inst, CEft)
             }
             144 => {
                 // CEft_Role
-                nop_command!(self, CEft, i32)
+                nop_command!(inst, CEft)
             }
             145 => {
                 // AverageLv
-                nop_command!(self, AverageLv, i32, i32)
+                nop_command!(inst, AverageLv)
             }
             147 => {
                 // Switch2Menu
-                nop_command!(self, Switch2Menu)
+                nop_command!(inst, Switch2Menu)
             }
             148 => {
                 // CEft_Load
-                nop_command!(self, CEft_Load, i32)
+                nop_command!(inst, CEft_Load)
             }
             149 => {
                 // GiveCloth
-                nop_command!(self, GiveCloth, i16)
+                nop_command!(inst, GiveCloth)
             }
             150 => {
                 // LoadAct
-                nop_command!(self, LoadAct, i32, string)
+                nop_command!(inst, LoadAct)
             }
             152 => {
                 // WaterMagic
-                nop_command!(self, WaterMagic, i32)
+                nop_command!(inst, WaterMagic)
             }
             153 => {
                 // FullTeamAtt
-                nop_command!(self, FullTeamAtt)
+                nop_command!(inst, FullTeamAtt)
             }
             155 => {
                 // CameraYaw
-                nop_command!(self, CameraYaw, f32)
+                nop_command!(inst, CameraYaw)
             }
             156 => {
                 // XJ_Pic
-                nop_command!(self, XJ_Pic)
+                nop_command!(inst, XJ_Pic)
             }
             158 => {
                 // ObjNotLoad
-                nop_command!(self, ObjNotLoad, i32)
+                nop_command!(inst, ObjNotLoad)
             }
             159 => {
                 // InitFlower
-                nop_command!(self, InitFlower)
+                nop_command!(inst, InitFlower)
             }
             201 => {
                 // RolePathOut
                 command!(
-                    self,
+                    inst,
                     SceCommandRolePathOut,
                     role_id: i32,
                     x: i32,
@@ -804,28 +944,28 @@ impl SceProcContext {
             }
             202 => {
                 // InTeam
-                nop_command!(self, InTeam, i32, i32)
+                nop_command!(inst, InTeam)
             }
             203 => {
                 // RoleSetLayer
-                command!(self, SceCommandRoleSetLayer, role_id: i32, layer: i32)
+                command!(inst, SceCommandRoleSetLayer, role_id: i32, layer: i32)
             }
             204 => {
                 // RoleCtrl
-                command!(self, SceCommandRoleCtrl, role_id: i32)
+                command!(inst, SceCommandRoleCtrl, role_id: i32)
             }
             205 => {
                 // RoleOverlap
-                nop_command!(self, RoleOverlap, i32, i32)
+                nop_command!(inst, RoleOverlap)
             }
             206 => {
                 // RoleScale
-                nop_command!(self, RoleScale, i32, f32)
+                nop_command!(inst, RoleScale)
             }
             207 => {
                 // RoleActAutoStand
                 command!(
-                    self,
+                    inst,
                     SceCommandRoleActAutoStand,
                     role_id: i32,
                     auto_play_idle: i32
@@ -833,28 +973,28 @@ impl SceProcContext {
             }
             208 => {
                 // RoleMoveBack
-                command!(self, SceCommandRoleMoveBack, role_id: i32, speed: f32)
+                command!(inst, SceCommandRoleMoveBack, role_id: i32, speed: f32)
             }
             209 => {
                 // RoleFaceRole
-                command!(self, SceCommandRoleFaceRole, role_id: i32, role_id2: i32)
+                command!(inst, SceCommandRoleFaceRole, role_id: i32, role_id2: i32)
             }
             210 => {
                 // RoleTurnFaceA
-                command!(self, SceCommandRoleSetFace, role_id: i32, direction: i32)
+                command!(inst, SceCommandRoleSetFace, role_id: i32, direction: i32)
             }
             211 => {
                 // TeamOpenA
-                nop_command!(self, TeamOpenA)
+                nop_command!(inst, TeamOpenA)
             }
             212 => {
                 // TeamCloseA
-                nop_command!(self, TeamCloseA)
+                nop_command!(inst, TeamCloseA)
             }
             214 => {
                 // RoleMovTo
                 command!(
-                    self,
+                    inst,
                     SceCommandRoleMoveTo,
                     role_id: i32,
                     x: i32,
@@ -864,19 +1004,18 @@ impl SceProcContext {
             }
             221 => {
                 // RoleEndAction
-                command!(self, SceCommandRoleEndAction, role_id: i32)
+                command!(inst, SceCommandRoleEndAction, role_id: i32)
             }
             250 => {
                 // CameraFree
-                nop_command!(self, CameraFree, i32)
+                nop_command!(inst, CameraFree)
             }
             251 => {
                 // ObjectMove
-                nop_command!(self, ObjectMove, i32, f32, f32, f32, f32)
+                nop_command!(inst, ObjectMove)
             }
             default => {
                 error!("Unsupported command: {}", default);
-                self.put(4);
                 panic!();
             }
         }
@@ -886,68 +1025,55 @@ impl SceProcContext {
         self.program_counter = addr as usize;
     }
 
-    fn put(&mut self, count: usize) {
-        self.program_counter -= count;
-    }
-
-    fn read_string(&mut self, len: usize) -> String {
-        let proc = self.sce.procs.get(&self.proc_id).unwrap();
-        let end = self.program_counter + len;
-        let text = encoding::all::GBK
-            .decode(
-                &proc.inst[self.program_counter..end - 1],
-                DecoderTrap::Ignore,
-            )
-            .unwrap();
-        self.program_counter = end;
-        text
-    }
-
-    fn read(&mut self, count: usize) -> &[u8] {
-        let proc = self.sce.procs.get(&self.proc_id).unwrap();
-        let ret = &proc.inst[self.program_counter..self.program_counter + count];
-        self.program_counter += count;
-        ret
-    }
-
     fn proc_completed(&self) -> bool {
         let proc = self.sce.procs.get(&self.proc_id).unwrap();
         self.program_counter >= proc.inst.len()
     }
 }
 
+/// Operands in the `sce_asm` text syntax, minus labels.
+fn format_arg(arg: &SceArg) -> String {
+    match arg {
+        SceArg::Int(v) => v.to_string(),
+        SceArg::UInt(v) => v.to_string(),
+        SceArg::Addr(addr) => format!("@{}", addr),
+        SceArg::Float(v) => v.to_string(),
+        SceArg::Var(v) => format!("${}", v),
+        SceArg::Str(_) => format!("{:?}", arg.as_text()),
+        SceArg::List(_) => format!("{:?}", arg.as_list()),
+    }
+}
+
 mod data_read {
-    use byteorder::{LittleEndian, ReadBytesExt};
+    use anyhow::Context;
+    use fileformats::pal3::sce::SceArg;
 
-    pub(super) fn i16(context: &mut super::SceProcContext) -> i16 {
-        context.read(2).read_i16::<LittleEndian>().unwrap()
+    fn operand(arg: Option<&SceArg>) -> anyhow::Result<&SceArg> {
+        arg.context("missing from the decoded instruction")
     }
 
-    pub(super) fn i32(context: &mut super::SceProcContext) -> i32 {
-        context.read(4).read_i32::<LittleEndian>().unwrap()
+    pub(super) fn i16(arg: Option<&SceArg>) -> anyhow::Result<i16> {
+        operand(arg).map(SceArg::as_i16)
     }
 
-    pub(super) fn u32(context: &mut super::SceProcContext) -> u32 {
-        context.read(4).read_u32::<LittleEndian>().unwrap()
+    pub(super) fn i32(arg: Option<&SceArg>) -> anyhow::Result<i32> {
+        operand(arg).map(SceArg::as_i32)
     }
 
-    pub(super) fn f32(context: &mut super::SceProcContext) -> f32 {
-        context.read(4).read_f32::<LittleEndian>().unwrap()
+    pub(super) fn u32(arg: Option<&SceArg>) -> anyhow::Result<u32> {
+        operand(arg).map(SceArg::as_u32)
     }
 
-    pub(super) fn string(context: &mut super::SceProcContext) -> String {
-        let len = context.read(2).read_u16::<LittleEndian>().unwrap();
-        context.read_string(len as usize)
+    pub(super) fn f32(arg: Option<&SceArg>) -> anyhow::Result<f32> {
+        operand(arg).map(SceArg::as_f32)
     }
 
-    pub(super) fn list(context: &mut super::SceProcContext) -> Vec<String> {
-        let len = context.read(2).read_u16::<LittleEndian>().unwrap();
-        (0..len)
-            .map(|_| {
-                let _ = context.read(1);
-                string(context)
-            })
-            .collect()
+    pub(super) fn string(arg: Option<&SceArg>) -> anyhow::Result<String> {
+        operand(arg).map(SceArg::as_text)
+    }
+
+    pub(super) fn list(arg: Option<&SceArg>) -> anyhow::Result<Vec<String>> {
+        operand(arg).map(SceArg::as_list)
     }
 }
 
@@ -956,6 +1082,7 @@ pub struct SceExecutionContext {
     sce_name: String,
     proc_stack: Vec<SceProcContext>,
     options: Option<SceExecutionOptions>,
+    tracer: Tracer,
 }
 
 impl SceExecutionContext {
@@ -965,6 +1092,7 @@ impl SceExecutionContext {
             sce_name,
             proc_stack: vec![],
             options,
+            tracer: Tracer::default(),
         }
     }
 
@@ -976,6 +1104,7 @@ impl SceExecutionContext {
     pub fn call_proc(&mut self, proc_id: u32, global_state: &mut GlobalState) {
         self.proc_stack
             .push(SceProcContext::new_from_id(self.sce.clone(), proc_id));
+        self.trace_enter();
         self.proc_begin(proc_id, global_state);
     }
 
@@ -984,9 +1113,40 @@ impl SceExecutionContext {
         if let Some(c) = context {
             self.proc_begin(c.proc_id, global_state);
             self.proc_stack.push(c);
+            self.trace_enter();
         }
     }
 
+    pub fn set_trace_sink(&mut self, sink: Option<Rc<dyn TraceSink>>) {
+        self.tracer.set_sink(sink);
+    }
+
+    /// Record an event for the running instruction; `kind` gets the
+    /// proc's label and the instruction's address. Used by commands that
+    /// touch variables or branch.
+    pub fn trace(&self, kind: impl FnOnce(String, usize) -> TraceEventKind) {
+        if !self.tracer.is_enabled() {
+            return;
+        }
+
+        if let Some(proc) = self.proc_stack.last() {
+            self.tracer.record(kind(proc.label(), proc.inst_addr));
+        }
+    }
+
+    fn trace_enter(&self) {
+        if !self.tracer.is_enabled() {
+            return;
+        }
+
+        let proc = self.proc_stack.last().unwrap();
+        self.tracer.record(TraceEventKind::FnEnter {
+            name: proc.label(),
+            function_index: proc.proc_id as usize,
+            depth: self.proc_stack.len(),
+        });
+    }
+
     pub fn jump_to(&mut self, addr: u32) {
         self.proc_stack.last_mut().unwrap().jump_to(addr);
     }
@@ -995,8 +1155,8 @@ impl SceExecutionContext {
         self.proc_stack.last_mut().unwrap().set_local(var, value);
     }
 
-    pub fn get_local(&mut self, var: i16) -> Option<i32> {
-        self.proc_stack.last_mut().unwrap().get_local(var)
+    pub fn get_local(&self, var: i16) -> Option<i32> {
+        self.proc_stack.last().unwrap().get_local(var)
     }
 
     pub fn current_proc_context_mut(&mut self) -> &mut SceProcContext {
@@ -1028,6 +1188,10 @@ impl SceExecutionContext {
     /// Resolved against the loaded `SceFile`'s header table.
     pub fn current_proc_name(&self) -> Option<String> {
         let proc_id = self.proc_stack.last()?.proc_id();
+        self.proc_name(proc_id)
+    }
+
+    pub fn proc_name(&self, proc_id: u32) -> Option<String> {
         self.sce
             .proc_headers
             .iter()
@@ -1035,10 +1199,26 @@ impl SceExecutionContext {
             .map(|h| h.name.clone())
     }
 
+    pub fn sce(&self) -> &SceFile {
+        &self.sce
+    }
+
+    /// The proc that will run the next instruction: the innermost one
+    /// that hasn't run off its end yet.
+    pub fn next_proc(&self) -> Option<&SceProcContext> {
+        self.proc_stack.iter().rev().find(|p| !p.proc_completed())
+    }
+
     pub fn get_next_cmd(&mut self, global_state: &mut GlobalState) -> Option<Box<dyn SceCommand>> {
         while let Some(p) = self.proc_stack.last() {
             if p.proc_completed() {
                 debug!("Sce proc {} completed", p.proc_id);
+                if self.tracer.is_enabled() {
+                    self.tracer.record(TraceEventKind::FnExit {
+                        name: p.label(),
+                        depth: self.proc_stack.len(),
+                    });
+                }
                 self.proc_end(p.proc_id, global_state);
                 self.proc_stack.pop();
             } else {
@@ -1046,7 +1226,10 @@ impl SceExecutionContext {
             }
         }
 
-        self.proc_stack.last_mut().and_then(|p| p.get_next_cmd())
+        let tracer = &self.tracer;
+        self.proc_stack
+            .last_mut()
+            .and_then(|p| p.get_next_cmd(tracer))
     }
 
     fn proc_begin(&self, proc_id: u32, global_state: &mut GlobalState) {
@@ -1065,3 +1248,18 @@ impl SceExecutionContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use fileformats::pal3::sce::SceArg;
+
+    use super::data_read;
+
+    #[test]
+    fn missing_operands_are_errors() {
+        assert_eq!(data_read::i16(Some(&SceArg::Int(-3))).unwrap(), -3);
+        assert!(data_read::i16(None).is_err());
+        assert!(data_read::f32(None).is_err());
+        assert!(data_read::string(None).is_err());
+    }
+}
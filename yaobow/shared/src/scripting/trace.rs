@@ -1,13 +1,28 @@
-//! Structured execution trace produced by the AngelScript VM.
+//! Structured execution trace produced by the script VMs.
 //!
 //! The [`TraceSink`] trait is the integration point: install one via
-//! [`ScriptVm::set_trace_sink`](super::ScriptVm::set_trace_sink) and
-//! every branch, sysfn call, and global read/write the VM executes is
-//! delivered as a [`TraceEvent`]. When no sink is installed the VM
+//! [`ScriptVm::set_trace_sink`](super::angelscript::ScriptVm::set_trace_sink),
+//! [`SceVm::set_trace_sink`](super::sce::vm::SceVm::set_trace_sink) or
+//! [`Lua5032Vm::set_trace_sink`](super::lua50_32::Lua5032Vm::set_trace_sink)
+//! and every branch, engine call, and global read/write the VM executes
+//! is delivered as a [`TraceEvent`]. When no sink is installed the VM
 //! skips the recording entirely (single `Option::is_none()` check on
 //! the hot path), so the unobserved cost is zero beyond the existing
 //! interpreter loop.
 //!
+//! The event kinds were shaped after AngelScript's opcodes; the other
+//! VMs map onto them as follows:
+//!
+//! * SCE: procedures are functions (`function_index` is the proc id,
+//!   `pc` the instruction's code offset), every instruction is a
+//!   [`TraceEventKind::Command`], `$N` variables are globals (N < 0,
+//!   [`GlobalScope::Shared`]) or [`GlobalScope::Local`]s, and a
+//!   `TestGoto` is a [`BranchKind::TestGoto`] branch.
+//! * Lua: `pc` is the line number, engine commands are
+//!   [`TraceEventKind::Command`]s, flag reads and writes are
+//!   [`GlobalScope::Shared`] globals and coroutine yields are
+//!   [`TraceEventKind::Suspend`]s.
+//!
 //! The producer side (the VM) emits owned data — interned function
 //! names as `String`, snapshot register values — so the consumer can
 //! cross thread/queue boundaries (e.g. push events into the
@@ -15,11 +30,14 @@
 //!
 //! This module deliberately stays free of dependencies on the agent
 //! server crate: a downstream adapter (typically in
-//! `yaobow/shared/src/openpal4/agent.rs`) implements [`TraceSink`]
+//! `yaobow/shared/src/agent_common/trace.rs`) implements [`TraceSink`]
 //! against whatever transport it wants. Tests in this crate use a
 //! simple `Mutex<Vec<_>>` sink.
 
-/// Sink for trace events emitted by [`ScriptVm`](super::ScriptVm).
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Sink for trace events emitted by the script VMs.
 ///
 /// Implementations must be cheap on the hot path — the VM calls
 /// [`Self::record`] from inside its opcode dispatch loop. A typical
@@ -50,7 +68,7 @@ pub enum TraceEventKind {
         /// Name of the entered function. May be empty if the module's
         /// function table didn't carry a name for the slot.
         name: String,
-        /// Index into the owning [`ScriptModule::functions`](super::module::ScriptModule).
+        /// Index into the owning [`ScriptModule::functions`](super::angelscript::ScriptModule).
         function_index: usize,
         /// Depth *after* the push (i.e. `call_stack.len()` once the
         /// new frame is active).
@@ -121,6 +139,16 @@ pub enum TraceEventKind {
     /// Cooperative-yield point (`Suspend` opcode). Signals "engine
     /// is going to let other work run before this function resumes".
     Suspend { fn_name: String, pc: usize },
+
+    /// An engine command: an SCE instruction, or a Lua call into a
+    /// registered C function. `args` are display-formatted.
+    Command {
+        fn_name: String,
+        pc: usize,
+        /// Command name (`"RoleMoveTo"`, `"npc.Create"`, …).
+        name: String,
+        args: Vec<String>,
+    },
 }
 
 /// Kind of conditional jump.
@@ -147,6 +175,9 @@ pub enum BranchKind {
     JpJlez,
     /// `Jnp` (a.k.a. "Jlez"): branch taken when the comparison result `<= 0`.
     JnpJgz,
+    /// SCE `TestGoto`: taken when the `Fop` accumulator (the operand,
+    /// 0 or 1) is false. `offset` is the absolute target address.
+    TestGoto,
 }
 
 /// Which global-variable namespace the read/write referenced.
@@ -163,6 +194,36 @@ pub enum GlobalScope {
     /// Module-local global (positive index, indexes
     /// `ScriptModule::globals`).
     Module,
+    /// SCE procedure-local variable (`$N` with N >= 0).
+    Local,
+}
+
+/// Optional sink plus sequence counter, for VMs that emit from more
+/// than one place (SCE commands, Lua trampolines). Takes `&self` so it
+/// can sit behind shared references.
+#[derive(Default)]
+pub struct Tracer {
+    sink: Option<Rc<dyn TraceSink>>,
+    seq: Cell<u64>,
+}
+
+impl Tracer {
+    pub fn set_sink(&mut self, sink: Option<Rc<dyn TraceSink>>) {
+        self.sink = sink;
+    }
+
+    /// Check before building an event's strings.
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    pub fn record(&self, kind: TraceEventKind) {
+        if let Some(sink) = &self.sink {
+            let seq = self.seq.get();
+            self.seq.set(seq.wrapping_add(1));
+            sink.record(TraceEvent { seq, kind });
+        }
+    }
 }
 
 #[cfg(test)]
@@ -197,3 +258,40 @@ pub mod test_support {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::VecSink;
+    use super::*;
+
+    fn suspend(pc: usize) -> TraceEventKind {
+        TraceEventKind::Suspend {
+            fn_name: "main".to_string(),
+            pc,
+        }
+    }
+
+    #[test]
+    fn tracer_numbers_events_and_is_inert_without_a_sink() {
+        let mut tracer = Tracer::default();
+        assert!(!tracer.is_enabled());
+        tracer.record(suspend(0));
+
+        let sink = Rc::new(VecSink::new());
+        tracer.set_sink(Some(sink.clone()));
+        assert!(tracer.is_enabled());
+        tracer.record(suspend(1));
+        tracer.record(suspend(2));
+
+        let events = sink.drain();
+        assert_eq!(events.iter().map(|e| e.seq).collect::<Vec<_>>(), [0, 1]);
+        assert!(matches!(
+            events[1].kind,
+            TraceEventKind::Suspend { pc: 2, .. }
+        ));
+
+        tracer.set_sink(None);
+        tracer.record(suspend(3));
+        assert!(sink.drain().is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+use imgui::{Condition, Ui};
+use radiance::input::{InputEngine, Key};
+
+/// Minimal modal "pick one" prompt drawn over the lower half of the
+/// screen: a line of prompt text followed by a vertical list of
+/// options. Used by script continuations that must block on a player
+/// decision (PAL4 inn / quest-offer dialogs).
+///
+/// The prompt is immediate-mode: the owning continuation calls
+/// [`ChoicePrompt::update`] once per frame and stops looping when it
+/// returns `Some(index)`. Keyboard / gamepad navigate with Up/Down and
+/// confirm with Space / South; a mouse click on an option confirms it
+/// directly.
+pub struct ChoicePrompt {
+    title: String,
+    items: Vec<String>,
+    selected: usize,
+}
+
+impl ChoicePrompt {
+    pub fn new(title: String, items: Vec<String>) -> Self {
+        Self {
+            title,
+            items,
+            selected: 0,
+        }
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    /// Draw one frame of the prompt and poll for a decision. Returns
+    /// the 0-based index of the confirmed option, or `None` while the
+    /// player is still choosing.
+    pub fn update(&mut self, ui: &Ui, input: &dyn InputEngine) -> Option<usize> {
+        if self.items.is_empty() {
+            return Some(0);
+        }
+
+        if input.get_key_state(Key::Up).pressed()
+            || input.get_key_state(Key::GamePadDPadUp).pressed()
+        {
+            self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+        }
+        if input.get_key_state(Key::Down).pressed()
+            || input.get_key_state(Key::GamePadDPadDown).pressed()
+        {
+            self.selected = (self.selected + 1) % self.items.len();
+        }
+
+        let mut confirmed = None;
+        if input.get_key_state(Key::Space).pressed()
+            || input.get_key_state(Key::GamePadSouth).pressed()
+        {
+            confirmed = Some(self.selected);
+        }
+
+        let [window_width, window_height] = ui.io().display_size;
+        let width = (window_width * 0.4).max(240.);
+        let height = window_height * 0.3;
+        let _font_token = radiance::imgui::game_font(radiance::imgui::GameFontSize::LARGE)
+            .map(|f| ui.push_font(f));
+        ui.window("choice_prompt")
+            .collapsible(false)
+            .title_bar(false)
+            .resizable(false)
+            .movable(false)
+            .no_decoration()
+            .position(
+                [(window_width - width) / 2., window_height * 0.55],
+                Condition::Always,
+            )
+            .size([width, height], Condition::Always)
+            .build(|| {
+                ui.text_wrapped(&self.title);
+                ui.separator();
+                for (i, item) in self.items.iter().enumerate() {
+                    if ui
+                        .selectable_config(item)
+                        .selected(i == self.selected)
+                        .build()
+                    {
+                        self.selected = i;
+                        confirmed = Some(i);
+                    }
+                }
+            });
+
+        confirmed
+    }
+}
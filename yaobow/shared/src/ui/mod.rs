@@ -1,2 +1,3 @@
+pub mod choice_prompt;
 pub mod dialog_box;
 pub mod dialog_markup;
@@ -0,0 +1,157 @@
+//! Boots the first PAL5Q scene against a minimal fixture install.
+//!
+//! Writes a plain-file tree (no `.pkg` archives) with a prequel-style
+//! `scriptlist.ini`, role index, `MapInfo.ini` and a single empty map
+//! block, mounts it through the production `packfs::init_virtual_fs`,
+//! and drives the same path the story director takes on a new game:
+//! resolve and run the entry script, then load the scene for the map
+//! it changes to.
+
+mod support;
+
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use fileformats::binrw::{BinWrite, NullString};
+use fileformats::role_bin::{AssetItem, RoleBinFile};
+use packfs::init_virtual_fs;
+use radiance::comdef::ISceneExt;
+use radiance::rendering::ComponentFactory;
+use shared::GameType;
+use shared::openpal5::asset_loader::AssetLoader;
+use shared::openpal5::scene::Pal5Scene;
+use shared::openpal5::script::ScriptIndex;
+use shared::scripting::lua50_32::{Lua5032Vm, LuaValue};
+use support::NullComponentFactory;
+
+/// Stand-ins for the map commands the entry script calls; the map id
+/// it changes to lands in `__changed_to`.
+const MAP_COMMANDS: &str = r#"
+map = {}
+function map.ChangeNoScript(id, sub) __changed_to = id end
+"#;
+
+/// A `.nod` block with no nodes: magic, version 9, zero nodes, and the
+/// two trailing words.
+fn empty_nod() -> Vec<u8> {
+    [0x0001e240u32, 9, 0, 0, 0]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+
+/// A `role_*.bin` indexing one asset.
+fn role_bin(id: u32) -> Vec<u8> {
+    let file = RoleBinFile {
+        version: 105,
+        item_count: 1,
+        items: vec![AssetItem {
+            id,
+            unknown: [0; 17],
+            unknown_f32: [0.; 3],
+            file_path: NullString::from(format!("xq\\{}.dff", id)),
+            folder_folder: NullString::from("xq"),
+            empty_string: NullString::default(),
+            empty_string2: NullString::default(),
+        }],
+    };
+    let mut out = Cursor::new(Vec::new());
+    file.write(&mut out).unwrap();
+    out.into_inner()
+}
+
+fn write(root: &Path, path: &str, content: &[u8]) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+fn fixture_tree() -> PathBuf {
+    let root = std::env::temp_dir().join(format!("yaobow-pal5q-fixture-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+
+    // The prequel numbers its root scripts differently from PAL5, so
+    // `NewGame` is not id 1.
+    write(
+        &root,
+        "Config/data/scriptlist.ini",
+        b"[.]\n3=prelogue\n5=NewGame\n\n[mainline]\n7001=q001_1\n",
+    );
+    write(
+        &root,
+        "script/NewGame.lua",
+        b"function NewGame()\n  map.ChangeNoScript(4, 0)\nend\n",
+    );
+    // Role index files are read in number order up to the first gap.
+    write(&root, "Config/role_00.bin", &role_bin(100));
+    write(&root, "Config/role_01.bin", &role_bin(101));
+    write(&root, "Config/role_03.bin", &role_bin(300));
+    write(
+        &root,
+        "Config/Data/MapInfo.ini",
+        b"[30]\nName=xq_tail\n[4]\nName=xq_first\nAmbient=0.6\n",
+    );
+    write(&root, "Map/xq_first/xq_first_0_0.nod", &empty_nod());
+
+    root
+}
+
+fn global(vm: &Lua5032Vm<()>, name: &str) -> Option<LuaValue> {
+    vm.enumerate_globals()
+        .into_iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v)
+}
+
+#[test]
+fn boots_first_pal5q_scene_from_fixture_tree() {
+    let root = fixture_tree();
+    let game = GameType::PAL5Q;
+    let vfs = Rc::new(init_virtual_fs(&root, game.pkg_key()));
+    let factory: Rc<dyn ComponentFactory> = Rc::new(NullComponentFactory::new());
+
+    assert!(AssetLoader::new(factory.clone(), vfs.clone(), GameType::SWD5).is_err());
+
+    let scripts = ScriptIndex::load(&vfs).expect("scriptlist.ini");
+    let asset_loader = AssetLoader::new(factory, vfs, game).expect("PAL5Q asset loader");
+    let mut indexed: Vec<u32> = asset_loader.index.keys().copied().collect();
+    indexed.sort_unstable();
+    assert_eq!(indexed, vec![100, 101]);
+
+    let entry = asset_loader.entry_script_id(&scripts);
+    assert_eq!(entry, Some(5));
+    let (name, source) = scripts
+        .load_source(asset_loader.vfs(), entry.unwrap())
+        .expect("entry script source");
+    assert_eq!(name, "NewGame");
+
+    // Run the entry script the way the director does: load it, check it
+    // defines its own-named function, and enter that function.
+    let vm = Lua5032Vm::create(Rc::new(RefCell::new(())));
+    vm.load_chunk(MAP_COMMANDS.as_bytes(), "map_commands")
+        .unwrap();
+    vm.load_chunk(&source, &name).unwrap();
+    assert!(vm.script_functions().contains(&name));
+    vm.set_entry(&name).unwrap();
+    vm.execute().unwrap();
+    let Some(LuaValue::Number(map_id)) = global(&vm, "__changed_to") else {
+        panic!("entry script did not change map");
+    };
+
+    // The prequel has no known pre-map-change scene, so nothing loads
+    // before the entry script's own map change picks the first scene.
+    assert_eq!(asset_loader.bootstrap_map(), None);
+    assert_eq!(asset_loader.scene_for_map(0), None);
+    assert_eq!(asset_loader.scene_for_map(99), None);
+    let map = asset_loader
+        .scene_for_map(map_id as i32)
+        .expect("map listed in MapInfo.ini");
+    assert_eq!(map, "xq_first");
+
+    let scene = Pal5Scene::load(&asset_loader, &map).expect("first scene loads");
+    assert!(scene.scene.entities().is_empty());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
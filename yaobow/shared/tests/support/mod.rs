@@ -0,0 +1,134 @@
+//! Shared test-support helpers for the integration tests in this
+//! directory, included with `mod support;`.
+//!
+//! [`NullComponentFactory`] hands out inert textures, render objects and
+//! render targets so asset loaders and scene builders can run without a
+//! rendering backend. Nothing is ever drawn; render objects keep their
+//! vertex buffer so `update_vertices` still works.
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell, RefMut};
+use std::rc::Rc;
+
+use imgui::TextureId;
+
+use radiance::rendering::{
+    ComponentFactory, MaterialDef, RenderObject, RenderObjectHandle, RenderTarget,
+    RenderingComponent, Texture, TextureDef, VertexBuffer, VideoPlayer,
+};
+
+/// [`ComponentFactory`] that allocates nothing on a GPU. Each imgui
+/// texture upload gets a fresh [`TextureId`]; [`Self::imgui_uploads`]
+/// counts them.
+#[derive(Default)]
+pub struct NullComponentFactory {
+    imgui_uploads: Cell<usize>,
+}
+
+impl NullComponentFactory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of `create_imgui_texture` calls so far.
+    pub fn imgui_uploads(&self) -> usize {
+        self.imgui_uploads.get()
+    }
+}
+
+impl ComponentFactory for NullComponentFactory {
+    fn create_texture(&self, texture_def: &TextureDef) -> Box<dyn Texture> {
+        let (width, height) =
+            texture_def.with_image(|image| image.map_or((1, 1), |image| image.dimensions()));
+        Box::new(NullTexture { width, height })
+    }
+
+    fn create_imgui_texture(
+        &self,
+        _buffer: &[u8],
+        _row_length: u32,
+        width: u32,
+        height: u32,
+        texture_id: Option<TextureId>,
+    ) -> (Box<dyn Texture>, TextureId) {
+        let upload = self.imgui_uploads.get() + 1;
+        self.imgui_uploads.set(upload);
+        let texture_id = texture_id.unwrap_or_else(|| TextureId::new(upload));
+        (Box::new(NullTexture { width, height }), texture_id)
+    }
+
+    fn remove_imgui_texture(&self, _texture_id: Option<TextureId>) {}
+
+    fn create_render_object(
+        &self,
+        vertices: VertexBuffer,
+        _indices: Vec<u32>,
+        _material_def: &MaterialDef,
+        _host_dynamic: bool,
+    ) -> RenderObjectHandle {
+        RenderObjectHandle::from_dyn(Rc::new(NullRenderObject {
+            vertices: RefCell::new(vertices),
+        }))
+    }
+
+    fn create_rendering_component(&self, objects: Vec<RenderObjectHandle>) -> RenderingComponent {
+        let mut component = RenderingComponent::new();
+        for object in objects {
+            component.push_render_object(object);
+        }
+        component
+    }
+
+    fn create_video_player(&self) -> Box<VideoPlayer> {
+        Box::new(VideoPlayer::new())
+    }
+
+    fn create_render_target(&self, width: u32, height: u32) -> Box<dyn RenderTarget> {
+        Box::new(NullRenderTarget {
+            extent: (width, height),
+        })
+    }
+}
+
+struct NullTexture {
+    width: u32,
+    height: u32,
+}
+
+impl Texture for NullTexture {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+struct NullRenderObject {
+    vertices: RefCell<VertexBuffer>,
+}
+
+impl RenderObject for NullRenderObject {
+    fn update_vertices(&self, updater: &dyn Fn(RefMut<VertexBuffer>)) {
+        updater(self.vertices.borrow_mut());
+    }
+}
+
+struct NullRenderTarget {
+    extent: (u32, u32),
+}
+
+impl RenderTarget for NullRenderTarget {
+    fn extent(&self) -> (u32, u32) {
+        self.extent
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.extent = (width, height);
+    }
+
+    fn imgui_texture_id(&self) -> u64 {
+        0
+    }
+}
@@ -1,11 +1,8 @@
 pub mod yaobow_host_context;
 
-use std::cell::RefCell;
-use std::io::Cursor;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
-use fileformats::{binrw::BinRead, npc::NpcInfoFile};
-
 use agent_server::AgentServer;
 use crosscom::ComRc;
 use radiance::{
@@ -46,6 +43,9 @@ pub struct BootOptions {
     /// PAL4 agent-server boot options. Only meaningful when
     /// `initial_game == Some(GameType::PAL4)`.
     pub agent_opts: Option<AgentBootOptions>,
+    /// Loopback port for the PAL4 script debugger (DAP). Only
+    /// meaningful when `initial_game == Some(GameType::PAL4)`.
+    pub script_debug_port: Option<u16>,
 }
 
 impl BootOptions {
@@ -62,6 +62,7 @@ impl BootOptions {
             initial_game: Some(game),
             asset_path: None,
             agent_opts: None,
+            script_debug_port: None,
         }
     }
 
@@ -92,6 +93,13 @@ impl BootOptions {
         }
         self
     }
+
+    pub fn with_script_debug_port_opt(mut self, port: Option<u16>) -> Self {
+        if let Some(p) = port {
+            self.script_debug_port = Some(p);
+        }
+        self
+    }
 }
 
 /// The yaobow application loader (phase 2 — direct script handoff).
@@ -129,6 +137,7 @@ pub struct YaobowApplicationLoader {
     initial_game: Option<GameType>,
     initial_asset_path: RefCell<Option<String>>,
     initial_agent_opts: RefCell<Option<AgentBootOptions>>,
+    initial_script_debug_port: Cell<Option<u16>>,
     /// Live PAL4 agent-server HTTP listener. Held for the loader
     /// lifetime so the listener thread is joined exactly once at
     /// process exit.
@@ -243,6 +252,7 @@ impl YaobowApplicationLoader {
             initial_game: None,
             initial_asset_path: RefCell::new(None),
             initial_agent_opts: RefCell::new(None),
+            initial_script_debug_port: Cell::new(None),
             agent_server: RefCell::new(None),
         }
     }
@@ -285,6 +295,7 @@ impl YaobowApplicationLoader {
             }
             GameType::PAL4 => {
                 self.boot_pal4_agent_if_requested(host_ctx)?;
+                self.start_pal4_script_debugger_if_requested(host_ctx);
                 host_ctx.pal4().create_director(&asset_path)
             }
             GameType::PAL5 | GameType::PAL5Q => {
@@ -296,7 +307,7 @@ impl YaobowApplicationLoader {
                     .create_director(&asset_path, game_ordinal)
                     .ok_or_else(|| {
                         format!(
-                            "PAL5 story director build failed (likely missing PAL5 assets at {asset_path})"
+                            "PAL5 title director build failed (likely missing PAL5 assets at {asset_path})"
                         )
                     })?
             }
@@ -305,7 +316,9 @@ impl YaobowApplicationLoader {
                 let game_ordinal = ordinal_for_game(game);
                 host_ctx.swd5().create_director(&asset_path, game_ordinal)
             }
-            _ => return Err(format!("unsupported game type: {}", game.app_name())),
+            GameType::Gujian | GameType::Gujian2 => {
+                crate::opengujian::create_director(&self.app, &asset_path, game)
+            }
         };
 
         scene_manager.set_director(director);
@@ -431,6 +444,23 @@ impl YaobowApplicationLoader {
         Ok(())
     }
 
+    /// Start the PAL4 script debugger (if `--script-debug-port` was
+    /// passed). A bind failure is logged and the game boots without
+    /// it, since the debugger is a development aid.
+    fn start_pal4_script_debugger_if_requested(&self, host_ctx: &ComRc<IYaobowHostContext>) {
+        let Some(port) = self.initial_script_debug_port.take() else {
+            return;
+        };
+
+        let service = host_ctx
+            .pal4()
+            .inner::<shared::openpal4::service::Pal4Service>();
+        match service.start_script_debugger(port) {
+            Ok(addr) => log::info!("script debugger: DAP listening on {}", addr),
+            Err(e) => log::error!("script debugger: failed to listen on port {}: {}", port, e),
+        }
+    }
+
     /// Construct a generic `AgentBridge` (game-agnostic) from the
     /// real engine input, boot the HTTP listener against it, and
     /// stash the listener handle in `self.agent_server`. Returns
@@ -507,6 +537,7 @@ pub fn create_application(opts: BootOptions) -> ComRc<IApplication> {
     if let Some(a) = opts.agent_opts {
         loader.initial_agent_opts = RefCell::new(Some(a));
     }
+    loader.initial_script_debug_port = Cell::new(opts.script_debug_port);
     app.add_component(
         IApplicationLoaderComponent::uuid(),
         ComRc::from_object(loader),
@@ -638,7 +669,17 @@ pub fn run_openpal4() {
 }
 
 pub fn run_openpal4_with_agent(agent: Option<AgentBootOptions>) {
-    run_app(boot_for(GameType::PAL4).with_agent_opts_opt(agent));
+    run_openpal4_with_options(agent, None);
+}
+
+/// PAL4 direct boot with the optional agent server and script
+/// debugger (`--script-debug-port`).
+pub fn run_openpal4_with_options(agent: Option<AgentBootOptions>, script_debug_port: Option<u16>) {
+    run_app(
+        boot_for(GameType::PAL4)
+            .with_agent_opts_opt(agent)
+            .with_script_debug_port_opt(script_debug_port),
+    );
 }
 
 pub fn run_openswd5() {
@@ -666,8 +707,9 @@ pub fn run_openswdcf_with_agent(agent: Option<AgentBootOptions>) {
 }
 
 pub fn run_opengujian() {
-    let data = std::fs::read("F:\\PAL4\\gamedata\\scenedata\\scenedata\\q01\\N01\\npcInfo.npc")
-        .expect("Gujian NPC info path must exist on this machine");
-    let cam = NpcInfoFile::read(&mut Cursor::new(data));
-    println!("cam: {:?}", cam);
+    run_app(boot_for(GameType::Gujian));
+}
+
+pub fn run_opengujian2() {
+    run_app(boot_for(GameType::Gujian2));
 }
@@ -4,8 +4,8 @@ use agent_server::AgentLogSink;
 use log::{Level, LevelFilter, Log, Metadata, Record};
 use shared::video::register_opengb_video_decoders;
 use yaobow_lib::{
-    Pal4AgentBootOptions, run_opengujian, run_openpal3, run_openpal3_with_agent, run_openpal3a,
-    run_openpal3a_with_agent, run_openpal4, run_openpal4_with_agent, run_openpal5,
+    Pal4AgentBootOptions, run_opengujian, run_opengujian2, run_openpal3, run_openpal3_with_agent,
+    run_openpal3a, run_openpal3a_with_agent, run_openpal4, run_openpal4_with_options, run_openpal5,
     run_openpal5_with_agent, run_openpal5q, run_openpal5q_with_agent, run_openswd5,
     run_openswd5_with_agent, run_openswdcf, run_openswdcf_with_agent, run_openswdhc,
     run_openswdhc_with_agent, run_title_selection,
@@ -40,6 +40,11 @@ pub fn main() {
         } else {
             None
         };
+        let script_debug_port = if args.len() > 2 && args[1] == "--pal4" {
+            parse_script_debug_port(&args[2..])
+        } else {
+            None
+        };
 
         // Initialise the global logger *after* arg parsing so we can
         // tee into `AgentLogSink` when `--agent-port` is set. Doing it
@@ -60,8 +65,8 @@ pub fn main() {
                     }
                 }
                 "--pal4" => {
-                    if agent_opts.is_some() {
-                        run_openpal4_with_agent(agent_opts);
+                    if agent_opts.is_some() || script_debug_port.is_some() {
+                        run_openpal4_with_options(agent_opts, script_debug_port);
                     } else {
                         run_openpal4();
                     }
@@ -109,6 +114,7 @@ pub fn main() {
                     }
                 }
                 "--gujian" => run_opengujian(),
+                "--gujian2" => run_opengujian2(),
                 "--test" => {}
                 &_ => {}
             }
@@ -162,6 +168,18 @@ fn parse_agent_args(extra: &[String]) -> Option<Pal4AgentBootOptions> {
     Some(opts)
 }
 
+/// Parse `--script-debug-port <port>` (PAL4 only): the loopback port
+/// the AngelScript DAP debugger listens on.
+fn parse_script_debug_port(extra: &[String]) -> Option<u16> {
+    let mut iter = extra.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--script-debug-port" {
+            return iter.next().and_then(|s| s.parse().ok());
+        }
+    }
+    None
+}
+
 fn init_logger(agent_sink: Option<AgentLogSink>) {
     #[cfg(any(windows, linux, macos, android))]
     {
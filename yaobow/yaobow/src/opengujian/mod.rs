@@ -0,0 +1,30 @@
+//! Gujian / Gujian2 runtime. There is no story runtime yet: a launch
+//! mounts the install (`.zpk` / `.zpkg` packages through `packfs`) and
+//! opens the [`viewer`], which browses the NIF models in a free-view
+//! scene.
+
+pub mod viewer;
+
+pub use viewer::GujianModelViewerDirector;
+
+use std::rc::Rc;
+
+use crosscom::ComRc;
+use packfs::init_virtual_fs;
+use radiance::comdef::{IApplication, IApplicationExt, IDirector};
+
+use shared::GameType;
+use shared::opengujian::asset_loader::AssetLoader;
+
+/// Build the model viewer for a Gujian launch at `asset_path`.
+pub fn create_director(
+    app: &ComRc<IApplication>,
+    asset_path: &str,
+    game: GameType,
+) -> ComRc<IDirector> {
+    let component_factory = app.engine().borrow().rendering_component_factory();
+    let vfs = Rc::new(init_virtual_fs(asset_path, game.pkg_key()));
+    let asset_loader = AssetLoader::new(component_factory, vfs, game);
+
+    ComRc::from_object(GujianModelViewerDirector::new(app.clone(), asset_loader))
+}
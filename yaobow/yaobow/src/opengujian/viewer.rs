@@ -0,0 +1,139 @@
+//! Gujian model viewer — a Rust imgui director listing every `.nif` in
+//! the mounted install. Picking one replaces the scene with that model;
+//! the camera is driven by [`FreeViewController`] (WASD / QE).
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crosscom::ComRc;
+use radiance::comdef::{IApplication, IApplicationExt, IDirector, IDirectorImpl, ISceneExt};
+use radiance::math::Vec3;
+use radiance::scene::CoreScene;
+use radiance::utils::free_view::FreeViewController;
+
+use shared::opengujian::asset_loader::AssetLoader;
+
+const MAX_LIST_ROWS: usize = 500;
+
+pub struct GujianModelViewerDirector {
+    app: ComRc<IApplication>,
+    asset_loader: Rc<AssetLoader>,
+    control: FreeViewController,
+    models: Vec<String>,
+    filter: RefCell<String>,
+    selected: Cell<Option<usize>>,
+    /// Model picked this frame, loaded at the end of `update`.
+    chosen: Cell<Option<usize>>,
+    error: RefCell<Option<String>>,
+}
+
+ComObject_GujianModelViewerDirector!(super::GujianModelViewerDirector);
+
+impl GujianModelViewerDirector {
+    pub fn new(app: ComRc<IApplication>, asset_loader: Rc<AssetLoader>) -> Self {
+        let input = app.engine().borrow().input_engine();
+        let models = asset_loader.model_paths();
+        log::info!(
+            "{}: {} models found",
+            asset_loader.game().app_name(),
+            models.len()
+        );
+
+        Self {
+            app,
+            asset_loader,
+            control: FreeViewController::new(input),
+            models,
+            filter: RefCell::new(String::new()),
+            selected: Cell::new(None),
+            chosen: Cell::new(None),
+            error: RefCell::new(None),
+        }
+    }
+
+    fn render(&self) {
+        let ui_manager = self.app.engine().borrow().ui_manager();
+        let ui = ui_manager.ui();
+        let [_, h] = ui.io().display_size;
+
+        ui.window("gujian_models")
+            .position([10.0, 10.0], imgui::Condition::FirstUseEver)
+            .size([360.0, h - 20.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text(self.asset_loader.game().app_name());
+                ui.text_disabled("WASD / QE to move the camera.");
+                ui.input_text("Filter", &mut self.filter.borrow_mut())
+                    .build();
+                if let Some(error) = self.error.borrow().as_ref() {
+                    ui.text_colored([1.0, 0.4, 0.4, 1.0], error);
+                }
+                ui.separator();
+
+                let filter = self.filter.borrow().to_lowercase();
+                for (i, path) in self
+                    .models
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| p.to_lowercase().contains(&filter))
+                    .take(MAX_LIST_ROWS)
+                {
+                    if ui
+                        .selectable_config(path)
+                        .selected(self.selected.get() == Some(i))
+                        .build()
+                    {
+                        self.chosen.set(Some(i));
+                    }
+                }
+            });
+    }
+
+    fn load(&self, index: usize) {
+        let path = &self.models[index];
+        let scene = match self.asset_loader.load_model_scene(path) {
+            Ok(scene) => scene,
+            Err(e) => {
+                log::error!("Cannot load {}: {:#}", path, e);
+                *self.error.borrow_mut() = Some(format!("Cannot load {}: {}", path, e));
+                return;
+            }
+        };
+
+        self.selected.set(Some(index));
+        *self.error.borrow_mut() = None;
+
+        let scene_manager = self.app.engine().borrow().scene_manager().clone();
+        scene_manager.unload_all_scenes();
+        scene_manager.push_scene(scene.clone());
+        scene
+            .camera_mut()
+            .transform_mut()
+            .set_position(&Vec3::new(0., 100., 300.))
+            .look_at(&Vec3::new(0., 50., 0.));
+    }
+}
+
+impl IDirectorImpl for GujianModelViewerDirector {
+    fn activate(&self) {
+        let scene_manager = self.app.engine().borrow().scene_manager().clone();
+        scene_manager.unload_all_scenes();
+        scene_manager.push_scene(CoreScene::create());
+    }
+
+    fn update(&self, delta_sec: f32) -> Option<ComRc<IDirector>> {
+        self.render();
+
+        if let Some(index) = self.chosen.take() {
+            self.load(index);
+        }
+
+        let scene_manager = self.app.engine().borrow().scene_manager().clone();
+        if let Some(scene) = scene_manager.scene() {
+            self.control.update(scene, delta_sec);
+        }
+
+        None
+    }
+
+    fn deactivate(&self) {}
+}
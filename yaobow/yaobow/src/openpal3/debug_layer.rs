@@ -9,8 +9,11 @@ use radiance::{
     math::Vec3,
     radiance::UiManager,
 };
-use shared::openpal3::{
-    comdef::IAdventureDirector, directors::SceneManagerExtensions, scene::RoleController,
+use shared::{
+    openpal3::{
+        comdef::IAdventureDirector, directors::SceneManagerExtensions, scene::RoleController,
+    },
+    scripting::debugger::DebuggerWindow,
 };
 
 pub struct OpenPal3DebugLayer {
@@ -20,6 +23,7 @@ pub struct OpenPal3DebugLayer {
 
     visible: RefCell<bool>,
     fps_counter: RefCell<FpsCounter>,
+    script_debugger: RefCell<DebuggerWindow>,
 }
 
 ComObject_OpenPal3DebugLayer!(super::OpenPal3DebugLayer);
@@ -36,6 +40,7 @@ impl OpenPal3DebugLayer {
             ui,
             visible: RefCell::new(false),
             fps_counter: RefCell::new(FpsCounter::new()),
+            script_debugger: RefCell::new(DebuggerWindow::new()),
         }
     }
 
@@ -172,6 +177,20 @@ impl OpenPal3DebugLayer {
         });
     }
 
+    fn render_script_debugger(&self) {
+        let Some(director) = self.scene_manager.director() else {
+            return;
+        };
+        let Some(adv) = director.query_interface::<IAdventureDirector>() else {
+            return;
+        };
+        let adv_inner = adv.inner::<shared::openpal3::directors::AdventureDirector>();
+        let sce_vm = adv_inner.sce_vm();
+        self.script_debugger
+            .borrow_mut()
+            .render(self.ui.ui(), &*sce_vm);
+    }
+
     fn build_sce_tab(scene_manager: ComRc<ISceneManager>, ui: &Ui) {
         TabItem::new("Sce").build(ui, || {
             if let Some(d) = scene_manager.director().as_ref() {
@@ -215,6 +234,7 @@ impl IUiLayerImpl for OpenPal3DebugLayer {
             }
 
             self.render_window(delta_sec);
+            self.render_script_debugger();
         })();
 
         if let Some(font) = font {
@@ -6,35 +6,44 @@
 //! [`shared::agent_common::AgentBridge`]; the generic command handlers
 //! live in [`shared::agent_common::handlers`]. This module:
 //!
-//! * Builds [`StateSnapshot`]s from PAL5 state ([`Pal5ScriptContext`]).
-//! * Dispatches [`AgentCommand`]s to the generic bridge surface and
-//!   returns `NotImplemented` for the per-game gameplay endpoints PAL5
-//!   has no clean mapping for yet.
+//! * Builds [`StateSnapshot`]s from PAL5 state ([`Pal5ScriptContext`]),
+//!   including the party, items, equipment and formulas.
+//! * Dispatches [`AgentCommand`]s to the generic bridge surface, writes
+//!   save slots, and returns `NotImplemented` for the per-game gameplay
+//!   endpoints PAL5 has no clean mapping for yet.
 //!
 //! The dispatcher is invoked from `Pal5Service::pump_agent` (the single
 //! per-frame drainer); it never crosses the HTTP↔game thread boundary
-//! directly (everything goes through the shared command queue).
+//! directly (everything goes through the shared command queue). Mode
+//! control (`/v1/load`, `/v1/menu/*`) switches directors, so the
+//! service handles it before this dispatcher is reached.
 
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
 use agent_server::protocol::{
-    AgentCommand, AgentError, AgentResponse, DialogSnapshot, StateSnapshot,
+    AgentCommand, AgentError, AgentResponse, DialogSnapshot, InventoryEntry, PartyMember,
+    SlotParams, StateSnapshot,
 };
 use radiance::input::Key;
 use shared::agent_common::AgentBridge;
 use shared::agent_common::handlers;
+use shared::scripting::debugger::ScriptDebugTarget;
+use shared::scripting::lua50_32::Lua5032Vm;
 
 use super::context::Pal5ScriptContext;
 
 /// Stitched together once per command by `Pal5Service::pump_agent`.
 ///
-/// The `context` is `None` only in the brief window before the first
-/// `Pal5StoryDirector` is installed; once present it stays for the rest
-/// of the launch (PAL5 has no start-menu / title mode).
+/// The `context` is `None` whenever no `Pal5StoryDirector` is installed
+/// — at the title menu, or before the first director.
 pub struct Pal5DispatchCtx<'a> {
     pub bridge: &'a Rc<AgentBridge>,
     pub context: Option<Rc<RefCell<Pal5ScriptContext>>>,
+    /// The story director's Lua VM, for the `/v1/debug/*` and
+    /// `/v1/script/trace/*` routes. `None` whenever `context` is.
+    pub vm: Option<&'a Lua5032Vm<Pal5ScriptContext>>,
 }
 
 /// Dispatch a single [`AgentCommand`] against the supplied PAL5
@@ -91,18 +100,15 @@ pub fn dispatch_pal5_command(ctx: &Pal5DispatchCtx, command: AgentCommand) -> Ag
             AgentResponse::Ok
         }
 
+        C::SaveSlot(p) => handle_save_slot(ctx, p),
+        C::EnterNewGame | C::EnterLoadGame(_) | C::LoadSlot(_) | C::ExitGame => AgentResponse::err(
+            AgentError::internal("PAL5 mode control must be handled by Pal5Service::pump_agent"),
+        ),
+
         // --- not yet implemented for PAL5 ---------------------------------
         C::TeleportPlayer(_) => AgentResponse::err(AgentError::not_implemented(
             "PAL5 has no controlled-role teleport surface yet",
         )),
-        C::SaveSlot(_) | C::EnterNewGame | C::EnterLoadGame(_) | C::LoadSlot(_) => {
-            AgentResponse::err(AgentError::not_implemented(
-                "PAL5 save/load + mode control are not implemented (single bootstrap script)",
-            ))
-        }
-        C::ExitGame => AgentResponse::err(AgentError::not_implemented(
-            "PAL5 exit-to-menu is not implemented (no menu mode)",
-        )),
         C::ChooseDialog(_) => AgentResponse::err(AgentError::not_implemented(
             "PAL5 dialog choice buffering not yet implemented",
         )),
@@ -121,9 +127,19 @@ pub fn dispatch_pal5_command(ctx: &Pal5DispatchCtx, command: AgentCommand) -> Ag
         C::ScriptEval(_) => AgentResponse::err(AgentError::not_implemented(
             "PAL5 script_eval is not supported",
         )),
-        C::TraceStart(_) | C::TraceStop | C::TraceDrain(_) => AgentResponse::err(
-            AgentError::not_implemented("PAL5 Lua VM has no trace adapter yet"),
-        ),
+        C::TraceStart(p) => match ctx.vm {
+            Some(vm) => {
+                handlers::handle_trace_start(ctx.bridge, p, |sink| vm.set_trace_sink(Some(sink)))
+            }
+            None => AgentResponse::err(AgentError::conflict(
+                "the PAL5 story script is not running yet",
+            )),
+        },
+        C::TraceStop => handlers::handle_trace_stop(ctx.bridge),
+        C::TraceDrain(p) => handlers::handle_trace_drain(ctx.bridge, p),
+        c if c.is_debug() => {
+            handlers::handle_debug_command(ctx.vm.map(|vm| vm as &dyn ScriptDebugTarget), c)
+        }
 
         // AgentCommand is `#[non_exhaustive]` — surface any future
         // variants as `not_implemented` rather than panicking.
@@ -133,6 +149,40 @@ pub fn dispatch_pal5_command(ctx: &Pal5DispatchCtx, command: AgentCommand) -> Ag
     }
 }
 
+/// `POST /v1/save` — write the story's current state to `slot`.
+fn handle_save_slot(ctx: &Pal5DispatchCtx, params: SlotParams) -> AgentResponse {
+    if params.slot < 0 {
+        return AgentResponse::err(bad_slot_err(params.slot));
+    }
+    let Some(context) = ctx.context.as_ref() else {
+        return AgentResponse::err(AgentError::conflict(
+            "no PAL5 story is running (title menu); start or load a game first",
+        ));
+    };
+    if context.borrow().script_running() {
+        return AgentResponse::err(AgentError::conflict(
+            "a story script is running; save once the story is back in free roam",
+        ));
+    }
+    let Some(state) = context.borrow().persistent_state() else {
+        return AgentResponse::err(AgentError::conflict(
+            "the story has not begun a scene yet; there is nothing to save",
+        ));
+    };
+
+    match state.save(params.slot) {
+        Ok(()) => AgentResponse::Ok,
+        Err(e) => AgentResponse::err(AgentError::internal(format!(
+            "cannot save slot {}: {e}",
+            params.slot
+        ))),
+    }
+}
+
+pub fn bad_slot_err(slot: i32) -> AgentError {
+    AgentError::bad_request(format!("save slot must be non-negative, got {slot}"))
+}
+
 /// Build a [`StateSnapshot`] from whatever PAL5 state is reachable.
 pub fn build_snapshot(ctx: &Pal5DispatchCtx) -> StateSnapshot {
     let mut snap = StateSnapshot {
@@ -156,6 +206,27 @@ pub fn build_snapshot(ctx: &Pal5DispatchCtx) -> StateSnapshot {
         let (eye, look) = context.camera_pose();
         snap.camera_eye = [eye.x, eye.y, eye.z];
         snap.camera_target = [look.x, look.y, look.z];
+
+        // Party: `slot` is the role id; the leader is role 1.
+        let party = context.party();
+        snap.leader = 1;
+        snap.party = party
+            .members()
+            .iter()
+            .map(|(role_id, m)| PartyMember {
+                slot: *role_id as usize,
+                level: m.level,
+                hp: m.hp,
+                max_hp: m.max_hp,
+                mp: m.mp,
+                max_mp: m.max_mp,
+                in_team: m.in_team,
+                magic: m.magic.iter().copied().collect(),
+            })
+            .collect();
+        snap.inventory = inventory_entries(party.items());
+        snap.equipment = inventory_entries(party.equipment());
+        snap.formulas = party.formulas().iter().copied().collect();
     }
 
     // PAL5 dialog text isn't structured for the agent yet; leave the
@@ -163,3 +234,13 @@ pub fn build_snapshot(ctx: &Pal5DispatchCtx) -> StateSnapshot {
     snap.dialog = DialogSnapshot::default();
     snap
 }
+
+fn inventory_entries(counts: &BTreeMap<i32, i32>) -> Vec<InventoryEntry> {
+    counts
+        .iter()
+        .map(|(id, count)| InventoryEntry {
+            id: *id,
+            count: *count,
+        })
+        .collect()
+}
@@ -1,10 +1,10 @@
 //! PAL5 Lua command bridge: `extern "C"` trampolines, namespaced
-//! registration, the dispatch harness, and the `__pal5_load`/
-//! `__pal5_done` engine hooks.
+//! registration, the dispatch harness, and the `__pal5_*` engine hooks
+//! it calls.
 //!
 //! PAL5's script API is table-namespaced (`global.Wait`, `npc.Create`,
-//! …) and coroutine-driven (`global.Wait` / `WaitForCameraLerp` yield
-//! the script thread). Because Lua 5.0 cannot `yield` across a C-call
+//! …) and coroutine-driven (`global.Wait` / `WaitForCameraLerp` /
+//! `WaitForNpc*` yield the script thread). Because Lua 5.0 cannot `yield` across a C-call
 //! boundary, `Include`/`CallScript` are implemented in the Lua harness
 //! (so the dispatched script's inner `Wait` stays a pure Lua→Lua call),
 //! and only the leaf commands are C functions.
@@ -13,15 +13,25 @@ use std::cell::RefCell;
 
 use lua50_32_sys::lua_State;
 
-use shared::scripting::lua50_32::Lua5032Vm;
+use shared::scripting::lua50_32::{Lua5032Vm, trace_command, trace_flag};
 
-use super::context::Pal5ScriptContext;
+use super::context::{NpcWait, Pal5ScriptContext};
 
-/// Lua dispatch harness. Loaded first, before `NewGame`. `global` (and
+/// Lua dispatch harness. Loaded first, before the entry script. `global` (and
 /// the other namespace tables) already exist here because the C command
 /// registration created them. `__pal5_load` returns the loaded script's
 /// entry function (or nil), so `CallScript` invokes it as a Lua→Lua call
-/// — keeping the inner `Wait` yield legal.
+/// — keeping the inner `Wait` yield legal. `CallScript` brackets the
+/// call with `__pal5_enter`/`__pal5_leave` so the context knows a
+/// script is running and refuses to save.
+///
+/// `__pal5_main` starts a new game by calling the entry script's
+/// function (`__pal5_entry_name` / `__pal5_entry_id`, set by the
+/// director to `NewGame` and its id), bracketed the same way. It then
+/// enters `__pal5_events`, the free-roam loop that runs each queued
+/// event script and otherwise parks in `__pal5_wait_event`. Saves are
+/// only taken there, so `__pal5_events` is also the entry after a
+/// load (see [`story_entry`]).
 const HARNESS: &str = r#"
 function global.Include(id)
   __pal5_load(id)
@@ -29,12 +39,30 @@ end
 
 function global.CallScript(id)
   local f = __pal5_load(id)
-  if f then return f() end
+  if f then
+    __pal5_enter(id)
+    local r = f()
+    __pal5_leave()
+    return r
+  end
 end
 
 function __pal5_main()
-  NewGame()
-  __pal5_done()
+  __pal5_enter(__pal5_entry_id)
+  _G[__pal5_entry_name]()
+  __pal5_leave()
+  __pal5_events()
+end
+
+function __pal5_events()
+  while true do
+    local id = __pal5_next_event()
+    if id then
+      global.CallScript(id)
+    else
+      __pal5_wait_event()
+    end
+  end
 end
 "#;
 
@@ -48,10 +76,12 @@ macro_rules! borrow_ctx {
 /// Register a namespaced command that maps to a `Pal5ScriptContext`
 /// method. Two arms: void (`cmd!(vm, "ns", "Name", rust_fn, a: number)`)
 /// and numeric-return (`cmd!(vm, "ns", "Name", rust_fn, ... -> num)`).
+/// Every call is reported to the VM's execution trace as `ns.Name`.
 macro_rules! cmd {
     ($vm:ident, $ns:expr, $lua:expr, $rust:ident $(, $p:ident : $t:ident)*) => {
         paste::paste! {
             extern "C" fn [<__pal5_ $rust>](state: *mut lua_State) -> i32 {
+                trace_command(state, concat!($ns, ".", $lua));
                 unsafe {
                     let context = borrow_ctx!(state);
                     $(
@@ -68,6 +98,7 @@ macro_rules! cmd {
     ($vm:ident, $ns:expr, $lua:expr, $rust:ident $(, $p:ident : $t:ident)* => num) => {
         paste::paste! {
             extern "C" fn [<__pal5_ $rust>](state: *mut lua_State) -> i32 {
+                trace_command(state, concat!($ns, ".", $lua));
                 unsafe {
                     let context = borrow_ctx!(state);
                     $(
@@ -99,7 +130,8 @@ extern "C" fn pal5_wait(state: *mut lua_State) -> i32 {
     }
 }
 
-/// `global.WaitForCameraLerp()` — yields the remaining camera-lerp time.
+/// `global.WaitForCameraLerp()` — yields the time left on the camera
+/// lerp or camera path in flight.
 extern "C" fn pal5_wait_camera_lerp(state: *mut lua_State) -> i32 {
     unsafe {
         let context = borrow_ctx!(state);
@@ -109,12 +141,124 @@ extern "C" fn pal5_wait_camera_lerp(state: *mut lua_State) -> i32 {
     }
 }
 
-/// `__pal5_done()` — flags the story as finished (the coroutine returns
-/// right after, so it is never resumed again).
-extern "C" fn pal5_done(state: *mut lua_State) -> i32 {
+/// Defines a `global.WaitForNpc*(handle)` command: installs the wait
+/// condition on the context and yields with no sleep, so the driver
+/// holds the script until the condition clears.
+macro_rules! npc_wait {
+    ($name:ident, $wait:ident) => {
+        extern "C" fn $name(state: *mut lua_State) -> i32 {
+            unsafe {
+                let context = borrow_ctx!(state);
+                let handle = lua50_32_sys::lua_tonumber(state, 1) as i32;
+                lua50_32_sys::lua_settop(state, 0);
+                context.borrow_mut().wait_for_npc(NpcWait::$wait(handle));
+                lua50_32_sys::lua_pushnumber(state, 0.0);
+                lua50_32_sys::lua_yield(state, 1)
+            }
+        }
+    };
+}
+
+npc_wait!(pal5_wait_for_npc_pos, Pos);
+npc_wait!(pal5_wait_for_npc_anim, Anim);
+npc_wait!(pal5_wait_for_npc_turn, Turn);
+
+/// `flag.SetValue(flag, value)`. Written out rather than via `cmd!` so
+/// the write shows up in the trace as a plot-global write.
+extern "C" fn pal5_flag_set_value(state: *mut lua_State) -> i32 {
+    trace_command(state, "flag.SetValue");
+    unsafe {
+        let context = borrow_ctx!(state);
+        let flag = lua50_32_sys::lua_tonumber(state, 1);
+        let value = lua50_32_sys::lua_tonumber(state, 2);
+        lua50_32_sys::lua_settop(state, 0);
+        context.borrow_mut().flag_set_value(flag, value);
+        trace_flag(state, true, flag as u32, value as i32 as u32);
+    }
+    0
+}
+
+/// `flag.GetValue(flag)`, traced as a plot-global read.
+extern "C" fn pal5_flag_get_value(state: *mut lua_State) -> i32 {
+    trace_command(state, "flag.GetValue");
+    unsafe {
+        let context = borrow_ctx!(state);
+        let flag = lua50_32_sys::lua_tonumber(state, 1);
+        lua50_32_sys::lua_settop(state, 0);
+        let value = context.borrow_mut().flag_get_value(flag);
+        trace_flag(state, false, flag as u32, value as i32 as u32);
+        lua50_32_sys::lua_pushnumber(state, value);
+    }
+    1
+}
+
+/// `player.Control([on])` — with no argument or a non-zero one the
+/// player gets control back; `Control(0)` takes it away.
+extern "C" fn pal5_player_control(state: *mut lua_State) -> i32 {
+    trace_command(state, "player.Control");
+    unsafe {
+        let context = borrow_ctx!(state);
+        let on = lua50_32_sys::lua_gettop(state) < 1 || lua50_32_sys::lua_tonumber(state, 1) != 0.0;
+        lua50_32_sys::lua_settop(state, 0);
+        context.borrow_mut().player_control(on);
+    }
+    0
+}
+
+/// `__pal5_wait_event()` — the harness' event loop has nothing to run:
+/// yields, and the driver holds the script until an event is queued.
+extern "C" fn pal5_wait_event(state: *mut lua_State) -> i32 {
+    unsafe {
+        let context = borrow_ctx!(state);
+        context.borrow_mut().await_event();
+        lua50_32_sys::lua_pushnumber(state, 0.0);
+        lua50_32_sys::lua_yield(state, 1)
+    }
+}
+
+/// `__pal5_next_event()` — the next queued event script id, or nil.
+extern "C" fn pal5_next_event(state: *mut lua_State) -> i32 {
+    unsafe {
+        let context = borrow_ctx!(state);
+        match context.borrow_mut().next_event() {
+            Some(id) => lua50_32_sys::lua_pushnumber(state, id as f64),
+            None => lua50_32_sys::lua_pushnil(state),
+        }
+    }
+    1
+}
+
+/// `map.Change(map, sub)` — starts the fade-out and yields; the driver
+/// holds the script until the new map is loaded.
+extern "C" fn pal5_map_change(state: *mut lua_State) -> i32 {
+    trace_command(state, "map.Change");
+    unsafe {
+        let context = borrow_ctx!(state);
+        let map_id = lua50_32_sys::lua_tonumber(state, 1);
+        let sub_id = lua50_32_sys::lua_tonumber(state, 2);
+        lua50_32_sys::lua_settop(state, 0);
+        context.borrow_mut().begin_map_change(map_id, sub_id);
+        lua50_32_sys::lua_pushnumber(state, 0.0);
+        lua50_32_sys::lua_yield(state, 1)
+    }
+}
+
+/// `__pal5_enter(id)` — a `CallScript` is about to run script `id`.
+extern "C" fn pal5_enter(state: *mut lua_State) -> i32 {
+    unsafe {
+        let context = borrow_ctx!(state);
+        let id = lua50_32_sys::lua_tonumber(state, 1) as u32;
+        lua50_32_sys::lua_settop(state, 0);
+        context.borrow_mut().enter_script(id);
+    }
+    0
+}
+
+/// `__pal5_leave()` — the innermost `CallScript` returned.
+extern "C" fn pal5_leave(state: *mut lua_State) -> i32 {
     unsafe {
         let context = borrow_ctx!(state);
-        context.borrow_mut().mark_finished();
+        context.borrow_mut().leave_script();
     }
     0
 }
@@ -174,9 +318,19 @@ extern "C" fn pal5_load(state: *mut lua_State) -> i32 {
     }
 }
 
+/// Harness function the story coroutine starts in: `__pal5_main` for a
+/// new game, the free-roam event loop for a loaded one.
+pub fn story_entry(loaded: bool) -> &'static str {
+    if loaded {
+        "__pal5_events"
+    } else {
+        "__pal5_main"
+    }
+}
+
 /// Build the PAL5 VM: register every first-segment command (essentials
 /// real, the rest logged stubs), then load the dispatch harness. The
-/// caller loads `NewGame` and sets `__pal5_main` as the entry.
+/// caller loads the entry script and sets [`story_entry`] as the entry.
 pub fn create_lua_vm(
     context: std::rc::Rc<RefCell<Pal5ScriptContext>>,
 ) -> anyhow::Result<Lua5032Vm<Pal5ScriptContext>> {
@@ -184,11 +338,18 @@ pub fn create_lua_vm(
 
     // Engine hooks used by the harness.
     vm.register("__pal5_load", Some(pal5_load));
-    vm.register("__pal5_done", Some(pal5_done));
+    vm.register("__pal5_enter", Some(pal5_enter));
+    vm.register("__pal5_leave", Some(pal5_leave));
+    vm.register("__pal5_next_event", Some(pal5_next_event));
+    vm.register("__pal5_wait_event", Some(pal5_wait_event));
 
     // Coroutine yields.
     vm.register_namespaced("global", "Wait", Some(pal5_wait));
     vm.register_namespaced("global", "WaitForCameraLerp", Some(pal5_wait_camera_lerp));
+    vm.register_namespaced("global", "WaitForNpcPos", Some(pal5_wait_for_npc_pos));
+    vm.register_namespaced("global", "WaitForNpcPos3D", Some(pal5_wait_for_npc_pos));
+    vm.register_namespaced("global", "WaitForNpcAnim", Some(pal5_wait_for_npc_anim));
+    vm.register_namespaced("global", "WaitForNpcTurn", Some(pal5_wait_for_npc_turn));
 
     // ---- global ----
     cmd!(vm, "global", "Print", global_print, t: string);
@@ -204,8 +365,8 @@ pub fn create_lua_vm(
     cmd!(vm, "global", "PlayCg", global_play_cg, a: number);
 
     // ---- flag ----
-    cmd!(vm, "flag", "SetValue", flag_set_value, a: number, b: number);
-    cmd!(vm, "flag", "GetValue", flag_get_value, a: number => num);
+    vm.register_namespaced("flag", "SetValue", Some(pal5_flag_set_value));
+    vm.register_namespaced("flag", "GetValue", Some(pal5_flag_get_value));
 
     // ---- player ----
     cmd!(vm, "player", "Create", player_create, a: number, b: number, c: number);
@@ -214,6 +375,15 @@ pub fn create_lua_vm(
     cmd!(vm, "player", "Remove", player_remove, a: number);
     cmd!(vm, "player", "IsPlayerInTeam", player_is_in_team, a: number => num);
     cmd!(vm, "player", "GetItemCount", player_get_item_count, a: number => num);
+    cmd!(vm, "player", "SetAt", player_set_at, a: number, b: number, c: number);
+    cmd!(vm, "player", "Stop", player_stop);
+    vm.register_namespaced("player", "Control", Some(pal5_player_control));
+    cmd!(vm, "player", "AddItem", player_add_item, a: number, b: number);
+    cmd!(vm, "player", "AddEquip", player_add_equip, a: number, b: number);
+    cmd!(vm, "player", "AddMagic", player_add_magic, a: number, b: number);
+    cmd!(vm, "player", "AddFormula", player_add_formula, a: number);
+    cmd!(vm, "player", "ChangeHP", player_change_hp, a: number, b: number);
+    cmd!(vm, "player", "ChangeMP", player_change_mp, a: number, b: number);
 
     // ---- npc ----
     cmd!(vm, "npc", "Create", npc_create, a: number, b: number, c: number, d: number);
@@ -221,9 +391,22 @@ pub fn create_lua_vm(
     cmd!(vm, "npc", "SetPos3D", npc_set_pos_3d, a: number, b: number, c: number, d: number);
     cmd!(vm, "npc", "MoveTo", npc_move_to, a: number, b: number, c: number);
     cmd!(vm, "npc", "RunTo", npc_run_to, a: number, b: number, c: number);
+    cmd!(vm, "npc", "FloatTo", npc_float_to, a: number, b: number, c: number, d: number, e: number);
+    cmd!(vm, "npc", "SetSpeed", npc_set_speed, a: number, b: number);
+    cmd!(vm, "npc", "TurnTo", npc_turn_to, a: number, b: number);
+    cmd!(vm, "npc", "TurnToNpc", npc_turn_to_npc, a: number, b: number);
+    cmd!(vm, "npc", "TurnToPos", npc_turn_to_pos, a: number, b: number, c: number);
+    cmd!(vm, "npc", "AddPatrolPoint", npc_add_patrol_point, a: number, b: number, c: number);
+    cmd!(vm, "npc", "SetPatrolType", npc_set_patrol_type, a: number, b: number);
+    cmd!(vm, "npc", "SetAnim", npc_set_anim, a: number, b: number, c: number);
+    cmd!(vm, "npc", "AddAnimChain", npc_add_anim_chain, a: number, b: number, c: number);
     cmd!(vm, "npc", "SetVisible", npc_set_visible, a: number, b: number);
     cmd!(vm, "npc", "Destroy", npc_destroy, a: number);
     cmd!(vm, "npc", "IsCreated", npc_is_created, a: number => num);
+    cmd!(vm, "npc", "CreateObject", npc_create_object, a: number, b: number, c: number, d: number);
+    cmd!(vm, "npc", "CreateChest", npc_create_chest,
+        a: number, b: number, c: number, d: number, e: number, f: number);
+    cmd!(vm, "npc", "CreateSE", npc_create_se, a: number, b: number, c: number, d: number);
 
     // ---- camera ----
     cmd!(vm, "camera", "ChangeCameraStatic", camera_change_static,
@@ -231,6 +414,12 @@ pub fn create_lua_vm(
     cmd!(vm, "camera", "ChangeCameraStaticEye", camera_change_static_eye,
         a: number, b: number, c: number, d: number, e: number, f: number);
     cmd!(vm, "camera", "ResetLerp", camera_reset_lerp, a: number);
+    cmd!(vm, "camera", "ChangeCameraPath", camera_change_path, a: number, b: number);
+    cmd!(vm, "camera", "ChangeCameraStaticToNpc", camera_change_static_to_npc,
+        a: number, b: number, c: number, d: number);
+    cmd!(vm, "camera", "Save", camera_save);
+    cmd!(vm, "camera", "Resume", camera_resume);
+    cmd!(vm, "camera", "Shake", camera_shake, a: number, b: number);
 
     // ---- effect ----
     cmd!(vm, "effect", "FadeIn", effect_fade_in, a: number, b: number);
@@ -244,6 +433,9 @@ pub fn create_lua_vm(
     // ---- map ----
     cmd!(vm, "map", "ChangeNoScript", map_change_no_script, a: number, b: number);
     cmd!(vm, "map", "GetCurrentMapID", map_get_current_map_id => num);
+    cmd!(vm, "map", "AddEvent", map_add_event, a: number, b: number, c: number, d: number, e: number);
+    cmd!(vm, "map", "CreateNameSE", map_create_name_se, a: number);
+    vm.register_namespaced("map", "Change", Some(pal5_map_change));
 
     // ---- stubs (logged no-ops for the bootstrap) ----
     for (ns, name) in STUBS {
@@ -255,55 +447,92 @@ pub fn create_lua_vm(
 }
 
 /// Commands registered as no-ops for the first-segment bootstrap. These
-/// either have no visible effect for the intro (item/magic grants,
-/// patrol AI) or are deferred (movies, anim chains, camera paths). They
-/// MUST still be registered so the scripts don't hit `call nil`.
+/// either have no visible effect for the intro or are deferred
+/// (movies). They MUST still be registered
+/// so the scripts don't hit `call nil`.
 const STUBS: &[(&str, &str)] = &[
-    // global waits whose conditions are already satisfied (NPC moves
-    // and anims resolve instantly in the bootstrap) or are deferred.
-    ("global", "WaitForNpcPos"),
-    ("global", "WaitForNpcPos3D"),
-    ("global", "WaitForNpcAnim"),
-    ("global", "WaitForNpcTurn"),
+    // deferred global waits.
     ("global", "WaitForCgEnd"),
-    // player grants / control.
-    ("player", "AddItem"),
-    ("player", "AddMagic"),
-    ("player", "AddEquip"),
-    ("player", "AddFormula"),
-    ("player", "Control"),
-    ("player", "Stop"),
-    ("player", "SetAt"),
-    ("player", "ChangeMP"),
-    ("player", "ChangeHP"),
     // npc behaviour not visible in a single static frame.
     ("npc", "SetAt"),
     ("npc", "SetAtPos"),
-    ("npc", "SetAnim"),
-    ("npc", "AddAnimChain"),
-    ("npc", "TurnTo"),
-    ("npc", "TurnToNpc"),
-    ("npc", "TurnToPos"),
-    ("npc", "AddPatrolPoint"),
-    ("npc", "SetPatrolType"),
-    ("npc", "SetSpeed"),
-    ("npc", "FloatTo"),
-    ("npc", "CreateSE"),
-    ("npc", "CreateObject"),
-    ("npc", "CreateChest"),
-    // camera extras.
-    ("camera", "ChangeCameraPath"),
-    ("camera", "ChangeCameraStaticToNpc"),
-    ("camera", "Save"),
-    ("camera", "Resume"),
-    ("camera", "Shake"),
-    // map / effect / ui extras.
-    ("map", "AddEvent"),
-    ("map", "CreateNameSE"),
-    ("map", "Change"),
+    // effect / ui extras.
     ("effect", "SetFilterTexture"),
     ("ui", "SetDialogFontSize"),
     ("ui", "MirrorPic"),
     ("ui", "Dialog_t"),
     ("ui", "AddQuest"),
 ];
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use shared::scripting::lua50_32::LuaValue;
+
+    use super::*;
+
+    /// Lua stand-ins for the engine hooks the harness calls. `depth`
+    /// mirrors the context's script stack; `grants` counts the side
+    /// effect of the entry script, which a replay would repeat.
+    const HOOKS: &str = r#"
+global = {}
+map = {}
+grants = 0
+depth = 0
+events = {}
+function __pal5_load(id) return nil end
+function __pal5_enter(id) depth = depth + 1 end
+function __pal5_leave() depth = depth - 1 end
+function __pal5_next_event() return table.remove(events, 1) end
+function __pal5_wait_event() coroutine.yield(0) end
+function global.Wait(sec) coroutine.yield(sec) end
+function NewGame()
+  grants = grants + 1
+  global.Wait(1)
+end
+__pal5_entry_name = "NewGame"
+__pal5_entry_id = 5
+"#;
+
+    /// A harness VM whose restored state has `grants` already applied.
+    fn harness_vm(grants: f64) -> Lua5032Vm<()> {
+        let vm = Lua5032Vm::create(Rc::new(RefCell::new(())));
+        vm.load_chunk(HOOKS.as_bytes(), "hooks").unwrap();
+        vm.load_chunk(HARNESS.as_bytes(), "pal5_harness").unwrap();
+        vm.set_global("grants", &LuaValue::Number(grants));
+        vm
+    }
+
+    fn global(vm: &Lua5032Vm<()>, name: &str) -> Option<LuaValue> {
+        vm.enumerate_globals()
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v)
+    }
+
+    #[test]
+    fn loading_a_save_does_not_replay_grants() {
+        let vm = harness_vm(0.);
+        vm.set_entry(story_entry(false)).unwrap();
+
+        // Parked in the entry script's `Wait`: the script counts as
+        // running, so the context refuses to save here.
+        assert_eq!(vm.execute().unwrap(), 1.);
+        assert_eq!(global(&vm, "depth"), Some(LuaValue::Number(1.)));
+        assert_eq!(global(&vm, "grants"), Some(LuaValue::Number(1.)));
+
+        // The entry script returned and the story waits for an event,
+        // the only point a save is taken.
+        assert_eq!(vm.execute().unwrap(), 0.);
+        assert_eq!(global(&vm, "depth"), Some(LuaValue::Number(0.)));
+
+        // Loading that save enters the event loop with the grant
+        // restored, without running the entry script again.
+        let loaded = harness_vm(1.);
+        loaded.set_entry(story_entry(true)).unwrap();
+        assert_eq!(loaded.execute().unwrap(), 0.);
+        assert_eq!(global(&loaded, "grants"), Some(LuaValue::Number(1.)));
+        assert_eq!(global(&loaded, "depth"), Some(LuaValue::Number(0.)));
+    }
+}
@@ -7,12 +7,37 @@
 //!
 //! This is the "functional bootstrap" surface: the essentials (scene
 //! load, NPC/player create+place, static/lerp camera, fades,
-//! dialog/print, best-effort audio) are implemented; the rest (battle,
-//! item/magic grants, patrol AI, anim chains) are logged no-ops so the
-//! `NewGame -> m001_1` intro runs end-to-end without erroring.
+//! dialog/print, best-effort audio, party grants) are implemented; the
+//! rest (battle, movies) are logged no-ops so the `NewGame -> m001_1`
+//! intro runs end-to-end without erroring.
+//!
+//! NPCs and players are full [`Pal5Actor`]s: scripted moves, turns,
+//! patrols and animation chains play out over time, and the
+//! `global.WaitForNpc*` commands hold the script (via [`NpcWait`]) until
+//! they finish. Directional input walks the leader unless the script
+//! holds it with `player.Stop` / `player.Control(0)`.
+//!
+//! Cutscene cameras: static shots (optionally lerped), NPC-relative
+//! shots, spline paths from the camera path table (held by
+//! `global.WaitForCameraLerp` until they finish), a `camera.Save` /
+//! `Resume` stack and a decaying shake on top of whichever is active.
+//!
+//! The `player.*` grants update a [`Pal5Party`] (stats, magic, items,
+//! equipment, formulas). The context also owns everything a save slot
+//! records ([`Pal5PersistentState`]): flags, the party, the players, the
+//! current map with its event volumes. It also tracks the running
+//! `CallScript`s, since a save is only allowed once they have all
+//! returned.
+//!
+//! Free roam: once the story script returns, the harness' event loop
+//! parks the coroutine until the leader walks into a `map.AddEvent`
+//! volume, then runs that event's script. `map.Change` fades out, loads
+//! the new map and fades back in; which script the new map runs on entry
+//! isn't known yet, so none does. `npc.CreateChest` and `npc.CreateSE`
+//! are logged as unimplemented.
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::os::raw::c_char;
 use std::rc::Rc;
 
@@ -27,14 +52,31 @@ use radiance::rendering::ComponentFactory;
 use radiance::utils::act_drop::ActDrop;
 use radiance::utils::interp_value::InterpValue;
 
+use shared::GameType;
+use shared::openpal5::actor::Pal5Actor;
 use shared::openpal5::asset_loader::AssetLoader;
+use shared::openpal5::camera::{CameraShake, CameraSpline};
+use shared::openpal5::npc_motion::PatrolType;
+use shared::openpal5::party::Pal5Party;
+use shared::openpal5::persistent_state::{Pal5MapEvent, Pal5PersistentState, Pal5SavedCamera};
 use shared::openpal5::scene::Pal5Scene;
 use shared::openpal5::script::ScriptIndex;
+use shared::utils::get_moving_direction;
+
+/// Role id of the leader, the player that input walks and the agent
+/// reports as `leader_pos`.
+const LEADER_ROLE: i32 = 1;
+
+/// Height above an NPC's feet that `camera.ChangeCameraStaticToNpc`
+/// looks at, roughly chest height at PAL5's true scale.
+const NPC_LOOK_HEIGHT: f32 = 120.0;
+
+/// Length of each half of the `map.Change` transition: the fade to
+/// black before the new map loads, and the fade back in after.
+const MAP_FADE_SEC: f32 = 0.5;
 
-/// The single scene name the bootstrap loads for the first segment.
-/// Map-id → scene-name resolution (`map.xml` / `MapInfo.ini`) is a
-/// follow-up; the狂风寨 intro only needs this one scene.
-const BOOTSTRAP_SCENE: &str = "kuangfengzhai";
+/// How long the `map.CreateNameSE` map-name banner stays up.
+const NAME_BANNER_SEC: f32 = 3.0;
 
 struct CameraLerp {
     from_eye: Vec3,
@@ -50,7 +92,26 @@ struct Dialog {
     text: String,
 }
 
+/// A `map.Change` fading out toward its destination map.
+struct MapChange {
+    map_id: i32,
+    sub_map_id: i32,
+    remaining: f32,
+}
+
+/// A `global.WaitForNpc*` condition holding the script thread.
+#[derive(Clone, Copy, Debug)]
+pub enum NpcWait {
+    /// `WaitForNpcPos` / `WaitForNpcPos3D`: the scripted move is done.
+    Pos(i32),
+    /// `WaitForNpcAnim`: the scripted animation chain has played out.
+    Anim(i32),
+    /// `WaitForNpcTurn`: the NPC faces its turn target.
+    Turn(i32),
+}
+
 pub struct Pal5ScriptContext {
+    game: GameType,
     asset_loader: Rc<AssetLoader>,
     script_index: Rc<ScriptIndex>,
     scene_manager: ComRc<ISceneManager>,
@@ -60,16 +121,43 @@ pub struct Pal5ScriptContext {
     ui: Rc<UiManager>,
 
     scene: Option<Pal5Scene>,
-    scene_loaded: bool,
+    /// Name of the scene last loaded (or attempted); `None` until the
+    /// story begins its first scene.
+    scene_name: Option<String>,
+    map_id: i32,
+    sub_map_id: i32,
 
     flags: HashMap<i32, i32>,
-    npcs: HashMap<i32, ComRc<IEntity>>,
-    players: HashMap<i32, ComRc<IEntity>>,
+    party: Pal5Party,
+    npcs: HashMap<i32, Pal5Actor>,
+    players: HashMap<i32, Pal5Actor>,
+    /// Set by `player.Stop` / `player.Control(0)`; while set, input
+    /// doesn't move the leader.
+    player_locked: bool,
+    /// Ids of the `CallScript`s currently running, outermost first.
+    script_stack: Vec<u32>,
+
+    /// The harness' event loop is parked waiting for an event script.
+    awaiting_event: bool,
+    /// Event scripts queued for the event loop.
+    pending_events: VecDeque<u32>,
+    /// `map.AddEvent` volumes on the current map.
+    map_events: Vec<Pal5MapEvent>,
+    /// `map.Change` in progress; holds the script until the new map is
+    /// in.
+    map_change: Option<MapChange>,
+    /// `map.CreateNameSE` banner text and its time left.
+    name_banner: Option<(String, f32)>,
 
     cam_eye: Vec3,
     cam_look: Vec3,
     pending_lerp_ms: f32,
     lerp: Option<CameraLerp>,
+    /// `camera.ChangeCameraPath` in flight.
+    path: Option<CameraSpline>,
+    shake: Option<CameraShake>,
+    /// Poses pushed by `camera.Save`, popped by `camera.Resume`.
+    camera_stack: Vec<(Vec3, Vec3)>,
 
     actdrop: ActDrop,
     dialog: Option<Dialog>,
@@ -79,12 +167,15 @@ pub struct Pal5ScriptContext {
     sounds: Vec<RefCell<Box<dyn AudioMemorySource>>>,
 
     sleep_sec: f32,
+    npc_wait: Option<NpcWait>,
+    /// The VM stopped on a script error and is never resumed again.
     finished: bool,
 }
 
 impl Pal5ScriptContext {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
+        game: GameType,
         asset_loader: Rc<AssetLoader>,
         script_index: Rc<ScriptIndex>,
         scene_manager: ComRc<ISceneManager>,
@@ -95,6 +186,7 @@ impl Pal5ScriptContext {
     ) -> Self {
         let bgm = audio_engine.create_source();
         Self {
+            game,
             asset_loader,
             script_index,
             scene_manager,
@@ -103,26 +195,44 @@ impl Pal5ScriptContext {
             input_engine,
             ui,
             scene: None,
-            scene_loaded: false,
+            scene_name: None,
+            map_id: 0,
+            sub_map_id: 0,
             flags: HashMap::new(),
+            party: Pal5Party::new(),
             npcs: HashMap::new(),
             players: HashMap::new(),
+            player_locked: false,
+            script_stack: Vec::new(),
+            awaiting_event: false,
+            pending_events: VecDeque::new(),
+            map_events: Vec::new(),
+            map_change: None,
+            name_banner: None,
             cam_eye: Vec3::new(0.0, 0.0, 0.0),
             cam_look: Vec3::new(0.0, 0.0, 1.0),
             pending_lerp_ms: 0.0,
             lerp: None,
+            path: None,
+            shake: None,
+            camera_stack: Vec::new(),
             actdrop: ActDrop::new(),
             dialog: None,
             anykey_latch: false,
             bgm,
             sounds: Vec::new(),
             sleep_sec: 0.0,
+            npc_wait: None,
             finished: false,
         }
     }
 
     // ---- driver-facing helpers -----------------------------------
 
+    pub fn game(&self) -> GameType {
+        self.game
+    }
+
     pub fn script_index(&self) -> &Rc<ScriptIndex> {
         &self.script_index
     }
@@ -138,6 +248,44 @@ impl Pal5ScriptContext {
 
     pub fn is_sleeping(&self) -> bool {
         self.sleep_sec > 0.0
+            || self.npc_wait.is_some()
+            || self.map_change.is_some()
+            || (self.awaiting_event && self.pending_events.is_empty())
+    }
+
+    /// The event loop parked the script until an event is queued.
+    pub fn await_event(&mut self) {
+        self.awaiting_event = true;
+    }
+
+    /// Next queued event script for the event loop.
+    pub fn next_event(&mut self) -> Option<u32> {
+        self.awaiting_event = false;
+        self.pending_events.pop_front()
+    }
+
+    /// No script is running and the player has control: events fire.
+    fn free_roaming(&self) -> bool {
+        self.awaiting_event
+            && self.pending_events.is_empty()
+            && !self.player_locked
+            && self.dialog.is_none()
+            && self.map_change.is_none()
+    }
+
+    /// Hold the script until `wait` is satisfied. Checked every frame
+    /// in [`update`](Self::update); a wait on an unknown NPC is
+    /// satisfied immediately.
+    pub fn wait_for_npc(&mut self, wait: NpcWait) {
+        self.npc_wait = Some(wait);
+    }
+
+    fn npc_wait_satisfied(&self, wait: NpcWait) -> bool {
+        match wait {
+            NpcWait::Pos(h) => self.npcs.get(&h).is_none_or(|a| !a.motion().is_moving()),
+            NpcWait::Anim(h) => self.npcs.get(&h).is_none_or(|a| a.anim_done()),
+            NpcWait::Turn(h) => self.npcs.get(&h).is_none_or(|a| !a.motion().is_turning()),
+        }
     }
 
     pub fn mark_finished(&mut self) {
@@ -148,42 +296,123 @@ impl Pal5ScriptContext {
         self.finished
     }
 
+    /// Time left on the camera lerp or path in flight, which
+    /// `global.WaitForCameraLerp` sleeps for.
     pub fn camera_lerp_remaining(&self) -> f32 {
-        self.lerp
+        let lerp = self
+            .lerp
             .as_ref()
             .map(|l| (l.duration - l.elapsed).max(0.0))
-            .unwrap_or(0.0)
+            .unwrap_or(0.0);
+        let path = self.path.as_ref().map(|p| p.remaining()).unwrap_or(0.0);
+        lerp.max(path)
     }
 
     /// Current scene name for the agent snapshot — empty until the
-    /// bootstrap scene has been loaded.
+    /// story has begun its first scene.
     pub fn current_scene_name(&self) -> String {
-        if self.scene_loaded {
-            BOOTSTRAP_SCENE.to_string()
-        } else {
-            String::new()
+        self.scene_name.clone().unwrap_or_default()
+    }
+
+    /// `CallScript` entered script `id` (called by the harness before
+    /// the script's entry function runs).
+    pub fn enter_script(&mut self, id: u32) {
+        self.script_stack.push(id);
+    }
+
+    /// The innermost `CallScript` returned.
+    pub fn leave_script(&mut self) {
+        self.script_stack.pop();
+    }
+
+    /// A script is running or about to: the entry script, an event
+    /// or map script, or an event queued for the event loop. The Lua
+    /// coroutine can't be saved, so a save taken now couldn't resume
+    /// it; saves wait until this is `false`.
+    pub fn script_running(&self) -> bool {
+        !self.script_stack.is_empty() || !self.pending_events.is_empty()
+    }
+
+    /// Capture the state a save slot records. `None` before the story
+    /// has begun its first scene, when there is nothing to come back
+    /// to, and while [`script_running`](Self::script_running).
+    pub fn persistent_state(&self) -> Option<Pal5PersistentState> {
+        if self.script_running() {
+            return None;
+        }
+
+        let scene_name = self.scene_name.clone()?;
+        let mut state = Pal5PersistentState::new(self.game.app_name().to_string());
+        state.set_map(self.map_id, self.sub_map_id, scene_name);
+        state.set_position(self.leader_position());
+        state.set_camera(Some(Pal5SavedCamera {
+            eye: [self.cam_eye.x, self.cam_eye.y, self.cam_eye.z],
+            look_at: [self.cam_look.x, self.cam_look.y, self.cam_look.z],
+        }));
+        state.set_flags(self.flags.iter().map(|(k, v)| (*k, *v)));
+        state.set_players(self.players.keys().copied());
+        state.set_party(self.party.clone());
+        state.set_player_locked(self.player_locked);
+        state.set_events(self.map_events.clone());
+        Some(state)
+    }
+
+    /// Rebuild a saved state on a fresh context: flags and the party,
+    /// the saved map with its events, the players at the
+    /// leader's position, and the camera. NPCs aren't saved; the event
+    /// scripts that run next create the ones they need.
+    pub fn restore(&mut self, state: &Pal5PersistentState) {
+        self.flags = state.flags().iter().map(|(k, v)| (*k, *v)).collect();
+        self.party = state.party().clone();
+        self.player_locked = state.player_locked();
+
+        self.map_id = state.map_id();
+        self.sub_map_id = state.sub_map_id();
+        self.ensure_scene();
+
+        let [x, y, z] = state.position().unwrap_or([0.0; 3]);
+        for role_id in state.players() {
+            self.player_create(*role_id as f64, x as f64, z as f64);
+        }
+        for actor in self.players.values_mut() {
+            actor.motion_mut().set_position(Vec3::new(x, y, z));
+        }
+        for event in state.events() {
+            self.add_map_event(*event);
+        }
+
+        if let Some(camera) = state.camera() {
+            let [ex, ey, ez] = camera.eye;
+            let [lx, ly, lz] = camera.look_at;
+            self.set_camera_pose(Vec3::new(ex, ey, ez), Vec3::new(lx, ly, lz));
         }
     }
 
     /// Leader (player 1) world position for the agent snapshot, if the
     /// player entity has been created.
     pub fn leader_position(&self) -> Option<[f32; 3]> {
-        let entity = self.players.get(&1)?;
-        let pos = entity.transform().borrow().position();
+        let pos = self.players.get(&LEADER_ROLE)?.motion().position();
         Some([pos.x, pos.y, pos.z])
     }
 
+    /// Party stats, magic and inventory for the agent snapshot.
+    pub fn party(&self) -> &Pal5Party {
+        &self.party
+    }
+
     /// Agent fast-forward tick: collapse any pending `Wait` sleep and
     /// dismiss the current dialog so the Lua VM resumes immediately
     /// this frame. Mirrors PAL3's SCE fast-forward, which skips
     /// `giWait` / dialog waits.
     pub fn fast_forward_skip(&mut self) {
         self.sleep_sec = 0.0;
+        self.npc_wait = None;
         self.dialog = None;
     }
 
     /// Per-frame update (runs every frame, even while sleeping).
     pub fn update(&mut self, delta_sec: f32) {
+        self.update_map_change(delta_sec);
         if self.is_sleeping() {
             self.sleep_sec -= delta_sec;
             // A keypress fast-forwards the current Wait (skip dialog).
@@ -192,10 +421,104 @@ impl Pal5ScriptContext {
             }
         }
 
+        self.update_leader(delta_sec);
+        for actor in self.npcs.values_mut().chain(self.players.values_mut()) {
+            actor.update(delta_sec);
+        }
+        if let Some(wait) = self.npc_wait
+            && self.npc_wait_satisfied(wait)
+        {
+            self.npc_wait = None;
+        }
+        self.update_events();
+
         self.update_camera_lerp(delta_sec);
+        self.update_camera_path(delta_sec);
+        self.update_camera_shake(delta_sec);
         self.actdrop.update(self.ui.ui(), delta_sec);
         self.update_audio();
         self.update_dialog();
+        self.update_name_banner(delta_sec);
+    }
+
+    /// Queue the script of any event volume the leader walked into.
+    /// Volumes entered while a script runs are tracked but don't fire.
+    fn update_events(&mut self) {
+        let Some(position) = self.leader_vec3() else {
+            return;
+        };
+        let Some(scene) = self.scene.as_mut() else {
+            return;
+        };
+        let entered = scene.entered_events(&position);
+        if self.free_roaming() {
+            self.pending_events.extend(entered);
+        }
+    }
+
+    /// Finish the `map.Change` fade-out: load the destination map while
+    /// the screen is black, then fade back in.
+    fn update_map_change(&mut self, delta_sec: f32) {
+        let Some(change) = self.map_change.as_mut() else {
+            return;
+        };
+        change.remaining -= delta_sec;
+        if change.remaining > 0.0 {
+            return;
+        }
+        self.map_id = change.map_id;
+        self.sub_map_id = change.sub_map_id;
+        self.map_change = None;
+        match self.map_scene_name() {
+            Some(name) => self.load_scene(name),
+            None => log::warn!("PAL5: map {} has no scene, staying put", self.map_id),
+        }
+        self.actdrop
+            .set_darkness(InterpValue::new(1.0, 0.0, MAP_FADE_SEC));
+    }
+
+    fn update_name_banner(&mut self, delta_sec: f32) {
+        let Some((name, remaining)) = self.name_banner.as_mut() else {
+            return;
+        };
+        *remaining -= delta_sec;
+        if *remaining <= 0.0 {
+            self.name_banner = None;
+            return;
+        }
+        let ui = self.ui.ui();
+        let [w, _] = ui.io().display_size;
+        ui.window("pal5_map_name")
+            .position([w * 0.5, 80.0], imgui::Condition::Always)
+            .position_pivot([0.5, 0.5])
+            .always_auto_resize(true)
+            .movable(false)
+            .resizable(false)
+            .collapsible(false)
+            .title_bar(false)
+            .bg_alpha(0.4)
+            .build(|| ui.text(name.as_str()));
+    }
+
+    /// Turn directional input into leader movement, relative to the
+    /// camera. Input is ignored (the leader stands) while the script
+    /// holds the player or a dialog is up.
+    fn update_leader(&mut self, delta_sec: f32) {
+        let held = self.player_locked || self.dialog.is_some();
+        let direction = match self.scene.as_ref() {
+            Some(scene) if !held => {
+                get_moving_direction(self.input_engine.clone(), scene.scene.clone())
+            }
+            _ => Vec3::new_zeros(),
+        };
+        let direction = if direction.norm().is_finite() {
+            direction
+        } else {
+            Vec3::new_zeros()
+        };
+        if let Some(leader) = self.players.get_mut(&LEADER_ROLE) {
+            leader.motion_mut().steer(&direction, delta_sec);
+        }
     }
 
     fn update_camera_lerp(&mut self, delta_sec: f32) {
@@ -217,6 +540,30 @@ impl Pal5ScriptContext {
         }
     }
 
+    fn update_camera_path(&mut self, delta_sec: f32) {
+        let Some(path) = self.path.as_mut() else {
+            return;
+        };
+        path.update(delta_sec);
+        let (eye, look) = path.pose();
+        let done = path.is_done();
+        self.apply_camera(eye, look);
+        if done {
+            self.path = None;
+        }
+    }
+
+    fn update_camera_shake(&mut self, delta_sec: f32) {
+        let Some(shake) = self.shake.as_mut() else {
+            return;
+        };
+        shake.update(delta_sec);
+        if shake.is_done() {
+            self.shake = None;
+        }
+        self.place_camera();
+    }
+
     fn update_audio(&mut self) {
         for s in &self.sounds {
             s.borrow_mut().update();
@@ -249,13 +596,32 @@ impl Pal5ScriptContext {
         }
     }
 
+    /// Set the scripted camera pose and place the scene camera there.
     fn apply_camera(&mut self, eye: Vec3, look: Vec3) {
+        self.cam_eye = eye;
+        self.cam_look = look;
+        self.place_camera();
+    }
+
+    /// Place the scene camera at the scripted pose plus any shake.
+    fn place_camera(&self) {
+        let offset = self
+            .shake
+            .as_ref()
+            .map(|s| s.offset())
+            .unwrap_or_else(Vec3::new_zeros);
+        let eye = Vec3::add(&self.cam_eye, &offset);
+        let look = Vec3::add(&self.cam_look, &offset);
         if let Some(cam) = self.scene_manager.camera() {
             cam.set_position(eye.x, eye.y, eye.z);
             cam.look_at(look.x, look.y, look.z);
         }
-        self.cam_eye = eye;
-        self.cam_look = look;
+    }
+
+    fn leader_vec3(&self) -> Option<Vec3> {
+        self.players
+            .get(&LEADER_ROLE)
+            .map(|a| a.motion().position())
     }
 
     fn anykey_pressed(&self) -> bool {
@@ -265,22 +631,52 @@ impl Pal5ScriptContext {
             || input.get_key_state(Key::GamePadSouth).pressed()
     }
 
+    /// Scene for the current map id ([`AssetLoader::scene_for_map`]).
+    /// `None` until the prequel's first map change.
+    fn map_scene_name(&self) -> Option<String> {
+        self.asset_loader.scene_for_map(self.map_id)
+    }
+
+    /// Load the current map's scene if none is up yet. With no known
+    /// scene this waits for the next map change instead.
     fn ensure_scene(&mut self) {
-        if self.scene_loaded {
+        if self.scene_name.is_some() {
             return;
         }
-        self.scene_loaded = true;
-        match Pal5Scene::load(&self.asset_loader, BOOTSTRAP_SCENE) {
+        if let Some(name) = self.map_scene_name() {
+            self.load_scene(name);
+        }
+    }
+
+    /// Replace the current scene. Players move to the new scene; NPCs
+    /// and events belong to the map they were created on and are
+    /// dropped.
+    fn load_scene(&mut self, name: String) {
+        match Pal5Scene::load(&self.asset_loader, &name) {
             Ok(scene) => {
+                if self.scene.take().is_some() {
+                    self.scene_manager.pop_scene();
+                }
                 self.scene_manager.push_scene(scene.scene.clone());
+                for actor in self.players.values() {
+                    scene.scene.add_entity(actor.entity().clone());
+                }
+                for (_, actor) in self.npcs.drain() {
+                    actor.set_visible(false);
+                }
+                self.map_events.clear();
+                self.pending_events.clear();
                 self.scene = Some(scene);
-                log::info!("PAL5: loaded bootstrap scene '{}'", BOOTSTRAP_SCENE);
+                log::info!("PAL5: loaded scene '{}'", name);
             }
-            Err(e) => log::error!("PAL5: failed to load scene '{}': {}", BOOTSTRAP_SCENE, e),
+            Err(e) => log::error!("PAL5: failed to load scene '{}': {}", name, e),
         }
+        self.scene_name = Some(name);
     }
 
-    fn spawn_model(&self, model_id: i32, x: f32, z: f32) -> Option<ComRc<IEntity>> {
+    /// Load a role-index model into the scene. Returns the entity and
+    /// its model path (used to find the model's animation clips).
+    fn spawn_model(&self, model_id: i32, x: f32, z: f32) -> Option<(ComRc<IEntity>, String)> {
         let item = self.asset_loader.index.get(&(model_id as u32))?;
         let file_path = item.file_path.to_string();
         if !file_path.ends_with(".dff") {
@@ -295,7 +691,7 @@ impl Pal5ScriptContext {
                 if let Some(scene) = &self.scene {
                     scene.scene.add_entity(entity.clone());
                 }
-                Some(entity)
+                Some((entity, file_path))
             }
             Err(e) => {
                 log::warn!("PAL5: load model {} ({}): {}", model_id, file_path, e);
@@ -304,6 +700,15 @@ impl Pal5ScriptContext {
         }
     }
 
+    /// Register an event volume on the current scene.
+    fn add_map_event(&mut self, event: Pal5MapEvent) {
+        let leader = self.leader_vec3();
+        if let Some(scene) = self.scene.as_mut() {
+            scene.add_event(&event, leader.as_ref());
+        }
+        self.map_events.push(event);
+    }
+
     // ---- command handlers: global --------------------------------
 
     pub fn global_print(&mut self, text: *const c_char) {
@@ -347,6 +752,8 @@ impl Pal5ScriptContext {
 
     pub fn global_play_cg(&mut self, cg_id: f64) {
         // CG/movie playback is best-effort; skipped for the bootstrap.
+        // No CG id -> movie table is known for PAL5 or the prequel, so
+        // there is nothing per-game to resolve yet.
         log::info!("PAL5: PlayCg({}) (skipped)", cg_id as i32);
     }
 
@@ -362,84 +769,218 @@ impl Pal5ScriptContext {
 
     // ---- command handlers: player --------------------------------
 
+    /// `player.Create(role, x, z)`: place the role on the map and bring
+    /// it into the team.
     pub fn player_create(&mut self, role_id: f64, x: f64, z: f64) {
-        if let Some(e) = self.spawn_model(role_id as i32, x as f32, z as f32) {
-            self.players.insert(role_id as i32, e);
+        self.party.join(role_id as i32);
+        if let Some((e, model_path)) = self.spawn_model(role_id as i32, x as f32, z as f32) {
+            let position = Vec3::new(x as f32, 0.0, z as f32);
+            let actor = Pal5Actor::new(e, self.asset_loader.clone(), &model_path, position);
+            if let Some(old) = self.players.insert(role_id as i32, actor) {
+                old.set_visible(false);
+            }
         }
     }
 
     pub fn player_set_pos(&mut self, x: f64, z: f64) {
         // Player 1 is the leader; reposition every known player entity.
-        for e in self.players.values() {
-            e.transform()
-                .borrow_mut()
-                .set_position(&Vec3::new(x as f32, 0.0, z as f32));
+        for actor in self.players.values_mut() {
+            actor
+                .motion_mut()
+                .set_position(Vec3::new(x as f32, 0.0, z as f32));
+        }
+    }
+
+    /// `player.SetAt(x, z, degrees)`: place the leader and face it.
+    pub fn player_set_at(&mut self, x: f64, z: f64, degrees: f64) {
+        self.player_set_pos(x, z);
+        if let Some(leader) = self.players.get_mut(&LEADER_ROLE) {
+            leader.motion_mut().set_heading(degrees as f32);
         }
     }
 
     pub fn player_set_visible(&mut self, role_id: f64, visible: f64) {
-        if let Some(e) = self.players.get(&(role_id as i32)) {
-            e.set_visible(visible != 0.0);
+        if let Some(a) = self.players.get(&(role_id as i32)) {
+            a.set_visible(visible != 0.0);
         }
     }
 
     pub fn player_remove(&mut self, role_id: f64) {
-        if let Some(e) = self.players.remove(&(role_id as i32)) {
-            e.set_visible(false);
+        if let Some(a) = self.players.remove(&(role_id as i32)) {
+            a.set_visible(false);
         }
     }
 
-    pub fn player_is_in_team(&mut self, _role_id: f64) -> f64 {
-        1.0
+    /// `player.IsPlayerInTeam(role)`: whether `player.Create` has
+    /// brought the role into the team.
+    pub fn player_is_in_team(&mut self, role_id: f64) -> f64 {
+        self.party.is_in_team(role_id as i32) as i32 as f64
     }
 
-    pub fn player_get_item_count(&mut self, _item_id: f64) -> f64 {
-        0.0
+    /// `player.Control(on)`: give the player control back, or with `0`
+    /// take it away.
+    pub fn player_control(&mut self, on: bool) {
+        self.player_locked = !on;
+    }
+
+    /// `player.Stop()`: take control away and halt the leader.
+    pub fn player_stop(&mut self) {
+        self.player_locked = true;
+        if let Some(leader) = self.players.get_mut(&LEADER_ROLE) {
+            leader.motion_mut().stop();
+        }
+    }
+
+    pub fn player_get_item_count(&mut self, item_id: f64) -> f64 {
+        self.party.item_count(item_id as i32) as f64
+    }
+
+    /// `player.AddItem(id, count)`; a negative count takes items away.
+    /// A missing count reads as `0` from Lua and grants one.
+    pub fn player_add_item(&mut self, item_id: f64, count: f64) {
+        self.party.add_item(item_id as i32, grant_count(count));
+    }
+
+    /// `player.AddEquip(id, count)`, counted like `AddItem`.
+    pub fn player_add_equip(&mut self, equip_id: f64, count: f64) {
+        self.party
+            .add_equipment(equip_id as i32, grant_count(count));
+    }
+
+    pub fn player_add_magic(&mut self, role_id: f64, magic_id: f64) {
+        self.party.add_magic(role_id as i32, magic_id as i32);
+    }
+
+    pub fn player_add_formula(&mut self, formula_id: f64) {
+        self.party.add_formula(formula_id as i32);
+    }
+
+    pub fn player_change_hp(&mut self, role_id: f64, delta: f64) {
+        self.party.change_hp(role_id as i32, delta as i32);
+    }
+
+    pub fn player_change_mp(&mut self, role_id: f64, delta: f64) {
+        self.party.change_mp(role_id as i32, delta as i32);
     }
 
     // ---- command handlers: npc -----------------------------------
 
     pub fn npc_create(&mut self, model_id: f64, handle: f64, x: f64, z: f64) {
-        if let Some(e) = self.spawn_model(model_id as i32, x as f32, z as f32) {
-            self.npcs.insert(handle as i32, e);
+        if let Some((e, model_path)) = self.spawn_model(model_id as i32, x as f32, z as f32) {
+            let position = Vec3::new(x as f32, 0.0, z as f32);
+            let actor = Pal5Actor::new(e, self.asset_loader.clone(), &model_path, position);
+            if let Some(old) = self.npcs.insert(handle as i32, actor) {
+                old.set_visible(false);
+            }
         }
     }
 
+    fn npc(&mut self, handle: f64) -> Option<&mut Pal5Actor> {
+        self.npcs.get_mut(&(handle as i32))
+    }
+
     pub fn npc_set_pos(&mut self, handle: f64, x: f64, z: f64) {
-        if let Some(e) = self.npcs.get(&(handle as i32)) {
-            e.transform()
-                .borrow_mut()
-                .set_position(&Vec3::new(x as f32, 0.0, z as f32));
+        if let Some(a) = self.npc(handle) {
+            a.motion_mut()
+                .set_position(Vec3::new(x as f32, 0.0, z as f32));
         }
     }
 
     pub fn npc_set_pos_3d(&mut self, handle: f64, x: f64, y: f64, z: f64) {
-        if let Some(e) = self.npcs.get(&(handle as i32)) {
-            e.transform()
-                .borrow_mut()
-                .set_position(&Vec3::new(x as f32, y as f32, z as f32));
+        if let Some(a) = self.npc(handle) {
+            a.motion_mut()
+                .set_position(Vec3::new(x as f32, y as f32, z as f32));
         }
     }
 
     pub fn npc_move_to(&mut self, handle: f64, x: f64, z: f64) {
-        // Movement snaps instantly for the bootstrap (WaitForNpcPos is
-        // then immediately satisfied).
-        self.npc_set_pos(handle, x, z);
+        if let Some(a) = self.npc(handle) {
+            a.motion_mut().move_to(x as f32, z as f32, false);
+        }
     }
 
     pub fn npc_run_to(&mut self, handle: f64, x: f64, z: f64) {
-        self.npc_set_pos(handle, x, z);
+        if let Some(a) = self.npc(handle) {
+            a.motion_mut().move_to(x as f32, z as f32, true);
+        }
+    }
+
+    /// `npc.FloatTo(h, x, y, z, ms)`: straight 3D glide; without a
+    /// duration the NPC's walking speed is used.
+    pub fn npc_float_to(&mut self, handle: f64, x: f64, y: f64, z: f64, ms: f64) {
+        if let Some(a) = self.npc(handle) {
+            let dest = Vec3::new(x as f32, y as f32, z as f32);
+            a.motion_mut().float_to(dest, ms as f32 / 1000.0);
+        }
+    }
+
+    pub fn npc_set_speed(&mut self, handle: f64, speed: f64) {
+        if let Some(a) = self.npc(handle) {
+            a.motion_mut().set_speed(speed as f32);
+        }
+    }
+
+    pub fn npc_turn_to(&mut self, handle: f64, degrees: f64) {
+        if let Some(a) = self.npc(handle) {
+            a.motion_mut().turn_to(degrees as f32);
+        }
+    }
+
+    pub fn npc_turn_to_pos(&mut self, handle: f64, x: f64, z: f64) {
+        if let Some(a) = self.npc(handle) {
+            a.motion_mut().turn_to_point(x as f32, z as f32);
+        }
+    }
+
+    /// Turn to face another NPC, or with handle 0 the leader.
+    pub fn npc_turn_to_npc(&mut self, handle: f64, other: f64) {
+        let target = if other as i32 == 0 {
+            self.leader_position().map(|[x, _, z]| (x, z))
+        } else {
+            self.npcs.get(&(other as i32)).map(|a| {
+                let p = a.motion().position();
+                (p.x, p.z)
+            })
+        };
+        if let (Some((x, z)), Some(a)) = (target, self.npc(handle)) {
+            a.motion_mut().turn_to_point(x, z);
+        }
+    }
+
+    pub fn npc_add_patrol_point(&mut self, handle: f64, x: f64, z: f64) {
+        if let Some(a) = self.npc(handle) {
+            a.motion_mut().add_patrol_point(x as f32, z as f32);
+        }
+    }
+
+    pub fn npc_set_patrol_type(&mut self, handle: f64, patrol_type: f64) {
+        if let Some(a) = self.npc(handle) {
+            a.motion_mut()
+                .set_patrol_type(PatrolType::from_i32(patrol_type as i32));
+        }
+    }
+
+    pub fn npc_set_anim(&mut self, handle: f64, anim_id: f64, looping: f64) {
+        if let Some(a) = self.npc(handle) {
+            a.set_anim(anim_id as u32, looping != 0.0);
+        }
+    }
+
+    pub fn npc_add_anim_chain(&mut self, handle: f64, anim_id: f64, looping: f64) {
+        if let Some(a) = self.npc(handle) {
+            a.add_anim_chain(anim_id as u32, looping != 0.0);
+        }
     }
 
     pub fn npc_set_visible(&mut self, handle: f64, visible: f64) {
-        if let Some(e) = self.npcs.get(&(handle as i32)) {
-            e.set_visible(visible != 0.0);
+        if let Some(a) = self.npcs.get(&(handle as i32)) {
+            a.set_visible(visible != 0.0);
         }
     }
 
     pub fn npc_destroy(&mut self, handle: f64) {
-        if let Some(e) = self.npcs.remove(&(handle as i32)) {
-            e.set_visible(false);
+        if let Some(a) = self.npcs.remove(&(handle as i32)) {
+            a.set_visible(false);
         }
     }
 
@@ -447,11 +988,52 @@ impl Pal5ScriptContext {
         self.npcs.contains_key(&(handle as i32)) as i32 as f64
     }
 
+    /// `npc.CreateObject(model, handle, x, z)`: a scene prop. Props
+    /// share the NPC handles, so `npc.SetPos` / `npc.Destroy` and the
+    /// animation commands work on them too.
+    pub fn npc_create_object(&mut self, model_id: f64, handle: f64, x: f64, z: f64) {
+        self.npc_create(model_id, handle, x, z);
+    }
+
+    /// `npc.CreateChest`: takes six numbers, but which is the chest,
+    /// model, position, item and count hasn't been worked out from the
+    /// scripts, so no chest is placed.
+    pub fn npc_create_chest(&mut self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+        log::warn!(
+            "npc.CreateChest({}, {}, {}, {}, {}, {}): unimplemented, argument layout unknown",
+            a,
+            b,
+            c,
+            d,
+            e,
+            f
+        );
+    }
+
+    /// `npc.CreateSE`: an ambient sound emitter. PAL5 sound ids don't map
+    /// to files yet (see `global.PlaySound`), so nothing plays.
+    pub fn npc_create_se(&mut self, a: f64, b: f64, c: f64, d: f64) {
+        log::warn!(
+            "npc.CreateSE({}, {}, {}, {}): unimplemented, no sound table",
+            a,
+            b,
+            c,
+            d
+        );
+    }
+
     // ---- command handlers: camera --------------------------------
 
     pub fn camera_change_static(&mut self, ex: f64, ey: f64, ez: f64, lx: f64, ly: f64, lz: f64) {
         let eye = Vec3::new(ex as f32, ey as f32, ez as f32);
         let look = Vec3::new(lx as f32, ly as f32, lz as f32);
+        self.change_camera(eye, look);
+    }
+
+    /// Move to a static shot, lerping when `camera.ResetLerp` asked for
+    /// it. Cancels any camera path.
+    fn change_camera(&mut self, eye: Vec3, look: Vec3) {
+        self.path = None;
         if self.pending_lerp_ms > 0.0 {
             self.lerp = Some(CameraLerp {
                 from_eye: self.cam_eye,
@@ -484,13 +1066,77 @@ impl Pal5ScriptContext {
         self.pending_lerp_ms = ms as f32;
     }
 
+    /// `camera.ChangeCameraPath(id, ms)`: run path `id` from the camera
+    /// path table over `ms`, or the path's own time when `ms` is `0`.
+    pub fn camera_change_path(&mut self, path_id: f64, ms: f64) {
+        let Some(path) = self.asset_loader.load_camera_path(path_id as i32) else {
+            log::warn!("PAL5: camera path {} not found", path_id as i32);
+            return;
+        };
+        let duration = if ms > 0.0 {
+            ms as f32 / 1000.0
+        } else {
+            path.duration
+        };
+        let Some(spline) = CameraSpline::new(&path, duration) else {
+            return;
+        };
+        let (eye, look) = spline.pose();
+        self.lerp = None;
+        self.pending_lerp_ms = 0.0;
+        self.path = Some(spline);
+        self.apply_camera(eye, look);
+    }
+
+    /// `camera.ChangeCameraStaticToNpc(h, dx, dy, dz)`: a static shot
+    /// from `(dx, dy, dz)` off NPC `h` (the leader for `0`), looking at
+    /// its chest.
+    pub fn camera_change_static_to_npc(&mut self, handle: f64, dx: f64, dy: f64, dz: f64) {
+        let target = if handle as i32 == 0 {
+            self.leader_position().map(|[x, y, z]| Vec3::new(x, y, z))
+        } else {
+            self.npcs
+                .get(&(handle as i32))
+                .map(|a| a.motion().position())
+        };
+        let Some(target) = target else {
+            log::warn!("PAL5: camera target npc {} not found", handle as i32);
+            return;
+        };
+        let eye = Vec3::add(&target, &Vec3::new(dx as f32, dy as f32, dz as f32));
+        let look = Vec3::add(&target, &Vec3::new(0.0, NPC_LOOK_HEIGHT, 0.0));
+        self.change_camera(eye, look);
+    }
+
+    /// `camera.Save()`: remember the current shot.
+    pub fn camera_save(&mut self) {
+        self.camera_stack.push((self.cam_eye, self.cam_look));
+    }
+
+    /// `camera.Resume()`: go back to the last saved shot, lerping when
+    /// `camera.ResetLerp` asked for it.
+    pub fn camera_resume(&mut self) {
+        match self.camera_stack.pop() {
+            Some((eye, look)) => self.change_camera(eye, look),
+            None => log::warn!("PAL5: camera.Resume with no saved camera"),
+        }
+    }
+
+    /// `camera.Shake(amplitude, ms)`.
+    pub fn camera_shake(&mut self, amplitude: f64, ms: f64) {
+        self.shake = Some(CameraShake::new(amplitude as f32, ms as f32 / 1000.0));
+    }
+
     /// Place the camera at an absolute pose (used by the agent server's
-    /// `/v1/camera/pose`). Cancels any in-flight lerp and pending-lerp
-    /// request so the pose is not immediately animated away. Stable only
-    /// while the debug camera is enabled (plot frozen); otherwise the
-    /// next scripted camera command will overwrite it.
+    /// `/v1/camera/pose`). Cancels any in-flight lerp, path, shake and
+    /// pending-lerp request so the pose is not immediately animated
+    /// away. Stable only while the debug camera is enabled (plot
+    /// frozen); otherwise the next scripted camera command will
+    /// overwrite it.
     pub fn set_camera_pose(&mut self, eye: Vec3, look: Vec3) {
         self.lerp = None;
+        self.path = None;
+        self.shake = None;
         self.pending_lerp_ms = 0.0;
         self.apply_camera(eye, look);
     }
@@ -530,12 +1176,59 @@ impl Pal5ScriptContext {
 
     // ---- command handlers: map -----------------------------------
 
-    pub fn map_change_no_script(&mut self, _map_id: f64, _sub_id: f64) {
-        self.ensure_scene();
+    pub fn map_change_no_script(&mut self, map_id: f64, sub_id: f64) {
+        self.map_id = map_id as i32;
+        self.sub_map_id = sub_id as i32;
+        match self.map_scene_name() {
+            Some(name) if self.scene_name.as_deref() != Some(name.as_str()) => {
+                self.load_scene(name)
+            }
+            Some(_) => {}
+            None => log::warn!("PAL5: map {} has no scene, staying put", self.map_id),
+        }
     }
 
     pub fn map_get_current_map_id(&mut self) -> f64 {
-        1.0
+        self.map_id as f64
+    }
+
+    /// `map.AddEvent(script, x1, z1, x2, z2)`: call script `script` when
+    /// the leader walks into the XZ rectangle with corners `(x1, z1)`
+    /// and `(x2, z2)`.
+    pub fn map_add_event(&mut self, script_id: f64, x1: f64, z1: f64, x2: f64, z2: f64) {
+        let (x1, z1, x2, z2) = (x1 as f32, z1 as f32, x2 as f32, z2 as f32);
+        self.add_map_event(Pal5MapEvent {
+            script_id: script_id as u32,
+            rect: (x1.min(x2), x1.max(x2), z1.min(z2), z1.max(z2)),
+        });
+    }
+
+    /// `map.CreateNameSE(sound)`: show the current map's name banner,
+    /// with its sound best-effort like `global.PlaySound`.
+    pub fn map_create_name_se(&mut self, sound_id: f64) {
+        log::debug!("PAL5: CreateNameSE({})", sound_id as i32);
+        self.name_banner = self.map_scene_name().map(|name| (name, NAME_BANNER_SEC));
+    }
+
+    /// `map.Change(map, sub)`: fade out; the new map loads once the
+    /// screen is black (see `update_map_change`). The script is held
+    /// until then. Nothing links a map to its entry script yet, so unlike
+    /// the game none is run afterwards.
+    pub fn begin_map_change(&mut self, map_id: f64, sub_id: f64) {
+        self.map_change = Some(MapChange {
+            map_id: map_id as i32,
+            sub_map_id: sub_id as i32,
+            remaining: MAP_FADE_SEC,
+        });
+        self.actdrop
+            .set_darkness(InterpValue::new(0.0, 1.0, MAP_FADE_SEC));
+    }
+}
+
+fn grant_count(count: f64) -> i32 {
+    match count as i32 {
+        0 => 1,
+        n => n,
     }
 }
 
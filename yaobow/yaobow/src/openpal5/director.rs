@@ -14,14 +14,13 @@ use radiance::radiance::UiManager;
 use radiance::utils::free_view::FreeViewController;
 
 use shared::agent_common::AgentBridge;
-use shared::scripting::lua50_32::Lua5032Vm;
+use shared::openpal5::persistent_state::Pal5PersistentState;
+use shared::scripting::debugger::{DebuggerWindow, ScriptDebugTarget};
+use shared::scripting::lua50_32::{Lua5032Vm, LuaValue};
 
-use super::commands::create_lua_vm;
+use super::commands::{create_lua_vm, story_entry};
 use super::context::Pal5ScriptContext;
 
-/// Script id of the canonical "start a new game" entry (`NewGame.lua`).
-const NEWGAME_SCRIPT_ID: u32 = 1;
-
 pub struct Pal5StoryDirector {
     vm: Lua5032Vm<Pal5ScriptContext>,
     context: Rc<RefCell<Pal5ScriptContext>>,
@@ -42,6 +41,11 @@ pub struct Pal5StoryDirector {
     input: Rc<RefCell<dyn InputEngine>>,
     scene_manager: ComRc<ISceneManager>,
     ui: Rc<UiManager>,
+
+    /// Script debugger window. Shown with the debug camera, and kept up
+    /// afterwards while the debugger has breakpoints / watches set or
+    /// holds the VM, so a stop is visible with the plot running.
+    script_debugger: RefCell<DebuggerWindow>,
 }
 
 ComObject_Pal5StoryDirector!(super::Pal5StoryDirector);
@@ -62,20 +66,60 @@ impl Pal5StoryDirector {
         input: Rc<RefCell<dyn InputEngine>>,
         scene_manager: ComRc<ISceneManager>,
         ui: Rc<UiManager>,
+    ) -> anyhow::Result<Self> {
+        Self::build(context, None, agent_bridge, input, scene_manager, ui)
+    }
+
+    /// Like [`Self::with_agent_bridge`], but continue a saved game: the
+    /// context is restored from `save` and the VM enters the harness'
+    /// free-roam event loop on the restored map, where the save was
+    /// taken.
+    pub fn from_save(
+        context: Pal5ScriptContext,
+        save: &Pal5PersistentState,
+        agent_bridge: Option<Rc<AgentBridge>>,
+        input: Rc<RefCell<dyn InputEngine>>,
+        scene_manager: ComRc<ISceneManager>,
+        ui: Rc<UiManager>,
+    ) -> anyhow::Result<Self> {
+        Self::build(context, Some(save), agent_bridge, input, scene_manager, ui)
+    }
+
+    fn build(
+        context: Pal5ScriptContext,
+        save: Option<&Pal5PersistentState>,
+        agent_bridge: Option<Rc<AgentBridge>>,
+        input: Rc<RefCell<dyn InputEngine>>,
+        scene_manager: ComRc<ISceneManager>,
+        ui: Rc<UiManager>,
     ) -> anyhow::Result<Self> {
         let context = Rc::new(RefCell::new(context));
         let vm = create_lua_vm(context.clone())?;
 
-        // Load the `NewGame` entry script, then enter via the harness'
-        // `__pal5_main` wrapper (which calls `NewGame()` and flags
-        // completion when it returns).
-        let (_name, source) = {
+        // Load the game's entry script (`NewGame`; it also defines the
+        // helpers later scripts rely on), then enter via the harness:
+        // `__pal5_main` calls the entry function named by
+        // `__pal5_entry_name` and then waits for map events in
+        // `__pal5_events`, where a loaded game starts.
+        let (id, name, source) = {
             let c = context.borrow();
-            c.script_index()
-                .load_source(c.asset_loader().vfs(), NEWGAME_SCRIPT_ID)?
+            let id = c
+                .asset_loader()
+                .entry_script_id(c.script_index())
+                .ok_or_else(|| anyhow::anyhow!("no entry script in scriptlist.ini"))?;
+            let (name, source) = c.script_index().load_source(c.asset_loader().vfs(), id)?;
+            (id, name, source)
         };
-        vm.load_chunk(&source, "NewGame")?;
-        vm.set_entry("__pal5_main")?;
+        vm.load_chunk(&source, &name)?;
+        if !vm.script_functions().contains(&name) {
+            anyhow::bail!("entry script '{}' defines no {}() function", name, name);
+        }
+        vm.set_global("__pal5_entry_name", &LuaValue::Str(name));
+        vm.set_global("__pal5_entry_id", &LuaValue::Number(id as f64));
+        if let Some(save) = save {
+            context.borrow_mut().restore(save);
+        }
+        vm.set_entry(story_entry(save.is_some()))?;
 
         let free_view = FreeViewController::new(input.clone());
 
@@ -88,6 +132,7 @@ impl Pal5StoryDirector {
             input,
             scene_manager,
             ui,
+            script_debugger: RefCell::new(DebuggerWindow::new()),
         })
     }
 
@@ -97,6 +142,20 @@ impl Pal5StoryDirector {
         self.context.clone()
     }
 
+    /// The story Lua VM, for the agent's `/v1/debug/*` routes.
+    pub fn vm(&self) -> &Lua5032Vm<Pal5ScriptContext> {
+        &self.vm
+    }
+
+    fn render_script_debugger(&self) {
+        let shown = self.debug_cam_active() || self.vm.debug_state().borrow().is_active();
+        if shown {
+            self.script_debugger
+                .borrow_mut()
+                .render(self.ui.ui(), &self.vm);
+        }
+    }
+
     /// Toggle the free-fly debug camera when the `` ` ``/tilde key is
     /// pressed. The shared [`FreeViewController`] drives the existing
     /// scene camera transform in place, so toggling on continues from
@@ -175,6 +234,7 @@ impl IDirectorImpl for Pal5StoryDirector {
         // so neither the story nor the scripted camera advance — and
         // drive the scene camera manually instead.
         self.handle_debug_cam_toggle();
+        self.render_script_debugger();
         if self.debug_cam_active() {
             if let Some(scene) = self.scene_manager.scene() {
                 self.free_view.update(scene, delta_sec);
@@ -183,6 +243,12 @@ impl IDirectorImpl for Pal5StoryDirector {
             return None;
         }
 
+        // A script debugger stop freezes the plot the same way, short of
+        // handing the camera over.
+        if self.vm.debug_held() {
+            return None;
+        }
+
         // Pause / fixed-step gating: when an agent bridge is present
         // and paused, `advance` is false and `effective_dt` is 0, so
         // the script clock freezes until a `/v1/time/step` is queued.
@@ -2,14 +2,17 @@
 //!
 //! The low-level PAL5 asset/scene loaders live in `shared::openpal5`;
 //! this module adds the *story runtime*: the Lua command handlers
-//! ([`context`]), the Lua bridge ([`commands`]), and the per-frame
-//! director ([`director`]) that runs the game scripts.
+//! ([`context`]), the Lua bridge ([`commands`]), the per-frame
+//! director ([`director`]) that runs the game scripts, and the title
+//! menu ([`title`]) the mode router ([`modes`]) starts a launch on.
 
 pub mod agent;
 pub mod commands;
 pub mod context;
 pub mod director;
+pub mod modes;
 pub mod service;
+pub mod title;
 
 pub use director::Pal5StoryDirector;
 pub use service::Pal5Service;
@@ -18,36 +21,45 @@ use std::rc::Rc;
 
 use crosscom::ComRc;
 use packfs::init_virtual_fs;
-use radiance::comdef::{IApplication, IApplicationExt, IDirector};
+use radiance::comdef::{IApplicationExt, IDirector};
 use radiance::scene::CoreScene;
 
-use shared::GameType;
-use shared::agent_common::AgentBridge;
 use shared::openpal5::asset_loader::AssetLoader;
+use shared::openpal5::persistent_state::Pal5PersistentState;
 use shared::openpal5::script::ScriptIndex;
 
 use context::Pal5ScriptContext;
+use modes::Pal5Launch;
 
-/// Build the PAL5 story director for `game` (PAL5 / PAL5Q) from
-/// `asset_path`. Mirrors `Swd5Service::create_director`: it pulls the
+/// Build the PAL5 story director for the launch's game (PAL5 / PAL5Q)
+/// and asset path. Mirrors `Swd5Service::create_director`: it pulls the
 /// engine handles off the live application, builds the per-launch vfs +
-/// asset loader + script index, pushes an empty initial scene so the
-/// first VM tick sees a valid scene-manager state, and returns the
-/// ready `IDirector`.
+/// asset loader + script index, replaces whatever scenes the previous
+/// mode left with an empty initial scene so the first VM tick sees a
+/// valid scene-manager state, and returns the ready `IDirector`. With
+/// `save`, the director continues that saved game instead of starting
+/// `NewGame`.
 ///
-/// When `agent_bridge` is `Some` (`--pal5 --agent-port`), the director
-/// is plumbed with the bridge (so pause/step + fast-forward take
+/// When the launch carries an agent bridge (`--pal5 --agent-port`), the
+/// director is plumbed with it (so pause/step + fast-forward take
 /// effect) and the context reads the bridge's synthetic-input overlay
 /// so `/v1/input/*` reaches the Lua VM.
 ///
 /// Returns `None` when the scriptlist can't be read (e.g. missing PAL5
 /// assets at `asset_path`) so the caller can surface a clear error.
 pub fn create_story_director(
-    app: ComRc<IApplication>,
-    asset_path: &str,
-    game: GameType,
-    agent_bridge: Option<Rc<AgentBridge>>,
+    launch: &Pal5Launch,
+    save: Option<&Pal5PersistentState>,
 ) -> Option<ComRc<IDirector>> {
+    let Pal5Launch {
+        app,
+        asset_path,
+        game,
+        agent_bridge,
+        ..
+    } = launch;
+    let agent_bridge = agent_bridge.clone();
+
     let engine_rc = app.engine();
     let engine = engine_rc.borrow();
     let component_factory = engine.rendering_component_factory();
@@ -77,13 +89,21 @@ pub fn create_story_director(
             return None;
         }
     };
-    let asset_loader = AssetLoader::new(component_factory.clone(), vfs);
+    let asset_loader = match AssetLoader::new(component_factory.clone(), vfs, *game) {
+        Ok(loader) => loader,
+        Err(e) => {
+            log::error!("PAL5: {}", e);
+            return None;
+        }
+    };
 
     // Empty initial scene so the VM's first tick has a valid scene
     // manager (scripts push the real scene via BeginScene/ChangeMap).
+    scene_manager.unload_all_scenes();
     scene_manager.push_scene(CoreScene::create());
 
     let context = Pal5ScriptContext::new(
+        *game,
         asset_loader,
         script_index,
         scene_manager.clone(),
@@ -93,13 +113,24 @@ pub fn create_story_director(
         ui.clone(),
     );
 
-    match Pal5StoryDirector::with_agent_bridge(
-        context,
-        agent_bridge,
-        input_engine,
-        scene_manager,
-        ui,
-    ) {
+    let director = match save {
+        Some(save) => Pal5StoryDirector::from_save(
+            context,
+            save,
+            agent_bridge,
+            input_engine,
+            scene_manager,
+            ui,
+        ),
+        None => Pal5StoryDirector::with_agent_bridge(
+            context,
+            agent_bridge,
+            input_engine,
+            scene_manager,
+            ui,
+        ),
+    };
+    match director {
         Ok(director) => Some(ComRc::<IDirector>::from_object(director)),
         Err(e) => {
             log::error!("PAL5: failed to build story director: {}", e);
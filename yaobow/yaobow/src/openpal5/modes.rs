@@ -0,0 +1,187 @@
+//! PAL5 mode router + mode-factory registry — the single place that
+//! knows the PAL5 game-mode graph. Mirrors PAL4's `Pal4ModeRegistry`.
+//!
+//! A [`Pal5ModeIntent`] names a way to enter a mode (title menu, new
+//! game, continue from a slot); [`route`] looks its [`Pal5ModeKind`] up
+//! in the [`Pal5ModeRegistry`] and asks the registered factory for the
+//! director. Adding a mode is a `register` call, not an edit here.
+//!
+//! Unlike PAL4, whose start menu is script-built and calls back into
+//! its service, PAL5's title menu is a Rust director that routes its own
+//! choices. So the factories take a [`Pal5Launch`] — the per-launch
+//! handles (app, asset path, game, agent bridge, registry) — which the
+//! title director keeps a copy of.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crosscom::ComRc;
+use radiance::comdef::{IApplication, IDirector};
+
+use shared::GameType;
+use shared::agent_common::AgentBridge;
+use shared::openpal5::persistent_state::Pal5PersistentState;
+
+use super::title::Pal5TitleDirector;
+
+/// Typed PAL5 mode graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pal5ModeIntent {
+    /// The title menu (New Game / Load Game / Settings).
+    TitleMenu,
+
+    /// A fresh story playthrough (New Game).
+    Story,
+
+    /// A story playthrough continued from save `slot` (Load Game).
+    StoryFromSave { slot: i32 },
+}
+
+/// Coarse mode discriminant used as the registry key; `Story` and
+/// `StoryFromSave` share the story factory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pal5ModeKind {
+    TitleMenu,
+    Story,
+}
+
+impl Pal5ModeIntent {
+    /// The registry key this intent dispatches on.
+    pub fn kind(&self) -> Pal5ModeKind {
+        match self {
+            Pal5ModeIntent::TitleMenu => Pal5ModeKind::TitleMenu,
+            Pal5ModeIntent::Story | Pal5ModeIntent::StoryFromSave { .. } => Pal5ModeKind::Story,
+        }
+    }
+}
+
+/// Everything a mode factory needs to build a director for one launch.
+/// Cheap to clone; the title director holds one so it can route.
+#[derive(Clone)]
+pub struct Pal5Launch {
+    pub app: ComRc<IApplication>,
+    pub asset_path: String,
+    pub game: GameType,
+    pub agent_bridge: Option<Rc<AgentBridge>>,
+    pub registry: Rc<RefCell<Pal5ModeRegistry>>,
+}
+
+/// Factory that builds the director for a given intent. `None` when the
+/// director can't be built (e.g. missing PAL5 assets).
+pub type Pal5ModeFactory = Box<dyn Fn(&Pal5Launch, Pal5ModeIntent) -> Option<ComRc<IDirector>>>;
+
+/// Registry of mode factories keyed by [`Pal5ModeKind`]. Owned by
+/// `Pal5Service` and shared with every [`Pal5Launch`].
+pub struct Pal5ModeRegistry {
+    factories: HashMap<Pal5ModeKind, Pal5ModeFactory>,
+}
+
+impl Pal5ModeRegistry {
+    /// Build a registry with the two built-in modes: the Rust title menu
+    /// and the Lua story director.
+    pub fn with_builtins() -> Self {
+        let mut factories: HashMap<Pal5ModeKind, Pal5ModeFactory> = HashMap::new();
+
+        factories.insert(
+            Pal5ModeKind::TitleMenu,
+            Box::new(|launch: &Pal5Launch, intent: Pal5ModeIntent| match intent {
+                Pal5ModeIntent::TitleMenu => Some(ComRc::<IDirector>::from_object(
+                    Pal5TitleDirector::new(launch.clone()),
+                )),
+                other => unreachable_intent(Pal5ModeKind::TitleMenu, other),
+            }),
+        );
+
+        factories.insert(
+            Pal5ModeKind::Story,
+            Box::new(|launch: &Pal5Launch, intent: Pal5ModeIntent| match intent {
+                Pal5ModeIntent::Story => super::create_story_director(launch, None),
+                Pal5ModeIntent::StoryFromSave { slot } => {
+                    // Callers validate the slot first; a slot that
+                    // vanished since then falls back to a new game
+                    // rather than leaving no director at all.
+                    match Pal5PersistentState::load(launch.game.app_name(), slot) {
+                        Ok(save) => super::create_story_director(launch, Some(&save)),
+                        Err(e) => {
+                            log::error!("PAL5: cannot load slot {}: {}", slot, e);
+                            super::create_story_director(launch, None)
+                        }
+                    }
+                }
+                other => unreachable_intent(Pal5ModeKind::Story, other),
+            }),
+        );
+
+        Self { factories }
+    }
+
+    /// Register (or replace) the factory for `kind`.
+    pub fn register(&mut self, kind: Pal5ModeKind, factory: Pal5ModeFactory) {
+        self.factories.insert(kind, factory);
+    }
+
+    /// Build the director for `intent`, or `None` when no factory is
+    /// registered for its kind or the factory failed.
+    pub fn build(&self, launch: &Pal5Launch, intent: Pal5ModeIntent) -> Option<ComRc<IDirector>> {
+        let factory = self.factories.get(&intent.kind())?;
+        factory(launch, intent)
+    }
+}
+
+impl Default for Pal5ModeRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+/// A built-in factory was handed an intent of another kind — a
+/// programming error in [`Pal5ModeRegistry::with_builtins`].
+fn unreachable_intent(kind: Pal5ModeKind, intent: Pal5ModeIntent) -> ! {
+    panic!(
+        "Pal5ModeRegistry: factory for {:?} received mismatched intent {:?}",
+        kind, intent
+    );
+}
+
+/// Single switchboard: build the director for `intent` through the
+/// launch's registry. Logs when nothing could be built.
+pub fn route(launch: &Pal5Launch, intent: Pal5ModeIntent) -> Option<ComRc<IDirector>> {
+    let director = launch.registry.borrow().build(launch, intent);
+    if director.is_none() {
+        log::error!("PAL5: no director for {:?}", intent);
+    }
+    director
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intent_kind_maps_both_story_variants_to_story() {
+        assert_eq!(Pal5ModeIntent::TitleMenu.kind(), Pal5ModeKind::TitleMenu);
+        assert_eq!(Pal5ModeIntent::Story.kind(), Pal5ModeKind::Story);
+        assert_eq!(
+            Pal5ModeIntent::StoryFromSave { slot: 2 }.kind(),
+            Pal5ModeKind::Story
+        );
+    }
+
+    #[test]
+    fn builtins_registry_has_both_modes() {
+        // The factories need a live application, so only the keys are
+        // checked here: replacing each built-in must not panic.
+        let mut registry = Pal5ModeRegistry::with_builtins();
+        assert_eq!(registry.factories.len(), 2);
+        registry.register(
+            Pal5ModeKind::TitleMenu,
+            Box::new(|_launch, _intent| unreachable!("test factory never invoked")),
+        );
+        registry.register(
+            Pal5ModeKind::Story,
+            Box::new(|_launch, _intent| unreachable!("test factory never invoked")),
+        );
+        assert_eq!(registry.factories.len(), 2);
+    }
+}
@@ -11,8 +11,9 @@
 //!
 //! Both the title-selector launch (`title.p7` → `host.pal5()
 //! .create_director`) and the CLI direct-boot (`--pal5`) go through
-//! `create_director`, so there is a single source of truth for PAL5
-//! director construction.
+//! `create_director`, which records the launch and routes it to the
+//! PAL5 title menu through the [`Pal5ModeRegistry`] the service owns.
+//! Every later mode switch goes through the same registry.
 //!
 //! ## Agent server
 //!
@@ -22,20 +23,27 @@
 //! synthetic-input overlay to the director so `/v1/input/*` reaches the
 //! Lua VM, (2) plumbs the bridge into the director so pause / step /
 //! fast-forward take effect, and (3) drains the agent command queue
-//! once per frame via [`Pal5Service::pump_agent`].
+//! once per frame via [`Pal5Service::pump_agent`]. Mode control
+//! (`/v1/load`, `/v1/menu/new_game`, `/v1/menu/exit`) is handled by the
+//! service itself, since it switches directors.
 
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use agent_server::protocol::{AgentCommand, AgentError, AgentResponse};
 use crosscom::ComRc;
-use radiance::comdef::{IApplication, IApplicationExt, IDirector};
+use radiance::comdef::{IApplication, IApplicationExt, IDirector, ISceneManager};
 
 use shared::GameType;
 use shared::agent_common::AgentBridge;
 use shared::openpal5::comdef::{IPal5Service, IPal5ServiceImpl};
+use shared::openpal5::persistent_state::Pal5PersistentState;
 
-use super::agent::{Pal5DispatchCtx, dispatch_pal5_command};
+use super::agent::{Pal5DispatchCtx, bad_slot_err, dispatch_pal5_command};
 use super::director::Pal5StoryDirector;
+use super::modes::{
+    self, Pal5Launch, Pal5ModeFactory, Pal5ModeIntent, Pal5ModeKind, Pal5ModeRegistry,
+};
 
 pub struct Pal5Service {
     app: ComRc<IApplication>,
@@ -44,6 +52,12 @@ pub struct Pal5Service {
     /// constructed `Pal5StoryDirector` honour pause/step + see
     /// synthetic input.
     agent_bridge: RefCell<Option<Rc<AgentBridge>>>,
+    /// The PAL5 mode-factory registry, shared with every [`Pal5Launch`]
+    /// so the title menu can route its own choices.
+    mode_registry: Rc<RefCell<Pal5ModeRegistry>>,
+    /// The most recent launch, recorded by `create_director`. `None`
+    /// until PAL5 has been launched; mode-control commands need it.
+    launch: RefCell<Option<Pal5Launch>>,
 }
 
 ComObject_Pal5Service!(super::Pal5Service);
@@ -53,9 +67,17 @@ impl Pal5Service {
         ComRc::from_object(Self {
             app,
             agent_bridge: RefCell::new(None),
+            mode_registry: Rc::new(RefCell::new(Pal5ModeRegistry::with_builtins())),
+            launch: RefCell::new(None),
         })
     }
 
+    /// Register (or replace) the director factory for `kind` in the
+    /// mode registry. The boot-time extension hook for new PAL5 modes.
+    pub fn register_mode(&self, kind: Pal5ModeKind, factory: Pal5ModeFactory) {
+        self.mode_registry.borrow_mut().register(kind, factory);
+    }
+
     /// Install the agent bridge so the next `create_director` plumbs
     /// synthetic input + pause/step gating into the new director.
     /// Called once at boot by `YaobowApplicationLoader` when
@@ -97,30 +119,100 @@ impl Pal5Service {
             consumer.drain(|env| envelopes.push(env));
         }
 
-        if !envelopes.is_empty() {
-            // Resolve the active director and clone out its context
-            // handle for snapshot reads. PAL5 only ever installs a
-            // `Pal5StoryDirector` (no menu / title mode); the QI to
-            // `IPal5StoryDirector` keeps the `inner` downcast sound.
-            let scene_manager = self.app.engine().borrow().scene_manager().clone();
-            let context = scene_manager
-                .director()
-                .and_then(|d| d.query_interface::<crate::comdef::IPal5StoryDirector>())
-                .map(|d| d.inner::<Pal5StoryDirector>().context());
-
-            for env in envelopes {
-                let ctx = Pal5DispatchCtx {
-                    bridge: &bridge,
-                    context: context.clone(),
-                };
-                let response = dispatch_pal5_command(&ctx, env.command.clone());
+        let scene_manager = self.app.engine().borrow().scene_manager().clone();
+        for env in envelopes {
+            if Self::is_mode_control_command(&env.command) {
+                let response = self.dispatch_mode_control(&scene_manager, env.command.clone());
                 env.reply(response);
+                continue;
             }
+
+            // Resolve the active director per command, so a mode switch
+            // earlier in the batch is observed. Only a story director
+            // has a context; at the title menu the QI to
+            // `IPal5StoryDirector` fails and the dispatcher sees `None`.
+            // The QI also keeps the `inner` downcast sound.
+            let director = scene_manager
+                .director()
+                .and_then(|d| d.query_interface::<crate::comdef::IPal5StoryDirector>());
+            let director = director.as_ref().map(|d| d.inner::<Pal5StoryDirector>());
+            let ctx = Pal5DispatchCtx {
+                bridge: &bridge,
+                context: director.map(|d| d.context()),
+                vm: director.map(|d| d.vm()),
+            };
+            let response = dispatch_pal5_command(&ctx, env.command.clone());
+            env.reply(response);
         }
 
         // Telemetry: always advance frame counter + publish dt/fps.
         bridge.publish_frame_telemetry(delta_sec);
     }
+
+    /// Commands that switch directors (or quit), handled here rather
+    /// than by the per-director dispatcher.
+    fn is_mode_control_command(command: &AgentCommand) -> bool {
+        matches!(
+            command,
+            AgentCommand::EnterNewGame
+                | AgentCommand::EnterLoadGame(_)
+                | AgentCommand::LoadSlot(_)
+                | AgentCommand::ExitGame
+        )
+    }
+
+    /// Install the director for a mode-control command, in any mode.
+    /// A PAL5 load always starts a fresh story director (the Lua
+    /// coroutine can't be restored in place), so `/v1/load` means the
+    /// same at the title menu and mid-story. The slot is read here so a
+    /// missing or malformed one is reported to the agent.
+    fn dispatch_mode_control(
+        &self,
+        scene_manager: &ComRc<ISceneManager>,
+        command: AgentCommand,
+    ) -> AgentResponse {
+        if let AgentCommand::ExitGame = command {
+            self.app.request_exit();
+            return AgentResponse::Ok;
+        }
+
+        let Some(launch) = self.launch.borrow().clone() else {
+            return AgentResponse::err(AgentError::conflict(
+                "PAL5 has not been launched yet (no asset path)",
+            ));
+        };
+
+        let intent = match command {
+            AgentCommand::EnterNewGame => Pal5ModeIntent::Story,
+            AgentCommand::EnterLoadGame(p) | AgentCommand::LoadSlot(p) => {
+                if p.slot < 0 {
+                    return AgentResponse::err(bad_slot_err(p.slot));
+                }
+                if let Err(e) = Pal5PersistentState::load(launch.game.app_name(), p.slot) {
+                    return AgentResponse::err(AgentError::bad_request(format!(
+                        "cannot load slot {}: {e}",
+                        p.slot
+                    )));
+                }
+                Pal5ModeIntent::StoryFromSave { slot: p.slot }
+            }
+            _ => {
+                return AgentResponse::err(AgentError::internal(
+                    "dispatch_mode_control called with a non-mode-control command",
+                ));
+            }
+        };
+
+        match modes::route(&launch, intent) {
+            Some(director) => {
+                scene_manager.set_director(director);
+                AgentResponse::Ok
+            }
+            None => AgentResponse::err(AgentError::internal(format!(
+                "cannot build the PAL5 director for {intent:?} (see log)"
+            ))),
+        }
+    }
 }
 
 impl IPal5ServiceImpl for Pal5Service {
@@ -134,7 +226,14 @@ impl IPal5ServiceImpl for Pal5Service {
                 .and_then(GameType::from_config_key)
                 .unwrap_or(GameType::PAL5);
 
-        let bridge = self.agent_bridge.borrow().clone();
-        super::create_story_director(self.app.clone(), asset_path, game, bridge)
+        let launch = Pal5Launch {
+            app: self.app.clone(),
+            asset_path: asset_path.to_string(),
+            game,
+            agent_bridge: self.agent_bridge.borrow().clone(),
+            registry: self.mode_registry.clone(),
+        };
+        *self.launch.borrow_mut() = Some(launch.clone());
+        modes::route(&launch, Pal5ModeIntent::TitleMenu)
     }
 }
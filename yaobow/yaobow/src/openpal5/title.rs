@@ -0,0 +1,172 @@
+//! PAL5 title menu — a Rust imgui director, the mode a PAL5 / PAL5Q
+//! launch starts on.
+//!
+//! Three pages: the main menu (New Game / Load Game / Settings / Exit),
+//! the save-slot list, and the settings page (master volume, persisted to
+//! `yaobow.toml`). A choice is turned into a [`Pal5ModeIntent`] and
+//! routed through the launch's mode registry; the resulting director is
+//! handed back from `update`, which makes the scene manager switch to it.
+
+use std::cell::{Cell, RefCell};
+
+use crosscom::ComRc;
+use radiance::comdef::{IApplicationExt, IDirector, IDirectorImpl};
+use radiance::scene::CoreScene;
+
+use shared::config::YaobowConfig;
+use shared::openpal5::persistent_state::Pal5PersistentState;
+
+use super::modes::{self, Pal5Launch, Pal5ModeIntent};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TitlePage {
+    Main,
+    Load,
+    Settings,
+}
+
+pub struct Pal5TitleDirector {
+    launch: Pal5Launch,
+    page: Cell<TitlePage>,
+    /// Mode picked this frame, routed at the end of `update`.
+    chosen: Cell<Option<Pal5ModeIntent>>,
+    /// Shown under the menu when the chosen mode failed to build.
+    error: RefCell<Option<String>>,
+    /// Slider value on the settings page; applied live, written to the
+    /// config when the page is left.
+    volume: Cell<f32>,
+}
+
+ComObject_Pal5TitleDirector!(super::Pal5TitleDirector);
+
+impl Pal5TitleDirector {
+    pub fn new(launch: Pal5Launch) -> Self {
+        Self {
+            launch,
+            page: Cell::new(TitlePage::Main),
+            chosen: Cell::new(None),
+            error: RefCell::new(None),
+            volume: Cell::new(YaobowConfig::load().master_volume()),
+        }
+    }
+
+    fn render(&self) {
+        let ui_manager = self.launch.app.engine().borrow().ui_manager();
+        let ui = ui_manager.ui();
+        let [w, h] = ui.io().display_size;
+
+        ui.window("pal5_title")
+            .position([w * 0.5, h * 0.5], imgui::Condition::Always)
+            .position_pivot([0.5, 0.5])
+            .always_auto_resize(true)
+            .movable(false)
+            .resizable(false)
+            .collapsible(false)
+            .title_bar(false)
+            .build(|| {
+                ui.text(self.launch.game.app_name());
+                ui.separator();
+                match self.page.get() {
+                    TitlePage::Main => self.render_main(ui),
+                    TitlePage::Load => self.render_load(ui),
+                    TitlePage::Settings => self.render_settings(ui),
+                }
+                if let Some(error) = self.error.borrow().as_ref() {
+                    ui.separator();
+                    ui.text_colored([1.0, 0.4, 0.4, 1.0], error);
+                }
+            });
+    }
+
+    fn render_main(&self, ui: &imgui::Ui) {
+        const SIZE: [f32; 2] = [200.0, 0.0];
+        if ui.button_with_size("New Game", SIZE) {
+            self.chosen.set(Some(Pal5ModeIntent::Story));
+        }
+        if ui.button_with_size("Load Game", SIZE) {
+            self.page.set(TitlePage::Load);
+        }
+        if ui.button_with_size("Settings", SIZE) {
+            self.page.set(TitlePage::Settings);
+        }
+        if ui.button_with_size("Exit", SIZE) {
+            self.launch.app.request_exit();
+        }
+    }
+
+    fn render_load(&self, ui: &imgui::Ui) {
+        let app_name = self.launch.game.app_name();
+        for slot in 1..=Pal5PersistentState::SLOT_COUNT {
+            let save = Pal5PersistentState::peek(app_name, slot);
+            let label = match &save {
+                Some(save) => format!("Slot {} - {}", slot, save.summary()),
+                None => format!("Slot {} - (empty)", slot),
+            };
+            let _disabled = ui.begin_disabled(save.is_none());
+            if ui.button_with_size(label, [280.0, 0.0]) {
+                self.chosen
+                    .set(Some(Pal5ModeIntent::StoryFromSave { slot }));
+            }
+        }
+        ui.separator();
+        if ui.button("Back") {
+            self.page.set(TitlePage::Main);
+        }
+    }
+
+    fn render_settings(&self, ui: &imgui::Ui) {
+        let mut volume = self.volume.get() * 100.0;
+        if ui
+            .slider_config("Master volume", 0.0, 100.0)
+            .display_format("%.0f%%")
+            .build(&mut volume)
+        {
+            self.volume.set(volume / 100.0);
+            self.audio_engine().set_master_volume(volume / 100.0);
+        }
+        ui.separator();
+        if ui.button("Back") {
+            self.save_settings();
+            self.page.set(TitlePage::Main);
+        }
+    }
+
+    fn audio_engine(&self) -> std::rc::Rc<dyn radiance::audio::AudioEngine> {
+        self.launch.app.engine().borrow().audio_engine()
+    }
+
+    fn save_settings(&self) {
+        let mut config = YaobowConfig::load();
+        config.set_master_volume(self.volume.get());
+        if let Err(e) = config.save() {
+            log::error!("PAL5: cannot save settings: {}", e);
+            *self.error.borrow_mut() = Some(format!("Cannot save settings: {}", e));
+        }
+    }
+}
+
+impl IDirectorImpl for Pal5TitleDirector {
+    fn activate(&self) {
+        // Nothing to show behind the menu; drop whatever the previous
+        // mode left and give the renderer an empty scene.
+        let scene_manager = self.launch.app.engine().borrow().scene_manager().clone();
+        scene_manager.unload_all_scenes();
+        scene_manager.push_scene(CoreScene::create());
+    }
+
+    fn update(&self, _delta_sec: f32) -> Option<ComRc<IDirector>> {
+        self.render();
+
+        let intent = self.chosen.take()?;
+        let director = modes::route(&self.launch, intent);
+        if director.is_none() {
+            *self.error.borrow_mut() = Some(format!(
+                "Cannot start the game from {} (see log)",
+                self.launch.asset_path
+            ));
+        }
+        director
+    }
+
+    fn deactivate(&self) {}
+}
@@ -4,12 +4,14 @@ use std::rc::Rc;
 use crosscom::ComRc;
 use radiance::comdef::{IApplication, IApplicationExt, IDirector};
 use radiance_scripting::comdef::services::{IAppService, IAppServiceImpl};
+use radiance_scripting::hot_reload::{self, ScriptReloadDirector};
 use radiance_scripting::services::ImguiTextureCache;
 use radiance_scripting::{ScriptHost, install_imgui_ui_renderer_with_cache, wrap_director};
 use shared::config::YaobowConfig;
 
 use crate::GameType;
 use crate::directors::DevToolsAssetLoader;
+use crate::script_source::dev_source_dirs;
 use crate::services::editor_host_context::EditorHostContext;
 use shared::config_service::ConfigService;
 
@@ -93,9 +95,19 @@ impl IAppServiceImpl for AppService {
                     raw_vfs,
                 ))
             }
-            GameType::PAL5 => DevToolsAssetLoader::Pal5(
-                shared::openpal5::asset_loader::AssetLoader::new(factory.clone(), Rc::new(raw_vfs)),
-            ),
+            GameType::PAL5 | GameType::PAL5Q => {
+                match shared::openpal5::asset_loader::AssetLoader::new(
+                    factory.clone(),
+                    Rc::new(raw_vfs),
+                    game,
+                ) {
+                    Ok(loader) => DevToolsAssetLoader::Pal5(loader),
+                    Err(e) => {
+                        log::error!("failed to open {}: {}", game.full_name(), e);
+                        return None;
+                    }
+                }
+            }
             GameType::SWD5 | GameType::SWDHC | GameType::SWDCF => {
                 DevToolsAssetLoader::Swd5(shared::openswd5::asset_loader::AssetLoader::new(
                     factory.clone(),
@@ -103,6 +115,13 @@ impl IAppServiceImpl for AppService {
                     game,
                 ))
             }
+            GameType::Gujian | GameType::Gujian2 => {
+                DevToolsAssetLoader::Gujian(shared::opengujian::asset_loader::AssetLoader::new(
+                    factory.clone(),
+                    Rc::new(raw_vfs),
+                    game,
+                ))
+            }
             _ => DevToolsAssetLoader::Pal3(Rc::new(
                 shared::openpal3::asset_manager::AssetManager::new(
                     factory.clone(),
@@ -148,25 +167,37 @@ impl IAppServiceImpl for AppService {
             std::path::PathBuf::from(&asset_path),
         );
 
-        let host_id = self.script_host.intern(host_ctx);
-        let host_box = self
-            .script_host
-            .foreign_box(
-                "yaobow_editor.comdef.editor_services.IEditorHostContext",
-                host_id,
-            )
-            .ok()?;
-        let director_data = self
-            .script_host
-            .call_returning_data("init_main_editor", vec![host_box])
-            .ok()?;
-
         // Reverse-wrap via the runtime-typed CCW factory. The fat
         // CCW gives both `IDirector` (for `SceneManager`) and
         // `IUiLayer` (for the engine UI renderer) from a single
         // wrap, so we don't need a follow-up QI.
-        let runtime_handle = self.script_host.runtime_handle();
-        let director: ComRc<IDirector> = wrap_director(&runtime_handle, director_data).ok()?;
+        // Interned once: the factory runs again on every dev-mode reload
+        // and only needs a fresh box around the same handle.
+        let host_id = self.script_host.intern(host_ctx);
+        let make_director = {
+            let host = self.script_host.clone();
+            move || {
+                let host_box = host.foreign_box(
+                    "yaobow_editor.comdef.editor_services.IEditorHostContext",
+                    host_id,
+                )?;
+                let director_data = host.call_returning_data("init_main_editor", vec![host_box])?;
+                wrap_director(&host.runtime_handle(), director_data)
+            }
+        };
+
+        // In script dev mode the main editor is rebuilt from the on-disk
+        // scripts whenever they change.
+        let director: ComRc<IDirector> = if hot_reload::dev_mode_enabled() {
+            ScriptReloadDirector::create(
+                self.script_host.clone(),
+                engine.ui_manager(),
+                dev_source_dirs(),
+                Box::new(make_director),
+            )
+        } else {
+            make_director().ok()?
+        };
 
         drop(engine);
         install_imgui_ui_renderer_with_cache(&self.app, self.textures.clone());
@@ -21,6 +21,7 @@ pub enum DevToolsAssetLoader {
     Pal4(Rc<shared::openpal4::asset_loader::AssetLoader>),
     Pal5(Rc<shared::openpal5::asset_loader::AssetLoader>),
     Swd5(Rc<shared::openswd5::asset_loader::AssetLoader>),
+    Gujian(Rc<shared::opengujian::asset_loader::AssetLoader>),
 }
 
 impl DevToolsAssetLoader {
@@ -52,12 +53,20 @@ impl DevToolsAssetLoader {
         }
     }
 
+    pub fn gujian(&self) -> Option<Rc<shared::opengujian::asset_loader::AssetLoader>> {
+        match self {
+            DevToolsAssetLoader::Gujian(asset_mgr) => Some(asset_mgr.clone()),
+            _ => None,
+        }
+    }
+
     pub fn component_factory(&self) -> Rc<dyn ComponentFactory> {
         match self {
             DevToolsAssetLoader::Pal3(asset_mgr) => asset_mgr.component_factory(),
             DevToolsAssetLoader::Pal4(asset_mgr) => asset_mgr.component_factory(),
             DevToolsAssetLoader::Pal5(asset_mgr) => asset_mgr.component_factory(),
             DevToolsAssetLoader::Swd5(asset_mgr) => asset_mgr.component_factory(),
+            DevToolsAssetLoader::Gujian(asset_mgr) => asset_mgr.component_factory(),
         }
     }
 
@@ -67,6 +76,7 @@ impl DevToolsAssetLoader {
             DevToolsAssetLoader::Pal4(asset_mgr) => asset_mgr.vfs(),
             DevToolsAssetLoader::Pal5(asset_mgr) => asset_mgr.vfs(),
             DevToolsAssetLoader::Swd5(asset_mgr) => asset_mgr.vfs(),
+            DevToolsAssetLoader::Gujian(asset_mgr) => asset_mgr.vfs(),
         }
     }
 
@@ -76,6 +86,7 @@ impl DevToolsAssetLoader {
             DevToolsAssetLoader::Pal4(asset_mgr) => asset_mgr.vfs_rc(),
             DevToolsAssetLoader::Pal5(asset_mgr) => asset_mgr.vfs_rc(),
             DevToolsAssetLoader::Swd5(asset_mgr) => asset_mgr.vfs_rc(),
+            DevToolsAssetLoader::Gujian(asset_mgr) => asset_mgr.vfs_rc(),
         }
     }
 }
@@ -14,15 +14,13 @@ use std::rc::Rc;
 use crosscom::ComRc;
 use radiance::asset::AssetManager;
 use radiance::comdef::{IApplication, IApplicationExt, IDirector};
+use radiance_scripting::hot_reload::{self, ScriptReloadDirector};
 use radiance_scripting::services::ImguiTextureCache;
-use radiance_scripting::{
-    ScriptHost, bootstrap_script_root_from_path, install_imgui_ui_renderer_with_cache,
-    wrap_director,
-};
+use radiance_scripting::{ScriptHost, install_imgui_ui_renderer_with_cache, wrap_director};
 use shared::config::YaobowConfig;
 
 use crate::directors::app_service::AppService;
-use crate::script_source::mount_scripts;
+use crate::script_source::{dev_source_dirs, mount_scripts};
 use crate::services::editor_host_context::EditorHostContext;
 use shared::config_service::ConfigService;
 
@@ -73,18 +71,10 @@ impl ScriptedWelcomePage {
 
         // Install the dedicated script `AssetManager` so the
         // VFS-backed `ModuleProvider` can resolve every
-        // `import <crate>.<module>;` lookup, then load the editor's
-        // root via `bootstrap_script_root_from_path`.
+        // `import <crate>.<module>;` lookup; the director factory
+        // below loads the editor's root `/yaobow_editor/main.p7`.
         let assets = build_editor_script_assets();
         host.set_script_assets(assets);
-        let director_data = bootstrap_script_root_from_path(
-            &host,
-            "/yaobow_editor/main.p7",
-            host_ctx,
-            EDITOR_HOST_CONTEXT_TYPE_TAG,
-            "init",
-        )
-        .expect("welcome script init must succeed");
 
         // Reverse-wrap the script-side `box<radiance.IDirector>`
         // through the runtime-typed CCW factory. The fat CCW gives us
@@ -93,9 +83,36 @@ impl ScriptedWelcomePage {
         // `wrap_director` call, because the script struct's
         // `conforming_to` list backs every advertised interface with
         // its own slot.
-        let runtime_handle = host.runtime_handle();
-        let director: ComRc<IDirector> =
-            wrap_director(&runtime_handle, director_data).expect("wrap_director must succeed");
+        //
+        // The host context is interned once up front; the factory runs
+        // again on every dev-mode reload and only boxes the same handle.
+        let host_id = host.intern(host_ctx);
+        let make_director = {
+            let host = host.clone();
+            move || {
+                if !host.has_function("init") {
+                    host.load_source_from_path("/yaobow_editor/main.p7")?;
+                }
+                let host_box = host.foreign_box(EDITOR_HOST_CONTEXT_TYPE_TAG, host_id)?;
+                let director_data = host.call_returning_data("init", vec![host_box])?;
+                wrap_director(&host.runtime_handle(), director_data)
+            }
+        };
+
+        // In script dev mode the director is rebuilt from the on-disk
+        // scripts whenever they change; a startup error is shown by the
+        // reload director until the scripts are fixed.
+        let director: ComRc<IDirector> = if hot_reload::dev_mode_enabled() {
+            let ui = app.engine().borrow().ui_manager();
+            ScriptReloadDirector::create(
+                host.clone(),
+                ui,
+                dev_source_dirs(),
+                Box::new(make_director),
+            )
+        } else {
+            make_director().expect("welcome script init must succeed")
+        };
 
         // Install the engine-side UI renderer so `CoreRadianceEngine::update`
         // drives the active director's `render` (via its `IUiLayer` slot)
@@ -125,5 +142,8 @@ fn build_editor_script_assets() -> Rc<AssetManager> {
     radiance_scripting::mount_scripts(&assets);
     shared::mount_scripts(&assets);
     mount_scripts(&assets);
+    if hot_reload::dev_mode_enabled() {
+        hot_reload::mount_dev_scripts(&assets, &dev_source_dirs());
+    }
     assets
 }
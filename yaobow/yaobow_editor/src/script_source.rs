@@ -9,6 +9,7 @@
 //! resolves at `/yaobow_editor/main.p7`.
 
 use radiance::asset::AssetManager;
+use radiance_scripting::ScriptSourceDir;
 
 /// In-binary `.ypk` produced by `build.rs`.
 const EDITOR_YPK: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/yaobow_editor.ypk"));
@@ -20,3 +21,17 @@ pub fn mount_scripts(assets: &AssetManager) {
         .mount_ypk_bytes("/yaobow_editor", EDITOR_YPK)
         .expect("yaobow_editor.ypk must mount");
 }
+
+/// This crate's on-disk `scripts/`, mounted over the ypk in script dev
+/// mode (see [`radiance_scripting::hot_reload`]).
+pub fn source_dir() -> ScriptSourceDir {
+    ScriptSourceDir::new(
+        "/yaobow_editor",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/scripts"),
+    )
+}
+
+/// Every on-disk script directory the editor's script VFS draws from.
+pub fn dev_source_dirs() -> Vec<ScriptSourceDir> {
+    vec![radiance_scripting::hot_reload::source_dir(), source_dir()]
+}
@@ -29,6 +29,8 @@ use shared::openpal3::loaders::{
     cvd_loader::cvd_load_from_file, nav_loader::nav_load_from_file, sce_loader::sce_load_from_file,
     scn_loader::scn_load_from_file, tli::TliDict,
 };
+use shared::openpal4::scripting::sysfn_names;
+use shared::scripting::angelscript::{DecompileOptions, ScriptModule, decompile_module};
 
 use crate::comdef::editor_services::{
     IAudioHandle, IImageHandle, IModelHandle, IPreviewerHub, IPreviewerHubImpl, IResourceManager,
@@ -118,6 +120,18 @@ impl PreviewerHub {
         // script/UI path; codegen copies the &str into a CString immediately.
         unsafe { (*self.last_string.as_ptr()).as_str() }
     }
+
+    /// `.csb` modules only ship with PAL4; other games get generic
+    /// `sysfn<N>` names.
+    fn decompile_options(&self) -> DecompileOptions {
+        DecompileOptions {
+            sysfn_names: if self.game_type == GameType::PAL4 {
+                sysfn_names()
+            } else {
+                Vec::new()
+            },
+        }
+    }
 }
 
 fn extension(path: &str) -> Option<String> {
@@ -133,8 +147,8 @@ fn classify_path(path: &str) -> i32 {
         Some("tga" | "png" | "dds") => KIND_IMAGE,
         Some("mp3" | "smp" | "wav" | "ogg") => KIND_AUDIO,
         Some("bik") => KIND_VIDEO,
-        Some("mv3" | "cvd" | "dff" | "anm" | "bsp" | "pol") => KIND_MODEL,
-        Some("scn" | "nav" | "sce" | "nod" | "tli") => KIND_STRUCTURED,
+        Some("mv3" | "cvd" | "dff" | "anm" | "bsp" | "pol" | "nif") => KIND_MODEL,
+        Some("scn" | "nav" | "sce" | "nod" | "tli" | "csb") => KIND_STRUCTURED,
         // .xml is content-classified separately: see `classify_xml` —
         // a `<GUILayout>` root tags the file as KIND_UI_LAYOUT, all
         // other .xml files fall through to KIND_UNSUPPORTED.
@@ -236,6 +250,13 @@ impl IPreviewerHubImpl for PreviewerHub {
                 Ok(bytes) => jsonify(&TliDict::parse(&bytes)),
                 Err(e) => e.to_string(),
             },
+            Some("csb") => match self.vfs.read_to_end(&path) {
+                Ok(bytes) => match ScriptModule::read_from_buffer(&bytes) {
+                    Ok(module) => decompile_module(&module, &self.decompile_options()),
+                    Err(e) => format!("{:#}", e),
+                },
+                Err(e) => e.to_string(),
+            },
             _ => "Unsupported".to_string(),
         };
         self.set_last(text)
@@ -334,6 +355,7 @@ impl IPreviewerHubImpl for PreviewerHub {
             "dff" | "anm" => load_dff(&self.vfs, &path, &self.asset_loader, self.game_type)?,
             "bsp" => load_bsp(&self.vfs, &path, &self.asset_loader, self.game_type)?,
             "pol" => load_pol(&self.vfs, &path, &self.asset_loader)?,
+            "nif" => load_nif(&self.vfs, &path, &self.asset_loader)?,
             _ => return None,
         };
         let glb_exporter = build_glb_exporter(self.vfs.clone(), &path, &ext);
@@ -459,6 +481,36 @@ fn load_mv3(
     Some((text, entity))
 }
 
+fn load_nif(
+    vfs: &MiniFs,
+    path: &Path,
+    asset_loader: &DevToolsAssetLoader,
+) -> Option<(String, ComRc<IEntity>)> {
+    use fileformats::nif::NifModel;
+    use shared::loaders::nif::create_entity_from_nif_model;
+    let text = vfs
+        .open(path)
+        .ok()
+        .and_then(|f| NifModel::read(&mut BufReader::new(f)).ok())
+        .map(|m| format!("{:#?}", m))
+        .unwrap_or_else(|| "Unsupported".to_string());
+    // Gujian installs go through their asset loader, like the in-game
+    // viewer; NIFs found under other games load directly.
+    let entity = match asset_loader.gujian() {
+        Some(gujian) => gujian.load_model(path.to_str()?),
+        None => create_entity_from_nif_model(
+            &asset_loader.component_factory(),
+            vfs,
+            path,
+            "preview".to_string(),
+            true,
+        ),
+    }
+    .map_err(|e| log::warn!("NIF preview failed for {}: {:#}", path.display(), e))
+    .ok()?;
+    Some((text, entity))
+}
+
 fn load_cvd(
     vfs: &MiniFs,
     path: &Path,
@@ -526,6 +578,7 @@ fn load_dff(
                 dynamic_lighting: false,
                 fog_exempt: false,
                 foliage_resolver: None,
+                water: None,
             },
         )
         .map_err(|e| {
@@ -556,6 +609,7 @@ fn load_dff(
                 dynamic_lighting: false,
                 fog_exempt: false,
                 foliage_resolver: None,
+                water: None,
             },
         )
         .map_err(|e| {
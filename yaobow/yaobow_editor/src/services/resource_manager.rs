@@ -31,7 +31,7 @@ const CATEGORIES: &[&str] = &[
 fn classify(ext: &str) -> usize {
     match ext {
         "scn" | "nav" | "bsp" | "gob" => 0,
-        "mv3" | "cvd" | "dff" | "anm" | "pol" => 1,
+        "mv3" | "cvd" | "dff" | "anm" | "pol" | "nif" => 1,
         "tga" | "png" | "dds" | "bmp" | "jpg" | "jpeg" => 2,
         "mp3" | "smp" | "wav" | "ogg" => 3,
         "bik" => 4,